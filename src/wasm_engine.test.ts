@@ -1,5 +1,6 @@
 import { assertEquals } from "@std/assert";
 import { WasmEngine } from "./wasm_engine.ts";
+import { engine_create_series_str, engine_free_series_str, engine_free_series_u32, hasWasmExport } from "./wasm_wrapper.ts";
 
 Deno.test("WasmEngine scalar ops f64", () => {
     const eng = WasmEngine.instance;
@@ -393,3 +394,101 @@ Deno.test("WasmEngine WASM-specific edge cases", () => {
         eng.freeSeries(infId);
     }
 });
+
+// Engine exports newer than the committed bindings run once `deno task wasm:build` has regenerated wasm/lib
+
+Deno.test({
+    name: "WasmEngine exports Arrow IPC for every dtype",
+    ignore: !hasWasmExport("engine_export_arrow"),
+    fn: () => {
+        const eng = WasmEngine.instance;
+        const values = eng.registerSeriesF64(new Float64Array([1.5, NaN, 3]));
+        const labels = engine_create_series_str(["a", "b", "c"]);
+        try {
+            const stream = eng.exportArrow([values, labels], ["values", "labels"]);
+            const view = new DataView(stream.buffer, stream.byteOffset, stream.byteLength);
+            // Continuation marker, then end-of-stream marker at the end
+            assertEquals(view.getUint32(0, true), 0xFFFFFFFF);
+            assertEquals(view.getUint32(stream.length - 8, true), 0xFFFFFFFF);
+            assertEquals(view.getInt32(stream.length - 4, true), 0);
+            assertEquals(eng.exportArrow([values, 0xFFFFFFFE], []).length, 0);
+        } finally {
+            eng.freeSeries(values);
+            engine_free_series_str(labels);
+        }
+    },
+});
+
+Deno.test({
+    name: "WasmEngine join indices match the join task",
+    ignore: !hasWasmExport("engine_task_start_join_f64"),
+    fn: () => {
+        const eng = WasmEngine.instance;
+        const left = eng.registerSeriesF64(new Float64Array([2, NaN, 1, 2]));
+        const right = eng.registerSeriesF64(new Float64Array([1, 2, 2]));
+        const ids: number[] = [];
+        try {
+            const pairs = eng.joinIndicesF64(left, right);
+            ids.push(...pairs);
+            assertEquals(eng.seriesToVecU32(pairs[0]), [0, 0, 2, 3, 3]);
+            assertEquals(eng.seriesToVecU32(pairs[1]), [1, 2, 0, 1, 2]);
+            const task = eng.startJoinTaskF64(left, right);
+            while (eng.stepTask(task, 5) < 1) {
+                // keep stepping until the task finishes
+            }
+            const result = eng.taskResult(task);
+            ids.push(...result);
+            assertEquals(eng.seriesToVecU32(result[0]), eng.seriesToVecU32(pairs[0]));
+            assertEquals(eng.seriesToVecU32(result[1]), eng.seriesToVecU32(pairs[1]));
+        } finally {
+            ids.forEach((id) => engine_free_series_u32(id));
+            eng.freeSeries(left);
+            eng.freeSeries(right);
+        }
+    },
+});
+
+Deno.test({
+    name: "WasmEngine sort task matches the one-shot sort",
+    ignore: !hasWasmExport("engine_task_start_sort_f64"),
+    fn: () => {
+        const eng = WasmEngine.instance;
+        const id = eng.registerSeriesF64(new Float64Array([3, NaN, 1, 2]));
+        try {
+            const task = eng.startSortTaskF64(id, true, true);
+            while (eng.stepTask(task, 5) < 1) {
+                // keep stepping until the task finishes
+            }
+            const [sorted] = eng.taskResult(task);
+            const direct = eng.sortValuesF64(id, true, true);
+            assertEquals(eng.seriesToVecF64(sorted), eng.seriesToVecF64(direct));
+            eng.freeSeries(sorted);
+            eng.freeSeries(direct);
+        } finally {
+            eng.freeSeries(id);
+        }
+    },
+});
+
+Deno.test({
+    name: "WasmEngine lazy filter and memory limit errors",
+    ignore: !hasWasmExport("engine_expr_collect"),
+    fn: () => {
+        const eng = WasmEngine.instance;
+        const id = eng.registerSeriesF64(new Float64Array([4, 1, 3]));
+        try {
+            const kept = eng.filterWhereF64(id, 4, 2);
+            assertEquals(eng.seriesToVecF64(kept), [4, 3]);
+            eng.freeSeries(kept);
+            WasmEngine.setMemoryLimit(WasmEngine.getMemoryUsage() + 8);
+            try {
+                assertEquals(eng.filterWhereF64(id, 4, 0) >>> 0, 0xFFFFFFFF);
+                assertEquals(WasmEngine.lastErrorCode(), 4);
+            } finally {
+                WasmEngine.setMemoryLimit(0);
+            }
+        } finally {
+            eng.freeSeries(id);
+        }
+    },
+});
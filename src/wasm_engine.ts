@@ -7,6 +7,12 @@ import {
     count_non_null_f64,
     engine_create_series_f64,
     engine_create_series_i32,
+    engine_export_arrow,
+    engine_expr_col,
+    engine_expr_collect,
+    engine_expr_compare,
+    engine_expr_filter,
+    engine_expr_lit,
     engine_filter_f64,
     engine_flush,
     engine_free_series,
@@ -19,6 +25,8 @@ import {
     engine_groupby_std_f64,
     engine_groupby_sum_f64,
     engine_groupby_var_f64,
+    engine_join_indices_f64,
+    engine_last_error_code,
     engine_memory_usage,
     engine_series_count,
     engine_series_count_f64,
@@ -31,11 +39,17 @@ import {
     engine_series_sum_f64,
     engine_series_to_vec_f64,
     engine_series_to_vec_i32,
+    engine_series_to_vec_u32,
+    engine_set_memory_limit,
     engine_sort_indices_f64,
     engine_sort_indices_i32,
     engine_sort_two_columns_indices_f64,
     engine_sort_two_columns_indices_i32,
     engine_sort_values_f64,
+    engine_task_result,
+    engine_task_start_join_f64,
+    engine_task_start_sort_f64,
+    engine_task_step,
     isin_f64,
     isin_i32,
     isin_string,
@@ -173,6 +187,52 @@ export class WasmEngine {
         return isin_string(data, values);
     }
 
+    seriesToVecU32(seriesId: number): number[] {
+        return Array.from(engine_series_to_vec_u32(seriesId));
+    }
+
+    /** Arrow IPC stream of registered series of any dtype (empty on failure) */
+    exportArrow(seriesIds: number[], names: string[]): Uint8Array {
+        return engine_export_arrow(new Uint32Array(seriesIds), JSON.stringify(names));
+    }
+
+    /** Row index pairs `[leftIndicesId, rightIndicesId]` of an inner join on float64 keys */
+    joinIndicesF64(leftKeysId: number, rightKeysId: number): number[] {
+        return Array.from(engine_join_indices_f64(leftKeysId, rightKeysId));
+    }
+
+    /** Rows of a series where it compares to `value` (op: 0=eq ... 5=ge), as a lazy expression */
+    filterWhereF64(seriesId: number, op: number, value: number): number {
+        const col = engine_expr_col(seriesId);
+        return engine_expr_collect(engine_expr_filter(col, engine_expr_compare(op, col, engine_expr_lit(value))));
+    }
+
+    startSortTaskF64(seriesId: number, ascending: boolean, nullsLast: boolean): number {
+        return engine_task_start_sort_f64(seriesId, ascending ? 1 : 0, nullsLast ? 1 : 0);
+    }
+
+    startJoinTaskF64(leftKeysId: number, rightKeysId: number): number {
+        return engine_task_start_join_f64(leftKeysId, rightKeysId);
+    }
+
+    /** Run a task for up to `msBudget` ms; progress in [0, 1], or -1 if it is unknown or cancelled */
+    stepTask(taskId: number, msBudget: number): number {
+        return engine_task_step(taskId, msBudget);
+    }
+
+    taskResult(taskId: number): number[] {
+        return Array.from(engine_task_result(taskId));
+    }
+
+    static setMemoryLimit(bytes: number): void {
+        engine_set_memory_limit(bytes);
+    }
+
+    /** Code of the last engine error (0 = none) */
+    static lastErrorCode(): number {
+        return engine_last_error_code();
+    }
+
     static getMemoryUsage(): number {
         return engine_memory_usage();
     }
@@ -24,6 +24,25 @@ if (isWasmEngineEnabled()) {
     }
 }
 
+// Exports added to the engine after the bindings in wasm/lib were last
+// generated. Each resolves to the engine function once `deno task wasm:build`
+// has regenerated the bindings, and to the throwing fallback until then.
+const wasmExports = wasmFunctions as unknown as Record<string, unknown> | undefined;
+
+function engineExport<F extends (...args: never[]) => unknown>(functionName: string): F {
+    const fn = wasmExports?.[functionName];
+    return (typeof fn === "function" ? fn : createWasmFallback(functionName)) as F;
+}
+
+// JS callback taken by the user-defined kernels (typed `Function` in the bindings)
+// deno-lint-ignore ban-types
+type JsFunction = Function;
+
+/** Whether the loaded WASM module provides `functionName` */
+export function hasWasmExport(functionName: string): boolean {
+    return typeof wasmExports?.[functionName] === "function";
+}
+
 // Export WASM functions or fallbacks
 export const engine_create_series_f64 = wasmFunctions?.engine_create_series_f64 || createWasmFallback("engine_create_series_f64");
 export const engine_create_series_i32 = wasmFunctions?.engine_create_series_i32 || createWasmFallback("engine_create_series_i32");
@@ -62,3 +81,435 @@ export const isin_string = wasmFunctions?.isin_string || createWasmFallback("isi
 export const engine_memory_usage = wasmFunctions?.engine_memory_usage || createWasmFallback("engine_memory_usage");
 export const engine_series_count = wasmFunctions?.engine_series_count || createWasmFallback("engine_series_count");
 export const count_non_null_f64 = wasmFunctions?.count_non_null_f64 || createWasmFallback("count_non_null_f64");
+
+// arrow
+export const engine_export_arrow: (series_ids: Uint32Array, names_json: string) => Uint8Array = engineExport("engine_export_arrow");
+
+// batch
+export const engine_execute_batch: (commands: Uint8Array) => Uint8Array = engineExport("engine_execute_batch");
+
+// binning
+export const engine_digitize_f64: (series_id: number, edges: Float64Array, right: number) => number = engineExport("engine_digitize_f64");
+export const engine_bin2d_f64: (
+    x_id: number,
+    y_id: number,
+    x_edges: Float64Array,
+    y_edges: Float64Array,
+    value_id: number,
+    agg_kind: number,
+) => Float64Array = engineExport("engine_bin2d_f64");
+
+// cast
+export const engine_cast: (series_id: number, target_dtype: number, rounding_mode: number, null_policy: number) => number =
+    engineExport("engine_cast");
+export const engine_cast_report_json: (
+    series_id: number,
+    target_dtype: number,
+    rounding_mode: number,
+    null_policy: number,
+    max_rows: number,
+) => string = engineExport("engine_cast_report_json");
+
+// checksum
+export const engine_series_checksum: (series_id: number) => bigint = engineExport("engine_series_checksum");
+
+// compare
+export const engine_series_equals: (a_id: number, b_id: number, null_equal: number, tolerance: number) => Uint32Array =
+    engineExport("engine_series_equals");
+export const engine_series_compare_report_json: (a_id: number, b_id: number, null_equal: number, tolerance: number, max_rows: number) => string =
+    engineExport("engine_series_compare_report_json");
+
+// core
+export const engine_create_series_i64: (data: BigInt64Array) => number = engineExport("engine_create_series_i64");
+export const engine_create_series_bool: (data: Uint8Array) => number = engineExport("engine_create_series_bool");
+export const engine_create_series_u32: (data: Uint32Array) => number = engineExport("engine_create_series_u32");
+export const engine_create_series_u8: (data: Uint8Array) => number = engineExport("engine_create_series_u8");
+export const engine_create_series_str: (values: string[]) => number = engineExport("engine_create_series_str");
+export const engine_create_series_str_packed: (bytes: Uint8Array, offsets: Uint32Array, null_mask: Uint8Array) => number =
+    engineExport("engine_create_series_str_packed");
+export const engine_full_f64: (len: number, fill_value: number) => number = engineExport("engine_full_f64");
+export const engine_arange_f64: (start: number, stop: number, step: number) => number = engineExport("engine_arange_f64");
+export const engine_linspace_f64: (start: number, stop: number, n: number) => number = engineExport("engine_linspace_f64");
+export const engine_alloc_uninit_f64: (len: number) => number = engineExport("engine_alloc_uninit_f64");
+export const engine_adopt_buffer_f64: (ptr: number, len: number) => number = engineExport("engine_adopt_buffer_f64");
+export const engine_free_uninit_f64: (ptr: number) => boolean = engineExport("engine_free_uninit_f64");
+export const engine_chunked_create_f64: () => number = engineExport("engine_chunked_create_f64");
+export const engine_chunked_append_f64: (series_id: number, data: Float64Array) => boolean = engineExport("engine_chunked_append_f64");
+export const engine_chunked_append_buffer_f64: (series_id: number, ptr: number, len: number) => boolean =
+    engineExport("engine_chunked_append_buffer_f64");
+export const engine_chunked_num_chunks: (series_id: number) => number = engineExport("engine_chunked_num_chunks");
+export const engine_chunked_len_f64: (series_id: number) => number = engineExport("engine_chunked_len_f64");
+export const engine_chunked_rechunk_f64: (series_id: number) => number = engineExport("engine_chunked_rechunk_f64");
+export const engine_append_rows: (series_ids: Uint32Array, values_flat: Float64Array) => boolean = engineExport("engine_append_rows");
+export const engine_delete_rows: (series_ids: Uint32Array, indices: Uint32Array) => boolean = engineExport("engine_delete_rows");
+export const engine_free_series_i64: (series_id: number) => void = engineExport("engine_free_series_i64");
+export const engine_free_series_bool: (series_id: number) => void = engineExport("engine_free_series_bool");
+export const engine_free_series_u32: (series_id: number) => void = engineExport("engine_free_series_u32");
+export const engine_free_series_u8: (series_id: number) => void = engineExport("engine_free_series_u8");
+export const engine_free_series_str: (series_id: number) => void = engineExport("engine_free_series_str");
+export const engine_free_series_rle: (series_id: number) => void = engineExport("engine_free_series_rle");
+export const engine_series_retain: (series_id: number) => number = engineExport("engine_series_retain");
+export const engine_series_release: (series_id: number) => number = engineExport("engine_series_release");
+export const engine_series_refcount: (series_id: number) => number = engineExport("engine_series_refcount");
+export const engine_series_clone: (series_id: number) => number = engineExport("engine_series_clone");
+export const engine_series_slice_view_f64: (series_id: number, offset: number, len: number) => number = engineExport("engine_series_slice_view_f64");
+export const engine_series_is_shared: (series_id: number) => boolean = engineExport("engine_series_is_shared");
+export const engine_series_set_name: (series_id: number, name: string) => boolean = engineExport("engine_series_set_name");
+export const engine_series_set_dtype: (series_id: number, dtype: string) => boolean = engineExport("engine_series_set_dtype");
+export const engine_series_invalidate_stats: (series_id: number) => void = engineExport("engine_series_invalidate_stats");
+export const engine_series_info_json: (series_id: number) => string = engineExport("engine_series_info_json");
+export const engine_series_is_monotonic_increasing: (series_id: number) => boolean = engineExport("engine_series_is_monotonic_increasing");
+export const engine_series_is_monotonic_decreasing: (series_id: number) => boolean = engineExport("engine_series_is_monotonic_decreasing");
+export const engine_series_null_count_f64: (series_id: number) => number = engineExport("engine_series_null_count_f64");
+export const engine_series_exists: (series_id: number) => boolean = engineExport("engine_series_exists");
+export const engine_set_creation_tag: (tag: string) => void = engineExport("engine_set_creation_tag");
+export const engine_series_set_tag: (series_id: number, tag: string) => boolean = engineExport("engine_series_set_tag");
+export const engine_list_series_json: () => string = engineExport("engine_list_series_json");
+export const engine_scope_push: () => number = engineExport("engine_scope_push");
+export const engine_scope_pop: () => number = engineExport("engine_scope_pop");
+export const engine_series_pin: (series_id: number) => boolean = engineExport("engine_series_pin");
+export const engine_series_unpin: (series_id: number) => boolean = engineExport("engine_series_unpin");
+export const engine_set_memory_limit: (bytes: number) => void = engineExport("engine_set_memory_limit");
+export const engine_memory_limit: () => number = engineExport("engine_memory_limit");
+export const engine_memory_report_json: () => string = engineExport("engine_memory_report_json");
+export const engine_frame_create: (names_json: string, series_ids: Uint32Array) => number = engineExport("engine_frame_create");
+export const engine_frame_free: (frame_id: number) => boolean = engineExport("engine_frame_free");
+export const engine_frame_nrows: (frame_id: number) => number = engineExport("engine_frame_nrows");
+export const engine_frame_ncols: (frame_id: number) => number = engineExport("engine_frame_ncols");
+export const engine_frame_columns_json: (frame_id: number) => string = engineExport("engine_frame_columns_json");
+export const engine_frame_series_ids: (frame_id: number) => Uint32Array = engineExport("engine_frame_series_ids");
+export const engine_frame_column: (frame_id: number, name: string) => number = engineExport("engine_frame_column");
+export const engine_frame_add_column: (frame_id: number, name: string, series_id: number) => boolean = engineExport("engine_frame_add_column");
+export const engine_frame_drop_column: (frame_id: number, name: string) => boolean = engineExport("engine_frame_drop_column");
+export const engine_frame_rename_column: (frame_id: number, old_name: string, new_name: string) => boolean =
+    engineExport("engine_frame_rename_column");
+export const engine_frame_select: (frame_id: number, names_json: string) => number = engineExport("engine_frame_select");
+
+// decimal
+export const engine_create_series_decimal: (unscaled: BigInt64Array, scale: number) => number = engineExport("engine_create_series_decimal");
+export const engine_decimal_scale: (series_id: number) => number = engineExport("engine_decimal_scale");
+export const engine_decimal_from_f64: (series_id: number, scale: number, rounding_mode: number) => number = engineExport("engine_decimal_from_f64");
+export const engine_decimal_from_str: (series_id: number, scale: number, rounding_mode: number) => number = engineExport("engine_decimal_from_str");
+export const engine_decimal_to_f64: (series_id: number) => number = engineExport("engine_decimal_to_f64");
+export const engine_decimal_to_str: (series_id: number) => number = engineExport("engine_decimal_to_str");
+export const engine_decimal_rescale: (series_id: number, scale: number, rounding_mode: number) => number = engineExport("engine_decimal_rescale");
+export const engine_decimal_binary_op: (a_id: number, b_id: number, op: number) => number = engineExport("engine_decimal_binary_op");
+export const engine_decimal_compare: (a_id: number, b_id: number, op: number) => Uint8Array = engineExport("engine_decimal_compare");
+export const engine_decimal_sum: (series_id: number) => string = engineExport("engine_decimal_sum");
+export const engine_decimal_mean: (series_id: number) => string = engineExport("engine_decimal_mean");
+
+// error
+export const engine_install_panic_hook: () => void = engineExport("engine_install_panic_hook");
+export const engine_last_error_code: () => number = engineExport("engine_last_error_code");
+export const engine_last_error_message: () => string = engineExport("engine_last_error_message");
+export const engine_clear_error: () => void = engineExport("engine_clear_error");
+export const engine_take_panic_message: () => string = engineExport("engine_take_panic_message");
+
+// expr
+export const engine_expr_col: (series_id: number) => number = engineExport("engine_expr_col");
+export const engine_expr_lit: (value: number) => number = engineExport("engine_expr_lit");
+export const engine_expr_compare: (op: number, left: number, right: number) => number = engineExport("engine_expr_compare");
+export const engine_expr_and: (left: number, right: number) => number = engineExport("engine_expr_and");
+export const engine_expr_or: (left: number, right: number) => number = engineExport("engine_expr_or");
+export const engine_expr_not: (input: number) => number = engineExport("engine_expr_not");
+export const engine_expr_arith: (op: number, left: number, right: number) => number = engineExport("engine_expr_arith");
+export const engine_expr_filter: (input: number, predicate: number) => number = engineExport("engine_expr_filter");
+export const engine_expr_sort: (input: number, ascending: number, nulls_last: number) => number = engineExport("engine_expr_sort");
+export const engine_expr_agg: (input: number, agg: number) => number = engineExport("engine_expr_agg");
+export const engine_expr_groupby: (input: number, group_keys_json: string, agg: number) => number = engineExport("engine_expr_groupby");
+export const engine_expr_collect: (root: number) => number = engineExport("engine_expr_collect");
+export const engine_expr_clear: () => void = engineExport("engine_expr_clear");
+
+// fft
+export const engine_fft_f64: (series_id: number) => Uint32Array = engineExport("engine_fft_f64");
+export const engine_periodogram: (series_id: number, fs: number) => Uint32Array = engineExport("engine_periodogram");
+
+// filtering
+export const engine_frame_filter: (frame_id: number, mask: Uint8Array) => number = engineExport("engine_frame_filter");
+export const engine_rle_compare_mask: (rle_id: number, op: number, value: number) => Uint8Array = engineExport("engine_rle_compare_mask");
+export const engine_filter_rle: (rle_id: number, mask: Uint8Array) => number = engineExport("engine_filter_rle");
+export const engine_count_where_f64: (series_id: number, op: number, threshold: number) => number = engineExport("engine_count_where_f64");
+export const engine_mask_count_true: (mask_id: number) => number = engineExport("engine_mask_count_true");
+export const engine_mask_any: (mask_id: number) => boolean = engineExport("engine_mask_any");
+export const engine_mask_all: (mask_id: number) => boolean = engineExport("engine_mask_all");
+export const engine_series_any_nonzero_f64: (series_id: number) => boolean = engineExport("engine_series_any_nonzero_f64");
+
+// finance
+export const engine_sma: (series_id: number, window: number) => number = engineExport("engine_sma");
+export const engine_ema: (series_id: number, span: number) => number = engineExport("engine_ema");
+export const engine_rsi: (series_id: number, period: number) => number = engineExport("engine_rsi");
+export const engine_macd: (series_id: number, fast: number, slow: number, signal: number) => Uint32Array = engineExport("engine_macd");
+export const engine_bollinger: (series_id: number, window: number, num_std: number) => Uint32Array = engineExport("engine_bollinger");
+export const engine_waterfall_f64: (series_id: number) => Uint32Array = engineExport("engine_waterfall_f64");
+
+// frequency
+export const engine_freq_table: (series_id: number) => Uint32Array = engineExport("engine_freq_table");
+export const value_counts_str_packed: (bytes: Uint8Array, offsets: Uint32Array) => Uint32Array = engineExport("value_counts_str_packed");
+export const engine_series_entropy: (codes_id: number) => number = engineExport("engine_series_entropy");
+export const engine_series_gini: (codes_id: number) => number = engineExport("engine_series_gini");
+
+// geo
+export const engine_haversine_f64: (lat1_id: number, lon1_id: number, lat2_id: number, lon2_id: number) => number =
+    engineExport("engine_haversine_f64");
+export const engine_point_in_bbox: (lat_id: number, lon_id: number, min_lat: number, min_lon: number, max_lat: number, max_lon: number) => number =
+    engineExport("engine_point_in_bbox");
+export const engine_geohash_encode: (lat_id: number, lon_id: number, precision: number) => number = engineExport("engine_geohash_encode");
+export const engine_geohash_encode_i64: (lat_id: number, lon_id: number, precision: number) => number = engineExport("engine_geohash_encode_i64");
+export const engine_geohash_decode: (series_id: number) => Uint32Array = engineExport("engine_geohash_decode");
+export const engine_geohash_decode_i64: (series_id: number, precision: number) => Uint32Array = engineExport("engine_geohash_decode_i64");
+
+// groupby
+export const engine_set_groupby_order: (order: number, keys_json: string) => boolean = engineExport("engine_set_groupby_order");
+export const engine_groupby_order: () => number = engineExport("engine_groupby_order");
+export const engine_groupby_size: (group_keys_json: string) => number = engineExport("engine_groupby_size");
+export const engine_groupby_describe_f64: (series_id: number, group_keys_json: string) => Uint32Array = engineExport("engine_groupby_describe_f64");
+export const engine_groupby_sample_indices: (group_keys_json: string, n_per_group: number, seed: bigint) => Uint32Array =
+    engineExport("engine_groupby_sample_indices");
+export const engine_groupby_filtered_f64: (series_id: number, group_keys_json: string, mask: Uint8Array, agg_mask: number) => Uint32Array =
+    engineExport("engine_groupby_filtered_f64");
+export const engine_groupby_packed_f64: (series_id: number, key_bytes: Uint8Array, key_offsets: Uint32Array, agg_mask: number) => Uint32Array =
+    engineExport("engine_groupby_packed_f64");
+export const engine_groupby_rle_f64: (series_id: number, rle_key_id: number, agg_mask: number) => Uint32Array =
+    engineExport("engine_groupby_rle_f64");
+export const engine_groupby_interned_f64: (series_id: number, key_id: number, agg_mask: number) => Uint32Array =
+    engineExport("engine_groupby_interned_f64");
+export const engine_groupby_ewm_f64: (value_id: number, key_codes_id: number, alpha: number, agg_kind: number) => number =
+    engineExport("engine_groupby_ewm_f64");
+export const engine_groupby_share_f64: (value_id: number, key_codes_id: number) => number = engineExport("engine_groupby_share_f64");
+export const engine_groupby_apply: (value_id: number, key_codes_id: number, cb: JsFunction) => Uint32Array = engineExport("engine_groupby_apply");
+export const engine_groupby_entropy: (codes_id: number, key_codes_id: number) => Uint32Array = engineExport("engine_groupby_entropy");
+export const engine_groupby_gini: (codes_id: number, key_codes_id: number) => Uint32Array = engineExport("engine_groupby_gini");
+export const engine_partition_indices: (key_codes_id: number) => Uint32Array = engineExport("engine_partition_indices");
+
+// intern
+export const engine_intern_str: (series_id: number) => number = engineExport("engine_intern_str");
+export const engine_intern_keys_json: (keys_json: string) => number = engineExport("engine_intern_keys_json");
+export const engine_intern_keys_packed: (bytes: Uint8Array, offsets: Uint32Array) => number = engineExport("engine_intern_keys_packed");
+export const engine_interned_to_str: (series_id: number) => number = engineExport("engine_interned_to_str");
+export const engine_interned_codes: (series_id: number) => Uint32Array = engineExport("engine_interned_codes");
+export const engine_intern_lookup: (text: string) => number = engineExport("engine_intern_lookup");
+export const engine_intern_get: (code: number) => string = engineExport("engine_intern_get");
+export const engine_intern_stats: () => string = engineExport("engine_intern_stats");
+
+// join
+export const engine_sorted_lookup_f64: (sorted_keys_id: number, sorted_values_id: number, probe_series_id: number) => number =
+    engineExport("engine_sorted_lookup_f64");
+export const engine_sorted_lookup_nearest_f64: (sorted_keys_id: number, sorted_values_id: number, probe_series_id: number) => number =
+    engineExport("engine_sorted_lookup_nearest_f64");
+export const engine_cross_join_indices: (left_len: number, right_len: number, limit: number) => Uint32Array =
+    engineExport("engine_cross_join_indices");
+export const engine_join_indices_f64: (left_keys_id: number, right_keys_id: number) => Uint32Array = engineExport("engine_join_indices_f64");
+export const engine_align_op_f64: (
+    left_index_id: number,
+    left_values_id: number,
+    right_index_id: number,
+    right_values_id: number,
+    op: number,
+) => Uint32Array = engineExport("engine_align_op_f64");
+export const engine_align_add_f64: (left_index_id: number, left_values_id: number, right_index_id: number, right_values_id: number) => Uint32Array =
+    engineExport("engine_align_add_f64");
+export const engine_reindex_f64: (index_id: number, values_id: number, new_index_id: number, fill_policy: number) => number =
+    engineExport("engine_reindex_f64");
+
+// json_records
+export const engine_parse_json_records: (bytes: Uint8Array, schema_json: string) => Uint32Array = engineExport("engine_parse_json_records");
+export const engine_str_json_get: (series_id: number, path: string) => number = engineExport("engine_str_json_get");
+
+// logging
+export const engine_set_log_level: (level: number) => void = engineExport("engine_set_log_level");
+
+// membership
+export const isin_string_packed: (bytes: Uint8Array, offsets: Uint32Array, values_bytes: Uint8Array, values_offsets: Uint32Array) => Uint8Array =
+    engineExport("isin_string_packed");
+
+// normalize
+export const engine_str_normalize: (series_id: number, form: number, case_fold: boolean) => number = engineExport("engine_str_normalize");
+
+// number_format
+export const engine_format_f64: (series_id: number, pattern: string, locale_opts: string) => number = engineExport("engine_format_f64");
+
+// parquet
+export const engine_read_parquet: (bytes: Uint8Array, columns_json: string) => Uint32Array = engineExport("engine_read_parquet");
+export const engine_parquet_schema_json: (bytes: Uint8Array) => string = engineExport("engine_parquet_schema_json");
+
+// profiling
+export const engine_profiling_enable: () => void = engineExport("engine_profiling_enable");
+export const engine_profiling_disable: () => void = engineExport("engine_profiling_disable");
+export const engine_profiling_reset: () => void = engineExport("engine_profiling_reset");
+export const engine_profiling_report_json: () => string = engineExport("engine_profiling_report_json");
+
+// random
+export const engine_random_f64: (len: number, distribution: number, params: Float64Array, seed: bigint) => number = engineExport("engine_random_f64");
+
+// rle
+export const engine_series_rle_encode: (series_id: number) => number = engineExport("engine_series_rle_encode");
+export const engine_series_rle_decode: (rle_id: number) => number = engineExport("engine_series_rle_decode");
+export const engine_rle_num_runs: (rle_id: number) => number = engineExport("engine_rle_num_runs");
+export const engine_rle_values: (rle_id: number) => Float64Array = engineExport("engine_rle_values");
+export const engine_rle_run_ends: (rle_id: number) => Uint32Array = engineExport("engine_rle_run_ends");
+
+// rolling
+export const engine_rolling_by_time_f64: (time_series_id: number, value_series_id: number, window_ms: number, agg_kind: number) => number =
+    engineExport("engine_rolling_by_time_f64");
+export const engine_rolling_apply_f64: (series_id: number, window: number, cb: JsFunction) => number = engineExport("engine_rolling_apply_f64");
+
+// rowwise
+export const engine_row_reduce_f64: (series_ids: Uint32Array, op: number) => number = engineExport("engine_row_reduce_f64");
+export const engine_row_expr_f64: (series_ids: Uint32Array, expr_bytes: Uint8Array) => number = engineExport("engine_row_expr_f64");
+
+// series
+export const engine_series_ptr_i64: (series_id: number) => number = engineExport("engine_series_ptr_i64");
+export const engine_series_len_i64: (series_id: number) => number = engineExport("engine_series_len_i64");
+export const engine_series_to_vec_i64: (series_id: number) => BigInt64Array = engineExport("engine_series_to_vec_i64");
+export const engine_series_ptr_bool: (series_id: number) => number = engineExport("engine_series_ptr_bool");
+export const engine_series_len_bool: (series_id: number) => number = engineExport("engine_series_len_bool");
+export const engine_series_to_vec_bool: (series_id: number) => Uint8Array = engineExport("engine_series_to_vec_bool");
+export const engine_series_ptr_u32: (series_id: number) => number = engineExport("engine_series_ptr_u32");
+export const engine_series_len_u32: (series_id: number) => number = engineExport("engine_series_len_u32");
+export const engine_series_to_vec_u32: (series_id: number) => Uint32Array = engineExport("engine_series_to_vec_u32");
+export const engine_series_ptr_u8: (series_id: number) => number = engineExport("engine_series_ptr_u8");
+export const engine_series_len_u8: (series_id: number) => number = engineExport("engine_series_len_u8");
+export const engine_series_to_vec_u8: (series_id: number) => Uint8Array = engineExport("engine_series_to_vec_u8");
+export const engine_series_len_str: (series_id: number) => number = engineExport("engine_series_len_str");
+export const engine_series_to_vec_str: (series_id: number) => string[] = engineExport("engine_series_to_vec_str");
+export const engine_series_to_json_str: (series_id: number) => string = engineExport("engine_series_to_json_str");
+export const engine_series_str_bytes_ptr: (series_id: number) => number = engineExport("engine_series_str_bytes_ptr");
+export const engine_series_str_bytes_len: (series_id: number) => number = engineExport("engine_series_str_bytes_len");
+export const engine_series_str_offsets_ptr: (series_id: number) => number = engineExport("engine_series_str_offsets_ptr");
+export const engine_series_str_null_mask: (series_id: number) => Uint8Array = engineExport("engine_series_str_null_mask");
+export const engine_series_get_str: (series_id: number, row: number) => string | undefined = engineExport("engine_series_get_str");
+export const engine_series_minmax_with_index_f64: (series_id: number) => Float64Array = engineExport("engine_series_minmax_with_index_f64");
+export const engine_series_first_valid_index_f64: (series_id: number) => number = engineExport("engine_series_first_valid_index_f64");
+export const engine_series_last_valid_index_f64: (series_id: number) => number = engineExport("engine_series_last_valid_index_f64");
+export const engine_series_agg_multi_f64: (series_id: number, agg_mask: number) => Float64Array = engineExport("engine_series_agg_multi_f64");
+export const engine_many_series_agg_f64: (series_ids: Uint32Array, agg_kind: number) => Float64Array = engineExport("engine_many_series_agg_f64");
+export const engine_share_of_total_f64: (series_id: number) => number = engineExport("engine_share_of_total_f64");
+export const engine_series_sum_u32: (series_id: number) => number = engineExport("engine_series_sum_u32");
+export const engine_series_mean_u32: (series_id: number) => number = engineExport("engine_series_mean_u32");
+export const engine_series_min_u32: (series_id: number) => number = engineExport("engine_series_min_u32");
+export const engine_series_max_u32: (series_id: number) => number = engineExport("engine_series_max_u32");
+export const engine_series_sum_u8: (series_id: number) => number = engineExport("engine_series_sum_u8");
+export const engine_series_mean_u8: (series_id: number) => number = engineExport("engine_series_mean_u8");
+export const engine_series_min_u8: (series_id: number) => number = engineExport("engine_series_min_u8");
+export const engine_series_max_u8: (series_id: number) => number = engineExport("engine_series_max_u8");
+export const engine_series_copy_into_f64: (series_id: number, dst_ptr: number, dst_len: number) => number =
+    engineExport("engine_series_copy_into_f64");
+export const engine_series_copy_into_i32: (series_id: number, dst_ptr: number, dst_len: number) => number =
+    engineExport("engine_series_copy_into_i32");
+export const engine_isna_mask_into_f64: (series_id: number, dst_ptr: number, dst_len: number) => number = engineExport("engine_isna_mask_into_f64");
+export const engine_series_map_f64: (series_id: number, cb: JsFunction, batch_size: number) => number = engineExport("engine_series_map_f64");
+export const engine_series_fillna_f64: (series_id: number, value: number, in_place: number) => number = engineExport("engine_series_fillna_f64");
+export const engine_series_scalar_op_f64: (series_id: number, op: number, scalar: number, in_place: number) => number =
+    engineExport("engine_series_scalar_op_f64");
+export const engine_series_set_where_f64: (series_id: number, mask_id: number, value: number, other_id: number, in_place: number) => number =
+    engineExport("engine_series_set_where_f64");
+export const engine_series_scatter_f64: (series_id: number, indices: Uint32Array, values: Float64Array, in_place: number) => number =
+    engineExport("engine_series_scatter_f64");
+export const engine_reverse_f64: (series_id: number, in_place: number) => number = engineExport("engine_reverse_f64");
+export const engine_roll_f64: (series_id: number, shift: number, in_place: number) => number = engineExport("engine_roll_f64");
+export const engine_lag_matrix_f64: (series_id: number, lags: Uint32Array) => Uint32Array = engineExport("engine_lag_matrix_f64");
+export const engine_slice_f64: (series_id: number, start: number, stop: number, step: number) => number = engineExport("engine_slice_f64");
+
+// signal
+export const engine_convolve_f64: (series_id: number, kernel: Float64Array, mode: number) => number = engineExport("engine_convolve_f64");
+export const engine_gaussian_smooth_f64: (series_id: number, sigma: number) => number = engineExport("engine_gaussian_smooth_f64");
+export const engine_savgol_f64: (series_id: number, window: number, polyorder: number) => number = engineExport("engine_savgol_f64");
+
+// sorting
+export const engine_sort_values_f64_inplace: (series_id: number, ascending: number, nulls_last: number) => number =
+    engineExport("engine_sort_values_f64_inplace");
+export const engine_frame_sort: (frame_id: number, by_json: string, ascending: Uint8Array, nulls_last: number) => number =
+    engineExport("engine_frame_sort");
+export const engine_sort_by_codes: (codes_series_ids: Uint32Array, ascending: Uint8Array) => Uint32Array = engineExport("engine_sort_by_codes");
+export const sort_two_columns_f64_u32: (col1: Float64Array, col2: Float64Array, asc1: number, asc2: number, nulls_last: number) => Uint32Array =
+    engineExport("sort_two_columns_f64_u32");
+export const sort_two_columns_i32_u32: (col1: Int32Array, col2: Int32Array, asc1: number, asc2: number, nulls_last: number) => Uint32Array =
+    engineExport("sort_two_columns_i32_u32");
+export const sort_single_column_f64_u32: (data: Float64Array, ascending: boolean, nulls_last: boolean) => Uint32Array =
+    engineExport("sort_single_column_f64_u32");
+export const sort_single_column_i32_u32: (data: Int32Array, ascending: boolean, nulls_last: boolean) => Uint32Array =
+    engineExport("sort_single_column_i32_u32");
+export const engine_sort_indices_into_f64: (series_id: number, ascending: number, nulls_last: number, dst_ptr: number, dst_len: number) => number =
+    engineExport("engine_sort_indices_into_f64");
+export const engine_sort_indices_into_i32: (series_id: number, ascending: number, nulls_last: number, dst_ptr: number, dst_len: number) => number =
+    engineExport("engine_sort_indices_into_i32");
+
+// statistics
+export const sum_i64: (data: BigInt64Array) => bigint = engineExport("sum_i64");
+export const mean_i64_as_f64: (data: BigInt64Array) => number = engineExport("mean_i64_as_f64");
+export const min_i64: (data: BigInt64Array) => bigint = engineExport("min_i64");
+export const max_i64: (data: BigInt64Array) => bigint = engineExport("max_i64");
+
+// statistics/correlation
+export const engine_cov_matrix_f64: (series_ids: Uint32Array, min_periods: number, nulls: number) => Float64Array =
+    engineExport("engine_cov_matrix_f64");
+export const engine_corr_matrix_f64: (series_ids: Uint32Array, min_periods: number, nulls: number) => Float64Array =
+    engineExport("engine_corr_matrix_f64");
+export const engine_series_cov_f64: (a_id: number, b_id: number, min_periods: number) => number = engineExport("engine_series_cov_f64");
+export const engine_series_corr_f64: (a_id: number, b_id: number, min_periods: number) => number = engineExport("engine_series_corr_f64");
+
+// statistics/hypothesis
+export const engine_ttest_ind_f64: (a_id: number, b_id: number, equal_var: number) => Float64Array = engineExport("engine_ttest_ind_f64");
+export const engine_chi2_crosstab: (row_keys_id: number, col_keys_id: number) => Float64Array = engineExport("engine_chi2_crosstab");
+
+// statistics/pca
+export const engine_pca: (series_ids: Uint32Array, n_components: number) => Uint32Array = engineExport("engine_pca");
+
+// statistics/robust
+export const engine_winsorize_f64: (series_id: number, lower_q: number, upper_q: number) => number = engineExport("engine_winsorize_f64");
+export const engine_trimmed_mean_f64: (series_id: number, proportion: number) => number = engineExport("engine_trimmed_mean_f64");
+
+// tasks
+export const engine_cancel_flag_ptr: () => number = engineExport("engine_cancel_flag_ptr");
+export const engine_request_cancel: () => void = engineExport("engine_request_cancel");
+export const engine_cancel_reset: () => void = engineExport("engine_cancel_reset");
+export const engine_task_start_sort_f64: (series_id: number, ascending: number, nulls_last: number) => number =
+    engineExport("engine_task_start_sort_f64");
+export const engine_task_start_groupby_f64: (series_id: number, group_keys_json: string, agg_mask: number) => number =
+    engineExport("engine_task_start_groupby_f64");
+export const engine_task_start_join_f64: (left_keys_id: number, right_keys_id: number) => number = engineExport("engine_task_start_join_f64");
+export const engine_task_step: (task_id: number, ms_budget: number) => number = engineExport("engine_task_step");
+export const engine_task_cancel: (task_id: number) => boolean = engineExport("engine_task_cancel");
+export const engine_task_progress: (task_id: number) => number = engineExport("engine_task_progress");
+export const engine_task_result: (task_id: number) => Uint32Array = engineExport("engine_task_result");
+
+// text
+export const engine_str_tokenize: (series_id: number, delimiter_or_regex: string) => Uint32Array = engineExport("engine_str_tokenize");
+export const engine_ngram_counts: (series_id: number, n: number) => Uint32Array = engineExport("engine_ngram_counts");
+export const engine_tfidf: (series_id: number, max_features: number) => Uint32Array = engineExport("engine_tfidf");
+
+// timeseries
+export const engine_min_max_decimate: (time_id: number, value_id: number, bucket_count: number) => Uint32Array =
+    engineExport("engine_min_max_decimate");
+export const engine_segmented_stats_f64: (value_id: number, boundary_indices: Uint32Array) => Uint32Array =
+    engineExport("engine_segmented_stats_f64");
+export const engine_cusum_f64: (series_id: number, target: number, k: number, h: number) => Uint32Array = engineExport("engine_cusum_f64");
+export const engine_interp_to_grid_f64: (src_times_id: number, src_values_id: number, target_times_id: number) => number =
+    engineExport("engine_interp_to_grid_f64");
+
+// validate
+export const engine_validate_f64: (series_id: number, rules_json: string) => string = engineExport("engine_validate_f64");
+export const engine_str_is_email: (series_id: number) => number = engineExport("engine_str_is_email");
+export const engine_str_is_url: (series_id: number) => number = engineExport("engine_str_is_url");
+export const engine_str_is_numeric: (series_id: number) => number = engineExport("engine_str_is_numeric");
+
+// window
+export const engine_window_row_number: (partition_codes_id: number, order_series_id: number) => number = engineExport("engine_window_row_number");
+export const engine_window_ntile: (partition_codes_id: number, order_series_id: number, n: number) => number = engineExport("engine_window_ntile");
+export const engine_window_percent_rank: (partition_codes_id: number, order_series_id: number) => number = engineExport("engine_window_percent_rank");
+export const engine_window_cume_dist: (partition_codes_id: number, order_series_id: number) => number = engineExport("engine_window_cume_dist");
+export const engine_window_dense_rank: (partition_codes_id: number, order_series_id: number) => number = engineExport("engine_window_dense_rank");
+export const engine_window_lag_f64: (
+    value_id: number,
+    partition_codes_id: number,
+    order_series_id: number,
+    offset: number,
+    _default: number,
+) => number = engineExport("engine_window_lag_f64");
+export const engine_window_lead_f64: (
+    value_id: number,
+    partition_codes_id: number,
+    order_series_id: number,
+    offset: number,
+    _default: number,
+) => number = engineExport("engine_window_lead_f64");
@@ -0,0 +1,611 @@
+//! Arrow interop: Arrow IPC stream export of registered series
+//!
+//! This module serializes registered series into the Arrow IPC streaming
+//! format (schema message, one record batch, end-of-stream marker) so results
+//! can be handed to Arrow-speaking libraries without per-value conversion.
+
+use serde_json;
+use wasm_bindgen::prelude::*;
+use crate::core::{EngineState, ENGINE};
+use crate::error::{set_last_error, EngineError};
+use crate::profiling::{profile, series_bytes};
+
+// Arrow IPC constants (Schema.fbs / Message.fbs)
+const METADATA_V5: i16 = 4;
+const HEADER_SCHEMA: u8 = 1;
+const HEADER_RECORD_BATCH: u8 = 3;
+const TYPE_INT: u8 = 2;
+const TYPE_FLOATING_POINT: u8 = 3;
+const TYPE_UTF8: u8 = 5;
+const TYPE_BOOL: u8 = 6;
+const TYPE_DECIMAL: u8 = 7;
+const PRECISION_DOUBLE: i16 = 2;
+// Decimal128 precision that holds every i64 value
+const DECIMAL_PRECISION: i32 = 19;
+const CONTINUATION: u32 = 0xFFFF_FFFF;
+
+/// Field value inside a flatbuffer table
+enum FbField {
+    U8(u8),
+    Bool(bool),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    Str(String),
+    Table(FbTable),
+    Tables(Vec<FbTable>),
+    /// Vector of 16-byte (two i64) structs, e.g. FieldNode or Buffer
+    Structs(Vec<(i64, i64)>),
+}
+
+impl FbField {
+    fn inline_size(&self) -> usize {
+        match self {
+            FbField::U8(_) | FbField::Bool(_) => 1,
+            FbField::I16(_) => 2,
+            FbField::I64(_) => 8,
+            _ => 4,
+        }
+    }
+}
+
+/// Flatbuffer table as a list of (vtable slot, value) pairs
+#[derive(Default)]
+struct FbTable {
+    fields: Vec<(usize, FbField)>,
+}
+
+impl FbTable {
+    fn with(mut self, slot: usize, field: FbField) -> Self {
+        self.fields.push((slot, field));
+        self
+    }
+}
+
+/// Front-to-back flatbuffer writer: vtables precede their tables and child
+/// objects are appended after the parent, so every uoffset points forward.
+struct FbWriter {
+    buf: Vec<u8>,
+}
+
+impl FbWriter {
+    fn finish(root: &FbTable) -> Vec<u8> {
+        let mut w = FbWriter { buf: vec![0; 4] };
+        let root_pos = w.write_table(root);
+        w.patch_u32(0, root_pos as u32);
+        w.pad_to(8);
+        w.buf
+    }
+
+    fn pad_to(&mut self, align: usize) {
+        while !self.buf.len().is_multiple_of(align) {
+            self.buf.push(0);
+        }
+    }
+
+    fn patch_u32(&mut self, at: usize, value: u32) {
+        self.buf[at..at + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_table(&mut self, table: &FbTable) -> usize {
+        // Inline layout relative to the table start (soffset occupies 0..4)
+        let mut inline_size: usize = 4;
+        let mut field_offsets: Vec<usize> = Vec::with_capacity(table.fields.len());
+        for (_, field) in table.fields.iter() {
+            let size = field.inline_size();
+            inline_size = inline_size.div_ceil(size) * size;
+            field_offsets.push(inline_size);
+            inline_size += size;
+        }
+        let num_slots = table.fields.iter().map(|(slot, _)| slot + 1).max().unwrap_or(0);
+
+        // Vtable
+        self.pad_to(2);
+        let vtable_pos = self.buf.len();
+        let mut slots = vec![0u16; num_slots];
+        for ((slot, _), off) in table.fields.iter().zip(field_offsets.iter()) {
+            slots[*slot] = *off as u16;
+        }
+        self.buf.extend_from_slice(&((4 + 2 * num_slots) as u16).to_le_bytes());
+        self.buf.extend_from_slice(&(inline_size as u16).to_le_bytes());
+        for s in slots {
+            self.buf.extend_from_slice(&s.to_le_bytes());
+        }
+
+        // Table body, 8-aligned so that i64 fields are naturally aligned
+        self.pad_to(8);
+        let table_pos = self.buf.len();
+        self.buf.resize(table_pos + inline_size, 0);
+        let soffset = (table_pos - vtable_pos) as i32;
+        self.buf[table_pos..table_pos + 4].copy_from_slice(&soffset.to_le_bytes());
+
+        let mut pending: Vec<(usize, &FbField)> = Vec::new();
+        for ((_, field), off) in table.fields.iter().zip(field_offsets.iter()) {
+            let at = table_pos + off;
+            match field {
+                FbField::U8(v) => self.buf[at] = *v,
+                FbField::Bool(v) => self.buf[at] = *v as u8,
+                FbField::I16(v) => self.buf[at..at + 2].copy_from_slice(&v.to_le_bytes()),
+                FbField::I32(v) => self.buf[at..at + 4].copy_from_slice(&v.to_le_bytes()),
+                FbField::I64(v) => self.buf[at..at + 8].copy_from_slice(&v.to_le_bytes()),
+                _ => pending.push((at, field)),
+            }
+        }
+
+        // Children are written after the table and the uoffsets patched in
+        for (at, field) in pending {
+            let child_pos = self.write_child(field);
+            self.patch_u32(at, (child_pos - at) as u32);
+        }
+        table_pos
+    }
+
+    fn write_child(&mut self, field: &FbField) -> usize {
+        match field {
+            FbField::Str(s) => {
+                self.pad_to(4);
+                let pos = self.buf.len();
+                self.buf.extend_from_slice(&(s.len() as u32).to_le_bytes());
+                self.buf.extend_from_slice(s.as_bytes());
+                self.buf.push(0);
+                pos
+            }
+            FbField::Table(t) => self.write_table(t),
+            FbField::Tables(tables) => {
+                self.pad_to(4);
+                let pos = self.buf.len();
+                self.buf.extend_from_slice(&(tables.len() as u32).to_le_bytes());
+                let slots_pos = self.buf.len();
+                self.buf.resize(slots_pos + 4 * tables.len(), 0);
+                for (i, t) in tables.iter().enumerate() {
+                    let at = slots_pos + 4 * i;
+                    let table_pos = self.write_table(t);
+                    self.patch_u32(at, (table_pos - at) as u32);
+                }
+                pos
+            }
+            FbField::Structs(items) => {
+                // Length prefix must sit directly before the 8-aligned data
+                while !(self.buf.len() + 4).is_multiple_of(8) {
+                    self.buf.push(0);
+                }
+                let pos = self.buf.len();
+                self.buf.extend_from_slice(&(items.len() as u32).to_le_bytes());
+                for (a, b) in items.iter() {
+                    self.buf.extend_from_slice(&a.to_le_bytes());
+                    self.buf.extend_from_slice(&b.to_le_bytes());
+                }
+                pos
+            }
+            _ => unreachable!("scalar fields are written inline"),
+        }
+    }
+}
+
+/// Column data copied out of the engine for export
+enum ExportColumn {
+    F64(Vec<f64>),
+    I32(Vec<i32>),
+    I64(Vec<i64>),
+    /// Fixed-point i64 values and their scale
+    Decimal(Vec<i64>, u8),
+    Bool(Vec<u8>),
+    U32(Vec<u32>),
+    U8(Vec<u8>),
+    Utf8(Vec<Option<String>>),
+}
+
+impl ExportColumn {
+    /// Copy a registered series of any dtype; None if the id is unknown
+    fn read(eng: &EngineState, id: u32) -> Option<ExportColumn> {
+        fn copy<T: Copy>(ptr: *mut T, len: usize) -> Vec<T> {
+            if ptr.is_null() || len == 0 { Vec::new() } else { unsafe { std::slice::from_raw_parts(ptr, len).to_vec() } }
+        }
+        if let Some(values) = eng.f64_values(id) {
+            return Some(ExportColumn::F64(values.to_vec()));
+        }
+        if let Some(&(ptr, len)) = eng.series_store_i32.get(&id) {
+            return Some(ExportColumn::I32(copy(ptr, len)));
+        }
+        if let Some(&(ptr, len)) = eng.series_store_i64.get(&id) {
+            return Some(match eng.decimal_scales.get(&id) {
+                Some(&scale) => ExportColumn::Decimal(copy(ptr, len), scale),
+                None => ExportColumn::I64(copy(ptr, len)),
+            });
+        }
+        if let Some(&(ptr, len)) = eng.series_store_bool.get(&id) {
+            return Some(ExportColumn::Bool(copy(ptr, len)));
+        }
+        if let Some(&(ptr, len)) = eng.series_store_u32.get(&id) {
+            return Some(ExportColumn::U32(copy(ptr, len)));
+        }
+        if let Some(&(ptr, len)) = eng.series_store_u8.get(&id) {
+            return Some(ExportColumn::U8(copy(ptr, len)));
+        }
+        if let Some(rle) = eng.series_store_rle.get(&id) {
+            return Some(ExportColumn::F64(rle.runs().flat_map(|(start, end, v)| std::iter::repeat_n(v, end - start)).collect()));
+        }
+        if let Some(strings) = eng.series_store_str.get(&id) {
+            return Some(ExportColumn::Utf8(strings.iter().map(|s| s.map(str::to_string)).collect()));
+        }
+        let codes = eng.series_store_interned.get(&id)?;
+        Some(ExportColumn::Utf8(codes.iter().map(|&code| eng.interner.get(code).map(str::to_string)).collect()))
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            ExportColumn::F64(v) => v.len(),
+            ExportColumn::I32(v) => v.len(),
+            ExportColumn::I64(v) | ExportColumn::Decimal(v, _) => v.len(),
+            ExportColumn::Bool(v) | ExportColumn::U8(v) => v.len(),
+            ExportColumn::U32(v) => v.len(),
+            ExportColumn::Utf8(v) => v.len(),
+        }
+    }
+
+    fn is_null(&self, i: usize) -> bool {
+        match self {
+            ExportColumn::F64(v) => v[i].is_nan(),
+            ExportColumn::I32(v) => v[i] == i32::MIN,
+            ExportColumn::I64(v) | ExportColumn::Decimal(v, _) => v[i] == i64::MIN,
+            ExportColumn::Utf8(v) => v[i].is_none(),
+            ExportColumn::Bool(_) | ExportColumn::U32(_) | ExportColumn::U8(_) => false,
+        }
+    }
+
+    fn field(&self, name: &str) -> FbTable {
+        let int = |bits: i32, signed: bool| (TYPE_INT, FbTable::default().with(0, FbField::I32(bits)).with(1, FbField::Bool(signed)));
+        let (type_type, type_table) = match self {
+            ExportColumn::F64(_) => (
+                TYPE_FLOATING_POINT,
+                FbTable::default().with(0, FbField::I16(PRECISION_DOUBLE)),
+            ),
+            ExportColumn::I32(_) => int(32, true),
+            ExportColumn::I64(_) => int(64, true),
+            ExportColumn::Decimal(_, scale) => (
+                TYPE_DECIMAL,
+                FbTable::default()
+                    .with(0, FbField::I32(DECIMAL_PRECISION))
+                    .with(1, FbField::I32(*scale as i32))
+                    .with(2, FbField::I32(128)),
+            ),
+            ExportColumn::Bool(_) => (TYPE_BOOL, FbTable::default()),
+            ExportColumn::U32(_) => int(32, false),
+            ExportColumn::U8(_) => int(8, false),
+            ExportColumn::Utf8(_) => (TYPE_UTF8, FbTable::default()),
+        };
+        FbTable::default()
+            .with(0, FbField::Str(name.to_string()))
+            .with(1, FbField::Bool(true))
+            .with(2, FbField::U8(type_type))
+            .with(3, FbField::Table(type_table))
+            .with(5, FbField::Tables(Vec::new()))
+    }
+
+    /// Buffers following the validity bitmap: the values, or for strings
+    /// the i32 offsets and the UTF-8 data. None if string data exceeds the
+    /// i32 offset range.
+    fn value_buffers(&self) -> Option<Vec<Vec<u8>>> {
+        Some(match self {
+            ExportColumn::F64(v) => vec![v.iter().flat_map(|x| x.to_le_bytes()).collect()],
+            ExportColumn::I32(v) => vec![v.iter().flat_map(|x| x.to_le_bytes()).collect()],
+            ExportColumn::I64(v) => vec![v.iter().flat_map(|x| x.to_le_bytes()).collect()],
+            ExportColumn::Decimal(v, _) => vec![v.iter().flat_map(|&x| (x as i128).to_le_bytes()).collect()],
+            ExportColumn::Bool(v) => {
+                let mut bits = vec![0u8; v.len().div_ceil(8)];
+                for (i, _) in v.iter().enumerate().filter(|(_, &b)| b != 0) {
+                    bits[i / 8] |= 1 << (i % 8);
+                }
+                vec![bits]
+            }
+            ExportColumn::U32(v) => vec![v.iter().flat_map(|x| x.to_le_bytes()).collect()],
+            ExportColumn::U8(v) => vec![v.clone()],
+            ExportColumn::Utf8(v) => {
+                let mut offsets: Vec<u8> = 0i32.to_le_bytes().to_vec();
+                let mut data: Vec<u8> = Vec::new();
+                for s in v.iter() {
+                    data.extend_from_slice(s.as_deref().unwrap_or_default().as_bytes());
+                    offsets.extend_from_slice(&i32::try_from(data.len()).ok()?.to_le_bytes());
+                }
+                vec![offsets, data]
+            }
+        })
+    }
+}
+
+/// Append one encapsulated IPC message (continuation, length, metadata, body)
+fn write_message(out: &mut Vec<u8>, header_type: u8, header: FbTable, body: &[u8]) {
+    let message = FbTable::default()
+        .with(0, FbField::I16(METADATA_V5))
+        .with(1, FbField::U8(header_type))
+        .with(2, FbField::Table(header))
+        .with(3, FbField::I64(body.len() as i64));
+    let metadata = FbWriter::finish(&message);
+    out.extend_from_slice(&CONTINUATION.to_le_bytes());
+    out.extend_from_slice(&(metadata.len() as i32).to_le_bytes());
+    out.extend_from_slice(&metadata);
+    out.extend_from_slice(body);
+}
+
+fn pad_body(body: &mut Vec<u8>) {
+    while !body.len().is_multiple_of(8) {
+        body.push(0);
+    }
+}
+
+/// Export registered series as an Arrow IPC stream. Float64 series
+/// (including chunked and run-length encoded ones) export as Float64,
+/// int32/int64/uint32/uint8 as Int of that width, decimals as Decimal128
+/// with their scale, bool as Bool and string series (including interned
+/// ones) as Utf8. NaN, i32::MIN, i64::MIN and null strings are exported as
+/// nulls. Returns an empty buffer if any id is unknown or the lengths
+/// differ, or with error code 1 if a string column holds more than
+/// i32::MAX bytes.
+#[wasm_bindgen]
+pub fn engine_export_arrow(series_ids: &[u32], names_json: &str) -> Vec<u8> {
+    let _prof = profile("engine_export_arrow", || series_ids.iter().map(|id| series_bytes(*id)).sum());
+    let names: Vec<String> = serde_json::from_str(names_json).unwrap_or_default();
+
+    let columns: Option<Vec<ExportColumn>> = ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        series_ids.iter().map(|&id| ExportColumn::read(&eng, id)).collect()
+    });
+    let columns = match columns {
+        Some(c) => c,
+        None => {
+            engine_log!(warn, "engine_export_arrow: unknown series in ids={:?}", series_ids);
+            return Vec::new();
+        }
+    };
+    let num_rows = columns.first().map(|c| c.len()).unwrap_or(0);
+    if columns.iter().any(|c| c.len() != num_rows) {
+        engine_log!(warn, "engine_export_arrow: series lengths differ ids={:?}", series_ids);
+        return Vec::new();
+    }
+
+    let mut out: Vec<u8> = Vec::new();
+
+    // Schema message
+    let fields: Vec<FbTable> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            let name = names.get(i).cloned().unwrap_or_else(|| format!("column_{}", i));
+            c.field(&name)
+        })
+        .collect();
+    let schema = FbTable::default()
+        .with(0, FbField::I16(0))
+        .with(1, FbField::Tables(fields));
+    write_message(&mut out, HEADER_SCHEMA, schema, &[]);
+
+    // Record batch: validity bitmap (omitted when there are no nulls) + values
+    let mut body: Vec<u8> = Vec::new();
+    let mut nodes: Vec<(i64, i64)> = Vec::with_capacity(columns.len());
+    let mut buffers: Vec<(i64, i64)> = Vec::with_capacity(columns.len() * 2);
+    for (col, &id) in columns.iter().zip(series_ids) {
+        let values = match col.value_buffers() {
+            Some(values) => values,
+            None => {
+                engine_log!(warn, "engine_export_arrow: string data of series {} exceeds i32 offsets", id);
+                set_last_error(EngineError::Layout { len: col.len() });
+                return Vec::new();
+            }
+        };
+        let null_count = (0..num_rows).filter(|&i| col.is_null(i)).count();
+        nodes.push((num_rows as i64, null_count as i64));
+
+        let validity_start = body.len();
+        if null_count > 0 {
+            let mut bitmap = vec![0u8; num_rows.div_ceil(8)];
+            for i in 0..num_rows {
+                if !col.is_null(i) {
+                    bitmap[i / 8] |= 1 << (i % 8);
+                }
+            }
+            body.extend_from_slice(&bitmap);
+        }
+        let validity_len = body.len() - validity_start;
+        pad_body(&mut body);
+        buffers.push((validity_start as i64, validity_len as i64));
+
+        for buffer in values {
+            let start = body.len();
+            body.extend_from_slice(&buffer);
+            pad_body(&mut body);
+            buffers.push((start as i64, buffer.len() as i64));
+        }
+    }
+    let batch = FbTable::default()
+        .with(0, FbField::I64(num_rows as i64))
+        .with(1, FbField::Structs(nodes))
+        .with(2, FbField::Structs(buffers));
+    write_message(&mut out, HEADER_RECORD_BATCH, batch, &body);
+
+    // End-of-stream marker
+    out.extend_from_slice(&CONTINUATION.to_le_bytes());
+    out.extend_from_slice(&0i32.to_le_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        engine_chunked_append_f64, engine_chunked_create_f64, engine_create_series_bool, engine_create_series_f64, engine_create_series_i32,
+        engine_create_series_str_packed, engine_create_series_u8,
+    };
+    use crate::decimal::engine_create_series_decimal;
+    use crate::intern::engine_intern_str;
+
+    fn u32_at(b: &[u8], at: usize) -> usize {
+        u32::from_le_bytes(b[at..at + 4].try_into().unwrap()) as usize
+    }
+
+    /// Minimal flatbuffer table reader following the flatbuffers binary format
+    struct Table<'a> {
+        buf: &'a [u8],
+        pos: usize,
+        vtable: usize,
+    }
+
+    impl<'a> Table<'a> {
+        fn at(buf: &'a [u8], pos: usize) -> Self {
+            let soffset = i32::from_le_bytes(buf[pos..pos + 4].try_into().unwrap());
+            Table { buf, pos, vtable: (pos as i64 - soffset as i64) as usize }
+        }
+
+        fn root(buf: &'a [u8]) -> Self {
+            Table::at(buf, u32_at(buf, 0))
+        }
+
+        /// Absolute position of a field, None if absent
+        fn field(&self, slot: usize) -> Option<usize> {
+            let vtable_len = u16::from_le_bytes(self.buf[self.vtable..self.vtable + 2].try_into().unwrap()) as usize;
+            let entry = 4 + 2 * slot;
+            if entry >= vtable_len {
+                return None;
+            }
+            let off = u16::from_le_bytes(self.buf[self.vtable + entry..self.vtable + entry + 2].try_into().unwrap()) as usize;
+            (off != 0).then_some(self.pos + off)
+        }
+
+        fn bytes<const N: usize>(&self, slot: usize) -> [u8; N] {
+            self.field(slot).map_or([0; N], |at| self.buf[at..at + N].try_into().unwrap())
+        }
+
+        fn deref(&self, slot: usize) -> usize {
+            let at = self.field(slot).unwrap();
+            at + u32_at(self.buf, at)
+        }
+
+        fn table(&self, slot: usize) -> Table<'a> {
+            Table::at(self.buf, self.deref(slot))
+        }
+
+        fn string(&self, slot: usize) -> &'a str {
+            let at = self.deref(slot);
+            std::str::from_utf8(&self.buf[at + 4..at + 4 + u32_at(self.buf, at)]).unwrap()
+        }
+
+        fn tables(&self, slot: usize) -> Vec<Table<'a>> {
+            let at = self.deref(slot);
+            (0..u32_at(self.buf, at)).map(|i| at + 4 + 4 * i).map(|p| Table::at(self.buf, p + u32_at(self.buf, p))).collect()
+        }
+
+        fn structs(&self, slot: usize) -> Vec<(i64, i64)> {
+            let at = self.deref(slot);
+            assert_eq!((at + 4) % 8, 0, "struct vector data must be 8-aligned");
+            (0..u32_at(self.buf, at))
+                .map(|i| at + 4 + 16 * i)
+                .map(|p| (i64::from_le_bytes(self.buf[p..p + 8].try_into().unwrap()), i64::from_le_bytes(self.buf[p + 8..p + 16].try_into().unwrap())))
+                .collect()
+        }
+    }
+
+    /// Split an IPC stream into (message metadata, body) pairs, checking the
+    /// framing and the end-of-stream marker
+    fn messages(stream: &[u8]) -> Vec<(&[u8], &[u8])> {
+        let mut out = Vec::new();
+        let mut pos = 0;
+        loop {
+            assert_eq!(u32_at(stream, pos) as u32, CONTINUATION);
+            let len = u32_at(stream, pos + 4);
+            pos += 8;
+            if len == 0 {
+                assert_eq!(pos, stream.len());
+                return out;
+            }
+            assert_eq!(len % 8, 0, "metadata must be padded to 8 bytes");
+            let metadata = &stream[pos..pos + len];
+            let body_len = i64::from_le_bytes(Table::root(metadata).bytes(3)) as usize;
+            pos += len;
+            out.push((metadata, &stream[pos..pos + body_len]));
+            pos += body_len;
+        }
+    }
+
+    #[test]
+    fn exports_schema_and_record_batch() {
+        let values = engine_create_series_f64(&[1.5, f64::NAN, 3.0]);
+        let counts = engine_create_series_i32(&[7, 8, 9]);
+        let stream = engine_export_arrow(&[values, counts], r#"["values"]"#);
+        let msgs = messages(&stream);
+        assert_eq!(msgs.len(), 2);
+
+        let schema_msg = Table::root(msgs[0].0);
+        assert_eq!(i16::from_le_bytes(schema_msg.bytes(0)), METADATA_V5);
+        assert_eq!(schema_msg.bytes::<1>(1)[0], HEADER_SCHEMA);
+        let fields = schema_msg.table(2).tables(1);
+        assert_eq!(fields.iter().map(|f| f.string(0)).collect::<Vec<_>>(), vec!["values", "column_1"]);
+        assert_eq!(fields[0].bytes::<1>(2)[0], TYPE_FLOATING_POINT);
+        assert_eq!(i16::from_le_bytes(fields[0].table(3).bytes(0)), PRECISION_DOUBLE);
+        assert_eq!(fields[1].bytes::<1>(2)[0], TYPE_INT);
+        assert_eq!(i32::from_le_bytes(fields[1].table(3).bytes(0)), 32);
+        assert_eq!(fields[1].table(3).bytes::<1>(1)[0], 1);
+
+        let (metadata, body) = msgs[1];
+        let batch_msg = Table::root(metadata);
+        assert_eq!(batch_msg.bytes::<1>(1)[0], HEADER_RECORD_BATCH);
+        let batch = batch_msg.table(2);
+        assert_eq!(i64::from_le_bytes(batch.bytes(0)), 3);
+        assert_eq!(batch.structs(1), vec![(3, 1), (3, 0)]);
+        let buffers = batch.structs(2);
+        assert_eq!(buffers.len(), 4);
+        assert!(buffers.iter().all(|&(offset, _)| offset % 8 == 0));
+        let slice = |(offset, len): (i64, i64)| &body[offset as usize..(offset + len) as usize];
+        assert_eq!(slice(buffers[0]), &[0b101]);
+        let floats: Vec<f64> = slice(buffers[1]).chunks(8).map(|b| f64::from_le_bytes(b.try_into().unwrap())).collect();
+        assert_eq!([floats[0], floats[2]], [1.5, 3.0]);
+        assert_eq!(buffers[2].1, 0);
+        let ints: Vec<i32> = slice(buffers[3]).chunks(4).map(|b| i32::from_le_bytes(b.try_into().unwrap())).collect();
+        assert_eq!(ints, vec![7, 8, 9]);
+    }
+
+    #[test]
+    fn rejects_unknown_or_misaligned_series() {
+        let a = engine_create_series_f64(&[1.0, 2.0]);
+        let b = engine_create_series_f64(&[1.0]);
+        assert!(engine_export_arrow(&[a, b], "[]").is_empty());
+        assert!(engine_export_arrow(&[a, u32::MAX], "[]").is_empty());
+    }
+
+    #[test]
+    fn exports_every_dtype() {
+        let chunked = engine_chunked_create_f64();
+        assert!(engine_chunked_append_f64(chunked, &[1.0, 2.0]));
+        assert!(engine_chunked_append_f64(chunked, &[3.0]));
+        let decimal = engine_create_series_decimal(&[125, i64::MIN, -5], 2);
+        let flags = engine_create_series_bool(&[1, 0, 1]);
+        let bytes = engine_create_series_u8(&[7, 8, 9]);
+        let text = engine_create_series_str_packed(b"abcd", &[0, 2, 2, 4], &[0, 1, 0]);
+        let interned = engine_intern_str(text);
+        let stream = engine_export_arrow(&[chunked, decimal, flags, bytes, text, interned], "[]");
+        let msgs = messages(&stream);
+        let fields = Table::root(msgs[0].0).table(2).tables(1);
+        let types: Vec<u8> = fields.iter().map(|f| f.bytes::<1>(2)[0]).collect();
+        assert_eq!(types, [TYPE_FLOATING_POINT, TYPE_DECIMAL, TYPE_BOOL, TYPE_INT, TYPE_UTF8, TYPE_UTF8]);
+        assert_eq!(i32::from_le_bytes(fields[1].table(3).bytes(1)), 2);
+        assert_eq!(i32::from_le_bytes(fields[3].table(3).bytes(0)), 8);
+        assert_eq!(fields[3].table(3).bytes::<1>(1)[0], 0);
+
+        let (metadata, body) = msgs[1];
+        let batch = Table::root(metadata).table(2);
+        assert_eq!(batch.structs(1), vec![(3, 0), (3, 1), (3, 0), (3, 0), (3, 1), (3, 1)]);
+        let buffers = batch.structs(2);
+        assert_eq!(buffers.len(), 14);
+        let slice = |(offset, len): (i64, i64)| &body[offset as usize..(offset + len) as usize];
+        let floats: Vec<f64> = slice(buffers[1]).chunks(8).map(|b| f64::from_le_bytes(b.try_into().unwrap())).collect();
+        assert_eq!(floats, [1.0, 2.0, 3.0]);
+        let decimals: Vec<i128> = slice(buffers[3]).chunks(16).map(|b| i128::from_le_bytes(b.try_into().unwrap())).collect();
+        assert_eq!([decimals[0], decimals[2]], [125, -5]);
+        assert_eq!(slice(buffers[5]), &[0b101]);
+        assert_eq!(slice(buffers[7]), &[7, 8, 9]);
+        for strings in [8, 11] {
+            assert_eq!(slice(buffers[strings]), &[0b101]);
+            let offsets: Vec<i32> = slice(buffers[strings + 1]).chunks(4).map(|b| i32::from_le_bytes(b.try_into().unwrap())).collect();
+            assert_eq!(offsets, [0, 2, 2, 4]);
+            assert_eq!(slice(buffers[strings + 2]), b"abcd");
+        }
+    }
+}
@@ -0,0 +1,376 @@
+//! Batched command protocol: many engine operations in one boundary crossing
+//!
+//! A batch is a compact little-endian byte stream: one version byte followed
+//! by commands, each an opcode byte plus operands. Series operands are u32
+//! values; with the high bit set (`REF_BIT`) they refer to the result of an
+//! earlier command in the same batch, so a whole pipeline can be expressed
+//! without knowing the ids it will produce.
+//!
+//! Opcode table (version 1):
+//!
+//! | opcode | command          | operands                                   | result  |
+//! |--------|------------------|--------------------------------------------|---------|
+//! | 0x01   | create f64       | u32 len, len x f64                         | series  |
+//! | 0x02   | create i32       | u32 len, len x i32                         | series  |
+//! | 0x03   | free             | series                                     | none    |
+//! | 0x10   | filter f64       | series, u32 len, len x u8 mask             | series  |
+//! | 0x20   | sort values f64  | series, u8 ascending, u8 nulls_last        | series  |
+//! | 0x30   | groupby f64      | series, u8 agg, u32 len, len x u8 json keys| series  |
+//! | 0x40   | aggregate f64    | series, u8 agg                             | scalar  |
+//!
+//! `agg` codes: 0=sum, 1=mean, 2=count, 3=min, 4=max, 5=std, 6=var.
+//!
+//! The response is a version byte, a u32 command count, then one 10-byte
+//! entry per executed command: u8 status (0=ok, 1=error), u8 result kind
+//! (0=none, 1=series id, 2=scalar) and an 8-byte payload (u32 id widened to
+//! u64, or f64 bits). Parsing stops at the first malformed command.
+
+use wasm_bindgen::prelude::*;
+use crate::core::{engine_create_series_f64, engine_create_series_i32, engine_free_series, engine_free_series_i32, ENGINE};
+use crate::error::catch_panic;
+use crate::filtering::engine_filter_f64;
+use crate::groupby::*;
+use crate::profiling::profile;
+use crate::series::*;
+use crate::sorting::engine_sort_values_f64;
+
+pub const BATCH_PROTOCOL_VERSION: u8 = 1;
+pub const REF_BIT: u32 = 0x8000_0000;
+
+pub const OP_CREATE_F64: u8 = 0x01;
+pub const OP_CREATE_I32: u8 = 0x02;
+pub const OP_FREE: u8 = 0x03;
+pub const OP_FILTER_F64: u8 = 0x10;
+pub const OP_SORT_VALUES_F64: u8 = 0x20;
+pub const OP_GROUPBY_F64: u8 = 0x30;
+pub const OP_AGG_F64: u8 = 0x40;
+
+pub const STATUS_OK: u8 = 0;
+pub const STATUS_ERROR: u8 = 1;
+pub const RESULT_NONE: u8 = 0;
+pub const RESULT_SERIES: u8 = 1;
+pub const RESULT_SCALAR: u8 = 2;
+
+/// Outcome of one command
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum CommandResult {
+    Error,
+    None,
+    Series(u32),
+    Scalar(f64),
+}
+
+struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let end = self.pos.checked_add(n)?;
+        let out = self.data.get(self.pos..end)?;
+        self.pos = end;
+        Some(out)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4).map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+    }
+}
+
+/// Resolve a series operand, following references to earlier results
+fn resolve(operand: u32, results: &[CommandResult]) -> Option<u32> {
+    if operand & REF_BIT == 0 {
+        return Some(operand);
+    }
+    match results.get((operand & !REF_BIT) as usize) {
+        Some(CommandResult::Series(id)) => Some(*id),
+        _ => None,
+    }
+}
+
+fn series_exists(series_id: u32) -> bool {
+    ENGINE.with(|cell| cell.borrow().series_store.contains_key(&series_id))
+}
+
+fn series_result(id: u32) -> CommandResult {
+    if id == u32::MAX { CommandResult::Error } else { CommandResult::Series(id) }
+}
+
+/// Parse and execute one command; None means the stream is malformed
+fn execute_command(cur: &mut Cursor, results: &[CommandResult]) -> Option<CommandResult> {
+    let opcode = cur.u8()?;
+    let result = match opcode {
+        OP_CREATE_F64 => {
+            let len = cur.u32()? as usize;
+            let bytes = cur.take(len.checked_mul(8)?)?;
+            let data: Vec<f64> = bytes.chunks_exact(8).map(|b| f64::from_le_bytes(b.try_into().unwrap())).collect();
+            series_result(engine_create_series_f64(&data))
+        }
+        OP_CREATE_I32 => {
+            let len = cur.u32()? as usize;
+            let bytes = cur.take(len.checked_mul(4)?)?;
+            let data: Vec<i32> = bytes.chunks_exact(4).map(|b| i32::from_le_bytes(b.try_into().unwrap())).collect();
+            series_result(engine_create_series_i32(&data))
+        }
+        OP_FREE => match resolve(cur.u32()?, results) {
+            Some(id) => {
+                engine_free_series(id);
+                engine_free_series_i32(id);
+                CommandResult::None
+            }
+            None => CommandResult::Error,
+        },
+        OP_FILTER_F64 => {
+            let operand = cur.u32()?;
+            let len = cur.u32()? as usize;
+            let mask = cur.take(len)?;
+            match resolve(operand, results) {
+                Some(id) => series_result(engine_filter_f64(id, mask)),
+                None => CommandResult::Error,
+            }
+        }
+        OP_SORT_VALUES_F64 => {
+            let operand = cur.u32()?;
+            let ascending = cur.u8()?;
+            let nulls_last = cur.u8()?;
+            match resolve(operand, results) {
+                Some(id) => series_result(engine_sort_values_f64(id, ascending, nulls_last)),
+                None => CommandResult::Error,
+            }
+        }
+        OP_GROUPBY_F64 => {
+            let operand = cur.u32()?;
+            let agg = cur.u8()?;
+            let len = cur.u32()? as usize;
+            let keys = std::str::from_utf8(cur.take(len)?).ok()?;
+            match resolve(operand, results) {
+                Some(id) => series_result(match agg {
+                    0 => engine_groupby_sum_f64(id, keys),
+                    1 => engine_groupby_mean_f64(id, keys),
+                    2 => engine_groupby_count_f64(id, keys),
+                    3 => engine_groupby_min_f64(id, keys),
+                    4 => engine_groupby_max_f64(id, keys),
+                    5 => engine_groupby_std_f64(id, keys),
+                    6 => engine_groupby_var_f64(id, keys),
+                    _ => u32::MAX,
+                }),
+                None => CommandResult::Error,
+            }
+        }
+        OP_AGG_F64 => {
+            let operand = cur.u32()?;
+            let agg = cur.u8()?;
+            match resolve(operand, results).filter(|id| series_exists(*id)) {
+                Some(id) => match agg {
+                    0 => CommandResult::Scalar(engine_series_sum_f64(id)),
+                    1 => CommandResult::Scalar(engine_series_mean_f64(id)),
+                    2 => CommandResult::Scalar(engine_series_count_f64(id) as f64),
+                    3 => CommandResult::Scalar(engine_series_min_f64(id)),
+                    4 => CommandResult::Scalar(engine_series_max_f64(id)),
+                    5 => CommandResult::Scalar(engine_series_std_f64(id)),
+                    6 => {
+                        let std = engine_series_std_f64(id);
+                        CommandResult::Scalar(std * std)
+                    }
+                    _ => CommandResult::Error,
+                },
+                None => CommandResult::Error,
+            }
+        }
+        _ => return None,
+    };
+    Some(result)
+}
+
+/// Execute a batch of commands (see module docs for the wire format) and
+/// return the encoded results. An unsupported version yields an empty buffer.
+#[wasm_bindgen]
+pub fn engine_execute_batch(commands: &[u8]) -> Vec<u8> {
+    let _prof = profile("engine_execute_batch", || commands.len());
+    catch_panic(Vec::new(), || execute_batch(commands))
+}
+
+fn execute_batch(commands: &[u8]) -> Vec<u8> {
+    let mut cur = Cursor { data: commands, pos: 0 };
+    if cur.u8() != Some(BATCH_PROTOCOL_VERSION) {
+        engine_log!(warn, "engine_execute_batch: unsupported protocol version={:?}", commands.first());
+        return Vec::new();
+    }
+    let mut results: Vec<CommandResult> = Vec::new();
+    while cur.pos < commands.len() {
+        let start = cur.pos;
+        match execute_command(&mut cur, &results) {
+            Some(r) => results.push(r),
+            None => {
+                engine_log!(warn, "engine_execute_batch: malformed command index={} offset={}", results.len(), start);
+                break;
+            }
+        }
+    }
+    engine_log!(debug, "engine_execute_batch: commands={} errors={}", results.len(), results.iter().filter(|r| **r == CommandResult::Error).count());
+
+    let mut out: Vec<u8> = Vec::with_capacity(5 + results.len() * 10);
+    out.push(BATCH_PROTOCOL_VERSION);
+    out.extend_from_slice(&(results.len() as u32).to_le_bytes());
+    for r in results {
+        let (status, kind, payload) = match r {
+            CommandResult::Error => (STATUS_ERROR, RESULT_NONE, 0u64),
+            CommandResult::None => (STATUS_OK, RESULT_NONE, 0u64),
+            CommandResult::Series(id) => (STATUS_OK, RESULT_SERIES, id as u64),
+            CommandResult::Scalar(v) => (STATUS_OK, RESULT_SCALAR, v.to_bits()),
+        };
+        out.push(status);
+        out.push(kind);
+        out.extend_from_slice(&payload.to_le_bytes());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::engine_set_memory_limit;
+
+    /// Test-side encoder mirroring what the TS layer emits
+    struct BatchBuilder {
+        buf: Vec<u8>,
+    }
+
+    impl BatchBuilder {
+        fn new() -> Self {
+            BatchBuilder { buf: vec![BATCH_PROTOCOL_VERSION] }
+        }
+
+        fn create_f64(mut self, data: &[f64]) -> Self {
+            self.buf.push(OP_CREATE_F64);
+            self.buf.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            data.iter().for_each(|v| self.buf.extend_from_slice(&v.to_le_bytes()));
+            self
+        }
+
+        fn filter(mut self, series: u32, mask: &[u8]) -> Self {
+            self.buf.push(OP_FILTER_F64);
+            self.buf.extend_from_slice(&series.to_le_bytes());
+            self.buf.extend_from_slice(&(mask.len() as u32).to_le_bytes());
+            self.buf.extend_from_slice(mask);
+            self
+        }
+
+        fn sort(mut self, series: u32, ascending: u8) -> Self {
+            self.buf.push(OP_SORT_VALUES_F64);
+            self.buf.extend_from_slice(&series.to_le_bytes());
+            self.buf.extend_from_slice(&[ascending, 1]);
+            self
+        }
+
+        fn groupby(mut self, series: u32, agg: u8, keys_json: &str) -> Self {
+            self.buf.push(OP_GROUPBY_F64);
+            self.buf.extend_from_slice(&series.to_le_bytes());
+            self.buf.push(agg);
+            self.buf.extend_from_slice(&(keys_json.len() as u32).to_le_bytes());
+            self.buf.extend_from_slice(keys_json.as_bytes());
+            self
+        }
+
+        fn agg(mut self, series: u32, agg: u8) -> Self {
+            self.buf.push(OP_AGG_F64);
+            self.buf.extend_from_slice(&series.to_le_bytes());
+            self.buf.push(agg);
+            self
+        }
+
+        fn free(mut self, series: u32) -> Self {
+            self.buf.push(OP_FREE);
+            self.buf.extend_from_slice(&series.to_le_bytes());
+            self
+        }
+    }
+
+    fn decode(out: &[u8]) -> Vec<CommandResult> {
+        assert_eq!(out[0], BATCH_PROTOCOL_VERSION);
+        let count = u32::from_le_bytes(out[1..5].try_into().unwrap()) as usize;
+        assert_eq!(out.len(), 5 + count * 10);
+        (0..count)
+            .map(|i| {
+                let e = &out[5 + i * 10..5 + (i + 1) * 10];
+                let payload = u64::from_le_bytes(e[2..10].try_into().unwrap());
+                match (e[0], e[1]) {
+                    (STATUS_ERROR, _) => CommandResult::Error,
+                    (_, RESULT_SERIES) => CommandResult::Series(payload as u32),
+                    (_, RESULT_SCALAR) => CommandResult::Scalar(f64::from_bits(payload)),
+                    _ => CommandResult::None,
+                }
+            })
+            .collect()
+    }
+
+    fn series_of(r: CommandResult) -> u32 {
+        match r {
+            CommandResult::Series(id) => id,
+            other => panic!("expected series, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn pipeline_round_trip_matches_direct_calls() {
+        let batch = BatchBuilder::new()
+            .create_f64(&[5.0, 1.0, f64::NAN, 3.0])
+            .filter(REF_BIT, &[1, 1, 0, 1])
+            .sort(REF_BIT | 1, 1)
+            .agg(REF_BIT | 2, 0)
+            .agg(REF_BIT | 2, 4);
+        let results = decode(&engine_execute_batch(&batch.buf));
+        assert_eq!(results.len(), 5);
+
+        let sorted = series_of(results[2]);
+        assert_eq!(engine_series_to_vec_f64(sorted), vec![1.0, 3.0, 5.0]);
+        assert_eq!(results[3], CommandResult::Scalar(9.0));
+        assert_eq!(results[4], CommandResult::Scalar(5.0));
+    }
+
+    #[test]
+    fn groupby_and_free_round_trip() {
+        let batch = BatchBuilder::new()
+            .create_f64(&[1.0, 2.0, 3.0])
+            .groupby(REF_BIT, 0, r#"["b","a","b"]"#)
+            .free(REF_BIT);
+        let results = decode(&engine_execute_batch(&batch.buf));
+        assert_eq!(engine_series_to_vec_f64(series_of(results[1])), vec![2.0, 4.0]);
+        assert_eq!(results[2], CommandResult::None);
+        assert_eq!(engine_series_len_f64(series_of(results[0])), 0);
+    }
+
+    #[test]
+    fn errors_are_reported_per_command() {
+        let batch = BatchBuilder::new()
+            .filter(REF_BIT | 7, &[1])
+            .agg(u32::MAX - 1, 0)
+            .create_f64(&[1.0]);
+        let results = decode(&engine_execute_batch(&batch.buf));
+        assert_eq!(results[0], CommandResult::Error);
+        assert_eq!(results[1], CommandResult::Error);
+        assert!(matches!(results[2], CommandResult::Series(_)));
+    }
+
+    #[test]
+    fn malformed_stream_stops_parsing_and_bad_version_is_rejected() {
+        let mut batch = BatchBuilder::new().create_f64(&[1.0]).buf;
+        batch.extend_from_slice(&[OP_CREATE_F64, 10, 0, 0, 0, 1, 2]);
+        assert_eq!(decode(&engine_execute_batch(&batch)).len(), 1);
+        assert!(engine_execute_batch(&[99, OP_FREE, 0, 0, 0, 0]).is_empty());
+    }
+
+    #[test]
+    fn failed_create_is_an_error() {
+        engine_set_memory_limit(64);
+        let batch = BatchBuilder::new().create_f64(&[1.0; 16]).agg(REF_BIT, 0);
+        let results = decode(&engine_execute_batch(&batch.buf));
+        engine_set_memory_limit(0);
+        assert_eq!(results, vec![CommandResult::Error, CommandResult::Error]);
+    }
+}
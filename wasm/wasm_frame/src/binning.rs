@@ -0,0 +1,143 @@
+//! Binning and bucket assignment
+//!
+//! Assigns values to buckets between bin edges: the primitive behind
+//! `cut` and histograms. Bucket indices are returned as uint32 series with
+//! u32::MAX for null, so they can be used directly as group codes (see
+//! `groupby::key_codes`).
+
+use wasm_bindgen::prelude::*;
+use crate::core::{f64_values, ENGINE};
+use crate::profiling::{profile, series_bytes};
+use crate::statistics::{RunningStats, AGG_COUNT};
+
+/// Bucket of `v` among ascending `edges`, as numpy's `digitize`: the number
+/// of edges at or below `v` (below `v` when `right`), from 0 (before the
+/// first edge) to `edges.len()` (past the last); u32::MAX for NaN
+pub(crate) fn digitize(v: f64, edges: &[f64], right: bool) -> u32 {
+    if v.is_nan() {
+        return u32::MAX;
+    }
+    let bucket = if right { edges.partition_point(|&e| e < v) } else { edges.partition_point(|&e| e <= v) };
+    bucket as u32
+}
+
+/// Whether bin edges are ascending and free of NaN
+fn valid_edges(edges: &[f64]) -> bool {
+    edges.windows(2).all(|w| w[0] <= w[1]) && !edges.iter().any(|e| e.is_nan())
+}
+
+/// Bin of `v` among ascending `edges` as numpy's `histogram`: bins are
+/// half-open `[edges[i], edges[i + 1])` except the last, which includes its
+/// right edge. None for NaN and values outside the edges (at least two).
+fn histogram_bin(v: f64, edges: &[f64]) -> Option<usize> {
+    let bins = edges.len() - 1;
+    match digitize(v, edges, false) as usize {
+        k if (1..=bins).contains(&k) => Some(k - 1),
+        _ if v == edges[bins] => Some(bins - 1),
+        _ => None,
+    }
+}
+
+/// Bucket index of each value of a float64 series among ascending `edges`
+/// by binary search, as numpy's `digitize`: `i` means `edges[i - 1] <= v <
+/// edges[i]` (with `right` = 1: `edges[i - 1] < v <= edges[i]`), 0 is below
+/// the first edge and `edges.len()` past the last. Nulls get u32::MAX.
+///
+/// Returns a uint32 series id, or u32::MAX if the id is unknown or the
+/// edges are not ascending (or contain NaN).
+#[wasm_bindgen]
+pub fn engine_digitize_f64(series_id: u32, edges: &[f64], right: u8) -> u32 {
+    let _prof = profile("engine_digitize_f64", || series_bytes(series_id));
+    if !valid_edges(edges) {
+        engine_log!(warn, "engine_digitize_f64: edges must be ascending and non-null");
+        return u32::MAX;
+    }
+    let values = match unsafe { f64_values(series_id) } {
+        Some(values) => values,
+        None => return u32::MAX,
+    };
+    let buckets: Vec<u32> = values.iter().map(|v| digitize(v, edges, right != 0)).collect();
+    ENGINE.with(|cell| cell.borrow_mut().register_series_u32(&buckets))
+}
+
+/// Aggregate over a 2-D grid of bins, e.g. for density heatmaps: each row
+/// falls in the bin of its `x` value among `x_edges` and its `y` value
+/// among `y_edges` (ascending; bins as numpy's `histogram2d`, half-open
+/// except the last, which includes its right edge). Rows with a null or
+/// out-of-range coordinate are skipped.
+///
+/// With `value_id` = u32::MAX the grid holds the number of rows per bin
+/// (`agg_kind` is ignored); otherwise it aggregates that float64 series
+/// with `agg_kind` (0=sum, 1=mean, 2=count, 3=min, 4=max, 5=std, 6=var),
+/// skipping null values, so empty bins are NaN except for sum and count.
+///
+/// Returns `(y_edges.len() - 1) * (x_edges.len() - 1)` values, row-major
+/// with one row per y bin; empty if a series is unknown, the lengths
+/// differ or either edge list is invalid or has fewer than two edges.
+#[wasm_bindgen]
+pub fn engine_bin2d_f64(x_id: u32, y_id: u32, x_edges: &[f64], y_edges: &[f64], value_id: u32, agg_kind: u8) -> Box<[f64]> {
+    let _prof = profile("engine_bin2d_f64", || series_bytes(x_id) + series_bytes(y_id));
+    if x_edges.len() < 2 || y_edges.len() < 2 || !valid_edges(x_edges) || !valid_edges(y_edges) {
+        engine_log!(warn, "engine_bin2d_f64: edges must be at least two ascending, non-null values");
+        return Box::new([]);
+    }
+    let (x, y) = match (unsafe { f64_values(x_id) }, unsafe { f64_values(y_id) }) {
+        (Some(x), Some(y)) if x.len() == y.len() => (x, y),
+        _ => return Box::new([]),
+    };
+    let values = match value_id {
+        u32::MAX => None,
+        id => match unsafe { f64_values(id) } {
+            Some(values) if values.len() == x.len() => Some(values),
+            _ => {
+                engine_log!(warn, "engine_bin2d_f64: unknown value series or length mismatch value_id={}", value_id);
+                return Box::new([]);
+            }
+        },
+    };
+    let nx = x_edges.len() - 1;
+    let mut cells = vec![RunningStats::default(); nx * (y_edges.len() - 1)];
+    for (row, (xv, yv)) in x.iter().zip(y.iter()).enumerate() {
+        if let (Some(i), Some(j)) = (histogram_bin(xv, x_edges), histogram_bin(yv, y_edges)) {
+            // Without a value series each row is counted as a 0
+            cells[j * nx + i].push(values.as_ref().map_or(0.0, |values| values.get(row)));
+        }
+    }
+    let agg = if values.is_none() { AGG_COUNT } else { agg_kind };
+    cells.iter().map(|stats| stats.finish(agg)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::engine_create_series_f64;
+    use crate::series::engine_series_to_vec_u32;
+    use crate::statistics::{AGG_MEAN, AGG_SUM};
+
+    #[test]
+    fn digitize_counts_edges_on_either_side() {
+        let id = engine_create_series_f64(&[-1.0, 0.0, 5.0, 10.0, 25.0, f64::NAN]);
+        let edges = [0.0, 10.0, 20.0];
+        assert_eq!(engine_series_to_vec_u32(engine_digitize_f64(id, &edges, 0)), [0, 1, 1, 2, 3, u32::MAX]);
+        assert_eq!(engine_series_to_vec_u32(engine_digitize_f64(id, &edges, 1)), [0, 0, 1, 1, 3, u32::MAX]);
+        assert_eq!(engine_series_to_vec_u32(engine_digitize_f64(id, &[], 0)), [0, 0, 0, 0, 0, u32::MAX]);
+        assert_eq!(engine_digitize_f64(id, &[1.0, 0.0], 0), u32::MAX);
+        assert_eq!(engine_digitize_f64(id, &[0.0, f64::NAN], 0), u32::MAX);
+    }
+
+    #[test]
+    fn bin2d_aggregates_a_grid_row_major_by_y() {
+        let x = engine_create_series_f64(&[0.5, 1.5, 1.5, 2.0, f64::NAN, 3.0]);
+        let y = engine_create_series_f64(&[0.5, 0.5, 1.5, 2.0, 0.5, 0.5]);
+        let values = engine_create_series_f64(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let edges = [0.0, 1.0, 2.0];
+        // The last bin includes its right edge; the null and out-of-range rows are skipped
+        assert_eq!(*engine_bin2d_f64(x, y, &edges, &edges, u32::MAX, AGG_SUM), [1.0, 1.0, 0.0, 2.0]);
+        assert_eq!(*engine_bin2d_f64(x, y, &edges, &edges, values, AGG_SUM), [1.0, 2.0, 0.0, 7.0]);
+        let means = engine_bin2d_f64(x, y, &edges, &edges, values, AGG_MEAN);
+        assert_eq!((means[0], means[1], means[3]), (1.0, 2.0, 3.5));
+        assert!(means[2].is_nan());
+        assert!(engine_bin2d_f64(x, y, &[0.0], &edges, u32::MAX, AGG_SUM).is_empty());
+        assert!(engine_bin2d_f64(x, y, &edges, &edges, engine_create_series_f64(&[1.0]), AGG_SUM).is_empty());
+    }
+}
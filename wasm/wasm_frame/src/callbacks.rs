@@ -0,0 +1,160 @@
+//! JS callback bridge for user-defined kernels
+//!
+//! Functions taking a callback receive a plain JS `Function` and call it
+//! with a `Float64Array` view that aliases WASM memory, so values are not
+//! copied into JS. A view is only valid for the duration of the call it was
+//! passed to: it must not be kept, and the callback must not rely on it
+//! after calling back into the engine (which may grow memory). Callbacks
+//! need the JS host; native builds report error code 6 instead.
+
+use std::marker::PhantomData;
+use wasm_bindgen::prelude::*;
+use crate::error::EngineError;
+
+#[wasm_bindgen]
+extern "C" {
+    /// JS function passed to callback-taking engine functions
+    #[wasm_bindgen(typescript_type = "Function")]
+    pub type JsFunction;
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(method, catch, js_name = call)]
+    fn call2(this: &JsFunction, context: &JsValue, a: &JsValue, b: &JsValue) -> Result<JsValue, JsValue>;
+
+    type WasmMemory;
+    #[wasm_bindgen(method, getter)]
+    fn buffer(this: &WasmMemory) -> JsValue;
+
+    type Float64Array;
+    #[wasm_bindgen(constructor)]
+    fn new(buffer: &JsValue, byte_offset: u32, length: u32) -> Float64Array;
+    #[wasm_bindgen(method, getter)]
+    fn length(this: &Float64Array) -> u32;
+    #[wasm_bindgen(method)]
+    fn subarray(this: &Float64Array, begin: u32, end: u32) -> Float64Array;
+}
+
+/// Float64Array view over `len` values at `ptr`
+#[cfg(target_arch = "wasm32")]
+fn view_at(ptr: *const f64, len: usize) -> Float64Array {
+    let memory: WasmMemory = wasm_bindgen::memory().unchecked_into();
+    Float64Array::new(&memory.buffer(), ptr as u32, len as u32)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn call_view(cb: &JsFunction, view: &Float64Array, arg: f64) -> Result<JsValue, EngineError> {
+    cb.call2(&JsValue::NULL, view, &JsValue::from_f64(arg))
+        .map_err(|err| EngineError::Callback(err.as_string().unwrap_or_else(|| format!("{:?}", err))))
+}
+
+/// Call `cb(view, arg)` with a Float64Array view over `len` values at `ptr`
+#[cfg(target_arch = "wasm32")]
+fn call_raw(cb: &JsFunction, ptr: *const f64, len: usize, arg: f64) -> Result<JsValue, EngineError> {
+    call_view(cb, &view_at(ptr, len), arg)
+}
+
+/// Calls a callback with windows of one contiguous buffer, for kernels that
+/// call it once per row or batch: a single Float64Array spans the buffer
+/// and each call gets a `subarray` of it. The spanning view is rebuilt only
+/// when a memory growth during an earlier call has detached it.
+pub(crate) struct WindowCaller<'a> {
+    ptr: *mut f64,
+    len: usize,
+    values: PhantomData<&'a mut [f64]>,
+    #[cfg(target_arch = "wasm32")]
+    view: Option<Float64Array>,
+}
+
+impl<'a> WindowCaller<'a> {
+    pub(crate) fn new(values: &'a [f64]) -> Self {
+        Self::over(values.as_ptr() as *mut f64, values.len())
+    }
+
+    /// For a buffer the callback may update in place through its views
+    pub(crate) fn new_mut(values: &'a mut [f64]) -> Self {
+        Self::over(values.as_mut_ptr(), values.len())
+    }
+
+    fn over(ptr: *mut f64, len: usize) -> Self {
+        WindowCaller {
+            ptr,
+            len,
+            values: PhantomData,
+            #[cfg(target_arch = "wasm32")]
+            view: None,
+        }
+    }
+
+    /// Call `cb(view, arg)` with a view over `values[start..end]`; results
+    /// that are not numbers become NaN
+    pub(crate) fn call(&mut self, cb: &JsFunction, start: usize, end: usize, arg: f64) -> Result<f64, EngineError> {
+        self.call_window(cb, start, end, arg).map(|result| result.as_f64().unwrap_or(f64::NAN))
+    }
+
+    /// Call `cb(view, arg)` with a view over `values[start..end]` that the
+    /// callback may update in place (the caller was built with `new_mut`);
+    /// its result is ignored
+    pub(crate) fn call_mut(&mut self, cb: &JsFunction, start: usize, end: usize, arg: f64) -> Result<(), EngineError> {
+        self.call_window(cb, start, end, arg).map(|_| ())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn call_window(&mut self, cb: &JsFunction, start: usize, end: usize, arg: f64) -> Result<JsValue, EngineError> {
+        let (ptr, len) = (self.ptr, self.len);
+        // A detached view reports length 0
+        if self.view.as_ref().is_some_and(|view| view.length() as usize != len) {
+            self.view = None;
+        }
+        let view = self.view.get_or_insert_with(|| view_at(ptr, len));
+        call_view(cb, &view.subarray(start as u32, end as u32), arg)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn call_window(&mut self, _cb: &JsFunction, start: usize, end: usize, _arg: f64) -> Result<JsValue, EngineError> {
+        debug_assert!(!self.ptr.is_null() && start <= end && end <= self.len);
+        Err(EngineError::Callback("JS callbacks require the wasm32 target".to_string()))
+    }
+}
+
+/// Call `cb(view, arg)` with a Float64Array view over `values`; results
+/// that are not numbers become NaN
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn call_with_view(cb: &JsFunction, values: &[f64], arg: f64) -> Result<f64, EngineError> {
+    call_raw(cb, values.as_ptr(), values.len(), arg).map(|result| result.as_f64().unwrap_or(f64::NAN))
+}
+
+/// Call `cb(view, arg)` with a Float64Array view over `values`; results
+/// that are not numbers become NaN
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn call_with_view(_cb: &JsFunction, _values: &[f64], _arg: f64) -> Result<f64, EngineError> {
+    Err(EngineError::Callback("JS callbacks require the wasm32 target".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{engine_create_series_f64, engine_create_series_u32};
+    use crate::error::{engine_last_error_code, ERROR_CALLBACK};
+    use crate::groupby::engine_groupby_apply;
+    use crate::rolling::engine_rolling_apply_f64;
+    use crate::series::engine_series_map_f64;
+
+    #[test]
+    fn native_builds_report_callback_errors() {
+        let cb: JsFunction = JsValue::UNDEFINED.unchecked_into();
+        let values = [1.0, 2.0, 3.0];
+        assert!(matches!(call_with_view(&cb, &values, 0.0), Err(EngineError::Callback(_))));
+        assert!(WindowCaller::new(&values).call(&cb, 0, 2, 0.0).is_err());
+        let id = engine_create_series_f64(&values);
+        assert_eq!(engine_series_map_f64(id, &cb, 2), u32::MAX);
+        assert_eq!(engine_last_error_code(), ERROR_CALLBACK);
+        assert_eq!(engine_rolling_apply_f64(id, 2, &cb), u32::MAX);
+        assert_eq!(engine_last_error_code(), ERROR_CALLBACK);
+        let codes = engine_create_series_u32(&[0, 1, 0]);
+        assert!(engine_groupby_apply(id, codes, &cb).is_empty());
+        assert_eq!(engine_last_error_code(), ERROR_CALLBACK);
+    }
+}
@@ -0,0 +1,462 @@
+//! Type casting between series stores
+//!
+//! `engine_cast` converts a registered series to another physical dtype
+//! inside the engine. Nulls carry over (NaN, i32::MIN, i64::MIN, null
+//! strings); booleans and unsigned series have no null and receive
+//! `false` / 0. Decimal series are read in real units. Values that cannot
+//! be represented in the target (non-finite or out-of-range floats,
+//! unparsable strings) are handled by the null policy.
+
+use wasm_bindgen::prelude::*;
+use crate::core::{EngineState, F64Values, Interner, RleSeries, StrSeries, ENGINE};
+use crate::decimal::{format_decimal, pow10};
+use crate::error::{set_last_error, EngineError};
+use crate::profiling::{profile, series_bytes};
+
+// Target dtype codes
+pub const DTYPE_F64: u8 = 0;
+pub const DTYPE_I32: u8 = 1;
+pub const DTYPE_I64: u8 = 2;
+pub const DTYPE_BOOL: u8 = 3;
+pub const DTYPE_STR: u8 = 4;
+pub const DTYPE_U32: u8 = 5;
+pub const DTYPE_U8: u8 = 6;
+
+// Rounding modes for float to integer casts
+pub const ROUND_TRUNC: u8 = 0;
+pub const ROUND_NEAREST: u8 = 1;
+pub const ROUND_FLOOR: u8 = 2;
+pub const ROUND_CEIL: u8 = 3;
+
+// Policies for values the target dtype cannot represent
+pub const CAST_NULL: u8 = 0;
+pub const CAST_FAIL: u8 = 1;
+pub const CAST_ZERO: u8 = 2;
+pub const CAST_SATURATE: u8 = 3;
+
+/// One source value, independent of the source dtype
+#[derive(Clone, Copy)]
+pub(crate) enum Cell<'a> {
+    Null,
+    Float(f64),
+    Int(i64),
+    /// Unscaled fixed-point value and its scale
+    Decimal(i64, u8),
+    Bool(bool),
+    Text(&'a str),
+}
+
+impl Cell<'_> {
+    /// Numeric value of the cell (decimals in real units, bools as 0/1);
+    /// None for nulls and text
+    pub(crate) fn number(self) -> Option<f64> {
+        match self {
+            Cell::Float(v) => Some(v),
+            Cell::Int(v) => Some(v as f64),
+            Cell::Decimal(v, scale) => Some(v as f64 / pow10(scale) as f64),
+            Cell::Bool(b) => Some(b as u8 as f64),
+            Cell::Null | Cell::Text(_) => None,
+        }
+    }
+}
+
+/// Borrowed values of a series of any dtype
+pub(crate) enum Source<'a> {
+    F64(F64Values<'a>),
+    I32(&'a [i32]),
+    I64(&'a [i64]),
+    Decimal(&'a [i64], u8),
+    Bool(&'a [u8]),
+    U32(&'a [u32]),
+    U8(&'a [u8]),
+    Str(&'a StrSeries),
+    Rle(&'a RleSeries),
+    Interned(&'a [u32], &'a Interner),
+}
+
+impl<'a> Source<'a> {
+    pub(crate) fn new(eng: &'a EngineState, series_id: u32) -> Option<Source<'a>> {
+        if let Some(values) = eng.f64_values(series_id) {
+            Some(Source::F64(values))
+        } else if let Some(&(ptr, len)) = eng.series_store_i32.get(&series_id) {
+            Some(Source::I32(unsafe { std::slice::from_raw_parts(ptr, len) }))
+        } else if let Some(&(ptr, len)) = eng.series_store_i64.get(&series_id) {
+            let values = unsafe { std::slice::from_raw_parts(ptr, len) };
+            match eng.decimal_scales.get(&series_id) {
+                Some(&scale) => Some(Source::Decimal(values, scale)),
+                None => Some(Source::I64(values)),
+            }
+        } else if let Some(&(ptr, len)) = eng.series_store_bool.get(&series_id) {
+            Some(Source::Bool(unsafe { std::slice::from_raw_parts(ptr, len) }))
+        } else if let Some(&(ptr, len)) = eng.series_store_u32.get(&series_id) {
+            Some(Source::U32(unsafe { std::slice::from_raw_parts(ptr, len) }))
+        } else if let Some(&(ptr, len)) = eng.series_store_u8.get(&series_id) {
+            Some(Source::U8(unsafe { std::slice::from_raw_parts(ptr, len) }))
+        } else if let Some(strings) = eng.series_store_str.get(&series_id) {
+            Some(Source::Str(strings.as_ref()))
+        } else if let Some(rle) = eng.series_store_rle.get(&series_id) {
+            Some(Source::Rle(rle.as_ref()))
+        } else {
+            eng.series_store_interned.get(&series_id).map(|codes| Source::Interned(codes.as_slice(), &eng.interner))
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Source::F64(values) => values.len(),
+            Source::I32(values) => values.len(),
+            Source::I64(values) | Source::Decimal(values, _) => values.len(),
+            Source::Bool(values) => values.len(),
+            Source::U32(values) => values.len(),
+            Source::U8(values) => values.len(),
+            Source::Str(strings) => strings.len(),
+            Source::Rle(rle) => rle.len(),
+            Source::Interned(codes, _) => codes.len(),
+        }
+    }
+
+    pub(crate) fn cell(&self, row: usize) -> Cell<'a> {
+        match self {
+            Source::F64(values) => {
+                let v = values.get(row);
+                if v.is_nan() { Cell::Null } else { Cell::Float(v) }
+            }
+            Source::I32(values) => match values[row] {
+                i32::MIN => Cell::Null,
+                v => Cell::Int(v as i64),
+            },
+            Source::I64(values) => match values[row] {
+                i64::MIN => Cell::Null,
+                v => Cell::Int(v),
+            },
+            Source::Decimal(values, scale) => match values[row] {
+                i64::MIN => Cell::Null,
+                v => Cell::Decimal(v, *scale),
+            },
+            Source::Bool(values) => Cell::Bool(values[row] != 0),
+            Source::U32(values) => Cell::Int(values[row] as i64),
+            Source::U8(values) => Cell::Int(values[row] as i64),
+            Source::Str(strings) => strings.get(row).map_or(Cell::Null, Cell::Text),
+            Source::Rle(rle) => {
+                let v = rle.get(row);
+                if v.is_nan() { Cell::Null } else { Cell::Float(v) }
+            }
+            Source::Interned(codes, interner) => interner.get(codes[row]).map_or(Cell::Null, Cell::Text),
+        }
+    }
+}
+
+/// Result of converting one value
+pub(crate) enum Conv<T> {
+    Value(T),
+    Null,
+    /// Not representable; carries the saturated value when there is one
+    Invalid(Option<T>),
+}
+
+fn round_with(v: f64, rounding_mode: u8) -> f64 {
+    match rounding_mode {
+        ROUND_NEAREST => v.round(),
+        ROUND_FLOOR => v.floor(),
+        ROUND_CEIL => v.ceil(),
+        _ => v.trunc(),
+    }
+}
+
+/// Convert to an integer in `lo..=hi` (the null sentinel lies below `lo`)
+fn to_int(cell: Cell, rounding_mode: u8, lo: i64, hi: i64) -> Conv<i64> {
+    let v = match cell {
+        Cell::Null => return Conv::Null,
+        Cell::Int(i) => {
+            return if i < lo {
+                Conv::Invalid(Some(lo))
+            } else if i > hi {
+                Conv::Invalid(Some(hi))
+            } else {
+                Conv::Value(i)
+            };
+        }
+        Cell::Bool(b) => return Conv::Value(b as i64),
+        Cell::Float(v) => v,
+        Cell::Decimal(v, scale) => v as f64 / pow10(scale) as f64,
+        Cell::Text(text) => {
+            let text = text.trim();
+            if let Ok(i) = text.parse::<i64>() {
+                return to_int(Cell::Int(i), rounding_mode, lo, hi);
+            }
+            match text.parse::<f64>() {
+                Ok(v) if !v.is_nan() => v,
+                _ => return Conv::Invalid(None),
+            }
+        }
+    };
+    let r = round_with(v, rounding_mode);
+    if r > (lo - 1) as f64 && r < hi as f64 + 1.0 {
+        Conv::Value(r as i64)
+    } else {
+        Conv::Invalid(Some(if r > 0.0 { hi } else { lo }))
+    }
+}
+
+/// Convert to an unsigned integer in `0..=max`
+fn to_unsigned(cell: Cell, rounding_mode: u8, max: u32) -> Conv<u32> {
+    match to_int(cell, rounding_mode, 0, max as i64) {
+        Conv::Value(v) => Conv::Value(v as u32),
+        Conv::Null => Conv::Null,
+        Conv::Invalid(saturated) => Conv::Invalid(saturated.map(|v| v as u32)),
+    }
+}
+
+fn to_f64(cell: Cell) -> Conv<f64> {
+    match cell {
+        Cell::Null => Conv::Null,
+        Cell::Float(v) => Conv::Value(v),
+        Cell::Int(i) => Conv::Value(i as f64),
+        Cell::Decimal(v, scale) => Conv::Value(v as f64 / pow10(scale) as f64),
+        Cell::Bool(b) => Conv::Value(b as u8 as f64),
+        Cell::Text(text) => match text.trim().parse::<f64>() {
+            Ok(v) if !v.is_nan() => Conv::Value(v),
+            _ => Conv::Invalid(None),
+        },
+    }
+}
+
+fn to_bool(cell: Cell) -> Conv<u8> {
+    match cell {
+        Cell::Null => Conv::Null,
+        Cell::Float(v) => Conv::Value((v != 0.0) as u8),
+        Cell::Int(i) | Cell::Decimal(i, _) => Conv::Value((i != 0) as u8),
+        Cell::Bool(b) => Conv::Value(b as u8),
+        Cell::Text(text) => match text.trim().to_ascii_lowercase().as_str() {
+            "true" | "1" => Conv::Value(1),
+            "false" | "0" => Conv::Value(0),
+            _ => Conv::Invalid(None),
+        },
+    }
+}
+
+/// Convert every row of `source`, resolving invalid values with `null_policy`
+/// and passing their rows to `on_invalid`. Fails with the first invalid row
+/// under `CAST_FAIL`.
+pub(crate) fn convert<T: Copy>(
+    source: &Source,
+    null_policy: u8,
+    null: T,
+    zero: T,
+    f: impl Fn(Cell) -> Conv<T>,
+    on_invalid: &mut dyn FnMut(usize),
+) -> Result<Vec<T>, usize> {
+    let mut out = Vec::with_capacity(source.len());
+    for row in 0..source.len() {
+        out.push(match f(source.cell(row)) {
+            Conv::Value(v) => v,
+            Conv::Null => null,
+            Conv::Invalid(saturated) => {
+                on_invalid(row);
+                match null_policy {
+                    CAST_FAIL => return Err(row),
+                    CAST_ZERO => zero,
+                    CAST_SATURATE => saturated.unwrap_or(null),
+                    _ => null,
+                }
+            }
+        });
+    }
+    Ok(out)
+}
+
+fn to_text(source: &Source) -> StrSeries {
+    let mut strings = StrSeries::default();
+    for row in 0..source.len() {
+        match source.cell(row) {
+            Cell::Null => strings.push(None),
+            Cell::Float(v) => strings.push(Some(&v.to_string())),
+            Cell::Int(i) => strings.push(Some(&i.to_string())),
+            Cell::Decimal(v, scale) => strings.push(Some(&format_decimal(v as i128, scale))),
+            Cell::Bool(b) => strings.push(Some(if b { "true" } else { "false" })),
+            Cell::Text(text) => strings.push(Some(text)),
+        }
+    }
+    strings
+}
+
+/// Converted values, ready to register
+enum Casted {
+    F64(Vec<f64>),
+    I32(Vec<i32>),
+    I64(Vec<i64>),
+    Bool(Vec<u8>),
+    U32(Vec<u32>),
+    U8(Vec<u8>),
+    Str(StrSeries),
+}
+
+fn dtype_name(target_dtype: u8) -> &'static str {
+    match target_dtype {
+        DTYPE_F64 => "float64",
+        DTYPE_I32 => "int32",
+        DTYPE_I64 => "int64",
+        DTYPE_BOOL => "bool",
+        DTYPE_U32 => "uint32",
+        DTYPE_U8 => "uint8",
+        _ => "string",
+    }
+}
+
+/// Cast a series (any dtype) to `target_dtype`
+/// (0 = float64, 1 = int32, 2 = int64, 3 = bool, 4 = string, 5 = uint32,
+/// 6 = uint8), returning a
+/// new series id. Floats are rounded to integers with `rounding_mode`
+/// (0 = trunc, 1 = round half away from zero, 2 = floor, 3 = ceil). Values
+/// the target cannot hold (out of range, non-finite, unparsable strings) are
+/// resolved with `null_policy`: 0 = null (false / 0 for bool and unsigned
+/// targets, which have no null), 1 = fail,
+/// 2 = zero, 3 = saturate to the target range (null if there is no bound,
+/// e.g. unparsable strings). Strings parse with surrounding whitespace
+/// trimmed; "true"/"false"/"1"/"0" cast to bool. int32 saturates at
+/// i32::MIN + 1 since i32::MIN is null (likewise for int64).
+///
+/// Returns u32::MAX for an unknown series or dtype code, or on failure
+/// (error code 5 names the offending row, see `engine_last_error_code`).
+/// The name of the source series carries over.
+#[wasm_bindgen]
+pub fn engine_cast(series_id: u32, target_dtype: u8, rounding_mode: u8, null_policy: u8) -> u32 {
+    let _prof = profile("engine_cast", || series_bytes(series_id));
+    cast_series(series_id, target_dtype, rounding_mode, null_policy, &mut |_| {})
+}
+
+/// `engine_cast` that also reports the values the target could not hold
+/// (overflowing or non-finite floats, unparsable strings) instead of
+/// resolving them silently, for surfacing data-quality warnings. Returns
+/// `{"series_id", "invalid_count", "invalid_rows"}` where `series_id` is
+/// u32::MAX on failure and `invalid_rows` lists at most `max_rows` row
+/// indices (ascending); `invalid_count` counts them all. With the fail
+/// policy the scan stops at the first invalid row. Nulls in the source are
+/// not counted.
+#[wasm_bindgen]
+pub fn engine_cast_report_json(series_id: u32, target_dtype: u8, rounding_mode: u8, null_policy: u8, max_rows: usize) -> String {
+    let _prof = profile("engine_cast_report_json", || series_bytes(series_id));
+    let mut invalid_count = 0usize;
+    let mut invalid_rows: Vec<usize> = Vec::new();
+    let id = cast_series(series_id, target_dtype, rounding_mode, null_policy, &mut |row| {
+        invalid_count += 1;
+        if invalid_rows.len() < max_rows {
+            invalid_rows.push(row);
+        }
+    });
+    serde_json::json!({
+        "series_id": id,
+        "invalid_count": invalid_count,
+        "invalid_rows": invalid_rows,
+    })
+    .to_string()
+}
+
+fn cast_series(series_id: u32, target_dtype: u8, rounding_mode: u8, null_policy: u8, on_invalid: &mut dyn FnMut(usize)) -> u32 {
+    if target_dtype > DTYPE_U8 {
+        engine_log!(warn, "engine_cast: unknown target dtype {}", target_dtype);
+        return u32::MAX;
+    }
+    let casted = ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        let source = Source::new(&eng, series_id)?;
+        let result = match target_dtype {
+            DTYPE_F64 => convert(&source, null_policy, f64::NAN, 0.0, to_f64, on_invalid).map(Casted::F64),
+            DTYPE_I32 => convert(&source, null_policy, i32::MIN, 0, |c| {
+                match to_int(c, rounding_mode, i32::MIN as i64 + 1, i32::MAX as i64) {
+                    Conv::Value(v) => Conv::Value(v as i32),
+                    Conv::Null => Conv::Null,
+                    Conv::Invalid(saturated) => Conv::Invalid(saturated.map(|v| v as i32)),
+                }
+            }, on_invalid)
+            .map(Casted::I32),
+            DTYPE_I64 => {
+                convert(&source, null_policy, i64::MIN, 0, |c| to_int(c, rounding_mode, i64::MIN + 1, i64::MAX), on_invalid)
+                    .map(Casted::I64)
+            }
+            DTYPE_BOOL => convert(&source, null_policy, 0, 0, to_bool, on_invalid).map(Casted::Bool),
+            DTYPE_U32 => convert(&source, null_policy, 0, 0, |c| to_unsigned(c, rounding_mode, u32::MAX), on_invalid)
+                .map(Casted::U32),
+            DTYPE_U8 => convert(&source, null_policy, 0, 0, |c| to_unsigned(c, rounding_mode, u8::MAX as u32), on_invalid)
+                .map(|values| Casted::U8(values.into_iter().map(|v| v as u8).collect())),
+            _ => Ok(Casted::Str(to_text(&source))),
+        };
+        Some(result)
+    });
+    let casted = match casted {
+        Some(Ok(casted)) => casted,
+        Some(Err(row)) => {
+            set_last_error(EngineError::Cast { row, dtype: dtype_name(target_dtype) });
+            return u32::MAX;
+        }
+        None => {
+            engine_log!(warn, "engine_cast: unknown series_id={}", series_id);
+            return u32::MAX;
+        }
+    };
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        let id = match casted {
+            Casted::F64(values) => eng.register_series_f64(&values),
+            Casted::I32(values) => eng.register_series_i32(&values),
+            Casted::I64(values) => eng.register_series_i64(&values),
+            Casted::Bool(values) => eng.register_series_bool(&values),
+            Casted::U32(values) => eng.register_series_u32(&values),
+            Casted::U8(values) => eng.register_series_u8(&values),
+            Casted::Str(strings) => eng.register_series_str(strings),
+        };
+        if id != u32::MAX {
+            if let Some(name) = eng.meta.get(&series_id).and_then(|m| m.name.clone()) {
+                eng.meta.entry(id).or_default().name = Some(name);
+            }
+        }
+        id
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{engine_create_series_f64, engine_create_series_i32, engine_create_series_i64, engine_create_series_str_packed};
+    use crate::decimal::engine_create_series_decimal;
+    use crate::error::{engine_last_error_code, engine_last_error_message, ERROR_CAST};
+    use crate::series::{engine_series_to_json_str, engine_series_to_vec_bool, engine_series_to_vec_i32, engine_series_to_vec_u8};
+
+    #[test]
+    fn int32_saturates_above_the_null_sentinel() {
+        let floats = engine_create_series_f64(&[3e10, -3e10, f64::NAN, 2.5, -2.5]);
+        let casted = engine_cast(floats, DTYPE_I32, ROUND_NEAREST, CAST_SATURATE);
+        assert_eq!(engine_series_to_vec_i32(casted), [i32::MAX, i32::MIN + 1, i32::MIN, 3, -3]);
+        // i32::MIN is null in int32, so an int64 holding it saturates too
+        let ints = engine_create_series_i64(&[i32::MIN as i64, i32::MIN as i64 + 1]);
+        assert_eq!(engine_series_to_vec_i32(engine_cast(ints, DTYPE_I32, ROUND_TRUNC, CAST_SATURATE)), [i32::MIN + 1, i32::MIN + 1]);
+        assert_eq!(engine_series_to_vec_i32(engine_cast(ints, DTYPE_I32, ROUND_TRUNC, CAST_NULL)), [i32::MIN, i32::MIN + 1]);
+        let bytes = engine_create_series_f64(&[255.9, 256.0, -1.0]);
+        assert_eq!(engine_series_to_vec_u8(engine_cast(bytes, DTYPE_U8, ROUND_TRUNC, CAST_SATURATE)), [255, 255, 0]);
+    }
+
+    #[test]
+    fn null_policies_resolve_unparsable_strings() {
+        let text = engine_create_series_str_packed(b"1 2 x", &[0, 1, 4, 5, 5], &[0, 0, 0, 1]);
+        assert_eq!(engine_series_to_vec_i32(engine_cast(text, DTYPE_I32, ROUND_TRUNC, CAST_NULL)), [1, 2, i32::MIN, i32::MIN]);
+        assert_eq!(engine_series_to_vec_i32(engine_cast(text, DTYPE_I32, ROUND_TRUNC, CAST_ZERO)), [1, 2, 0, i32::MIN]);
+        assert_eq!(engine_series_to_vec_i32(engine_cast(text, DTYPE_I32, ROUND_TRUNC, CAST_SATURATE)), [1, 2, i32::MIN, i32::MIN]);
+        assert_eq!(engine_cast(text, DTYPE_I32, ROUND_TRUNC, CAST_FAIL), u32::MAX);
+        assert_eq!(engine_last_error_code(), ERROR_CAST);
+        assert_eq!(engine_last_error_message(), "value at row 2 cannot be cast to int32");
+        let report: serde_json::Value = serde_json::from_str(&engine_cast_report_json(text, DTYPE_F64, ROUND_TRUNC, CAST_NULL, 8)).unwrap();
+        assert_eq!((report["invalid_count"].as_u64(), report["invalid_rows"].to_string()), (Some(1), "[2]".to_string()));
+    }
+
+    #[test]
+    fn casts_to_bool_and_text() {
+        let floats = engine_create_series_f64(&[0.0, 1.5, f64::NAN]);
+        assert_eq!(engine_series_to_vec_bool(engine_cast(floats, DTYPE_BOOL, ROUND_TRUNC, CAST_NULL)), [0, 1, 0]);
+        let ints = engine_create_series_i32(&[1, i32::MIN]);
+        assert_eq!(engine_series_to_json_str(engine_cast(ints, DTYPE_STR, ROUND_TRUNC, CAST_NULL)), r#"["1",null]"#);
+        let decimals = engine_create_series_decimal(&[125, -5], 2);
+        assert_eq!(engine_series_to_json_str(engine_cast(decimals, DTYPE_STR, ROUND_TRUNC, CAST_NULL)), r#"["1.25","-0.05"]"#);
+        assert_eq!(engine_cast(ints, 9, ROUND_TRUNC, CAST_NULL), u32::MAX);
+        assert_eq!(engine_cast(u32::MAX - 1, DTYPE_F64, ROUND_TRUNC, CAST_NULL), u32::MAX);
+    }
+}
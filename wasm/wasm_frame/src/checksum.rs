@@ -0,0 +1,218 @@
+//! Content hashing of series
+//!
+//! A series checksum is XXH64 (seed 0) over its physical dtype name and its
+//! values in little-endian byte order, so it only depends on the content:
+//! equal values give equal checksums across ids, chunk layouts and engine
+//! instances. The TS layer keys caches and memoized results on it.
+
+use wasm_bindgen::prelude::*;
+use crate::cast::Source;
+use crate::core::ENGINE;
+use crate::profiling::{profile, series_bytes};
+
+const PRIME1: u64 = 0x9E37_79B1_85EB_CA87;
+const PRIME2: u64 = 0xC2B2_AE3D_27D4_EB4F;
+const PRIME3: u64 = 0x1656_67B1_9E37_79F9;
+const PRIME4: u64 = 0x85EB_CA77_C2B2_AE63;
+const PRIME5: u64 = 0x27D4_EB2F_1656_67C5;
+
+fn round(acc: u64, input: u64) -> u64 {
+    acc.wrapping_add(input.wrapping_mul(PRIME2)).rotate_left(31).wrapping_mul(PRIME1)
+}
+
+fn merge_round(acc: u64, lane: u64) -> u64 {
+    (acc ^ round(0, lane)).wrapping_mul(PRIME1).wrapping_add(PRIME4)
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..8].try_into().unwrap())
+}
+
+/// Streaming XXH64: feeding the input in pieces gives the same digest as
+/// hashing it in one go
+pub(crate) struct Xxh64 {
+    lanes: [u64; 4],
+    /// Bytes not yet consumed by a full 32-byte stripe
+    buffer: [u8; 32],
+    buffered: usize,
+    total: u64,
+    seed: u64,
+}
+
+impl Xxh64 {
+    pub(crate) fn new(seed: u64) -> Self {
+        Xxh64 {
+            lanes: [
+                seed.wrapping_add(PRIME1).wrapping_add(PRIME2),
+                seed.wrapping_add(PRIME2),
+                seed,
+                seed.wrapping_sub(PRIME1),
+            ],
+            buffer: [0; 32],
+            buffered: 0,
+            total: 0,
+            seed,
+        }
+    }
+
+    fn stripe(&mut self, stripe: &[u8]) {
+        for (i, lane) in self.lanes.iter_mut().enumerate() {
+            *lane = round(*lane, read_u64(&stripe[i * 8..]));
+        }
+    }
+
+    pub(crate) fn update(&mut self, mut bytes: &[u8]) {
+        self.total += bytes.len() as u64;
+        if self.buffered > 0 {
+            let take = bytes.len().min(32 - self.buffered);
+            self.buffer[self.buffered..self.buffered + take].copy_from_slice(&bytes[..take]);
+            self.buffered += take;
+            bytes = &bytes[take..];
+            if self.buffered < 32 {
+                return;
+            }
+            let buffer = self.buffer;
+            self.stripe(&buffer);
+            self.buffered = 0;
+        }
+        let mut stripes = bytes.chunks_exact(32);
+        for stripe in stripes.by_ref() {
+            self.stripe(stripe);
+        }
+        let rest = stripes.remainder();
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffered = rest.len();
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        let mut h = if self.total >= 32 {
+            let [v1, v2, v3, v4] = self.lanes;
+            let h = v1.rotate_left(1).wrapping_add(v2.rotate_left(7)).wrapping_add(v3.rotate_left(12)).wrapping_add(v4.rotate_left(18));
+            self.lanes.iter().fold(h, |h, &lane| merge_round(h, lane))
+        } else {
+            self.seed.wrapping_add(PRIME5)
+        };
+        h = h.wrapping_add(self.total);
+        let mut rest = &self.buffer[..self.buffered];
+        while rest.len() >= 8 {
+            h = (h ^ round(0, read_u64(rest))).rotate_left(27).wrapping_mul(PRIME1).wrapping_add(PRIME4);
+            rest = &rest[8..];
+        }
+        if rest.len() >= 4 {
+            let k = u32::from_le_bytes(rest[..4].try_into().unwrap()) as u64;
+            h = (h ^ k.wrapping_mul(PRIME1)).rotate_left(23).wrapping_mul(PRIME2).wrapping_add(PRIME3);
+            rest = &rest[4..];
+        }
+        for &byte in rest {
+            h = (h ^ (byte as u64).wrapping_mul(PRIME5)).rotate_left(11).wrapping_mul(PRIME1);
+        }
+        h ^= h >> 33;
+        h = h.wrapping_mul(PRIME2);
+        h ^= h >> 29;
+        h = h.wrapping_mul(PRIME3);
+        h ^ (h >> 32)
+    }
+}
+
+/// Feed string rows as a u32 byte length (u32::MAX for null) and the bytes
+fn update_text<'a>(hasher: &mut Xxh64, rows: impl Iterator<Item = Option<&'a str>>) {
+    for value in rows {
+        match value {
+            Some(s) => {
+                hasher.update(&(s.len() as u32).to_le_bytes());
+                hasher.update(s.as_bytes());
+            }
+            None => hasher.update(&u32::MAX.to_le_bytes()),
+        }
+    }
+}
+
+/// Stable 64-bit content hash of a series (XXH64 of its dtype and values,
+/// see the module docs), e.g. as a cache key for results computed from the
+/// column. Every NaN hashes as the same null; string series hash their
+/// rows, interned series their strings rather than codes and RLE series
+/// their runs. Returns 0 if the id is unknown.
+#[wasm_bindgen]
+pub fn engine_series_checksum(series_id: u32) -> u64 {
+    let _prof = profile("engine_series_checksum", || series_bytes(series_id));
+    ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        let (source, dtype) = match (Source::new(&eng, series_id), eng.series_dtype(series_id)) {
+            (Some(source), Some(dtype)) => (source, dtype),
+            _ => return 0,
+        };
+        let mut hasher = Xxh64::new(0);
+        hasher.update(dtype.as_bytes());
+        hasher.update(&[0]);
+        match source {
+            Source::F64(values) => {
+                for (_, chunk) in values.chunks() {
+                    for &v in chunk {
+                        let v = if v.is_nan() { f64::NAN } else { v };
+                        hasher.update(&v.to_le_bytes());
+                    }
+                }
+            }
+            Source::I32(values) => values.iter().for_each(|v| hasher.update(&v.to_le_bytes())),
+            Source::I64(values) => values.iter().for_each(|v| hasher.update(&v.to_le_bytes())),
+            Source::Decimal(values, scale) => {
+                hasher.update(&[scale]);
+                values.iter().for_each(|v| hasher.update(&v.to_le_bytes()));
+            }
+            Source::U32(values) => values.iter().for_each(|v| hasher.update(&v.to_le_bytes())),
+            Source::Bool(values) | Source::U8(values) => hasher.update(values),
+            Source::Str(strings) => update_text(&mut hasher, (0..strings.len()).map(|row| strings.get(row))),
+            Source::Rle(rle) => {
+                for (_, end, v) in rle.runs() {
+                    let v = if v.is_nan() { f64::NAN } else { v };
+                    hasher.update(&v.to_le_bytes());
+                    hasher.update(&(end as u32).to_le_bytes());
+                }
+            }
+            Source::Interned(codes, interner) => update_text(&mut hasher, codes.iter().map(|&code| interner.get(code))),
+        }
+        hasher.finish()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{engine_chunked_append_f64, engine_chunked_create_f64, engine_create_series_f64, engine_create_series_i64};
+
+    fn xxh64(bytes: &[u8], seed: u64) -> u64 {
+        let mut hasher = Xxh64::new(seed);
+        hasher.update(bytes);
+        hasher.finish()
+    }
+
+    #[test]
+    fn xxh64_matches_the_reference_vectors() {
+        assert_eq!(xxh64(b"", 0), 0xEF46_DB37_51D8_E999);
+        assert_eq!(xxh64(b"a", 0), 0xD24E_C4F1_A98C_6E5B);
+        assert_eq!(xxh64(b"abc", 0), 0x44BC_2CF5_AD77_0999);
+        let long = b"Nobody inspects the spammish repetition";
+        assert_eq!(xxh64(long, 0), 0xFBCE_A83C_8A37_8BF1);
+        assert_eq!(xxh64(b"xxhash", 20141025), 0xB559_B98D_844E_0635);
+        // Streaming in uneven pieces crosses the 32-byte stripe boundary
+        let mut hasher = Xxh64::new(0);
+        for piece in long.chunks(5) {
+            hasher.update(piece);
+        }
+        assert_eq!(hasher.finish(), 0xFBCE_A83C_8A37_8BF1);
+    }
+
+    #[test]
+    fn checksums_depend_only_on_content() {
+        let plain = engine_create_series_f64(&[1.0, f64::NAN, 3.0]);
+        let chunked = engine_chunked_create_f64();
+        assert!(engine_chunked_append_f64(chunked, &[1.0]));
+        assert!(engine_chunked_append_f64(chunked, &[-f64::NAN, 3.0]));
+        assert_eq!(engine_series_checksum(plain), engine_series_checksum(chunked));
+        assert_ne!(engine_series_checksum(plain), engine_series_checksum(engine_create_series_f64(&[1.0, 3.0, f64::NAN])));
+        // Same bytes under another dtype hash differently
+        let bits = engine_create_series_i64(&[1.0f64.to_bits() as i64]);
+        assert_ne!(engine_series_checksum(bits), engine_series_checksum(engine_create_series_f64(&[1.0])));
+        assert_eq!(engine_series_checksum(u32::MAX - 1), 0);
+    }
+}
@@ -0,0 +1,161 @@
+//! Series comparison for regression testing
+//!
+//! Compares two series row by row, across dtypes: numeric values (floats,
+//! integers, decimals in real units, bools as 0/1) compare numerically
+//! within a tolerance, strings compare exactly, and a number never equals a
+//! string. Nulls equal each other only when requested.
+
+use wasm_bindgen::prelude::*;
+use crate::cast::{Cell, Source};
+use crate::core::ENGINE;
+use crate::profiling::{profile, series_bytes};
+
+/// Outcome of comparing two values
+#[derive(PartialEq)]
+enum RowDiff {
+    Equal,
+    /// Numbers further apart than the tolerance, by this much
+    Value(f64),
+    /// Exactly one side null, or nulls compared with `null_equal` off
+    Null,
+    /// Different strings, or a string against a number
+    Other,
+}
+
+fn compare_cells(a: Cell, b: Cell, null_equal: bool, tolerance: f64) -> RowDiff {
+    match (a, b) {
+        (Cell::Null, Cell::Null) if null_equal => RowDiff::Equal,
+        (Cell::Null, _) | (_, Cell::Null) => RowDiff::Null,
+        (Cell::Text(x), Cell::Text(y)) => if x == y { RowDiff::Equal } else { RowDiff::Other },
+        (a, b) => match (a.number(), b.number()) {
+            (Some(x), Some(y)) if x == y || (x - y).abs() <= tolerance => RowDiff::Equal,
+            (Some(x), Some(y)) => RowDiff::Value((x - y).abs()),
+            _ => RowDiff::Other,
+        },
+    }
+}
+
+/// Compare the overlapping rows of two series, calling `f(row, diff)` for
+/// each differing row. None if either id is unknown, otherwise the lengths.
+fn compare_series(a_id: u32, b_id: u32, null_equal: bool, tolerance: f64, mut f: impl FnMut(usize, RowDiff)) -> Option<(usize, usize)> {
+    ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        let (a, b) = (Source::new(&eng, a_id)?, Source::new(&eng, b_id)?);
+        for row in 0..a.len().min(b.len()) {
+            let diff = compare_cells(a.cell(row), b.cell(row), null_equal, tolerance);
+            if diff != RowDiff::Equal {
+                f(row, diff);
+            }
+        }
+        Some((a.len(), b.len()))
+    })
+}
+
+/// Whether two series hold the same values (see the module docs; NaN
+/// `tolerance` or 0 means exact). With `null_equal` = 1, null rows equal
+/// each other. Returns `[equal, first_mismatch]`: `equal` is 1 or 0 and
+/// `first_mismatch` the first differing row (the shorter length if one
+/// series is a prefix of the other, u32::MAX if equal); an empty array if
+/// either id is unknown.
+#[wasm_bindgen]
+pub fn engine_series_equals(a_id: u32, b_id: u32, null_equal: u8, tolerance: f64) -> Box<[u32]> {
+    let _prof = profile("engine_series_equals", || series_bytes(a_id) + series_bytes(b_id));
+    let mut first: Option<usize> = None;
+    let lens = compare_series(a_id, b_id, null_equal != 0, tolerance.max(0.0), |row, _| {
+        first.get_or_insert(row);
+    });
+    let (len_a, len_b) = match lens {
+        Some(lens) => lens,
+        None => return Box::new([]),
+    };
+    let first = first.or((len_a != len_b).then(|| len_a.min(len_b)));
+    match first {
+        Some(row) => Box::new([0, row as u32]),
+        None => Box::new([1, u32::MAX]),
+    }
+}
+
+/// Summary of the differences between two series as JSON:
+/// `{"equal", "len_a", "len_b", "compared", "mismatches", "null_mismatches",
+///   "value_mismatches", "other_mismatches", "max_abs_diff", "mismatch_rows"}`.
+/// `compared` is the number of overlapping rows; `null_mismatches` counts
+/// rows null on one side only (or on both when `null_equal` is 0),
+/// `value_mismatches` numbers differing beyond `tolerance`, whose largest
+/// difference is `max_abs_diff` (null if there are none), and
+/// `other_mismatches` differing strings or a string against a number.
+/// `mismatch_rows` lists at most `max_rows` differing rows (ascending).
+/// Returns an empty string if either id is unknown.
+#[wasm_bindgen]
+pub fn engine_series_compare_report_json(a_id: u32, b_id: u32, null_equal: u8, tolerance: f64, max_rows: usize) -> String {
+    let _prof = profile("engine_series_compare_report_json", || series_bytes(a_id) + series_bytes(b_id));
+    let (mut nulls, mut values, mut others) = (0usize, 0usize, 0usize);
+    let mut max_abs_diff: Option<f64> = None;
+    let mut rows: Vec<usize> = Vec::new();
+    let lens = compare_series(a_id, b_id, null_equal != 0, tolerance.max(0.0), |row, diff| {
+        match diff {
+            RowDiff::Value(d) => {
+                values += 1;
+                max_abs_diff = Some(max_abs_diff.map_or(d, |m| m.max(d)));
+            }
+            RowDiff::Null => nulls += 1,
+            _ => others += 1,
+        }
+        if rows.len() < max_rows {
+            rows.push(row);
+        }
+    });
+    let (len_a, len_b) = match lens {
+        Some(lens) => lens,
+        None => return String::new(),
+    };
+    let mismatches = nulls + values + others;
+    serde_json::json!({
+        "equal": mismatches == 0 && len_a == len_b,
+        "len_a": len_a,
+        "len_b": len_b,
+        "compared": len_a.min(len_b),
+        "mismatches": mismatches,
+        "null_mismatches": nulls,
+        "value_mismatches": values,
+        "other_mismatches": others,
+        "max_abs_diff": max_abs_diff,
+        "mismatch_rows": rows,
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{engine_create_series_f64, engine_create_series_i32, engine_create_series_str};
+
+    #[test]
+    fn equality_crosses_dtypes_within_tolerance() {
+        let floats = engine_create_series_f64(&[1.0, 2.0, f64::NAN, 4.0]);
+        let ints = engine_create_series_i32(&[1, 3, i32::MIN, 4]);
+        assert_eq!(*engine_series_equals(floats, ints, 1, 0.0), [0, 1]);
+        assert_eq!(*engine_series_equals(floats, ints, 1, 1.0), [1, u32::MAX]);
+        assert_eq!(*engine_series_equals(floats, ints, 0, 1.0), [0, 2]);
+        assert_eq!(*engine_series_equals(floats, ints, 1, f64::NAN), [0, 1]);
+        let prefix = engine_create_series_f64(&[1.0, 2.0]);
+        assert_eq!(*engine_series_equals(floats, prefix, 1, 0.0), [0, 2]);
+        assert!(engine_series_equals(floats, u32::MAX - 1, 1, 0.0).is_empty());
+    }
+
+    #[test]
+    fn report_counts_each_kind_of_mismatch() {
+        let a = engine_create_series_f64(&[1.0, 5.0, f64::NAN, 7.0, 9.0]);
+        let b = engine_create_series_str(vec!["1".into(), "x".into(), "y".into()]);
+        let report: serde_json::Value = serde_json::from_str(&engine_series_compare_report_json(a, b, 1, 0.0, 10)).unwrap();
+        // Numbers never equal strings, even when the text is numeric
+        assert_eq!(report["other_mismatches"], 2);
+        assert_eq!(report["null_mismatches"], 1);
+        assert_eq!((report["equal"].as_bool(), report["compared"].as_u64()), (Some(false), Some(3)));
+        let c = engine_create_series_f64(&[1.5, 5.0, f64::NAN, 4.0, 9.0]);
+        let report: serde_json::Value = serde_json::from_str(&engine_series_compare_report_json(a, c, 0, 0.5, 1)).unwrap();
+        assert_eq!((report["value_mismatches"].as_u64(), report["null_mismatches"].as_u64()), (Some(1), Some(1)));
+        assert_eq!(report["max_abs_diff"], 3.0);
+        assert_eq!(report["mismatch_rows"], serde_json::json!([2]));
+        assert_eq!(engine_series_compare_report_json(a, u32::MAX - 1, 0, 0.0, 1), "");
+    }
+}
@@ -1,11 +1,15 @@
-//! Core engine functionality: memory management and state
+//! Core engine functionality: memory management, state and frames
 //! 
 //! This module provides the foundational EngineState and memory management
 //! functions for the WASM engine.
 
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::rc::Rc;
+use std::collections::{HashMap, HashSet};
 use wasm_bindgen::prelude::*;
+use crate::error::{install_panic_hook, set_last_error, EngineError};
+use crate::groupby::GroupOrder;
+use crate::profiling::{profile, series_bytes};
 
 // Simple ID generator and registries protected by a global mutex.
 // This keeps design straightforward for single-threaded wasm; can be upgraded later.
@@ -17,160 +21,2653 @@ pub struct EngineState {
     pub series_store: HashMap<u32, (*mut f64, usize)>,
     // Store series as contiguous i32 buffers owned by WASM heap
     pub series_store_i32: HashMap<u32, (*mut i32, usize)>,
+    // Store series as contiguous i64 buffers (i64::MIN is null)
+    pub series_store_i64: HashMap<u32, (*mut i64, usize)>,
+    // Store boolean series as contiguous u8 buffers (0 = false, 1 = true)
+    pub series_store_bool: HashMap<u32, (*mut u8, usize)>,
+    // Store unsigned series (ids, row numbers, small codes); no null value
+    pub series_store_u32: HashMap<u32, (*mut u32, usize)>,
+    pub series_store_u8: HashMap<u32, (*mut u8, usize)>,
+    // Scale of i64 series holding fixed-point decimals (value x 10^scale)
+    pub decimal_scales: HashMap<u32, u8>,
+    // Store string series as UTF-8 bytes plus offsets, shared by clones
+    pub series_store_str: HashMap<u32, Rc<StrSeries>>,
+    // Store run-length encoded f64 series, shared by clones
+    pub series_store_rle: HashMap<u32, Rc<RleSeries>>,
+    // Store interned string series as codes into `interner` (u32::MAX is null)
+    pub series_store_interned: HashMap<u32, Rc<Vec<u32>>>,
+    // Engine-wide string dictionary shared by interned series
+    pub interner: Interner,
+    // Store f64 series as a sequence of buffers (large or streamed columns)
+    pub chunked_store: HashMap<u32, Vec<(*mut f64, usize)>>,
+    // Bytes currently allocated for series buffers
+    pub allocated_bytes: usize,
+    // Upper bound for allocated_bytes (0 = unlimited)
+    pub memory_limit: usize,
+    // Open scopes (innermost last), each listing the series ids created in it
+    pub scopes: Vec<Vec<u32>>,
+    // Series exempt from scope cleanup
+    pub pinned: HashSet<u32>,
+    // Reference counts of series retained more than once (absent = 1)
+    pub refcounts: HashMap<u32, u32>,
+    // Buffers referenced by more than one series, keyed by base address:
+    // (allocated length, number of series referencing it)
+    pub shared_buffers: HashMap<usize, (usize, u32)>,
+    // Series pointing into a shared buffer (clones and slice views), mapped
+    // to the buffer's base address
+    pub buffer_bases: HashMap<u32, usize>,
+    // Buffers handed out by `engine_alloc_uninit_f64` and not yet adopted:
+    // address -> length in values
+    pub pending_buffers: HashMap<usize, usize>,
+    // Per-series metadata (name, logical dtype, cached statistics, origin)
+    pub meta: HashMap<u32, SeriesMeta>,
+    // Sequence number given to the next created series
+    pub creation_seq: u64,
+    // Tag recorded on series created while it is set (leak diagnostics)
+    pub creation_tag: Option<Rc<str>>,
+    // Output order of groupby results (`engine_set_groupby_order`)
+    pub groupby_order: GroupOrder,
+    // Explicit group keys for the next groupby call only
+    pub groupby_keys: Option<Rc<[String]>>,
+    pub next_frame_id: u32,
+    // Frames (named column sets), each holding a reference to its columns
+    pub frames: HashMap<u32, Frame>,
+}
+
+/// Statistics cached per series until its values change
+#[derive(Clone, Copy, Debug)]
+pub struct SeriesStats {
+    pub null_count: usize,
+    /// NaN if the series has no non-null values
+    pub min: f64,
+    pub max: f64,
+}
+
+/// Monotonicity of a series as in pandas: non-strict, false if the series
+/// has a null, true for both when it is empty
+#[derive(Clone, Copy, Debug)]
+pub struct Monotonic {
+    pub increasing: bool,
+    pub decreasing: bool,
+}
+
+/// Metadata kept alongside a series buffer
+#[derive(Clone, Debug, Default)]
+pub struct SeriesMeta {
+    pub name: Option<String>,
+    /// Logical dtype set by the host (e.g. "datetime", "bool"); defaults to
+    /// the physical dtype
+    pub logical_dtype: Option<String>,
+    pub stats: Option<SeriesStats>,
+    /// Cached monotonicity, dropped with `stats` when the values change
+    pub monotonic: Option<Monotonic>,
+    /// Creation sequence number (monotonic, not reset by `engine_flush`)
+    pub created: u64,
+    /// Creation-site tag active when the series was created
+    pub tag: Option<Rc<str>>,
+}
+
+/// String series: UTF-8 `bytes` split by `offsets` (one more entry than
+/// there are values); `nulls` flags null rows and is empty if there are none
+#[derive(Clone, Debug)]
+pub struct StrSeries {
+    pub bytes: Vec<u8>,
+    pub offsets: Vec<u32>,
+    pub nulls: Vec<bool>,
+}
+
+impl Default for StrSeries {
+    fn default() -> Self {
+        StrSeries { bytes: Vec::new(), offsets: vec![0], nulls: Vec::new() }
+    }
+}
+
+impl StrSeries {
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_null(&self, row: usize) -> bool {
+        !self.nulls.is_empty() && self.nulls[row]
+    }
+
+    /// Value at `row`, None if null
+    pub fn get(&self, row: usize) -> Option<&str> {
+        if self.is_null(row) {
+            return None;
+        }
+        let bytes = &self.bytes[self.offsets[row] as usize..self.offsets[row + 1] as usize];
+        Some(std::str::from_utf8(bytes).unwrap_or_default())
+    }
+
+    pub fn push(&mut self, value: Option<&str>) {
+        match value {
+            Some(text) => {
+                if !self.nulls.is_empty() {
+                    self.nulls.push(false);
+                }
+                self.bytes.extend_from_slice(text.as_bytes());
+            }
+            None => {
+                if self.nulls.is_empty() {
+                    self.nulls = vec![false; self.len()];
+                }
+                self.nulls.push(true);
+            }
+        }
+        self.offsets.push(self.bytes.len() as u32);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Option<&str>> + '_ {
+        (0..self.len()).map(|row| self.get(row))
+    }
+
+    /// Heap bytes held by the series
+    pub fn heap_bytes(&self) -> usize {
+        self.bytes.len() + self.offsets.len() * std::mem::size_of::<u32>() + self.nulls.len()
+    }
+}
+
+/// Strings of the packed binary string protocol: UTF-8 `bytes` plus `n + 1`
+/// `offsets` where string `i` is `bytes[offsets[i]..offsets[i + 1]]` (empty
+/// `offsets` means no strings). None if the offsets decrease or run past
+/// `bytes`, or a string is not valid UTF-8.
+pub(crate) fn packed_strs<'a>(bytes: &'a [u8], offsets: &[u32]) -> Option<Vec<&'a str>> {
+    offsets
+        .windows(2)
+        .map(|w| {
+            let (start, end) = (w[0] as usize, w[1] as usize);
+            if start > end || end > bytes.len() {
+                return None;
+            }
+            std::str::from_utf8(&bytes[start..end]).ok()
+        })
+        .collect()
+}
+
+impl<'a> FromIterator<Option<&'a str>> for StrSeries {
+    fn from_iter<I: IntoIterator<Item = Option<&'a str>>>(iter: I) -> Self {
+        let mut series = StrSeries::default();
+        for value in iter {
+            series.push(value);
+        }
+        series
+    }
+}
+
+/// Engine-wide string dictionary: each distinct string is stored once and
+/// referred to by its code. Codes stay valid until `engine_flush`.
+#[derive(Default)]
+pub struct Interner {
+    strings: Vec<Rc<str>>,
+    codes: HashMap<Rc<str>, u32>,
+    bytes: usize,
+}
+
+impl Interner {
+    /// Code of `text` if it is already interned
+    pub fn lookup(&self, text: &str) -> Option<u32> {
+        self.codes.get(text).copied()
+    }
+
+    /// String behind `code` (None for the null code or an unknown code)
+    pub fn get(&self, code: u32) -> Option<&str> {
+        self.strings.get(code as usize).map(|s| s.as_ref())
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Bytes of string data held by the dictionary
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    fn insert(&mut self, text: &str) -> u32 {
+        let code = self.strings.len() as u32;
+        let text: Rc<str> = Rc::from(text);
+        self.bytes += text.len();
+        self.strings.push(text.clone());
+        self.codes.insert(text, code);
+        code
+    }
+}
+
+/// Run-length encoded f64 series: run `i` repeats `values[i]` up to row
+/// `ends[i]` (exclusive). Adjacent runs always differ; NaN runs are null.
+#[derive(Clone, Debug, Default)]
+pub struct RleSeries {
+    pub values: Vec<f64>,
+    pub ends: Vec<u32>,
+}
+
+impl RleSeries {
+    pub fn encode(values: impl IntoIterator<Item = f64>) -> Self {
+        let mut rle = RleSeries::default();
+        for v in values {
+            rle.push_run(v, 1);
+        }
+        rle
+    }
+
+    /// Append `count` rows of `value`, extending the last run if equal
+    pub fn push_run(&mut self, value: f64, count: u32) {
+        let end = self.len() as u32 + count;
+        match self.values.last() {
+            Some(&last) if last == value || (last.is_nan() && value.is_nan()) => *self.ends.last_mut().unwrap() = end,
+            _ => {
+                self.values.push(value);
+                self.ends.push(end);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ends.last().map_or(0, |&end| end as usize)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    pub fn num_runs(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Runs as `(start, end, value)` row ranges
+    pub fn runs(&self) -> impl Iterator<Item = (usize, usize, f64)> + '_ {
+        self.ends.iter().zip(&self.values).scan(0usize, |start, (&end, &v)| {
+            let run = (*start, end as usize, v);
+            *start = end as usize;
+            Some(run)
+        })
+    }
+
+    /// Value at `row` (row < len)
+    pub fn get(&self, row: usize) -> f64 {
+        self.values[self.ends.partition_point(|&end| end as usize <= row)]
+    }
+
+    pub fn decode(&self) -> Vec<f64> {
+        let mut out = Vec::with_capacity(self.len());
+        for (start, end, v) in self.runs() {
+            out.extend(std::iter::repeat_n(v, end - start));
+        }
+        out
+    }
+
+    /// Heap bytes held by the series
+    pub fn heap_bytes(&self) -> usize {
+        self.values.len() * std::mem::size_of::<f64>() + self.ends.len() * std::mem::size_of::<u32>()
+    }
+}
+
+/// Values of an f64 series spread over one or more buffers, readable by
+/// row or chunk by chunk. Contiguous series have a single chunk.
+pub struct F64Values<'a> {
+    chunks: Vec<&'a [f64]>,
+    // Row index of each chunk's first value
+    starts: Vec<usize>,
+    len: usize,
+}
+
+impl<'a> F64Values<'a> {
+    /// View over `(ptr, len)` engine buffers
+    ///
+    /// # Safety
+    ///
+    /// The buffers must stay allocated and unmodified for `'a`.
+    unsafe fn from_buffers(buffers: &[(*mut f64, usize)]) -> Self {
+        let mut values = F64Values { chunks: Vec::with_capacity(buffers.len()), starts: Vec::with_capacity(buffers.len()), len: 0 };
+        for &(ptr, len) in buffers {
+            if len > 0 {
+                values.chunks.push(unsafe { std::slice::from_raw_parts(ptr as *const f64, len) });
+                values.starts.push(values.len);
+                values.len += len;
+            }
+        }
+        values
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Non-empty chunks with the row index of their first value
+    pub fn chunks(&self) -> impl Iterator<Item = (usize, &'a [f64])> + '_ {
+        self.starts.iter().copied().zip(self.chunks.iter().copied())
+    }
+
+    /// Value at `row` (panics if out of range)
+    pub fn get(&self, row: usize) -> f64 {
+        if self.chunks.len() == 1 {
+            return self.chunks[0][row];
+        }
+        let chunk = self.starts.partition_point(|&start| start <= row) - 1;
+        self.chunks[chunk][row - self.starts[chunk]]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = f64> + '_ {
+        self.chunks.iter().flat_map(|chunk| chunk.iter().copied())
+    }
+
+    /// All values in one contiguous vector
+    pub fn to_vec(&self) -> Vec<f64> {
+        self.chunks.concat()
+    }
+}
+
+/// Values of a registered f64 series (contiguous or chunked), read without
+/// holding the engine borrowed, so the caller may register results meanwhile.
+///
+/// # Safety
+///
+/// The returned lifetime is unbounded. The caller must stop using the values
+/// before anything frees or rewrites the series' buffers: freeing the series,
+/// in-place edits (fill, append or delete rows, copy-on-write) or clearing
+/// the engine, including through JS callbacks invoked while they are alive.
+pub(crate) unsafe fn f64_values<'a>(series_id: u32) -> Option<F64Values<'a>> {
+    ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        let buffers: &[(*mut f64, usize)] = match eng.series_store.get(&series_id) {
+            Some(entry) => std::slice::from_ref(entry),
+            None => eng.chunked_store.get(&series_id)?,
+        };
+        Some(unsafe { F64Values::from_buffers(buffers) })
+    })
+}
+
+/// Null count and min/max of the non-null values
+fn compute_stats<T: Into<f64>>(values: impl IntoIterator<Item = T>, is_null: impl Fn(&T) -> bool) -> SeriesStats {
+    let mut stats = SeriesStats { null_count: 0, min: f64::NAN, max: f64::NAN };
+    for v in values {
+        if is_null(&v) {
+            stats.null_count += 1;
+            continue;
+        }
+        let v: f64 = v.into();
+        if stats.min.is_nan() || v < stats.min {
+            stats.min = v;
+        }
+        if stats.max.is_nan() || v > stats.max {
+            stats.max = v;
+        }
+    }
+    stats
+}
+
+/// Monotonicity of `values` in one pass (see `Monotonic`)
+fn compute_monotonic<T: PartialOrd>(values: impl IntoIterator<Item = T>, is_null: impl Fn(&T) -> bool) -> Monotonic {
+    let mut monotonic = Monotonic { increasing: true, decreasing: true };
+    let mut prev: Option<T> = None;
+    for v in values {
+        if is_null(&v) {
+            return Monotonic { increasing: false, decreasing: false };
+        }
+        if let Some(prev) = prev {
+            monotonic.increasing &= prev <= v;
+            monotonic.decreasing &= prev >= v;
+        }
+        prev = Some(v);
+    }
+    monotonic
+}
+
+/// Allocate a heap buffer holding a copy of `data`. Zero-length buffers use
+/// a dangling (non-null, aligned) pointer and never reach the allocator.
+fn alloc_copy<T: Copy>(data: &[T]) -> Result<*mut T, EngineError> {
+    if data.is_empty() {
+        return Ok(std::ptr::NonNull::dangling().as_ptr());
+    }
+    let layout = std::alloc::Layout::array::<T>(data.len()).map_err(|_| EngineError::Layout { len: data.len() })?;
+    let raw = unsafe { std::alloc::alloc(layout) } as *mut T;
+    if raw.is_null() {
+        return Err(EngineError::OutOfMemory { bytes: layout.size() });
+    }
+    unsafe { std::ptr::copy_nonoverlapping(data.as_ptr(), raw, data.len()) };
+    Ok(raw)
+}
+
+fn dealloc_buffer<T>(ptr: *mut T, len: usize) {
+    if ptr.is_null() || len == 0 {
+        return;
+    }
+    if let Ok(layout) = std::alloc::Layout::array::<T>(len) {
+        unsafe { std::alloc::dealloc(ptr as *mut u8, layout) };
+    }
+}
+
+/// Empty vector with room for `len` values, for kernel output built before
+/// it is registered. Fails with an error instead of aborting when the size
+/// overflows, would not fit under the memory limit or the allocator refuses
+/// it; the engine must not be borrowed by the caller.
+pub(crate) fn try_vec<T>(len: usize) -> Result<Vec<T>, EngineError> {
+    let bytes = len.checked_mul(std::mem::size_of::<T>()).ok_or(EngineError::Layout { len })?;
+    ENGINE.with(|cell| cell.borrow().check_budget(bytes))?;
+    let mut out = Vec::new();
+    out.try_reserve_exact(len).map_err(|_| EngineError::OutOfMemory { bytes })?;
+    Ok(out)
+}
+
+/// Caller-provided buffer of `len` values at `ptr` for the `*_into_*`
+/// copy-out functions. None if the pointer is null or misaligned for `T`,
+/// the size overflows, or the range overlaps a buffer the engine owns (a
+/// write there would corrupt a registered series).
+pub(crate) fn destination<T>(ptr: usize, len: usize) -> Option<*mut T> {
+    if ptr == 0 || !ptr.is_multiple_of(std::mem::align_of::<T>()) {
+        return None;
+    }
+    let end = len.checked_mul(std::mem::size_of::<T>()).and_then(|bytes| ptr.checked_add(bytes))?;
+    let owned = ENGINE.with(|cell| cell.borrow().owns_range(ptr, end));
+    if owned {
+        engine_log!(warn, "destination {:#x}..{:#x} overlaps an engine buffer", ptr, end);
+        return None;
+    }
+    Some(ptr as *mut T)
 }
 
 impl EngineState {
-    pub fn alloc_f64_buffer(&mut self, data: &[f64]) -> (*mut f64, usize) {
-        let len = data.len();
-        let ptr = unsafe {
-            let layout = std::alloc::Layout::from_size_align(
-                len * std::mem::size_of::<f64>(),
-                std::mem::align_of::<f64>(),
-            )
-            .unwrap();
-            let raw = std::alloc::alloc(layout) as *mut f64;
-            if !raw.is_null() {
-                std::ptr::copy_nonoverlapping(data.as_ptr(), raw, len);
+    /// Whether the byte range `start..end` overlaps a registered series
+    /// buffer or one handed out by `engine_alloc_uninit_f64`
+    fn owns_range(&self, start: usize, end: usize) -> bool {
+        fn overlaps<T>(start: usize, end: usize, ptr: *mut T, len: usize) -> bool {
+            let base = ptr as usize;
+            len > 0 && base < end && start < base + len * std::mem::size_of::<T>()
+        }
+        self.series_store.values().any(|&(p, l)| overlaps(start, end, p, l))
+            || self.series_store_i32.values().any(|&(p, l)| overlaps(start, end, p, l))
+            || self.series_store_i64.values().any(|&(p, l)| overlaps(start, end, p, l))
+            || self.series_store_bool.values().any(|&(p, l)| overlaps(start, end, p, l))
+            || self.series_store_u32.values().any(|&(p, l)| overlaps(start, end, p, l))
+            || self.series_store_u8.values().any(|&(p, l)| overlaps(start, end, p, l))
+            || self.chunked_store.values().flatten().any(|&(p, l)| overlaps(start, end, p, l))
+            || self.pending_buffers.iter().any(|(&p, &l)| overlaps(start, end, p as *mut f64, l))
+    }
+
+    /// Whether `bytes` more would fit under the memory limit
+    fn check_budget(&self, bytes: usize) -> Result<(), EngineError> {
+        if self.memory_limit > 0 && self.allocated_bytes.saturating_add(bytes) > self.memory_limit {
+            return Err(EngineError::MemoryLimit {
+                requested: bytes,
+                in_use: self.allocated_bytes,
+                limit: self.memory_limit,
+            });
+        }
+        Ok(())
+    }
+
+    /// Account for `bytes` about to be allocated, enforcing the memory limit
+    fn reserve_bytes(&mut self, bytes: usize) -> Result<(), EngineError> {
+        self.check_budget(bytes)?;
+        self.allocated_bytes = self.allocated_bytes.saturating_add(bytes);
+        Ok(())
+    }
+
+    fn alloc_tracked<T: Copy>(&mut self, data: &[T]) -> Result<(*mut T, usize), EngineError> {
+        let bytes = std::mem::size_of_val(data);
+        self.reserve_bytes(bytes)?;
+        alloc_copy(data).map(|ptr| (ptr, data.len())).inspect_err(|_| {
+            self.allocated_bytes -= bytes;
+        })
+    }
+
+    /// Allocate an uninitialized buffer of `len` values, accounted like a
+    /// series buffer
+    fn alloc_uninit_tracked<T>(&mut self, len: usize) -> Result<*mut T, EngineError> {
+        if len == 0 {
+            return Ok(std::ptr::NonNull::dangling().as_ptr());
+        }
+        let layout = std::alloc::Layout::array::<T>(len).map_err(|_| EngineError::Layout { len })?;
+        self.reserve_bytes(layout.size())?;
+        let raw = unsafe { std::alloc::alloc(layout) } as *mut T;
+        if raw.is_null() {
+            self.allocated_bytes -= layout.size();
+            return Err(EngineError::OutOfMemory { bytes: layout.size() });
+        }
+        Ok(raw)
+    }
+
+    fn free_tracked<T>(&mut self, ptr: *mut T, len: usize) {
+        if !ptr.is_null() {
+            self.allocated_bytes = self.allocated_bytes.saturating_sub(len * std::mem::size_of::<T>());
+        }
+        dealloc_buffer(ptr, len);
+    }
+
+    pub fn alloc_f64_buffer(&mut self, data: &[f64]) -> Result<(*mut f64, usize), EngineError> {
+        self.alloc_tracked(data)
+    }
+
+    pub fn free_f64_buffer(&mut self, ptr: *mut f64, len: usize) {
+        self.free_tracked(ptr, len);
+    }
+
+    pub fn alloc_i32_buffer(&mut self, data: &[i32]) -> Result<(*mut i32, usize), EngineError> {
+        self.alloc_tracked(data)
+    }
+
+    pub fn free_i32_buffer(&mut self, ptr: *mut i32, len: usize) {
+        self.free_tracked(ptr, len);
+    }
+
+    /// Allocate a fresh series id, recording it in the innermost open scope
+    /// and stamping its creation sequence and tag
+    fn next_id(&mut self) -> u32 {
+        let id = self.next_series_id;
+        self.next_series_id = self.next_series_id.wrapping_add(1);
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(id);
+        }
+        let meta = SeriesMeta { created: self.creation_seq, tag: self.creation_tag.clone(), ..SeriesMeta::default() };
+        self.creation_seq += 1;
+        self.meta.insert(id, meta);
+        id
+    }
+
+    /// Physical dtype name of a registered series
+    pub fn series_dtype(&self, series_id: u32) -> Option<&'static str> {
+        if self.series_store.contains_key(&series_id) || self.chunked_store.contains_key(&series_id) {
+            Some("float64")
+        } else if self.series_store_i32.contains_key(&series_id) {
+            Some("int32")
+        } else if self.decimal_scales.contains_key(&series_id) {
+            Some("decimal")
+        } else if self.series_store_i64.contains_key(&series_id) {
+            Some("int64")
+        } else if self.series_store_bool.contains_key(&series_id) {
+            Some("bool")
+        } else if self.series_store_u32.contains_key(&series_id) {
+            Some("uint32")
+        } else if self.series_store_u8.contains_key(&series_id) {
+            Some("uint8")
+        } else if self.series_store_str.contains_key(&series_id) {
+            Some("string")
+        } else if self.series_store_rle.contains_key(&series_id) {
+            Some("rle")
+        } else if self.series_store_interned.contains_key(&series_id) {
+            Some("interned")
+        } else {
+            None
+        }
+    }
+
+    /// Ids of all registered series (any dtype), in no particular order
+    pub fn series_ids(&self) -> Vec<u32> {
+        self.series_store
+            .keys()
+            .chain(self.series_store_i32.keys())
+            .chain(self.series_store_i64.keys())
+            .chain(self.series_store_bool.keys())
+            .chain(self.series_store_u32.keys())
+            .chain(self.series_store_u8.keys())
+            .chain(self.series_store_str.keys())
+            .chain(self.series_store_rle.keys())
+            .chain(self.series_store_interned.keys())
+            .chain(self.chunked_store.keys())
+            .copied()
+            .collect()
+    }
+
+    /// Bytes held by a series' values (0 if unknown)
+    pub fn series_nbytes(&self, series_id: u32) -> usize {
+        if let Some(strings) = self.series_store_str.get(&series_id) {
+            return strings.heap_bytes();
+        }
+        if let Some(rle) = self.series_store_rle.get(&series_id) {
+            return rle.heap_bytes();
+        }
+        let width = match self.series_dtype(series_id) {
+            Some("float64") | Some("int64") | Some("decimal") => 8,
+            Some("int32") | Some("uint32") | Some("interned") => 4,
+            Some("bool") | Some("uint8") => 1,
+            _ => 0,
+        };
+        self.series_len(series_id).unwrap_or(0) * width
+    }
+
+    /// Carry name and logical dtype (and optionally cached statistics) of
+    /// `source_id` over to a series derived from it
+    fn inherit_meta(&mut self, source_id: u32, id: u32, with_stats: bool) {
+        let source = match self.meta.get(&source_id) {
+            Some(meta) => meta.clone(),
+            None => return,
+        };
+        let meta = self.meta.entry(id).or_default();
+        meta.name = source.name;
+        meta.logical_dtype = source.logical_dtype;
+        if with_stats {
+            meta.stats = source.stats;
+            meta.monotonic = source.monotonic;
+        }
+    }
+
+    /// Length of a registered series (any dtype, contiguous or chunked)
+    pub fn series_len(&self, series_id: u32) -> Option<usize> {
+        if let Some((_, len)) = self.series_store.get(&series_id) {
+            Some(*len)
+        } else if let Some(chunks) = self.chunked_store.get(&series_id) {
+            Some(chunks.iter().map(|(_, len)| len).sum())
+        } else if let Some((_, len)) = self.series_store_i32.get(&series_id) {
+            Some(*len)
+        } else if let Some((_, len)) = self.series_store_i64.get(&series_id) {
+            Some(*len)
+        } else if let Some((_, len)) = self.series_store_bool.get(&series_id) {
+            Some(*len)
+        } else if let Some((_, len)) = self.series_store_u32.get(&series_id) {
+            Some(*len)
+        } else if let Some((_, len)) = self.series_store_u8.get(&series_id) {
+            Some(*len)
+        } else if let Some(strings) = self.series_store_str.get(&series_id) {
+            Some(strings.len())
+        } else if let Some(rle) = self.series_store_rle.get(&series_id) {
+            Some(rle.len())
+        } else {
+            self.series_store_interned.get(&series_id).map(|codes| codes.len())
+        }
+    }
+
+    /// Whether `series_id` is registered (any dtype)
+    pub fn has_series(&self, series_id: u32) -> bool {
+        self.series_dtype(series_id).is_some()
+    }
+
+    /// Values of an f64 series, contiguous or chunked, borrowed from the engine
+    pub fn f64_values(&self, series_id: u32) -> Option<F64Values<'_>> {
+        let buffers: &[(*mut f64, usize)] = match self.series_store.get(&series_id) {
+            Some(entry) => std::slice::from_ref(entry),
+            None => self.chunked_store.get(&series_id)?,
+        };
+        // The buffers live as long as the series, which `&self` keeps registered
+        Some(unsafe { F64Values::from_buffers(buffers) })
+    }
+
+    /// Number of references held on a series (0 if unknown)
+    pub fn refcount(&self, series_id: u32) -> u32 {
+        if !self.has_series(series_id) {
+            return 0;
+        }
+        self.refcounts.get(&series_id).copied().unwrap_or(1)
+    }
+
+    /// Add a reference to a series, returning the new count (0 if unknown)
+    pub fn retain_series(&mut self, series_id: u32) -> u32 {
+        let count = self.refcount(series_id);
+        if count == 0 {
+            return 0;
+        }
+        self.refcounts.insert(series_id, count + 1);
+        count + 1
+    }
+
+    /// Drop one reference to a series, unregistering it when none remain.
+    /// Returns the remaining count, or None if the id is unknown.
+    pub fn release_series(&mut self, series_id: u32) -> Option<u32> {
+        match self.refcount(series_id) {
+            0 => None,
+            1 => {
+                self.drop_series(series_id);
+                Some(0)
+            }
+            count => {
+                if count == 2 {
+                    self.refcounts.remove(&series_id);
+                } else {
+                    self.refcounts.insert(series_id, count - 1);
+                }
+                Some(count - 1)
+            }
+        }
+    }
+
+    /// Release a series of any dtype; false if the id is unknown
+    pub fn free_series(&mut self, series_id: u32) -> bool {
+        self.release_series(series_id).is_some()
+    }
+
+    /// Unregister a series regardless of its reference count, freeing its
+    /// buffer unless other series still share it
+    fn drop_series(&mut self, series_id: u32) -> bool {
+        self.pinned.remove(&series_id);
+        self.refcounts.remove(&series_id);
+        self.meta.remove(&series_id);
+        self.decimal_scales.remove(&series_id);
+        if let Some((ptr, len)) = self.series_store.remove(&series_id) {
+            self.release_buffer(series_id, ptr, len);
+            true
+        } else if let Some((ptr, len)) = self.series_store_i32.remove(&series_id) {
+            self.release_buffer(series_id, ptr, len);
+            true
+        } else if let Some((ptr, len)) = self.series_store_i64.remove(&series_id) {
+            self.release_buffer(series_id, ptr, len);
+            true
+        } else if let Some((ptr, len)) = self.series_store_bool.remove(&series_id) {
+            self.release_buffer(series_id, ptr, len);
+            true
+        } else if let Some((ptr, len)) = self.series_store_u32.remove(&series_id) {
+            self.release_buffer(series_id, ptr, len);
+            true
+        } else if let Some((ptr, len)) = self.series_store_u8.remove(&series_id) {
+            self.release_buffer(series_id, ptr, len);
+            true
+        } else if let Some(strings) = self.series_store_str.remove(&series_id) {
+            // Clones share the strings; the bytes are released with the last one
+            if Rc::strong_count(&strings) == 1 {
+                self.allocated_bytes = self.allocated_bytes.saturating_sub(strings.heap_bytes());
+            }
+            true
+        } else if let Some(rle) = self.series_store_rle.remove(&series_id) {
+            if Rc::strong_count(&rle) == 1 {
+                self.allocated_bytes = self.allocated_bytes.saturating_sub(rle.heap_bytes());
             }
-            raw
+            true
+        } else if let Some(codes) = self.series_store_interned.remove(&series_id) {
+            // Dictionary entries are kept for other series until engine_flush
+            if Rc::strong_count(&codes) == 1 {
+                self.allocated_bytes = self.allocated_bytes.saturating_sub(codes.len() * std::mem::size_of::<u32>());
+            }
+            true
+        } else if let Some(chunks) = self.chunked_store.remove(&series_id) {
+            for (ptr, len) in chunks {
+                self.free_f64_buffer(ptr, len);
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Drop a series' claim on its buffer, deallocating on the last claim
+    fn release_buffer<T>(&mut self, series_id: u32, ptr: *mut T, len: usize) {
+        let base = match self.buffer_bases.remove(&series_id) {
+            Some(base) => base,
+            None => return self.free_tracked(ptr, len),
         };
-        (ptr, len)
+        if let Some((alloc_len, users)) = self.shared_buffers.get_mut(&base) {
+            *users -= 1;
+            if *users == 0 {
+                let alloc_len = *alloc_len;
+                self.shared_buffers.remove(&base);
+                self.free_tracked(base as *mut T, alloc_len);
+            }
+        }
     }
 
-    pub fn free_f64_buffer(&mut self, ptr: *mut f64, len: usize) {
-        if !ptr.is_null() && len > 0 {
-            unsafe {
-                let layout = std::alloc::Layout::from_size_align(
-                    len * std::mem::size_of::<f64>(),
-                    std::mem::align_of::<f64>(),
-                )
-                .unwrap();
-                std::alloc::dealloc(ptr as *mut u8, layout);
-            }
+    /// Record that `new_id` references the buffer behind `source_id`
+    /// (allocated as `len` elements at `ptr` unless already shared)
+    fn share_buffer<T>(&mut self, source_id: u32, new_id: u32, ptr: *mut T, len: usize) {
+        let base = match self.buffer_bases.get(&source_id) {
+            Some(&base) => base,
+            None => {
+                let base = ptr as usize;
+                self.shared_buffers.insert(base, (len, 1));
+                self.buffer_bases.insert(source_id, base);
+                base
+            }
+        };
+        if let Some((_, users)) = self.shared_buffers.get_mut(&base) {
+            *users += 1;
+        }
+        self.buffer_bases.insert(new_id, base);
+    }
+
+    /// Register a new series sharing the buffer of `series_id` (no copy).
+    /// Returns None if the id is unknown.
+    pub fn clone_series(&mut self, series_id: u32) -> Option<u32> {
+        let id = if let Some(&(ptr, len)) = self.series_store.get(&series_id) {
+            let id = self.next_id();
+            self.share_buffer(series_id, id, ptr, len);
+            self.series_store.insert(id, (ptr, len));
+            id
+        } else if let Some(&(ptr, len)) = self.series_store_i32.get(&series_id) {
+            let id = self.next_id();
+            self.share_buffer(series_id, id, ptr, len);
+            self.series_store_i32.insert(id, (ptr, len));
+            id
+        } else if let Some(&(ptr, len)) = self.series_store_i64.get(&series_id) {
+            let id = self.next_id();
+            self.share_buffer(series_id, id, ptr, len);
+            self.series_store_i64.insert(id, (ptr, len));
+            if let Some(&scale) = self.decimal_scales.get(&series_id) {
+                self.decimal_scales.insert(id, scale);
+            }
+            id
+        } else if let Some(&(ptr, len)) = self.series_store_bool.get(&series_id) {
+            let id = self.next_id();
+            self.share_buffer(series_id, id, ptr, len);
+            self.series_store_bool.insert(id, (ptr, len));
+            id
+        } else if let Some(&(ptr, len)) = self.series_store_u32.get(&series_id) {
+            let id = self.next_id();
+            self.share_buffer(series_id, id, ptr, len);
+            self.series_store_u32.insert(id, (ptr, len));
+            id
+        } else if let Some(&(ptr, len)) = self.series_store_u8.get(&series_id) {
+            let id = self.next_id();
+            self.share_buffer(series_id, id, ptr, len);
+            self.series_store_u8.insert(id, (ptr, len));
+            id
+        } else if let Some(strings) = self.series_store_str.get(&series_id).cloned() {
+            let id = self.next_id();
+            self.series_store_str.insert(id, strings);
+            id
+        } else if let Some(rle) = self.series_store_rle.get(&series_id).cloned() {
+            let id = self.next_id();
+            self.series_store_rle.insert(id, rle);
+            id
+        } else if let Some(codes) = self.series_store_interned.get(&series_id).cloned() {
+            let id = self.next_id();
+            self.series_store_interned.insert(id, codes);
+            id
+        } else {
+            return None;
+        };
+        self.inherit_meta(series_id, id, true);
+        Some(id)
+    }
+
+    /// Register a view of `len` values starting at `offset` in the f64 series
+    /// `series_id`, sharing its buffer. The range is clamped to the series.
+    /// Returns None if the id is unknown.
+    pub fn view_series_f64(&mut self, series_id: u32, offset: usize, len: usize) -> Option<u32> {
+        let (ptr, total) = *self.series_store.get(&series_id)?;
+        let offset = offset.min(total);
+        let len = len.min(total - offset);
+        let id = self.next_id();
+        self.share_buffer(series_id, id, ptr, total);
+        // In bounds of the allocation (or one past its end for empty views)
+        let view_ptr = unsafe { ptr.add(offset) };
+        self.series_store.insert(id, (view_ptr, len));
+        // Views keep name and logical dtype; statistics cover other values
+        self.inherit_meta(series_id, id, false);
+        Some(id)
+    }
+
+    /// Give an f64 series a private copy of its buffer if the buffer is
+    /// shared, so it can be mutated without affecting clones or views. The
+    /// series' cached statistics are dropped.
+    /// Returns the (possibly new) pointer and length, or None if unknown.
+    pub fn make_mut_f64(&mut self, series_id: u32) -> Result<Option<(*mut f64, usize)>, EngineError> {
+        let (ptr, len) = match self.series_store.get(&series_id) {
+            Some(&entry) => entry,
+            None => return Ok(None),
+        };
+        self.invalidate_stats(series_id);
+        if !self.is_shared(series_id) {
+            return Ok(Some((ptr, len)));
+        }
+        let data = unsafe { std::slice::from_raw_parts(ptr, len) };
+        let (private, _) = self.alloc_f64_buffer(data)?;
+        self.release_buffer(series_id, ptr, len);
+        self.series_store.insert(series_id, (private, len));
+        engine_log!(debug, "copy-on-write series_id={} len={}", series_id, len);
+        Ok(Some((private, len)))
+    }
+
+    /// Drop cached statistics after the values of a series changed
+    pub fn invalidate_stats(&mut self, series_id: u32) {
+        if let Some(meta) = self.meta.get_mut(&series_id) {
+            meta.stats = None;
+            meta.monotonic = None;
+        }
+    }
+
+    /// Allocate a buffer for each of `columns`, all or nothing: on failure
+    /// the buffers allocated so far are freed again
+    fn alloc_f64_buffers(&mut self, columns: &[Vec<f64>]) -> Result<Vec<(*mut f64, usize)>, EngineError> {
+        let mut buffers = Vec::with_capacity(columns.len());
+        for column in columns {
+            match self.alloc_f64_buffer(column) {
+                Ok(buffer) => buffers.push(buffer),
+                Err(e) => {
+                    for (ptr, len) in buffers {
+                        self.free_f64_buffer(ptr, len);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Ok(buffers)
+    }
+
+    /// Make a buffer from `alloc_f64_buffer` the values of an f64 series,
+    /// releasing the previous buffer (or chunks) unless clones or views
+    /// still share it. With `append`, a chunked series keeps its chunks and
+    /// gains the buffer as its last one.
+    fn install_f64_buffer(&mut self, series_id: u32, buffer: (*mut f64, usize), append: bool) {
+        self.invalidate_stats(series_id);
+        if let Some(&(ptr, len)) = self.series_store.get(&series_id) {
+            self.release_buffer(series_id, ptr, len);
+            self.series_store.insert(series_id, buffer);
+        } else if append {
+            self.chunked_store.entry(series_id).or_default().push(buffer);
+        } else {
+            for (ptr, len) in self.chunked_store.insert(series_id, vec![buffer]).unwrap_or_default() {
+                self.free_f64_buffer(ptr, len);
+            }
+        }
+    }
+
+    /// Common length of several distinct f64 series (contiguous or
+    /// chunked); None if an id is unknown or repeated, or the lengths differ
+    fn aligned_f64_len(&self, series_ids: &[u32]) -> Option<usize> {
+        let mut len = None;
+        for (i, &id) in series_ids.iter().enumerate() {
+            let n = self.f64_values(id)?.len();
+            if series_ids[..i].contains(&id) || len.is_some_and(|len| len != n) {
+                return None;
+            }
+            len = Some(n);
+        }
+        len
+    }
+
+    /// Whether the series include some but not all columns of a frame, so
+    /// that changing their length would misalign the frame's columns
+    fn splits_frame(&self, series_ids: &[u32]) -> bool {
+        self.frames.values().any(|frame| {
+            let listed = frame.columns.iter().filter(|id| series_ids.contains(id)).count();
+            listed > 0 && listed < frame.columns.len()
+        })
+    }
+
+    /// Monotonicity of a float64, int32 or int64 (including decimal)
+    /// series, computed on first use and cached. None for other dtypes and
+    /// unknown ids.
+    pub fn series_monotonic(&mut self, series_id: u32) -> Option<Monotonic> {
+        if let Some(monotonic) = self.meta.get(&series_id).and_then(|m| m.monotonic) {
+            return Some(monotonic);
+        }
+        let monotonic = if let Some(values) = self.f64_values(series_id) {
+            compute_monotonic(values.iter(), |v: &f64| v.is_nan())
+        } else if let Some(&(ptr, len)) = self.series_store_i32.get(&series_id) {
+            compute_monotonic(unsafe { std::slice::from_raw_parts(ptr, len) }.iter().copied(), |v: &i32| *v == i32::MIN)
+        } else {
+            let &(ptr, len) = self.series_store_i64.get(&series_id)?;
+            compute_monotonic(unsafe { std::slice::from_raw_parts(ptr, len) }.iter().copied(), |v: &i64| *v == i64::MIN)
+        };
+        self.meta.entry(series_id).or_default().monotonic = Some(monotonic);
+        Some(monotonic)
+    }
+
+    /// Null count and min/max of a series (NaN / i32::MIN / i64::MIN are null;
+    /// decimal min/max are in real units; strings have no min/max),
+    /// computed on first use and cached. None if the id is unknown.
+    pub fn series_stats(&mut self, series_id: u32) -> Option<SeriesStats> {
+        if let Some(stats) = self.meta.get(&series_id).and_then(|m| m.stats) {
+            return Some(stats);
+        }
+        let stats = if let Some(values) = self.f64_values(series_id) {
+            compute_stats(values.iter(), |v: &f64| v.is_nan())
+        } else if let Some(&(ptr, len)) = self.series_store_i32.get(&series_id) {
+            compute_stats(unsafe { std::slice::from_raw_parts(ptr, len) }.iter().copied(), |v: &i32| *v == i32::MIN)
+        } else if let Some(&(ptr, len)) = self.series_store_i64.get(&series_id) {
+            let values = unsafe { std::slice::from_raw_parts(ptr, len) };
+            let unit = 10f64.powi(self.decimal_scales.get(&series_id).copied().unwrap_or(0) as i32);
+            compute_stats(values.iter().map(|&v| if v == i64::MIN { f64::NAN } else { v as f64 / unit }), |v: &f64| v.is_nan())
+        } else if let Some(&(ptr, len)) = self.series_store_bool.get(&series_id) {
+            compute_stats(unsafe { std::slice::from_raw_parts(ptr, len) }.iter().copied(), |_: &u8| false)
+        } else if let Some(&(ptr, len)) = self.series_store_u32.get(&series_id) {
+            compute_stats(unsafe { std::slice::from_raw_parts(ptr, len) }.iter().copied(), |_: &u32| false)
+        } else if let Some(&(ptr, len)) = self.series_store_u8.get(&series_id) {
+            compute_stats(unsafe { std::slice::from_raw_parts(ptr, len) }.iter().copied(), |_: &u8| false)
+        } else if let Some(rle) = self.series_store_rle.get(&series_id) {
+            let mut stats = compute_stats(rle.values.iter().copied(), |v: &f64| v.is_nan());
+            stats.null_count = rle.runs().filter(|run| run.2.is_nan()).map(|(start, end, _)| end - start).sum();
+            stats
+        } else if let Some(codes) = self.series_store_interned.get(&series_id) {
+            let null_count = codes.iter().filter(|&&code| code == u32::MAX).count();
+            SeriesStats { null_count, min: f64::NAN, max: f64::NAN }
+        } else {
+            let strings = self.series_store_str.get(&series_id)?;
+            let null_count = (0..strings.len()).filter(|&row| strings.is_null(row)).count();
+            SeriesStats { null_count, min: f64::NAN, max: f64::NAN }
+        };
+        self.meta.entry(series_id).or_default().stats = Some(stats);
+        Some(stats)
+    }
+
+    /// Whether the buffer behind `series_id` is referenced by other series
+    pub fn is_shared(&self, series_id: u32) -> bool {
+        if let Some(strings) = self.series_store_str.get(&series_id) {
+            return Rc::strong_count(strings) > 1;
+        }
+        if let Some(rle) = self.series_store_rle.get(&series_id) {
+            return Rc::strong_count(rle) > 1;
+        }
+        if let Some(codes) = self.series_store_interned.get(&series_id) {
+            return Rc::strong_count(codes) > 1;
+        }
+        self.buffer_bases
+            .get(&series_id)
+            .and_then(|base| self.shared_buffers.get(base))
+            .is_some_and(|&(_, users)| users > 1)
+    }
+
+    /// Copy `data` into a new f64 buffer and register it under a fresh id
+    pub fn try_register_series_f64(&mut self, data: &[f64]) -> Result<u32, EngineError> {
+        let (ptr, len) = self.alloc_f64_buffer(data)?;
+        let id = self.next_id();
+        self.series_store.insert(id, (ptr, len));
+        Ok(id)
+    }
+
+    /// Copy `data` into a new i32 buffer and register it under a fresh id
+    pub fn try_register_series_i32(&mut self, data: &[i32]) -> Result<u32, EngineError> {
+        let (ptr, len) = self.alloc_i32_buffer(data)?;
+        let id = self.next_id();
+        self.series_store_i32.insert(id, (ptr, len));
+        Ok(id)
+    }
+
+    /// Copy `data` into a new i64 buffer and register it under a fresh id
+    pub fn try_register_series_i64(&mut self, data: &[i64]) -> Result<u32, EngineError> {
+        let (ptr, len) = self.alloc_tracked(data)?;
+        let id = self.next_id();
+        self.series_store_i64.insert(id, (ptr, len));
+        Ok(id)
+    }
+
+    /// Copy `data` (0/1 values) into a new boolean buffer and register it
+    pub fn try_register_series_bool(&mut self, data: &[u8]) -> Result<u32, EngineError> {
+        let (ptr, len) = self.alloc_tracked(data)?;
+        let id = self.next_id();
+        self.series_store_bool.insert(id, (ptr, len));
+        Ok(id)
+    }
+
+    pub fn try_register_series_u32(&mut self, data: &[u32]) -> Result<u32, EngineError> {
+        let (ptr, len) = self.alloc_tracked(data)?;
+        let id = self.next_id();
+        self.series_store_u32.insert(id, (ptr, len));
+        Ok(id)
+    }
+
+    pub fn try_register_series_u8(&mut self, data: &[u8]) -> Result<u32, EngineError> {
+        let (ptr, len) = self.alloc_tracked(data)?;
+        let id = self.next_id();
+        self.series_store_u8.insert(id, (ptr, len));
+        Ok(id)
+    }
+
+    /// Register a string series under a fresh id
+    pub fn try_register_series_str(&mut self, strings: StrSeries) -> Result<u32, EngineError> {
+        self.reserve_bytes(strings.heap_bytes())?;
+        let id = self.next_id();
+        self.series_store_str.insert(id, Rc::new(strings));
+        Ok(id)
+    }
+
+    /// Register a run-length encoded series under a fresh id
+    pub fn try_register_series_rle(&mut self, rle: RleSeries) -> Result<u32, EngineError> {
+        self.reserve_bytes(rle.heap_bytes())?;
+        let id = self.next_id();
+        self.series_store_rle.insert(id, Rc::new(rle));
+        Ok(id)
+    }
+
+    /// Code of `text` in the engine-wide dictionary, adding it if new
+    pub fn intern(&mut self, text: &str) -> Result<u32, EngineError> {
+        if let Some(code) = self.interner.lookup(text) {
+            return Ok(code);
+        }
+        self.reserve_bytes(text.len())?;
+        Ok(self.interner.insert(text))
+    }
+
+    /// Register an interned string series from dictionary codes
+    /// (u32::MAX is null)
+    pub fn try_register_series_interned(&mut self, codes: Vec<u32>) -> Result<u32, EngineError> {
+        self.reserve_bytes(codes.len() * std::mem::size_of::<u32>())?;
+        let id = self.next_id();
+        self.series_store_interned.insert(id, Rc::new(codes));
+        Ok(id)
+    }
+
+    /// Register a buffer from `engine_alloc_uninit_f64` as a series, taking
+    /// ownership without copying. None if `ptr` is not a pending buffer of
+    /// exactly `len` values.
+    pub fn adopt_buffer_f64(&mut self, ptr: usize, len: usize) -> Option<u32> {
+        if len > 0 {
+            if self.pending_buffers.get(&ptr) != Some(&len) {
+                return None;
+            }
+            self.pending_buffers.remove(&ptr);
+        }
+        let ptr = if len == 0 { std::ptr::NonNull::dangling().as_ptr() } else { ptr as *mut f64 };
+        let id = self.next_id();
+        self.series_store.insert(id, (ptr, len));
+        Some(id)
+    }
+
+    /// Like `try_register_series_f64`, returning u32::MAX (and recording the
+    /// error) on failure
+    pub fn register_series_f64(&mut self, data: &[f64]) -> u32 {
+        self.try_register_series_f64(data).unwrap_or_else(|e| {
+            set_last_error(e);
+            u32::MAX
+        })
+    }
+
+    /// Like `try_register_series_i32`, returning u32::MAX (and recording the
+    /// error) on failure
+    pub fn register_series_i32(&mut self, data: &[i32]) -> u32 {
+        self.try_register_series_i32(data).unwrap_or_else(|e| {
+            set_last_error(e);
+            u32::MAX
+        })
+    }
+
+    pub fn register_series_i64(&mut self, data: &[i64]) -> u32 {
+        self.try_register_series_i64(data).unwrap_or_else(|e| {
+            set_last_error(e);
+            u32::MAX
+        })
+    }
+
+    pub fn register_series_bool(&mut self, data: &[u8]) -> u32 {
+        self.try_register_series_bool(data).unwrap_or_else(|e| {
+            set_last_error(e);
+            u32::MAX
+        })
+    }
+
+    pub fn register_series_u32(&mut self, data: &[u32]) -> u32 {
+        self.try_register_series_u32(data).unwrap_or_else(|e| {
+            set_last_error(e);
+            u32::MAX
+        })
+    }
+
+    pub fn register_series_u8(&mut self, data: &[u8]) -> u32 {
+        self.try_register_series_u8(data).unwrap_or_else(|e| {
+            set_last_error(e);
+            u32::MAX
+        })
+    }
+
+    pub fn register_series_str(&mut self, strings: StrSeries) -> u32 {
+        self.try_register_series_str(strings).unwrap_or_else(|e| {
+            set_last_error(e);
+            u32::MAX
+        })
+    }
+
+    pub fn register_series_rle(&mut self, rle: RleSeries) -> u32 {
+        self.try_register_series_rle(rle).unwrap_or_else(|e| {
+            set_last_error(e);
+            u32::MAX
+        })
+    }
+}
+
+thread_local! {
+    pub static ENGINE: RefCell<EngineState> = RefCell::new({
+        install_panic_hook();
+        EngineState::default()
+    });
+}
+
+// Basic series creation and management functions
+#[wasm_bindgen]
+pub fn engine_create_series_f64(data: &[f64]) -> u32 {
+    let _prof = profile("engine_create_series_f64", || data.len() * 8);
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(data))
+}
+
+#[wasm_bindgen]
+pub fn engine_create_series_i32(data: &[i32]) -> u32 {
+    let _prof = profile("engine_create_series_i32", || data.len() * 4);
+    ENGINE.with(|cell| cell.borrow_mut().register_series_i32(data))
+}
+
+/// Create an int64 series (i64::MIN is null)
+#[wasm_bindgen]
+pub fn engine_create_series_i64(data: &[i64]) -> u32 {
+    let _prof = profile("engine_create_series_i64", || data.len() * 8);
+    ENGINE.with(|cell| cell.borrow_mut().register_series_i64(data))
+}
+
+/// Create a boolean series from 0/1 bytes (any non-zero byte is true)
+#[wasm_bindgen]
+pub fn engine_create_series_bool(data: &[u8]) -> u32 {
+    let _prof = profile("engine_create_series_bool", || data.len());
+    let normalized: Vec<u8> = data.iter().map(|&b| (b != 0) as u8).collect();
+    ENGINE.with(|cell| cell.borrow_mut().register_series_bool(&normalized))
+}
+
+/// Create a uint32 series (ids, row numbers; no null value)
+#[wasm_bindgen]
+pub fn engine_create_series_u32(data: &[u32]) -> u32 {
+    let _prof = profile("engine_create_series_u32", || data.len() * 4);
+    ENGINE.with(|cell| cell.borrow_mut().register_series_u32(data))
+}
+
+/// Create a uint8 series (small codes, masks; no null value)
+#[wasm_bindgen]
+pub fn engine_create_series_u8(data: &[u8]) -> u32 {
+    let _prof = profile("engine_create_series_u8", || data.len());
+    ENGINE.with(|cell| cell.borrow_mut().register_series_u8(data))
+}
+
+/// Create a string series (no nulls; cast or build with nulls from the engine)
+#[wasm_bindgen]
+pub fn engine_create_series_str(values: Vec<String>) -> u32 {
+    let _prof = profile("engine_create_series_str", || values.iter().map(|v| v.len()).sum());
+    let strings: StrSeries = values.iter().map(|v| Some(v.as_str())).collect();
+    ENGINE.with(|cell| cell.borrow_mut().register_series_str(strings))
+}
+
+/// Create a string series from the packed binary protocol (`bytes` plus
+/// `n + 1` offsets, see `isin_string_packed`), avoiding per-string
+/// marshalling. `null_mask` is empty (no nulls) or one byte per string,
+/// non-zero for null. Returns u32::MAX if the buffers are malformed.
+#[wasm_bindgen]
+pub fn engine_create_series_str_packed(bytes: &[u8], offsets: &[u32], null_mask: &[u8]) -> u32 {
+    let _prof = profile("engine_create_series_str_packed", || bytes.len());
+    let strings = match packed_strs(bytes, offsets) {
+        Some(strings) if null_mask.is_empty() || null_mask.len() == strings.len() => strings,
+        _ => {
+            engine_log!(warn, "engine_create_series_str_packed: malformed buffers bytes={} offsets={} null_mask={}", bytes.len(), offsets.len(), null_mask.len());
+            return u32::MAX;
+        }
+    };
+    let strings: StrSeries = strings
+        .iter()
+        .enumerate()
+        .map(|(i, &text)| if null_mask.get(i).is_some_and(|&null| null != 0) { None } else { Some(text) })
+        .collect();
+    ENGINE.with(|cell| cell.borrow_mut().register_series_str(strings))
+}
+
+/// Create a float64 series of `len` copies of `fill_value` (NaN for an
+/// all-null column), as numpy's `full`. Returns u32::MAX if `len` values do
+/// not fit in memory or under the memory limit.
+#[wasm_bindgen]
+pub fn engine_full_f64(len: usize, fill_value: f64) -> u32 {
+    let _prof = profile("engine_full_f64", || len.saturating_mul(8));
+    generate_f64(len, |_| fill_value)
+}
+
+/// Create a float64 series `start, start + step, ...` up to but excluding
+/// `stop`, as numpy's `arange` (`ceil((stop - start) / step)` values, none
+/// if `stop` is not reached in the step's direction). Returns u32::MAX if
+/// an argument is not finite, `step` is 0, there would be more than
+/// u32::MAX values or they do not fit in memory or under the memory limit.
+#[wasm_bindgen]
+pub fn engine_arange_f64(start: f64, stop: f64, step: f64) -> u32 {
+    let n = ((stop - start) / step).ceil();
+    if !(start.is_finite() && stop.is_finite() && step.is_finite()) || step == 0.0 || n > u32::MAX as f64 {
+        engine_log!(warn, "engine_arange_f64: invalid range start={} stop={} step={}", start, stop, step);
+        return u32::MAX;
+    }
+    let n = n.max(0.0) as usize;
+    let _prof = profile("engine_arange_f64", || n.saturating_mul(8));
+    generate_f64(n, |i| start + i as f64 * step)
+}
+
+/// Create a float64 series of `n` evenly spaced values from `start` to
+/// `stop` inclusive, as numpy's `linspace` (just `start` when `n` is 1).
+/// Returns u32::MAX if `start` or `stop` is not finite, or `n` values do not
+/// fit in memory or under the memory limit.
+#[wasm_bindgen]
+pub fn engine_linspace_f64(start: f64, stop: f64, n: usize) -> u32 {
+    if !(start.is_finite() && stop.is_finite()) {
+        return u32::MAX;
+    }
+    let _prof = profile("engine_linspace_f64", || n.saturating_mul(8));
+    let step = if n > 1 { (stop - start) / (n - 1) as f64 } else { 0.0 };
+    // Hit the endpoint exactly despite rounding in the steps
+    generate_f64(n, |i| if n > 1 && i == n - 1 { stop } else { start + i as f64 * step })
+}
+
+/// Register a float64 series of `f(0), f(1), ..., f(len - 1)`, failing with
+/// u32::MAX (and a recorded error) before allocating if it would not fit
+fn generate_f64(len: usize, f: impl Fn(usize) -> f64) -> u32 {
+    let mut values: Vec<f64> = match try_vec(len) {
+        Ok(values) => values,
+        Err(e) => {
+            set_last_error(e);
+            return u32::MAX;
+        }
+    };
+    values.extend((0..len).map(f));
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&values))
+}
+
+/// Allocate an uninitialized buffer for `len` float64 values in WASM memory,
+/// for the host to fill in place (e.g. `Float64Array.set` or a file reader)
+/// before registering it with `engine_adopt_buffer_f64`, avoiding the copy
+/// made by `engine_create_series_f64`. The buffer counts toward the memory
+/// limit. Returns its address, or 0 on failure (see `engine_last_error_code`).
+#[wasm_bindgen]
+pub fn engine_alloc_uninit_f64(len: usize) -> usize {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        match eng.alloc_uninit_tracked::<f64>(len) {
+            Ok(ptr) => {
+                if len > 0 {
+                    eng.pending_buffers.insert(ptr as usize, len);
+                }
+                ptr as usize
+            }
+            Err(e) => {
+                set_last_error(e);
+                0
+            }
+        }
+    })
+}
+
+/// Register a filled buffer from `engine_alloc_uninit_f64` as a float64
+/// series without copying; the engine takes ownership. `len` must match the
+/// allocation. Returns the series id, or u32::MAX if `ptr` is not a pending buffer.
+#[wasm_bindgen]
+pub fn engine_adopt_buffer_f64(ptr: usize, len: usize) -> u32 {
+    let _prof = profile("engine_adopt_buffer_f64", || len.saturating_mul(8));
+    ENGINE.with(|cell| cell.borrow_mut().adopt_buffer_f64(ptr, len).unwrap_or(u32::MAX))
+}
+
+/// Release a buffer from `engine_alloc_uninit_f64` that will not be adopted.
+/// Returns false if `ptr` is not a pending buffer.
+#[wasm_bindgen]
+pub fn engine_free_uninit_f64(ptr: usize) -> bool {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        match eng.pending_buffers.remove(&ptr) {
+            Some(len) => {
+                eng.free_tracked(ptr as *mut f64, len);
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+// Chunked float64 series: a column stored as a sequence of buffers, for
+// data too large for one contiguous allocation or arriving in pieces.
+// Reductions, filters and groupby accept chunked ids like any f64 series;
+// `engine_series_ptr_f64` and `engine_series_len_f64` return 0 for them
+// since there is no single buffer (`engine_chunked_len_f64` gives the rows).
+
+/// Register an empty chunked float64 series
+#[wasm_bindgen]
+pub fn engine_chunked_create_f64() -> u32 {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        let id = eng.next_id();
+        eng.chunked_store.insert(id, Vec::new());
+        id
+    })
+}
+
+/// Append a copy of `data` as a new chunk. Returns false if the series is
+/// not a chunked series, is a column of a frame with other columns (use
+/// `engine_append_rows` to grow all of them) or allocation fails.
+#[wasm_bindgen]
+pub fn engine_chunked_append_f64(series_id: u32, data: &[f64]) -> bool {
+    let _prof = profile("engine_chunked_append_f64", || data.len() * 8);
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        if !eng.chunked_store.contains_key(&series_id) {
+            return false;
+        }
+        if !data.is_empty() && eng.splits_frame(&[series_id]) {
+            engine_log!(warn, "engine_chunked_append_f64: series is one of several columns of a frame series_id={}", series_id);
+            return false;
+        }
+        match eng.alloc_f64_buffer(data) {
+            Ok(buffer) => {
+                eng.invalidate_stats(series_id);
+                if let Some(chunks) = eng.chunked_store.get_mut(&series_id) {
+                    chunks.push(buffer);
+                }
+                true
+            }
+            Err(e) => {
+                set_last_error(e);
+                false
+            }
+        }
+    })
+}
+
+/// Append a filled buffer from `engine_alloc_uninit_f64` as a new chunk
+/// without copying. Returns false if the series is not chunked, is a column
+/// of a frame with other columns (as in `engine_chunked_append_f64`) or
+/// `ptr` is not a pending buffer of `len` values; the buffer stays pending
+/// then.
+#[wasm_bindgen]
+pub fn engine_chunked_append_buffer_f64(series_id: u32, ptr: usize, len: usize) -> bool {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        if !eng.chunked_store.contains_key(&series_id) || (len > 0 && eng.pending_buffers.get(&ptr) != Some(&len)) {
+            return false;
+        }
+        if len == 0 {
+            return true;
+        }
+        if eng.splits_frame(&[series_id]) {
+            engine_log!(warn, "engine_chunked_append_buffer_f64: series is one of several columns of a frame series_id={}", series_id);
+            return false;
+        }
+        eng.pending_buffers.remove(&ptr);
+        eng.invalidate_stats(series_id);
+        if let Some(chunks) = eng.chunked_store.get_mut(&series_id) {
+            chunks.push((ptr as *mut f64, len));
+        }
+        true
+    })
+}
+
+/// Number of chunks of a chunked series (1 for a contiguous f64 series, 0 if unknown)
+#[wasm_bindgen]
+pub fn engine_chunked_num_chunks(series_id: u32) -> usize {
+    ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        match eng.chunked_store.get(&series_id) {
+            Some(chunks) => chunks.len(),
+            None => eng.series_store.contains_key(&series_id) as usize,
+        }
+    })
+}
+
+/// Total rows of a chunked series (the length of a contiguous f64 series, 0 if unknown)
+#[wasm_bindgen]
+pub fn engine_chunked_len_f64(series_id: u32) -> usize {
+    ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        match eng.chunked_store.get(&series_id) {
+            Some(chunks) => chunks.iter().map(|&(_, len)| len).sum(),
+            None => eng.series_store.get(&series_id).map_or(0, |&(_, len)| len),
+        }
+    })
+}
+
+/// Copy a chunked (or contiguous) f64 series into a new contiguous series.
+/// Returns u32::MAX if the id is unknown or allocation fails.
+#[wasm_bindgen]
+pub fn engine_chunked_rechunk_f64(series_id: u32) -> u32 {
+    let _prof = profile("engine_chunked_rechunk_f64", || series_bytes(series_id));
+    match unsafe { f64_values(series_id) } {
+        Some(values) => ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&values.to_vec())),
+        None => u32::MAX,
+    }
+}
+
+// Row edits across the f64 columns of a frame. Each call updates every
+// listed series or, on a length mismatch or allocation failure, none. A
+// series held by a frame can only be edited together with all the other
+// columns of that frame.
+
+/// Append rows to several f64 series of the same length at once, e.g. for
+/// a live-updating frame: `values_flat` holds whole rows, row-major, one
+/// value per series. Contiguous series are copied into a larger buffer
+/// (clones and views keep the old values); chunked series gain a chunk.
+/// Returns false if an id is unknown or repeated, the lengths differ, the
+/// ids cover only part of a frame's columns, `values_flat` is not a whole
+/// number of rows or allocation fails.
+#[wasm_bindgen]
+pub fn engine_append_rows(series_ids: &[u32], values_flat: &[f64]) -> bool {
+    let _prof = profile("engine_append_rows", || values_flat.len() * 8);
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        let ncols = series_ids.len();
+        if eng.aligned_f64_len(series_ids).is_none() || ncols == 0 || !values_flat.len().is_multiple_of(ncols) {
+            engine_log!(warn, "engine_append_rows: unknown series, length mismatch or partial row ({} values, {} series)", values_flat.len(), ncols);
+            return false;
+        }
+        if eng.splits_frame(series_ids) {
+            engine_log!(warn, "engine_append_rows: series are only some of the columns of a frame");
+            return false;
+        }
+        let columns: Vec<Vec<f64>> = series_ids
+            .iter()
+            .enumerate()
+            .map(|(c, &id)| {
+                let mut column = match eng.chunked_store.contains_key(&id) {
+                    true => Vec::new(),
+                    false => eng.f64_values(id).map_or_else(Vec::new, |values| values.to_vec()),
+                };
+                column.extend(values_flat.iter().skip(c).step_by(ncols));
+                column
+            })
+            .collect();
+        match eng.alloc_f64_buffers(&columns) {
+            Ok(buffers) => {
+                for (&id, buffer) in series_ids.iter().zip(buffers) {
+                    eng.install_f64_buffer(id, buffer, true);
+                }
+                true
+            }
+            Err(e) => {
+                set_last_error(e);
+                false
+            }
+        }
+    })
+}
+
+/// Remove rows from several f64 series of the same length at once, e.g.
+/// for interactive row deletion; `indices` may be in any order and repeat.
+/// Each series gets a new compacted buffer (clones and views keep the old
+/// values; a chunked series ends up with one chunk). Returns false if an id
+/// is unknown or repeated, the lengths differ, the ids cover only part of
+/// a frame's columns, a row is out of range or allocation fails.
+#[wasm_bindgen]
+pub fn engine_delete_rows(series_ids: &[u32], indices: &[u32]) -> bool {
+    let _prof = profile("engine_delete_rows", || series_ids.iter().map(|&id| series_bytes(id)).sum());
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        let len = match eng.aligned_f64_len(series_ids) {
+            Some(len) if indices.iter().all(|&row| (row as usize) < len) => len,
+            _ => {
+                engine_log!(warn, "engine_delete_rows: unknown series, length mismatch or row out of range");
+                return false;
+            }
+        };
+        if eng.splits_frame(series_ids) {
+            engine_log!(warn, "engine_delete_rows: series are only some of the columns of a frame");
+            return false;
+        }
+        let mut keep = vec![true; len];
+        for &row in indices {
+            keep[row as usize] = false;
+        }
+        let columns: Vec<Vec<f64>> = series_ids
+            .iter()
+            .map(|&id| match eng.f64_values(id) {
+                Some(values) => values.iter().zip(&keep).filter(|(_, &k)| k).map(|(v, _)| v).collect(),
+                None => Vec::new(),
+            })
+            .collect();
+        match eng.alloc_f64_buffers(&columns) {
+            Ok(buffers) => {
+                for (&id, buffer) in series_ids.iter().zip(buffers) {
+                    eng.install_f64_buffer(id, buffer, false);
+                }
+                true
+            }
+            Err(e) => {
+                set_last_error(e);
+                false
+            }
+        }
+    })
+}
+
+/// Release a float64 series. A series retained with `engine_series_retain`
+/// stays registered until every reference has been released.
+#[wasm_bindgen]
+pub fn engine_free_series(series_id: u32) {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        if eng.series_store.contains_key(&series_id) || eng.chunked_store.contains_key(&series_id) {
+            eng.release_series(series_id);
+        }
+    })
+}
+
+/// Release an int32 series (see `engine_free_series`)
+#[wasm_bindgen]
+pub fn engine_free_series_i32(series_id: u32) {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        if eng.series_store_i32.contains_key(&series_id) {
+            eng.release_series(series_id);
+        }
+    })
+}
+
+/// Release an int64 series (see `engine_free_series`)
+#[wasm_bindgen]
+pub fn engine_free_series_i64(series_id: u32) {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        if eng.series_store_i64.contains_key(&series_id) {
+            eng.release_series(series_id);
+        }
+    })
+}
+
+/// Release a boolean series (see `engine_free_series`)
+#[wasm_bindgen]
+pub fn engine_free_series_bool(series_id: u32) {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        if eng.series_store_bool.contains_key(&series_id) {
+            eng.release_series(series_id);
+        }
+    })
+}
+
+/// Release a uint32 series (see `engine_free_series`)
+#[wasm_bindgen]
+pub fn engine_free_series_u32(series_id: u32) {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        if eng.series_store_u32.contains_key(&series_id) {
+            eng.release_series(series_id);
+        }
+    })
+}
+
+/// Release a uint8 series (see `engine_free_series`)
+#[wasm_bindgen]
+pub fn engine_free_series_u8(series_id: u32) {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        if eng.series_store_u8.contains_key(&series_id) {
+            eng.release_series(series_id);
+        }
+    })
+}
+
+/// Release a string series (see `engine_free_series`)
+#[wasm_bindgen]
+pub fn engine_free_series_str(series_id: u32) {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        if eng.series_store_str.contains_key(&series_id) {
+            eng.release_series(series_id);
         }
-    }
+    })
+}
 
-    pub fn alloc_i32_buffer(&mut self, data: &[i32]) -> (*mut i32, usize) {
-        let len = data.len();
-        let ptr = unsafe {
-            let layout = std::alloc::Layout::from_size_align(
-                len * std::mem::size_of::<i32>(),
-                std::mem::align_of::<i32>(),
-            )
-            .unwrap();
-            let raw = std::alloc::alloc(layout) as *mut i32;
-            if !raw.is_null() {
-                std::ptr::copy_nonoverlapping(data.as_ptr(), raw, len);
-            }
-            raw
-        };
-        (ptr, len)
-    }
+/// Release a run-length encoded series (see `engine_free_series`)
+#[wasm_bindgen]
+pub fn engine_free_series_rle(series_id: u32) {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        if eng.series_store_rle.contains_key(&series_id) {
+            eng.release_series(series_id);
+        }
+    })
+}
 
-    pub fn free_i32_buffer(&mut self, ptr: *mut i32, len: usize) {
-        if !ptr.is_null() && len > 0 {
-            unsafe {
-                let layout = std::alloc::Layout::from_size_align(
-                    len * std::mem::size_of::<i32>(),
-                    std::mem::align_of::<i32>(),
-                )
-                .unwrap();
-                std::alloc::dealloc(ptr as *mut u8, layout);
-            }
+#[wasm_bindgen]
+pub fn engine_flush() {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        // Collect ids first to avoid borrowing the maps while freeing
+        let ids: Vec<u32> = eng.series_ids();
+        for id in ids {
+            eng.drop_series(id);
         }
-    }
+        eng.next_series_id = 0;
+        eng.scopes.clear();
+        eng.pinned.clear();
+        for (ptr, len) in std::mem::take(&mut eng.pending_buffers) {
+            eng.free_tracked(ptr as *mut f64, len);
+        }
+        eng.meta.clear();
+        eng.frames.clear();
+        eng.next_frame_id = 0;
+        let dictionary_bytes = eng.interner.bytes();
+        eng.allocated_bytes = eng.allocated_bytes.saturating_sub(dictionary_bytes);
+        eng.interner = Interner::default();
+    })
 }
 
-thread_local! {
-    pub static ENGINE: RefCell<EngineState> = RefCell::new(EngineState::default());
+/// Add a reference to a series (any dtype); each reference must be released
+/// with `engine_series_release` or `engine_free_series`. Returns the new
+/// reference count, or 0 if the id is unknown.
+#[wasm_bindgen]
+pub fn engine_series_retain(series_id: u32) -> u32 {
+    ENGINE.with(|cell| cell.borrow_mut().retain_series(series_id))
 }
 
-// Basic series creation and management functions
+/// Drop a reference to a series (any dtype), freeing it when none remain.
+/// Returns the remaining reference count (0 once freed or if unknown).
 #[wasm_bindgen]
-pub fn engine_create_series_f64(data: &[f64]) -> u32 {
+pub fn engine_series_release(series_id: u32) -> u32 {
+    ENGINE.with(|cell| cell.borrow_mut().release_series(series_id).unwrap_or(0))
+}
+
+/// Current reference count of a series (0 if unknown)
+#[wasm_bindgen]
+pub fn engine_series_refcount(series_id: u32) -> u32 {
+    ENGINE.with(|cell| cell.borrow().refcount(series_id))
+}
+
+/// Register a new series id backed by the same buffer as `series_id`, without
+/// copying. The buffer is freed once every series sharing it is freed.
+/// Returns u32::MAX if the id is unknown.
+#[wasm_bindgen]
+pub fn engine_series_clone(series_id: u32) -> u32 {
+    ENGINE.with(|cell| cell.borrow_mut().clone_series(series_id).unwrap_or(u32::MAX))
+}
+
+/// Register a zero-copy view of `len` values starting at `offset` of a
+/// float64 series (clamped to its length), e.g. for `head()`/`tail()`. The
+/// view keeps the underlying buffer alive: freeing the source series does
+/// not invalidate it, and the buffer is freed with its last view or owner.
+/// Returns u32::MAX if the id is unknown.
+#[wasm_bindgen]
+pub fn engine_series_slice_view_f64(series_id: u32, offset: usize, len: usize) -> u32 {
+    ENGINE.with(|cell| cell.borrow_mut().view_series_f64(series_id, offset, len).unwrap_or(u32::MAX))
+}
+
+/// Whether the buffer of a series is shared with other series
+#[wasm_bindgen]
+pub fn engine_series_is_shared(series_id: u32) -> bool {
+    ENGINE.with(|cell| cell.borrow().is_shared(series_id))
+}
+
+/// Set the name of a series (empty string clears it). Returns false if the
+/// id is unknown.
+#[wasm_bindgen]
+pub fn engine_series_set_name(series_id: u32, name: &str) -> bool {
     ENGINE.with(|cell| {
         let mut eng = cell.borrow_mut();
-        let (ptr, len) = eng.alloc_f64_buffer(data);
-        let id = eng.next_series_id;
-        eng.next_series_id = eng.next_series_id.wrapping_add(1);
-        eng.series_store.insert(id, (ptr, len));
-        id
+        if !eng.has_series(series_id) {
+            return false;
+        }
+        eng.meta.entry(series_id).or_default().name = (!name.is_empty()).then(|| name.to_string());
+        true
     })
 }
 
+/// Set the logical dtype of a series (e.g. "datetime", "bool", "category";
+/// empty string resets it to the physical dtype). Returns false if the id is unknown.
 #[wasm_bindgen]
-pub fn engine_create_series_i32(data: &[i32]) -> u32 {
+pub fn engine_series_set_dtype(series_id: u32, dtype: &str) -> bool {
     ENGINE.with(|cell| {
         let mut eng = cell.borrow_mut();
-        let (ptr, len) = eng.alloc_i32_buffer(data);
-        let id = eng.next_series_id;
-        eng.next_series_id = eng.next_series_id.wrapping_add(1);
-        eng.series_store_i32.insert(id, (ptr, len));
-        id
+        if !eng.has_series(series_id) {
+            return false;
+        }
+        eng.meta.entry(series_id).or_default().logical_dtype = (!dtype.is_empty()).then(|| dtype.to_string());
+        true
     })
 }
 
+/// Drop cached statistics of a series, e.g. after writing to its buffer
+/// through `engine_series_ptr_*`
 #[wasm_bindgen]
-pub fn engine_free_series(series_id: u32) {
+pub fn engine_series_invalidate_stats(series_id: u32) {
+    ENGINE.with(|cell| cell.borrow_mut().invalidate_stats(series_id))
+}
+
+/// Series metadata as JSON:
+/// `{"id", "dtype", "logical_dtype", "name", "len", "null_count", "min", "max", "refcount", "shared"}`
+/// (`name` and, for all-null series, `min`/`max` are null). Statistics are
+/// computed on first request and cached until the series is modified.
+/// Returns an empty string if the id is unknown.
+#[wasm_bindgen]
+pub fn engine_series_info_json(series_id: u32) -> String {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        let stats = match eng.series_stats(series_id) {
+            Some(stats) => stats,
+            None => return String::new(),
+        };
+        let dtype = eng.series_dtype(series_id).unwrap_or("float64");
+        let meta = eng.meta.get(&series_id).cloned().unwrap_or_default();
+        serde_json::json!({
+            "id": series_id,
+            "dtype": dtype,
+            "logical_dtype": meta.logical_dtype.as_deref().unwrap_or(dtype),
+            "name": meta.name,
+            "len": eng.series_len(series_id).unwrap_or(0),
+            "null_count": stats.null_count,
+            "min": stats.min,
+            "max": stats.max,
+            "refcount": eng.refcount(series_id),
+            "shared": eng.is_shared(series_id),
+        })
+        .to_string()
+    })
+}
+
+/// Whether a float64, int32 or int64 series is monotonic increasing
+/// (each value >= the previous one; false if it has a null, true if
+/// empty), as pandas' `is_monotonic_increasing`. Cached until the series is
+/// modified, so kernels with a sorted-input precondition can check it
+/// cheaply. False for other dtypes and unknown ids.
+#[wasm_bindgen]
+pub fn engine_series_is_monotonic_increasing(series_id: u32) -> bool {
+    ENGINE.with(|cell| cell.borrow_mut().series_monotonic(series_id).is_some_and(|m| m.increasing))
+}
+
+/// Whether a float64, int32 or int64 series is monotonic decreasing (see
+/// `engine_series_is_monotonic_increasing`)
+#[wasm_bindgen]
+pub fn engine_series_is_monotonic_decreasing(series_id: u32) -> bool {
+    ENGINE.with(|cell| cell.borrow_mut().series_monotonic(series_id).is_some_and(|m| m.decreasing))
+}
+
+/// Number of nulls (NaN) in a float64 series, as `isna().sum()`. Counted on
+/// first request and cached with the series statistics until the series is
+/// modified, so repeated probes do not rescan the values. 0 if the id is
+/// not a float64 series.
+#[wasm_bindgen]
+pub fn engine_series_null_count_f64(series_id: u32) -> u32 {
     ENGINE.with(|cell| {
         let mut eng = cell.borrow_mut();
-        if let Some((ptr, len)) = eng.series_store.remove(&series_id) {
-            eng.free_f64_buffer(ptr, len);
+        if eng.series_dtype(series_id) != Some("float64") {
+            return 0;
         }
+        eng.series_stats(series_id).map_or(0, |stats| stats.null_count as u32)
     })
 }
 
+/// Whether `series_id` refers to a registered series (any dtype)
 #[wasm_bindgen]
-pub fn engine_free_series_i32(series_id: u32) {
+pub fn engine_series_exists(series_id: u32) -> bool {
+    ENGINE.with(|cell| cell.borrow().has_series(series_id))
+}
+
+/// Tag every series created from now on with `tag` (e.g. the TS call site),
+/// until changed; an empty string stops tagging. Tags show up in
+/// `engine_list_series_json` to trace leaked series back to their origin.
+#[wasm_bindgen]
+pub fn engine_set_creation_tag(tag: &str) {
+    ENGINE.with(|cell| cell.borrow_mut().creation_tag = (!tag.is_empty()).then(|| Rc::from(tag)))
+}
+
+/// Set the creation-site tag of an existing series (empty string clears it).
+/// Returns false if the id is unknown.
+#[wasm_bindgen]
+pub fn engine_series_set_tag(series_id: u32, tag: &str) -> bool {
     ENGINE.with(|cell| {
         let mut eng = cell.borrow_mut();
-        if let Some((ptr, len)) = eng.series_store_i32.remove(&series_id) {
-            eng.free_i32_buffer(ptr, len);
+        if !eng.has_series(series_id) {
+            return false;
         }
+        eng.meta.entry(series_id).or_default().tag = (!tag.is_empty()).then(|| Rc::from(tag));
+        true
     })
 }
 
+/// All registered series as JSON, oldest first:
+/// `[{"id", "dtype", "len", "seq", "tag", "name", "refcount", "pinned"}]`
+/// where `seq` is the creation sequence number and `tag` the creation-site
+/// tag (null if untagged). Series still listed after a workflow finished are leaks.
 #[wasm_bindgen]
-pub fn engine_flush() {
+pub fn engine_list_series_json() -> String {
+    ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        let mut ids: Vec<u32> = eng.series_ids();
+        let seq = |id: &u32| eng.meta.get(id).map_or(0, |m| m.created);
+        ids.sort_by_key(|id| (seq(id), *id));
+        let entries: Vec<serde_json::Value> = ids
+            .iter()
+            .map(|&id| {
+                let meta = eng.meta.get(&id);
+                serde_json::json!({
+                    "id": id,
+                    "dtype": eng.series_dtype(id),
+                    "len": eng.series_len(id).unwrap_or(0),
+                    "seq": seq(&id),
+                    "tag": meta.and_then(|m| m.tag.as_deref()),
+                    "name": meta.and_then(|m| m.name.as_deref()),
+                    "refcount": eng.refcount(id),
+                    "pinned": eng.pinned.contains(&id),
+                })
+            })
+            .collect();
+        serde_json::Value::Array(entries).to_string()
+    })
+}
+
+/// Open a scope: series created until the matching `engine_scope_pop` are
+/// freed when it closes (unless pinned). Scopes nest; returns the new depth.
+#[wasm_bindgen]
+pub fn engine_scope_push() -> usize {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        eng.scopes.push(Vec::new());
+        eng.scopes.len()
+    })
+}
+
+/// Close the innermost scope, releasing every series created in it that is
+/// still registered and not pinned (retained series survive until their
+/// remaining references are released). Returns the number of series released.
+#[wasm_bindgen]
+pub fn engine_scope_pop() -> usize {
     ENGINE.with(|cell| {
         let mut eng = cell.borrow_mut();
-        // Take the maps to avoid borrow issues, then free outside map
-        let old_f64 = std::mem::take(&mut eng.series_store);
-        for (_, (ptr, len)) in old_f64.into_iter() {
-            eng.free_f64_buffer(ptr, len);
+        let ids = match eng.scopes.pop() {
+            Some(ids) => ids,
+            None => return 0,
+        };
+        let mut freed = 0;
+        for id in ids {
+            if !eng.pinned.contains(&id) && eng.free_series(id) {
+                freed += 1;
+            }
         }
-        let old_i32 = std::mem::take(&mut eng.series_store_i32);
-        for (_, (ptr, len)) in old_i32.into_iter() {
-            eng.free_i32_buffer(ptr, len);
+        freed
+    })
+}
+
+/// Exempt a series from scope cleanup (it can still be freed explicitly)
+#[wasm_bindgen]
+pub fn engine_series_pin(series_id: u32) -> bool {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        let known = eng.has_series(series_id);
+        if known {
+            eng.pinned.insert(series_id);
         }
-        eng.next_series_id = 0;
+        known
     })
 }
 
+/// Make a pinned series subject to scope cleanup again
+#[wasm_bindgen]
+pub fn engine_series_unpin(series_id: u32) -> bool {
+    ENGINE.with(|cell| cell.borrow_mut().pinned.remove(&series_id))
+}
+
+/// Bytes allocated for series buffers (shared buffers are counted once)
 #[wasm_bindgen]
 pub fn engine_memory_usage() -> usize {
+    ENGINE.with(|cell| cell.borrow().allocated_bytes)
+}
+
+#[wasm_bindgen]
+pub fn engine_series_count() -> usize {
+    ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        eng.series_store.len()
+            + eng.series_store_i32.len()
+            + eng.series_store_i64.len()
+            + eng.series_store_bool.len()
+            + eng.series_store_u32.len()
+            + eng.series_store_u8.len()
+            + eng.series_store_str.len()
+            + eng.series_store_rle.len()
+            + eng.series_store_interned.len()
+            + eng.chunked_store.len()
+    })
+}
+
+/// Cap the bytes held by registered series (0 = unlimited). Allocating
+/// functions fail with their usual failure value and error code 4
+/// (`engine_last_error_code`) instead of growing past the limit.
+/// Lowering the limit below current usage does not free anything.
+#[wasm_bindgen]
+pub fn engine_set_memory_limit(bytes: usize) {
+    ENGINE.with(|cell| cell.borrow_mut().memory_limit = bytes)
+}
+
+/// Current memory limit in bytes (0 = unlimited)
+#[wasm_bindgen]
+pub fn engine_memory_limit() -> usize {
+    ENGINE.with(|cell| cell.borrow().memory_limit)
+}
+
+/// Memory usage as JSON: totals, per-dtype breakdown and per-series sizes
+/// (largest first):
+/// `{"total_bytes", "limit_bytes", "by_dtype": {"float64": {"series", "bytes"}, ...},
+///   "series": [{"id", "dtype", "len", "bytes", "shared"}]}`
+#[wasm_bindgen]
+pub fn engine_memory_report_json() -> String {
     ENGINE.with(|cell| {
         let eng = cell.borrow();
-        let mut total_bytes = 0;
-        
-        // Calculate f64 memory usage
-        for (_, (_, len)) in eng.series_store.iter() {
-            total_bytes += len * std::mem::size_of::<f64>();
+        let mut series: Vec<(u32, &str, usize, usize)> = Vec::new();
+        for id in eng.series_ids() {
+            let dtype = eng.series_dtype(id).unwrap_or_default();
+            series.push((id, dtype, eng.series_len(id).unwrap_or(0), eng.series_nbytes(id)));
+        }
+        // Per-series sizes count shared buffers once per series; total_bytes does not
+        series.sort_by(|a, b| b.3.cmp(&a.3).then(a.0.cmp(&b.0)));
+
+        let mut by_dtype = serde_json::Map::new();
+        for dtype in ["float64", "int32", "int64", "decimal", "bool", "uint32", "uint8", "string", "interned", "rle"] {
+            let (count, bytes) = series
+                .iter()
+                .filter(|s| s.1 == dtype)
+                .fold((0usize, 0usize), |(c, b), s| (c + 1, b + s.3));
+            by_dtype.insert(dtype.to_string(), serde_json::json!({ "series": count, "bytes": bytes }));
+        }
+        let entries: Vec<serde_json::Value> = series
+            .iter()
+            .map(|(id, dtype, len, bytes)| {
+                serde_json::json!({ "id": id, "dtype": dtype, "len": len, "bytes": bytes, "shared": eng.is_shared(*id) })
+            })
+            .collect();
+        serde_json::json!({
+            "total_bytes": eng.allocated_bytes,
+            "limit_bytes": eng.memory_limit,
+            "by_dtype": by_dtype,
+            "series": entries,
+        })
+        .to_string()
+    })
+}
+
+// Frames: named, equal-length columns backed by registered series. A frame
+// holds one reference on each column, so columns can be shared between
+// frames (and with the caller) without copying.
+
+#[derive(Clone, Debug, Default)]
+pub struct Frame {
+    pub names: Vec<String>,
+    pub columns: Vec<u32>,
+}
+
+impl Frame {
+    pub fn column(&self, name: &str) -> Option<u32> {
+        self.names.iter().position(|n| n == name).map(|i| self.columns[i])
+    }
+}
+
+impl EngineState {
+    /// Number of rows of a frame (0 for a frame without columns)
+    pub fn frame_nrows(&self, frame: &Frame) -> usize {
+        frame.columns.first().and_then(|&id| self.series_len(id)).unwrap_or(0)
+    }
+
+    /// Register a frame whose column references are already held
+    pub fn insert_frame(&mut self, frame: Frame) -> u32 {
+        let id = self.next_frame_id;
+        self.next_frame_id = self.next_frame_id.wrapping_add(1);
+        self.frames.insert(id, frame);
+        id
+    }
+
+    /// Unregister a frame, releasing its column references
+    pub fn free_frame(&mut self, frame_id: u32) -> bool {
+        match self.frames.remove(&frame_id) {
+            Some(frame) => {
+                for id in frame.columns {
+                    self.release_series(id);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Copy the rows `rows` of a series (any dtype) into a new series
+    fn take_series_rows(&mut self, series_id: u32, rows: &[usize]) -> Result<Option<u32>, EngineError> {
+        if let Some(values) = self.f64_values(series_id) {
+            let taken: Vec<f64> = rows.iter().map(|&r| values.get(r)).collect();
+            self.try_register_series_f64(&taken).map(Some)
+        } else if let Some(&(ptr, len)) = self.series_store_i32.get(&series_id) {
+            let values = unsafe { std::slice::from_raw_parts(ptr, len) };
+            let taken: Vec<i32> = rows.iter().map(|&r| values[r]).collect();
+            self.try_register_series_i32(&taken).map(Some)
+        } else if let Some(&(ptr, len)) = self.series_store_i64.get(&series_id) {
+            let values = unsafe { std::slice::from_raw_parts(ptr, len) };
+            let taken: Vec<i64> = rows.iter().map(|&r| values[r]).collect();
+            let id = self.try_register_series_i64(&taken)?;
+            if let Some(&scale) = self.decimal_scales.get(&series_id) {
+                self.decimal_scales.insert(id, scale);
+            }
+            Ok(Some(id))
+        } else if let Some(&(ptr, len)) = self.series_store_bool.get(&series_id) {
+            let values = unsafe { std::slice::from_raw_parts(ptr, len) };
+            let taken: Vec<u8> = rows.iter().map(|&r| values[r]).collect();
+            self.try_register_series_bool(&taken).map(Some)
+        } else if let Some(&(ptr, len)) = self.series_store_u32.get(&series_id) {
+            let values = unsafe { std::slice::from_raw_parts(ptr, len) };
+            let taken: Vec<u32> = rows.iter().map(|&r| values[r]).collect();
+            self.try_register_series_u32(&taken).map(Some)
+        } else if let Some(&(ptr, len)) = self.series_store_u8.get(&series_id) {
+            let values = unsafe { std::slice::from_raw_parts(ptr, len) };
+            let taken: Vec<u8> = rows.iter().map(|&r| values[r]).collect();
+            self.try_register_series_u8(&taken).map(Some)
+        } else if let Some(strings) = self.series_store_str.get(&series_id).cloned() {
+            let taken: StrSeries = rows.iter().map(|&r| strings.get(r)).collect();
+            self.try_register_series_str(taken).map(Some)
+        } else if let Some(rle) = self.series_store_rle.get(&series_id).cloned() {
+            let values = rle.decode();
+            self.try_register_series_rle(RleSeries::encode(rows.iter().map(|&r| values[r]))).map(Some)
+        } else if let Some(codes) = self.series_store_interned.get(&series_id).cloned() {
+            self.try_register_series_interned(rows.iter().map(|&r| codes[r]).collect()).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Build a new frame from the given rows (in order) of every column of
+    /// `frame_id`. Returns None if the frame is unknown, a row is out of
+    /// range or allocation fails (recording the error).
+    pub fn take_frame_rows(&mut self, frame_id: u32, rows: &[usize]) -> Option<u32> {
+        let frame = self.frames.get(&frame_id)?.clone();
+        let nrows = self.frame_nrows(&frame);
+        if rows.iter().any(|&r| r >= nrows) {
+            return None;
+        }
+        let mut columns = Vec::with_capacity(frame.columns.len());
+        for &id in &frame.columns {
+            match self.take_series_rows(id, rows) {
+                Ok(Some(new_id)) => columns.push(new_id),
+                result => {
+                    if let Err(e) = result {
+                        set_last_error(e);
+                    }
+                    for id in columns {
+                        self.release_series(id);
+                    }
+                    return None;
+                }
+            }
         }
-        
-        // Calculate i32 memory usage
-        for (_, (_, len)) in eng.series_store_i32.iter() {
-            total_bytes += len * std::mem::size_of::<i32>();
+        Some(self.insert_frame(Frame { names: frame.names, columns }))
+    }
+}
+
+/// Create a frame from column names (JSON array of strings) and series ids
+/// (f64 or i32) of equal length. The frame holds its own reference to each
+/// series, so the caller may free its ids afterwards. Returns a frame id, or
+/// u32::MAX if the names do not parse, are duplicated or do not match the
+/// ids, a series is unknown, or the lengths differ.
+#[wasm_bindgen]
+pub fn engine_frame_create(names_json: &str, series_ids: &[u32]) -> u32 {
+    let names: Vec<String> = match serde_json::from_str(names_json) {
+        Ok(names) => names,
+        Err(_) => return u32::MAX,
+    };
+    if names.len() != series_ids.len() || names.iter().enumerate().any(|(i, n)| names[..i].contains(n)) {
+        return u32::MAX;
+    }
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        let lens: Option<Vec<usize>> = series_ids.iter().map(|&id| eng.series_len(id)).collect();
+        match lens {
+            Some(lens) if lens.windows(2).all(|w| w[0] == w[1]) => {}
+            _ => return u32::MAX,
+        }
+        for &id in series_ids {
+            eng.retain_series(id);
         }
-        
-        total_bytes
+        eng.insert_frame(Frame { names, columns: series_ids.to_vec() })
     })
 }
 
+/// Free a frame, releasing its columns. Returns false if the frame is unknown.
 #[wasm_bindgen]
-pub fn engine_series_count() -> usize {
+pub fn engine_frame_free(frame_id: u32) -> bool {
+    ENGINE.with(|cell| cell.borrow_mut().free_frame(frame_id))
+}
+
+#[wasm_bindgen]
+pub fn engine_frame_nrows(frame_id: u32) -> usize {
+    ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        eng.frames.get(&frame_id).map(|f| eng.frame_nrows(f)).unwrap_or(0)
+    })
+}
+
+#[wasm_bindgen]
+pub fn engine_frame_ncols(frame_id: u32) -> usize {
+    ENGINE.with(|cell| cell.borrow().frames.get(&frame_id).map(|f| f.columns.len()).unwrap_or(0))
+}
+
+/// Column names of a frame as a JSON array ("[]" if the frame is unknown)
+#[wasm_bindgen]
+pub fn engine_frame_columns_json(frame_id: u32) -> String {
+    ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        let names: &[String] = eng.frames.get(&frame_id).map(|f| f.names.as_slice()).unwrap_or(&[]);
+        serde_json::to_string(names).unwrap_or_else(|_| "[]".to_string())
+    })
+}
+
+/// Series ids of a frame's columns, in column order
+#[wasm_bindgen]
+pub fn engine_frame_series_ids(frame_id: u32) -> Box<[u32]> {
     ENGINE.with(|cell| {
         let eng = cell.borrow();
-        eng.series_store.len() + eng.series_store_i32.len()
+        eng.frames.get(&frame_id).map(|f| f.columns.clone().into_boxed_slice()).unwrap_or_default()
+    })
+}
+
+/// Series id of the column `name` (owned by the frame; retain it to keep it
+/// beyond the frame's lifetime), or u32::MAX if not found
+#[wasm_bindgen]
+pub fn engine_frame_column(frame_id: u32, name: &str) -> u32 {
+    ENGINE.with(|cell| cell.borrow().frames.get(&frame_id).and_then(|f| f.column(name)).unwrap_or(u32::MAX))
+}
+
+/// Add a column to a frame, replacing any column with the same name. The
+/// series must match the frame's row count (any length if the frame has no
+/// columns). Returns false if the frame or series is unknown or the length differs.
+#[wasm_bindgen]
+pub fn engine_frame_add_column(frame_id: u32, name: &str, series_id: u32) -> bool {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        let mut frame = match eng.frames.get(&frame_id) {
+            Some(f) => f.clone(),
+            None => return false,
+        };
+        let len = match eng.series_len(series_id) {
+            Some(len) => len,
+            None => return false,
+        };
+        if !frame.columns.is_empty() && len != eng.frame_nrows(&frame) {
+            return false;
+        }
+        eng.retain_series(series_id);
+        match frame.names.iter().position(|n| n == name) {
+            Some(i) => {
+                let old = std::mem::replace(&mut frame.columns[i], series_id);
+                eng.release_series(old);
+            }
+            None => {
+                frame.names.push(name.to_string());
+                frame.columns.push(series_id);
+            }
+        }
+        eng.frames.insert(frame_id, frame);
+        true
+    })
+}
+
+/// Remove a column from a frame. Returns false if the frame or column is unknown.
+#[wasm_bindgen]
+pub fn engine_frame_drop_column(frame_id: u32, name: &str) -> bool {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        let removed = eng.frames.get_mut(&frame_id).and_then(|frame| {
+            let i = frame.names.iter().position(|n| n == name)?;
+            frame.names.remove(i);
+            Some(frame.columns.remove(i))
+        });
+        match removed {
+            Some(id) => {
+                eng.release_series(id);
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+/// Rename a column. Returns false if the frame or column is unknown or
+/// another column already has the new name.
+#[wasm_bindgen]
+pub fn engine_frame_rename_column(frame_id: u32, old_name: &str, new_name: &str) -> bool {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        let frame = match eng.frames.get_mut(&frame_id) {
+            Some(f) => f,
+            None => return false,
+        };
+        if old_name != new_name && frame.names.iter().any(|n| n == new_name) {
+            return false;
+        }
+        match frame.names.iter_mut().find(|n| *n == old_name) {
+            Some(n) => {
+                *n = new_name.to_string();
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+/// New frame with the named columns (JSON array, in the given order),
+/// sharing their series without copying. Returns u32::MAX if the frame or a
+/// column is unknown.
+#[wasm_bindgen]
+pub fn engine_frame_select(frame_id: u32, names_json: &str) -> u32 {
+    let names: Vec<String> = match serde_json::from_str(names_json) {
+        Ok(names) => names,
+        Err(_) => return u32::MAX,
+    };
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        let columns: Option<Vec<u32>> = match eng.frames.get(&frame_id) {
+            Some(frame) => names.iter().map(|n| frame.column(n)).collect(),
+            None => None,
+        };
+        match columns {
+            Some(columns) if names.iter().enumerate().all(|(i, n)| !names[..i].contains(n)) => {
+                for &id in &columns {
+                    eng.retain_series(id);
+                }
+                eng.insert_frame(Frame { names, columns })
+            }
+            _ => u32::MAX,
+        }
     })
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{engine_last_error_code, ERROR_LAYOUT, ERROR_MEMORY_LIMIT};
+    use crate::filtering::engine_frame_filter;
+    use crate::random::engine_random_f64;
+    use crate::series::{
+        engine_series_len_bool, engine_series_len_f64, engine_series_len_i64, engine_series_len_str, engine_series_ptr_bool,
+        engine_series_ptr_f64, engine_series_ptr_i64, engine_series_to_vec_f64,
+    };
+
+    fn frame_of(a: &[f64], b: &[f64]) -> (u32, u32, u32) {
+        let (a, b) = (engine_create_series_f64(a), engine_create_series_f64(b));
+        (engine_frame_create(r#"["a","b"]"#, &[a, b]), a, b)
+    }
+
+    #[test]
+    fn append_rows_rejects_part_of_a_frame() {
+        let (frame, a, b) = frame_of(&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]);
+        assert!(!engine_append_rows(&[a], &[7.0]));
+        assert_eq!(engine_frame_nrows(frame), 3);
+        assert!(engine_append_rows(&[b, a], &[8.0, 7.0]));
+        assert_eq!(engine_frame_nrows(frame), 4);
+        assert_eq!(unsafe { f64_values(a) }.unwrap().to_vec(), vec![1.0, 2.0, 3.0, 7.0]);
+        assert_eq!(unsafe { f64_values(b) }.unwrap().to_vec(), vec![4.0, 5.0, 6.0, 8.0]);
+    }
+
+    #[test]
+    fn delete_rows_rejects_part_of_a_frame() {
+        let (frame, a, b) = frame_of(&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]);
+        assert!(!engine_delete_rows(&[b], &[0]));
+        let filtered = engine_frame_filter(frame, &[1, 1, 1]);
+        assert_eq!(engine_frame_nrows(filtered), 3);
+        assert!(engine_delete_rows(&[a, b], &[0]));
+        assert_eq!(engine_frame_nrows(frame), 2);
+        assert_eq!(unsafe { f64_values(b) }.unwrap().to_vec(), vec![5.0, 6.0]);
+        assert_ne!(engine_frame_filter(frame, &[1, 0]), u32::MAX);
+    }
+
+    #[test]
+    fn oversized_buffers_fail_with_an_error_code() {
+        assert_eq!(try_vec::<f64>(usize::MAX).unwrap_err().code(), ERROR_LAYOUT);
+        engine_set_memory_limit(1 << 20);
+        assert_eq!(try_vec::<f64>(1 << 20).unwrap_err().code(), ERROR_MEMORY_LIMIT);
+        assert_eq!(engine_random_f64(1 << 40, 0, &[], 1), u32::MAX);
+        assert_eq!(engine_last_error_code(), ERROR_MEMORY_LIMIT);
+        assert!(try_vec::<f64>(1024).is_ok());
+        engine_set_memory_limit(0);
+    }
+
+    #[test]
+    fn chunked_series_have_no_buffer_view() {
+        let series = engine_chunked_create_f64();
+        assert!(engine_chunked_append_f64(series, &[1.0, 2.0]));
+        assert!(engine_chunked_append_f64(series, &[3.0]));
+        assert_eq!(engine_series_ptr_f64(series), 0);
+        assert_eq!(engine_series_len_f64(series), 0);
+        assert_eq!(engine_chunked_len_f64(series), 3);
+    }
+
+    #[test]
+    fn chunked_append_rejects_a_column_of_a_frame() {
+        let (a, b) = (engine_chunked_create_f64(), engine_chunked_create_f64());
+        let frame = engine_frame_create(r#"["a","b"]"#, &[a, b]);
+        assert!(!engine_chunked_append_f64(a, &[1.0]));
+        let ptr = engine_alloc_uninit_f64(1);
+        assert!(!engine_chunked_append_buffer_f64(a, ptr, 1));
+        assert!(engine_free_uninit_f64(ptr));
+        assert!(engine_append_rows(&[a, b], &[1.0, 2.0]));
+        assert_eq!(engine_frame_nrows(frame), 1);
+        assert_eq!(engine_chunked_len_f64(a), 1);
+    }
+
+    #[test]
+    fn constructors_fail_over_the_memory_limit() {
+        engine_set_memory_limit(1 << 20);
+        assert_eq!(engine_full_f64(1 << 42, 0.0), u32::MAX);
+        assert_eq!(engine_last_error_code(), ERROR_MEMORY_LIMIT);
+        assert_eq!(engine_arange_f64(0.0, 4e9, 1.0), u32::MAX);
+        assert_eq!(engine_linspace_f64(0.0, 1.0, usize::MAX), u32::MAX);
+        assert_eq!(engine_last_error_code(), ERROR_LAYOUT);
+        let series = engine_linspace_f64(0.0, 1.0, 3);
+        engine_set_memory_limit(0);
+        assert_eq!(engine_series_to_vec_f64(series), vec![0.0, 0.5, 1.0]);
+        assert_eq!(engine_series_to_vec_f64(engine_arange_f64(1.0, 0.0, -0.25)), vec![1.0, 0.75, 0.5, 0.25]);
+        assert_eq!(engine_series_to_vec_f64(engine_full_f64(2, 7.0)), vec![7.0, 7.0]);
+    }
+
+    #[test]
+    fn memory_report_lists_series_largest_first() {
+        engine_set_memory_limit(4096);
+        assert_eq!(engine_memory_limit(), 4096);
+        let small = engine_create_series_f64(&[1.0]);
+        let large = engine_create_series_f64(&[1.0, 2.0, 3.0]);
+        let clone = engine_series_clone(large);
+        let report: serde_json::Value = serde_json::from_str(&engine_memory_report_json()).unwrap();
+        engine_set_memory_limit(0);
+        assert_eq!(report["limit_bytes"], 4096);
+        assert_eq!(report["total_bytes"], engine_memory_usage());
+        assert_eq!(report["by_dtype"]["float64"]["series"], 3);
+        assert_eq!(report["by_dtype"]["int32"]["series"], 0);
+        let ids: Vec<u64> = report["series"].as_array().unwrap().iter().map(|s| s["id"].as_u64().unwrap()).collect();
+        assert_eq!(ids, [large as u64, clone as u64, small as u64]);
+        assert_eq!(report["series"][0]["shared"], true);
+        assert_eq!(report["series"][2]["shared"], false);
+        assert_eq!(report["series"][2]["len"], 1);
+    }
+
+    #[test]
+    fn scopes_free_their_unpinned_series() {
+        let outer = engine_create_series_f64(&[0.0]);
+        assert_eq!(engine_scope_push(), 1);
+        let (a, b) = (engine_create_series_f64(&[1.0]), engine_create_series_i32(&[2]));
+        assert!(engine_series_pin(b));
+        assert_eq!(engine_scope_push(), 2);
+        let c = engine_create_series_str(vec!["c".to_string()]);
+        assert_eq!(engine_scope_pop(), 1);
+        assert!(!engine_series_exists(c) && engine_series_exists(a));
+        assert_eq!(engine_scope_pop(), 1);
+        assert!(!engine_series_exists(a) && engine_series_exists(b) && engine_series_exists(outer));
+        assert_eq!(engine_scope_pop(), 0);
+        assert!(engine_series_unpin(b));
+        assert!(!engine_series_unpin(b));
+        assert!(!engine_series_pin(c));
+    }
+
+    #[test]
+    fn references_keep_series_until_the_last_release() {
+        let series = engine_create_series_f64(&[1.0, 2.0]);
+        assert_eq!(engine_series_refcount(series), 1);
+        assert_eq!(engine_series_retain(series), 2);
+        engine_free_series(series);
+        assert!(engine_series_exists(series));
+        assert_eq!(engine_series_release(series), 0);
+        assert!(!engine_series_exists(series));
+        assert_eq!((engine_series_retain(series), engine_series_refcount(series)), (0, 0));
+        // Clones share the buffer until one of them is freed
+        let source = engine_create_series_f64(&[3.0]);
+        let clone = engine_series_clone(source);
+        assert!(engine_series_is_shared(source) && engine_series_is_shared(clone));
+        assert_eq!(engine_series_release(source), 0);
+        assert!(!engine_series_is_shared(clone));
+        assert_eq!(engine_series_to_vec_f64(clone), vec![3.0]);
+    }
+
+    #[test]
+    fn frame_columns_can_be_added_renamed_dropped_and_selected() {
+        let (frame, a, b) = frame_of(&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]);
+        assert_eq!((engine_frame_ncols(frame), engine_frame_columns_json(frame)), (2, r#"["a","b"]"#.to_string()));
+        assert_eq!(*engine_frame_series_ids(frame), [a, b]);
+        assert_eq!((engine_frame_column(frame, "b"), engine_frame_column(frame, "z")), (b, u32::MAX));
+        assert_eq!(engine_series_refcount(a), 2);
+        let c = engine_create_series_f64(&[7.0, 8.0, 9.0]);
+        assert!(engine_frame_add_column(frame, "c", c));
+        assert!(!engine_frame_add_column(frame, "d", engine_create_series_f64(&[1.0])));
+        // Replacing a column releases the frame's reference on the old one
+        assert!(engine_frame_add_column(frame, "a", c));
+        assert_eq!(engine_series_refcount(a), 1);
+        assert!(!engine_frame_rename_column(frame, "c", "b"));
+        assert!(engine_frame_rename_column(frame, "c", "d"));
+        assert!(engine_frame_drop_column(frame, "d"));
+        assert!(!engine_frame_drop_column(frame, "d"));
+        assert_eq!(engine_frame_columns_json(frame), r#"["a","b"]"#);
+        let selected = engine_frame_select(frame, r#"["b","a"]"#);
+        assert_eq!(*engine_frame_series_ids(selected), [b, c]);
+        assert_eq!(engine_frame_select(frame, r#"["b","b"]"#), u32::MAX);
+        assert_eq!(engine_frame_select(frame, r#"["z"]"#), u32::MAX);
+        assert!(engine_frame_free(frame) && engine_frame_free(selected));
+        assert!(!engine_frame_free(frame));
+        assert_eq!((engine_series_refcount(b), engine_series_refcount(c)), (1, 1));
+        assert_eq!(engine_frame_columns_json(frame), "[]");
+    }
+
+    #[test]
+    fn series_info_reports_metadata_and_cached_statistics() {
+        let series = engine_create_series_f64(&[3.0, f64::NAN, -1.0]);
+        assert!(engine_series_set_name(series, "price"));
+        assert!(engine_series_set_dtype(series, "currency"));
+        let info: serde_json::Value = serde_json::from_str(&engine_series_info_json(series)).unwrap();
+        assert_eq!(info["dtype"], "float64");
+        assert_eq!(info["logical_dtype"], "currency");
+        assert_eq!(info["name"], "price");
+        assert_eq!(info["len"], 3);
+        assert_eq!(info["null_count"], 1);
+        assert_eq!((info["min"].as_f64(), info["max"].as_f64()), (Some(-1.0), Some(3.0)));
+        // Writes through the buffer pointer need an explicit invalidation
+        unsafe { *(engine_series_ptr_f64(series) as *mut f64).add(1) = 10.0 };
+        engine_series_invalidate_stats(series);
+        assert!(engine_series_set_name(series, "") && engine_series_set_dtype(series, ""));
+        let info: serde_json::Value = serde_json::from_str(&engine_series_info_json(series)).unwrap();
+        assert_eq!(info["null_count"], 0);
+        assert_eq!(info["max"].as_f64(), Some(10.0));
+        assert!(info["name"].is_null());
+        assert_eq!(info["logical_dtype"], "float64");
+        assert!(!engine_series_set_name(u32::MAX - 1, "x"));
+        assert_eq!(engine_series_info_json(u32::MAX - 1), "");
+    }
+
+    #[test]
+    fn adopted_buffers_become_series_without_a_copy() {
+        let ptr = engine_alloc_uninit_f64(3);
+        assert_ne!(ptr, 0);
+        let before = engine_memory_usage();
+        unsafe { std::slice::from_raw_parts_mut(ptr as *mut f64, 3).copy_from_slice(&[1.0, 2.0, 3.0]) };
+        assert_eq!(engine_adopt_buffer_f64(ptr, 2), u32::MAX);
+        let series = engine_adopt_buffer_f64(ptr, 3);
+        assert_eq!(engine_series_ptr_f64(series), ptr);
+        assert_eq!(engine_series_to_vec_f64(series), vec![1.0, 2.0, 3.0]);
+        assert_eq!(engine_memory_usage(), before);
+        // The buffer is no longer pending
+        assert_eq!(engine_adopt_buffer_f64(ptr, 3), u32::MAX);
+        assert!(!engine_free_uninit_f64(ptr));
+        assert!(engine_series_to_vec_f64(engine_adopt_buffer_f64(0, 0)).is_empty());
+    }
+
+    #[test]
+    fn rechunking_makes_a_contiguous_copy() {
+        let series = engine_chunked_create_f64();
+        assert_eq!(engine_chunked_num_chunks(series), 0);
+        assert!(engine_chunked_append_f64(series, &[1.0, f64::NAN]));
+        assert!(engine_chunked_append_f64(series, &[3.0]));
+        assert_eq!(engine_chunked_num_chunks(series), 2);
+        let contiguous = engine_chunked_rechunk_f64(series);
+        assert_eq!(engine_chunked_num_chunks(contiguous), 1);
+        assert_eq!(engine_series_len_f64(contiguous), 3);
+        assert_eq!(format!("{:?}", engine_series_to_vec_f64(contiguous)), "[1.0, NaN, 3.0]");
+        assert_eq!(engine_chunked_num_chunks(u32::MAX - 1), 0);
+        assert_eq!(engine_chunked_rechunk_f64(u32::MAX - 1), u32::MAX);
+    }
+
+    #[test]
+    fn series_listing_traces_creation_tags() {
+        let untagged = engine_create_series_f64(&[1.0]);
+        engine_set_creation_tag("loader");
+        let tagged = engine_create_series_i32(&[1, 2]);
+        engine_set_creation_tag("");
+        let later = engine_create_series_u8(&[1]);
+        assert!(engine_series_set_tag(later, "manual") && engine_series_pin(later));
+        let listed: serde_json::Value = serde_json::from_str(&engine_list_series_json()).unwrap();
+        let ids: Vec<u64> = listed.as_array().unwrap().iter().map(|s| s["id"].as_u64().unwrap()).collect();
+        assert_eq!(ids, [untagged as u64, tagged as u64, later as u64]);
+        assert!(listed[0]["tag"].is_null());
+        assert_eq!(listed[1]["tag"], "loader");
+        assert_eq!(listed[1]["dtype"], "int32");
+        assert_eq!(listed[2]["tag"], "manual");
+        assert_eq!(listed[2]["pinned"], true);
+        assert!(engine_series_exists(tagged));
+        engine_free_series_i32(tagged);
+        assert!(!engine_series_exists(tagged) && !engine_series_set_tag(tagged, "x"));
+    }
+
+    #[test]
+    fn typed_free_only_releases_matching_store() {
+        let ints = engine_create_series_i64(&[1, i64::MIN, 3]);
+        let flags = engine_create_series_bool(&[1, 0]);
+        let names = engine_create_series_str(vec!["a".into(), "b".into(), "c".into(), "d".into()]);
+        assert_eq!(engine_series_len_i64(ints), 3);
+        assert_ne!(engine_series_ptr_i64(ints), 0);
+        assert_eq!(engine_series_len_bool(flags), 2);
+        assert_ne!(engine_series_ptr_bool(flags), 0);
+        assert_eq!(engine_series_len_str(names), 4);
+        // Accessors and frees of another dtype ignore the id
+        assert_eq!(engine_series_len_bool(ints), 0);
+        assert_eq!(engine_series_ptr_i64(names), 0);
+        engine_free_series_bool(ints);
+        engine_free_series_i64(names);
+        engine_free_series_str(flags);
+        assert!(engine_series_exists(ints) && engine_series_exists(flags) && engine_series_exists(names));
+        engine_free_series_i64(ints);
+        engine_free_series_bool(flags);
+        engine_free_series_str(names);
+        assert!(!engine_series_exists(ints) && !engine_series_exists(flags) && !engine_series_exists(names));
+        assert_eq!(engine_series_len_i64(ints), 0);
+        assert_eq!(engine_series_ptr_bool(flags), 0);
+        assert_eq!(engine_series_len_str(names), 0);
+    }
+
+    #[test]
+    fn rle_free_releases_only_rle_series() {
+        use crate::rle::{engine_rle_num_runs, engine_series_rle_encode};
+        let values = engine_create_series_f64(&[1.0, 1.0, 2.0]);
+        let rle = engine_series_rle_encode(values);
+        engine_free_series_rle(values);
+        assert!(engine_series_exists(values));
+        assert_eq!(engine_rle_num_runs(rle), 2);
+        engine_free_series_rle(rle);
+        assert!(!engine_series_exists(rle));
+        assert_eq!(engine_rle_num_runs(rle), 0);
+    }
+
+    #[test]
+    fn monotonic_checks_follow_pandas_and_refresh_after_appends() {
+        let id = engine_chunked_create_f64();
+        assert!(engine_series_is_monotonic_increasing(id) && engine_series_is_monotonic_decreasing(id));
+        assert!(engine_chunked_append_f64(id, &[1.0, 2.0, 2.0]));
+        assert!(engine_series_is_monotonic_increasing(id));
+        assert!(!engine_series_is_monotonic_decreasing(id));
+        // The cached answer is dropped when a chunk is appended
+        assert!(engine_chunked_append_f64(id, &[0.5]));
+        assert!(!engine_series_is_monotonic_increasing(id));
+
+        let with_null = engine_create_series_f64(&[1.0, f64::NAN, 3.0]);
+        assert!(!engine_series_is_monotonic_increasing(with_null) && !engine_series_is_monotonic_decreasing(with_null));
+        assert!(engine_series_is_monotonic_decreasing(engine_create_series_i32(&[5, 5, -3])));
+        let ints = engine_create_series_i64(&[3, 2, i64::MIN]);
+        assert!(!engine_series_is_monotonic_decreasing(ints));
+        assert!(!engine_series_is_monotonic_increasing(engine_create_series_str(vec!["a".into(), "b".into()])));
+        assert!(!engine_series_is_monotonic_increasing(u32::MAX - 1));
+    }
+
+    #[test]
+    fn null_count_is_recounted_after_changes() {
+        let id = engine_chunked_create_f64();
+        assert_eq!(engine_series_null_count_f64(id), 0);
+        assert!(engine_chunked_append_f64(id, &[1.0, f64::NAN]));
+        assert_eq!(engine_series_null_count_f64(id), 1);
+        assert!(engine_chunked_append_f64(id, &[f64::NAN, f64::NAN, 2.0]));
+        assert_eq!(engine_series_null_count_f64(id), 3);
+
+        // Writes through the buffer pointer need an explicit invalidation
+        let flat = engine_create_series_f64(&[1.0, 2.0]);
+        assert_eq!(engine_series_null_count_f64(flat), 0);
+        unsafe { *(engine_series_ptr_f64(flat) as *mut f64) = f64::NAN };
+        engine_series_invalidate_stats(flat);
+        assert_eq!(engine_series_null_count_f64(flat), 1);
+        assert_eq!(engine_series_null_count_f64(engine_create_series_i64(&[i64::MIN])), 0);
+        assert_eq!(engine_series_null_count_f64(u32::MAX - 1), 0);
+    }
+}
@@ -0,0 +1,391 @@
+//! Fixed-point decimal series
+//!
+//! A decimal series is an int64 series whose values are scaled by
+//! 10^scale (scale 0..=18), so 12.34 at scale 2 is stored as 1234. Sums and
+//! arithmetic are exact integer operations, avoiding the cent-level drift of
+//! f64 for financial data. i64::MIN is null; results that overflow are null.
+//! `engine_cast` reads decimals in real units, so they also cast to the
+//! other dtypes.
+
+use std::cmp::Ordering;
+use wasm_bindgen::prelude::*;
+use crate::cast::{ROUND_CEIL, ROUND_FLOOR, ROUND_NEAREST};
+use crate::core::{f64_values, ENGINE};
+use crate::filtering::{CMP_EQ, CMP_GE, CMP_GT, CMP_LE, CMP_LT, CMP_NE};
+use crate::profiling::{profile, series_bytes};
+use crate::series::{ARITH_ADD, ARITH_MUL, ARITH_SUB};
+
+/// Largest supported scale (10^18 still fits in an i64)
+pub const DECIMAL_MAX_SCALE: u8 = 18;
+
+pub(crate) fn pow10(scale: u8) -> i128 {
+    10i128.pow(scale as u32)
+}
+
+/// `n / d` rounded with a cast rounding mode (`d` > 0)
+fn div_round(n: i128, d: i128, rounding_mode: u8) -> i128 {
+    let (q, r) = (n / d, n % d);
+    if r == 0 {
+        return q;
+    }
+    match rounding_mode {
+        ROUND_NEAREST if 2 * r.abs() >= d => q + n.signum(),
+        ROUND_FLOOR if n < 0 => q - 1,
+        ROUND_CEIL if n > 0 => q + 1,
+        _ => q,
+    }
+}
+
+/// Rescale an unscaled value between scales; None on overflow
+pub(crate) fn rescale(v: i128, from: u8, to: u8, rounding_mode: u8) -> Option<i64> {
+    let scaled = match to.cmp(&from) {
+        Ordering::Greater => v.checked_mul(pow10(to - from))?,
+        Ordering::Less => div_round(v, pow10(from - to), rounding_mode),
+        Ordering::Equal => v,
+    };
+    to_stored(scaled)
+}
+
+/// Narrow to the stored i64 range (i64::MIN is reserved for null)
+fn to_stored(v: i128) -> Option<i64> {
+    i64::try_from(v).ok().filter(|&v| v != i64::MIN)
+}
+
+/// Exact text of an unscaled value, e.g. (-1234, 2) -> "-12.34"
+pub(crate) fn format_decimal(v: i128, scale: u8) -> String {
+    let digits = v.unsigned_abs().to_string();
+    let sign = if v < 0 { "-" } else { "" };
+    if scale == 0 {
+        return format!("{}{}", sign, digits);
+    }
+    let scale = scale as usize;
+    let padded = format!("{:0>width$}", digits, width = scale + 1);
+    let (int_part, frac_part) = padded.split_at(padded.len() - scale);
+    format!("{}{}.{}", sign, int_part, frac_part)
+}
+
+/// Parse decimal text ("-12.345", "+7", ".5") at `scale`, rounding extra
+/// fraction digits with `rounding_mode`; None if invalid or out of range
+pub(crate) fn parse_decimal(text: &str, scale: u8, rounding_mode: u8) -> Option<i64> {
+    let text = text.trim();
+    let (negative, body) = match text.as_bytes().first()? {
+        b'-' => (true, &text[1..]),
+        b'+' => (false, &text[1..]),
+        _ => (false, text),
+    };
+    let (int_part, frac_part) = body.split_once('.').unwrap_or((body, ""));
+    if int_part.is_empty() && frac_part.is_empty() {
+        return None;
+    }
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if !all_digits(int_part) || !all_digits(frac_part) || int_part.len() + frac_part.len() > 38 {
+        return None;
+    }
+    let mut unscaled: i128 = 0;
+    for b in int_part.bytes().chain(frac_part.bytes()) {
+        unscaled = unscaled.checked_mul(10)?.checked_add((b - b'0') as i128)?;
+    }
+    if negative {
+        unscaled = -unscaled;
+    }
+    rescale(unscaled, u8::try_from(frac_part.len()).ok()?, scale, rounding_mode)
+}
+
+/// Unscaled values and scale of a decimal series (copied out of the engine)
+fn decimal_values(series_id: u32) -> Option<(Vec<i64>, u8)> {
+    ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        let scale = *eng.decimal_scales.get(&series_id)?;
+        let &(ptr, len) = eng.series_store_i64.get(&series_id)?;
+        Some((unsafe { std::slice::from_raw_parts(ptr, len) }.to_vec(), scale))
+    })
+}
+
+fn register_decimal(values: &[i64], scale: u8) -> u32 {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        let id = eng.register_series_i64(values);
+        if id != u32::MAX {
+            eng.decimal_scales.insert(id, scale);
+        }
+        id
+    })
+}
+
+/// Create a decimal series from unscaled values (value x 10^scale;
+/// i64::MIN is null). Returns u32::MAX if `scale` exceeds 18.
+#[wasm_bindgen]
+pub fn engine_create_series_decimal(unscaled: &[i64], scale: u8) -> u32 {
+    let _prof = profile("engine_create_series_decimal", || unscaled.len() * 8);
+    if scale > DECIMAL_MAX_SCALE {
+        return u32::MAX;
+    }
+    register_decimal(unscaled, scale)
+}
+
+/// Scale of a decimal series, or -1 if the id is not a decimal series
+#[wasm_bindgen]
+pub fn engine_decimal_scale(series_id: u32) -> i32 {
+    ENGINE.with(|cell| cell.borrow().decimal_scales.get(&series_id).map_or(-1, |&s| s as i32))
+}
+
+/// Convert an f64 series to a decimal series at `scale`, rounding with a
+/// cast rounding mode (0 = trunc, 1 = round, 2 = floor, 3 = ceil). NaN,
+/// infinities and out-of-range values become null. Note that f64 inputs
+/// carry their binary representation error (0.1 + 0.2); parse from
+/// strings with `engine_decimal_from_str` when exact digits matter.
+#[wasm_bindgen]
+pub fn engine_decimal_from_f64(series_id: u32, scale: u8, rounding_mode: u8) -> u32 {
+    let _prof = profile("engine_decimal_from_f64", || series_bytes(series_id));
+    if scale > DECIMAL_MAX_SCALE {
+        return u32::MAX;
+    }
+    let values = match unsafe { f64_values(series_id) } {
+        Some(values) => values,
+        None => return u32::MAX,
+    };
+    let unit = pow10(scale) as f64;
+    let converted: Vec<i64> = values
+        .iter()
+        .map(|v| {
+            let r = match rounding_mode {
+                ROUND_NEAREST => (v * unit).round(),
+                ROUND_FLOOR => (v * unit).floor(),
+                ROUND_CEIL => (v * unit).ceil(),
+                _ => (v * unit).trunc(),
+            };
+            // NaN fails both comparisons
+            if r > i64::MIN as f64 && r < i64::MAX as f64 { r as i64 } else { i64::MIN }
+        })
+        .collect();
+    register_decimal(&converted, scale)
+}
+
+/// Parse a string series into a decimal series at `scale` exactly, rounding
+/// extra fraction digits with `rounding_mode`. Null, unparsable and
+/// out-of-range values become null.
+#[wasm_bindgen]
+pub fn engine_decimal_from_str(series_id: u32, scale: u8, rounding_mode: u8) -> u32 {
+    let _prof = profile("engine_decimal_from_str", || series_bytes(series_id));
+    if scale > DECIMAL_MAX_SCALE {
+        return u32::MAX;
+    }
+    let converted: Option<Vec<i64>> = ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        let strings = eng.series_store_str.get(&series_id)?;
+        Some(
+            strings
+                .iter()
+                .map(|v| v.and_then(|text| parse_decimal(text, scale, rounding_mode)).unwrap_or(i64::MIN))
+                .collect(),
+        )
+    });
+    match converted {
+        Some(values) => register_decimal(&values, scale),
+        None => u32::MAX,
+    }
+}
+
+/// Convert a decimal series to f64 (nearest representable values)
+#[wasm_bindgen]
+pub fn engine_decimal_to_f64(series_id: u32) -> u32 {
+    let _prof = profile("engine_decimal_to_f64", || series_bytes(series_id));
+    let (values, scale) = match decimal_values(series_id) {
+        Some(v) => v,
+        None => return u32::MAX,
+    };
+    let unit = pow10(scale) as f64;
+    let out: Vec<f64> = values.iter().map(|&v| if v == i64::MIN { f64::NAN } else { v as f64 / unit }).collect();
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&out))
+}
+
+/// Convert a decimal series to a string series with exactly `scale`
+/// fraction digits ("12.30"); nulls stay null
+#[wasm_bindgen]
+pub fn engine_decimal_to_str(series_id: u32) -> u32 {
+    let _prof = profile("engine_decimal_to_str", || series_bytes(series_id));
+    let (values, scale) = match decimal_values(series_id) {
+        Some(v) => v,
+        None => return u32::MAX,
+    };
+    let text: Vec<Option<String>> =
+        values.iter().map(|&v| if v == i64::MIN { None } else { Some(format_decimal(v as i128, scale)) }).collect();
+    let strings = text.iter().map(|v| v.as_deref()).collect();
+    ENGINE.with(|cell| cell.borrow_mut().register_series_str(strings))
+}
+
+/// Change the scale of a decimal series, rounding with `rounding_mode` when
+/// the scale shrinks; values that overflow become null
+#[wasm_bindgen]
+pub fn engine_decimal_rescale(series_id: u32, scale: u8, rounding_mode: u8) -> u32 {
+    let _prof = profile("engine_decimal_rescale", || series_bytes(series_id));
+    let (values, from) = match decimal_values(series_id) {
+        Some(v) if scale <= DECIMAL_MAX_SCALE => v,
+        _ => return u32::MAX,
+    };
+    let out: Vec<i64> = values
+        .iter()
+        .map(|&v| if v == i64::MIN { i64::MIN } else { rescale(v as i128, from, scale, rounding_mode).unwrap_or(i64::MIN) })
+        .collect();
+    register_decimal(&out, scale)
+}
+
+/// Element-wise add (0), subtract (1) or multiply (2) of two decimal series
+/// of equal length. The result takes the larger of the two scales; products
+/// are rounded half away from zero to it. Nulls and overflowing results are
+/// null. Returns u32::MAX for unknown series, a length mismatch or another
+/// op code.
+#[wasm_bindgen]
+pub fn engine_decimal_binary_op(a_id: u32, b_id: u32, op: u8) -> u32 {
+    let _prof = profile("engine_decimal_binary_op", || series_bytes(a_id) + series_bytes(b_id));
+    let ((a, sa), (b, sb)) = match (decimal_values(a_id), decimal_values(b_id)) {
+        (Some(a), Some(b)) if a.0.len() == b.0.len() && op <= ARITH_MUL => (a, b),
+        _ => {
+            engine_log!(warn, "engine_decimal_binary_op: unknown series, length mismatch or op a={} b={} op={}", a_id, b_id, op);
+            return u32::MAX;
+        }
+    };
+    let scale = sa.max(sb);
+    let (ua, ub) = (pow10(scale - sa), pow10(scale - sb));
+    let out: Vec<i64> = a
+        .iter()
+        .zip(&b)
+        .map(|(&x, &y)| {
+            if x == i64::MIN || y == i64::MIN {
+                return i64::MIN;
+            }
+            let (x, y) = (x as i128, y as i128);
+            let result = match op {
+                ARITH_ADD => (x * ua).checked_add(y * ub).and_then(to_stored),
+                ARITH_SUB => (x * ua).checked_sub(y * ub).and_then(to_stored),
+                _ => x.checked_mul(y).and_then(|p| rescale(p, sa + sb, scale, ROUND_NEAREST)),
+            };
+            result.unwrap_or(i64::MIN)
+        })
+        .collect();
+    register_decimal(&out, scale)
+}
+
+/// Compare two decimal series element-wise (0=eq, 1=ne, 2=lt, 3=le, 4=gt,
+/// 5=ge), comparing exact values across scales. Returns a 0/1 mask where
+/// nulls never match, or an empty array on unknown series or length mismatch.
+#[wasm_bindgen]
+pub fn engine_decimal_compare(a_id: u32, b_id: u32, op: u8) -> Vec<u8> {
+    let _prof = profile("engine_decimal_compare", || series_bytes(a_id) + series_bytes(b_id));
+    let ((a, sa), (b, sb)) = match (decimal_values(a_id), decimal_values(b_id)) {
+        (Some(a), Some(b)) if a.0.len() == b.0.len() => (a, b),
+        _ => return Vec::new(),
+    };
+    let scale = sa.max(sb);
+    let (ua, ub) = (pow10(scale - sa), pow10(scale - sb));
+    a.iter()
+        .zip(&b)
+        .map(|(&x, &y)| {
+            if x == i64::MIN || y == i64::MIN {
+                return 0;
+            }
+            let ord = (x as i128 * ua).cmp(&(y as i128 * ub));
+            let hit = match op {
+                CMP_EQ => ord == Ordering::Equal,
+                CMP_NE => ord != Ordering::Equal,
+                CMP_LT => ord == Ordering::Less,
+                CMP_LE => ord != Ordering::Greater,
+                CMP_GT => ord == Ordering::Greater,
+                CMP_GE => ord != Ordering::Less,
+                _ => false,
+            };
+            hit as u8
+        })
+        .collect()
+}
+
+/// Exact sum of a decimal series as decimal text at the series scale
+/// (nulls skipped; zero for an all-null series). Empty string if unknown.
+#[wasm_bindgen]
+pub fn engine_decimal_sum(series_id: u32) -> String {
+    let _prof = profile("engine_decimal_sum", || series_bytes(series_id));
+    match decimal_values(series_id) {
+        Some((values, scale)) => {
+            let sum: i128 = values.iter().filter(|&&v| v != i64::MIN).map(|&v| v as i128).sum();
+            format_decimal(sum, scale)
+        }
+        None => String::new(),
+    }
+}
+
+/// Mean of a decimal series as decimal text, rounded half away from zero
+/// to the series scale (nulls skipped). Empty string if unknown or all null.
+#[wasm_bindgen]
+pub fn engine_decimal_mean(series_id: u32) -> String {
+    let _prof = profile("engine_decimal_mean", || series_bytes(series_id));
+    let (values, scale) = match decimal_values(series_id) {
+        Some(v) => v,
+        None => return String::new(),
+    };
+    let (sum, count) = values
+        .iter()
+        .filter(|&&v| v != i64::MIN)
+        .fold((0i128, 0i128), |(s, c), &v| (s + v as i128, c + 1));
+    if count == 0 {
+        return String::new();
+    }
+    format_decimal(div_round(sum, count, ROUND_NEAREST), scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cast::ROUND_TRUNC;
+    use crate::core::{engine_create_series_f64, engine_create_series_str};
+    use crate::series::{engine_series_to_json_str, engine_series_to_vec_f64, engine_series_to_vec_i64};
+
+    #[test]
+    fn rounding_modes_apply_when_the_scale_shrinks() {
+        let id = engine_create_series_decimal(&[1235, -1235, 1234, i64::MIN], 3);
+        let rounded = |mode| engine_series_to_vec_i64(engine_decimal_rescale(id, 2, mode));
+        assert_eq!(rounded(ROUND_TRUNC), [123, -123, 123, i64::MIN]);
+        assert_eq!(rounded(ROUND_NEAREST), [124, -124, 123, i64::MIN]);
+        assert_eq!(rounded(ROUND_FLOOR), [123, -124, 123, i64::MIN]);
+        assert_eq!(rounded(ROUND_CEIL), [124, -123, 124, i64::MIN]);
+        assert_eq!(engine_decimal_scale(engine_decimal_rescale(id, 2, ROUND_TRUNC)), 2);
+        assert_eq!(engine_decimal_rescale(id, DECIMAL_MAX_SCALE + 1, ROUND_TRUNC), u32::MAX);
+    }
+
+    #[test]
+    fn overflow_becomes_null() {
+        let big = engine_create_series_decimal(&[i64::MAX, 1, -i64::MAX], 0);
+        assert_eq!(engine_series_to_vec_i64(engine_decimal_rescale(big, 1, ROUND_TRUNC)), [i64::MIN, 10, i64::MIN]);
+        let one = engine_create_series_decimal(&[1, 1, 1], 0);
+        assert_eq!(engine_series_to_vec_i64(engine_decimal_binary_op(big, one, ARITH_ADD)), [i64::MIN, 2, -i64::MAX + 1]);
+        // i64::MIN is the null marker, so a difference landing on it overflows
+        assert_eq!(engine_series_to_vec_i64(engine_decimal_binary_op(big, one, ARITH_SUB))[2], i64::MIN);
+        let floats = engine_create_series_f64(&[1e300, f64::NAN, 0.125]);
+        assert_eq!(engine_series_to_vec_i64(engine_decimal_from_f64(floats, 2, ROUND_NEAREST)), [i64::MIN, i64::MIN, 13]);
+    }
+
+    #[test]
+    fn arithmetic_and_text_are_exact() {
+        let text = engine_create_series_str(vec!["0.1".into(), "-12.345".into(), ".5".into(), "1e3".into()]);
+        let parsed = engine_decimal_from_str(text, 2, ROUND_NEAREST);
+        assert_eq!(engine_series_to_vec_i64(parsed), [10, -1235, 50, i64::MIN]);
+        let cents = engine_create_series_decimal(&[20, 0, 150, 1], 2);
+        let sum = engine_decimal_binary_op(parsed, cents, ARITH_ADD);
+        assert_eq!(engine_series_to_json_str(engine_decimal_to_str(sum)), r#"["0.30","-12.35","2.00",null]"#);
+        let tenths = engine_create_series_decimal(&[15, 15, 15, 15], 1);
+        assert_eq!(engine_series_to_vec_i64(engine_decimal_binary_op(cents, tenths, ARITH_MUL)), [30, 0, 225, 2]);
+        assert_eq!(engine_decimal_compare(cents, tenths, CMP_LT), [1, 1, 0, 1]);
+        assert_eq!(engine_decimal_sum(parsed), "-11.75");
+        assert_eq!(engine_decimal_mean(parsed), "-3.92");
+        assert_eq!(engine_decimal_binary_op(parsed, engine_create_series_decimal(&[1], 2), ARITH_ADD), u32::MAX);
+    }
+
+    #[test]
+    fn to_f64_divides_by_the_scale() {
+        let id = engine_create_series_decimal(&[1234, -5, i64::MIN, 0], 2);
+        let floats = engine_series_to_vec_f64(engine_decimal_to_f64(id));
+        assert_eq!(format!("{:?}", floats), "[12.34, -0.05, NaN, 0.0]");
+        let whole = engine_create_series_decimal(&[7], 0);
+        assert_eq!(engine_series_to_vec_f64(engine_decimal_to_f64(whole)), [7.0]);
+        assert_eq!(engine_decimal_to_f64(engine_create_series_f64(&[1.0])), u32::MAX);
+    }
+}
@@ -0,0 +1,199 @@
+//! Error reporting: error codes, last-error slot and panic capture
+//!
+//! Engine functions keep their existing failure values (u32::MAX ids, empty
+//! arrays, NaN); the reason for the most recent failure is recorded here and
+//! can be read with `engine_last_error_code` / `engine_last_error_message`.
+//!
+//! Panics are fatal on wasm32. wasm32 cannot unwind, so a panic aborts and
+//! traps the instance: the failing call throws a `RuntimeError` in JS and
+//! every registered series is lost with it, so the instance has to be
+//! recreated. Failures the engine can foresee must therefore come back as
+//! error codes rather than panics; in particular, buffers sized by the caller
+//! or by untrusted input are allocated with `core::try_vec`, which reports
+//! oversized or over-budget requests as `ERROR_LAYOUT`, `ERROR_MEMORY_LIMIT`
+//! or `ERROR_OUT_OF_MEMORY`.
+//!
+//! A panic hook (installed on first engine use, or explicitly with
+//! `engine_install_panic_hook`) records the panic message, which can still be
+//! read after a trap, and writes it to `console.error`. `catch_panic` turns a
+//! panic into `ERROR_PANIC` on native builds only (tests, server-side use).
+
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::{Mutex, Once};
+use wasm_bindgen::prelude::*;
+
+// Error codes returned by `engine_last_error_code`
+pub const ERROR_NONE: u32 = 0;
+pub const ERROR_LAYOUT: u32 = 1;
+pub const ERROR_OUT_OF_MEMORY: u32 = 2;
+pub const ERROR_PANIC: u32 = 3;
+pub const ERROR_MEMORY_LIMIT: u32 = 4;
+pub const ERROR_CAST: u32 = 5;
+pub const ERROR_CALLBACK: u32 = 6;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EngineError {
+    /// Requested buffer size overflows the allocator's layout limits
+    Layout { len: usize },
+    /// The allocator returned null
+    OutOfMemory { bytes: usize },
+    /// A panic was caught
+    Panic(String),
+    /// The allocation would push registered series past the memory limit
+    MemoryLimit { requested: usize, in_use: usize, limit: usize },
+    /// A value could not be represented in the target dtype of a cast
+    Cast { row: usize, dtype: &'static str },
+    /// A JS callback threw or could not be called
+    Callback(String),
+}
+
+impl EngineError {
+    pub fn code(&self) -> u32 {
+        match self {
+            EngineError::Layout { .. } => ERROR_LAYOUT,
+            EngineError::OutOfMemory { .. } => ERROR_OUT_OF_MEMORY,
+            EngineError::Panic(_) => ERROR_PANIC,
+            EngineError::MemoryLimit { .. } => ERROR_MEMORY_LIMIT,
+            EngineError::Cast { .. } => ERROR_CAST,
+            EngineError::Callback(_) => ERROR_CALLBACK,
+        }
+    }
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::Layout { len } => write!(f, "invalid buffer layout for {} elements", len),
+            EngineError::OutOfMemory { bytes } => write!(f, "allocation of {} bytes failed", bytes),
+            EngineError::Panic(msg) => write!(f, "panic: {}", msg),
+            EngineError::MemoryLimit { requested, in_use, limit } => write!(
+                f,
+                "memory limit exceeded: {} bytes requested with {} of {} bytes in use",
+                requested, in_use, limit
+            ),
+            EngineError::Cast { row, dtype } => write!(f, "value at row {} cannot be cast to {}", row, dtype),
+            EngineError::Callback(msg) => write!(f, "callback failed: {}", msg),
+        }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<EngineError>> = const { RefCell::new(None) };
+}
+
+/// Message of the last panic, kept outside thread-locals so it survives a trap
+static PANIC_MESSAGE: Mutex<Option<String>> = Mutex::new(None);
+static PANIC_HOOK: Once = Once::new();
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console, js_name = error)]
+    fn console_error(message: &str);
+}
+
+/// Record `err` as the most recent failure
+pub(crate) fn set_last_error(err: EngineError) {
+    engine_log!(error, "{}", err);
+    let _ = LAST_ERROR.try_with(|cell| {
+        if let Ok(mut slot) = cell.try_borrow_mut() {
+            *slot = Some(err);
+        }
+    });
+}
+
+/// Install the panic hook (idempotent)
+pub(crate) fn install_panic_hook() {
+    PANIC_HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let message = info.to_string();
+            if let Ok(mut slot) = PANIC_MESSAGE.lock() {
+                *slot = Some(message.clone());
+            }
+            #[cfg(target_arch = "wasm32")]
+            console_error(&format!("wasm_frame {}", message));
+            previous(info);
+        }));
+    });
+}
+
+/// Run `f`, turning a panic into `EngineError::Panic` and returning
+/// `fallback`. Native builds only: on wasm32 panics abort, so the panic
+/// traps before this can catch it.
+pub(crate) fn catch_panic<R>(fallback: R, f: impl FnOnce() -> R + std::panic::UnwindSafe) -> R {
+    match std::panic::catch_unwind(f) {
+        Ok(r) => r,
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            set_last_error(EngineError::Panic(message));
+            fallback
+        }
+    }
+}
+
+/// Install the panic hook explicitly (it is also installed on first engine use)
+#[wasm_bindgen]
+pub fn engine_install_panic_hook() {
+    install_panic_hook();
+}
+
+/// Code of the most recent failure
+/// (0 = none, 1 = layout, 2 = out of memory, 3 = panic, 4 = memory limit,
+/// 5 = cast, 6 = callback)
+#[wasm_bindgen]
+pub fn engine_last_error_code() -> u32 {
+    LAST_ERROR.with(|cell| cell.borrow().as_ref().map(|e| e.code()).unwrap_or(ERROR_NONE))
+}
+
+/// Message of the most recent failure (empty if none)
+#[wasm_bindgen]
+pub fn engine_last_error_message() -> String {
+    LAST_ERROR.with(|cell| cell.borrow().as_ref().map(|e| e.to_string()).unwrap_or_default())
+}
+
+/// Clear the recorded failure
+#[wasm_bindgen]
+pub fn engine_clear_error() {
+    LAST_ERROR.with(|cell| *cell.borrow_mut() = None);
+}
+
+/// Take the message of the last panic seen by the hook (empty if none),
+/// including panics that trapped the instance
+#[wasm_bindgen]
+pub fn engine_take_panic_message() -> String {
+    PANIC_MESSAGE.lock().ok().and_then(|mut slot| slot.take()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_and_clears_the_last_error() {
+        engine_clear_error();
+        assert_eq!((engine_last_error_code(), engine_last_error_message()), (ERROR_NONE, String::new()));
+        set_last_error(EngineError::Cast { row: 3, dtype: "int32" });
+        assert_eq!(engine_last_error_code(), ERROR_CAST);
+        assert_eq!(engine_last_error_message(), "value at row 3 cannot be cast to int32");
+        set_last_error(EngineError::MemoryLimit { requested: 16, in_use: 8, limit: 20 });
+        assert_eq!(engine_last_error_code(), ERROR_MEMORY_LIMIT);
+        engine_clear_error();
+        assert_eq!(engine_last_error_code(), ERROR_NONE);
+    }
+
+    #[test]
+    fn caught_panics_become_errors() {
+        engine_install_panic_hook();
+        assert_eq!(catch_panic(7, || -> i32 { panic!("boom") }), 7);
+        assert_eq!(engine_last_error_code(), ERROR_PANIC);
+        assert_eq!(engine_last_error_message(), "panic: boom");
+        assert!(engine_take_panic_message().contains("boom"));
+        assert_eq!(catch_panic(7, || 1), 1);
+    }
+}
@@ -0,0 +1,534 @@
+//! Lazy expressions: a DAG of deferred operations over registered series
+//!
+//! `engine_expr_*` builders append nodes to a per-thread arena and return
+//! node ids; nothing is computed until `engine_expr_collect(root)`, which
+//! evaluates only the nodes reachable from `root` (projection pruning) with
+//! these rewrites applied:
+//!
+//! - predicate pushdown: a filter over element-wise arithmetic/comparisons is
+//!   applied at the column scans, so the arithmetic only runs on kept rows
+//! - fused filter + aggregation: aggregating a filtered expression streams
+//!   the kept rows into the accumulator without materializing them
+//! - sorts feeding an aggregation are skipped (aggregates are order-free)
+//!
+//! Shared sub-expressions are evaluated once per collect.
+//!
+//! Nodes stay in the arena after a collect, so they can be reused by later
+//! expressions, until `engine_expr_clear` drops them all. The arena holds at
+//! most `MAX_NODES` nodes; builders return u32::MAX once it is full.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use crate::core::{f64_values, ENGINE};
+use crate::error::catch_panic;
+use crate::filtering::compare_f64;
+use crate::groupby::{output_keys, take_group_order, GroupOrdering};
+use crate::profiling::profile;
+use crate::series::arith;
+use crate::sorting::order_f64;
+use crate::statistics::RunningStats;
+use crate::tasks::{cancel_requested, take_cancel_request, CancelScope};
+
+// Logical operator codes
+const LOGIC_AND: u8 = 0;
+const LOGIC_OR: u8 = 1;
+
+enum Node {
+    Column(u32),
+    Literal(f64),
+    Compare { op: u8, left: u32, right: u32 },
+    Logical { op: u8, left: u32, right: u32 },
+    Not(u32),
+    Arith { op: u8, left: u32, right: u32 },
+    Filter { input: u32, predicate: u32 },
+    Sort { input: u32, ascending: bool, nulls_last: bool },
+    Agg { input: u32, agg: u8 },
+    GroupBy { input: u32, keys: Vec<String>, agg: u8 },
+}
+
+impl Node {
+    /// Ids of the nodes this node reads
+    fn inputs(&self) -> Vec<u32> {
+        match *self {
+            Node::Column(_) | Node::Literal(_) => Vec::new(),
+            Node::Compare { left, right, .. } | Node::Logical { left, right, .. } | Node::Arith { left, right, .. } => vec![left, right],
+            Node::Filter { input, predicate } => vec![input, predicate],
+            Node::Not(input) | Node::Sort { input, .. } | Node::Agg { input, .. } | Node::GroupBy { input, .. } => vec![input],
+        }
+    }
+}
+
+/// Whether the expression at `root` contains a groupby node
+fn contains_groupby(nodes: &[Node], root: u32) -> bool {
+    let mut seen = vec![false; nodes.len()];
+    let mut stack = vec![root];
+    while let Some(id) = stack.pop() {
+        match nodes.get(id as usize) {
+            Some(Node::GroupBy { .. }) => return true,
+            Some(node) if !seen[id as usize] => {
+                seen[id as usize] = true;
+                stack.extend(node.inputs());
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Capacity of the node arena, so a host that never calls
+/// `engine_expr_clear` fails fast instead of growing memory without bound
+const MAX_NODES: usize = 1 << 16;
+
+thread_local! {
+    static EXPRS: RefCell<Vec<Node>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Intermediate result during evaluation
+enum Value {
+    Scalar(f64),
+    Values(Vec<f64>),
+    Mask(Vec<bool>),
+}
+
+impl Value {
+    fn len(&self) -> Option<usize> {
+        match self {
+            Value::Scalar(_) => None,
+            Value::Values(v) => Some(v.len()),
+            Value::Mask(m) => Some(m.len()),
+        }
+    }
+
+    fn get(&self, i: usize) -> f64 {
+        match self {
+            Value::Scalar(v) => *v,
+            Value::Values(v) => v[i],
+            Value::Mask(m) => m[i] as u8 as f64,
+        }
+    }
+}
+
+fn push_node(node: Node, children: &[u32]) -> u32 {
+    EXPRS.with(|cell| {
+        let mut nodes = cell.borrow_mut();
+        if children.iter().any(|&c| c as usize >= nodes.len()) {
+            return u32::MAX;
+        }
+        if nodes.len() >= MAX_NODES {
+            engine_log!(warn, "expression arena full ({} nodes); call engine_expr_clear", nodes.len());
+            return u32::MAX;
+        }
+        nodes.push(node);
+        (nodes.len() - 1) as u32
+    })
+}
+
+fn broadcast_len(a: &Value, b: &Value) -> Option<Option<usize>> {
+    match (a.len(), b.len()) {
+        (Some(x), Some(y)) if x != y => None,
+        (x, y) => Some(x.or(y)),
+    }
+}
+
+fn as_mask(v: &Value) -> Option<Vec<bool>> {
+    match v {
+        Value::Mask(m) => Some(m.clone()),
+        Value::Values(vals) => Some(vals.iter().map(|&x| !x.is_nan() && x != 0.0).collect()),
+        Value::Scalar(_) => None,
+    }
+}
+
+struct Evaluator<'n> {
+    nodes: &'n [Node],
+    memo: HashMap<u32, Rc<Value>>,
+    // Group order of the collect call, shared by its groupby nodes
+    order: GroupOrdering,
+}
+
+impl<'n> Evaluator<'n> {
+    fn eval(&mut self, id: u32) -> Option<Rc<Value>> {
+        if let Some(v) = self.memo.get(&id) {
+            return Some(v.clone());
+        }
+        if cancel_requested() {
+            return None;
+        }
+        let value = Rc::new(self.compute(id, None)?);
+        self.memo.insert(id, value.clone());
+        Some(value)
+    }
+
+    /// Evaluate `id`, restricted to the rows where `keep` is true when given.
+    /// Element-wise nodes pass the restriction down to their inputs.
+    fn compute(&mut self, id: u32, keep: Option<&[bool]>) -> Option<Value> {
+        let nodes = self.nodes;
+        match nodes.get(id as usize)? {
+            Node::Column(series_id) => {
+                // The engine is not mutated while an expression is being
+                // collected, so the borrowed buffers stay valid
+                let data = unsafe { f64_values(*series_id) }?;
+                Some(Value::Values(match keep {
+                    Some(mask) if mask.len() != data.len() => return None,
+                    Some(mask) => data.iter().zip(mask).filter(|(_, &k)| k).map(|(v, _)| v).collect(),
+                    None => data.to_vec(),
+                }))
+            }
+            Node::Literal(v) => Some(Value::Scalar(*v)),
+            Node::Compare { op, left, right } => {
+                let (a, b) = (self.compute_child(*left, keep)?, self.compute_child(*right, keep)?);
+                Some(match broadcast_len(&a, &b)? {
+                    Some(n) => Value::Mask((0..n).map(|i| compare_f64(*op, a.get(i), b.get(i))).collect()),
+                    None => Value::Scalar(compare_f64(*op, a.get(0), b.get(0)) as u8 as f64),
+                })
+            }
+            Node::Logical { op, left, right } => {
+                let a = as_mask(&self.compute_child(*left, keep)?)?;
+                let b = as_mask(&self.compute_child(*right, keep)?)?;
+                if a.len() != b.len() {
+                    return None;
+                }
+                Some(Value::Mask(match *op {
+                    LOGIC_AND => a.iter().zip(b.iter()).map(|(x, y)| *x && *y).collect(),
+                    LOGIC_OR => a.iter().zip(b.iter()).map(|(x, y)| *x || *y).collect(),
+                    _ => return None,
+                }))
+            }
+            Node::Not(input) => {
+                let m = as_mask(&self.compute_child(*input, keep)?)?;
+                Some(Value::Mask(m.into_iter().map(|x| !x).collect()))
+            }
+            Node::Arith { op, left, right } => {
+                let (a, b) = (self.compute_child(*left, keep)?, self.compute_child(*right, keep)?);
+                Some(match broadcast_len(&a, &b)? {
+                    Some(n) => Value::Values((0..n).map(|i| arith(*op, a.get(i), b.get(i))).collect()),
+                    None => Value::Scalar(arith(*op, a.get(0), b.get(0))),
+                })
+            }
+            Node::Filter { input, predicate } => {
+                let mask = self.filter_mask(*predicate, keep)?;
+                self.compute(*input, Some(&mask))
+            }
+            // Non element-wise nodes: evaluate fully, then apply any restriction
+            Node::Sort { input, ascending, nulls_last } => {
+                let values = self.eval_values(*input)?;
+                let order = order_f64(&values, *ascending, *nulls_last)?;
+                let sorted: Vec<f64> = order.iter().map(|&i| values[i]).collect();
+                restrict(Value::Values(sorted), keep)
+            }
+            Node::Agg { input, agg } => {
+                let mut stats = RunningStats::default();
+                self.accumulate(*input, None, &mut |v| stats.push(v))?;
+                restrict(Value::Scalar(stats.finish(*agg)), keep)
+            }
+            Node::GroupBy { input, keys, agg } => {
+                let values = self.eval_values(*input)?;
+                if values.len() != keys.len() {
+                    return None;
+                }
+                let mut groups: HashMap<&str, RunningStats> = HashMap::new();
+                for (k, v) in keys.iter().zip(values.iter()) {
+                    groups.entry(k.as_str()).or_default().push(*v);
+                }
+                let ordered = output_keys(&self.order, groups.keys().map(|k| k.to_string()).collect(), keys);
+                let out: Vec<f64> = ordered
+                    .iter()
+                    .map(|k| groups.get(k.as_str()).copied().unwrap_or_default().finish(*agg))
+                    .collect();
+                restrict(Value::Values(out), keep)
+            }
+        }
+    }
+
+    /// Children of element-wise nodes: share memoized results when unrestricted
+    fn compute_child(&mut self, id: u32, keep: Option<&[bool]>) -> Option<Value> {
+        match keep {
+            None => {
+                let v = self.eval(id)?;
+                Some(match v.as_ref() {
+                    Value::Scalar(x) => Value::Scalar(*x),
+                    Value::Values(x) => Value::Values(x.clone()),
+                    Value::Mask(x) => Value::Mask(x.clone()),
+                })
+            }
+            Some(_) => self.compute(id, keep),
+        }
+    }
+
+    fn eval_values(&mut self, id: u32) -> Option<Vec<f64>> {
+        match self.eval(id)?.as_ref() {
+            Value::Values(v) => Some(v.clone()),
+            Value::Mask(m) => Some(m.iter().map(|&x| x as u8 as f64).collect()),
+            Value::Scalar(_) => None,
+        }
+    }
+
+    /// Mask over the filter's input rows: the predicate result, narrowed by
+    /// any restriction `keep` expressed over the filter's output rows
+    fn filter_mask(&mut self, predicate: u32, keep: Option<&[bool]>) -> Option<Vec<bool>> {
+        let mut mask = as_mask(self.eval(predicate)?.as_ref())?;
+        if let Some(keep) = keep {
+            let mut outer = keep.iter();
+            for m in mask.iter_mut().filter(|m| **m) {
+                *m = *outer.next()?;
+            }
+            if outer.next().is_some() {
+                return None;
+            }
+        }
+        Some(mask)
+    }
+
+    /// Stream the values of `id` into `sink` without materializing filters
+    /// (fused filter + aggregation); sorts are skipped as order-irrelevant.
+    fn accumulate(&mut self, id: u32, keep: Option<&[bool]>, sink: &mut dyn FnMut(f64)) -> Option<()> {
+        let nodes = self.nodes;
+        match nodes.get(id as usize)? {
+            Node::Column(series_id) => {
+                let data = unsafe { f64_values(*series_id) }?;
+                match keep {
+                    Some(mask) if mask.len() != data.len() => return None,
+                    Some(mask) => data.iter().zip(mask).filter(|(_, &k)| k).for_each(|(v, _)| sink(v)),
+                    None => data.iter().for_each(&mut *sink),
+                }
+                Some(())
+            }
+            Node::Filter { input, predicate } => {
+                let mask = self.filter_mask(*predicate, keep)?;
+                self.accumulate(*input, Some(&mask), sink)
+            }
+            Node::Sort { input, .. } if keep.is_none() => self.accumulate(*input, None, sink),
+            _ => {
+                let value = match keep {
+                    None => self.eval(id)?,
+                    Some(_) => Rc::new(self.compute(id, keep)?),
+                };
+                match value.as_ref() {
+                    Value::Scalar(v) => sink(*v),
+                    Value::Values(v) => v.iter().for_each(|x| sink(*x)),
+                    Value::Mask(m) => m.iter().for_each(|&x| sink(x as u8 as f64)),
+                }
+                Some(())
+            }
+        }
+    }
+}
+
+fn restrict(value: Value, keep: Option<&[bool]>) -> Option<Value> {
+    let mask = match keep {
+        None => return Some(value),
+        Some(m) => m,
+    };
+    if value.len().is_some_and(|n| n != mask.len()) {
+        return None;
+    }
+    Some(match value {
+        Value::Scalar(v) => Value::Scalar(v),
+        Value::Values(v) => Value::Values(v.into_iter().zip(mask).filter(|(_, &k)| k).map(|(x, _)| x).collect()),
+        Value::Mask(m) => Value::Mask(m.into_iter().zip(mask).filter(|(_, &k)| k).map(|(x, _)| x).collect()),
+    })
+}
+
+/// Reference a registered f64 series
+#[wasm_bindgen]
+pub fn engine_expr_col(series_id: u32) -> u32 {
+    push_node(Node::Column(series_id), &[])
+}
+
+/// Scalar literal, broadcast against series operands
+#[wasm_bindgen]
+pub fn engine_expr_lit(value: f64) -> u32 {
+    push_node(Node::Literal(value), &[])
+}
+
+/// Comparison producing a mask (op: 0=eq, 1=ne, 2=lt, 3=le, 4=gt, 5=ge; nulls never match)
+#[wasm_bindgen]
+pub fn engine_expr_compare(op: u8, left: u32, right: u32) -> u32 {
+    push_node(Node::Compare { op, left, right }, &[left, right])
+}
+
+/// Logical AND of two masks
+#[wasm_bindgen]
+pub fn engine_expr_and(left: u32, right: u32) -> u32 {
+    push_node(Node::Logical { op: LOGIC_AND, left, right }, &[left, right])
+}
+
+/// Logical OR of two masks
+#[wasm_bindgen]
+pub fn engine_expr_or(left: u32, right: u32) -> u32 {
+    push_node(Node::Logical { op: LOGIC_OR, left, right }, &[left, right])
+}
+
+/// Logical NOT of a mask
+#[wasm_bindgen]
+pub fn engine_expr_not(input: u32) -> u32 {
+    push_node(Node::Not(input), &[input])
+}
+
+/// Element-wise arithmetic (op: 0=add, 1=sub, 2=mul, 3=div)
+#[wasm_bindgen]
+pub fn engine_expr_arith(op: u8, left: u32, right: u32) -> u32 {
+    push_node(Node::Arith { op, left, right }, &[left, right])
+}
+
+/// Keep the rows of `input` where `predicate` is true
+#[wasm_bindgen]
+pub fn engine_expr_filter(input: u32, predicate: u32) -> u32 {
+    push_node(Node::Filter { input, predicate }, &[input, predicate])
+}
+
+/// Sort values (NaN treated as null)
+#[wasm_bindgen]
+pub fn engine_expr_sort(input: u32, ascending: u8, nulls_last: u8) -> u32 {
+    push_node(Node::Sort { input, ascending: ascending != 0, nulls_last: nulls_last != 0 }, &[input])
+}
+
+/// Scalar aggregation (agg: 0=sum, 1=mean, 2=count, 3=min, 4=max, 5=std, 6=var)
+#[wasm_bindgen]
+pub fn engine_expr_agg(input: u32, agg: u8) -> u32 {
+    push_node(Node::Agg { input, agg }, &[input])
+}
+
+/// Grouped aggregation with JSON keys aligned to `input`; groups come in
+/// groupby order like the `engine_groupby_*` functions
+/// (`engine_set_groupby_order`, applied when the expression is collected)
+#[wasm_bindgen]
+pub fn engine_expr_groupby(input: u32, group_keys_json: &str, agg: u8) -> u32 {
+    let keys: Vec<String> = match serde_json::from_str(group_keys_json) {
+        Ok(k) => k,
+        Err(_) => return u32::MAX,
+    };
+    push_node(Node::GroupBy { input, keys, agg }, &[input])
+}
+
+/// Optimize and execute the expression rooted at `root`, registering the
+/// result as a new f64 series (scalars become length-1 series, masks 1/0).
+/// Returns u32::MAX on unknown nodes/series, mismatched lengths or cancellation.
+#[wasm_bindgen]
+pub fn engine_expr_collect(root: u32) -> u32 {
+    let _prof = profile("engine_expr_collect", || 0);
+    let _cancel = CancelScope::enter();
+    catch_panic(u32::MAX, || collect_expr(root))
+}
+
+fn collect_expr(root: u32) -> u32 {
+    let result = EXPRS.with(|cell| {
+        let nodes = cell.borrow();
+        // A collect with groupby nodes is one groupby call (`take_group_order`)
+        let order = if contains_groupby(&nodes, root) { take_group_order() } else { GroupOrdering::default() };
+        let mut evaluator = Evaluator { nodes: &nodes, memo: HashMap::new(), order };
+        evaluator.eval(root)
+    });
+    if take_cancel_request() {
+        engine_log!(info, "engine_expr_collect: cancelled root={}", root);
+        return u32::MAX;
+    }
+    let data: Vec<f64> = match result.as_deref() {
+        Some(Value::Scalar(v)) => vec![*v],
+        Some(Value::Values(v)) => v.clone(),
+        Some(Value::Mask(m)) => m.iter().map(|&x| x as u8 as f64).collect(),
+        None => {
+            engine_log!(warn, "engine_expr_collect: evaluation failed root={}", root);
+            return u32::MAX;
+        }
+    };
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&data))
+}
+
+/// Drop all expression nodes (previously returned node ids become invalid)
+#[wasm_bindgen]
+pub fn engine_expr_clear() {
+    EXPRS.with(|cell| cell.borrow_mut().clear());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::engine_create_series_f64;
+    use crate::filtering::{engine_filter_f64, CMP_GT, CMP_LT};
+    use crate::series::{engine_series_mean_f64, engine_series_scalar_op_f64, engine_series_std_f64, engine_series_to_vec_f64, ARITH_MUL};
+    use crate::statistics::{AGG_MEAN, AGG_STD};
+
+    const VALUES: [f64; 6] = [4.0, f64::NAN, 1.0, 3.0, 2.0, 5.0];
+
+    fn collect(root: u32) -> Vec<f64> {
+        engine_series_to_vec_f64(engine_expr_collect(root))
+    }
+
+    fn mask(values: &[f64], keep: impl Fn(f64) -> bool) -> Vec<u8> {
+        values.iter().map(|&v| keep(v) as u8).collect()
+    }
+
+    #[test]
+    fn filter_over_arithmetic_matches_the_kernels() {
+        let series = engine_create_series_f64(&VALUES);
+        let col = engine_expr_col(series);
+        let doubled = engine_expr_arith(ARITH_MUL, col, engine_expr_lit(2.0));
+        let root = engine_expr_filter(doubled, engine_expr_compare(CMP_GT, col, engine_expr_lit(1.5)));
+        let direct = engine_filter_f64(engine_series_scalar_op_f64(series, ARITH_MUL, 2.0, 0), &mask(&VALUES, |v| v > 1.5));
+        assert_eq!(collect(root), engine_series_to_vec_f64(direct));
+        assert_eq!(collect(root), [8.0, 6.0, 4.0, 10.0]);
+    }
+
+    #[test]
+    fn nested_filters_narrow_the_kept_rows() {
+        let series = engine_create_series_f64(&VALUES);
+        let col = engine_expr_col(series);
+        let inner = engine_expr_filter(col, engine_expr_compare(CMP_GT, col, engine_expr_lit(1.5)));
+        let root = engine_expr_filter(inner, engine_expr_compare(CMP_LT, inner, engine_expr_lit(4.5)));
+        let first = engine_filter_f64(series, &mask(&VALUES, |v| v > 1.5));
+        let kept = engine_series_to_vec_f64(first);
+        let direct = engine_filter_f64(first, &mask(&kept, |v| v < 4.5));
+        assert_eq!(collect(root), engine_series_to_vec_f64(direct));
+        assert_eq!(collect(root), [4.0, 3.0, 2.0]);
+    }
+
+    #[test]
+    fn aggregates_skip_the_sort_of_a_filtered_input() {
+        let series = engine_create_series_f64(&VALUES);
+        let col = engine_expr_col(series);
+        let filtered = engine_expr_filter(col, engine_expr_compare(CMP_LT, col, engine_expr_lit(4.5)));
+        let sorted = engine_expr_sort(filtered, 0, 1);
+        let direct = engine_filter_f64(series, &mask(&VALUES, |v| v < 4.5));
+        assert_eq!(collect(engine_expr_agg(sorted, AGG_MEAN)), [engine_series_mean_f64(direct)]);
+        let std = collect(engine_expr_agg(sorted, AGG_STD))[0];
+        assert!((std - engine_series_std_f64(direct)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn mismatched_lengths_fail() {
+        let long = engine_expr_col(engine_create_series_f64(&VALUES));
+        let short = engine_expr_col(engine_create_series_f64(&[1.0, 2.0]));
+        assert_eq!(engine_expr_collect(engine_expr_arith(ARITH_MUL, long, short)), u32::MAX);
+        let predicate = engine_expr_compare(CMP_GT, short, engine_expr_lit(0.0));
+        assert_eq!(engine_expr_collect(engine_expr_filter(long, predicate)), u32::MAX);
+        assert_eq!(engine_expr_collect(engine_expr_agg(engine_expr_filter(long, predicate), AGG_MEAN)), u32::MAX);
+    }
+
+    #[test]
+    fn full_arena_rejects_nodes_until_cleared() {
+        engine_expr_clear();
+        for _ in 0..MAX_NODES {
+            assert_ne!(engine_expr_lit(1.0), u32::MAX);
+        }
+        assert_eq!(engine_expr_lit(1.0), u32::MAX);
+        engine_expr_clear();
+        assert_eq!(engine_expr_lit(1.0), 0);
+    }
+
+    #[test]
+    fn logical_masks_combine_comparisons() {
+        let col = engine_expr_col(engine_create_series_f64(&VALUES));
+        let above = engine_expr_compare(CMP_GT, col, engine_expr_lit(1.5));
+        let below = engine_expr_compare(CMP_LT, col, engine_expr_lit(4.5));
+        assert_eq!(collect(engine_expr_filter(col, engine_expr_and(above, below))), [4.0, 3.0, 2.0]);
+        let low = engine_expr_compare(CMP_LT, col, engine_expr_lit(1.5));
+        let high = engine_expr_compare(CMP_GT, col, engine_expr_lit(4.5));
+        assert_eq!(collect(engine_expr_filter(col, engine_expr_or(low, high))), [1.0, 5.0]);
+        // A null never matches a comparison, so its negation keeps it
+        let kept = collect(engine_expr_filter(col, engine_expr_not(above)));
+        assert_eq!(format!("{:?}", kept), "[NaN, 1.0]");
+    }
+}
+
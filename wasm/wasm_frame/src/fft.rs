@@ -0,0 +1,237 @@
+//! Spectral analysis: FFT and periodogram
+//!
+//! Transforms of real-valued series of any length: power-of-two lengths use
+//! an iterative radix-2 FFT, other lengths Bluestein's algorithm on top of
+//! it, so every size is O(n log n). It is only compiled with the `fft`
+//! feature.
+
+use std::f64::consts::PI;
+use wasm_bindgen::prelude::*;
+use crate::core::{f64_values, ENGINE};
+use crate::profiling::{profile, series_bytes};
+
+#[derive(Clone, Copy, Default)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn new(re: f64, im: f64) -> Self {
+        Complex { re, im }
+    }
+
+    /// `e^(i * angle)`
+    fn from_angle(angle: f64) -> Self {
+        Complex::new(angle.cos(), angle.sin())
+    }
+
+    fn mul(self, o: Complex) -> Complex {
+        Complex::new(self.re * o.re - self.im * o.im, self.re * o.im + self.im * o.re)
+    }
+
+    fn conj(self) -> Complex {
+        Complex::new(self.re, -self.im)
+    }
+
+    fn norm_sqr(self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+}
+
+/// In-place radix-2 FFT (`inverse` without the 1/n scaling); the length
+/// must be a power of two
+fn fft_pow2(data: &mut [Complex], inverse: bool) {
+    let n = data.len();
+    if n < 2 {
+        return;
+    }
+    // Bit-reversal permutation
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            data.swap(i, j);
+        }
+    }
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let step = Complex::from_angle(sign * 2.0 * PI / len as f64);
+        for start in (0..n).step_by(len) {
+            let mut w = Complex::new(1.0, 0.0);
+            for k in 0..len / 2 {
+                let a = data[start + k];
+                let b = data[start + k + len / 2].mul(w);
+                data[start + k] = Complex::new(a.re + b.re, a.im + b.im);
+                data[start + k + len / 2] = Complex::new(a.re - b.re, a.im - b.im);
+                w = w.mul(step);
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Discrete Fourier transform `X[k] = sum x[j] e^(-2 pi i jk / n)` of any
+/// length (unscaled, as numpy's `fft`)
+fn dft(input: &[Complex]) -> Vec<Complex> {
+    let n = input.len();
+    if n.is_power_of_two() || n == 0 {
+        let mut data = input.to_vec();
+        fft_pow2(&mut data, false);
+        return data;
+    }
+    // Bluestein: jk = (j^2 + k^2 - (k - j)^2) / 2 turns the DFT into a
+    // convolution with the chirp e^(i pi m^2 / n), done with power-of-two FFTs
+    let chirp: Vec<Complex> = (0..n)
+        .map(|k| {
+            // k^2 mod 2n keeps the angle small and exact
+            let k2 = (k as u128 * k as u128 % (2 * n as u128)) as f64;
+            Complex::from_angle(-PI * k2 / n as f64)
+        })
+        .collect();
+    let m = (2 * n - 1).next_power_of_two();
+    let mut a = vec![Complex::default(); m];
+    for k in 0..n {
+        a[k] = input[k].mul(chirp[k]);
+    }
+    let mut b = vec![Complex::default(); m];
+    b[0] = chirp[0].conj();
+    for k in 1..n {
+        b[k] = chirp[k].conj();
+        b[m - k] = chirp[k].conj();
+    }
+    fft_pow2(&mut a, false);
+    fft_pow2(&mut b, false);
+    for (x, y) in a.iter_mut().zip(&b) {
+        *x = x.mul(*y);
+    }
+    fft_pow2(&mut a, true);
+    let scale = 1.0 / m as f64;
+    (0..n).map(|k| Complex::new(a[k].re * scale, a[k].im * scale).mul(chirp[k])).collect()
+}
+
+/// Non-negative frequency half of the DFT of a float64 series without
+/// nulls (`n / 2 + 1` bins, as numpy's `rfft`) and `n`; None (logged) if
+/// the id is unknown, the series is empty or has a null
+fn rfft(name: &str, series_id: u32, detrend: bool) -> Option<(Vec<Complex>, usize)> {
+    let values = match unsafe { f64_values(series_id) } {
+        Some(values) if !values.is_empty() && values.iter().all(|v| !v.is_nan()) => values,
+        _ => {
+            engine_log!(warn, "{}: unknown or empty series, or nulls present series_id={}", name, series_id);
+            return None;
+        }
+    };
+    let mean = if detrend { values.iter().sum::<f64>() / values.len() as f64 } else { 0.0 };
+    let input: Vec<Complex> = values.iter().map(|v| Complex::new(v - mean, 0.0)).collect();
+    let mut spectrum = dft(&input);
+    spectrum.truncate(values.len() / 2 + 1);
+    Some((spectrum, values.len()))
+}
+
+/// Real FFT of a float64 series of any length: magnitude and phase (in
+/// radians, `atan2(im, re)`) of the `n / 2 + 1` non-negative frequency bins,
+/// unscaled as numpy's `rfft`; bin `k` is frequency `k * fs / n` for a
+/// sample rate `fs`.
+///
+/// Returns `[magnitude_id, phase_id]`, or an empty array if the id is
+/// unknown, the series is empty or it contains nulls (fill them first).
+#[wasm_bindgen]
+pub fn engine_fft_f64(series_id: u32) -> Box<[u32]> {
+    let _prof = profile("engine_fft_f64", || series_bytes(series_id));
+    let spectrum = match rfft("engine_fft_f64", series_id, false) {
+        Some((spectrum, _)) => spectrum,
+        None => return Box::new([]),
+    };
+    let magnitude: Vec<f64> = spectrum.iter().map(|c| c.norm_sqr().sqrt()).collect();
+    let phase: Vec<f64> = spectrum.iter().map(|c| c.im.atan2(c.re)).collect();
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        Box::new([eng.register_series_f64(&magnitude), eng.register_series_f64(&phase)])
+    })
+}
+
+/// Power spectral density estimate of a series sampled at `fs` Hz, as
+/// scipy's `signal.periodogram` defaults: mean removed, no window, one-sided
+/// density scaling `|X[k]|^2 / (fs * n)` with every bin but DC (and Nyquist
+/// for even `n`) doubled.
+///
+/// Returns `[frequency_id, power_id]` with `n / 2 + 1` rows, or an empty
+/// array if the id is unknown, the series is empty or has nulls, or `fs`
+/// is not positive.
+#[wasm_bindgen]
+pub fn engine_periodogram(series_id: u32, fs: f64) -> Box<[u32]> {
+    let _prof = profile("engine_periodogram", || series_bytes(series_id));
+    if !(fs > 0.0 && fs.is_finite()) {
+        return Box::new([]);
+    }
+    let (spectrum, n) = match rfft("engine_periodogram", series_id, true) {
+        Some(result) => result,
+        None => return Box::new([]),
+    };
+    let frequency: Vec<f64> = (0..spectrum.len()).map(|k| k as f64 * fs / n as f64).collect();
+    let power: Vec<f64> = spectrum
+        .iter()
+        .enumerate()
+        .map(|(k, c)| {
+            let one_sided = k > 0 && !(n % 2 == 0 && k == n / 2);
+            c.norm_sqr() / (fs * n as f64) * if one_sided { 2.0 } else { 1.0 }
+        })
+        .collect();
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        Box::new([eng.register_series_f64(&frequency), eng.register_series_f64(&power)])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::engine_create_series_f64;
+    use crate::series::engine_series_to_vec_f64;
+
+    fn naive_dft(input: &[Complex]) -> Vec<Complex> {
+        let n = input.len();
+        (0..n)
+            .map(|k| {
+                input.iter().enumerate().fold(Complex::default(), |acc, (j, x)| {
+                    let term = x.mul(Complex::from_angle(-2.0 * PI * (j * k) as f64 / n as f64));
+                    Complex::new(acc.re + term.re, acc.im + term.im)
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_length_matches_the_naive_transform() {
+        for n in [1, 2, 5, 6, 8, 13] {
+            let input: Vec<Complex> = (0..n).map(|j| Complex::new((j * j % 7) as f64 - 2.0, (j % 3) as f64)).collect();
+            for (fast, slow) in dft(&input).iter().zip(naive_dft(&input)) {
+                assert!((fast.re - slow.re).abs() < 1e-9 && (fast.im - slow.im).abs() < 1e-9, "n = {}", n);
+            }
+        }
+    }
+
+    #[test]
+    fn spectra_of_pure_tones() {
+        let tone: Vec<f64> = (0..8).map(|j| (2.0 * PI * 2.0 * j as f64 / 8.0).cos()).collect();
+        let ids = engine_fft_f64(engine_create_series_f64(&tone));
+        let magnitude = engine_series_to_vec_f64(ids[0]);
+        assert_eq!(magnitude.len(), 5);
+        assert!(magnitude.iter().enumerate().all(|(k, m)| (m - if k == 2 { 4.0 } else { 0.0 }).abs() < 1e-9));
+        // A unit sine at 1 Hz sampled at 8 Hz has power 0.5 / Hz in its bin
+        let sine: Vec<f64> = (0..8).map(|j| 3.0 + (2.0 * PI * j as f64 / 8.0).sin()).collect();
+        let ids = engine_periodogram(engine_create_series_f64(&sine), 8.0);
+        assert_eq!(engine_series_to_vec_f64(ids[0]), [0.0, 1.0, 2.0, 3.0, 4.0]);
+        let power = engine_series_to_vec_f64(ids[1]);
+        assert!(power.iter().enumerate().all(|(k, p)| (p - if k == 1 { 0.5 } else { 0.0 }).abs() < 1e-9));
+        assert!(engine_periodogram(engine_create_series_f64(&sine), 0.0).is_empty());
+        assert!(engine_fft_f64(engine_create_series_f64(&[1.0, f64::NAN])).is_empty());
+        assert!(engine_fft_f64(engine_create_series_f64(&[])).is_empty());
+    }
+}
@@ -4,52 +4,181 @@
 //! both through the engine (using registered series) and directly on arrays.
 
 use wasm_bindgen::prelude::*;
-use crate::core::ENGINE;
+use crate::core::{f64_values, RleSeries, ENGINE};
+use crate::parallel::map_chunks;
+use crate::profiling::{profile, series_bytes};
 
 /// Filter float64 series using a boolean mask (1=true, 0=false)
 #[wasm_bindgen]
 pub fn engine_filter_f64(series_id: u32, mask: &[u8]) -> u32 {
-    let (src_ptr, src_len) = ENGINE.with(|cell| {
-        let eng = cell.borrow();
-        if let Some((ptr, len)) = eng.series_store.get(&series_id) {
-            (*ptr, *len)
-        } else {
-            (std::ptr::null_mut(), 0)
+    let _prof = profile("engine_filter_f64", || series_bytes(series_id));
+    let values = unsafe { f64_values(series_id) };
+    let src_len = values.as_ref().map_or(0, |v| v.len());
+    let values = match values {
+        Some(values) if src_len > 0 && mask.len() == src_len => values,
+        _ => {
+            engine_log!(warn, "engine_filter_f64: unknown series or mask mismatch series_id={} len={} mask_len={}", series_id, src_len, mask.len());
+            return u32::MAX;
         }
-    });
-    if src_ptr.is_null() || src_len == 0 || mask.len() != src_len {
-        return u32::MAX;
-    }
-    let mut out: Vec<f64> = Vec::new();
-    unsafe {
-        for i in 0..src_len {
-            if mask[i] != 0 {
-                out.push(*src_ptr.add(i));
-            }
+    };
+    let out: Vec<f64> = values
+        .chunks()
+        .flat_map(|(start, data)| {
+            map_chunks(data, |offset, chunk| {
+                let chunk_mask = &mask[start + offset..start + offset + chunk.len()];
+                chunk.iter().zip(chunk_mask).filter(|(_, &keep)| keep != 0).map(|(v, _)| *v).collect::<Vec<f64>>()
+            })
+        })
+        .collect::<Vec<Vec<f64>>>()
+        .concat();
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&out))
+}
+
+/// Filter every column of a frame with a boolean mask (1=keep), returning a
+/// new frame, or u32::MAX if the frame is unknown or the mask length differs
+#[wasm_bindgen]
+pub fn engine_frame_filter(frame_id: u32, mask: &[u8]) -> u32 {
+    let _prof = profile("engine_frame_filter", || mask.len());
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        let nrows = match eng.frames.get(&frame_id) {
+            Some(frame) => eng.frame_nrows(frame),
+            None => return u32::MAX,
+        };
+        if mask.len() != nrows {
+            engine_log!(warn, "engine_frame_filter: mask mismatch frame_id={} rows={} mask_len={}", frame_id, nrows, mask.len());
+            return u32::MAX;
         }
+        let rows: Vec<usize> = (0..nrows).filter(|&i| mask[i] != 0).collect();
+        eng.take_frame_rows(frame_id, &rows).unwrap_or(u32::MAX)
+    })
+}
+
+// Comparison operator codes: 0=eq, 1=ne, 2=lt, 3=le, 4=gt, 5=ge
+pub(crate) const CMP_EQ: u8 = 0;
+pub(crate) const CMP_NE: u8 = 1;
+pub(crate) const CMP_LT: u8 = 2;
+pub(crate) const CMP_LE: u8 = 3;
+pub(crate) const CMP_GT: u8 = 4;
+pub(crate) const CMP_GE: u8 = 5;
+
+/// Evaluate a comparison code; nulls (NaN) never match, including for `ne`
+pub(crate) fn compare_f64(op: u8, a: f64, b: f64) -> bool {
+    if a.is_nan() || b.is_nan() {
+        return false;
+    }
+    match op {
+        CMP_EQ => a == b,
+        CMP_NE => a != b,
+        CMP_LT => a < b,
+        CMP_LE => a <= b,
+        CMP_GT => a > b,
+        CMP_GE => a >= b,
+        _ => false,
     }
+}
+
+/// Evaluate a comparison (see `compare_f64`) against a run-length encoded
+/// series once per run, expanding the result to a 0/1 row mask. Returns an
+/// empty array if the id is not an RLE series.
+#[wasm_bindgen]
+pub fn engine_rle_compare_mask(rle_id: u32, op: u8, value: f64) -> Vec<u8> {
+    let _prof = profile("engine_rle_compare_mask", || series_bytes(rle_id));
     ENGINE.with(|cell| {
-        let mut eng = cell.borrow_mut();
-        let id = eng.next_series_id;
-        eng.next_series_id = eng.next_series_id.wrapping_add(1);
-        let len = out.len();
-        let dst_ptr = unsafe {
-            let layout = std::alloc::Layout::from_size_align(
-                len * std::mem::size_of::<f64>(),
-                std::mem::align_of::<f64>(),
-            )
-            .unwrap();
-            let raw = std::alloc::alloc(layout) as *mut f64;
-            if !raw.is_null() && len > 0 {
-                std::ptr::copy_nonoverlapping(out.as_ptr(), raw, len);
-            }
-            raw
+        let eng = cell.borrow();
+        let rle = match eng.series_store_rle.get(&rle_id) {
+            Some(rle) => rle,
+            None => return Vec::new(),
         };
-        eng.series_store.insert(id, (dst_ptr, len));
-        id
+        let mut mask = Vec::with_capacity(rle.len());
+        for (start, end, v) in rle.runs() {
+            mask.extend(std::iter::repeat_n(compare_f64(op, v, value) as u8, end - start));
+        }
+        mask
     })
 }
 
+/// Filter a run-length encoded series with a row mask (1=keep), returning a
+/// new RLE series; runs are trimmed rather than decoded. Returns u32::MAX if
+/// the id is unknown or the mask length differs.
+#[wasm_bindgen]
+pub fn engine_filter_rle(rle_id: u32, mask: &[u8]) -> u32 {
+    let _prof = profile("engine_filter_rle", || series_bytes(rle_id));
+    let rle = match ENGINE.with(|cell| cell.borrow().series_store_rle.get(&rle_id).cloned()) {
+        Some(rle) if rle.len() == mask.len() => rle,
+        _ => {
+            engine_log!(warn, "engine_filter_rle: unknown series or mask mismatch rle_id={} mask_len={}", rle_id, mask.len());
+            return u32::MAX;
+        }
+    };
+    let mut out = RleSeries::default();
+    for (start, end, v) in rle.runs() {
+        let kept = mask[start..end].iter().filter(|&&keep| keep != 0).count() as u32;
+        // Dropping whole runs can make equal values adjacent; push_run merges them
+        if kept > 0 {
+            out.push_run(v, kept);
+        }
+    }
+    ENGINE.with(|cell| cell.borrow_mut().register_series_rle(out))
+}
+
+/// Number of values of an f64 series for which `value <op> threshold` holds
+/// (see `compare_f64`; nulls never match), without building a mask. 0 if
+/// the id is unknown.
+#[wasm_bindgen]
+pub fn engine_count_where_f64(series_id: u32, op: u8, threshold: f64) -> u32 {
+    let _prof = profile("engine_count_where_f64", || series_bytes(series_id));
+    let values = match unsafe { f64_values(series_id) } { Some(v) => v, None => return 0 };
+    values
+        .chunks()
+        .flat_map(|(_, data)| map_chunks(data, |_, chunk| chunk.iter().filter(|&&v| compare_f64(op, v, threshold)).count() as u32))
+        .sum()
+}
+
+/// Run `f` on the values of a registered mask (a bool or uint8 series,
+/// non-zero = true); None if the id is not one
+pub(crate) fn with_mask<R>(mask_id: u32, f: impl FnOnce(&[u8]) -> R) -> Option<R> {
+    ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        let &(ptr, len) = eng.series_store_bool.get(&mask_id).or_else(|| eng.series_store_u8.get(&mask_id))?;
+        Some(f(unsafe { std::slice::from_raw_parts(ptr, len) }))
+    })
+}
+
+/// Number of true rows of a registered mask (a bool or uint8 series,
+/// non-zero = true); 0 if the id is not a mask
+#[wasm_bindgen]
+pub fn engine_mask_count_true(mask_id: u32) -> u32 {
+    let _prof = profile("engine_mask_count_true", || series_bytes(mask_id));
+    with_mask(mask_id, |mask| mask.iter().filter(|&&v| v != 0).count() as u32).unwrap_or(0)
+}
+
+/// Whether any row of a registered mask is true, stopping at the first;
+/// false if the mask is empty or the id is not a mask
+#[wasm_bindgen]
+pub fn engine_mask_any(mask_id: u32) -> bool {
+    let _prof = profile("engine_mask_any", || series_bytes(mask_id));
+    with_mask(mask_id, |mask| mask.iter().any(|&v| v != 0)).unwrap_or(false)
+}
+
+/// Whether every row of a registered mask is true, stopping at the first
+/// false row; true if the mask is empty, false if the id is not a mask
+#[wasm_bindgen]
+pub fn engine_mask_all(mask_id: u32) -> bool {
+    let _prof = profile("engine_mask_all", || series_bytes(mask_id));
+    with_mask(mask_id, |mask| mask.iter().all(|&v| v != 0)).unwrap_or(false)
+}
+
+/// Whether an f64 series has a non-zero value, skipping nulls (as pandas'
+/// `Series.any()`), stopping at the first; false if the id is unknown
+#[wasm_bindgen]
+pub fn engine_series_any_nonzero_f64(series_id: u32) -> bool {
+    let _prof = profile("engine_series_any_nonzero_f64", || series_bytes(series_id));
+    let values = match unsafe { f64_values(series_id) } { Some(v) => v, None => return false };
+    let found = values.chunks().any(|(_, chunk)| chunk.iter().any(|&v| v != 0.0 && !v.is_nan()));
+    found
+}
+
 /// High-performance filtering with boolean mask (using u8 array for WASM compatibility)
 #[wasm_bindgen]
 pub fn filter_f64(data: &[f64], mask: &[u8]) -> Vec<f64> {
@@ -63,3 +192,56 @@ pub fn filter_f64(data: &[f64], mask: &[u8]) -> Vec<f64> {
         .map(|(&val, _)| val)
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{engine_chunked_append_f64, engine_chunked_create_f64, engine_create_series_bool, engine_create_series_f64, engine_create_series_u8};
+
+    #[test]
+    fn count_where_skips_nulls_and_spans_chunks() {
+        let id = engine_chunked_create_f64();
+        assert!(engine_chunked_append_f64(id, &[1.0, f64::NAN, 3.0]));
+        assert!(engine_chunked_append_f64(id, &[3.0, -2.0]));
+        assert_eq!(engine_count_where_f64(id, CMP_GE, 3.0), 2);
+        assert_eq!(engine_count_where_f64(id, CMP_NE, 3.0), 2);
+        assert_eq!(engine_count_where_f64(id, CMP_LT, f64::NAN), 0);
+        assert_eq!(engine_count_where_f64(u32::MAX - 1, CMP_EQ, 1.0), 0);
+        assert_eq!(engine_mask_count_true(engine_create_series_bool(&[1, 0, 1, 1])), 3);
+        assert_eq!(engine_mask_count_true(engine_create_series_u8(&[0, 2, 255])), 2);
+        assert_eq!(engine_mask_count_true(id), 0);
+    }
+
+    #[test]
+    fn any_and_all_follow_pandas_on_empty_and_null_input() {
+        let (mixed, ones, empty) = (engine_create_series_bool(&[0, 1, 0]), engine_create_series_u8(&[1, 3]), engine_create_series_bool(&[]));
+        assert!(engine_mask_any(mixed) && !engine_mask_all(mixed));
+        assert!(engine_mask_any(ones) && engine_mask_all(ones));
+        assert!(!engine_mask_any(empty) && engine_mask_all(empty));
+        let floats = engine_create_series_f64(&[0.0, f64::NAN]);
+        assert!(!engine_mask_any(floats) && !engine_mask_all(floats));
+        assert!(!engine_series_any_nonzero_f64(floats));
+        let chunked = engine_chunked_create_f64();
+        assert!(engine_chunked_append_f64(chunked, &[0.0]));
+        assert!(engine_chunked_append_f64(chunked, &[f64::NAN, -0.5]));
+        assert!(engine_series_any_nonzero_f64(chunked));
+        assert!(!engine_series_any_nonzero_f64(mixed));
+    }
+
+    #[test]
+    fn rle_masks_and_filters_work_per_run() {
+        use crate::rle::{engine_rle_run_ends, engine_rle_values, engine_series_rle_encode};
+        let values = engine_create_series_f64(&[1.0, 1.0, f64::NAN, 2.0, 2.0, 1.0]);
+        let rle = engine_series_rle_encode(values);
+        assert_eq!(engine_rle_compare_mask(rle, CMP_GT, 1.0), [0, 0, 0, 1, 1, 0]);
+        assert_eq!(engine_rle_compare_mask(rle, CMP_NE, 2.0), [1, 1, 0, 0, 0, 1]);
+        assert!(engine_rle_compare_mask(values, CMP_GT, 1.0).is_empty());
+        // Dropping the null and 2.0 runs merges the surrounding 1.0 runs
+        let kept = engine_filter_rle(rle, &[1, 0, 0, 0, 0, 1]);
+        assert_eq!((engine_rle_values(kept), engine_rle_run_ends(kept)), (vec![1.0], vec![2]));
+        let trimmed = engine_filter_rle(rle, &[0, 1, 1, 0, 1, 0]);
+        assert_eq!(engine_rle_run_ends(trimmed), [1, 2, 3]);
+        assert_eq!(engine_filter_rle(rle, &[1, 1]), u32::MAX);
+        assert_eq!(engine_filter_rle(values, &[1; 6]), u32::MAX);
+    }
+}
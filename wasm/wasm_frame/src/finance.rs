@@ -0,0 +1,262 @@
+//! Technical indicators for price series
+//!
+//! Moving averages, RSI, MACD and Bollinger bands over float64 series, with
+//! the conventions of common charting libraries. Every indicator returns
+//! full-length series aligned with the input; rows before enough history
+//! (and null rows) are NaN. Also prepares waterfall charts from step
+//! contributions.
+
+use wasm_bindgen::prelude::*;
+use crate::core::{f64_values, ENGINE};
+use crate::profiling::{profile, series_bytes};
+
+/// Mean of each trailing window of `window` rows; NaN until the window is
+/// full and while it contains a null
+fn sma(values: &[f64], window: usize) -> Vec<f64> {
+    let mut out = vec![f64::NAN; values.len()];
+    let (mut sum, mut nulls) = (0.0, 0usize);
+    for (row, &v) in values.iter().enumerate() {
+        if v.is_nan() { nulls += 1 } else { sum += v }
+        if row >= window {
+            let old = values[row - window];
+            if old.is_nan() { nulls -= 1 } else { sum -= old }
+        }
+        if row + 1 >= window && nulls == 0 {
+            out[row] = sum / window as f64;
+        }
+    }
+    out
+}
+
+/// Exponential moving average with `alpha = 2 / (span + 1)` seeded by the
+/// first value, as pandas' `ewm(span, adjust=False, ignore_na=True)`:
+/// nulls are skipped and their rows repeat the last average
+fn ema(values: &[f64], span: usize) -> Vec<f64> {
+    let alpha = 2.0 / (span as f64 + 1.0);
+    let mut avg = f64::NAN;
+    values
+        .iter()
+        .map(|&v| {
+            if !v.is_nan() {
+                avg = if avg.is_nan() { v } else { avg + alpha * (v - avg) };
+            }
+            avg
+        })
+        .collect()
+}
+
+/// Values of a float64 series plus a positive parameter; None (logged) if
+/// the id is unknown or the parameter is 0
+fn indicator_input(name: &str, series_id: u32, param: u32) -> Option<Vec<f64>> {
+    match unsafe { f64_values(series_id) } {
+        Some(values) if param > 0 => Some(values.to_vec()),
+        _ => {
+            engine_log!(warn, "{}: unknown series or zero period series_id={}", name, series_id);
+            None
+        }
+    }
+}
+
+fn register(values: &[f64]) -> u32 {
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(values))
+}
+
+/// Simple moving average over `window` rows (pandas' `rolling(window)
+/// .mean()`): NaN for the first `window - 1` rows and for windows
+/// containing a null. Returns u32::MAX if the id is unknown or `window` is 0.
+#[wasm_bindgen]
+pub fn engine_sma(series_id: u32, window: u32) -> u32 {
+    let _prof = profile("engine_sma", || series_bytes(series_id));
+    match indicator_input("engine_sma", series_id, window) {
+        Some(values) => register(&sma(&values, window as usize)),
+        None => u32::MAX,
+    }
+}
+
+/// Exponential moving average with `alpha = 2 / (span + 1)`, seeded by the
+/// first value (pandas' `ewm(span=span, adjust=False).mean()`); nulls are
+/// skipped and their rows repeat the last average. Returns u32::MAX if the
+/// id is unknown or `span` is 0.
+#[wasm_bindgen]
+pub fn engine_ema(series_id: u32, span: u32) -> u32 {
+    let _prof = profile("engine_ema", || series_bytes(series_id));
+    match indicator_input("engine_ema", series_id, span) {
+        Some(values) => register(&ema(&values, span as usize)),
+        None => u32::MAX,
+    }
+}
+
+/// Relative strength index with Wilder's smoothing, in [0, 100]: average
+/// gains and losses are seeded with the mean of the first `period` price
+/// changes, then updated as `(avg * (period - 1) + change) / period`. The
+/// first `period` rows are NaN, as are rows with a null price (changes are
+/// taken between non-null prices) and rows where the price has not moved
+/// at all yet (0 / 0). Returns u32::MAX if the id is unknown or `period` is 0.
+#[wasm_bindgen]
+pub fn engine_rsi(series_id: u32, period: u32) -> u32 {
+    let _prof = profile("engine_rsi", || series_bytes(series_id));
+    let values = match indicator_input("engine_rsi", series_id, period) {
+        Some(values) => values,
+        None => return u32::MAX,
+    };
+    let p = period as f64;
+    let (mut prev, mut changes) = (f64::NAN, 0u32);
+    let (mut avg_gain, mut avg_loss) = (0.0, 0.0);
+    let out: Vec<f64> = values
+        .iter()
+        .map(|&v| {
+            if v.is_nan() {
+                return f64::NAN;
+            }
+            let change = v - prev;
+            prev = v;
+            if change.is_nan() {
+                return f64::NAN;
+            }
+            changes += 1;
+            let (gain, loss) = (change.max(0.0), (-change).max(0.0));
+            if changes <= period {
+                // Seed: plain mean of the first `period` changes
+                avg_gain += gain / p;
+                avg_loss += loss / p;
+                if changes < period {
+                    return f64::NAN;
+                }
+            } else {
+                avg_gain = (avg_gain * (p - 1.0) + gain) / p;
+                avg_loss = (avg_loss * (p - 1.0) + loss) / p;
+            }
+            100.0 * avg_gain / (avg_gain + avg_loss)
+        })
+        .collect();
+    register(&out)
+}
+
+/// Moving average convergence divergence: the `fast` minus the `slow` EMA
+/// of the series (see `engine_ema`; the usual periods are 12 and 26), its
+/// `signal` EMA (usually 9) and the histogram `macd - signal`.
+///
+/// Returns `[macd_id, signal_id, histogram_id]`, or an empty array if the
+/// id is unknown or a period is 0.
+#[wasm_bindgen]
+pub fn engine_macd(series_id: u32, fast: u32, slow: u32, signal: u32) -> Box<[u32]> {
+    let _prof = profile("engine_macd", || series_bytes(series_id));
+    let values = match indicator_input("engine_macd", series_id, fast.min(slow).min(signal)) {
+        Some(values) => values,
+        None => return Box::new([]),
+    };
+    let (fast_ema, slow_ema) = (ema(&values, fast as usize), ema(&values, slow as usize));
+    let macd: Vec<f64> = fast_ema.iter().zip(&slow_ema).map(|(f, s)| f - s).collect();
+    let signal_line = ema(&macd, signal as usize);
+    let histogram: Vec<f64> = macd.iter().zip(&signal_line).map(|(m, s)| m - s).collect();
+    Box::new([register(&macd), register(&signal_line), register(&histogram)])
+}
+
+/// Bollinger bands: the `window`-row simple moving average (see
+/// `engine_sma`) plus and minus `num_std` population standard deviations
+/// of the same window (usually 20 rows and 2).
+///
+/// Returns `[middle_id, upper_id, lower_id]`, or an empty array if the id
+/// is unknown or `window` is 0.
+#[wasm_bindgen]
+pub fn engine_bollinger(series_id: u32, window: u32, num_std: f64) -> Box<[u32]> {
+    let _prof = profile("engine_bollinger", || series_bytes(series_id));
+    let values = match indicator_input("engine_bollinger", series_id, window) {
+        Some(values) => values,
+        None => return Box::new([]),
+    };
+    let w = window as usize;
+    let middle = sma(&values, w);
+    // Deviations from each window's own mean; windows are short in practice
+    let width: Vec<f64> = middle
+        .iter()
+        .enumerate()
+        .map(|(row, &mean)| {
+            if mean.is_nan() {
+                return f64::NAN;
+            }
+            let ss: f64 = values[row + 1 - w..=row].iter().map(|v| (v - mean) * (v - mean)).sum();
+            num_std * (ss / w as f64).sqrt()
+        })
+        .collect();
+    let upper: Vec<f64> = middle.iter().zip(&width).map(|(m, d)| m + d).collect();
+    let lower: Vec<f64> = middle.iter().zip(&width).map(|(m, d)| m - d).collect();
+    Box::new([register(&middle), register(&upper), register(&lower)])
+}
+
+/// Waterfall chart preparation: with the series holding each step's
+/// contribution, returns `[delta_id, start_id, end_id]` where `end` is the
+/// running total after the step and `start` the total before it (so each
+/// bar spans `start..end`), and `delta` the contribution with nulls as 0
+/// (a null step leaves the total unchanged). Returns an empty array if the
+/// id is unknown.
+#[wasm_bindgen]
+pub fn engine_waterfall_f64(series_id: u32) -> Box<[u32]> {
+    let _prof = profile("engine_waterfall_f64", || series_bytes(series_id));
+    let values = match unsafe { f64_values(series_id) } { Some(v) => v, None => return Box::new([]) };
+    let delta: Vec<f64> = values.iter().map(|v| if v.is_nan() { 0.0 } else { v }).collect();
+    let mut total = 0.0;
+    let (start, end): (Vec<f64>, Vec<f64>) = delta
+        .iter()
+        .map(|d| {
+            let before = total;
+            total += d;
+            (before, total)
+        })
+        .unzip();
+    Box::new([register(&delta), register(&start), register(&end)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::engine_create_series_f64;
+    use crate::series::engine_series_to_vec_f64;
+
+    /// Values of a float64 series in debug form, so NaN compares equal
+    fn floats(id: u32) -> String {
+        format!("{:?}", engine_series_to_vec_f64(id))
+    }
+
+    #[test]
+    fn moving_averages_handle_history_and_nulls() {
+        let prices = engine_create_series_f64(&[1.0, 2.0, 3.0, f64::NAN, 5.0, 6.0, 7.0]);
+        assert_eq!(floats(engine_sma(prices, 2)), "[NaN, 1.5, 2.5, NaN, NaN, 5.5, 6.5]");
+        // span 3 gives alpha 0.5; the null row repeats the last average
+        let ema_input = engine_create_series_f64(&[2.0, f64::NAN, 4.0, 6.0]);
+        assert_eq!(floats(engine_ema(ema_input, 3)), "[2.0, 2.0, 3.0, 4.5]");
+        let bands = engine_bollinger(engine_create_series_f64(&[1.0, 3.0, f64::NAN]), 2, 1.0);
+        assert_eq!(bands.iter().map(|&id| floats(id)).collect::<Vec<_>>(), ["[NaN, 2.0, NaN]", "[NaN, 3.0, NaN]", "[NaN, 1.0, NaN]"]);
+        assert_eq!(engine_sma(prices, 0), u32::MAX);
+        assert!(engine_bollinger(u32::MAX - 1, 2, 1.0).is_empty());
+    }
+
+    #[test]
+    fn rsi_uses_wilder_smoothing() {
+        let prices = engine_create_series_f64(&[1.0, 2.0, 3.0, 2.0, f64::NAN, 4.0]);
+        let rsi = engine_series_to_vec_f64(engine_rsi(prices, 2));
+        assert!(rsi[0].is_nan() && rsi[1].is_nan() && rsi[4].is_nan());
+        assert_eq!(rsi[2..4], [100.0, 50.0]);
+        // Gains (0.5 + 2) / 2 against losses 0.5 / 2 after the null row
+        assert!((rsi[5] - 100.0 * 1.25 / 1.5).abs() < 1e-12);
+        let flat = engine_series_to_vec_f64(engine_rsi(engine_create_series_f64(&[5.0, 5.0]), 1));
+        assert!(flat[1].is_nan());
+    }
+
+    #[test]
+    fn macd_is_the_difference_of_two_emas() {
+        let prices = engine_create_series_f64(&[1.0, 2.0]);
+        let ids = engine_macd(prices, 1, 3, 1);
+        assert_eq!(ids.iter().map(|&id| engine_series_to_vec_f64(id)).collect::<Vec<_>>(), [[0.0, 0.5], [0.0, 0.5], [0.0, 0.0]]);
+        assert!(engine_macd(prices, 12, 26, 0).is_empty());
+    }
+
+    #[test]
+    fn waterfall_bars_span_the_running_total() {
+        let steps = engine_create_series_f64(&[10.0, -3.0, f64::NAN, 5.0]);
+        let ids = engine_waterfall_f64(steps);
+        let columns: Vec<Vec<f64>> = ids.iter().map(|&id| engine_series_to_vec_f64(id)).collect();
+        assert_eq!(columns, [[10.0, -3.0, 0.0, 5.0], [0.0, 10.0, 7.0, 7.0], [10.0, 7.0, 7.0, 12.0]]);
+        assert!(engine_waterfall_f64(u32::MAX - 1).is_empty());
+    }
+}
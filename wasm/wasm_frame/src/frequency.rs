@@ -0,0 +1,205 @@
+//! Value counts and frequency tables
+//!
+//! Counts distinct values of a series of any dtype. Numbers (including
+//! bools as 0/1 and decimals in real units) count by value, strings by
+//! content; nulls are left out, as pandas' `value_counts()`.
+
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+use crate::cast::Source;
+use crate::core::{packed_strs, ENGINE};
+use crate::groupby::key_codes;
+use crate::join::{labels, Label, LabelSeries};
+use crate::profiling::{profile, series_bytes};
+
+/// Distinct non-null values of a series with their counts, most frequent
+/// first (ties in order of first occurrence)
+fn value_counts<'a>(source: &Source<'a>) -> Vec<(Label<'a>, u32)> {
+    // label -> (count, first row)
+    let mut counts: HashMap<Label, (u32, usize)> = HashMap::new();
+    for (row, label) in labels(source).into_iter().enumerate() {
+        if label != Label::Null {
+            counts.entry(label).or_insert((0, row)).0 += 1;
+        }
+    }
+    let mut counts: Vec<(Label, (u32, usize))> = counts.into_iter().collect();
+    counts.sort_unstable_by(|a, b| b.1 .0.cmp(&a.1 .0).then(a.1 .1.cmp(&b.1 .1)));
+    counts.into_iter().map(|(label, (count, _))| (label, count)).collect()
+}
+
+/// Frequency table of a series of any dtype: its distinct non-null values,
+/// most frequent first (ties in order of first occurrence), with how often
+/// each occurs, its percentage of the non-null rows and the cumulative
+/// percentage down the table (reaching 100).
+///
+/// Returns `[values_id, counts_id, percent_id, cumulative_percent_id]`:
+/// values as float64 for numeric and bool series or as strings for string
+/// series, counts as uint32 and percentages as float64. An empty array if
+/// the id is unknown.
+#[wasm_bindgen]
+pub fn engine_freq_table(series_id: u32) -> Box<[u32]> {
+    let _prof = profile("engine_freq_table", || series_bytes(series_id));
+    let table = ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        let source = Source::new(&eng, series_id)?;
+        let counts = value_counts(&source);
+        let values: Vec<Label> = counts.iter().map(|&(label, _)| label).collect();
+        Some((LabelSeries::new(&values), counts.into_iter().map(|(_, count)| count).collect::<Vec<u32>>()))
+    });
+    let (values, counts) = match table {
+        Some(table) => table,
+        None => {
+            engine_log!(warn, "engine_freq_table: unknown series {}", series_id);
+            return Box::new([]);
+        }
+    };
+    let total: u32 = counts.iter().sum();
+    let percent: Vec<f64> = counts.iter().map(|&count| 100.0 * count as f64 / total as f64).collect();
+    let mut running = 0u32;
+    let cumulative: Vec<f64> = counts
+        .iter()
+        .map(|&count| {
+            running += count;
+            100.0 * running as f64 / total as f64
+        })
+        .collect();
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        Box::new([
+            values.register(&mut eng),
+            eng.register_series_u32(&counts),
+            eng.register_series_f64(&percent),
+            eng.register_series_f64(&cumulative),
+        ])
+    })
+}
+
+/// Value counts of strings in the packed binary protocol (see
+/// `isin_string_packed`), without registering a series. Distinct strings
+/// are ordered most frequent first (ties in order of first occurrence).
+///
+/// Returns `2 * k` values for `k` distinct strings: the row of each
+/// string's first occurrence (to slice the caller's own copy of the
+/// strings) followed by the matching counts, or an empty array if the
+/// packed array is malformed.
+#[wasm_bindgen]
+pub fn value_counts_str_packed(bytes: &[u8], offsets: &[u32]) -> Vec<u32> {
+    let strings = match packed_strs(bytes, offsets) {
+        Some(strings) => strings,
+        None => {
+            engine_log!(warn, "value_counts_str_packed: malformed buffers bytes={} offsets={}", bytes.len(), offsets.len());
+            return Vec::new();
+        }
+    };
+    // string -> (count, first row)
+    let mut counts: HashMap<&str, (u32, u32)> = HashMap::new();
+    for (row, s) in strings.into_iter().enumerate() {
+        counts.entry(s).or_insert((0, row as u32)).0 += 1;
+    }
+    let mut counts: Vec<(u32, u32)> = counts.into_values().collect();
+    counts.sort_unstable_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    counts.iter().map(|&(_, row)| row).chain(counts.iter().map(|&(count, _)| count)).collect()
+}
+
+/// Counts of the distinct non-null codes (in no particular order)
+pub(crate) fn code_counts(codes: impl IntoIterator<Item = u32>) -> Vec<u32> {
+    let mut counts: HashMap<u32, u32> = HashMap::new();
+    for code in codes {
+        if code != u32::MAX {
+            *counts.entry(code).or_insert(0) += 1;
+        }
+    }
+    counts.into_values().collect()
+}
+
+/// Shannon entropy in bits of the distribution given by `counts`; NaN if
+/// there are no values
+pub(crate) fn entropy(counts: &[u32]) -> f64 {
+    let total: f64 = counts.iter().map(|&count| count as f64).sum();
+    if total == 0.0 {
+        return f64::NAN;
+    }
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total;
+            p * (1.0 / p).log2()
+        })
+        .sum()
+}
+
+/// Gini impurity `1 - sum(p^2)` of the distribution given by `counts`; NaN
+/// if there are no values
+pub(crate) fn gini(counts: &[u32]) -> f64 {
+    let total: f64 = counts.iter().map(|&count| count as f64).sum();
+    if total == 0.0 {
+        return f64::NAN;
+    }
+    1.0 - counts.iter().map(|&count| (count as f64 / total).powi(2)).sum::<f64>()
+}
+
+/// Shannon entropy in bits of the categories of a group-code series (see
+/// `groupby::key_codes`), nulls left out: 0 when every value is the same,
+/// log2(k) for k equally frequent categories. NaN if there are no non-null
+/// values or the id is not a group-code series.
+#[wasm_bindgen]
+pub fn engine_series_entropy(codes_id: u32) -> f64 {
+    let _prof = profile("engine_series_entropy", || series_bytes(codes_id));
+    key_codes(codes_id).map_or(f64::NAN, |codes| entropy(&code_counts(codes)))
+}
+
+/// Gini impurity of the categories of a group-code series (see
+/// `groupby::key_codes`), nulls left out: the probability that two values
+/// drawn with replacement differ, 0 when every value is the same. NaN if
+/// there are no non-null values or the id is not a group-code series.
+#[wasm_bindgen]
+pub fn engine_series_gini(codes_id: u32) -> f64 {
+    let _prof = profile("engine_series_gini", || series_bytes(codes_id));
+    key_codes(codes_id).map_or(f64::NAN, |codes| gini(&code_counts(codes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{engine_create_series_f64, engine_create_series_str, engine_create_series_u32};
+    use crate::series::{engine_series_to_json_str, engine_series_to_vec_f64, engine_series_to_vec_u32};
+
+    #[test]
+    fn freq_table_orders_by_count_then_first_occurrence() {
+        let id = engine_create_series_f64(&[1.0, 2.0, 2.0, f64::NAN, 3.0, 1.0, 2.0]);
+        let ids = engine_freq_table(id);
+        assert_eq!(engine_series_to_vec_f64(ids[0]), [2.0, 1.0, 3.0]);
+        assert_eq!(engine_series_to_vec_u32(ids[1]), [3, 2, 1]);
+        assert_eq!(engine_series_to_vec_f64(ids[2]), [50.0, 100.0 / 3.0, 100.0 / 6.0]);
+        assert_eq!(engine_series_to_vec_f64(ids[3]), [50.0, 500.0 / 6.0, 100.0]);
+        let strings = engine_create_series_str(vec!["b".into(), "a".into(), "b".into(), "c".into(), "a".into()]);
+        let ids = engine_freq_table(strings);
+        assert_eq!(engine_series_to_json_str(ids[0]), r#"["b","a","c"]"#);
+        assert_eq!(engine_series_to_vec_u32(ids[1]), [2, 2, 1]);
+        assert!(engine_freq_table(u32::MAX - 1).is_empty());
+    }
+
+    #[test]
+    fn entropy_and_gini_measure_category_spread() {
+        assert_eq!(entropy(&[5]), 0.0);
+        assert_eq!(entropy(&[2, 2, 2, 2]), 2.0);
+        assert_eq!(gini(&[1, 1]), 0.5);
+        assert!(entropy(&[]).is_nan() && gini(&[0]).is_nan());
+        let codes = engine_create_series_u32(&[0, 1, u32::MAX, 0, 1]);
+        assert_eq!(engine_series_entropy(codes), 1.0);
+        assert_eq!(engine_series_gini(codes), 0.5);
+        assert!(engine_series_entropy(engine_create_series_str(vec!["a".into()])).is_nan());
+    }
+
+    #[test]
+    fn packed_value_counts_return_first_rows_then_counts() {
+        // "b", "a", "b", "", "a", "b"
+        let bytes = b"babab";
+        assert_eq!(value_counts_str_packed(bytes, &[0, 1, 2, 3, 3, 4, 5]), [0, 1, 3, 3, 2, 1]);
+        assert!(value_counts_str_packed(bytes, &[0]).is_empty());
+        assert!(value_counts_str_packed(bytes, &[0, 9]).is_empty());
+        assert!(value_counts_str_packed(bytes, &[2, 1]).is_empty());
+        assert!(value_counts_str_packed(&[0xFF], &[0, 1]).is_empty());
+    }
+}
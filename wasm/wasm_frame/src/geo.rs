@@ -0,0 +1,298 @@
+//! Geospatial helpers on latitude/longitude series
+//!
+//! Coordinates are float64 series in decimal degrees (WGS84 latitude and
+//! longitude); rows with a null coordinate give null (NaN) distances and
+//! false mask rows. Geohashes bucket points into grid cells, as base-32
+//! strings or as their bits in an int64, either of which can be used as a
+//! groupby key.
+
+use wasm_bindgen::prelude::*;
+use crate::core::{StrSeries, ENGINE};
+use crate::profiling::{profile, series_bytes};
+use crate::rowwise::aligned_columns;
+
+/// Mean Earth radius in kilometres (IUGG)
+pub const EARTH_RADIUS_KM: f64 = 6371.0088;
+
+/// Great-circle distance in kilometres between two points in degrees
+fn haversine(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let dphi = phi2 - phi1;
+    let dlambda = (lon2 - lon1).to_radians();
+    let a = (dphi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (dlambda / 2.0).sin().powi(2);
+    // Rounding can push `a` just past 1; clamp (unlike min) keeps NaN
+    2.0 * EARTH_RADIUS_KM * a.sqrt().clamp(0.0, 1.0).asin()
+}
+
+/// Per-row great-circle distance in kilometres between the points
+/// `(lat1, lon1)` and `(lat2, lon2)`, by the haversine formula on a sphere
+/// of radius `EARTH_RADIUS_KM` (within about 0.5% of the ellipsoidal
+/// distance). Rows with a null coordinate are null. Returns a new float64
+/// series, or u32::MAX if an id is unknown or the lengths differ.
+#[wasm_bindgen]
+pub fn engine_haversine_f64(lat1_id: u32, lon1_id: u32, lat2_id: u32, lon2_id: u32) -> u32 {
+    let _prof = profile("engine_haversine_f64", || series_bytes(lat1_id));
+    let (columns, len) = match aligned_columns("engine_haversine_f64", &[lat1_id, lon1_id, lat2_id, lon2_id]) {
+        Some(aligned) => aligned,
+        None => return u32::MAX,
+    };
+    let [lat1, lon1, lat2, lon2] = [0, 1, 2, 3].map(|i| columns[i].to_vec());
+    let out: Vec<f64> = (0..len).map(|row| haversine(lat1[row], lon1[row], lat2[row], lon2[row])).collect();
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&out))
+}
+
+/// Bool mask of the points `(lat, lon)` inside a bounding box, bounds
+/// included. A box with `min_lon > max_lon` crosses the antimeridian (e.g.
+/// 170 to -170 covers 20 degrees of longitude around 180). Rows with a null
+/// coordinate are false. Returns u32::MAX if either id is unknown, the
+/// lengths differ or a bound is null.
+#[wasm_bindgen]
+pub fn engine_point_in_bbox(lat_id: u32, lon_id: u32, min_lat: f64, min_lon: f64, max_lat: f64, max_lon: f64) -> u32 {
+    let _prof = profile("engine_point_in_bbox", || series_bytes(lat_id));
+    if [min_lat, min_lon, max_lat, max_lon].iter().any(|b| b.is_nan()) {
+        engine_log!(warn, "engine_point_in_bbox: null bound");
+        return u32::MAX;
+    }
+    let (columns, len) = match aligned_columns("engine_point_in_bbox", &[lat_id, lon_id]) {
+        Some(aligned) => aligned,
+        None => return u32::MAX,
+    };
+    let (lat, lon) = (columns[0].to_vec(), columns[1].to_vec());
+    let in_lon = |x: f64| if min_lon <= max_lon { (min_lon..=max_lon).contains(&x) } else { x >= min_lon || x <= max_lon };
+    let mask: Vec<u8> = (0..len).map(|row| ((min_lat..=max_lat).contains(&lat[row]) && in_lon(lon[row])) as u8).collect();
+    ENGINE.with(|cell| cell.borrow_mut().register_series_bool(&mask))
+}
+
+/// Longest geohash: 12 characters, 60 bits
+pub const GEOHASH_MAX_PRECISION: u32 = 12;
+
+const GEOHASH_ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// Geohash bits of a point, `5 * precision` of them with longitude first;
+/// None for a null or out-of-range coordinate
+fn geohash_bits(lat: f64, lon: f64, precision: u32) -> Option<u64> {
+    if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+        return None;
+    }
+    let (mut lat_range, mut lon_range) = ((-90.0, 90.0), (-180.0, 180.0));
+    let mut bits = 0u64;
+    for i in 0..5 * precision {
+        let (range, v) = if i % 2 == 0 { (&mut lon_range, lon) } else { (&mut lat_range, lat) };
+        let mid = (range.0 + range.1) / 2.0;
+        bits <<= 1;
+        if v >= mid {
+            bits |= 1;
+            range.0 = mid;
+        } else {
+            range.1 = mid;
+        }
+    }
+    Some(bits)
+}
+
+/// Centre `(lat, lon)` of the cell of `bit_count` geohash bits
+fn geohash_centre(bits: u64, bit_count: u32) -> (f64, f64) {
+    let (mut lat_range, mut lon_range) = ((-90.0, 90.0), (-180.0, 180.0));
+    for i in 0..bit_count {
+        let range = if i % 2 == 0 { &mut lon_range } else { &mut lat_range };
+        let mid = (range.0 + range.1) / 2.0;
+        if (bits >> (bit_count - 1 - i)) & 1 == 1 {
+            range.0 = mid;
+        } else {
+            range.1 = mid;
+        }
+    }
+    ((lat_range.0 + lat_range.1) / 2.0, (lon_range.0 + lon_range.1) / 2.0)
+}
+
+/// Geohash bits of each point of two aligned coordinate series, None for
+/// null or out-of-range points; None (logged) if an id is unknown, the
+/// lengths differ or `precision` is outside 1..=`GEOHASH_MAX_PRECISION`
+fn geohash_column(fn_name: &str, lat_id: u32, lon_id: u32, precision: u32) -> Option<Vec<Option<u64>>> {
+    if !(1..=GEOHASH_MAX_PRECISION).contains(&precision) {
+        engine_log!(warn, "{}: precision {} outside 1..={}", fn_name, precision, GEOHASH_MAX_PRECISION);
+        return None;
+    }
+    let (columns, _) = aligned_columns(fn_name, &[lat_id, lon_id])?;
+    Some(columns[0].iter().zip(columns[1].iter()).map(|(lat, lon)| geohash_bits(lat, lon, precision)).collect())
+}
+
+/// Geohash of each point `(lat, lon)` as a string of `precision` base-32
+/// characters (1 to 12; 5 gives cells of about 5 km, 8 about 40 m). Points
+/// sharing a prefix are close, so truncating gives coarser buckets. Null or
+/// out-of-range coordinates give null. Returns a new string series, or
+/// u32::MAX if either id is unknown, the lengths differ or `precision` is
+/// out of range.
+#[wasm_bindgen]
+pub fn engine_geohash_encode(lat_id: u32, lon_id: u32, precision: u32) -> u32 {
+    let _prof = profile("engine_geohash_encode", || series_bytes(lat_id));
+    let hashes = match geohash_column("engine_geohash_encode", lat_id, lon_id, precision) {
+        Some(hashes) => hashes,
+        None => return u32::MAX,
+    };
+    let mut out = StrSeries::default();
+    let mut text = String::with_capacity(precision as usize);
+    for bits in hashes {
+        match bits {
+            Some(bits) => {
+                text.clear();
+                text.extend((0..precision).rev().map(|i| GEOHASH_ALPHABET[((bits >> (5 * i)) & 31) as usize] as char));
+                out.push(Some(&text));
+            }
+            None => out.push(None),
+        }
+    }
+    ENGINE.with(|cell| cell.borrow_mut().register_series_str(out))
+}
+
+/// Geohash of each point `(lat, lon)` as an int64 holding its `5 *
+/// precision` bits (the same cells as `engine_geohash_encode`), a compact
+/// key for groupby and joins. Null or out-of-range coordinates give null
+/// (i64::MIN). Returns a new int64 series, or u32::MAX as
+/// `engine_geohash_encode`.
+#[wasm_bindgen]
+pub fn engine_geohash_encode_i64(lat_id: u32, lon_id: u32, precision: u32) -> u32 {
+    let _prof = profile("engine_geohash_encode_i64", || series_bytes(lat_id));
+    let hashes = match geohash_column("engine_geohash_encode_i64", lat_id, lon_id, precision) {
+        Some(hashes) => hashes,
+        None => return u32::MAX,
+    };
+    let out: Vec<i64> = hashes.into_iter().map(|bits| bits.map_or(i64::MIN, |bits| bits as i64)).collect();
+    ENGINE.with(|cell| cell.borrow_mut().register_series_i64(&out))
+}
+
+/// Centres of the cells of a string series of geohashes (of any lengths up
+/// to 12, case-insensitive), as `[lat_id, lon_id]` float64 series. Nulls and
+/// invalid geohashes give null coordinates. Returns an empty array if the id
+/// is not a string series.
+#[wasm_bindgen]
+pub fn engine_geohash_decode(series_id: u32) -> Box<[u32]> {
+    let _prof = profile("engine_geohash_decode", || series_bytes(series_id));
+    let centres: Option<Vec<(f64, f64)>> = ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        let strings = eng.series_store_str.get(&series_id)?;
+        let decode = |text: &str| -> Option<(f64, f64)> {
+            if text.is_empty() || text.len() > GEOHASH_MAX_PRECISION as usize {
+                return None;
+            }
+            let mut bits = 0u64;
+            for b in text.bytes() {
+                let digit = GEOHASH_ALPHABET.iter().position(|&c| c == b.to_ascii_lowercase())?;
+                bits = (bits << 5) | digit as u64;
+            }
+            Some(geohash_centre(bits, 5 * text.len() as u32))
+        };
+        Some(strings.iter().map(|text| text.and_then(decode).unwrap_or((f64::NAN, f64::NAN))).collect())
+    });
+    match centres {
+        Some(centres) => register_points(&centres),
+        None => {
+            engine_log!(warn, "engine_geohash_decode: unknown string series {}", series_id);
+            Box::new([])
+        }
+    }
+}
+
+/// Centres of the cells of an int64 series of geohash bits from
+/// `engine_geohash_encode_i64` at `precision`, as `[lat_id, lon_id]`
+/// float64 series; nulls and values with bits beyond the precision give
+/// null coordinates. Returns an empty array if the id is not an int64
+/// series or `precision` is outside 1 to 12.
+#[wasm_bindgen]
+pub fn engine_geohash_decode_i64(series_id: u32, precision: u32) -> Box<[u32]> {
+    let _prof = profile("engine_geohash_decode_i64", || series_bytes(series_id));
+    if !(1..=GEOHASH_MAX_PRECISION).contains(&precision) {
+        engine_log!(warn, "engine_geohash_decode_i64: precision {} outside 1..={}", precision, GEOHASH_MAX_PRECISION);
+        return Box::new([]);
+    }
+    let bit_count = 5 * precision;
+    let centres: Option<Vec<(f64, f64)>> = ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        let &(ptr, len) = eng.series_store_i64.get(&series_id)?;
+        let values = unsafe { std::slice::from_raw_parts(ptr, len) };
+        Some(
+            values
+                .iter()
+                .map(|&v| match v {
+                    v if v >= 0 && (v as u64) >> bit_count == 0 => geohash_centre(v as u64, bit_count),
+                    _ => (f64::NAN, f64::NAN),
+                })
+                .collect(),
+        )
+    });
+    match centres {
+        Some(centres) => register_points(&centres),
+        None => {
+            engine_log!(warn, "engine_geohash_decode_i64: unknown int64 series {}", series_id);
+            Box::new([])
+        }
+    }
+}
+
+/// Register points as `[lat_id, lon_id]` float64 series
+fn register_points(points: &[(f64, f64)]) -> Box<[u32]> {
+    let lat: Vec<f64> = points.iter().map(|p| p.0).collect();
+    let lon: Vec<f64> = points.iter().map(|p| p.1).collect();
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        Box::new([eng.register_series_f64(&lat), eng.register_series_f64(&lon)])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::engine_create_series_f64;
+    use crate::series::{engine_series_to_vec_bool, engine_series_to_vec_f64};
+
+    #[test]
+    fn haversine_distances_follow_the_sphere() {
+        let lat1 = engine_create_series_f64(&[0.0, 0.0, 51.5, f64::NAN]);
+        let lon1 = engine_create_series_f64(&[0.0, 0.0, -0.1, 0.0]);
+        let lat2 = engine_create_series_f64(&[1.0, 0.0, 51.5, 0.0]);
+        let lon2 = engine_create_series_f64(&[0.0, 180.0, -0.1, 0.0]);
+        let out = engine_series_to_vec_f64(engine_haversine_f64(lat1, lon1, lat2, lon2));
+        assert!((out[0] - EARTH_RADIUS_KM.to_radians()).abs() < 1e-9);
+        assert!((out[1] - std::f64::consts::PI * EARTH_RADIUS_KM).abs() < 1e-9);
+        assert_eq!(out[2], 0.0);
+        assert!(out[3].is_nan());
+        assert_eq!(engine_haversine_f64(lat1, lon1, lat2, engine_create_series_f64(&[0.0])), u32::MAX);
+    }
+
+    #[test]
+    fn bounding_boxes_include_bounds_and_may_cross_the_antimeridian() {
+        let lat = engine_create_series_f64(&[10.0, 20.0, 15.0, 15.0, f64::NAN]);
+        let lon = engine_create_series_f64(&[175.0, -175.0, 0.0, 180.0, 175.0]);
+        let mask = |min_lon, max_lon| engine_series_to_vec_bool(engine_point_in_bbox(lat, lon, 10.0, min_lon, 20.0, max_lon));
+        assert_eq!(mask(170.0, -170.0), [1, 1, 0, 1, 0]);
+        assert_eq!(mask(-10.0, 175.0), [1, 0, 1, 0, 0]);
+        assert_eq!(engine_point_in_bbox(lat, lon, f64::NAN, 0.0, 20.0, 10.0), u32::MAX);
+        assert_eq!(engine_point_in_bbox(lat, engine_create_series_f64(&[0.0]), 0.0, 0.0, 1.0, 1.0), u32::MAX);
+    }
+
+    #[test]
+    fn geohashes_match_the_reference_and_round_trip() {
+        use crate::core::engine_create_series_str;
+        use crate::series::{engine_series_to_json_str, engine_series_to_vec_i64};
+        let lat = engine_create_series_f64(&[57.64911, 42.6, f64::NAN, 91.0]);
+        let lon = engine_create_series_f64(&[10.40744, -5.6, 0.0, 0.0]);
+        let hashes = engine_geohash_encode(lat, lon, 11);
+        assert_eq!(engine_series_to_json_str(hashes), r#"["u4pruydqqvj","ezs42e44yx9",null,null]"#);
+        let bits = engine_geohash_encode_i64(lat, lon, 11);
+        assert_eq!(engine_series_to_vec_i64(bits)[2..], [i64::MIN, i64::MIN]);
+        // Both encodings decode to the same cell centres
+        let from_text = engine_geohash_decode(hashes);
+        let from_bits = engine_geohash_decode_i64(bits, 11);
+        for i in 0..2 {
+            assert_eq!(format!("{:?}", engine_series_to_vec_f64(from_text[i])), format!("{:?}", engine_series_to_vec_f64(from_bits[i])));
+        }
+        let cells = engine_geohash_decode(engine_create_series_str(vec!["EZS42".into(), "ezsa".into(), "".into()]));
+        assert_eq!(format!("{:?}", engine_series_to_vec_f64(cells[0])), "[42.60498046875, NaN, NaN]");
+        assert_eq!(format!("{:?}", engine_series_to_vec_f64(cells[1])), "[-5.60302734375, NaN, NaN]");
+        assert_eq!(engine_geohash_encode(lat, lon, 13), u32::MAX);
+        assert_eq!(engine_geohash_encode_i64(lat, lon, 0), u32::MAX);
+        assert!(engine_geohash_decode_i64(bits, 13).is_empty());
+        assert!(engine_geohash_decode(lat).is_empty());
+    }
+}
+
@@ -3,115 +3,214 @@
 //! This module provides functions for performing various aggregations
 //! on grouped data using registered series and group keys.
 
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::rc::Rc;
 use serde_json;
 use wasm_bindgen::prelude::*;
-use crate::core::ENGINE;
+use crate::callbacks::{call_with_view, JsFunction};
+use crate::core::{f64_values, packed_strs, StrSeries, ENGINE};
+use crate::error::set_last_error;
+use crate::frequency::{code_counts, entropy, gini};
+use crate::parallel::map_chunks;
+use crate::profiling::{profile, series_bytes};
+use crate::random::Rng;
+use crate::statistics::{quantile_sorted, RunningStats, AGG_COUNT, AGG_MAX, AGG_MEAN, AGG_MIN, AGG_STD, AGG_SUM, AGG_VAR};
+use crate::tasks::{cancel_requested, take_cancel_request, CancelScope, STEP_ROWS};
 
-/// GroupBy sum using an existing registered f64 series and JSON keys
-/// Returns a new series_id for the aggregated result (values sorted by key)
+/// Groups in key order (string keys that parse as numbers by value, ahead
+/// of the other strings, which sort lexicographically; numeric keys and
+/// codes ascending); the default
+pub const GROUP_ORDER_SORTED: u8 = 0;
+/// Groups in order of their first row
+pub const GROUP_ORDER_FIRST_SEEN: u8 = 1;
+/// Groups listed explicitly, for the next groupby call only
+/// (`engine_set_groupby_order`)
+pub const GROUP_ORDER_EXPLICIT: u8 = 2;
+
+/// Output order of groupby results
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum GroupOrder {
+    #[default]
+    Sorted,
+    FirstSeen,
+}
+
+/// Set the order in which groupby functions return their groups:
+/// `GROUP_ORDER_SORTED` (default) or `GROUP_ORDER_FIRST_SEEN`, which stay
+/// in effect until changed, or `GROUP_ORDER_EXPLICIT` with `keys_json` a
+/// JSON array of key strings (ignored for the other orders). An explicit
+/// list applies to the next groupby call only, which then holds exactly the
+/// listed keys in that order: groups not listed are dropped and listed keys
+/// without rows come back as empty groups (count 0, sum 0, other aggregates
+/// NaN). The call uses the list up as it starts, even if it then fails, and
+/// later calls use the sorted or first-seen order again. Numeric keys and
+/// codes are matched against the list by parsing each entry. Returns false,
+/// leaving the order unchanged, if `order` is unknown or `keys_json` is not
+/// an array of strings.
 #[wasm_bindgen]
-pub fn engine_groupby_sum_f64(series_id: u32, group_keys_json: &str) -> u32 {
-    let keys: Vec<String> = serde_json::from_str(group_keys_json).unwrap_or_default();
+pub fn engine_set_groupby_order(order: u8, keys_json: &str) -> bool {
+    let order = match order {
+        GROUP_ORDER_SORTED => GroupOrder::Sorted,
+        GROUP_ORDER_FIRST_SEEN => GroupOrder::FirstSeen,
+        GROUP_ORDER_EXPLICIT => match serde_json::from_str::<Vec<String>>(keys_json) {
+            Ok(keys) => {
+                ENGINE.with(|cell| cell.borrow_mut().groupby_keys = Some(keys.into()));
+                return true;
+            }
+            Err(_) => {
+                engine_log!(warn, "engine_set_groupby_order: invalid keys JSON");
+                return false;
+            }
+        },
+        _ => return false,
+    };
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        eng.groupby_order = order;
+        eng.groupby_keys = None;
+    });
+    true
+}
 
-    let (src_ptr, src_len) = ENGINE.with(|cell| {
+/// Current groupby order code (`GROUP_ORDER_EXPLICIT` while a key list is
+/// waiting for the next groupby call)
+#[wasm_bindgen]
+pub fn engine_groupby_order() -> u8 {
+    ENGINE.with(|cell| {
         let eng = cell.borrow();
-        if let Some((ptr, len)) = eng.series_store.get(&series_id) {
-            (*ptr, *len)
-        } else {
-            (std::ptr::null_mut(), 0)
+        match eng.groupby_order {
+            _ if eng.groupby_keys.is_some() => GROUP_ORDER_EXPLICIT,
+            GroupOrder::Sorted => GROUP_ORDER_SORTED,
+            GroupOrder::FirstSeen => GROUP_ORDER_FIRST_SEEN,
         }
-    });
-    if src_ptr.is_null() {
-        return u32::MAX;
+    })
+}
+
+/// Group order of one groupby call (`take_group_order`)
+#[derive(Clone, Debug, Default)]
+pub(crate) struct GroupOrdering {
+    order: GroupOrder,
+    keys: Option<Rc<[String]>>,
+}
+
+/// The order for a groupby call starting now. Every groupby entry point
+/// takes it before anything can fail, so a pending explicit list is used up
+/// by the call it was set for and never leaks into a later one.
+pub(crate) fn take_group_order() -> GroupOrdering {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        GroupOrdering { order: eng.groupby_order, keys: eng.groupby_keys.take() }
+    })
+}
+
+/// Sorted order of string keys: keys that parse as numbers ascend by value
+/// ahead of the other keys, which are lexicographic, so "2" precedes "10"
+pub(crate) fn compare_str_keys(a: &str, b: &str) -> Ordering {
+    let number = |k: &str| k.trim().parse::<f64>().ok().filter(|v| !v.is_nan());
+    match (number(a), number(b)) {
+        (Some(x), Some(y)) => x.total_cmp(&y).then_with(|| a.cmp(b)),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.cmp(b),
+    }
+}
+
+/// Arrange group keys for output under `order`: sorted with `cmp`, by
+/// `first_row`, or an explicit list mapped through `parse`, which then
+/// replaces `groups` entirely
+fn arrange_groups<K>(
+    order: &GroupOrdering,
+    mut groups: Vec<K>,
+    first_row: impl Fn(&K) -> usize,
+    cmp: impl Fn(&K, &K) -> Ordering,
+    parse: impl FnMut(&str) -> K,
+) -> Vec<K> {
+    if let Some(keys) = &order.keys {
+        return keys.iter().map(|k| k.as_str()).map(parse).collect();
+    }
+    match order.order {
+        GroupOrder::Sorted => groups.sort_by(cmp),
+        GroupOrder::FirstSeen => groups.sort_by_key(first_row),
     }
+    groups
+}
+
+/// Output keys of a JSON-keyed groupby: `candidates` (the keys that get a
+/// result) arranged under `order`
+pub(crate) fn output_keys(order: &GroupOrdering, candidates: Vec<String>, keys: &[String]) -> Vec<String> {
+    let mut first_rows: HashMap<&str, usize> = HashMap::new();
+    if order.keys.is_none() && order.order == GroupOrder::FirstSeen {
+        for (row, key) in keys.iter().enumerate() {
+            first_rows.entry(key.as_str()).or_insert(row);
+        }
+    }
+    arrange_groups(order, candidates, |k| first_rows.get(k.as_str()).copied().unwrap_or(usize::MAX), |a, b| compare_str_keys(a, b), str::to_string)
+}
+
+/// GroupBy sum using an existing registered f64 series and JSON keys
+/// Returns a new series_id for the aggregated result (values in groupby
+/// order, see `engine_set_groupby_order`)
+#[wasm_bindgen]
+pub fn engine_groupby_sum_f64(series_id: u32, group_keys_json: &str) -> u32 {
+    let _prof = profile("engine_groupby_sum_f64", || series_bytes(series_id));
+    let order = take_group_order();
+    let keys: Vec<String> = serde_json::from_str(group_keys_json).unwrap_or_default();
 
-    if keys.len() != src_len || src_ptr.is_null() {
+    let values = match unsafe { f64_values(series_id) } { Some(v) => v, None => return u32::MAX };
+    if keys.len() != values.len() {
         return u32::MAX;
     }
 
     // Build groups
     let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
     for (i, key) in keys.iter().enumerate() {
-        groups.entry(key.clone()).or_insert_with(Vec::new).push(i);
+        groups.entry(key.clone()).or_default().push(i);
     }
 
-    // Sort keys to maintain deterministic order
-    let mut sorted_keys: Vec<String> = groups.keys().cloned().collect();
-    sorted_keys.sort();
+    // Order keys deterministically (`engine_set_groupby_order`)
+    let sorted_keys = output_keys(&order, groups.keys().cloned().collect(), &keys);
 
     // Compute sums in a temporary Vec
     let mut results: Vec<f64> = Vec::with_capacity(sorted_keys.len());
-    unsafe {
-        for k in sorted_keys.iter() {
-            if let Some(ixs) = groups.get(k) {
-                let mut sum = 0.0;
-                for &idx in ixs {
-                    let v = *src_ptr.add(idx);
-                    if !v.is_nan() {
-                        sum += v;
-                    }
-                }
-                results.push(sum);
+    for k in sorted_keys.iter() {
+        let mut sum = 0.0;
+        for &idx in groups.get(k).map_or(&[][..], Vec::as_slice) {
+            let v = values.get(idx);
+            if !v.is_nan() {
+                sum += v;
             }
         }
+        results.push(sum);
     }
 
     // Register result as a new series in engine
-    ENGINE.with(|cell| {
-        let mut eng = cell.borrow_mut();
-        let id = eng.next_series_id;
-        eng.next_series_id = eng.next_series_id.wrapping_add(1);
-
-        let len = results.len();
-        let dst_ptr = unsafe {
-            let layout = std::alloc::Layout::from_size_align(
-                len * std::mem::size_of::<f64>(),
-                std::mem::align_of::<f64>(),
-            )
-            .unwrap();
-            let raw = std::alloc::alloc(layout) as *mut f64;
-            if !raw.is_null() && len > 0 {
-                std::ptr::copy_nonoverlapping(results.as_ptr(), raw, len);
-            }
-            raw
-        };
-        eng.series_store.insert(id, (dst_ptr, len));
-        id
-    })
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&results))
 }
 
 /// GroupBy mean using an existing registered f64 series and JSON keys
 #[wasm_bindgen]
 pub fn engine_groupby_mean_f64(series_id: u32, group_keys_json: &str) -> u32 {
+    let _prof = profile("engine_groupby_mean_f64", || series_bytes(series_id));
+    let order = take_group_order();
     let keys: Vec<String> = serde_json::from_str(group_keys_json).unwrap_or_default();
 
-    let (src_ptr, src_len) = ENGINE.with(|cell| {
-        let eng = cell.borrow();
-        if let Some((ptr, len)) = eng.series_store.get(&series_id) {
-            (*ptr, *len)
-        } else {
-            (std::ptr::null_mut(), 0)
-        }
-    });
-    if src_ptr.is_null() || keys.len() != src_len {
+    let values = match unsafe { f64_values(series_id) } { Some(v) => v, None => return u32::MAX };
+    if keys.len() != values.len() {
         return u32::MAX;
     }
 
     let mut groups: HashMap<String, (f64, usize)> = HashMap::new();
-    unsafe {
-        for (i, key) in keys.iter().enumerate() {
-            let v = *src_ptr.add(i);
-            if !v.is_nan() {
-                let entry = groups.entry(key.clone()).or_insert((0.0, 0));
-                entry.0 += v;
-                entry.1 += 1;
-            }
+    for (i, key) in keys.iter().enumerate() {
+        let v = values.get(i);
+        if !v.is_nan() {
+            let entry = groups.entry(key.clone()).or_insert((0.0, 0));
+            entry.0 += v;
+            entry.1 += 1;
         }
     }
 
-    let mut sorted_keys: Vec<String> = groups.keys().cloned().collect();
-    sorted_keys.sort();
+    let sorted_keys = output_keys(&order, groups.keys().cloned().collect(), &keys);
     let results: Vec<f64> = sorted_keys
         .into_iter()
         .map(|k| {
@@ -120,42 +219,18 @@ pub fn engine_groupby_mean_f64(series_id: u32, group_keys_json: &str) -> u32 {
         })
         .collect();
 
-    ENGINE.with(|cell| {
-        let mut eng = cell.borrow_mut();
-        let id = eng.next_series_id;
-        eng.next_series_id = eng.next_series_id.wrapping_add(1);
-        let len = results.len();
-        let dst_ptr = unsafe {
-            let layout = std::alloc::Layout::from_size_align(
-                len * std::mem::size_of::<f64>(),
-                std::mem::align_of::<f64>(),
-            )
-            .unwrap();
-            let raw = std::alloc::alloc(layout) as *mut f64;
-            if !raw.is_null() && len > 0 {
-                std::ptr::copy_nonoverlapping(results.as_ptr(), raw, len);
-            }
-            raw
-        };
-        eng.series_store.insert(id, (dst_ptr, len));
-        id
-    })
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&results))
 }
 
 /// GroupBy count (non-null) using an existing registered f64 series and JSON keys
 #[wasm_bindgen]
 pub fn engine_groupby_count_f64(series_id: u32, group_keys_json: &str) -> u32 {
+    let _prof = profile("engine_groupby_count_f64", || series_bytes(series_id));
+    let order = take_group_order();
     let keys: Vec<String> = serde_json::from_str(group_keys_json).unwrap_or_default();
 
-    let (src_ptr, src_len) = ENGINE.with(|cell| {
-        let eng = cell.borrow();
-        if let Some((ptr, len)) = eng.series_store.get(&series_id) {
-            (*ptr, *len)
-        } else {
-            (std::ptr::null_mut(), 0)
-        }
-    });
-    if src_ptr.is_null() || keys.len() != src_len {
+    let values = match unsafe { f64_values(series_id) } { Some(v) => v, None => return u32::MAX };
+    if keys.len() != values.len() {
         return u32::MAX;
     }
 
@@ -164,232 +239,278 @@ pub fn engine_groupby_count_f64(series_id: u32, group_keys_json: &str) -> u32 {
     
     // Then, count non-null values for each group
     let mut groups: HashMap<String, usize> = HashMap::new();
-    unsafe {
-        for (i, key) in keys.iter().enumerate() {
-            let v = *src_ptr.add(i);
-            // For count, we count non-null values (filter out NaN)
-            if !v.is_nan() {
-                *groups.entry(key.clone()).or_insert(0) += 1;
-            }
+    for (i, key) in keys.iter().enumerate() {
+        let v = values.get(i);
+        // For count, we count non-null values (filter out NaN)
+        if !v.is_nan() {
+            *groups.entry(key.clone()).or_insert(0) += 1;
         }
     }
 
     // Return results for all unique group keys, even if count is 0
-    let mut sorted_keys: Vec<String> = unique_keys.into_iter().collect();
-    sorted_keys.sort();
+    let sorted_keys = output_keys(&order, unique_keys.into_iter().collect(), &keys);
     let results: Vec<f64> = sorted_keys
         .into_iter()
         .map(|k| groups.get(&k).cloned().unwrap_or(0) as f64)
         .collect();
 
-    ENGINE.with(|cell| {
-        let mut eng = cell.borrow_mut();
-        let id = eng.next_series_id;
-        eng.next_series_id = eng.next_series_id.wrapping_add(1);
-        let len = results.len();
-        let dst_ptr = unsafe {
-            let layout = std::alloc::Layout::from_size_align(
-                len * std::mem::size_of::<f64>(),
-                std::mem::align_of::<f64>(),
-            )
-            .unwrap();
-            let raw = std::alloc::alloc(layout) as *mut f64;
-            if !raw.is_null() && len > 0 {
-                std::ptr::copy_nonoverlapping(results.as_ptr(), raw, len);
-            }
-            raw
-        };
-        eng.series_store.insert(id, (dst_ptr, len));
-        id
-    })
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&results))
+}
+
+/// GroupBy size: total rows per group, nulls included (unlike
+/// `engine_groupby_count_f64`, which counts non-null values of a series).
+/// Returns a float64 series of row counts in groupby order, or u32::MAX if
+/// `group_keys_json` is not an array of strings.
+#[wasm_bindgen]
+pub fn engine_groupby_size(group_keys_json: &str) -> u32 {
+    let _prof = profile("engine_groupby_size", || group_keys_json.len());
+    let order = take_group_order();
+    let keys: Vec<String> = match serde_json::from_str(group_keys_json) {
+        Ok(keys) => keys,
+        Err(_) => {
+            engine_log!(warn, "engine_groupby_size: invalid keys JSON");
+            return u32::MAX;
+        }
+    };
+
+    let mut sizes: HashMap<String, usize> = HashMap::new();
+    for key in keys.iter() {
+        *sizes.entry(key.clone()).or_insert(0) += 1;
+    }
+    let sorted_keys = output_keys(&order, sizes.keys().cloned().collect(), &keys);
+    let results: Vec<f64> = sorted_keys.iter().map(|k| sizes.get(k).copied().unwrap_or(0) as f64).collect();
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&results))
 }
 
 /// GroupBy min using an existing registered f64 series and JSON keys
 #[wasm_bindgen]
 pub fn engine_groupby_min_f64(series_id: u32, group_keys_json: &str) -> u32 {
+    let _prof = profile("engine_groupby_min_f64", || series_bytes(series_id));
+    let order = take_group_order();
     let keys: Vec<String> = serde_json::from_str(group_keys_json).unwrap_or_default();
-    let (src_ptr, src_len) = ENGINE.with(|cell| {
-        let eng = cell.borrow();
-        if let Some((ptr, len)) = eng.series_store.get(&series_id) { (*ptr, *len) } else { (std::ptr::null_mut(), 0) }
-    });
-    if src_ptr.is_null() || keys.len() != src_len { return u32::MAX; }
+    let values = match unsafe { f64_values(series_id) } { Some(v) => v, None => return u32::MAX };
+    if keys.len() != values.len() { return u32::MAX; }
     let mut groups: HashMap<String, f64> = HashMap::new();
-    unsafe {
-        for (i, key) in keys.iter().enumerate() {
-            let v = *src_ptr.add(i);
-            if !v.is_nan() {
-                groups.entry(key.clone()).and_modify(|m| { if v < *m { *m = v; } }).or_insert(v);
-            }
+    for (i, key) in keys.iter().enumerate() {
+        let v = values.get(i);
+        if !v.is_nan() {
+            groups.entry(key.clone()).and_modify(|m| { if v < *m { *m = v; } }).or_insert(v);
         }
     }
-    let mut sorted_keys: Vec<String> = groups.keys().cloned().collect();
-    sorted_keys.sort();
+    let sorted_keys = output_keys(&order, groups.keys().cloned().collect(), &keys);
     let results: Vec<f64> = sorted_keys.into_iter().map(|k| *groups.get(&k).unwrap_or(&f64::NAN)).collect();
-    ENGINE.with(|cell| {
-        let mut eng = cell.borrow_mut();
-        let id = eng.next_series_id; eng.next_series_id = eng.next_series_id.wrapping_add(1);
-        let len = results.len();
-        let dst_ptr = unsafe {
-            let layout = std::alloc::Layout::from_size_align(len * std::mem::size_of::<f64>(), std::mem::align_of::<f64>()).unwrap();
-            let raw = std::alloc::alloc(layout) as *mut f64;
-            if !raw.is_null() && len > 0 { std::ptr::copy_nonoverlapping(results.as_ptr(), raw, len); }
-            raw
-        };
-        eng.series_store.insert(id, (dst_ptr, len)); id
-    })
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&results))
 }
 
 /// GroupBy max using an existing registered f64 series and JSON keys
 #[wasm_bindgen]
 pub fn engine_groupby_max_f64(series_id: u32, group_keys_json: &str) -> u32 {
+    let _prof = profile("engine_groupby_max_f64", || series_bytes(series_id));
+    let order = take_group_order();
     let keys: Vec<String> = serde_json::from_str(group_keys_json).unwrap_or_default();
-    let (src_ptr, src_len) = ENGINE.with(|cell| {
-        let eng = cell.borrow();
-        if let Some((ptr, len)) = eng.series_store.get(&series_id) { (*ptr, *len) } else { (std::ptr::null_mut(), 0) }
-    });
-    if src_ptr.is_null() || keys.len() != src_len { return u32::MAX; }
+    let values = match unsafe { f64_values(series_id) } { Some(v) => v, None => return u32::MAX };
+    if keys.len() != values.len() { return u32::MAX; }
     let mut groups: HashMap<String, f64> = HashMap::new();
-    unsafe {
-        for (i, key) in keys.iter().enumerate() {
-            let v = *src_ptr.add(i);
-            if !v.is_nan() {
-                groups.entry(key.clone()).and_modify(|m| { if v > *m { *m = v; } }).or_insert(v);
-            }
+    for (i, key) in keys.iter().enumerate() {
+        let v = values.get(i);
+        if !v.is_nan() {
+            groups.entry(key.clone()).and_modify(|m| { if v > *m { *m = v; } }).or_insert(v);
         }
     }
-    let mut sorted_keys: Vec<String> = groups.keys().cloned().collect();
-    sorted_keys.sort();
+    let sorted_keys = output_keys(&order, groups.keys().cloned().collect(), &keys);
     let results: Vec<f64> = sorted_keys.into_iter().map(|k| *groups.get(&k).unwrap_or(&f64::NAN)).collect();
-    ENGINE.with(|cell| {
-        let mut eng = cell.borrow_mut();
-        let id = eng.next_series_id; eng.next_series_id = eng.next_series_id.wrapping_add(1);
-        let len = results.len();
-        let dst_ptr = unsafe {
-            let layout = std::alloc::Layout::from_size_align(len * std::mem::size_of::<f64>(), std::mem::align_of::<f64>()).unwrap();
-            let raw = std::alloc::alloc(layout) as *mut f64;
-            if !raw.is_null() && len > 0 { std::ptr::copy_nonoverlapping(results.as_ptr(), raw, len); }
-            raw
-        };
-        eng.series_store.insert(id, (dst_ptr, len)); id
-    })
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&results))
 }
 
 /// GroupBy std using an existing registered f64 series and JSON keys (sample std, N-1)
 #[wasm_bindgen]
 pub fn engine_groupby_std_f64(series_id: u32, group_keys_json: &str) -> u32 {
+    let _prof = profile("engine_groupby_std_f64", || series_bytes(series_id));
+    let order = take_group_order();
     let keys: Vec<String> = serde_json::from_str(group_keys_json).unwrap_or_default();
-    let (src_ptr, src_len) = ENGINE.with(|cell| {
-        let eng = cell.borrow();
-        if let Some((ptr, len)) = eng.series_store.get(&series_id) { (*ptr, *len) } else { (std::ptr::null_mut(), 0) }
-    });
-    if src_ptr.is_null() || keys.len() != src_len { return u32::MAX; }
+    let values = match unsafe { f64_values(series_id) } { Some(v) => v, None => return u32::MAX };
+    if keys.len() != values.len() { return u32::MAX; }
     let mut sums: HashMap<String, f64> = HashMap::new();
     let mut counts: HashMap<String, usize> = HashMap::new();
-    unsafe {
-        for (i, key) in keys.iter().enumerate() {
-            let v = *src_ptr.add(i);
-            if !v.is_nan() {
-                *sums.entry(key.clone()).or_insert(0.0) += v;
-                *counts.entry(key.clone()).or_insert(0) += 1;
-            }
+    for (i, key) in keys.iter().enumerate() {
+        let v = values.get(i);
+        if !v.is_nan() {
+            *sums.entry(key.clone()).or_insert(0.0) += v;
+            *counts.entry(key.clone()).or_insert(0) += 1;
         }
     }
     let mut means: HashMap<String, f64> = HashMap::new();
     for (k, c) in counts.iter() { let s = sums.get(k).cloned().unwrap_or(0.0); means.insert(k.clone(), if *c>0 { s/(*c as f64) } else { f64::NAN }); }
     let mut sumsqdiff: HashMap<String, f64> = HashMap::new();
-    unsafe {
-        for (i, key) in keys.iter().enumerate() {
-            let v = *src_ptr.add(i);
-            if !v.is_nan() {
-                let m = means.get(key).cloned().unwrap_or(f64::NAN);
-                if !m.is_nan() { *sumsqdiff.entry(key.clone()).or_insert(0.0) += (v - m)*(v - m); }
-            }
+    for (i, key) in keys.iter().enumerate() {
+        let v = values.get(i);
+        if !v.is_nan() {
+            let m = means.get(key).cloned().unwrap_or(f64::NAN);
+            if !m.is_nan() { *sumsqdiff.entry(key.clone()).or_insert(0.0) += (v - m)*(v - m); }
         }
     }
-    let mut sorted_keys: Vec<String> = counts.keys().cloned().collect();
-    sorted_keys.sort();
+    let sorted_keys = output_keys(&order, counts.keys().cloned().collect(), &keys);
     let results: Vec<f64> = sorted_keys.into_iter().map(|k| {
         let c = counts.get(&k).cloned().unwrap_or(0);
         if c>1 { let ss = sumsqdiff.get(&k).cloned().unwrap_or(0.0); (ss/((c-1) as f64)).sqrt() } else { f64::NAN }
     }).collect();
-    ENGINE.with(|cell| {
-        let mut eng = cell.borrow_mut();
-        let id = eng.next_series_id; eng.next_series_id = eng.next_series_id.wrapping_add(1);
-        let len = results.len();
-        let dst_ptr = unsafe {
-            let layout = std::alloc::Layout::from_size_align(len * std::mem::size_of::<f64>(), std::mem::align_of::<f64>()).unwrap();
-            let raw = std::alloc::alloc(layout) as *mut f64;
-            if !raw.is_null() && len > 0 { std::ptr::copy_nonoverlapping(results.as_ptr(), raw, len); }
-            raw
-        };
-        eng.series_store.insert(id, (dst_ptr, len)); id
-    })
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&results))
 }
 
 /// GroupBy var using an existing registered f64 series and JSON keys (sample var, N-1)
 #[wasm_bindgen]
 pub fn engine_groupby_var_f64(series_id: u32, group_keys_json: &str) -> u32 {
+    let _prof = profile("engine_groupby_var_f64", || series_bytes(series_id));
+    let order = take_group_order();
     let keys: Vec<String> = serde_json::from_str(group_keys_json).unwrap_or_default();
-    let (src_ptr, src_len) = ENGINE.with(|cell| {
-        let eng = cell.borrow();
-        if let Some((ptr, len)) = eng.series_store.get(&series_id) { (*ptr, *len) } else { (std::ptr::null_mut(), 0) }
-    });
-    if src_ptr.is_null() || keys.len() != src_len { return u32::MAX; }
+    let values = match unsafe { f64_values(series_id) } { Some(v) => v, None => return u32::MAX };
+    if keys.len() != values.len() { return u32::MAX; }
     let mut sums: HashMap<String, f64> = HashMap::new();
     let mut counts: HashMap<String, usize> = HashMap::new();
-    unsafe {
-        for (i, key) in keys.iter().enumerate() {
-            let v = *src_ptr.add(i);
-            if !v.is_nan() {
-                *sums.entry(key.clone()).or_insert(0.0) += v;
-                *counts.entry(key.clone()).or_insert(0) += 1;
-            }
+    for (i, key) in keys.iter().enumerate() {
+        let v = values.get(i);
+        if !v.is_nan() {
+            *sums.entry(key.clone()).or_insert(0.0) += v;
+            *counts.entry(key.clone()).or_insert(0) += 1;
         }
     }
     let mut means: HashMap<String, f64> = HashMap::new();
     for (k, c) in counts.iter() { let s = sums.get(k).cloned().unwrap_or(0.0); means.insert(k.clone(), if *c>0 { s/(*c as f64) } else { f64::NAN }); }
     let mut sumsqdiff: HashMap<String, f64> = HashMap::new();
-    unsafe {
-        for (i, key) in keys.iter().enumerate() {
-            let v = *src_ptr.add(i);
-            if !v.is_nan() {
-                let m = means.get(key).cloned().unwrap_or(f64::NAN);
-                if !m.is_nan() { *sumsqdiff.entry(key.clone()).or_insert(0.0) += (v - m)*(v - m); }
-            }
+    for (i, key) in keys.iter().enumerate() {
+        let v = values.get(i);
+        if !v.is_nan() {
+            let m = means.get(key).cloned().unwrap_or(f64::NAN);
+            if !m.is_nan() { *sumsqdiff.entry(key.clone()).or_insert(0.0) += (v - m)*(v - m); }
         }
     }
-    let mut sorted_keys: Vec<String> = counts.keys().cloned().collect();
-    sorted_keys.sort();
+    let sorted_keys = output_keys(&order, counts.keys().cloned().collect(), &keys);
     let results: Vec<f64> = sorted_keys.into_iter().map(|k| {
         let c = counts.get(&k).cloned().unwrap_or(0);
         if c>1 { let ss = sumsqdiff.get(&k).cloned().unwrap_or(0.0); ss/((c-1) as f64) } else { f64::NAN }
     }).collect();
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&results))
+}
+
+/// GroupBy describe: count, mean, std, min, 25%, 50%, 75% and max of the
+/// non-null values of each group in one pass over the keys (each group is
+/// sorted once for its quantiles, interpolated linearly as in pandas).
+/// Returns the eight result series ids in that order, each in groupby
+/// order, or an empty array if the series is unknown or the keys do not
+/// match its length.
+#[wasm_bindgen]
+pub fn engine_groupby_describe_f64(series_id: u32, group_keys_json: &str) -> Box<[u32]> {
+    let _prof = profile("engine_groupby_describe_f64", || series_bytes(series_id));
+    let order = take_group_order();
+    let keys: Vec<String> = serde_json::from_str(group_keys_json).unwrap_or_default();
+    let values = unsafe { f64_values(series_id) };
+    let src_len = values.as_ref().map_or(0, |v| v.len());
+    let values = match values {
+        Some(values) if keys.len() == src_len => values,
+        _ => {
+            engine_log!(warn, "engine_groupby_describe_f64: unknown series or key mismatch series_id={} len={} keys={}", series_id, src_len, keys.len());
+            return Box::new([]);
+        }
+    };
+
+    // Every key gets a row, as in `engine_groupby_count_f64`
+    let mut groups: HashMap<&str, Vec<f64>> = HashMap::new();
+    for (i, key) in keys.iter().enumerate() {
+        let group = groups.entry(key.as_str()).or_default();
+        let v = values.get(i);
+        if !v.is_nan() { group.push(v); }
+    }
+    let ordered_keys = output_keys(&order, groups.keys().map(|k| k.to_string()).collect(), &keys);
+
+    let mut columns: [Vec<f64>; 8] = Default::default();
+    for key in ordered_keys.iter() {
+        let group = groups.get_mut(key.as_str()).map_or(&mut [][..], |g| g.as_mut_slice());
+        group.sort_unstable_by(|a, b| a.total_cmp(b));
+        let mut stats = RunningStats::default();
+        group.iter().for_each(|&v| stats.push(v));
+        let row = [
+            stats.finish(AGG_COUNT),
+            stats.finish(AGG_MEAN),
+            stats.finish(AGG_STD),
+            stats.finish(AGG_MIN),
+            quantile_sorted(group, 0.25),
+            quantile_sorted(group, 0.5),
+            quantile_sorted(group, 0.75),
+            stats.finish(AGG_MAX),
+        ];
+        for (column, v) in columns.iter_mut().zip(row) {
+            column.push(v);
+        }
+    }
     ENGINE.with(|cell| {
         let mut eng = cell.borrow_mut();
-        let id = eng.next_series_id; eng.next_series_id = eng.next_series_id.wrapping_add(1);
-        let len = results.len();
-        let dst_ptr = unsafe {
-            let layout = std::alloc::Layout::from_size_align(len * std::mem::size_of::<f64>(), std::mem::align_of::<f64>()).unwrap();
-            let raw = std::alloc::alloc(layout) as *mut f64;
-            if !raw.is_null() && len > 0 { std::ptr::copy_nonoverlapping(results.as_ptr(), raw, len); }
-            raw
-        };
-        eng.series_store.insert(id, (dst_ptr, len)); id
+        columns.iter().map(|column| eng.register_series_f64(column)).collect()
     })
 }
 
+/// Stratified sample: up to `n_per_group` row indices from each group,
+/// chosen uniformly by reservoir sampling in one pass over the keys.
+/// The same `seed` gives the same sample. Indices are returned group by
+/// group in groupby order, ascending within each group; an empty array if
+/// `group_keys_json` is not an array of strings.
+#[wasm_bindgen]
+pub fn engine_groupby_sample_indices(group_keys_json: &str, n_per_group: u32, seed: u64) -> Box<[u32]> {
+    let _prof = profile("engine_groupby_sample_indices", || group_keys_json.len());
+    let order = take_group_order();
+    let keys: Vec<String> = match serde_json::from_str(group_keys_json) {
+        Ok(keys) => keys,
+        Err(_) => {
+            engine_log!(warn, "engine_groupby_sample_indices: invalid keys JSON");
+            return Box::new([]);
+        }
+    };
+
+    let n = n_per_group as usize;
+    let mut rng = Rng::new(seed);
+    // Per group: rows seen so far and the current reservoir
+    let mut groups: HashMap<&str, (u64, Vec<u32>)> = HashMap::new();
+    for (row, key) in keys.iter().enumerate() {
+        let (seen, reservoir) = groups.entry(key.as_str()).or_default();
+        *seen += 1;
+        if reservoir.len() < n {
+            reservoir.push(row as u32);
+        } else {
+            let slot = rng.below(*seen) as usize;
+            if slot < n {
+                reservoir[slot] = row as u32;
+            }
+        }
+    }
+
+    let ordered_keys = output_keys(&order, groups.keys().map(|k| k.to_string()).collect(), &keys);
+    let mut out = Vec::new();
+    for key in ordered_keys.iter() {
+        if let Some((_, reservoir)) = groups.get_mut(key.as_str()) {
+            reservoir.sort_unstable();
+            out.extend_from_slice(reservoir);
+        }
+    }
+    out.into_boxed_slice()
+}
+
 /// Batch multi-aggregation for groupby on f64 series.
 /// agg_mask bit layout (LSB -> MSB):
 /// 1=sum, 2=mean, 4=count, 8=min, 16=max, 32=std, 64=var
 /// Returns array of series ids in the above order for bits that are set.
 #[wasm_bindgen]
 pub fn engine_groupby_multi_f64(series_id: u32, group_keys_json: &str, agg_mask: u32) -> Box<[u32]> {
+    let _prof = profile("engine_groupby_multi_f64", || series_bytes(series_id));
+    let order = take_group_order();
     let keys: Vec<String> = serde_json::from_str(group_keys_json).unwrap_or_default();
-    let (src_ptr, src_len) = ENGINE.with(|cell| {
-        let eng = cell.borrow();
-        if let Some((ptr, len)) = eng.series_store.get(&series_id) { (*ptr, *len) } else { (std::ptr::null_mut(), 0) }
-    });
-    if src_ptr.is_null() || keys.len() != src_len { return Box::new([]); }
+    let values = unsafe { f64_values(series_id) };
+    let src_len = values.as_ref().map_or(0, |v| v.len());
+    let values = match values {
+        Some(values) if keys.len() == src_len => values,
+        _ => {
+            engine_log!(warn, "engine_groupby_multi_f64: unknown series or key mismatch series_id={} len={} keys={}", series_id, src_len, keys.len());
+            return Box::new([]);
+        }
+    };
 
     // Prepare maps
     let mut sums: HashMap<String, f64> = HashMap::new();
@@ -402,18 +523,16 @@ pub fn engine_groupby_multi_f64(series_id: u32, group_keys_json: &str, agg_mask:
     let need_min = (agg_mask & 8) != 0;
     let need_max = (agg_mask & 16) != 0;
 
-    unsafe {
-        for (i, key) in keys.iter().enumerate() {
-            let v = *src_ptr.add(i);
-            if v.is_nan() { continue; }
-            if need_sum { *sums.entry(key.clone()).or_insert(0.0) += v; }
-            if need_count { *counts.entry(key.clone()).or_insert(0) += 1; }
-            if need_min {
-                mins.entry(key.clone()).and_modify(|m| { if v < *m { *m = v; } }).or_insert(v);
-            }
-            if need_max {
-                maxs.entry(key.clone()).and_modify(|m| { if v > *m { *m = v; } }).or_insert(v);
-            }
+    for (i, key) in keys.iter().enumerate() {
+        let v = values.get(i);
+        if v.is_nan() { continue; }
+        if need_sum { *sums.entry(key.clone()).or_insert(0.0) += v; }
+        if need_count { *counts.entry(key.clone()).or_insert(0) += 1; }
+        if need_min {
+            mins.entry(key.clone()).and_modify(|m| { if v < *m { *m = v; } }).or_insert(v);
+        }
+        if need_max {
+            maxs.entry(key.clone()).and_modify(|m| { if v > *m { *m = v; } }).or_insert(v);
         }
     }
 
@@ -426,13 +545,11 @@ pub fn engine_groupby_multi_f64(series_id: u32, group_keys_json: &str, agg_mask:
     }
     let mut sumsqdiff: HashMap<String, f64> = HashMap::new();
     if (agg_mask & 32) != 0 || (agg_mask & 64) != 0 {
-        unsafe {
-            for (i, key) in keys.iter().enumerate() {
-                let v = *src_ptr.add(i);
-                if v.is_nan() { continue; }
-                let m = means.get(key).cloned().unwrap_or(f64::NAN);
-                if !m.is_nan() { *sumsqdiff.entry(key.clone()).or_insert(0.0) += (v - m) * (v - m); }
-            }
+        for (i, key) in keys.iter().enumerate() {
+            let v = values.get(i);
+            if v.is_nan() { continue; }
+            let m = means.get(key).cloned().unwrap_or(f64::NAN);
+            if !m.is_nan() { *sumsqdiff.entry(key.clone()).or_insert(0.0) += (v - m) * (v - m); }
         }
     }
 
@@ -444,23 +561,12 @@ pub fn engine_groupby_multi_f64(series_id: u32, group_keys_json: &str, agg_mask:
         for k in mins.keys() { if !ordered_keys.contains(k) { ordered_keys.push(k.clone()); } }
         for k in maxs.keys() { if !ordered_keys.contains(k) { ordered_keys.push(k.clone()); } }
     }
-    ordered_keys.sort();
+    let ordered_keys = output_keys(&order, ordered_keys, &keys);
 
     // Helper to register a result vec and return id
     let mut out_ids: Vec<u32> = Vec::new();
     let register_vec = |vals: Vec<f64>| -> u32 {
-        ENGINE.with(|cell| {
-            let mut eng = cell.borrow_mut();
-            let id = eng.next_series_id; eng.next_series_id = eng.next_series_id.wrapping_add(1);
-            let len = vals.len();
-            let dst_ptr = unsafe {
-                let layout = std::alloc::Layout::from_size_align(len * std::mem::size_of::<f64>(), std::mem::align_of::<f64>()).unwrap();
-                let raw = std::alloc::alloc(layout) as *mut f64;
-                if !raw.is_null() && len > 0 { std::ptr::copy_nonoverlapping(vals.as_ptr(), raw, len); }
-                raw
-            };
-            eng.series_store.insert(id, (dst_ptr, len)); id
-        })
+        ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&vals))
     };
 
     if (agg_mask & 1) != 0 {
@@ -503,3 +609,773 @@ pub fn engine_groupby_multi_f64(series_id: u32, group_keys_json: &str, agg_mask:
 
     out_ids.into_boxed_slice()
 }
+
+/// Fused filter + multi-aggregation groupby on an f64 series.
+/// Rows where `mask` is 0 are skipped during accumulation, so no filtered
+/// copies of the keys or values are made. `group_keys_json` and `mask` are
+/// aligned with the full (unfiltered) series. The groups are those of the
+/// filtered rows: a key whose rows are all masked out has no group, one
+/// whose kept values are all null gets an empty group (count 0, sum 0, other
+/// aggregates NaN). Returns the id of a string series holding the keys (in
+/// groupby order, see `engine_set_groupby_order`) followed by one id per bit
+/// set in `agg_mask` (bit layout of `engine_groupby_multi_f64`), or an empty
+/// array if the series is unknown or the keys or mask do not match it.
+#[wasm_bindgen]
+pub fn engine_groupby_filtered_f64(series_id: u32, group_keys_json: &str, mask: &[u8], agg_mask: u32) -> Box<[u32]> {
+    let _prof = profile("engine_groupby_filtered_f64", || series_bytes(series_id));
+    let order = take_group_order();
+    let _cancel = CancelScope::enter();
+    let keys: Vec<String> = serde_json::from_str(group_keys_json).unwrap_or_default();
+    let values = unsafe { f64_values(series_id) };
+    let src_len = values.as_ref().map_or(0, |v| v.len());
+    let values = match values {
+        Some(values) if keys.len() == src_len && mask.len() == src_len => values,
+        _ => {
+            engine_log!(warn, "engine_groupby_filtered_f64: unknown series or key/mask mismatch series_id={} len={} keys={} mask_len={}", series_id, src_len, keys.len(), mask.len());
+            return Box::new([]);
+        }
+    };
+
+    // Each group also keeps its first row for `GROUP_ORDER_FIRST_SEEN`
+    let partials = map_chunks(&keys, |offset, chunk| {
+        let mut groups: HashMap<&str, (usize, RunningStats)> = HashMap::new();
+        for (i, key) in chunk.iter().enumerate() {
+            if i % STEP_ROWS == 0 && cancel_requested() { break; }
+            let row = offset + i;
+            if mask[row] == 0 { continue; }
+            let stats = &mut groups.entry(key.as_str()).or_insert((row, RunningStats::default())).1;
+            let v = values.get(row);
+            if !v.is_nan() { stats.push(v); }
+        }
+        groups
+    });
+    if take_cancel_request() {
+        engine_log!(info, "engine_groupby_filtered_f64: cancelled series_id={}", series_id);
+        return Box::new([]);
+    }
+    let mut groups: HashMap<&str, (usize, RunningStats)> = HashMap::new();
+    for partial in partials {
+        for (key, (row, stats)) in partial {
+            let group = groups.entry(key).or_insert((row, RunningStats::default()));
+            group.0 = group.0.min(row);
+            group.1.merge(&stats);
+        }
+    }
+
+    let ordered = arrange_groups(&order, groups.keys().map(|k| k.to_string()).collect(), |k| groups[k.as_str()].0, |a, b| compare_str_keys(a, b), str::to_string);
+    let stats: Vec<RunningStats> = ordered.iter().map(|k| groups.get(k.as_str()).map_or_else(RunningStats::default, |g| g.1)).collect();
+    let keys: StrSeries = ordered.iter().map(|k| Some(k.as_str())).collect();
+    let keys_id = ENGINE.with(|cell| cell.borrow_mut().register_series_str(keys));
+    std::iter::once(keys_id).chain(register_group_aggs(&stats, agg_mask).iter().copied()).collect()
+}
+
+/// Multi-aggregation groupby keyed by strings in the packed binary
+/// protocol (see `isin_string_packed`), which are grouped straight from the
+/// byte buffer rather than decoded from JSON into owned strings. Returns the
+/// id of a string series holding the distinct keys (in groupby order, see
+/// `engine_set_groupby_order`) followed by one id per bit set in `agg_mask`
+/// (multi-aggregation bit layout), or an empty array if the series is
+/// unknown, the buffers are malformed or the key count differs.
+#[wasm_bindgen]
+pub fn engine_groupby_packed_f64(series_id: u32, key_bytes: &[u8], key_offsets: &[u32], agg_mask: u32) -> Box<[u32]> {
+    let _prof = profile("engine_groupby_packed_f64", || series_bytes(series_id));
+    let order = take_group_order();
+    let _cancel = CancelScope::enter();
+    let values = unsafe { f64_values(series_id) };
+    let keys = packed_strs(key_bytes, key_offsets);
+    let (values, keys) = match (values, keys) {
+        (Some(values), Some(keys)) if values.len() == keys.len() => (values, keys),
+        _ => {
+            engine_log!(warn, "engine_groupby_packed_f64: unknown series, malformed keys or length mismatch series_id={} offsets={}", series_id, key_offsets.len());
+            return Box::new([]);
+        }
+    };
+
+    // Each group also keeps its first row for `GROUP_ORDER_FIRST_SEEN`
+    let partials = map_chunks(&keys, |offset, chunk| {
+        let mut groups: HashMap<&str, (usize, RunningStats)> = HashMap::new();
+        for (i, &key) in chunk.iter().enumerate() {
+            if i % STEP_ROWS == 0 && cancel_requested() { break; }
+            let row = offset + i;
+            let stats = &mut groups.entry(key).or_insert((row, RunningStats::default())).1;
+            let v = values.get(row);
+            if !v.is_nan() { stats.push(v); }
+        }
+        groups
+    });
+    if take_cancel_request() {
+        engine_log!(info, "engine_groupby_packed_f64: cancelled series_id={}", series_id);
+        return Box::new([]);
+    }
+    let mut groups: HashMap<&str, (usize, RunningStats)> = HashMap::new();
+    for partial in partials {
+        for (key, (row, stats)) in partial {
+            let group = groups.entry(key).or_insert((row, RunningStats::default()));
+            group.0 = group.0.min(row);
+            group.1.merge(&stats);
+        }
+    }
+
+    let ordered = arrange_groups(&order, groups.keys().map(|k| k.to_string()).collect(), |k| groups[k.as_str()].0, |a, b| compare_str_keys(a, b), str::to_string);
+    let stats: Vec<RunningStats> = ordered.iter().map(|k| groups.get(k.as_str()).map_or_else(RunningStats::default, |g| g.1)).collect();
+    let keys: StrSeries = ordered.iter().map(|k| Some(k.as_str())).collect();
+    let keys_id = ENGINE.with(|cell| cell.borrow_mut().register_series_str(keys));
+    std::iter::once(keys_id).chain(register_group_aggs(&stats, agg_mask).iter().copied()).collect()
+}
+
+/// Multi-aggregation groupby keyed by a run-length encoded series
+/// (`engine_series_rle_encode`). Each run is accumulated into its group in
+/// one pass with no per-row key lookups, which makes data already sorted by
+/// the key cheap to aggregate. Rows with a null (NaN) key are skipped.
+/// Returns the id of a float64 series holding the distinct keys (in groupby
+/// order, see `engine_set_groupby_order`) followed by one id per bit set in `agg_mask` (multi-aggregation bit
+/// layout), or an empty array if either id is unknown or the lengths differ.
+#[wasm_bindgen]
+pub fn engine_groupby_rle_f64(series_id: u32, rle_key_id: u32, agg_mask: u32) -> Box<[u32]> {
+    let _prof = profile("engine_groupby_rle_f64", || series_bytes(series_id));
+    let order = take_group_order();
+    let keys = ENGINE.with(|cell| cell.borrow().series_store_rle.get(&rle_key_id).cloned());
+    let values = unsafe { f64_values(series_id) };
+    let (values, keys) = match (values, keys) {
+        (Some(values), Some(keys)) if values.len() == keys.len() => (values, keys),
+        _ => {
+            engine_log!(warn, "engine_groupby_rle_f64: unknown series or length mismatch series_id={} rle_key_id={}", series_id, rle_key_id);
+            return Box::new([]);
+        }
+    };
+
+    // Keys are matched by bits, with -0.0 folded into 0.0
+    let mut first_rows: HashMap<u64, usize> = HashMap::new();
+    for (start, _, key) in keys.runs() {
+        if !key.is_nan() { first_rows.entry((key + 0.0).to_bits()).or_insert(start); }
+    }
+    let distinct = arrange_groups(
+        &order,
+        first_rows.keys().map(|&bits| f64::from_bits(bits)).collect(),
+        |k| first_rows[&k.to_bits()],
+        |a, b| a.total_cmp(b),
+        |k| k.trim().parse::<f64>().map_or(f64::NAN, |k| k + 0.0),
+    );
+    let index: HashMap<u64, usize> = distinct.iter().enumerate().filter(|(_, k)| !k.is_nan()).map(|(i, k)| (k.to_bits(), i)).collect();
+    let mut groups: Vec<RunningStats> = vec![RunningStats::default(); distinct.len()];
+    for (start, end, key) in keys.runs() {
+        let stats = match index.get(&(key + 0.0).to_bits()) {
+            Some(&group) if !key.is_nan() => &mut groups[group],
+            _ => continue,
+        };
+        for row in start..end {
+            let v = values.get(row);
+            if !v.is_nan() { stats.push(v); }
+        }
+    }
+
+    let keys_id = ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&distinct));
+    std::iter::once(keys_id).chain(register_group_aggs(&groups, agg_mask).iter().copied()).collect()
+}
+
+/// Multi-aggregation groupby keyed by an interned string series
+/// (`engine_intern_str` / `engine_intern_keys_json`). Rows are grouped by
+/// dictionary code, so keys are neither parsed nor hashed as strings per
+/// call. Rows with a null key are skipped. Returns the id of an interned
+/// series holding the distinct keys (in groupby order, see
+/// `engine_set_groupby_order`) followed by one id per bit set in `agg_mask`
+/// (multi-aggregation bit layout), or an empty array if either id is
+/// unknown or the lengths differ.
+#[wasm_bindgen]
+pub fn engine_groupby_interned_f64(series_id: u32, key_id: u32, agg_mask: u32) -> Box<[u32]> {
+    let _prof = profile("engine_groupby_interned_f64", || series_bytes(series_id));
+    let order = take_group_order();
+    let codes = ENGINE.with(|cell| cell.borrow().series_store_interned.get(&key_id).cloned());
+    let values = unsafe { f64_values(series_id) };
+    let (values, codes) = match (values, codes) {
+        (Some(values), Some(codes)) if values.len() == codes.len() => (values, codes),
+        _ => {
+            engine_log!(warn, "engine_groupby_interned_f64: unknown series or length mismatch series_id={} key_id={}", series_id, key_id);
+            return Box::new([]);
+        }
+    };
+
+    let mut groups: HashMap<u32, (usize, RunningStats)> = HashMap::new();
+    for (row, &code) in codes.iter().enumerate() {
+        if code == u32::MAX { continue; }
+        let stats = &mut groups.entry(code).or_insert((row, RunningStats::default())).1;
+        let v = values.get(row);
+        if !v.is_nan() { stats.push(v); }
+    }
+    // Listed keys missing from the dictionary are interned so they can be returned
+    let keys = arrange_groups(
+        &order,
+        groups.keys().copied().collect(),
+        |code| groups[code].0,
+        |a, b| ENGINE.with(|cell| {
+            let eng = cell.borrow();
+            compare_str_keys(eng.interner.get(*a).unwrap_or(""), eng.interner.get(*b).unwrap_or(""))
+        }),
+        |k| ENGINE.with(|cell| cell.borrow_mut().intern(k)).unwrap_or(u32::MAX),
+    );
+    let stats: Vec<RunningStats> = keys.iter().map(|code| groups.get(code).map_or_else(RunningStats::default, |g| g.1)).collect();
+    let keys_id = ENGINE.with(|cell| cell.borrow_mut().try_register_series_interned(keys)).unwrap_or_else(|e| {
+        set_last_error(e);
+        u32::MAX
+    });
+    std::iter::once(keys_id).chain(register_group_aggs(&stats, agg_mask).iter().copied()).collect()
+}
+
+/// Exponentially weighted statistic within groups, as a full-length series
+/// aligned with the input. Rows are visited once in series order and each
+/// group keeps its own state, so every row sees the EWM of the earlier rows
+/// of its group (including itself). Weights follow pandas' `ewm(alpha,
+/// adjust=True)`: a row `k` rows back in the group weighs `(1 - alpha)^k`,
+/// with null values decaying the weights but not contributing.
+/// `agg_kind` is an aggregation code: 0 = sum, 1 = mean, 5 = std, 6 = var
+/// (std/var bias-corrected, NaN until the group has two values). Rows with a
+/// null key, and rows before a group's first value, are NaN.
+///
+/// `key_codes_id` is a group-code series (see `key_codes`). Returns
+/// u32::MAX if either id is unknown, the lengths differ, `alpha` is outside
+/// (0, 1] or `agg_kind` is unsupported.
+#[wasm_bindgen]
+pub fn engine_groupby_ewm_f64(value_id: u32, key_codes_id: u32, alpha: f64, agg_kind: u8) -> u32 {
+    let _prof = profile("engine_groupby_ewm_f64", || series_bytes(value_id));
+    let values = unsafe { f64_values(value_id) };
+    let codes = key_codes(key_codes_id);
+    let (values, codes) = match (values, codes) {
+        (Some(values), Some(codes))
+            if values.len() == codes.len()
+                && alpha > 0.0
+                && alpha <= 1.0
+                && matches!(agg_kind, AGG_SUM | AGG_MEAN | AGG_STD | AGG_VAR) =>
+        {
+            (values, codes)
+        }
+        _ => {
+            engine_log!(warn, "engine_groupby_ewm_f64: invalid input value_id={} key_codes_id={} alpha={} agg_kind={}", value_id, key_codes_id, alpha, agg_kind);
+            return u32::MAX;
+        }
+    };
+
+    let decay = 1.0 - alpha;
+    let mut states: HashMap<u32, EwmState> = HashMap::new();
+    let out: Vec<f64> = codes
+        .iter()
+        .enumerate()
+        .map(|(row, &code)| {
+            if code == u32::MAX {
+                return f64::NAN;
+            }
+            let state = states.entry(code).or_default();
+            state.push(values.get(row), decay);
+            state.finish(agg_kind)
+        })
+        .collect();
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&out))
+}
+
+/// Each value as a share of its group's total (nulls skipped in the
+/// totals), as pandas' `s / s.groupby(keys).transform("sum")`, in one pass
+/// to total the groups and one to divide. Rows with a null value or key
+/// are NaN. `key_codes_id` is a group-code series (see `key_codes`).
+/// Returns u32::MAX if either id is unknown or the lengths differ.
+#[wasm_bindgen]
+pub fn engine_groupby_share_f64(value_id: u32, key_codes_id: u32) -> u32 {
+    let _prof = profile("engine_groupby_share_f64", || series_bytes(value_id));
+    let (values, codes) = match (unsafe { f64_values(value_id) }, key_codes(key_codes_id)) {
+        (Some(values), Some(codes)) if values.len() == codes.len() => (values, codes),
+        _ => {
+            engine_log!(warn, "engine_groupby_share_f64: unknown series or length mismatch value_id={} key_codes_id={}", value_id, key_codes_id);
+            return u32::MAX;
+        }
+    };
+    let mut totals: HashMap<u32, f64> = HashMap::new();
+    for (v, &code) in values.iter().zip(&codes) {
+        if code != u32::MAX && !v.is_nan() {
+            *totals.entry(code).or_insert(0.0) += v;
+        }
+    }
+    let out: Vec<f64> = values
+        .iter()
+        .zip(&codes)
+        .map(|(v, code)| totals.get(code).map_or(f64::NAN, |total| v / total))
+        .collect();
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&out))
+}
+
+/// Custom per-group aggregation with a JS callback. `cb(values, code)` is
+/// called once per group, in groupby order (`engine_set_groupby_order`), with a `Float64Array`
+/// view of the group's values (row order, nulls included as NaN) that is
+/// valid only during the call, and returns the group's scalar result
+/// (non-numbers become NaN). Rows with a null key are skipped.
+/// `key_codes_id` is a group-code series (see `key_codes`).
+///
+/// Returns `[codes_id, results_id]`: a uint32 series of the group codes and
+/// a float64 series of the results, or an empty array if an id is unknown,
+/// the lengths differ or the callback throws (error code 6).
+#[wasm_bindgen]
+pub fn engine_groupby_apply(value_id: u32, key_codes_id: u32, cb: &JsFunction) -> Box<[u32]> {
+    let _prof = profile("engine_groupby_apply", || series_bytes(value_id));
+    let order = take_group_order();
+    let values = unsafe { f64_values(value_id) };
+    let codes = key_codes(key_codes_id);
+    let (values, codes) = match (values, codes) {
+        (Some(values), Some(codes)) if values.len() == codes.len() => (values, codes),
+        _ => {
+            engine_log!(warn, "engine_groupby_apply: unknown series or length mismatch value_id={} key_codes_id={}", value_id, key_codes_id);
+            return Box::new([]);
+        }
+    };
+    let mut groups: HashMap<u32, (usize, Vec<f64>)> = HashMap::new();
+    for (row, &code) in codes.iter().enumerate() {
+        if code != u32::MAX {
+            groups.entry(code).or_insert((row, Vec::new())).1.push(values.get(row));
+        }
+    }
+    let codes = arrange_groups(
+        &order,
+        groups.keys().copied().collect(),
+        |code| groups[code].0,
+        |a, b| a.cmp(b),
+        |k| k.trim().parse().unwrap_or(u32::MAX),
+    );
+
+    // The engine is not borrowed and the input values are no longer held
+    // here, so the callback may call back into it (even to free the input)
+    drop(values);
+    let mut results = Vec::with_capacity(codes.len());
+    for &code in codes.iter() {
+        let group = groups.get(&code).map_or(&[][..], |g| g.1.as_slice());
+        match call_with_view(cb, group, code as f64) {
+            Ok(result) => results.push(result),
+            Err(e) => {
+                set_last_error(e);
+                return Box::new([]);
+            }
+        }
+    }
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        Box::new([eng.register_series_u32(&codes), eng.register_series_f64(&results)])
+    })
+}
+
+/// Per-group category counts of `codes_id` grouped by `key_codes_id` (both
+/// group-code series), reduced with `measure`; shared by the per-group
+/// entropy and Gini impurity
+fn groupby_impurity(name: &'static str, codes_id: u32, key_codes_id: u32, measure: fn(&[u32]) -> f64) -> Box<[u32]> {
+    let order = take_group_order();
+    let (categories, keys) = match (key_codes(codes_id), key_codes(key_codes_id)) {
+        (Some(categories), Some(keys)) if categories.len() == keys.len() => (categories, keys),
+        _ => {
+            engine_log!(warn, "{}: unknown series or length mismatch codes_id={} key_codes_id={}", name, codes_id, key_codes_id);
+            return Box::new([]);
+        }
+    };
+    let mut groups: HashMap<u32, (usize, Vec<u32>)> = HashMap::new();
+    for (row, &key) in keys.iter().enumerate() {
+        if key != u32::MAX {
+            groups.entry(key).or_insert((row, Vec::new())).1.push(categories[row]);
+        }
+    }
+    let codes = arrange_groups(
+        &order,
+        groups.keys().copied().collect(),
+        |code| groups[code].0,
+        |a, b| a.cmp(b),
+        |k| k.trim().parse().unwrap_or(u32::MAX),
+    );
+    let results: Vec<f64> = codes
+        .iter()
+        .map(|code| groups.get(code).map_or(f64::NAN, |(_, rows)| measure(&code_counts(rows.iter().copied()))))
+        .collect();
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        Box::new([eng.register_series_u32(&codes), eng.register_series_f64(&results)])
+    })
+}
+
+/// Entropy in bits of the categories `codes_id` within each group of
+/// `key_codes_id` (see `engine_series_entropy`), in groupby order; rows
+/// with a null key are skipped. Returns `[codes_id, results_id]` as
+/// `engine_groupby_apply`, or an empty array if an id is not a group-code
+/// series or the lengths differ.
+#[wasm_bindgen]
+pub fn engine_groupby_entropy(codes_id: u32, key_codes_id: u32) -> Box<[u32]> {
+    let _prof = profile("engine_groupby_entropy", || series_bytes(codes_id));
+    groupby_impurity("engine_groupby_entropy", codes_id, key_codes_id, entropy)
+}
+
+/// Gini impurity of the categories `codes_id` within each group of
+/// `key_codes_id` (see `engine_series_gini`), in groupby order; results as
+/// `engine_groupby_entropy`
+#[wasm_bindgen]
+pub fn engine_groupby_gini(codes_id: u32, key_codes_id: u32) -> Box<[u32]> {
+    let _prof = profile("engine_groupby_gini", || series_bytes(codes_id));
+    groupby_impurity("engine_groupby_gini", codes_id, key_codes_id, gini)
+}
+
+/// Stable partition of the rows by group code, for splitting a frame into
+/// per-group frames without one filter call per group. `key_codes_id` is a
+/// group-code series (see `key_codes`); rows with a null key are left out.
+///
+/// Returns `[indices_id, codes_id, ends_id]`, three uint32 series: the row
+/// indices grouped by key (row order within each group), the code of each
+/// group in groupby order, and the exclusive end of each group's range in
+/// the indices (group `g` is `indices[ends[g - 1]..ends[g]]`, from 0 for the
+/// first). An empty array if the id is not a group-code series.
+#[wasm_bindgen]
+pub fn engine_partition_indices(key_codes_id: u32) -> Box<[u32]> {
+    let _prof = profile("engine_partition_indices", || series_bytes(key_codes_id));
+    let order = take_group_order();
+    let codes = match key_codes(key_codes_id) {
+        Some(codes) => codes,
+        None => {
+            engine_log!(warn, "engine_partition_indices: not a group-code series key_codes_id={}", key_codes_id);
+            return Box::new([]);
+        }
+    };
+    let mut groups: HashMap<u32, (usize, Vec<u32>)> = HashMap::new();
+    for (row, &code) in codes.iter().enumerate() {
+        if code != u32::MAX {
+            groups.entry(code).or_insert((row, Vec::new())).1.push(row as u32);
+        }
+    }
+    let codes = arrange_groups(
+        &order,
+        groups.keys().copied().collect(),
+        |code| groups[code].0,
+        |a, b| a.cmp(b),
+        |k| k.trim().parse().unwrap_or(u32::MAX),
+    );
+
+    let mut indices: Vec<u32> = Vec::new();
+    let mut ends: Vec<u32> = Vec::with_capacity(codes.len());
+    for code in codes.iter() {
+        if let Some((_, rows)) = groups.get(code) {
+            indices.extend_from_slice(rows);
+        }
+        ends.push(indices.len() as u32);
+    }
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        Box::new([eng.register_series_u32(&indices), eng.register_series_u32(&codes), eng.register_series_u32(&ends)])
+    })
+}
+
+/// Online EWM state (pandas' adjusted `ewmcov` recurrence for one series)
+#[derive(Default)]
+struct EwmState {
+    started: bool,
+    mean: f64,
+    var: f64,
+    old_wt: f64,
+    sum_wt: f64,
+    sum_wt2: f64,
+}
+
+impl EwmState {
+    fn push(&mut self, v: f64, decay: f64) {
+        if !self.started {
+            if !v.is_nan() {
+                *self = EwmState { started: true, mean: v, var: 0.0, old_wt: 1.0, sum_wt: 1.0, sum_wt2: 1.0 };
+            }
+            return;
+        }
+        self.old_wt *= decay;
+        self.sum_wt *= decay;
+        self.sum_wt2 *= decay * decay;
+        if v.is_nan() {
+            return;
+        }
+        let old_mean = self.mean;
+        let total = self.old_wt + 1.0;
+        self.mean = (self.old_wt * old_mean + v) / total;
+        self.var = (self.old_wt * (self.var + (old_mean - self.mean).powi(2)) + (v - self.mean).powi(2)) / total;
+        self.sum_wt += 1.0;
+        self.sum_wt2 += 1.0;
+        self.old_wt += 1.0;
+    }
+
+    fn finish(&self, agg_kind: u8) -> f64 {
+        if !self.started {
+            return f64::NAN;
+        }
+        match agg_kind {
+            AGG_SUM => self.mean * self.old_wt,
+            AGG_MEAN => self.mean,
+            _ => {
+                let numerator = self.sum_wt * self.sum_wt;
+                let denominator = numerator - self.sum_wt2;
+                let var = if denominator > 0.0 { numerator / denominator * self.var } else { f64::NAN };
+                if agg_kind == AGG_STD { var.sqrt() } else { var }
+            }
+        }
+    }
+}
+
+/// Group codes of a key-code series, one per row with u32::MAX for null:
+/// uint32 and interned series are used as-is, int32 / int64 codes must be
+/// non-negative (negative and null are null), uint8 codes are widened and
+/// float64 codes must be non-negative integers (others are null).
+/// None if the id is not one of these dtypes.
+pub(crate) fn key_codes(series_id: u32) -> Option<Vec<u32>> {
+    fn code(v: i64) -> u32 {
+        u32::try_from(v).unwrap_or(u32::MAX)
+    }
+    ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        if let Some(codes) = eng.series_store_interned.get(&series_id) {
+            Some(codes.to_vec())
+        } else if let Some(&(ptr, len)) = eng.series_store_u32.get(&series_id) {
+            Some(unsafe { std::slice::from_raw_parts(ptr, len) }.to_vec())
+        } else if let Some(&(ptr, len)) = eng.series_store_i32.get(&series_id) {
+            Some(unsafe { std::slice::from_raw_parts(ptr, len) }.iter().map(|&v| code(v as i64)).collect())
+        } else if let Some(&(ptr, len)) = eng.series_store_i64.get(&series_id) {
+            Some(unsafe { std::slice::from_raw_parts(ptr, len) }.iter().map(|&v| code(v)).collect())
+        } else if let Some(&(ptr, len)) = eng.series_store_u8.get(&series_id) {
+            Some(unsafe { std::slice::from_raw_parts(ptr, len) }.iter().map(|&v| v as u32).collect())
+        } else {
+            let values = eng.f64_values(series_id)?;
+            Some(
+                values
+                    .iter()
+                    .map(|v| if v >= 0.0 && v < u32::MAX as f64 && v.fract() == 0.0 { v as u32 } else { u32::MAX })
+                    .collect(),
+            )
+        }
+    })
+}
+
+/// Register one result series per bit set in `agg_mask` (multi-aggregation
+/// bit layout), each holding that aggregate of every group in order
+pub(crate) fn register_group_aggs(groups: &[RunningStats], agg_mask: u32) -> Box<[u32]> {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        let out_ids: Vec<u32> = (AGG_SUM..=AGG_VAR)
+            .filter(|agg| (agg_mask & (1 << agg)) != 0)
+            .map(|agg| {
+                let vals: Vec<f64> = groups.iter().map(|s| s.finish(agg)).collect();
+                eng.register_series_f64(&vals)
+            })
+            .collect();
+        out_ids.into_boxed_slice()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::engine_create_series_f64;
+    use crate::series::engine_series_to_vec_f64;
+
+    #[test]
+    fn explicit_order_applies_to_the_next_groupby_only() {
+        let values = engine_create_series_f64(&[1.0, 2.0, 3.0]);
+        let keys = r#"["b","a","c"]"#;
+        assert!(engine_set_groupby_order(GROUP_ORDER_EXPLICIT, r#"["c","b"]"#));
+        assert_eq!(engine_groupby_order(), GROUP_ORDER_EXPLICIT);
+        assert_eq!(engine_series_to_vec_f64(engine_groupby_sum_f64(values, keys)), vec![3.0, 1.0]);
+        assert_eq!(engine_groupby_order(), GROUP_ORDER_SORTED);
+        assert_eq!(engine_series_to_vec_f64(engine_groupby_sum_f64(values, keys)), vec![2.0, 1.0, 3.0]);
+    }
+
+    #[test]
+    fn expr_and_task_groupby_follow_the_groupby_order() {
+        use crate::expr::{engine_expr_col, engine_expr_collect, engine_expr_groupby};
+        use crate::tasks::{engine_task_result, engine_task_start_groupby_f64, engine_task_step};
+        let values = engine_create_series_f64(&[1.0, 2.0, 3.0, 4.0]);
+        let keys = r#"["10","2","10","1"]"#;
+        assert!(engine_set_groupby_order(GROUP_ORDER_FIRST_SEEN, ""));
+        let expr = engine_expr_groupby(engine_expr_col(values), keys, AGG_SUM);
+        assert_eq!(engine_series_to_vec_f64(engine_expr_collect(expr)), vec![4.0, 2.0, 4.0]);
+        let task = engine_task_start_groupby_f64(values, keys, 1);
+        assert_eq!(engine_task_step(task, 1000.0), 1.0);
+        assert_eq!(engine_series_to_vec_f64(engine_task_result(task)[0]), vec![4.0, 2.0, 4.0]);
+        assert!(engine_set_groupby_order(GROUP_ORDER_EXPLICIT, r#"["1","3"]"#));
+        assert_eq!(engine_series_to_vec_f64(engine_expr_collect(expr)), vec![4.0, 0.0]);
+    }
+
+    #[test]
+    fn filtered_groupby_returns_keys_of_the_kept_rows() {
+        use crate::series::engine_series_to_vec_str;
+        let values = engine_create_series_f64(&[1.0, f64::NAN, 3.0, 4.0]);
+        let ids = engine_groupby_filtered_f64(values, r#"["a","b","c","a"]"#, &[1, 1, 0, 1], (1 << AGG_SUM) | (1 << AGG_COUNT));
+        assert_eq!(ids.len(), 3);
+        assert_eq!(engine_series_to_vec_str(ids[0]), vec!["a", "b"]);
+        assert_eq!(engine_series_to_vec_f64(ids[1]), vec![5.0, 0.0]);
+        assert_eq!(engine_series_to_vec_f64(ids[2]), vec![2.0, 0.0]);
+    }
+
+    #[test]
+    fn failed_groupby_uses_up_the_explicit_order() {
+        let values = engine_create_series_f64(&[1.0, 2.0]);
+        assert!(engine_set_groupby_order(GROUP_ORDER_EXPLICIT, r#"["z"]"#));
+        assert_eq!(engine_groupby_sum_f64(u32::MAX - 1, r#"["a","b"]"#), u32::MAX);
+        assert_eq!(engine_groupby_order(), GROUP_ORDER_SORTED);
+        assert_eq!(engine_series_to_vec_f64(engine_groupby_sum_f64(values, r#"["b","a"]"#)), vec![2.0, 1.0]);
+    }
+
+    #[test]
+    fn sorted_order_compares_numeric_keys_by_value() {
+        let values = engine_create_series_f64(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+        let keys = r#"["10","x","2","1","-1.5"]"#;
+        assert_eq!(engine_series_to_vec_f64(engine_groupby_sum_f64(values, keys)), vec![5.0, 4.0, 3.0, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn packed_key_groupby_matches_json_keys() {
+        use crate::series::engine_series_to_vec_str;
+        let values = engine_create_series_f64(&[1.0, 2.0, f64::NAN, 4.0]);
+        // "b", "a", "b", "b"
+        let mask = (1 << AGG_SUM) | (1 << AGG_COUNT);
+        let ids = engine_groupby_packed_f64(values, b"babb", &[0, 1, 2, 3, 4], mask);
+        assert_eq!(ids.len(), 3);
+        assert_eq!(engine_series_to_vec_str(ids[0]), vec!["a", "b"]);
+        assert_eq!(engine_series_to_vec_f64(ids[1]), vec![2.0, 5.0]);
+        assert_eq!(engine_series_to_vec_f64(ids[2]), vec![1.0, 2.0]);
+        assert!(engine_groupby_packed_f64(values, b"bab", &[0, 1, 2, 3], mask).is_empty());
+        assert!(engine_groupby_packed_f64(values, b"babb", &[0, 1, 2, 3, 9], mask).is_empty());
+        assert!(engine_groupby_packed_f64(u32::MAX - 1, b"babb", &[0, 1, 2, 3, 4], mask).is_empty());
+    }
+
+    #[test]
+    fn describe_matches_pandas_per_group() {
+        let values = engine_create_series_f64(&[4.0, f64::NAN, 1.0, 3.0, f64::NAN, 2.0, 5.0]);
+        let ids = engine_groupby_describe_f64(values, r#"["a","b","a","a","c","a","b"]"#);
+        let columns: Vec<String> = ids.iter().map(|&id| format!("{:?}", engine_series_to_vec_f64(id))).collect();
+        assert_eq!(columns, [
+            "[4.0, 1.0, 0.0]",
+            "[2.5, 5.0, NaN]",
+            &format!("[{:?}, NaN, NaN]", (5.0f64 / 3.0).sqrt()),
+            "[1.0, 5.0, NaN]",
+            "[1.75, 5.0, NaN]",
+            "[2.5, 5.0, NaN]",
+            "[3.25, 5.0, NaN]",
+            "[4.0, 5.0, NaN]",
+        ]);
+        assert!(engine_groupby_describe_f64(values, r#"["a"]"#).is_empty());
+        assert!(engine_groupby_describe_f64(u32::MAX - 1, "[]").is_empty());
+    }
+
+    #[test]
+    fn rle_keyed_groupby_folds_signed_zero_and_skips_null_keys() {
+        use crate::rle::engine_series_rle_encode;
+        let values = engine_create_series_f64(&[1.0, 2.0, 3.0, f64::NAN, 5.0, 6.0]);
+        let keys = engine_series_rle_encode(engine_create_series_f64(&[2.0, 2.0, -0.0, 0.0, f64::NAN, 2.0]));
+        let ids = engine_groupby_rle_f64(values, keys, (1 << AGG_SUM) | (1 << AGG_COUNT));
+        assert_eq!(ids.len(), 3);
+        assert_eq!(format!("{:?}", engine_series_to_vec_f64(ids[0])), "[0.0, 2.0]");
+        assert_eq!(engine_series_to_vec_f64(ids[1]), vec![3.0, 9.0]);
+        assert_eq!(engine_series_to_vec_f64(ids[2]), vec![1.0, 3.0]);
+        assert!(engine_groupby_rle_f64(engine_create_series_f64(&[1.0]), keys, 1 << AGG_SUM).is_empty());
+        assert!(engine_groupby_rle_f64(values, values, 1 << AGG_SUM).is_empty());
+    }
+
+    #[test]
+    fn interned_groupby_skips_null_keys_and_interns_listed_keys() {
+        use crate::intern::{engine_intern_keys_json, engine_interned_to_str};
+        use crate::series::engine_series_to_json_str;
+        let values = engine_create_series_f64(&[1.0, 2.0, 3.0, 4.0]);
+        let keys = engine_intern_keys_json(r#"["b","a",null,"b"]"#);
+        let mask = (1 << AGG_SUM) | (1 << AGG_COUNT);
+        let ids = engine_groupby_interned_f64(values, keys, mask);
+        assert_eq!(engine_series_to_json_str(engine_interned_to_str(ids[0])), r#"["a","b"]"#);
+        assert_eq!(engine_series_to_vec_f64(ids[1]), vec![2.0, 5.0]);
+        assert_eq!(engine_series_to_vec_f64(ids[2]), vec![1.0, 2.0]);
+        assert!(engine_set_groupby_order(GROUP_ORDER_EXPLICIT, r#"["c","a"]"#));
+        let ids = engine_groupby_interned_f64(values, keys, mask);
+        assert_eq!(engine_series_to_json_str(engine_interned_to_str(ids[0])), r#"["c","a"]"#);
+        assert_eq!(engine_series_to_vec_f64(ids[1]), vec![0.0, 2.0]);
+        assert!(engine_groupby_interned_f64(values, engine_intern_keys_json(r#"["a"]"#), mask).is_empty());
+        assert!(engine_groupby_interned_f64(values, values, mask).is_empty());
+    }
+
+    #[test]
+    fn ewm_keeps_state_per_group_like_pandas() {
+        use crate::core::engine_create_series_u32;
+        let values = engine_create_series_f64(&[1.0, 10.0, 3.0, f64::NAN, 5.0]);
+        let codes = engine_create_series_u32(&[0, 1, 0, 0, u32::MAX]);
+        let ewm = |kind| engine_series_to_vec_f64(engine_groupby_ewm_f64(values, codes, 0.5, kind));
+        let mean = ewm(AGG_MEAN);
+        assert_eq!(&mean[..2], [1.0, 10.0]);
+        assert!((mean[2] - 7.0 / 3.0).abs() < 1e-12 && (mean[3] - 7.0 / 3.0).abs() < 1e-12);
+        assert!(mean[4].is_nan());
+        // A null value decays the earlier weights without contributing
+        let sum = ewm(AGG_SUM);
+        assert!((sum[2] - 3.5).abs() < 1e-12 && (sum[3] - 1.75).abs() < 1e-12);
+        let var = ewm(AGG_VAR);
+        assert!(var[0].is_nan() && var[1].is_nan());
+        assert!((var[2] - 2.0).abs() < 1e-12);
+        assert!((ewm(AGG_STD)[2] - 2f64.sqrt()).abs() < 1e-12);
+        assert_eq!(engine_groupby_ewm_f64(values, codes, 0.0, AGG_MEAN), u32::MAX);
+        assert_eq!(engine_groupby_ewm_f64(values, codes, 1.5, AGG_MEAN), u32::MAX);
+        assert_eq!(engine_groupby_ewm_f64(values, codes, 0.5, AGG_COUNT), u32::MAX);
+        assert_eq!(engine_groupby_ewm_f64(values, engine_create_series_u32(&[0]), 0.5, AGG_MEAN), u32::MAX);
+    }
+
+    #[test]
+    fn size_counts_every_row_per_group() {
+        assert_eq!(engine_series_to_vec_f64(engine_groupby_size(r#"["b","a","b","c","b"]"#)), vec![1.0, 3.0, 1.0]);
+        assert!(engine_series_to_vec_f64(engine_groupby_size("[]")).is_empty());
+        assert_eq!(engine_groupby_size(r#"["a",1]"#), u32::MAX);
+    }
+
+    #[test]
+    fn sample_indices_are_seeded_and_capped_per_group() {
+        let keys = r#"["a","b","a","a","a","b","a","a"]"#;
+        let sample = engine_groupby_sample_indices(keys, 3, 7);
+        assert_eq!(sample.len(), 5);
+        let (a, b) = sample.split_at(3);
+        assert!(a.windows(2).all(|w| w[0] < w[1]));
+        assert!(a.iter().all(|&row| [0, 2, 3, 4, 6, 7].contains(&row)));
+        assert_eq!(b, [1, 5]);
+        assert_eq!(engine_groupby_sample_indices(keys, 3, 7), sample);
+        assert_eq!(&*engine_groupby_sample_indices(keys, 10, 1), [0, 2, 3, 4, 6, 7, 1, 5]);
+        assert!(engine_groupby_sample_indices(keys, 0, 7).is_empty());
+        assert!(engine_groupby_sample_indices("{", 3, 7).is_empty());
+    }
+
+    #[test]
+    fn partition_groups_rows_stably_and_drops_null_keys() {
+        use crate::core::engine_create_series_i32;
+        use crate::series::engine_series_to_vec_u32;
+        let codes = engine_create_series_i32(&[2, 0, -1, 2, 0, 1]);
+        let parts = engine_partition_indices(codes);
+        let vecs: Vec<Vec<u32>> = parts.iter().map(|&id| engine_series_to_vec_u32(id)).collect();
+        assert_eq!(vecs, [vec![1, 4, 5, 0, 3], vec![0, 1, 2], vec![2, 3, 5]]);
+        assert!(engine_set_groupby_order(GROUP_ORDER_FIRST_SEEN, ""));
+        let parts = engine_partition_indices(codes);
+        let vecs: Vec<Vec<u32>> = parts.iter().map(|&id| engine_series_to_vec_u32(id)).collect();
+        assert_eq!(vecs, [vec![0, 3, 1, 4, 5], vec![2, 0, 1], vec![2, 4, 5]]);
+        assert!(engine_partition_indices(u32::MAX - 1).is_empty());
+    }
+
+    #[test]
+    fn impurity_per_group_leaves_out_null_categories() {
+        use crate::core::engine_create_series_u32;
+        use crate::series::engine_series_to_vec_u32;
+        let categories = engine_create_series_u32(&[0, 1, 0, 0, u32::MAX, 2, 2, u32::MAX]);
+        let keys = engine_create_series_u32(&[1, 1, 0, 0, 0, u32::MAX, 1, 2]);
+        let entropy = engine_groupby_entropy(categories, keys);
+        assert_eq!(engine_series_to_vec_u32(entropy[0]), [0, 1, 2]);
+        let bits = engine_series_to_vec_f64(entropy[1]);
+        assert_eq!(bits[0], 0.0);
+        assert!((bits[1] - 3f64.log2()).abs() < 1e-12);
+        assert!(bits[2].is_nan());
+        let gini = engine_series_to_vec_f64(engine_groupby_gini(categories, keys)[1]);
+        assert_eq!(gini[0], 0.0);
+        assert!((gini[1] - 2.0 / 3.0).abs() < 1e-12);
+        assert!(gini[2].is_nan());
+        assert!(engine_groupby_entropy(categories, engine_create_series_u32(&[0])).is_empty());
+        assert!(engine_groupby_gini(u32::MAX - 1, keys).is_empty());
+    }
+
+    #[test]
+    fn share_divides_by_the_group_total() {
+        let values = engine_create_series_f64(&[1.0, 3.0, f64::NAN, 2.0, 5.0, 4.0]);
+        let codes = engine_create_series_f64(&[0.0, 0.0, 0.0, 1.0, 1.5, 1.0]);
+        let share = engine_series_to_vec_f64(engine_groupby_share_f64(values, codes));
+        // 1.5 is not a group code, so that row has a null key
+        assert_eq!(format!("{:?}", share), format!("[0.25, 0.75, NaN, {:?}, NaN, {:?}]", 2.0 / 6.0, 4.0 / 6.0));
+        assert_eq!(engine_groupby_share_f64(values, engine_create_series_f64(&[0.0])), u32::MAX);
+        assert_eq!(engine_groupby_share_f64(u32::MAX - 1, codes), u32::MAX);
+    }
+}
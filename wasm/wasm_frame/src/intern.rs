@@ -0,0 +1,175 @@
+//! Interned string series
+//!
+//! Interned series store a u32 code per row into one engine-wide string
+//! dictionary (`EngineState::interner`), so values repeated within and across
+//! series, including group keys, are stored once. Codes are stable until
+//! `engine_flush`, which also clears the dictionary; freeing a series keeps
+//! its dictionary entries for the other series.
+
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+use crate::core::{packed_strs, EngineState, ENGINE};
+use crate::error::{set_last_error, EngineError};
+use crate::profiling::{profile, series_bytes};
+
+/// Intern `values` and register them as an interned series
+fn register_interned<'a>(eng: &mut EngineState, values: impl Iterator<Item = Option<&'a str>>) -> u32 {
+    let codes: Result<Vec<u32>, EngineError> =
+        values.map(|v| v.map_or(Ok(u32::MAX), |text| eng.intern(text))).collect();
+    match codes.and_then(|codes| eng.try_register_series_interned(codes)) {
+        Ok(id) => id,
+        Err(e) => {
+            set_last_error(e);
+            u32::MAX
+        }
+    }
+}
+
+/// Convert a string series into an interned series. Returns u32::MAX if the
+/// id is not a string series or the memory limit is reached.
+#[wasm_bindgen]
+pub fn engine_intern_str(series_id: u32) -> u32 {
+    let _prof = profile("engine_intern_str", || series_bytes(series_id));
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        let strings = match eng.series_store_str.get(&series_id) {
+            Some(strings) => strings.clone(),
+            None => return u32::MAX,
+        };
+        register_interned(&mut eng, strings.iter())
+    })
+}
+
+/// Intern group keys given as a JSON array of strings (nulls allowed), the
+/// format taken by the JSON-keyed groupby functions, for reuse across
+/// `engine_groupby_interned_f64` calls
+#[wasm_bindgen]
+pub fn engine_intern_keys_json(keys_json: &str) -> u32 {
+    let _prof = profile("engine_intern_keys_json", || keys_json.len());
+    let keys: Vec<Option<String>> = match serde_json::from_str(keys_json) {
+        Ok(keys) => keys,
+        Err(_) => {
+            engine_log!(warn, "engine_intern_keys_json: invalid keys JSON");
+            return u32::MAX;
+        }
+    };
+    ENGINE.with(|cell| register_interned(&mut cell.borrow_mut(), keys.iter().map(|k| k.as_deref())))
+}
+
+/// Intern group keys given in the packed binary string protocol (see
+/// `isin_string_packed`), the cheaper alternative to `engine_intern_keys_json`
+/// for large key columns. Returns u32::MAX if the buffers are malformed.
+#[wasm_bindgen]
+pub fn engine_intern_keys_packed(bytes: &[u8], offsets: &[u32]) -> u32 {
+    let _prof = profile("engine_intern_keys_packed", || bytes.len());
+    let keys = match packed_strs(bytes, offsets) {
+        Some(keys) => keys,
+        None => {
+            engine_log!(warn, "engine_intern_keys_packed: malformed buffers bytes={} offsets={}", bytes.len(), offsets.len());
+            return u32::MAX;
+        }
+    };
+    ENGINE.with(|cell| register_interned(&mut cell.borrow_mut(), keys.into_iter().map(Some)))
+}
+
+/// Expand an interned series into a plain string series
+#[wasm_bindgen]
+pub fn engine_interned_to_str(series_id: u32) -> u32 {
+    let _prof = profile("engine_interned_to_str", || series_bytes(series_id));
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        let strings = match eng.series_store_interned.get(&series_id) {
+            Some(codes) => codes.iter().map(|&code| eng.interner.get(code)).collect(),
+            None => return u32::MAX,
+        };
+        eng.register_series_str(strings)
+    })
+}
+
+/// Dictionary codes of an interned series (u32::MAX is null)
+#[wasm_bindgen]
+pub fn engine_interned_codes(series_id: u32) -> Vec<u32> {
+    ENGINE.with(|cell| cell.borrow().series_store_interned.get(&series_id).map(|codes| codes.to_vec()).unwrap_or_default())
+}
+
+/// Dictionary code of `text`, or u32::MAX if it has not been interned
+#[wasm_bindgen]
+pub fn engine_intern_lookup(text: &str) -> u32 {
+    ENGINE.with(|cell| cell.borrow().interner.lookup(text).unwrap_or(u32::MAX))
+}
+
+/// String behind a dictionary code (empty if unknown)
+#[wasm_bindgen]
+pub fn engine_intern_get(code: u32) -> String {
+    ENGINE.with(|cell| cell.borrow().interner.get(code).unwrap_or_default().to_string())
+}
+
+/// Dictionary usage and savings as JSON:
+/// `{"unique_strings", "dictionary_bytes", "interned_series", "values",
+///   "interned_bytes", "plain_bytes", "saved_bytes"}`.
+/// `plain_bytes` is what the same values would take as plain string series
+/// (bytes plus one u32 offset per value); `interned_bytes` is the dictionary
+/// plus one u32 code per value. Clones sharing codes are counted once.
+#[wasm_bindgen]
+pub fn engine_intern_stats() -> String {
+    ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        let mut seen = HashSet::new();
+        let (mut values, mut plain_bytes) = (0usize, 0usize);
+        for codes in eng.series_store_interned.values() {
+            if !seen.insert(codes.as_ptr()) {
+                continue;
+            }
+            values += codes.len();
+            plain_bytes += codes
+                .iter()
+                .map(|&code| eng.interner.get(code).map_or(0, str::len) + std::mem::size_of::<u32>())
+                .sum::<usize>();
+        }
+        let interned_bytes = eng.interner.bytes() + values * std::mem::size_of::<u32>();
+        serde_json::json!({
+            "unique_strings": eng.interner.len(),
+            "dictionary_bytes": eng.interner.bytes(),
+            "interned_series": eng.series_store_interned.len(),
+            "values": values,
+            "interned_bytes": interned_bytes,
+            "plain_bytes": plain_bytes,
+            "saved_bytes": plain_bytes as i64 - interned_bytes as i64,
+        })
+        .to_string()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::engine_create_series_str_packed;
+    use crate::series::engine_series_to_json_str;
+
+    #[test]
+    fn repeated_values_share_codes_across_series() {
+        let plain = engine_create_series_str_packed(b"abba", &[0, 1, 3, 4, 4, 4], &[0, 0, 0, 0, 1]);
+        let interned = engine_intern_str(plain);
+        let codes = engine_interned_codes(interned);
+        assert_eq!((codes[0], codes[4]), (codes[2], u32::MAX));
+        assert_eq!(engine_intern_lookup(""), codes[3]);
+        assert_ne!(codes[0], codes[1]);
+        let keys = engine_intern_keys_json(r#"["bb", null, "a"]"#);
+        assert_eq!(engine_interned_codes(keys), [codes[1], u32::MAX, codes[0]]);
+        assert_eq!(engine_interned_codes(engine_intern_keys_packed(b"bba", &[0, 2, 3])), [codes[1], codes[0]]);
+        assert_eq!(engine_intern_lookup("bb"), codes[1]);
+        assert_eq!(engine_intern_lookup("zz"), u32::MAX);
+        assert_eq!(engine_intern_get(codes[0]), "a");
+        assert_eq!(engine_series_to_json_str(engine_interned_to_str(interned)), r#"["a","bb","a","",null]"#);
+        let stats: serde_json::Value = serde_json::from_str(&engine_intern_stats()).unwrap();
+        assert_eq!(stats["unique_strings"], 3);
+    }
+
+    #[test]
+    fn malformed_input_is_rejected() {
+        assert_eq!(engine_intern_keys_json("{"), u32::MAX);
+        assert_eq!(engine_intern_keys_packed(b"ab", &[0, 3]), u32::MAX);
+        assert_eq!(engine_intern_str(u32::MAX - 1), u32::MAX);
+        assert_eq!(engine_interned_to_str(u32::MAX - 1), u32::MAX);
+    }
+}
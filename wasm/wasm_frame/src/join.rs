@@ -0,0 +1,434 @@
+//! Joins and lookups between series
+//!
+//! Hash joins on float64 keys, and lighter alternatives for common shapes:
+//! lookups into a table already sorted by key, index enumeration for small
+//! joins, and index alignment for arithmetic between labelled series.
+
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+use crate::cast::{Cell, Source};
+use crate::core::{engine_series_is_monotonic_increasing, f64_values, try_vec, EngineState, F64Values, StrSeries, ENGINE};
+use crate::error::set_last_error;
+use crate::profiling::{profile, series_bytes};
+use crate::series::{arith, ARITH_ADD, ARITH_DIV};
+use crate::tasks::{cancel_requested, cancelled, CancelScope, STEP_ROWS};
+
+/// Sorted lookup table: keys ascending without nulls, values aligned
+///
+/// # Safety
+///
+/// As for `f64_values`: the values series must outlive the returned table.
+unsafe fn lookup_table<'a>(sorted_keys_id: u32, sorted_values_id: u32) -> Option<(Vec<f64>, F64Values<'a>)> {
+    let keys: Vec<f64> = unsafe { f64_values(sorted_keys_id) }?.iter().collect();
+    let values = unsafe { f64_values(sorted_values_id) }?;
+    let sorted = engine_series_is_monotonic_increasing(sorted_keys_id);
+    (sorted && keys.len() == values.len()).then_some((keys, values))
+}
+
+/// Look up each probe in a sorted table with `find` (returning a row of
+/// the table) and register the looked-up values
+fn sorted_lookup(name: &'static str, sorted_keys_id: u32, sorted_values_id: u32, probe_series_id: u32, find: impl Fn(&[f64], f64) -> Option<usize>) -> u32 {
+    let _prof = profile(name, || series_bytes(probe_series_id));
+    let (keys, values, probes) = match (unsafe { lookup_table(sorted_keys_id, sorted_values_id) }, unsafe { f64_values(probe_series_id) }) {
+        (Some((keys, values)), Some(probes)) => (keys, values, probes),
+        _ => {
+            engine_log!(warn, "{}: unknown series, unsorted keys or length mismatch sorted_keys_id={} sorted_values_id={}", name, sorted_keys_id, sorted_values_id);
+            return u32::MAX;
+        }
+    };
+    let out: Vec<f64> = probes
+        .iter()
+        .map(|probe| if probe.is_nan() { None } else { find(&keys, probe) })
+        .map(|row| row.map_or(f64::NAN, |row| values.get(row)))
+        .collect();
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&out))
+}
+
+/// Vectorized binary-search lookup: for each probe, the value of the first
+/// table row whose key equals it (NaN if none or the probe is null).
+/// `sorted_keys_id` must be sorted ascending without nulls and aligned with
+/// `sorted_values_id`. Returns the id of a float64 series aligned with the
+/// probes, or u32::MAX if an id is unknown, the keys are not sorted or the
+/// table lengths differ.
+#[wasm_bindgen]
+pub fn engine_sorted_lookup_f64(sorted_keys_id: u32, sorted_values_id: u32, probe_series_id: u32) -> u32 {
+    sorted_lookup("engine_sorted_lookup_f64", sorted_keys_id, sorted_values_id, probe_series_id, |keys, probe| {
+        let row = keys.partition_point(|&k| k < probe);
+        (row < keys.len() && keys[row] == probe).then_some(row)
+    })
+}
+
+/// Like `engine_sorted_lookup_f64`, but each probe takes the value of the
+/// nearest key (the first row of the lower key on a tie); NaN only for
+/// null probes or an empty table
+#[wasm_bindgen]
+pub fn engine_sorted_lookup_nearest_f64(sorted_keys_id: u32, sorted_values_id: u32, probe_series_id: u32) -> u32 {
+    sorted_lookup("engine_sorted_lookup_nearest_f64", sorted_keys_id, sorted_values_id, probe_series_id, |keys, probe| {
+        let above = keys.partition_point(|&k| k < probe);
+        if above == keys.len() {
+            return keys.len().checked_sub(1).map(|last| keys.partition_point(|&k| k < keys[last]));
+        }
+        if above == 0 || keys[above] - probe < probe - keys[above - 1] {
+            return Some(above);
+        }
+        let below = keys[above - 1];
+        Some(keys.partition_point(|&k| k < below))
+    })
+}
+
+/// Row index pairs of the Cartesian product of a `left_len`-row and a
+/// `right_len`-row table, left-major (every right row for left row 0, then
+/// left row 1, ...), for small enumeration joins. Returns
+/// `[left_indices_id, right_indices_id]` (two uint32 series), or an empty
+/// array if the product has more than `limit` rows, so an accidental large
+/// cross join fails fast instead of exhausting memory (an empty array too
+/// if the pairs do not fit under the memory limit).
+#[wasm_bindgen]
+pub fn engine_cross_join_indices(left_len: u32, right_len: u32, limit: u32) -> Box<[u32]> {
+    let rows = left_len as u64 * right_len as u64;
+    let _prof = profile("engine_cross_join_indices", || rows.min(limit as u64) as usize * 2 * std::mem::size_of::<u32>());
+    if rows > limit as u64 {
+        engine_log!(warn, "engine_cross_join_indices: {} x {} rows exceeds limit {}", left_len, right_len, limit);
+        return Box::new([]);
+    }
+    let (mut left, mut right): (Vec<u32>, Vec<u32>) = match (try_vec(rows as usize), try_vec(rows as usize)) {
+        (Ok(left), Ok(right)) => (left, right),
+        (Err(e), _) | (_, Err(e)) => {
+            set_last_error(e);
+            return Box::new([]);
+        }
+    };
+    left.extend((0..left_len).flat_map(|l| std::iter::repeat_n(l, right_len as usize)));
+    right.extend((0..left_len).flat_map(|_| 0..right_len));
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        Box::new([eng.register_series_u32(&left), eng.register_series_u32(&right)])
+    })
+}
+
+/// Rows of the right-hand table of a hash join, by key. Null (NaN) keys
+/// never match and -0.0 matches 0.0.
+#[derive(Default)]
+pub(crate) struct JoinTable {
+    rows: HashMap<u64, Vec<u32>>,
+}
+
+impl JoinTable {
+    fn key(value: f64) -> Option<u64> {
+        if value.is_nan() { None } else { Some((value + 0.0).to_bits()) }
+    }
+
+    /// Add right row `row`; rows must be inserted in ascending order
+    pub(crate) fn insert(&mut self, row: u32, value: f64) {
+        if let Some(key) = Self::key(value) {
+            self.rows.entry(key).or_default().push(row);
+        }
+    }
+
+    /// Append the pairs of left row `row` with every matching right row
+    pub(crate) fn probe(&self, row: u32, value: f64, left: &mut Vec<u32>, right: &mut Vec<u32>) {
+        if let Some(matches) = Self::key(value).and_then(|key| self.rows.get(&key)) {
+            left.extend(std::iter::repeat_n(row, matches.len()));
+            right.extend_from_slice(matches);
+        }
+    }
+}
+
+/// Register the row index pairs of a join as two uint32 series
+pub(crate) fn register_join_indices(left: &[u32], right: &[u32]) -> Box<[u32]> {
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        Box::new([eng.register_series_u32(left), eng.register_series_u32(right)])
+    })
+}
+
+/// Row index pairs of the inner hash join of two float64 key series:
+/// left-major, and for each left row its matching right rows in order.
+/// Null keys never match. Returns `[left_indices_id, right_indices_id]`
+/// (two uint32 series), or an empty array if an id is unknown, a table has
+/// more than u32::MAX rows or the join is cancelled.
+#[wasm_bindgen]
+pub fn engine_join_indices_f64(left_keys_id: u32, right_keys_id: u32) -> Box<[u32]> {
+    let _prof = profile("engine_join_indices_f64", || series_bytes(left_keys_id) + series_bytes(right_keys_id));
+    let _cancel = CancelScope::enter();
+    let (left_keys, right_keys) = match unsafe { (f64_values(left_keys_id), f64_values(right_keys_id)) } {
+        (Some(l), Some(r)) if l.len() <= u32::MAX as usize && r.len() <= u32::MAX as usize => (l, r),
+        _ => return Box::new([]),
+    };
+    let mut table = JoinTable::default();
+    for (row, value) in right_keys.iter().enumerate() {
+        table.insert(row as u32, value);
+    }
+    let (mut left, mut right) = (Vec::new(), Vec::new());
+    for (row, value) in left_keys.iter().enumerate() {
+        if row % STEP_ROWS == 0 && cancel_requested() {
+            return cancelled("engine_join_indices_f64", Box::new([]));
+        }
+        table.probe(row as u32, value, &mut left, &mut right);
+    }
+    register_join_indices(&left, &right)
+}
+
+/// Index label of a row: numbers (in total order), text or null
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) enum Label<'a> {
+    Num(i64),
+    Text(&'a str),
+    Null,
+}
+
+impl Label<'_> {
+    fn num(v: f64) -> Self {
+        // Flip the magnitude bits of negatives so integer order is numeric order
+        let bits = (v + 0.0).to_bits() as i64;
+        Label::Num(bits ^ (((bits >> 63) as u64) >> 1) as i64)
+    }
+
+    fn to_f64(self) -> f64 {
+        match self {
+            Label::Num(key) => f64::from_bits((key ^ (((key >> 63) as u64) >> 1) as i64) as u64),
+            _ => f64::NAN,
+        }
+    }
+}
+
+/// Labels of an index series of any dtype
+pub(crate) fn labels<'a>(source: &Source<'a>) -> Vec<Label<'a>> {
+    (0..source.len())
+        .map(|row| match source.cell(row) {
+            Cell::Null => Label::Null,
+            Cell::Text(text) => Label::Text(text),
+            cell => cell.number().map_or(Label::Null, Label::num),
+        })
+        .collect()
+}
+
+/// Series of labels: float64 if every label is numeric (or null),
+/// otherwise a string series with numbers formatted
+pub(crate) enum LabelSeries {
+    Num(Vec<f64>),
+    Text(StrSeries),
+}
+
+impl LabelSeries {
+    pub(crate) fn new(labels: &[Label]) -> Self {
+        if labels.iter().all(|label| !matches!(label, Label::Text(_))) {
+            return LabelSeries::Num(labels.iter().map(|label| label.to_f64()).collect());
+        }
+        let mut strings = StrSeries::default();
+        for label in labels {
+            match label {
+                Label::Text(text) => strings.push(Some(text)),
+                Label::Num(_) => strings.push(Some(&label.to_f64().to_string())),
+                Label::Null => strings.push(None),
+            }
+        }
+        LabelSeries::Text(strings)
+    }
+
+    pub(crate) fn register(self, eng: &mut EngineState) -> u32 {
+        match self {
+            LabelSeries::Num(values) => eng.register_series_f64(&values),
+            LabelSeries::Text(strings) => eng.register_series_str(strings),
+        }
+    }
+}
+
+/// Outer-align two (index, values) pairs on their index labels and combine
+/// the values with `op` (0 = add, 1 = sub, 2 = mul, 3 = div), as pandas'
+/// aligned arithmetic: identical indexes keep their order, otherwise the
+/// result index is the sorted union of both (numbers before strings, nulls
+/// last) and labels present on one side only give NaN. Indexes may be of
+/// any dtype; values are float64.
+///
+/// Returns `[index_id, values_id]`: a float64 index when every label is
+/// numeric, otherwise a string index, and the float64 results. An empty
+/// array if an id is unknown, an index and its values differ in length, an
+/// index has duplicate labels or `op` is unknown.
+#[wasm_bindgen]
+pub fn engine_align_op_f64(left_index_id: u32, left_values_id: u32, right_index_id: u32, right_values_id: u32, op: u8) -> Box<[u32]> {
+    let _prof = profile("engine_align_op_f64", || series_bytes(left_values_id) + series_bytes(right_values_id));
+    let aligned = ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        let (left_index, right_index) = (Source::new(&eng, left_index_id)?, Source::new(&eng, right_index_id)?);
+        let (left_values, right_values) = (eng.f64_values(left_values_id)?, eng.f64_values(right_values_id)?);
+        if op > ARITH_DIV || left_index.len() != left_values.len() || right_index.len() != right_values.len() {
+            return None;
+        }
+        let (left, right) = (labels(&left_index), labels(&right_index));
+        let left_rows: HashMap<Label, usize> = left.iter().enumerate().map(|(row, &label)| (label, row)).collect();
+        let right_rows: HashMap<Label, usize> = right.iter().enumerate().map(|(row, &label)| (label, row)).collect();
+        if left_rows.len() != left.len() || right_rows.len() != right.len() {
+            return None;
+        }
+
+        let union: Vec<Label> = if left == right {
+            left
+        } else {
+            let mut union: Vec<Label> = left_rows.keys().chain(right_rows.keys()).copied().collect();
+            union.sort_unstable();
+            union.dedup();
+            union
+        };
+        let values: Vec<f64> = union
+            .iter()
+            .map(|label| {
+                let a = left_rows.get(label).map_or(f64::NAN, |&row| left_values.get(row));
+                let b = right_rows.get(label).map_or(f64::NAN, |&row| right_values.get(row));
+                arith(op, a, b)
+            })
+            .collect();
+        Some((LabelSeries::new(&union), values))
+    });
+    let (index, values) = match aligned {
+        Some(aligned) => aligned,
+        None => {
+            engine_log!(warn, "engine_align_op_f64: invalid input left=({}, {}) right=({}, {}) op={}", left_index_id, left_values_id, right_index_id, right_values_id, op);
+            return Box::new([]);
+        }
+    };
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        let index_id = index.register(&mut eng);
+        Box::new([index_id, eng.register_series_f64(&values)])
+    })
+}
+
+/// Index-aligned addition (see `engine_align_op_f64`)
+#[wasm_bindgen]
+pub fn engine_align_add_f64(left_index_id: u32, left_values_id: u32, right_index_id: u32, right_values_id: u32) -> Box<[u32]> {
+    engine_align_op_f64(left_index_id, left_values_id, right_index_id, right_values_id, ARITH_ADD)
+}
+
+/// Reindex fill policies: labels missing from the old index are NaN, or take
+/// the value of the previous / next old label (old index must be increasing)
+pub const FILL_NONE: u8 = 0;
+pub const FILL_FORWARD: u8 = 1;
+pub const FILL_BACKWARD: u8 = 2;
+
+/// Conform a labelled series to a new index, as pandas' `reindex`: each
+/// label of `new_index_id` takes the value at the same label of
+/// `index_id`, reordering, dropping and expanding rows as needed. Labels
+/// missing from the old index are NaN with `FILL_NONE`, or with
+/// `FILL_FORWARD` / `FILL_BACKWARD` take the value of the nearest old label
+/// before / after them (NaN past the ends), which requires the old index to
+/// be strictly increasing (e.g. aligning a series to a calendar). Indexes
+/// may be of any dtype and are compared as in `engine_align_op_f64`.
+///
+/// Returns the id of a float64 series aligned with the new index, or
+/// u32::MAX if an id is unknown, the old index and values differ in length,
+/// the old index has duplicate labels (or is not increasing for a fill) or
+/// `fill_policy` is unknown.
+#[wasm_bindgen]
+pub fn engine_reindex_f64(index_id: u32, values_id: u32, new_index_id: u32, fill_policy: u8) -> u32 {
+    let _prof = profile("engine_reindex_f64", || series_bytes(values_id));
+    let out = ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        let (index, new_index) = (Source::new(&eng, index_id)?, Source::new(&eng, new_index_id)?);
+        let values = eng.f64_values(values_id)?;
+        if fill_policy > FILL_BACKWARD || index.len() != values.len() {
+            return None;
+        }
+        let (old, new) = (labels(&index), labels(&new_index));
+        let out: Vec<f64> = if fill_policy == FILL_NONE {
+            let rows: HashMap<Label, usize> = old.iter().enumerate().map(|(row, &label)| (label, row)).collect();
+            if rows.len() != old.len() {
+                return None;
+            }
+            new.iter().map(|label| rows.get(label).map_or(f64::NAN, |&row| values.get(row))).collect()
+        } else {
+            if !old.windows(2).all(|w| w[0] < w[1]) {
+                return None;
+            }
+            new.iter()
+                .map(|label| {
+                    if *label == Label::Null {
+                        return f64::NAN;
+                    }
+                    let row = if fill_policy == FILL_FORWARD {
+                        old.partition_point(|old| old <= label).checked_sub(1)
+                    } else {
+                        Some(old.partition_point(|old| old < label)).filter(|&row| row < old.len())
+                    };
+                    row.map_or(f64::NAN, |row| values.get(row))
+                })
+                .collect()
+        };
+        Some(out)
+    });
+    match out {
+        Some(out) => ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&out)),
+        None => {
+            engine_log!(warn, "engine_reindex_f64: invalid input index_id={} values_id={} new_index_id={} fill_policy={}", index_id, values_id, new_index_id, fill_policy);
+            u32::MAX
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{engine_create_series_f64, engine_create_series_str};
+    use crate::series::{engine_series_to_json_str, engine_series_to_vec_f64, engine_series_to_vec_u32};
+
+    fn series(values: &[f64]) -> u32 {
+        engine_create_series_f64(values)
+    }
+
+    /// Values of a float64 series in debug form, so NaN compares equal
+    fn floats(id: u32) -> String {
+        format!("{:?}", engine_series_to_vec_f64(id))
+    }
+
+    fn pairs(ids: &[u32]) -> (Vec<u32>, Vec<u32>) {
+        (engine_series_to_vec_u32(ids[0]), engine_series_to_vec_u32(ids[1]))
+    }
+
+    #[test]
+    fn sorted_lookups_find_exact_and_nearest_keys() {
+        let (keys, values) = (series(&[1.0, 2.0, 2.0, 5.0]), series(&[10.0, 20.0, 21.0, 50.0]));
+        let probes = series(&[2.0, 3.0, f64::NAN, 5.0, 0.0]);
+        assert_eq!(floats(engine_sorted_lookup_f64(keys, values, probes)), "[20.0, NaN, NaN, 50.0, NaN]");
+        // 3.5 is as close to 2 as to 5, so it takes the first row of the lower key
+        let probes = series(&[3.5, 4.0, 100.0, -1.0, 2.0, f64::NAN]);
+        assert_eq!(floats(engine_sorted_lookup_nearest_f64(keys, values, probes)), "[20.0, 50.0, 50.0, 10.0, 20.0, NaN]");
+        let unsorted = series(&[2.0, 1.0, 3.0, 4.0]);
+        assert_eq!(engine_sorted_lookup_f64(unsorted, values, probes), u32::MAX);
+        assert_eq!(engine_sorted_lookup_f64(keys, series(&[1.0]), probes), u32::MAX);
+    }
+
+    #[test]
+    fn joins_enumerate_matching_rows() {
+        assert_eq!(pairs(&engine_cross_join_indices(2, 3, 6)), (vec![0, 0, 0, 1, 1, 1], vec![0, 1, 2, 0, 1, 2]));
+        assert!(engine_cross_join_indices(2, 3, 5).is_empty());
+        let left = series(&[1.0, f64::NAN, 2.0, -0.0]);
+        let right = series(&[2.0, 1.0, 1.0, 0.0, f64::NAN]);
+        assert_eq!(pairs(&engine_join_indices_f64(left, right)), (vec![0, 0, 2, 3], vec![1, 2, 0, 3]));
+        assert!(engine_join_indices_f64(left, u32::MAX - 1).is_empty());
+    }
+
+    #[test]
+    fn aligned_arithmetic_takes_the_label_union() {
+        let ids = engine_align_add_f64(series(&[1.0, 2.0, 3.0]), series(&[10.0, 20.0, 30.0]), series(&[3.0, 1.0, 4.0]), series(&[1.0, 2.0, 3.0]));
+        assert_eq!(floats(ids[0]), "[1.0, 2.0, 3.0, 4.0]");
+        assert_eq!(floats(ids[1]), "[12.0, NaN, 31.0, NaN]");
+        let text = |values: &[&str]| engine_create_series_str(values.iter().map(|v| v.to_string()).collect());
+        let ids = engine_align_op_f64(text(&["b", "a"]), series(&[4.0, 6.0]), text(&["b", "c"]), series(&[2.0, 1.0]), ARITH_DIV);
+        assert_eq!(engine_series_to_json_str(ids[0]), r#"["a","b","c"]"#);
+        assert_eq!(floats(ids[1]), "[NaN, 2.0, NaN]");
+        let duplicated = series(&[1.0, 1.0]);
+        assert!(engine_align_add_f64(duplicated, series(&[1.0, 2.0]), duplicated, series(&[1.0, 2.0])).is_empty());
+    }
+
+    #[test]
+    fn reindex_fills_missing_labels() {
+        let (index, values) = (series(&[1.0, 3.0, 5.0]), series(&[10.0, 30.0, 50.0]));
+        let new_index = series(&[0.0, 1.0, 2.0, 5.0, 6.0]);
+        let reindexed = |policy| floats(engine_reindex_f64(index, values, new_index, policy));
+        assert_eq!(reindexed(FILL_NONE), "[NaN, 10.0, NaN, 50.0, NaN]");
+        assert_eq!(reindexed(FILL_FORWARD), "[NaN, 10.0, 10.0, 50.0, 50.0]");
+        assert_eq!(reindexed(FILL_BACKWARD), "[10.0, 10.0, 30.0, 50.0, NaN]");
+        assert_eq!(engine_reindex_f64(index, values, new_index, 3), u32::MAX);
+        assert_eq!(engine_reindex_f64(series(&[3.0, 1.0, 5.0]), values, new_index, FILL_FORWARD), u32::MAX);
+    }
+}
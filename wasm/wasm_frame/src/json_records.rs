@@ -0,0 +1,342 @@
+//! JSON records ingestion: typed columns from JSON arrays and NDJSON
+//!
+//! This module parses an array of objects or newline-delimited JSON records
+//! directly into typed engine series, one record at a time, instead of
+//! going through `JSON.parse` and per-field extraction on the JS side. The
+//! same goes for string columns holding one JSON document per row, from
+//! which a field can be extracted into its own series.
+
+use serde_json::{self, Map, Value};
+use wasm_bindgen::prelude::*;
+use crate::core::{StrSeries, ENGINE};
+use crate::error::catch_panic;
+use crate::profiling::{profile, series_bytes};
+
+#[derive(Clone, Copy)]
+enum FieldType {
+    Float64,
+    Int32,
+    Bool,
+}
+
+impl FieldType {
+    fn parse(dtype: &str) -> Option<FieldType> {
+        match dtype {
+            "float64" | "f64" | "number" => Some(FieldType::Float64),
+            "int32" | "i32" => Some(FieldType::Int32),
+            "bool" | "boolean" => Some(FieldType::Bool),
+            _ => None,
+        }
+    }
+}
+
+/// Accumulates one typed column; missing or unconvertible values become
+/// nulls (NaN for f64-backed columns, i32::MIN for i32 columns).
+enum ColumnBuilder {
+    F64(Vec<f64>),
+    I32(Vec<i32>),
+}
+
+impl ColumnBuilder {
+    fn new(field_type: FieldType) -> Self {
+        match field_type {
+            FieldType::Int32 => ColumnBuilder::I32(Vec::new()),
+            _ => ColumnBuilder::F64(Vec::new()),
+        }
+    }
+
+    fn push(&mut self, field_type: FieldType, value: Option<&Value>) {
+        match self {
+            ColumnBuilder::F64(out) => out.push(match field_type {
+                FieldType::Bool => to_bool(value),
+                _ => to_f64(value),
+            }),
+            ColumnBuilder::I32(out) => out.push(to_i32(value)),
+        }
+    }
+}
+
+fn to_f64(value: Option<&Value>) -> f64 {
+    match value {
+        Some(Value::Number(n)) => n.as_f64().unwrap_or(f64::NAN),
+        Some(Value::Bool(b)) => *b as u8 as f64,
+        Some(Value::String(s)) => s.trim().parse::<f64>().unwrap_or(f64::NAN),
+        _ => f64::NAN,
+    }
+}
+
+fn to_i32(value: Option<&Value>) -> i32 {
+    let v = match value {
+        Some(Value::Number(n)) => n.as_i64().or_else(|| {
+            n.as_f64().filter(|f| f.fract() == 0.0 && f.abs() <= i32::MAX as f64).map(|f| f as i64)
+        }),
+        Some(Value::Bool(b)) => Some(*b as i64),
+        Some(Value::String(s)) => s.trim().parse::<i64>().ok(),
+        _ => None,
+    };
+    match v {
+        Some(v) if v > i32::MIN as i64 && v <= i32::MAX as i64 => v as i32,
+        _ => i32::MIN,
+    }
+}
+
+fn to_bool(value: Option<&Value>) -> f64 {
+    match value {
+        Some(Value::Bool(b)) => *b as u8 as f64,
+        Some(Value::Number(n)) => n.as_f64().map(|f| (f != 0.0) as u8 as f64).unwrap_or(f64::NAN),
+        Some(Value::String(s)) => match s.trim() {
+            "true" | "True" | "TRUE" | "1" => 1.0,
+            "false" | "False" | "FALSE" | "0" => 0.0,
+            _ => f64::NAN,
+        },
+        _ => f64::NAN,
+    }
+}
+
+fn skip_whitespace(bytes: &[u8], mut pos: usize) -> usize {
+    while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+        pos += 1;
+    }
+    pos
+}
+
+/// Visit each record of a JSON array or NDJSON buffer, parsing one record
+/// at a time so the whole document is never materialized.
+fn for_each_record<F: FnMut(&Map<String, Value>)>(bytes: &[u8], mut visit: F) -> Result<(), String> {
+    let mut pos = skip_whitespace(bytes, 0);
+    if bytes.get(pos) != Some(&b'[') {
+        for value in serde_json::Deserializer::from_slice(bytes).into_iter::<Value>() {
+            match value.map_err(|e| e.to_string())? {
+                Value::Object(record) => visit(&record),
+                _ => return Err("records must be JSON objects".to_string()),
+            }
+        }
+        return Ok(());
+    }
+
+    pos = skip_whitespace(bytes, pos + 1);
+    if bytes.get(pos) == Some(&b']') {
+        return Ok(());
+    }
+    loop {
+        let mut stream = serde_json::Deserializer::from_slice(&bytes[pos..]).into_iter::<Value>();
+        match stream.next() {
+            Some(Ok(Value::Object(record))) => visit(&record),
+            Some(Ok(_)) => return Err("records must be JSON objects".to_string()),
+            Some(Err(e)) => return Err(e.to_string()),
+            None => return Err("unexpected end of array".to_string()),
+        }
+        pos = skip_whitespace(bytes, pos + stream.byte_offset());
+        match bytes.get(pos) {
+            Some(b',') => pos = skip_whitespace(bytes, pos + 1),
+            Some(b']') => return Ok(()),
+            _ => return Err("expected ',' or ']'".to_string()),
+        }
+    }
+}
+
+/// Parse JSON records (array of objects or NDJSON) into typed series.
+/// `schema_json` is an array of `{"name": ..., "dtype": ...}` with dtype
+/// "float64", "int32" or "bool" (bool columns are stored as f64 1/0).
+/// Numeric strings are converted; missing or invalid values become nulls.
+/// Returns one series id per schema field, or an empty array on error.
+#[wasm_bindgen]
+pub fn engine_parse_json_records(bytes: &[u8], schema_json: &str) -> Box<[u32]> {
+    let _prof = profile("engine_parse_json_records", || bytes.len());
+    catch_panic(Box::new([]), || parse_json_records(bytes, schema_json))
+}
+
+fn parse_json_records(bytes: &[u8], schema_json: &str) -> Box<[u32]> {
+    let schema: Vec<Value> = serde_json::from_str(schema_json).unwrap_or_default();
+    let mut fields: Vec<(String, FieldType)> = Vec::with_capacity(schema.len());
+    for entry in schema.iter() {
+        let name = entry.get("name").and_then(|v| v.as_str());
+        let dtype = entry.get("dtype").and_then(|v| v.as_str()).and_then(FieldType::parse);
+        match (name, dtype) {
+            (Some(name), Some(dtype)) => fields.push((name.to_string(), dtype)),
+            _ => return Box::new([]),
+        }
+    }
+    if fields.is_empty() {
+        return Box::new([]);
+    }
+
+    let mut columns: Vec<ColumnBuilder> = fields.iter().map(|(_, t)| ColumnBuilder::new(*t)).collect();
+    let parsed = for_each_record(bytes, |record| {
+        for ((name, field_type), column) in fields.iter().zip(columns.iter_mut()) {
+            column.push(*field_type, record.get(name));
+        }
+    });
+    if let Err(e) = parsed {
+        engine_log!(warn, "engine_parse_json_records: {}", e);
+        return Box::new([]);
+    }
+
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        let ids: Vec<u32> = columns
+            .iter()
+            .map(|c| match c {
+                ColumnBuilder::F64(v) => eng.register_series_f64(v),
+                ColumnBuilder::I32(v) => eng.register_series_i32(v),
+            })
+            .collect();
+        ids.into_boxed_slice()
+    })
+}
+
+/// One step of a path into a JSON document
+enum PathStep {
+    Key(String),
+    Index(usize),
+}
+
+/// Parse a dotted path (`a.b[0].c`, with `[n]` array indexes); None if an
+/// index is malformed
+fn parse_path(path: &str) -> Option<Vec<PathStep>> {
+    let mut steps = Vec::new();
+    for part in path.split('.') {
+        let (key, mut indexes) = part.split_at(part.find('[').unwrap_or(part.len()));
+        if !key.is_empty() {
+            steps.push(PathStep::Key(key.to_string()));
+        }
+        while !indexes.is_empty() {
+            let (index, rest) = indexes.strip_prefix('[')?.split_once(']')?;
+            steps.push(PathStep::Index(index.parse().ok()?));
+            indexes = rest;
+        }
+    }
+    Some(steps)
+}
+
+/// Extract the value at `path` (a dotted path, or a JSON Pointer if it
+/// starts with `/`) from every row of a string series holding JSON
+/// documents, as a new series. The type follows the extracted values: if
+/// all are numbers or bools the result is float64 (bools as 1/0, as
+/// `engine_parse_json_records`), otherwise a string series with strings
+/// as-is and numbers, bools, objects and arrays as JSON text. Null rows,
+/// invalid JSON, missing paths and JSON nulls become nulls.
+///
+/// A dotted path is a sequence of object keys separated by `.`, each
+/// optionally followed by array indexes (`user.tags[0]`, `[2].id`); keys
+/// containing `.` or `[` need a JSON Pointer (`/a.b/0`). An empty path
+/// selects the whole document. Returns u32::MAX if the id is not a string
+/// series or the path is malformed.
+#[wasm_bindgen]
+pub fn engine_str_json_get(series_id: u32, path: &str) -> u32 {
+    let _prof = profile("engine_str_json_get", || series_bytes(series_id));
+    // A JSON Pointer is resolved by serde_json itself
+    let pointer = path.starts_with('/');
+    let steps = match if pointer { Some(Vec::new()) } else { parse_path(path) } {
+        Some(steps) => steps,
+        None => {
+            engine_log!(warn, "engine_str_json_get: malformed path {:?}", path);
+            return u32::MAX;
+        }
+    };
+    let lookup = |document: &Value| -> Option<Value> {
+        if pointer {
+            return document.pointer(path).cloned();
+        }
+        let mut value = document;
+        for step in &steps {
+            value = match step {
+                PathStep::Key(key) => value.as_object()?.get(key)?,
+                PathStep::Index(index) => value.as_array()?.get(*index)?,
+            };
+        }
+        Some(value.clone())
+    };
+    let extracted: Option<Vec<Option<Value>>> = ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        let strings = eng.series_store_str.get(&series_id)?;
+        Some(
+            strings
+                .iter()
+                .map(|text| {
+                    let document: Value = serde_json::from_str(text?).ok()?;
+                    lookup(&document).filter(|value| !value.is_null())
+                })
+                .collect(),
+        )
+    });
+    let extracted = match extracted {
+        Some(extracted) => extracted,
+        None => {
+            engine_log!(warn, "engine_str_json_get: unknown string series {}", series_id);
+            return u32::MAX;
+        }
+    };
+
+    let numeric = extracted.iter().flatten().all(|value| value.is_number() || value.is_boolean());
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        if numeric {
+            let values: Vec<f64> = extracted.iter().map(|value| to_f64(value.as_ref())).collect();
+            return eng.register_series_f64(&values);
+        }
+        let text: Vec<Option<String>> = extracted
+            .into_iter()
+            .map(|value| {
+                value.map(|value| match value {
+                    Value::String(s) => s,
+                    other => other.to_string(),
+                })
+            })
+            .collect();
+        let strings: StrSeries = text.iter().map(|v| v.as_deref()).collect();
+        eng.register_series_str(strings)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::engine_create_series_str;
+    use crate::series::{engine_series_to_json_str, engine_series_to_vec_f64, engine_series_to_vec_i32};
+
+    const SCHEMA: &str = r#"[{"name":"x","dtype":"float64"},{"name":"n","dtype":"int32"},{"name":"ok","dtype":"bool"}]"#;
+
+    #[test]
+    fn parses_arrays_and_ndjson_alike() {
+        let array = br#" [ {"x": 1.5, "n": 2, "ok": true}, {"x": "2.5", "n": "7", "ok": "false"}, {"n": 3e9} ] "#;
+        let ndjson = b"{\"x\": 1.5, \"n\": 2, \"ok\": true}\n{\"x\": \"2.5\", \"n\": \"7\", \"ok\": \"false\"}\n{\"n\": 3e9}\n";
+        for bytes in [&array[..], &ndjson[..]] {
+            let ids = engine_parse_json_records(bytes, SCHEMA);
+            let x = engine_series_to_vec_f64(ids[0]);
+            assert_eq!(x[..2], [1.5, 2.5]);
+            assert!(x[2].is_nan());
+            // 3e9 does not fit in int32 and becomes null
+            assert_eq!(engine_series_to_vec_i32(ids[1]), [2, 7, i32::MIN]);
+            let ok = engine_series_to_vec_f64(ids[2]);
+            assert_eq!(ok[..2], [1.0, 0.0]);
+            assert!(ok[2].is_nan());
+        }
+        assert_eq!(engine_series_to_vec_f64(engine_parse_json_records(b"[]", SCHEMA)[0]).len(), 0);
+    }
+
+    #[test]
+    fn rejects_malformed_records_and_schemas() {
+        assert!(engine_parse_json_records(b"[1, 2]", SCHEMA).is_empty());
+        assert!(engine_parse_json_records(br#"[{"x": 1} {"x": 2}]"#, SCHEMA).is_empty());
+        assert!(engine_parse_json_records(br#"[{"x": 1}"#, SCHEMA).is_empty());
+        assert!(engine_parse_json_records(br#"[{"x": 1}]"#, r#"[{"name":"x","dtype":"date"}]"#).is_empty());
+        assert!(engine_parse_json_records(br#"[{"x": 1}]"#, "[]").is_empty());
+    }
+
+    #[test]
+    fn json_get_follows_paths_and_pointers() {
+        let docs = engine_create_series_str(vec![
+            r#"{"user": {"tags": ["a", "b"], "age": 30}}"#.to_string(),
+            r#"{"user": {"tags": [], "age": true}}"#.to_string(),
+            "not json".to_string(),
+        ]);
+        assert_eq!(engine_series_to_json_str(engine_str_json_get(docs, "user.tags[1]")), r#"["b",null,null]"#);
+        let ages = engine_series_to_vec_f64(engine_str_json_get(docs, "/user/age"));
+        assert_eq!(ages[..2], [30.0, 1.0]);
+        assert!(ages[2].is_nan());
+        assert_eq!(engine_series_to_json_str(engine_str_json_get(docs, "user.tags")), r#"["[\"a\",\"b\"]","[]",null]"#);
+        assert_eq!(engine_str_json_get(docs, "user.tags[x]"), u32::MAX);
+        assert_eq!(engine_str_json_get(u32::MAX - 1, "user"), u32::MAX);
+    }
+}
@@ -4,6 +4,16 @@
 //! that are compiled to WebAssembly for use in the BoxFrame TypeScript library.
 //! The functionality is organized into logical modules for better maintainability.
 
+// Logging macros (declared first so every module can use them)
+#[macro_use]
+mod logging;
+#[cfg(feature = "tracing")]
+pub use logging::*;
+
+// Error codes and panic capture
+pub mod error;
+pub use error::*;
+
 // Core engine functionality
 pub mod core;
 pub use core::*;
@@ -12,18 +22,98 @@ pub use core::*;
 pub mod series;
 pub use series::*;
 
+// Type casting between series stores
+pub mod cast;
+pub use cast::*;
+
+// Fixed-point decimal series
+pub mod decimal;
+pub use decimal::*;
+
+// Locale-aware number formatting for display
+pub mod number_format;
+pub use number_format::*;
+
+// Run-length encoded series
+pub mod rle;
+pub use rle::*;
+
+// Interned string series
+pub mod intern;
+pub use intern::*;
+
+// JS callback bridge
+pub mod callbacks;
+pub use callbacks::*;
+
 // GroupBy operations
 pub mod groupby;
 pub use groupby::*;
 
+// Window functions over partitions
+pub mod window;
+pub use window::*;
+
+// Rolling window aggregations
+pub mod rolling;
+pub use rolling::*;
+
+// Time-series helpers, segment statistics and change detection
+pub mod timeseries;
+pub use timeseries::*;
+
+// Technical indicators for price series
+pub mod finance;
+pub use finance::*;
+
+// Convolution and smoothing filters
+pub mod signal;
+pub use signal::*;
+
+// Geospatial distances and bounding boxes
+pub mod geo;
+pub use geo::*;
+
 // Sorting operations
 pub mod sorting;
 pub use sorting::*;
 
+// Joins and sorted lookups
+pub mod join;
+pub use join::*;
+
 // Filtering operations
 pub mod filtering;
 pub use filtering::*;
 
+// Series comparison
+pub mod compare;
+pub use compare::*;
+
+// Row-wise operations across columns
+pub mod rowwise;
+pub use rowwise::*;
+
+// Data-quality validation
+pub mod validate;
+pub use validate::*;
+
+// Content hashing
+pub mod checksum;
+pub use checksum::*;
+
+// Value counts and frequency tables
+pub mod frequency;
+pub use frequency::*;
+
+// Text analytics: tokenization, n-grams and TF-IDF
+pub mod text;
+pub use text::*;
+
+// Binning and bucket assignment
+pub mod binning;
+pub use binning::*;
+
 // Statistical functions
 pub mod statistics;
 pub use statistics::*;
@@ -31,3 +121,55 @@ pub use statistics::*;
 // Membership operations
 pub mod membership;
 pub use membership::*;
+
+// Seeded pseudo-random numbers
+pub mod random;
+pub use random::*;
+
+// Chunked execution helpers
+pub mod parallel;
+
+// Arrow interop
+pub mod arrow;
+pub use arrow::*;
+
+// Parquet reading (feature-gated)
+#[cfg(feature = "parquet")]
+pub mod parquet;
+#[cfg(feature = "parquet")]
+pub use parquet::*;
+
+// Spectral analysis (feature-gated)
+#[cfg(feature = "fft")]
+pub mod fft;
+#[cfg(feature = "fft")]
+pub use fft::*;
+
+// Unicode normalization of string keys (feature-gated)
+#[cfg(feature = "unicode")]
+pub mod normalize;
+#[cfg(feature = "unicode")]
+pub use normalize::*;
+
+// JSON records ingestion
+pub mod json_records;
+pub use json_records::*;
+
+// Batched command protocol
+pub mod batch;
+pub use batch::*;
+
+// Lazy expressions
+pub mod expr;
+pub use expr::*;
+
+// Monotonic clock
+pub mod clock;
+
+// Cooperative chunked tasks
+pub mod tasks;
+pub use tasks::*;
+
+// Profiling counters
+pub mod profiling;
+pub use profiling::*;
@@ -0,0 +1,103 @@
+//! Structured logging to the JS console (feature `tracing`)
+//!
+//! Modules emit events with `engine_log!(level, "message key={}", value)`.
+//! With the `tracing` feature the events go through the `log` facade to a
+//! logger that routes them to `console.debug/info/warn/error`; the level is
+//! set at runtime with `engine_set_log_level` and starts as off. Without the
+//! feature the macro compiles to nothing.
+
+#[cfg(feature = "tracing")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "tracing")]
+macro_rules! engine_log {
+    ($level:ident, $($arg:tt)+) => {
+        log::$level!(target: "wasm_frame", $($arg)+)
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! engine_log {
+    ($level:ident, $($arg:tt)+) => {
+        if false {
+            let _ = format_args!($($arg)+);
+        }
+    };
+}
+
+#[cfg(all(feature = "tracing", target_arch = "wasm32"))]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console, js_name = debug)]
+    fn console_debug(message: &str);
+    #[wasm_bindgen(js_namespace = console, js_name = info)]
+    fn console_info(message: &str);
+    #[wasm_bindgen(js_namespace = console, js_name = warn)]
+    fn console_warn(message: &str);
+    #[wasm_bindgen(js_namespace = console, js_name = error)]
+    fn console_error(message: &str);
+}
+
+#[cfg(feature = "tracing")]
+struct ConsoleLogger;
+
+#[cfg(feature = "tracing")]
+impl log::Log for ConsoleLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message = format!("[{}] {} {}", record.target(), record.level(), record.args());
+        #[cfg(target_arch = "wasm32")]
+        match record.level() {
+            log::Level::Error => console_error(&message),
+            log::Level::Warn => console_warn(&message),
+            log::Level::Info => console_info(&message),
+            log::Level::Debug | log::Level::Trace => console_debug(&message),
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        eprintln!("{}", message);
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(feature = "tracing")]
+static LOGGER: ConsoleLogger = ConsoleLogger;
+
+/// Set the console log level: 0=off, 1=error, 2=warn, 3=info, 4=debug, 5=trace
+#[cfg(feature = "tracing")]
+#[wasm_bindgen]
+pub fn engine_set_log_level(level: u8) {
+    // Installing fails only if a logger is already set, which is fine
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(match level {
+        0 => log::LevelFilter::Off,
+        1 => log::LevelFilter::Error,
+        2 => log::LevelFilter::Warn,
+        3 => log::LevelFilter::Info,
+        4 => log::LevelFilter::Debug,
+        _ => log::LevelFilter::Trace,
+    });
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_level_maps_to_filters() {
+        engine_set_log_level(2);
+        assert_eq!(log::max_level(), log::LevelFilter::Warn);
+        assert!(log::logger().enabled(&log::Metadata::builder().level(log::Level::Error).target("wasm_frame").build()));
+        assert!(!log::logger().enabled(&log::Metadata::builder().level(log::Level::Info).target("wasm_frame").build()));
+        engine_set_log_level(9);
+        assert_eq!(log::max_level(), log::LevelFilter::Trace);
+        engine_set_log_level(0);
+        assert_eq!(log::max_level(), log::LevelFilter::Off);
+    }
+}
@@ -5,6 +5,7 @@
 
 use std::collections::HashSet;
 use wasm_bindgen::prelude::*;
+use crate::core::packed_strs;
 
 /// Check if values in an array are members of a given set (i32)
 /// 
@@ -67,4 +68,47 @@ pub fn isin_string(data: Vec<String>, values: Vec<String>) -> Vec<u8> {
     data.into_iter()
         .map(|val| if value_set.contains(&val) { 1 } else { 0 })
         .collect()
-}
\ No newline at end of file
+}
+
+/// Check if values in an array are members of a given set (strings in the
+/// packed binary protocol, avoiding per-string marshalling)
+///
+/// A packed string array is a UTF-8 byte buffer plus `n + 1` u32 offsets,
+/// string `i` being `bytes[offsets[i]..offsets[i + 1]]`. The same layout is
+/// taken by `engine_create_series_str_packed` and `engine_intern_keys_packed`.
+///
+/// # Arguments
+/// * `bytes`, `offsets` - Packed string values to check
+/// * `values_bytes`, `values_offsets` - Packed string values to check membership against
+///
+/// # Returns
+/// * Array of u8 values (0 = false, 1 = true) indicating membership, or an
+///   empty array if either packed array is malformed
+#[wasm_bindgen]
+pub fn isin_string_packed(bytes: &[u8], offsets: &[u32], values_bytes: &[u8], values_offsets: &[u32]) -> Vec<u8> {
+    let (data, values) = match (packed_strs(bytes, offsets), packed_strs(values_bytes, values_offsets)) {
+        (Some(data), Some(values)) => (data, values),
+        _ => return Vec::new(),
+    };
+    let value_set: HashSet<&str> = values.into_iter().collect();
+    data.iter().map(|val| value_set.contains(val) as u8).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packed_isin_matches_whole_strings() {
+        // "ab", "", "b", "\u{E9}" against "b", "\u{E9}"
+        let bytes = "abb\u{E9}".as_bytes();
+        let offsets = [0, 2, 2, 3, 5];
+        assert_eq!(isin_string_packed(bytes, &offsets, "b\u{E9}".as_bytes(), &[0, 1, 3]), [0, 0, 1, 1]);
+        assert_eq!(isin_string_packed(bytes, &offsets, b"", &[0, 0]), [0, 1, 0, 0]);
+        assert_eq!(isin_string_packed(bytes, &offsets, b"", &[0]), [0, 0, 0, 0]);
+        // Offsets past the buffer, decreasing or splitting a character
+        assert!(isin_string_packed(bytes, &[0, 6], b"b", &[0, 1]).is_empty());
+        assert!(isin_string_packed(bytes, &offsets, b"b", &[1, 0]).is_empty());
+        assert!(isin_string_packed(bytes, &[0, 4], b"b", &[0, 1]).is_empty());
+    }
+}
@@ -0,0 +1,231 @@
+//! Unicode normalization and case folding of string series
+//!
+//! Brings strings with different Unicode representations of the same text
+//! (precomposed "é" against "e" + combining acute, fullwidth against ASCII
+//! letters, "Straße" against "STRASSE") to one form, so that they match as
+//! groupby and join keys. Normalization follows UAX #15 and case folding the
+//! full mappings of `CaseFolding.txt`, using the tables in
+//! `normalize/tables.rs`. It is only compiled with the `unicode` feature.
+
+use wasm_bindgen::prelude::*;
+use crate::core::{StrSeries, ENGINE};
+use crate::profiling::{profile, series_bytes};
+
+mod tables;
+use tables::{CANONICAL_DECOMPOSITION, CASE_FOLDING, COMBINING_CLASS, COMPATIBILITY_DECOMPOSITION, COMPOSITION};
+
+// Normalization forms
+pub const NORM_NFC: u8 = 0;
+pub const NORM_NFKC: u8 = 1;
+pub const NORM_NFD: u8 = 2;
+pub const NORM_NFKD: u8 = 3;
+
+// Hangul syllables (Unicode 3.12, conjoining jamo behavior)
+const HANGUL_S_BASE: u32 = 0xAC00;
+const HANGUL_L_BASE: u32 = 0x1100;
+const HANGUL_V_BASE: u32 = 0x1161;
+const HANGUL_T_BASE: u32 = 0x11A7;
+const HANGUL_V_COUNT: u32 = 21;
+const HANGUL_T_COUNT: u32 = 28;
+const HANGUL_N_COUNT: u32 = HANGUL_V_COUNT * HANGUL_T_COUNT;
+const HANGUL_S_COUNT: u32 = 19 * HANGUL_N_COUNT;
+
+fn combining_class(c: char) -> u8 {
+    let cp = c as u32;
+    match COMBINING_CLASS.binary_search_by(|&(first, last, _)| {
+        if last < cp {
+            std::cmp::Ordering::Less
+        } else if first > cp {
+            std::cmp::Ordering::Greater
+        } else {
+            std::cmp::Ordering::Equal
+        }
+    }) {
+        Ok(i) => COMBINING_CLASS[i].2,
+        Err(_) => 0,
+    }
+}
+
+fn lookup(table: &'static [(char, &'static str)], c: char) -> Option<&'static str> {
+    table.binary_search_by_key(&c, |&(key, _)| key).ok().map(|i| table[i].1)
+}
+
+/// Append the full decomposition of `c` to `out`, compatibility mappings
+/// included if `compat`
+fn decompose_char(c: char, compat: bool, out: &mut Vec<char>) {
+    let s = (c as u32).wrapping_sub(HANGUL_S_BASE);
+    if s < HANGUL_S_COUNT {
+        let l = HANGUL_L_BASE + s / HANGUL_N_COUNT;
+        let v = HANGUL_V_BASE + (s % HANGUL_N_COUNT) / HANGUL_T_COUNT;
+        let t = s % HANGUL_T_COUNT;
+        out.extend([l, v].into_iter().filter_map(char::from_u32));
+        if t > 0 {
+            out.extend(char::from_u32(HANGUL_T_BASE + t));
+        }
+        return;
+    }
+    let mapped = if compat { lookup(COMPATIBILITY_DECOMPOSITION, c) } else { None };
+    match mapped.or_else(|| lookup(CANONICAL_DECOMPOSITION, c)) {
+        Some(decomposed) => out.extend(decomposed.chars()),
+        None => out.push(c),
+    }
+}
+
+/// Decompose `chars` and put each run of combining marks in canonical order
+fn decompose(chars: impl Iterator<Item = char>, compat: bool) -> Vec<char> {
+    let mut out = Vec::new();
+    for c in chars {
+        decompose_char(c, compat, &mut out);
+    }
+    // Stable sort of each run of non-starters by combining class
+    let mut start = 0;
+    while start < out.len() {
+        if combining_class(out[start]) == 0 {
+            start += 1;
+            continue;
+        }
+        let end = (start..out.len()).find(|&i| combining_class(out[i]) == 0).unwrap_or(out.len());
+        out[start..end].sort_by_key(|&c| combining_class(c));
+        start = end;
+    }
+    out
+}
+
+/// Primary composite of `a` followed by `b`, if there is one
+fn compose_pair(a: char, b: char) -> Option<char> {
+    let (a, b) = (a as u32, b as u32);
+    let l = a.wrapping_sub(HANGUL_L_BASE);
+    let v = b.wrapping_sub(HANGUL_V_BASE);
+    if l < 19 && v < HANGUL_V_COUNT {
+        return char::from_u32(HANGUL_S_BASE + (l * HANGUL_V_COUNT + v) * HANGUL_T_COUNT);
+    }
+    let s = a.wrapping_sub(HANGUL_S_BASE);
+    let t = b.wrapping_sub(HANGUL_T_BASE);
+    if s < HANGUL_S_COUNT && s % HANGUL_T_COUNT == 0 && t > 0 && t < HANGUL_T_COUNT {
+        return char::from_u32(a + t);
+    }
+    let key = (char::from_u32(a)?, char::from_u32(b)?);
+    COMPOSITION.binary_search_by_key(&key, |&(first, second, _)| (first, second)).ok().map(|i| COMPOSITION[i].2)
+}
+
+/// Canonical composition of decomposed, canonically ordered `chars`
+fn compose(chars: Vec<char>) -> Vec<char> {
+    let mut out: Vec<char> = Vec::with_capacity(chars.len());
+    let mut starter: Option<usize> = None;
+    // Combining class of the last character kept after the starter
+    let mut last_class = 0;
+    for c in chars {
+        let class = combining_class(c);
+        if let Some(s) = starter {
+            let adjacent = out.len() == s + 1;
+            if adjacent || (last_class != 0 && last_class < class) {
+                if let Some(composed) = compose_pair(out[s], c) {
+                    out[s] = composed;
+                    continue;
+                }
+            }
+        }
+        if class == 0 {
+            starter = Some(out.len());
+        }
+        last_class = class;
+        out.push(c);
+    }
+    out
+}
+
+/// Full case folding of `chars`
+fn case_fold(chars: Vec<char>) -> Vec<char> {
+    let mut out = Vec::with_capacity(chars.len());
+    for c in chars {
+        match lookup(CASE_FOLDING, c) {
+            Some(folded) => out.extend(folded.chars()),
+            None => out.extend(c.to_lowercase()),
+        }
+    }
+    out
+}
+
+/// `text` in normalization form `form`, case folded first if `fold` (as
+/// the canonical caseless match of Unicode 3.13: fold the NFD form, then
+/// normalize the result)
+fn normalize(text: &str, form: u8, fold: bool) -> String {
+    // ASCII is left unchanged by every form
+    if text.is_ascii() {
+        return if fold { text.to_ascii_lowercase() } else { text.to_string() };
+    }
+    let compat = form == NORM_NFKC || form == NORM_NFKD;
+    let mut chars = if fold { case_fold(decompose(text.chars(), false)) } else { text.chars().collect() };
+    chars = decompose(chars.into_iter(), compat);
+    if form == NORM_NFC || form == NORM_NFKC {
+        chars = compose(chars);
+    }
+    chars.into_iter().collect()
+}
+
+/// Normalize a string series to a Unicode normalization form: `NORM_NFC`
+/// (canonical composition), `NORM_NFKC` (compatibility composition, which
+/// also maps ligatures, fullwidth and superscript forms to plain letters),
+/// `NORM_NFD` or `NORM_NFKD` (the decomposed forms). With `case_fold`
+/// strings are also fully case folded ("Straße" and "STRASSE" both become
+/// "strasse"). Nulls stay null. Returns a new string series, or u32::MAX if
+/// the id is not a string series or `form` is unknown.
+#[wasm_bindgen]
+pub fn engine_str_normalize(series_id: u32, form: u8, case_fold: bool) -> u32 {
+    let _prof = profile("engine_str_normalize", || series_bytes(series_id));
+    if form > NORM_NFKD {
+        engine_log!(warn, "engine_str_normalize: unknown form {}", form);
+        return u32::MAX;
+    }
+    let normalized = ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        let strings = eng.series_store_str.get(&series_id)?;
+        let mut out = StrSeries::default();
+        for text in strings.iter() {
+            out.push(text.map(|text| normalize(text, form, case_fold)).as_deref());
+        }
+        Some(out)
+    });
+    match normalized {
+        Some(strings) => ENGINE.with(|cell| cell.borrow_mut().register_series_str(strings)),
+        None => {
+            engine_log!(warn, "engine_str_normalize: unknown string series {}", series_id);
+            u32::MAX
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{engine_create_series_f64, engine_create_series_str, engine_create_series_str_packed};
+    use crate::series::{engine_series_to_json_str, engine_series_to_vec_str};
+
+    fn normalized(text: &str, form: u8, case_fold: bool) -> String {
+        let id = engine_create_series_str(vec![text.to_string()]);
+        engine_series_to_vec_str(engine_str_normalize(id, form, case_fold)).remove(0)
+    }
+
+    #[test]
+    fn forms_compose_decompose_and_map_compatibility_characters() {
+        assert_eq!(normalized("e\u{301}", NORM_NFC, false), "\u{E9}");
+        assert_eq!(normalized("\u{E9}", NORM_NFD, false), "e\u{301}");
+        // Combining marks are put in canonical order (below before above)
+        assert_eq!(normalized("a\u{301}\u{316}", NORM_NFD, false), "a\u{316}\u{301}");
+        assert_eq!(normalized("\u{FB01}\u{FF25}", NORM_NFC, false), "\u{FB01}\u{FF25}");
+        assert_eq!(normalized("\u{FB01}\u{FF25}", NORM_NFKC, false), "fiE");
+        assert_eq!(normalized("\u{D55C}", NORM_NFD, false), "\u{1112}\u{1161}\u{11AB}");
+        assert_eq!(normalized("\u{1112}\u{1161}\u{11AB}", NORM_NFC, false), "\u{D55C}");
+    }
+
+    #[test]
+    fn case_folding_makes_caseless_matches_equal() {
+        assert_eq!(normalized("Stra\u{DF}e", NORM_NFC, true), "strasse");
+        assert_eq!(normalized("STRASSE", NORM_NFC, true), "strasse");
+        assert_eq!(normalized("\u{C9}COLE", NORM_NFC, true), normalized("e\u{301}cole", NORM_NFC, true));
+        let id = engine_create_series_str_packed(b"Ab", &[0, 2, 2], &[0, 1]);
+        assert_eq!(engine_series_to_json_str(engine_str_normalize(id, NORM_NFKC, true)), r#"["ab",null]"#);
+        assert_eq!(engine_str_normalize(id, 4, false), u32::MAX);
+        assert_eq!(engine_str_normalize(engine_create_series_f64(&[1.0]), NORM_NFC, false), u32::MAX);
+    }
+}
@@ -0,0 +1,300 @@
+//! Number formatting for display
+//!
+//! Formats float64 series into string series with a decimal pattern and
+//! locale separators, so that grids of numbers can be rendered without a
+//! per-cell `Intl.NumberFormat` call. Values are rounded half away from
+//! zero on their shortest decimal representation, as `Intl.NumberFormat`
+//! does, so 1.005 with two decimals gives "1.01".
+
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+use crate::core::{f64_values, StrSeries, ENGINE};
+use crate::profiling::{profile, series_bytes};
+
+/// Parsed decimal pattern
+struct NumberPattern {
+    prefix: String,
+    suffix: String,
+    min_int: usize,
+    min_frac: usize,
+    max_frac: usize,
+    /// Digits in the group next to the decimal point, and in the groups
+    /// further left (0 = no grouping)
+    primary_group: usize,
+    secondary_group: usize,
+    /// Power of ten the value is scaled by (2 for percent, 3 for per mille)
+    shift: i64,
+}
+
+impl NumberPattern {
+    /// Parse an ICU-style pattern such as `#,##0.00`, `0.0%` or
+    /// `¤#,##0.00`; None if it is malformed
+    fn parse(pattern: &str, currency: &str) -> Option<NumberPattern> {
+        let is_number = |c: char| "#0,.".contains(c);
+        let start = pattern.find(is_number)?;
+        let end = pattern.rfind(is_number)? + 1;
+        let (number, affixes) = (&pattern[start..end], [&pattern[..start], &pattern[end..]]);
+        if number.chars().any(|c| !is_number(c)) {
+            return None;
+        }
+        let (int_part, frac_part) = number.split_once('.').unwrap_or((number, ""));
+        if frac_part.contains(['.', ',']) || frac_part.trim_start_matches('0').contains('0') {
+            return None;
+        }
+        let groups: Vec<&str> = int_part.split(',').collect();
+        let (primary_group, secondary_group) = match groups.len() {
+            1 => (0, 0),
+            n => {
+                let primary = groups[n - 1].len();
+                (primary, if n > 2 { groups[n - 2].len() } else { primary })
+            }
+        };
+        if groups.len() > 1 && (primary_group == 0 || secondary_group == 0) {
+            return None;
+        }
+        let min_int = int_part.chars().filter(|&c| c == '0').count();
+        let min_frac = frac_part.chars().filter(|&c| c == '0').count();
+        let mut shift = 0;
+        let [prefix, suffix] = affixes.map(|affix| {
+            let mut out = String::new();
+            for c in affix.chars() {
+                match c {
+                    '%' => shift += 2,
+                    '\u{2030}' => shift += 3,
+                    _ => {}
+                }
+                match c {
+                    '\u{a4}' => out.push_str(currency),
+                    _ => out.push(c),
+                }
+            }
+            out
+        });
+        if min_int == 0 && frac_part.is_empty() && !int_part.contains('#') {
+            return None;
+        }
+        Some(NumberPattern { prefix, suffix, min_int, min_frac, max_frac: frac_part.len(), primary_group, secondary_group, shift })
+    }
+}
+
+/// Separators and symbols from `locale_opts`
+struct LocaleOptions {
+    decimal: String,
+    group: String,
+    currency: String,
+    minus: String,
+    /// Text for NaN; None keeps nulls null
+    null: Option<String>,
+}
+
+impl LocaleOptions {
+    /// Parse a JSON object of options (empty for the defaults); None if it
+    /// is not one or an option is not a string
+    fn parse(locale_opts: &str) -> Option<LocaleOptions> {
+        let opts = match locale_opts.trim() {
+            "" => serde_json::Map::new(),
+            text => match serde_json::from_str(text).ok()? {
+                Value::Object(opts) => opts,
+                _ => return None,
+            },
+        };
+        let get = |key: &str, default: &str| -> Option<String> {
+            match opts.get(key) {
+                None => Some(default.to_string()),
+                Some(Value::String(s)) => Some(s.clone()),
+                Some(_) => None,
+            }
+        };
+        let null = match opts.get("null") {
+            None | Some(Value::Null) => None,
+            Some(Value::String(s)) => Some(s.clone()),
+            Some(_) => return None,
+        };
+        Some(LocaleOptions {
+            decimal: get("decimal", ".")?,
+            group: get("group", ",")?,
+            currency: get("currency", "$")?,
+            minus: get("minus", "-")?,
+            null,
+        })
+    }
+}
+
+/// Integer and fraction digits of `|x| * 10^shift` rounded half away from
+/// zero to `max_frac` fraction digits, from its shortest representation
+fn rounded_digits(x: f64, shift: i64, max_frac: usize) -> (Vec<u8>, Vec<u8>) {
+    // Display never uses exponent notation, so this is every digit
+    let text = format!("{}", x.abs());
+    let (int_part, frac_part) = text.split_once('.').unwrap_or((&text, ""));
+    let mut digits: Vec<u8> = int_part.bytes().chain(frac_part.bytes()).map(|b| b - b'0').collect();
+    let mut point = int_part.len() as i64 + shift;
+    if point < 1 {
+        digits.splice(0..0, std::iter::repeat_n(0, (1 - point) as usize));
+        point = 1;
+    }
+    let point = point as usize;
+    if digits.len() < point {
+        digits.resize(point, 0);
+    }
+    let mut int_len = point;
+    let keep = point + max_frac;
+    if digits.len() > keep {
+        let round_up = digits[keep] >= 5;
+        digits.truncate(keep);
+        if round_up {
+            match digits.iter().rposition(|&d| d != 9) {
+                Some(i) => {
+                    digits[i] += 1;
+                    digits[i + 1..].fill(0);
+                }
+                None => {
+                    digits.fill(0);
+                    digits.insert(0, 1);
+                    int_len += 1;
+                }
+            }
+        }
+    }
+    let frac = digits.split_off(int_len);
+    (digits, frac)
+}
+
+/// Format one finite or infinite value
+fn format_value(x: f64, pattern: &NumberPattern, opts: &LocaleOptions) -> String {
+    let mut out = String::new();
+    if x.is_sign_negative() {
+        out.push_str(&opts.minus);
+    }
+    out.push_str(&pattern.prefix);
+    if x.is_infinite() {
+        out.push('\u{221e}');
+        out.push_str(&pattern.suffix);
+        return out;
+    }
+    let (int_digits, mut frac_digits) = rounded_digits(x, pattern.shift, pattern.max_frac);
+    let first = int_digits.iter().position(|&d| d != 0).unwrap_or(int_digits.len());
+    let mut int_digits = int_digits[first..].to_vec();
+    if int_digits.len() < pattern.min_int {
+        int_digits.splice(0..0, std::iter::repeat_n(0, pattern.min_int - int_digits.len()));
+    }
+    while frac_digits.len() > pattern.min_frac && frac_digits.last() == Some(&0) {
+        frac_digits.pop();
+    }
+    if frac_digits.len() < pattern.min_frac {
+        frac_digits.resize(pattern.min_frac, 0);
+    }
+    // A pattern with no required digits still shows zero as "0"
+    if int_digits.is_empty() && frac_digits.is_empty() {
+        int_digits.push(0);
+    }
+    let n = int_digits.len();
+    for (i, &d) in int_digits.iter().enumerate() {
+        out.push((b'0' + d) as char);
+        // Digits still to come on the left of the point
+        let left = n - 1 - i;
+        let at_group = pattern.primary_group > 0
+            && left > 0
+            && (left == pattern.primary_group
+                || (left > pattern.primary_group && (left - pattern.primary_group).is_multiple_of(pattern.secondary_group)));
+        if at_group {
+            out.push_str(&opts.group);
+        }
+    }
+    if !frac_digits.is_empty() {
+        out.push_str(&opts.decimal);
+        out.extend(frac_digits.iter().map(|&d| (b'0' + d) as char));
+    }
+    out.push_str(&pattern.suffix);
+    out
+}
+
+/// Format a float64 series as a string series with an ICU-style decimal
+/// pattern (empty for the default `#,##0.###`):
+/// - `0` is a required digit and `#` an optional one: `0.00` always shows
+///   two decimals, `#,##0.##` up to two; integer `0`s pad with zeros
+/// - `,` in the integer part groups digits, the group size being the
+///   digits after the last `,` (`#,##,##0` gives Indian 12,34,567 grouping)
+/// - text before and after is copied, except `%` (value x 100), `‰`
+///   (value x 1000) and `¤` (the currency symbol): `0.0%`, `¤#,##0.00`
+///
+/// `locale_opts` is a JSON object (empty for the defaults) of strings:
+/// `"decimal"` (default "."), `"group"` (","), `"currency"` ("$"),
+/// `"minus"` ("-") and `"null"` (text for nulls, which otherwise stay
+/// null). For example `{"decimal": ",", "group": ".", "currency": "€"}`
+/// with `#,##0.00 ¤` gives "1.234,50 €". Negative values are prefixed with
+/// the minus sign and infinities formatted as "∞". Returns a new string
+/// series, or u32::MAX if the id is unknown or the pattern or options are
+/// malformed.
+#[wasm_bindgen]
+pub fn engine_format_f64(series_id: u32, pattern: &str, locale_opts: &str) -> u32 {
+    let _prof = profile("engine_format_f64", || series_bytes(series_id));
+    let opts = match LocaleOptions::parse(locale_opts) {
+        Some(opts) => opts,
+        None => {
+            engine_log!(warn, "engine_format_f64: invalid locale options {:?}", locale_opts);
+            return u32::MAX;
+        }
+    };
+    let pattern = match NumberPattern::parse(if pattern.is_empty() { "#,##0.###" } else { pattern }, &opts.currency) {
+        Some(parsed) => parsed,
+        None => {
+            engine_log!(warn, "engine_format_f64: malformed pattern {:?}", pattern);
+            return u32::MAX;
+        }
+    };
+    let values = match unsafe { f64_values(series_id) } {
+        Some(values) => values,
+        None => {
+            engine_log!(warn, "engine_format_f64: unknown series {}", series_id);
+            return u32::MAX;
+        }
+    };
+    let mut out = StrSeries::default();
+    for v in values.iter() {
+        if v.is_nan() {
+            out.push(opts.null.as_deref());
+        } else {
+            out.push(Some(&format_value(v, &pattern, &opts)));
+        }
+    }
+    ENGINE.with(|cell| cell.borrow_mut().register_series_str(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::engine_create_series_f64;
+    use crate::series::engine_series_to_json_str;
+
+    fn format(values: &[f64], pattern: &str, locale_opts: &str) -> String {
+        engine_series_to_json_str(engine_format_f64(engine_create_series_f64(values), pattern, locale_opts))
+    }
+
+    #[test]
+    fn patterns_control_digits_grouping_and_affixes() {
+        assert_eq!(format(&[1234567.891, -0.5, 2.0], "", ""), r#"["1,234,567.891","-0.5","2"]"#);
+        assert_eq!(format(&[1.005, 0.125, 7.0], "0.00", ""), r#"["1.01","0.13","7.00"]"#);
+        assert_eq!(format(&[7.0, 1234.0], "000", ""), r#"["007","1234"]"#);
+        assert_eq!(format(&[1234567.0], "#,##,##0", ""), r#"["12,34,567"]"#);
+        assert_eq!(format(&[0.1234, 0.0021], "0.0%", ""), r#"["12.3%","0.2%"]"#);
+        assert_eq!(format(&[0.0021], "0\u{2030}", ""), r#"["2‰"]"#);
+        assert_eq!(format(&[f64::INFINITY, f64::NEG_INFINITY, f64::NAN], "0.00", ""), r#"["∞","-∞",null]"#);
+    }
+
+    #[test]
+    fn locale_options_replace_separators_and_symbols() {
+        let opts = r#"{"decimal": ",", "group": ".", "currency": "€", "null": "n/a"}"#;
+        assert_eq!(format(&[1234.5, f64::NAN], "#,##0.00 \u{A4}", opts), r#"["1.234,50 €","n/a"]"#);
+        assert_eq!(format(&[-3.0], "\u{A4}0", r#"{"minus": "\u2212"}"#), r#"["−$3"]"#);
+    }
+
+    #[test]
+    fn malformed_patterns_and_options_are_rejected() {
+        let id = engine_create_series_f64(&[1.0]);
+        assert_eq!(engine_format_f64(id, "0.0#0", ""), u32::MAX);
+        assert_eq!(engine_format_f64(id, "abc", ""), u32::MAX);
+        assert_eq!(engine_format_f64(id, "#,##0.0,0", ""), u32::MAX);
+        assert_eq!(engine_format_f64(id, "0.00", "{bad"), u32::MAX);
+        assert_eq!(engine_format_f64(u32::MAX - 1, "0.00", ""), u32::MAX);
+    }
+}
@@ -0,0 +1,107 @@
+//! Chunked execution helpers
+//!
+//! Kernels split their input into contiguous chunks with `map_chunks` and
+//! combine the per-chunk results, and long sorts run in runs between which
+//! cancellation is polled. Every input is currently processed as a single
+//! chunk, so results are identical to the sequential kernels: the engine has
+//! no worker-pool backend (that needs a wasm threads build with
+//! SharedArrayBuffer and atomics), so chunks run on the calling thread.
+
+use std::cmp::Ordering;
+use crate::tasks::{cancel_requested, STEP_ROWS};
+
+/// Rows sorted between cancellation checks in `sort_indices_by`
+const SORT_RUN: usize = 16 * STEP_ROWS;
+
+/// Apply `f(offset, chunk)` to contiguous chunks of `data`, returning the
+/// results in chunk order. `offset` is the index of the chunk's first item.
+pub(crate) fn map_chunks<'a, T, R, F>(data: &'a [T], f: F) -> Vec<R>
+where
+    F: Fn(usize, &'a [T]) -> R,
+{
+    vec![f(0, data)]
+}
+
+/// Stable sort of the indices `0..len` by `cmp`. Inputs longer than
+/// `SORT_RUN` are sorted in runs of that length, then merged pairwise
+/// (earlier runs win ties), so that the cancellation flag can be polled
+/// between runs and while merging; None if cancellation was requested.
+pub(crate) fn sort_indices_by<F>(len: usize, cmp: F) -> Option<Vec<usize>>
+where
+    F: Fn(usize, usize) -> Ordering,
+{
+    if cancel_requested() {
+        return None;
+    }
+    let mut indices: Vec<usize> = (0..len).collect();
+    if len <= SORT_RUN {
+        indices.sort_by(|&a, &b| cmp(a, b));
+        return Some(indices);
+    }
+    let mut runs: Vec<Vec<usize>> = indices
+        .chunks(SORT_RUN)
+        .map_while(|run| {
+            if cancel_requested() {
+                return None;
+            }
+            let mut run = run.to_vec();
+            run.sort_by(|&a, &b| cmp(a, b));
+            Some(run)
+        })
+        .collect();
+    while runs.len() > 1 {
+        let mut merged: Vec<Vec<usize>> = Vec::with_capacity(runs.len().div_ceil(2));
+        let mut iter = runs.into_iter();
+        while let Some(left) = iter.next() {
+            match iter.next() {
+                Some(right) => merged.push(merge_runs(&left, &right, &cmp)?),
+                None => merged.push(left),
+            }
+        }
+        runs = merged;
+    }
+    // A run cut short by cancellation leaves fewer than `len` indices
+    match runs.pop() {
+        Some(sorted) if sorted.len() == len && !cancel_requested() => Some(sorted),
+        _ => None,
+    }
+}
+
+/// Merge two sorted runs (ties go to `left`); None if cancellation is
+/// requested while merging
+fn merge_runs<F: Fn(usize, usize) -> Ordering>(left: &[usize], right: &[usize], cmp: &F) -> Option<Vec<usize>> {
+    let mut out = Vec::with_capacity(left.len() + right.len());
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() && j < right.len() {
+        if out.len() % STEP_ROWS == 0 && cancel_requested() {
+            return None;
+        }
+        if cmp(right[j], left[i]) == Ordering::Less {
+            out.push(right[j]);
+            j += 1;
+        } else {
+            out.push(left[i]);
+            i += 1;
+        }
+    }
+    out.extend_from_slice(&left[i..]);
+    out.extend_from_slice(&right[j..]);
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merged_runs_sort_stably() {
+        // Several runs, with ties spanning run boundaries
+        let len = 3 * SORT_RUN + 17;
+        let keys: Vec<u32> = (0..len as u32).map(|i| i.wrapping_mul(2_654_435_761) % 1000).collect();
+        let sorted = sort_indices_by(len, |a, b| keys[a].cmp(&keys[b])).unwrap();
+        let mut expected: Vec<usize> = (0..len).collect();
+        expected.sort_by_key(|&i| keys[i]);
+        assert_eq!(sorted, expected);
+        assert_eq!(map_chunks(&keys, |offset, chunk| offset + chunk.len()), [len]);
+    }
+}
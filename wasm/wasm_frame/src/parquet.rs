@@ -0,0 +1,865 @@
+//! Parquet reading: column projection and row-group filtering
+//!
+//! This module decodes flat (non-nested) Parquet files and registers the
+//! selected columns as series. Supported are PLAIN and dictionary encoded
+//! data pages (v1 and v2), uncompressed or Snappy compressed. It is only
+//! compiled with the `parquet` feature.
+
+use serde_json;
+use wasm_bindgen::prelude::*;
+use crate::core::{try_vec, ENGINE};
+use crate::error::catch_panic;
+use crate::profiling::profile;
+
+// Parquet physical types
+const TYPE_BOOLEAN: i64 = 0;
+const TYPE_INT32: i64 = 1;
+const TYPE_INT64: i64 = 2;
+const TYPE_FLOAT: i64 = 4;
+const TYPE_DOUBLE: i64 = 5;
+
+// Page types, encodings and codecs
+const PAGE_DATA: i64 = 0;
+const PAGE_DICTIONARY: i64 = 2;
+const PAGE_DATA_V2: i64 = 3;
+const ENCODING_PLAIN: i64 = 0;
+const ENCODING_PLAIN_DICTIONARY: i64 = 2;
+const ENCODING_RLE_DICTIONARY: i64 = 8;
+const CODEC_UNCOMPRESSED: i64 = 0;
+const CODEC_SNAPPY: i64 = 1;
+
+const MAGIC: &[u8] = b"PAR1";
+
+/// Decoded Thrift compact protocol value
+enum TValue {
+    Bool(bool),
+    Int(i64),
+    /// Value of a type no Parquet metadata field we read uses (doubles)
+    Other,
+    Binary(Vec<u8>),
+    List(Vec<TValue>),
+    Struct(Vec<(i16, TValue)>),
+}
+
+type TStruct = Vec<(i16, TValue)>;
+
+fn t_field(fields: &[(i16, TValue)], id: i16) -> Option<&TValue> {
+    fields.iter().find(|(fid, _)| *fid == id).map(|(_, v)| v)
+}
+
+fn t_int(fields: &[(i16, TValue)], id: i16) -> Option<i64> {
+    match t_field(fields, id) {
+        Some(TValue::Int(v)) => Some(*v),
+        _ => None,
+    }
+}
+
+fn t_bool(fields: &[(i16, TValue)], id: i16) -> Option<bool> {
+    match t_field(fields, id) {
+        Some(TValue::Bool(v)) => Some(*v),
+        _ => None,
+    }
+}
+
+fn t_binary(fields: &[(i16, TValue)], id: i16) -> Option<&[u8]> {
+    match t_field(fields, id) {
+        Some(TValue::Binary(v)) => Some(v),
+        _ => None,
+    }
+}
+
+fn t_list(fields: &[(i16, TValue)], id: i16) -> &[TValue] {
+    match t_field(fields, id) {
+        Some(TValue::List(v)) => v,
+        _ => &[],
+    }
+}
+
+fn t_struct(fields: &[(i16, TValue)], id: i16) -> Option<&[(i16, TValue)]> {
+    match t_field(fields, id) {
+        Some(TValue::Struct(v)) => Some(v),
+        _ => None,
+    }
+}
+
+/// Byte cursor shared by the Thrift, RLE/bit-packing and Snappy decoders
+struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        ByteReader { data, pos: 0 }
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        let b = *self.data.get(self.pos).ok_or("unexpected end of data")?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(n).filter(|&end| end <= self.data.len()).ok_or("unexpected end of data")?;
+        let out = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(out)
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut result: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let b = self.byte()?;
+            result |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 {
+                return Ok(result);
+            }
+            shift += 7;
+            if shift > 63 {
+                return Err("varint overflow".to_string());
+            }
+        }
+    }
+
+    fn zigzag(&mut self) -> Result<i64, String> {
+        let v = self.varint()?;
+        Ok(((v >> 1) as i64) ^ -((v & 1) as i64))
+    }
+
+    fn read_struct(&mut self) -> Result<TStruct, String> {
+        let mut fields: TStruct = Vec::new();
+        let mut last_id: i16 = 0;
+        loop {
+            let header = self.byte()?;
+            if header == 0 {
+                return Ok(fields);
+            }
+            let ttype = header & 0x0f;
+            let delta = (header >> 4) as i16;
+            let id = if delta != 0 { last_id + delta } else { self.zigzag()? as i16 };
+            last_id = id;
+            let value = match ttype {
+                1 => TValue::Bool(true),
+                2 => TValue::Bool(false),
+                t => self.read_value(t)?,
+            };
+            fields.push((id, value));
+        }
+    }
+
+    fn read_value(&mut self, ttype: u8) -> Result<TValue, String> {
+        match ttype {
+            1 | 2 => Ok(TValue::Bool(self.byte()? == 1)),
+            3 => Ok(TValue::Int(self.byte()? as i8 as i64)),
+            4..=6 => Ok(TValue::Int(self.zigzag()?)),
+            7 => {
+                self.take(8)?;
+                Ok(TValue::Other)
+            }
+            8 => {
+                let len = to_usize(self.varint()?);
+                Ok(TValue::Binary(self.take(len)?.to_vec()))
+            }
+            9 | 10 => {
+                let header = self.byte()?;
+                let mut size = (header >> 4) as usize;
+                if size == 15 {
+                    size = to_usize(self.varint()?);
+                }
+                let elem_type = header & 0x0f;
+                let mut items = Vec::with_capacity(size.min(1024));
+                for _ in 0..size {
+                    items.push(self.read_value(elem_type)?);
+                }
+                Ok(TValue::List(items))
+            }
+            11 => {
+                let size = to_usize(self.varint()?);
+                let mut items = Vec::with_capacity(size.min(1024));
+                if size > 0 {
+                    let kv = self.byte()?;
+                    for _ in 0..size {
+                        let k = self.read_value(kv >> 4)?;
+                        let v = self.read_value(kv & 0x0f)?;
+                        items.push(TValue::List(vec![k, v]));
+                    }
+                }
+                Ok(TValue::List(items))
+            }
+            12 => Ok(TValue::Struct(self.read_struct()?)),
+            t => Err(format!("unsupported thrift type {}", t)),
+        }
+    }
+}
+
+/// Leaf column of a flat schema
+struct ColumnInfo {
+    name: String,
+    physical: i64,
+    optional: bool,
+}
+
+/// Location and statistics of one column chunk
+struct ChunkInfo {
+    codec: i64,
+    num_values: usize,
+    start: usize,
+    len: usize,
+    min: Option<Vec<u8>>,
+    max: Option<Vec<u8>>,
+}
+
+struct RowGroupInfo {
+    num_rows: usize,
+    chunks: Vec<ChunkInfo>,
+}
+
+struct FileInfo {
+    num_rows: usize,
+    columns: Vec<ColumnInfo>,
+    row_groups: Vec<RowGroupInfo>,
+}
+
+/// A size or count read from the file; values that do not fit in usize
+/// (negative ones read as 0) saturate, so range checks reject them
+fn to_usize<T: TryInto<usize> + Default + PartialOrd>(v: T) -> usize {
+    if v < T::default() { 0 } else { v.try_into().unwrap_or(usize::MAX) }
+}
+
+fn parse_file(bytes: &[u8]) -> Result<FileInfo, String> {
+    let n = bytes.len();
+    if n < 12 || &bytes[..4] != MAGIC || &bytes[n - 4..] != MAGIC {
+        return Err("not a parquet file".to_string());
+    }
+    let meta_len = u32::from_le_bytes(bytes[n - 8..n - 4].try_into().unwrap()) as usize;
+    if meta_len.checked_add(12).is_none_or(|needed| needed > n) {
+        return Err("invalid footer length".to_string());
+    }
+    let meta = ByteReader::new(&bytes[n - 8 - meta_len..n - 8]).read_struct()?;
+
+    // Schema: root element followed by leaves (nested schemas are rejected)
+    let schema = t_list(&meta, 2);
+    let mut columns: Vec<ColumnInfo> = Vec::new();
+    for element in schema.iter().skip(1) {
+        let fields = match element {
+            TValue::Struct(f) => f,
+            _ => return Err("invalid schema element".to_string()),
+        };
+        if t_int(fields, 5).unwrap_or(0) > 0 {
+            return Err("nested schemas are not supported".to_string());
+        }
+        let repetition = t_int(fields, 3).unwrap_or(0);
+        if repetition == 2 {
+            return Err("repeated columns are not supported".to_string());
+        }
+        let name = t_binary(fields, 4)
+            .map(|b| String::from_utf8_lossy(b).into_owned())
+            .unwrap_or_default();
+        columns.push(ColumnInfo {
+            name,
+            physical: t_int(fields, 1).unwrap_or(-1),
+            optional: repetition == 1,
+        });
+    }
+
+    let mut row_groups: Vec<RowGroupInfo> = Vec::new();
+    for rg in t_list(&meta, 4) {
+        let rg_fields = match rg {
+            TValue::Struct(f) => f,
+            _ => return Err("invalid row group".to_string()),
+        };
+        let mut chunks: Vec<ChunkInfo> = Vec::new();
+        for chunk in t_list(rg_fields, 1) {
+            let chunk_fields = match chunk {
+                TValue::Struct(f) => f,
+                _ => return Err("invalid column chunk".to_string()),
+            };
+            let md = t_struct(chunk_fields, 3).ok_or("missing column metadata")?;
+            let data_offset = t_int(md, 9).unwrap_or(0);
+            let start = match t_int(md, 11) {
+                Some(dict_offset) if dict_offset > 0 && dict_offset < data_offset => dict_offset,
+                _ => data_offset,
+            };
+            let stats = t_struct(md, 12);
+            let stat = |new_id: i16, old_id: i16| {
+                stats.and_then(|s| t_binary(s, new_id).or_else(|| t_binary(s, old_id))).map(|b| b.to_vec())
+            };
+            chunks.push(ChunkInfo {
+                codec: t_int(md, 4).unwrap_or(0),
+                num_values: to_usize(t_int(md, 5).unwrap_or(0)),
+                start: to_usize(start),
+                len: to_usize(t_int(md, 7).unwrap_or(0)),
+                min: stat(6, 2),
+                max: stat(5, 1),
+            });
+        }
+        if chunks.len() != columns.len() {
+            return Err("column chunk count does not match schema".to_string());
+        }
+        row_groups.push(RowGroupInfo {
+            num_rows: to_usize(t_int(rg_fields, 3).unwrap_or(0)),
+            chunks,
+        });
+    }
+
+    Ok(FileInfo {
+        num_rows: to_usize(t_int(&meta, 3).unwrap_or(0)),
+        columns,
+        row_groups,
+    })
+}
+
+fn physical_type_name(physical: i64) -> &'static str {
+    match physical {
+        0 => "BOOLEAN",
+        1 => "INT32",
+        2 => "INT64",
+        3 => "INT96",
+        4 => "FLOAT",
+        5 => "DOUBLE",
+        6 => "BYTE_ARRAY",
+        7 => "FIXED_LEN_BYTE_ARRAY",
+        _ => "UNKNOWN",
+    }
+}
+
+fn is_supported_type(physical: i64) -> bool {
+    matches!(physical, TYPE_BOOLEAN | TYPE_INT32 | TYPE_INT64 | TYPE_FLOAT | TYPE_DOUBLE)
+}
+
+/// Decode a PLAIN-encoded statistics value as f64
+fn stat_value(physical: i64, bytes: &[u8]) -> Option<f64> {
+    match physical {
+        TYPE_BOOLEAN => bytes.first().map(|b| *b as f64),
+        TYPE_INT32 => bytes.get(..4).map(|b| i32::from_le_bytes(b.try_into().unwrap()) as f64),
+        TYPE_INT64 => bytes.get(..8).map(|b| i64::from_le_bytes(b.try_into().unwrap()) as f64),
+        TYPE_FLOAT => bytes.get(..4).map(|b| f32::from_le_bytes(b.try_into().unwrap()) as f64),
+        TYPE_DOUBLE => bytes.get(..8).map(|b| f64::from_le_bytes(b.try_into().unwrap())),
+        _ => None,
+    }
+}
+
+/// Raw Snappy block decompression
+fn snappy_decompress(src: &[u8]) -> Result<Vec<u8>, String> {
+    let mut r = ByteReader::new(src);
+    let expected = to_usize(r.varint()?);
+    // The declared length is untrusted: reserve no more than the block can
+    // expand to (a 3-byte copy tag yields at most 64 bytes) and reject
+    // output beyond it as it is produced
+    let mut out: Vec<u8> = Vec::with_capacity(expected.min(src.len().saturating_mul(22)));
+    while r.pos < src.len() {
+        let tag = r.byte()?;
+        let (len, offset) = match tag & 3 {
+            0 => {
+                let mut len = (tag >> 2) as usize;
+                if len >= 60 {
+                    let extra = r.take(len - 59)?;
+                    len = extra.iter().rev().fold(0usize, |acc, b| (acc << 8) | *b as usize);
+                }
+                let literal = r.take(len.saturating_add(1))?;
+                if literal.len() > expected - out.len() {
+                    return Err("snappy length mismatch".to_string());
+                }
+                out.extend_from_slice(literal);
+                continue;
+            }
+            1 => {
+                let len = 4 + ((tag >> 2) & 7) as usize;
+                let offset = (((tag >> 5) as usize) << 8) | r.byte()? as usize;
+                (len, offset)
+            }
+            2 => {
+                let b = r.take(2)?;
+                ((tag >> 2) as usize + 1, u16::from_le_bytes([b[0], b[1]]) as usize)
+            }
+            _ => {
+                let b = r.take(4)?;
+                ((tag >> 2) as usize + 1, u32::from_le_bytes(b.try_into().unwrap()) as usize)
+            }
+        };
+        if offset == 0 || offset > out.len() {
+            return Err("invalid snappy copy offset".to_string());
+        }
+        if len > expected - out.len() {
+            return Err("snappy length mismatch".to_string());
+        }
+        for _ in 0..len {
+            out.push(out[out.len() - offset]);
+        }
+    }
+    if out.len() != expected {
+        return Err("snappy length mismatch".to_string());
+    }
+    Ok(out)
+}
+
+fn decompress(codec: i64, data: &[u8]) -> Result<Vec<u8>, String> {
+    match codec {
+        CODEC_UNCOMPRESSED => Ok(data.to_vec()),
+        CODEC_SNAPPY => snappy_decompress(data),
+        c => Err(format!("unsupported compression codec {}", c)),
+    }
+}
+
+/// Decode `count` values of the RLE / bit-packing hybrid encoding
+fn decode_hybrid(data: &[u8], bit_width: usize, count: usize) -> Result<Vec<u32>, String> {
+    if bit_width > 32 {
+        return Err(format!("invalid bit width {}", bit_width));
+    }
+    let mut r = ByteReader::new(data);
+    let mut out: Vec<u32> = try_vec(count).map_err(|e| e.to_string())?;
+    let byte_width = bit_width.div_ceil(8);
+    while out.len() < count {
+        let header = r.varint()?;
+        if header & 1 == 0 {
+            let run = to_usize(header >> 1);
+            let value = r.take(byte_width)?.iter().rev().fold(0u32, |acc, b| (acc << 8) | *b as u32);
+            let n = run.min(count - out.len());
+            out.extend(std::iter::repeat_n(value, n));
+        } else {
+            let groups = to_usize(header >> 1);
+            let packed = r.take(groups.checked_mul(bit_width).ok_or("unexpected end of data")?)?;
+            for i in 0..groups.saturating_mul(8) {
+                if out.len() == count {
+                    break;
+                }
+                let mut value: u32 = 0;
+                for b in 0..bit_width {
+                    let bit = i * bit_width + b;
+                    value |= (((packed[bit / 8] >> (bit % 8)) & 1) as u32) << b;
+                }
+                out.push(value);
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Non-null values of a column in their engine representation
+enum ColumnValues {
+    I32(Vec<i32>),
+    F64(Vec<f64>),
+}
+
+impl ColumnValues {
+    fn empty(physical: i64) -> Self {
+        if physical == TYPE_INT32 {
+            ColumnValues::I32(Vec::new())
+        } else {
+            ColumnValues::F64(Vec::new())
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            ColumnValues::I32(v) => v.len(),
+            ColumnValues::F64(v) => v.len(),
+        }
+    }
+
+    fn gather(&self, indices: &[u32]) -> Result<ColumnValues, String> {
+        if indices.iter().any(|&i| i as usize >= self.len()) {
+            return Err("dictionary index out of range".to_string());
+        }
+        Ok(match self {
+            ColumnValues::I32(d) => ColumnValues::I32(indices.iter().map(|&i| d[i as usize]).collect()),
+            ColumnValues::F64(d) => ColumnValues::F64(indices.iter().map(|&i| d[i as usize]).collect()),
+        })
+    }
+
+    /// Append page values, inserting nulls where the definition level is 0
+    fn append(&mut self, values: ColumnValues, levels: Option<&[u32]>) -> Result<(), String> {
+        match (self, values) {
+            (ColumnValues::I32(out), ColumnValues::I32(vals)) => {
+                scatter_into(out, vals, levels, i32::MIN);
+            }
+            (ColumnValues::F64(out), ColumnValues::F64(vals)) => {
+                scatter_into(out, vals, levels, f64::NAN);
+            }
+            _ => return Err("page type does not match column type".to_string()),
+        }
+        Ok(())
+    }
+}
+
+fn scatter_into<T: Copy>(out: &mut Vec<T>, values: Vec<T>, levels: Option<&[u32]>, null: T) {
+    match levels {
+        None => out.extend(values),
+        Some(levels) => {
+            let mut it = values.into_iter();
+            for &level in levels {
+                out.push(if level > 0 { it.next().unwrap_or(null) } else { null });
+            }
+        }
+    }
+}
+
+fn decode_plain(data: &[u8], physical: i64, count: usize) -> Result<ColumnValues, String> {
+    let width = match physical {
+        TYPE_BOOLEAN => 0,
+        TYPE_INT32 | TYPE_FLOAT => 4,
+        TYPE_INT64 | TYPE_DOUBLE => 8,
+        p => return Err(format!("unsupported physical type {}", physical_type_name(p))),
+    };
+    let needed = if width == 0 { Some(count.div_ceil(8)) } else { count.checked_mul(width) };
+    if needed.is_none_or(|needed| data.len() < needed) {
+        return Err("page too short".to_string());
+    }
+    Ok(match physical {
+        TYPE_BOOLEAN => ColumnValues::F64((0..count).map(|i| ((data[i / 8] >> (i % 8)) & 1) as f64).collect()),
+        TYPE_INT32 => ColumnValues::I32(
+            data.chunks_exact(4).take(count).map(|b| i32::from_le_bytes(b.try_into().unwrap())).collect(),
+        ),
+        TYPE_INT64 => ColumnValues::F64(
+            data.chunks_exact(8).take(count).map(|b| i64::from_le_bytes(b.try_into().unwrap()) as f64).collect(),
+        ),
+        TYPE_FLOAT => ColumnValues::F64(
+            data.chunks_exact(4).take(count).map(|b| f32::from_le_bytes(b.try_into().unwrap()) as f64).collect(),
+        ),
+        _ => ColumnValues::F64(
+            data.chunks_exact(8).take(count).map(|b| f64::from_le_bytes(b.try_into().unwrap())).collect(),
+        ),
+    })
+}
+
+fn decode_values(
+    data: &[u8],
+    encoding: i64,
+    physical: i64,
+    count: usize,
+    dictionary: Option<&ColumnValues>,
+) -> Result<ColumnValues, String> {
+    match encoding {
+        ENCODING_PLAIN => decode_plain(data, physical, count),
+        ENCODING_PLAIN_DICTIONARY | ENCODING_RLE_DICTIONARY => {
+            let dict = dictionary.ok_or("dictionary page missing")?;
+            let bit_width = *data.first().ok_or("empty dictionary page")? as usize;
+            let indices = decode_hybrid(&data[1..], bit_width, count)?;
+            dict.gather(&indices)
+        }
+        e => Err(format!("unsupported encoding {}", e)),
+    }
+}
+
+/// Decode all pages of one column chunk
+fn read_column_chunk(bytes: &[u8], column: &ColumnInfo, chunk: &ChunkInfo) -> Result<ColumnValues, String> {
+    let end = chunk.start.checked_add(chunk.len).ok_or("invalid chunk range")?;
+    if end > bytes.len() {
+        return Err("column chunk out of range".to_string());
+    }
+    let mut out = ColumnValues::empty(column.physical);
+    let mut dictionary: Option<ColumnValues> = None;
+    let mut pos = chunk.start;
+    while out.len() < chunk.num_values && pos < end {
+        let mut r = ByteReader::new(&bytes[pos..end]);
+        let header = r.read_struct()?;
+        pos += r.pos;
+        let page_type = t_int(&header, 1).unwrap_or(-1);
+        let compressed_size = to_usize(t_int(&header, 3).unwrap_or(0));
+        if pos.checked_add(compressed_size).is_none_or(|page_end| page_end > end) {
+            return Err("page out of range".to_string());
+        }
+        let page = &bytes[pos..pos + compressed_size];
+        pos += compressed_size;
+
+        match page_type {
+            PAGE_DICTIONARY => {
+                let dh = t_struct(&header, 7).ok_or("missing dictionary page header")?;
+                let n = to_usize(t_int(dh, 1).unwrap_or(0));
+                let data = decompress(chunk.codec, page)?;
+                dictionary = Some(decode_plain(&data, column.physical, n)?);
+            }
+            PAGE_DATA => {
+                let dh = t_struct(&header, 5).ok_or("missing data page header")?;
+                let n = to_usize(t_int(dh, 1).unwrap_or(0));
+                let encoding = t_int(dh, 2).unwrap_or(ENCODING_PLAIN);
+                let data = decompress(chunk.codec, page)?;
+                let mut cursor = 0;
+                let levels = if column.optional {
+                    let len_bytes = data.get(..4).ok_or("page too short")?;
+                    let len = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+                    let level_bytes = len.checked_add(4).and_then(|end| data.get(4..end)).ok_or("page too short")?;
+                    cursor = 4 + len;
+                    Some(decode_hybrid(level_bytes, 1, n)?)
+                } else {
+                    None
+                };
+                let non_null = levels.as_ref().map(|l| l.iter().filter(|&&v| v > 0).count()).unwrap_or(n);
+                let values = decode_values(&data[cursor..], encoding, column.physical, non_null, dictionary.as_ref())?;
+                out.append(values, levels.as_deref())?;
+            }
+            PAGE_DATA_V2 => {
+                let dh = t_struct(&header, 8).ok_or("missing data page v2 header")?;
+                let n = to_usize(t_int(dh, 1).unwrap_or(0));
+                let num_nulls = to_usize(t_int(dh, 2).unwrap_or(0));
+                let encoding = t_int(dh, 4).unwrap_or(ENCODING_PLAIN);
+                let def_len = to_usize(t_int(dh, 5).unwrap_or(0));
+                let rep_len = to_usize(t_int(dh, 6).unwrap_or(0));
+                let is_compressed = t_bool(dh, 7).unwrap_or(true);
+                if rep_len.checked_add(def_len).is_none_or(|levels_len| levels_len > page.len()) {
+                    return Err("page too short".to_string());
+                }
+                let levels = if column.optional && def_len > 0 {
+                    Some(decode_hybrid(&page[rep_len..rep_len + def_len], 1, n)?)
+                } else {
+                    None
+                };
+                let raw = &page[rep_len + def_len..];
+                let data = if is_compressed { decompress(chunk.codec, raw)? } else { raw.to_vec() };
+                let values = decode_values(&data, encoding, column.physical, n - num_nulls.min(n), dictionary.as_ref())?;
+                out.append(values, levels.as_deref())?;
+            }
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+/// Projection and row-group selection parsed from `columns_json`
+struct ReadOptions {
+    columns: Option<Vec<String>>,
+    row_groups: Option<Vec<usize>>,
+    filters: Vec<(String, f64, f64)>,
+}
+
+fn parse_options(columns_json: &str) -> ReadOptions {
+    let value: serde_json::Value = serde_json::from_str(columns_json).unwrap_or(serde_json::Value::Null);
+    let names = |v: Option<&serde_json::Value>| -> Option<Vec<String>> {
+        v.and_then(|v| v.as_array())
+            .map(|a| a.iter().filter_map(|s| s.as_str().map(|s| s.to_string())).collect())
+    };
+    match &value {
+        serde_json::Value::Array(_) => ReadOptions { columns: names(Some(&value)), row_groups: None, filters: Vec::new() },
+        serde_json::Value::Object(obj) => ReadOptions {
+            columns: names(obj.get("columns")),
+            row_groups: obj
+                .get("row_groups")
+                .and_then(|v| v.as_array())
+                .map(|a| a.iter().filter_map(|i| i.as_u64().map(|i| i as usize)).collect()),
+            filters: obj
+                .get("filters")
+                .and_then(|v| v.as_array())
+                .map(|a| {
+                    a.iter()
+                        .filter_map(|f| {
+                            let column = f.get("column")?.as_str()?.to_string();
+                            let min = f.get("min").and_then(|v| v.as_f64()).unwrap_or(f64::NEG_INFINITY);
+                            let max = f.get("max").and_then(|v| v.as_f64()).unwrap_or(f64::INFINITY);
+                            Some((column, min, max))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        },
+        _ => ReadOptions { columns: None, row_groups: None, filters: Vec::new() },
+    }
+}
+
+/// A row group is skipped only when its statistics prove that no row can
+/// satisfy one of the range filters.
+fn row_group_matches(info: &FileInfo, rg: &RowGroupInfo, filters: &[(String, f64, f64)]) -> bool {
+    filters.iter().all(|(name, lo, hi)| {
+        let col = match info.columns.iter().position(|c| &c.name == name) {
+            Some(c) => c,
+            None => return true,
+        };
+        let physical = info.columns[col].physical;
+        let chunk = &rg.chunks[col];
+        let min = chunk.min.as_deref().and_then(|b| stat_value(physical, b));
+        let max = chunk.max.as_deref().and_then(|b| stat_value(physical, b));
+        match (min, max) {
+            (Some(min), Some(max)) => max >= *lo && min <= *hi,
+            _ => true,
+        }
+    })
+}
+
+fn read_parquet(bytes: &[u8], columns_json: &str) -> Result<Vec<ColumnValues>, String> {
+    let info = parse_file(bytes)?;
+    let options = parse_options(columns_json);
+
+    let selected: Vec<usize> = match &options.columns {
+        Some(names) => names
+            .iter()
+            .map(|n| info.columns.iter().position(|c| &c.name == n).ok_or(format!("unknown column {}", n)))
+            .collect::<Result<_, _>>()?,
+        None => (0..info.columns.len()).filter(|&i| is_supported_type(info.columns[i].physical)).collect(),
+    };
+    if let Some(c) = selected.iter().find(|&&c| !is_supported_type(info.columns[c].physical)) {
+        return Err(format!("unsupported column type {}", physical_type_name(info.columns[*c].physical)));
+    }
+
+    let mut outputs: Vec<ColumnValues> = selected.iter().map(|&c| ColumnValues::empty(info.columns[c].physical)).collect();
+    for (rg_index, rg) in info.row_groups.iter().enumerate() {
+        if let Some(groups) = &options.row_groups {
+            if !groups.contains(&rg_index) {
+                continue;
+            }
+        }
+        if !row_group_matches(&info, rg, &options.filters) {
+            continue;
+        }
+        for (out, &c) in outputs.iter_mut().zip(selected.iter()) {
+            let values = read_column_chunk(bytes, &info.columns[c], &rg.chunks[c])?;
+            if values.len() != rg.num_rows {
+                return Err("column chunk row count mismatch".to_string());
+            }
+            out.append(values, None)?;
+        }
+    }
+    Ok(outputs)
+}
+
+/// Read a flat Parquet file and register the selected columns as series.
+/// `columns_json` is either an array of column names or an object
+/// `{"columns": [...], "row_groups": [...], "filters": [{"column", "min", "max"}]}`;
+/// filters skip row groups whose min/max statistics fall outside the range.
+/// INT32 columns become i32 series, all other numeric columns f64 series.
+/// Returns series ids in projection order (empty on error).
+#[wasm_bindgen]
+pub fn engine_read_parquet(bytes: &[u8], columns_json: &str) -> Box<[u32]> {
+    let _prof = profile("engine_read_parquet", || bytes.len());
+    catch_panic(Box::new([]), || read_parquet_series(bytes, columns_json))
+}
+
+fn read_parquet_series(bytes: &[u8], columns_json: &str) -> Box<[u32]> {
+    let columns = match read_parquet(bytes, columns_json) {
+        Ok(c) => c,
+        Err(e) => {
+            engine_log!(warn, "engine_read_parquet: {} bytes={}", e, bytes.len());
+            return Box::new([]);
+        }
+    };
+    engine_log!(debug, "engine_read_parquet: columns={} rows={}", columns.len(), columns.first().map(|c| c.len()).unwrap_or(0));
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        let ids: Vec<u32> = columns
+            .iter()
+            .map(|c| match c {
+                ColumnValues::I32(v) => eng.register_series_i32(v),
+                ColumnValues::F64(v) => eng.register_series_f64(v),
+            })
+            .collect();
+        ids.into_boxed_slice()
+    })
+}
+
+/// Describe a Parquet file's columns and row groups as JSON
+#[wasm_bindgen]
+pub fn engine_parquet_schema_json(bytes: &[u8]) -> String {
+    let _prof = profile("engine_parquet_schema_json", || bytes.len());
+    catch_panic(String::new(), || parquet_schema_json(bytes))
+}
+
+fn parquet_schema_json(bytes: &[u8]) -> String {
+    let info = match parse_file(bytes) {
+        Ok(i) => i,
+        Err(e) => return serde_json::json!({ "error": e }).to_string(),
+    };
+    let columns: Vec<serde_json::Value> = info
+        .columns
+        .iter()
+        .map(|c| {
+            serde_json::json!({
+                "name": c.name,
+                "physical_type": physical_type_name(c.physical),
+                "nullable": c.optional,
+                "supported": is_supported_type(c.physical),
+            })
+        })
+        .collect();
+    let row_groups: Vec<serde_json::Value> = info
+        .row_groups
+        .iter()
+        .map(|rg| serde_json::json!({ "num_rows": rg.num_rows }))
+        .collect();
+    serde_json::json!({
+        "num_rows": info.num_rows,
+        "columns": columns,
+        "row_groups": row_groups,
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::series::{engine_series_to_vec_f64, engine_series_to_vec_i32};
+
+    /// Written by `testdata/make_parquet.py` (see there for the contents)
+    const SMALL: &[u8] = include_bytes!("../testdata/small.parquet");
+
+    #[test]
+    fn reads_supported_columns_with_nulls() {
+        let ids = engine_read_parquet(SMALL, "null");
+        assert_eq!(ids.len(), 4);
+        assert_eq!(engine_series_to_vec_f64(ids[0]), vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        let score = engine_series_to_vec_f64(ids[1]);
+        assert_eq!(score.iter().map(|v| v.is_nan()).collect::<Vec<_>>(), vec![false, true, false, false, false]);
+        assert_eq!([score[0], score[2], score[3], score[4]], [1.5, 2.5, 9.0, 9.0]);
+        assert_eq!(engine_series_to_vec_i32(ids[2]), vec![10, 20, i32::MIN, i32::MIN, 7]);
+        assert_eq!(engine_series_to_vec_f64(ids[3]), vec![1.0, 0.0, 1.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn projects_and_filters_row_groups() {
+        let ids = engine_read_parquet(SMALL, r#"["count","id"]"#);
+        assert_eq!(engine_series_to_vec_f64(ids[1]), vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        let ids = engine_read_parquet(SMALL, r#"{"columns":["id"],"filters":[{"column":"score","min":5}]}"#);
+        assert_eq!(engine_series_to_vec_f64(ids[0]), vec![4.0, 5.0]);
+        let ids = engine_read_parquet(SMALL, r#"{"columns":["id"],"row_groups":[0]}"#);
+        assert_eq!(engine_series_to_vec_f64(ids[0]), vec![1.0, 2.0, 3.0]);
+        assert!(engine_read_parquet(SMALL, r#"["name"]"#).is_empty());
+        assert!(engine_read_parquet(SMALL, r#"["missing"]"#).is_empty());
+    }
+
+    #[test]
+    fn describes_schema() {
+        let schema: serde_json::Value = serde_json::from_str(&engine_parquet_schema_json(SMALL)).unwrap();
+        assert_eq!(schema["num_rows"], 5);
+        assert_eq!(schema["row_groups"].as_array().unwrap().len(), 2);
+        assert_eq!(schema["columns"][1]["physical_type"], "DOUBLE");
+        assert_eq!(schema["columns"][1]["nullable"], true);
+        assert_eq!(schema["columns"][4]["supported"], false);
+    }
+
+    #[test]
+    fn rejects_damaged_files() {
+        // Called without `catch_panic`, so a panic fails the test
+        assert!(read_parquet(&SMALL[..SMALL.len() - 1], "null").is_err());
+        assert!(read_parquet(&SMALL[..SMALL.len() / 2], "null").is_err());
+        for start in (4..SMALL.len() - 8).step_by(7) {
+            let mut damaged = SMALL.to_vec();
+            damaged[start..start + 4].fill(0xFF);
+            let _ = read_parquet(&damaged, "null");
+        }
+    }
+
+    #[test]
+    fn decodes_hybrid_runs() {
+        // RLE run of 3 x 5, then one bit-packed group of 1, 2, 3 at width 3
+        let data = [0x06, 0x05, 0x03, 0b1101_0001, 0b0000_0000, 0x00];
+        assert_eq!(decode_hybrid(&data, 3, 6).unwrap(), vec![5, 5, 5, 1, 2, 3]);
+        assert_eq!(decode_hybrid(&[0x08], 0, 4).unwrap(), vec![0; 4]);
+        assert!(decode_hybrid(&[0x03, 0xFF], 33, 8).is_err());
+        assert!(decode_hybrid(&[0x03], 8, 8).is_err());
+    }
+
+    #[test]
+    fn rejects_untrusted_sizes() {
+        // A footer length past the end of the file
+        let mut footer = b"PAR1".to_vec();
+        footer.extend_from_slice(&u32::MAX.to_le_bytes());
+        footer.extend_from_slice(b"PAR1");
+        assert!(read_parquet(&footer, "null").is_err());
+        // Counts and lengths whose byte size does not fit in usize
+        assert!(decode_hybrid(&[0x03], 8, usize::MAX).is_err());
+        assert!(decode_hybrid(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F], 32, 8).is_err());
+        assert!(decode_plain(&[0; 8], TYPE_DOUBLE, usize::MAX).is_err());
+        // A declared snappy length far beyond what the block holds
+        assert!(snappy_decompress(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F, 0x00, 0x41]).is_err());
+        assert_eq!(snappy_decompress(&[0x01, 0x00, 0x41]).unwrap(), b"A");
+        assert!(snappy_decompress(&[0x01, 0x04, 0x41, 0x42]).is_err());
+    }
+}
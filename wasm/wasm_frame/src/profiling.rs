@@ -0,0 +1,132 @@
+//! Per-operation timing and profiling counters
+//!
+//! Once enabled with `engine_profiling_enable`, each data-processing
+//! `engine_*` call records its call count, total wall time and the bytes of
+//! input it processed; `engine_profiling_report_json` returns the totals.
+//! When profiling is disabled the instrumentation costs a single flag check.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use serde_json::json;
+use wasm_bindgen::prelude::*;
+use crate::clock::now_ms;
+use crate::core::ENGINE;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Default)]
+struct OpStats {
+    calls: u64,
+    total_ms: f64,
+    max_ms: f64,
+    bytes: u64,
+}
+
+thread_local! {
+    static PROFILE: RefCell<HashMap<&'static str, OpStats>> = RefCell::new(HashMap::new());
+}
+
+/// Records one call when dropped
+pub(crate) struct ProfileGuard {
+    name: &'static str,
+    start: f64,
+    bytes: usize,
+}
+
+impl Drop for ProfileGuard {
+    fn drop(&mut self) {
+        let elapsed = now_ms() - self.start;
+        PROFILE.with(|cell| {
+            let mut ops = cell.borrow_mut();
+            let stats = ops.entry(self.name).or_default();
+            stats.calls += 1;
+            stats.total_ms += elapsed;
+            stats.max_ms = stats.max_ms.max(elapsed);
+            stats.bytes += self.bytes as u64;
+        });
+    }
+}
+
+/// Start timing `name` if profiling is enabled; `bytes` is only evaluated
+/// when it is. Bind the result (`let _prof = profile(...)`) so the call is
+/// recorded when the guard goes out of scope.
+pub(crate) fn profile<F: FnOnce() -> usize>(name: &'static str, bytes: F) -> Option<ProfileGuard> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return None;
+    }
+    Some(ProfileGuard { name, start: now_ms(), bytes: bytes() })
+}
+
+/// Size in bytes of a registered series (any dtype), 0 if unknown
+pub(crate) fn series_bytes(series_id: u32) -> usize {
+    ENGINE.with(|cell| cell.borrow().series_nbytes(series_id))
+}
+
+/// Start recording per-operation counters
+#[wasm_bindgen]
+pub fn engine_profiling_enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Stop recording (collected counters are kept)
+#[wasm_bindgen]
+pub fn engine_profiling_disable() {
+    ENABLED.store(false, Ordering::Relaxed);
+}
+
+/// Clear all collected counters
+#[wasm_bindgen]
+pub fn engine_profiling_reset() {
+    PROFILE.with(|cell| cell.borrow_mut().clear());
+}
+
+/// Collected counters as JSON:
+/// `{"enabled": bool, "operations": [{"name", "calls", "total_ms", "mean_ms", "max_ms", "bytes"}]}`
+/// with operations ordered by total time, slowest first.
+#[wasm_bindgen]
+pub fn engine_profiling_report_json() -> String {
+    PROFILE.with(|cell| {
+        let ops = cell.borrow();
+        let mut entries: Vec<(&&'static str, &OpStats)> = ops.iter().collect();
+        entries.sort_by(|a, b| b.1.total_ms.total_cmp(&a.1.total_ms).then_with(|| a.0.cmp(b.0)));
+        let operations: Vec<serde_json::Value> = entries
+            .into_iter()
+            .map(|(name, s)| {
+                json!({
+                    "name": name,
+                    "calls": s.calls,
+                    "total_ms": s.total_ms,
+                    "mean_ms": s.total_ms / s.calls as f64,
+                    "max_ms": s.max_ms,
+                    "bytes": s.bytes,
+                })
+            })
+            .collect();
+        json!({ "enabled": ENABLED.load(Ordering::Relaxed), "operations": operations }).to_string()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::engine_create_series_f64;
+    use crate::series::engine_series_sum_f64;
+
+    #[test]
+    fn records_calls_and_bytes_while_enabled() {
+        let series = engine_create_series_f64(&[1.0, 2.0, 3.0]);
+        engine_profiling_reset();
+        engine_profiling_enable();
+        engine_series_sum_f64(series);
+        engine_series_sum_f64(series);
+        engine_profiling_disable();
+        engine_series_sum_f64(series);
+        let report: serde_json::Value = serde_json::from_str(&engine_profiling_report_json()).unwrap();
+        assert_eq!(report["enabled"], false);
+        let op = report["operations"].as_array().unwrap().iter().find(|op| op["name"] == "engine_series_sum_f64").unwrap();
+        assert_eq!((op["calls"].as_u64(), op["bytes"].as_u64()), (Some(2), Some(48)));
+        engine_profiling_reset();
+        assert_eq!(engine_profiling_report_json(), r#"{"enabled":false,"operations":[]}"#);
+    }
+}
@@ -0,0 +1,75 @@
+//! Run-length encoded series
+//!
+//! Sorted and low-cardinality columns compress to a handful of runs. An RLE
+//! series is registered like any other series (dtype "rle") and can be used
+//! directly as a groupby key (`engine_groupby_rle_f64`) or filtered run by
+//! run (`engine_rle_compare_mask`, `engine_filter_rle`) without decoding.
+
+use wasm_bindgen::prelude::*;
+use crate::core::{f64_values, RleSeries, ENGINE};
+use crate::profiling::{profile, series_bytes};
+
+/// Run-length encode an f64 series (NaN runs are kept as null runs).
+/// Returns the id of the new RLE series, or u32::MAX if the id is unknown.
+#[wasm_bindgen]
+pub fn engine_series_rle_encode(series_id: u32) -> u32 {
+    let _prof = profile("engine_series_rle_encode", || series_bytes(series_id));
+    let rle = match unsafe { f64_values(series_id) } {
+        Some(values) => RleSeries::encode(values.iter()),
+        None => return u32::MAX,
+    };
+    ENGINE.with(|cell| cell.borrow_mut().register_series_rle(rle))
+}
+
+/// Expand an RLE series back into a float64 series
+#[wasm_bindgen]
+pub fn engine_series_rle_decode(rle_id: u32) -> u32 {
+    let _prof = profile("engine_series_rle_decode", || series_bytes(rle_id));
+    let rle = match ENGINE.with(|cell| cell.borrow().series_store_rle.get(&rle_id).cloned()) {
+        Some(rle) => rle,
+        None => return u32::MAX,
+    };
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&rle.decode()))
+}
+
+/// Number of runs in an RLE series (0 if unknown)
+#[wasm_bindgen]
+pub fn engine_rle_num_runs(rle_id: u32) -> usize {
+    ENGINE.with(|cell| cell.borrow().series_store_rle.get(&rle_id).map_or(0, |rle| rle.num_runs()))
+}
+
+/// Value of each run of an RLE series
+#[wasm_bindgen]
+pub fn engine_rle_values(rle_id: u32) -> Vec<f64> {
+    ENGINE.with(|cell| cell.borrow().series_store_rle.get(&rle_id).map(|rle| rle.values.clone()).unwrap_or_default())
+}
+
+/// Exclusive end row of each run of an RLE series
+#[wasm_bindgen]
+pub fn engine_rle_run_ends(rle_id: u32) -> Vec<u32> {
+    ENGINE.with(|cell| cell.borrow().series_store_rle.get(&rle_id).map(|rle| rle.ends.clone()).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::engine_create_series_f64;
+    use crate::series::engine_series_to_vec_f64;
+
+    #[test]
+    fn encodes_runs_and_round_trips() {
+        let id = engine_create_series_f64(&[1.0, 1.0, f64::NAN, f64::NAN, 2.0, 1.0]);
+        let rle = engine_series_rle_encode(id);
+        assert_eq!(engine_rle_num_runs(rle), 4);
+        assert_eq!(engine_rle_run_ends(rle), [2, 4, 5, 6]);
+        let values = engine_rle_values(rle);
+        assert_eq!((values[0], values[2], values[3]), (1.0, 2.0, 1.0));
+        assert!(values[1].is_nan());
+        let decoded = engine_series_to_vec_f64(engine_series_rle_decode(rle));
+        assert_eq!(decoded.len(), 6);
+        assert!(decoded[2].is_nan() && decoded[3].is_nan());
+        assert_eq!((decoded[1], decoded[4], decoded[5]), (1.0, 2.0, 1.0));
+        assert_eq!(engine_series_rle_decode(id), u32::MAX);
+        assert_eq!(engine_rle_num_runs(id), 0);
+    }
+}
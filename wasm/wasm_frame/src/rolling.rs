@@ -0,0 +1,203 @@
+//! Rolling window aggregations
+//!
+//! Rolling functions return a full-length series aligned with the input,
+//! where each row holds an aggregate of the values in its trailing window.
+//! Null values are skipped; a window with no values gives NaN (count: 0).
+
+use std::collections::VecDeque;
+use wasm_bindgen::prelude::*;
+use crate::callbacks::{JsFunction, WindowCaller};
+use crate::core::{f64_values, ENGINE};
+use crate::error::set_last_error;
+use crate::profiling::{profile, series_bytes};
+use crate::statistics::{AGG_COUNT, AGG_MAX, AGG_MEAN, AGG_MIN, AGG_STD, AGG_SUM, AGG_VAR};
+use crate::window::order_keys;
+
+/// Aggregate of a sliding window of rows entering at the back and leaving
+/// at the front: Welford sums with removal plus monotonic queues for min/max
+#[derive(Default)]
+struct SlidingStats {
+    count: usize,
+    sum: f64,
+    mean: f64,
+    m2: f64,
+    /// Rows that may still become the window min (values ascending)
+    min_rows: VecDeque<(usize, f64)>,
+    /// Rows that may still become the window max (values descending)
+    max_rows: VecDeque<(usize, f64)>,
+}
+
+impl SlidingStats {
+    fn push(&mut self, row: usize, v: f64) {
+        if v.is_nan() {
+            return;
+        }
+        self.count += 1;
+        self.sum += v;
+        let delta = v - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (v - self.mean);
+        while self.min_rows.back().is_some_and(|&(_, m)| m >= v) {
+            self.min_rows.pop_back();
+        }
+        self.min_rows.push_back((row, v));
+        while self.max_rows.back().is_some_and(|&(_, m)| m <= v) {
+            self.max_rows.pop_back();
+        }
+        self.max_rows.push_back((row, v));
+    }
+
+    /// Remove the oldest row of the window (rows leave in the order they entered)
+    fn pop(&mut self, row: usize, v: f64) {
+        if v.is_nan() {
+            return;
+        }
+        self.count -= 1;
+        if self.count == 0 {
+            *self = SlidingStats::default();
+            return;
+        }
+        self.sum -= v;
+        let delta = v - self.mean;
+        self.mean -= delta / self.count as f64;
+        self.m2 = (self.m2 - delta * (v - self.mean)).max(0.0);
+        if self.min_rows.front().is_some_and(|&(r, _)| r == row) {
+            self.min_rows.pop_front();
+        }
+        if self.max_rows.front().is_some_and(|&(r, _)| r == row) {
+            self.max_rows.pop_front();
+        }
+    }
+
+    fn finish(&self, agg: u8) -> f64 {
+        let n = self.count;
+        match agg {
+            AGG_COUNT => n as f64,
+            _ if n == 0 => f64::NAN,
+            AGG_SUM => self.sum,
+            AGG_MEAN => self.mean,
+            AGG_MIN => self.min_rows.front().map_or(f64::NAN, |&(_, v)| v),
+            AGG_MAX => self.max_rows.front().map_or(f64::NAN, |&(_, v)| v),
+            AGG_VAR if n > 1 => self.m2 / (n - 1) as f64,
+            AGG_STD if n > 1 => (self.m2 / (n - 1) as f64).sqrt(),
+            _ => f64::NAN,
+        }
+    }
+}
+
+/// Rolling aggregate over a time range instead of a row count: each row
+/// aggregates the rows whose timestamp lies in `(t - window_ms, t]`, as
+/// pandas' `rolling("<n>ms")`, so irregularly sampled data gets windows of
+/// equal duration. Computed with a two-pointer sweep in O(n).
+///
+/// `time_series_id` is a numeric series of timestamps in milliseconds
+/// (float64, int64, ...) in non-decreasing order without nulls.
+/// `agg_kind` is an aggregation code: 0 = sum, 1 = mean, 2 = count,
+/// 3 = min, 4 = max, 5 = std, 6 = var (sample std/var). Returns u32::MAX if
+/// an id is unknown, the lengths differ, the timestamps are unsorted or
+/// null, `window_ms` is not positive or `agg_kind` is unknown.
+#[wasm_bindgen]
+pub fn engine_rolling_by_time_f64(time_series_id: u32, value_series_id: u32, window_ms: f64, agg_kind: u8) -> u32 {
+    let _prof = profile("engine_rolling_by_time_f64", || series_bytes(value_series_id));
+    let times = order_keys(time_series_id);
+    let values = unsafe { f64_values(value_series_id) };
+    let (times, values) = match (times, values) {
+        (Some(times), Some(values))
+            if times.len() == values.len()
+                && window_ms > 0.0
+                && agg_kind <= AGG_VAR
+                && sorted_without_nulls(time_series_id, &times) =>
+        {
+            (times, values)
+        }
+        _ => {
+            engine_log!(warn, "engine_rolling_by_time_f64: invalid input time_series_id={} value_series_id={} window_ms={} agg_kind={}", time_series_id, value_series_id, window_ms, agg_kind);
+            return u32::MAX;
+        }
+    };
+
+    let mut stats = SlidingStats::default();
+    let mut start = 0;
+    let out: Vec<f64> = (0..times.len())
+        .map(|row| {
+            stats.push(row, values.get(row));
+            // The current row always stays in its window, even when
+            // `window_ms` is below the timestamps' precision
+            while start < row && times[start] <= times[row] - window_ms {
+                stats.pop(start, values.get(start));
+                start += 1;
+            }
+            stats.finish(agg_kind)
+        })
+        .collect();
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&out))
+}
+
+/// Custom rolling metric with a JS callback. `cb(values, row)` is called
+/// for each row with a `Float64Array` view of the `window` values ending at
+/// that row (valid only during the call) and returns the row's result
+/// (non-numbers become NaN). As in pandas' `rolling(window).apply(raw=True)`,
+/// rows before the first full window and windows containing a null are NaN
+/// without calling `cb`.
+///
+/// Returns u32::MAX if the id is unknown, `window` is 0 or the callback
+/// throws (error code 6).
+#[wasm_bindgen]
+pub fn engine_rolling_apply_f64(series_id: u32, window: usize, cb: &JsFunction) -> u32 {
+    let _prof = profile("engine_rolling_apply_f64", || series_bytes(series_id));
+    let values: Vec<f64> = match unsafe { f64_values(series_id) } {
+        Some(values) if window > 0 => values.iter().collect(),
+        _ => {
+            engine_log!(warn, "engine_rolling_apply_f64: unknown series or zero window series_id={} window={}", series_id, window);
+            return u32::MAX;
+        }
+    };
+
+    // Views are windows of one view over a contiguous copy; the engine is
+    // not borrowed while `cb` runs, so it may call back into it
+    let mut caller = WindowCaller::new(&values);
+    let mut out = vec![f64::NAN; values.len()];
+    let mut nulls = 0;
+    for row in 0..values.len() {
+        nulls += values[row].is_nan() as usize;
+        if row >= window {
+            nulls -= values[row - window].is_nan() as usize;
+        }
+        if row + 1 < window || nulls > 0 {
+            continue;
+        }
+        match caller.call(cb, row + 1 - window, row + 1, row as f64) {
+            Ok(result) => out[row] = result,
+            Err(e) => {
+                set_last_error(e);
+                return u32::MAX;
+            }
+        }
+    }
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&out))
+}
+
+/// Whether timestamps are non-decreasing without nulls, from the cached
+/// monotonicity for float64 / int32 / int64 series
+fn sorted_without_nulls(series_id: u32, times: &[f64]) -> bool {
+    match ENGINE.with(|cell| cell.borrow_mut().series_monotonic(series_id)) {
+        Some(monotonic) => monotonic.increasing,
+        None => times.iter().all(|t| !t.is_nan()) && times.windows(2).all(|w| w[0] <= w[1]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::engine_create_series_f64;
+
+    #[test]
+    fn rolling_by_time_keeps_the_current_row_for_tiny_windows() {
+        let times = engine_create_series_f64(&[1.7e12, 1.7e12 + 1.0]);
+        let values = engine_create_series_f64(&[1.0, 2.0]);
+        let out = engine_rolling_by_time_f64(times, values, 1e-5, AGG_SUM);
+        assert_eq!(unsafe { f64_values(out) }.unwrap().to_vec(), vec![1.0, 2.0]);
+        let out = engine_rolling_by_time_f64(times, values, 2.0, AGG_SUM);
+        assert_eq!(unsafe { f64_values(out) }.unwrap().to_vec(), vec![1.0, 3.0]);
+    }
+}
@@ -0,0 +1,256 @@
+//! Row-wise (axis=1) operations across columns
+//!
+//! Combines several equally long float64 series row by row into a new
+//! series, e.g. a total or score column computed from a frame's columns.
+//!
+//! Row expressions are compiled from a compact little-endian postfix byte
+//! encoding: each instruction is an opcode byte plus operands, evaluated on
+//! a stack whose single remaining entry is the result.
+//!
+//! | opcode | instruction | operands           | stack effect |
+//! |--------|-------------|--------------------|--------------|
+//! | 0x01   | column      | u32 index into ids | push         |
+//! | 0x02   | constant    | f64                | push         |
+//! | 0x10   | add         |                    | pop 2, push  |
+//! | 0x11   | subtract    |                    | pop 2, push  |
+//! | 0x12   | multiply    |                    | pop 2, push  |
+//! | 0x13   | divide      |                    | pop 2, push  |
+//! | 0x14   | power       |                    | pop 2, push  |
+//! | 0x20   | negate      |                    | pop 1, push  |
+//! | 0x21   | abs         |                    | pop 1, push  |
+//!
+//! So `(a - b) / c` over ids `[a, b, c]` is column 0, column 1, 0x11,
+//! column 2, 0x13. Nulls (NaN) propagate; division by zero gives infinity
+//! as in IEEE arithmetic.
+
+use wasm_bindgen::prelude::*;
+use crate::core::{f64_values, F64Values, ENGINE};
+use crate::profiling::{profile, series_bytes};
+use crate::series::{arith, ARITH_ADD};
+use crate::statistics::RunningStats;
+
+/// Values of several float64 series of the same length; None (logged) if
+/// an id is unknown or the lengths differ
+pub(crate) fn aligned_columns<'a>(fn_name: &str, series_ids: &[u32]) -> Option<(Vec<F64Values<'a>>, usize)> {
+    let columns: Option<Vec<F64Values>> = series_ids.iter().map(|&id| unsafe { f64_values(id) }).collect();
+    let columns = match columns {
+        Some(columns) => columns,
+        None => {
+            engine_log!(warn, "{}: unknown series in {:?}", fn_name, series_ids);
+            return None;
+        }
+    };
+    let len = columns.first().map_or(0, |c| c.len());
+    if columns.iter().any(|c| c.len() != len) {
+        engine_log!(warn, "{}: series lengths differ", fn_name);
+        return None;
+    }
+    Some((columns, len))
+}
+
+/// Reduce each row across several float64 series with an aggregation code
+/// (0=sum, 1=mean, 2=count, 3=min, 4=max, 5=std, 6=var), as pandas'
+/// `DataFrame.agg(op, axis=1)`: nulls are skipped, so an all-null row sums
+/// to 0, counts 0 and is null for the other aggregates.
+///
+/// Returns a new float64 series id, or u32::MAX if no ids are given, an id
+/// is unknown or the lengths differ.
+#[wasm_bindgen]
+pub fn engine_row_reduce_f64(series_ids: &[u32], op: u8) -> u32 {
+    let _prof = profile("engine_row_reduce_f64", || series_ids.iter().map(|&id| series_bytes(id)).sum());
+    if series_ids.is_empty() {
+        return u32::MAX;
+    }
+    let (columns, len) = match aligned_columns("engine_row_reduce_f64", series_ids) {
+        Some(aligned) => aligned,
+        None => return u32::MAX,
+    };
+    // Column at a time keeps the reads sequential
+    let mut rows = vec![RunningStats::default(); len];
+    for column in &columns {
+        for (start, chunk) in column.chunks() {
+            for (stats, &v) in rows[start..start + chunk.len()].iter_mut().zip(chunk) {
+                stats.push(v);
+            }
+        }
+    }
+    let out: Vec<f64> = rows.iter().map(|stats| stats.finish(op)).collect();
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&out))
+}
+
+pub const ROW_OP_COLUMN: u8 = 0x01;
+pub const ROW_OP_CONST: u8 = 0x02;
+pub const ROW_OP_ADD: u8 = 0x10;
+pub const ROW_OP_SUB: u8 = 0x11;
+pub const ROW_OP_MUL: u8 = 0x12;
+pub const ROW_OP_DIV: u8 = 0x13;
+pub const ROW_OP_POW: u8 = 0x14;
+pub const ROW_OP_NEG: u8 = 0x20;
+pub const ROW_OP_ABS: u8 = 0x21;
+
+/// One compiled instruction of a row expression
+#[derive(Clone, Copy)]
+enum RowInstr {
+    Column(usize),
+    Const(f64),
+    /// Binary opcode (0x10..=0x14)
+    Binary(u8),
+    /// Unary opcode (0x20..=0x21)
+    Unary(u8),
+}
+
+/// Decode and check a postfix expression over `ncols` columns: operands in
+/// range, no stack underflow and exactly one value left. None if malformed.
+fn compile_row_expr(bytes: &[u8], ncols: usize) -> Option<Vec<RowInstr>> {
+    let mut program = Vec::new();
+    let (mut pos, mut depth) = (0usize, 0usize);
+    while pos < bytes.len() {
+        let opcode = bytes[pos];
+        pos += 1;
+        let instr = match opcode {
+            ROW_OP_COLUMN => {
+                let index = u32::from_le_bytes(bytes.get(pos..pos + 4)?.try_into().ok()?) as usize;
+                pos += 4;
+                if index >= ncols {
+                    return None;
+                }
+                RowInstr::Column(index)
+            }
+            ROW_OP_CONST => {
+                let value = f64::from_le_bytes(bytes.get(pos..pos + 8)?.try_into().ok()?);
+                pos += 8;
+                RowInstr::Const(value)
+            }
+            ROW_OP_ADD..=ROW_OP_POW => RowInstr::Binary(opcode),
+            ROW_OP_NEG | ROW_OP_ABS => RowInstr::Unary(opcode),
+            _ => return None,
+        };
+        depth = match instr {
+            RowInstr::Column(_) | RowInstr::Const(_) => depth + 1,
+            RowInstr::Binary(_) => depth.checked_sub(2)? + 1,
+            RowInstr::Unary(_) => depth.checked_sub(1)? + 1,
+        };
+        program.push(instr);
+    }
+    (depth == 1).then_some(program)
+}
+
+/// Evaluate a row expression over several float64 series of the same
+/// length, e.g. `(a - b) / c`, from the postfix encoding described in the
+/// module docs; column operands index into `series_ids`.
+///
+/// Returns a new float64 series id, or u32::MAX if the expression is
+/// malformed, an id is unknown or the lengths differ.
+#[wasm_bindgen]
+pub fn engine_row_expr_f64(series_ids: &[u32], expr_bytes: &[u8]) -> u32 {
+    let _prof = profile("engine_row_expr_f64", || series_ids.iter().map(|&id| series_bytes(id)).sum());
+    let program = match compile_row_expr(expr_bytes, series_ids.len()) {
+        Some(program) => program,
+        None => {
+            engine_log!(warn, "engine_row_expr_f64: malformed expression ({} bytes, {} columns)", expr_bytes.len(), series_ids.len());
+            return u32::MAX;
+        }
+    };
+    let (columns, len) = match aligned_columns("engine_row_expr_f64", series_ids) {
+        Some(aligned) => aligned,
+        None => return u32::MAX,
+    };
+    // Evaluated an instruction at a time over whole columns rather than
+    // dispatching per row
+    let mut stack: Vec<Vec<f64>> = Vec::new();
+    for instr in program {
+        match instr {
+            RowInstr::Column(index) => stack.push(columns[index].to_vec()),
+            RowInstr::Const(value) => stack.push(vec![value; len]),
+            RowInstr::Binary(opcode) => {
+                let rhs = stack.pop().unwrap();
+                let lhs = stack.last_mut().unwrap();
+                for (a, &b) in lhs.iter_mut().zip(&rhs) {
+                    *a = if opcode == ROW_OP_POW { a.powf(b) } else { arith(opcode - ROW_OP_ADD + ARITH_ADD, *a, b) };
+                }
+            }
+            RowInstr::Unary(opcode) => {
+                for v in stack.last_mut().unwrap().iter_mut() {
+                    *v = if opcode == ROW_OP_NEG { -*v } else { v.abs() };
+                }
+            }
+        }
+    }
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&stack[0]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::engine_create_series_f64;
+    use crate::series::engine_series_to_vec_f64;
+    use crate::statistics::{AGG_COUNT, AGG_MAX, AGG_MEAN, AGG_SUM};
+
+    #[test]
+    fn row_reduce_skips_nulls() {
+        let a = engine_create_series_f64(&[1.0, f64::NAN, f64::NAN]);
+        let b = engine_create_series_f64(&[3.0, 4.0, f64::NAN]);
+        let reduce = |op| engine_series_to_vec_f64(engine_row_reduce_f64(&[a, b], op));
+        assert_eq!(reduce(AGG_SUM), [4.0, 4.0, 0.0]);
+        assert_eq!(reduce(AGG_COUNT), [2.0, 1.0, 0.0]);
+        assert_eq!(reduce(AGG_MEAN)[..2], [2.0, 4.0]);
+        assert!(reduce(AGG_MAX)[2].is_nan());
+        assert_eq!(engine_row_reduce_f64(&[], AGG_SUM), u32::MAX);
+        assert_eq!(engine_row_reduce_f64(&[a, engine_create_series_f64(&[1.0])], AGG_SUM), u32::MAX);
+    }
+
+    /// Postfix bytes of column and constant operands and opcodes
+    fn program(instrs: &[RowInstr]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for instr in instrs {
+            match *instr {
+                RowInstr::Column(index) => {
+                    bytes.push(ROW_OP_COLUMN);
+                    bytes.extend((index as u32).to_le_bytes());
+                }
+                RowInstr::Const(value) => {
+                    bytes.push(ROW_OP_CONST);
+                    bytes.extend(value.to_le_bytes());
+                }
+                RowInstr::Binary(opcode) | RowInstr::Unary(opcode) => bytes.push(opcode),
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn row_expr_evaluates_postfix_programs() {
+        use RowInstr::{Binary, Column, Const, Unary};
+        let ids = [
+            engine_create_series_f64(&[5.0, 2.0, f64::NAN]),
+            engine_create_series_f64(&[1.0, 2.0, 1.0]),
+            engine_create_series_f64(&[2.0, 0.0, 1.0]),
+        ];
+        // (a - b) / c
+        let ratio = program(&[Column(0), Column(1), Binary(ROW_OP_SUB), Column(2), Binary(ROW_OP_DIV)]);
+        let out = engine_series_to_vec_f64(engine_row_expr_f64(&ids, &ratio));
+        assert_eq!(out[..1], [2.0]);
+        assert!(out[1].is_nan() && out[2].is_nan());
+        // -|b - a| * 2 ^ c
+        let scaled = program(&[Column(1), Column(0), Binary(ROW_OP_SUB), Unary(ROW_OP_ABS), Unary(ROW_OP_NEG), Const(2.0), Column(2), Binary(ROW_OP_POW), Binary(ROW_OP_MUL)]);
+        assert_eq!(engine_series_to_vec_f64(engine_row_expr_f64(&ids, &scaled))[..2], [-16.0, 0.0]);
+    }
+
+    #[test]
+    fn malformed_programs_are_rejected() {
+        use RowInstr::{Binary, Column};
+        let ids = [engine_create_series_f64(&[1.0]), engine_create_series_f64(&[2.0])];
+        for bad in [
+            program(&[Column(0), Binary(ROW_OP_ADD)]),
+            program(&[Column(0), Column(1)]),
+            program(&[Column(2)]),
+            vec![ROW_OP_CONST, 0, 0],
+            vec![0x7F],
+            Vec::new(),
+        ] {
+            assert_eq!(engine_row_expr_f64(&ids, &bad), u32::MAX);
+        }
+        let sum = program(&[Column(0), Column(1), Binary(ROW_OP_ADD)]);
+        assert_eq!(engine_series_to_vec_f64(engine_row_expr_f64(&ids, &sum)), [3.0]);
+    }
+}
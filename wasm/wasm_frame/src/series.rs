@@ -4,7 +4,13 @@
 //! between formats, and performing scalar operations on registered series.
 
 use wasm_bindgen::prelude::*;
-use crate::core::ENGINE;
+use crate::callbacks::{JsFunction, WindowCaller};
+use crate::core::{destination, f64_values, ENGINE};
+use crate::error::set_last_error;
+use crate::filtering::with_mask;
+use crate::parallel::map_chunks;
+use crate::profiling::{profile, series_bytes};
+use crate::statistics::{RunningStats, AGG_SUM, AGG_VAR};
 
 // Series pointer and length accessors
 #[wasm_bindgen]
@@ -19,16 +25,12 @@ pub fn engine_series_ptr_f64(series_id: u32) -> usize {
     })
 }
 
+/// Length of a contiguous f64 series' buffer, pairing with
+/// `engine_series_ptr_f64` for a `Float64Array` view: 0 for series without a
+/// single buffer (chunked or run-length encoded), like the pointer
 #[wasm_bindgen]
 pub fn engine_series_len_f64(series_id: u32) -> usize {
-    ENGINE.with(|cell| {
-        let eng = cell.borrow();
-        if let Some((_, len)) = eng.series_store.get(&series_id) {
-            *len
-        } else {
-            0
-        }
-    })
+    ENGINE.with(|cell| cell.borrow().series_store.get(&series_id).map_or(0, |&(_, len)| len))
 }
 
 #[wasm_bindgen]
@@ -52,12 +54,17 @@ pub fn engine_series_len_i32(series_id: u32) -> usize {
 // Series conversion functions
 #[wasm_bindgen]
 pub fn engine_series_to_vec_f64(series_id: u32) -> Vec<f64> {
+    let _prof = profile("engine_series_to_vec_f64", || series_bytes(series_id));
+    unsafe { f64_values(series_id) }.map(|values| values.to_vec()).unwrap_or_default()
+}
+
+#[wasm_bindgen]
+pub fn engine_series_to_vec_i32(series_id: u32) -> Vec<i32> {
+    let _prof = profile("engine_series_to_vec_i32", || series_bytes(series_id));
     ENGINE.with(|cell| {
         let eng = cell.borrow();
-        if let Some((ptr, len)) = eng.series_store.get(&series_id) {
-            if ptr.is_null() || *len == 0 {
-                return Vec::new();
-            }
+        if let Some((ptr, len)) = eng.series_store_i32.get(&series_id) {
+            if ptr.is_null() || *len == 0 { return Vec::new(); }
             unsafe {
                 let slice = std::slice::from_raw_parts(*ptr, *len);
                 return slice.to_vec();
@@ -68,128 +75,983 @@ pub fn engine_series_to_vec_f64(series_id: u32) -> Vec<f64> {
 }
 
 #[wasm_bindgen]
-pub fn engine_series_to_vec_i32(series_id: u32) -> Vec<i32> {
+pub fn engine_series_ptr_i64(series_id: u32) -> usize {
+    ENGINE.with(|cell| cell.borrow().series_store_i64.get(&series_id).map_or(0, |(ptr, _)| *ptr as usize))
+}
+
+#[wasm_bindgen]
+pub fn engine_series_len_i64(series_id: u32) -> usize {
+    ENGINE.with(|cell| cell.borrow().series_store_i64.get(&series_id).map_or(0, |(_, len)| *len))
+}
+
+#[wasm_bindgen]
+pub fn engine_series_to_vec_i64(series_id: u32) -> Vec<i64> {
+    let _prof = profile("engine_series_to_vec_i64", || series_bytes(series_id));
+    ENGINE.with(|cell| match cell.borrow().series_store_i64.get(&series_id) {
+        Some(&(ptr, len)) if !ptr.is_null() && len > 0 => unsafe { std::slice::from_raw_parts(ptr, len).to_vec() },
+        _ => Vec::new(),
+    })
+}
+
+#[wasm_bindgen]
+pub fn engine_series_ptr_bool(series_id: u32) -> usize {
+    ENGINE.with(|cell| cell.borrow().series_store_bool.get(&series_id).map_or(0, |(ptr, _)| *ptr as usize))
+}
+
+#[wasm_bindgen]
+pub fn engine_series_len_bool(series_id: u32) -> usize {
+    ENGINE.with(|cell| cell.borrow().series_store_bool.get(&series_id).map_or(0, |(_, len)| *len))
+}
+
+#[wasm_bindgen]
+pub fn engine_series_to_vec_bool(series_id: u32) -> Vec<u8> {
+    let _prof = profile("engine_series_to_vec_bool", || series_bytes(series_id));
+    ENGINE.with(|cell| match cell.borrow().series_store_bool.get(&series_id) {
+        Some(&(ptr, len)) if !ptr.is_null() && len > 0 => unsafe { std::slice::from_raw_parts(ptr, len).to_vec() },
+        _ => Vec::new(),
+    })
+}
+
+#[wasm_bindgen]
+pub fn engine_series_ptr_u32(series_id: u32) -> usize {
+    ENGINE.with(|cell| cell.borrow().series_store_u32.get(&series_id).map_or(0, |(ptr, _)| *ptr as usize))
+}
+
+#[wasm_bindgen]
+pub fn engine_series_len_u32(series_id: u32) -> usize {
+    ENGINE.with(|cell| cell.borrow().series_store_u32.get(&series_id).map_or(0, |(_, len)| *len))
+}
+
+#[wasm_bindgen]
+pub fn engine_series_to_vec_u32(series_id: u32) -> Vec<u32> {
+    let _prof = profile("engine_series_to_vec_u32", || series_bytes(series_id));
+    ENGINE.with(|cell| match cell.borrow().series_store_u32.get(&series_id) {
+        Some(&(ptr, len)) if !ptr.is_null() && len > 0 => unsafe { std::slice::from_raw_parts(ptr, len).to_vec() },
+        _ => Vec::new(),
+    })
+}
+
+#[wasm_bindgen]
+pub fn engine_series_ptr_u8(series_id: u32) -> usize {
+    ENGINE.with(|cell| cell.borrow().series_store_u8.get(&series_id).map_or(0, |(ptr, _)| *ptr as usize))
+}
+
+#[wasm_bindgen]
+pub fn engine_series_len_u8(series_id: u32) -> usize {
+    ENGINE.with(|cell| cell.borrow().series_store_u8.get(&series_id).map_or(0, |(_, len)| *len))
+}
+
+#[wasm_bindgen]
+pub fn engine_series_to_vec_u8(series_id: u32) -> Vec<u8> {
+    let _prof = profile("engine_series_to_vec_u8", || series_bytes(series_id));
+    ENGINE.with(|cell| match cell.borrow().series_store_u8.get(&series_id) {
+        Some(&(ptr, len)) if !ptr.is_null() && len > 0 => unsafe { std::slice::from_raw_parts(ptr, len).to_vec() },
+        _ => Vec::new(),
+    })
+}
+
+#[wasm_bindgen]
+pub fn engine_series_len_str(series_id: u32) -> usize {
+    ENGINE.with(|cell| cell.borrow().series_store_str.get(&series_id).map_or(0, |s| s.len()))
+}
+
+/// Values of a string series; nulls become empty strings
+/// (use `engine_series_to_json_str` to tell them apart)
+#[wasm_bindgen]
+pub fn engine_series_to_vec_str(series_id: u32) -> Vec<String> {
+    let _prof = profile("engine_series_to_vec_str", || series_bytes(series_id));
+    ENGINE.with(|cell| match cell.borrow().series_store_str.get(&series_id) {
+        Some(strings) => strings.iter().map(|v| v.unwrap_or_default().to_string()).collect(),
+        None => Vec::new(),
+    })
+}
+
+/// Values of a string series as a JSON array with nulls as `null`
+/// (empty string if the id is unknown)
+#[wasm_bindgen]
+pub fn engine_series_to_json_str(series_id: u32) -> String {
+    ENGINE.with(|cell| match cell.borrow().series_store_str.get(&series_id) {
+        Some(strings) => serde_json::Value::from(strings.iter().map(|v| v.map(str::to_string)).collect::<Vec<_>>()).to_string(),
+        None => String::new(),
+    })
+}
+
+/// Pointer to the UTF-8 bytes of a string series, for a zero-copy view
+/// with `engine_series_str_bytes_len` (0 if the id is unknown). Value `i`
+/// is `bytes[offsets[i]..offsets[i + 1]]`, the packed binary protocol of
+/// `engine_create_series_str_packed`; null values are empty.
+#[wasm_bindgen]
+pub fn engine_series_str_bytes_ptr(series_id: u32) -> usize {
+    ENGINE.with(|cell| cell.borrow().series_store_str.get(&series_id).map_or(0, |s| s.bytes.as_ptr() as usize))
+}
+
+#[wasm_bindgen]
+pub fn engine_series_str_bytes_len(series_id: u32) -> usize {
+    ENGINE.with(|cell| cell.borrow().series_store_str.get(&series_id).map_or(0, |s| s.bytes.len()))
+}
+
+/// Pointer to the `len + 1` u32 offsets of a string series (see
+/// `engine_series_str_bytes_ptr`; 0 if the id is unknown)
+#[wasm_bindgen]
+pub fn engine_series_str_offsets_ptr(series_id: u32) -> usize {
+    ENGINE.with(|cell| cell.borrow().series_store_str.get(&series_id).map_or(0, |s| s.offsets.as_ptr() as usize))
+}
+
+/// Null mask of a string series, one byte per value (1 = null); empty if
+/// the id is unknown
+#[wasm_bindgen]
+pub fn engine_series_str_null_mask(series_id: u32) -> Vec<u8> {
+    ENGINE.with(|cell| match cell.borrow().series_store_str.get(&series_id) {
+        Some(strings) => (0..strings.len()).map(|row| strings.is_null(row) as u8).collect(),
+        None => Vec::new(),
+    })
+}
+
+/// Value at `row` of a string series; undefined if it is null, the row is
+/// out of range or the id is unknown
+#[wasm_bindgen]
+pub fn engine_series_get_str(series_id: u32, row: usize) -> Option<String> {
     ENGINE.with(|cell| {
         let eng = cell.borrow();
-        if let Some((ptr, len)) = eng.series_store_i32.get(&series_id) {
-            if ptr.is_null() || *len == 0 { return Vec::new(); }
-            unsafe {
-                let slice = std::slice::from_raw_parts(*ptr, *len);
-                return slice.to_vec();
-            }
+        let strings = eng.series_store_str.get(&series_id)?;
+        if row >= strings.len() {
+            return None;
         }
-        Vec::new()
+        strings.get(row).map(str::to_string)
     })
 }
 
 // Scalar operations on registered f64 series
 #[wasm_bindgen]
 pub fn engine_series_sum_f64(series_id: u32) -> f64 {
-    let (ptr, len) = ENGINE.with(|cell| {
-        let eng = cell.borrow();
-        if let Some((p, l)) = eng.series_store.get(&series_id) { (*p, *l) } else { (std::ptr::null_mut(), 0) }
-    });
-    if ptr.is_null() || len == 0 { return 0.0; }
-    let mut sum = 0.0;
-    unsafe {
-        for i in 0..len {
-            let v = *ptr.add(i);
-            if !v.is_nan() { sum += v; }
-        }
-    }
-    sum
+    let _prof = profile("engine_series_sum_f64", || series_bytes(series_id));
+    let values = match unsafe { f64_values(series_id) } { Some(v) => v, None => return 0.0 };
+    values
+        .chunks()
+        .flat_map(|(_, data)| map_chunks(data, |_, chunk| chunk.iter().filter(|v| !v.is_nan()).sum::<f64>()))
+        .sum()
 }
 
 #[wasm_bindgen]
 pub fn engine_series_mean_f64(series_id: u32) -> f64 {
-    let (ptr, len) = ENGINE.with(|cell| {
-        let eng = cell.borrow();
-        if let Some((p, l)) = eng.series_store.get(&series_id) { (*p, *l) } else { (std::ptr::null_mut(), 0) }
-    });
-    if ptr.is_null() || len == 0 { return f64::NAN; }
-    let mut sum = 0.0; let mut cnt: usize = 0;
-    unsafe {
-        for i in 0..len {
-            let v = *ptr.add(i);
-            if !v.is_nan() { sum += v; cnt += 1; }
-        }
-    }
+    let _prof = profile("engine_series_mean_f64", || series_bytes(series_id));
+    let values = match unsafe { f64_values(series_id) } { Some(v) => v, None => return f64::NAN };
+    let (sum, cnt) = values
+        .chunks()
+        .flat_map(|(_, data)| {
+            map_chunks(data, |_, chunk| {
+                chunk.iter().filter(|v| !v.is_nan()).fold((0.0, 0usize), |(s, c), v| (s + v, c + 1))
+            })
+        })
+        .fold((0.0, 0usize), |(s, c), (ps, pc)| (s + ps, c + pc));
     if cnt == 0 { f64::NAN } else { sum / (cnt as f64) }
 }
 
 #[wasm_bindgen]
 pub fn engine_series_std_f64(series_id: u32) -> f64 {
-    let (ptr, len) = ENGINE.with(|cell| {
-        let eng = cell.borrow();
-        if let Some((p, l)) = eng.series_store.get(&series_id) { (*p, *l) } else { (std::ptr::null_mut(), 0) }
-    });
-    if ptr.is_null() { return f64::NAN; }
+    let _prof = profile("engine_series_std_f64", || series_bytes(series_id));
+    let values = match unsafe { f64_values(series_id) } { Some(v) => v, None => return f64::NAN };
     let mut sum = 0.0; let mut cnt: usize = 0;
-    unsafe {
-        for i in 0..len {
-            let v = *ptr.add(i);
-            if !v.is_nan() { sum += v; cnt += 1; }
-        }
+    for v in values.iter() {
+        if !v.is_nan() { sum += v; cnt += 1; }
     }
     if cnt <= 1 { return f64::NAN; }
     let mean = sum / (cnt as f64);
     let mut sumsq = 0.0;
-    unsafe {
-        for i in 0..len {
-            let v = *ptr.add(i);
-            if !v.is_nan() { let d = v - mean; sumsq += d*d; }
-        }
+    for v in values.iter() {
+        if !v.is_nan() { let d = v - mean; sumsq += d*d; }
     }
     (sumsq / ((cnt - 1) as f64)).sqrt()
 }
 
 #[wasm_bindgen]
 pub fn engine_series_min_f64(series_id: u32) -> f64 {
-    let (ptr, len) = ENGINE.with(|cell| {
-        let eng = cell.borrow();
-        if let Some((p, l)) = eng.series_store.get(&series_id) { (*p, *l) } else { (std::ptr::null_mut(), 0) }
+    let _prof = profile("engine_series_min_f64", || series_bytes(series_id));
+    let values = match unsafe { f64_values(series_id) } { Some(v) => v, None => return f64::NAN };
+    let partials = values.chunks().flat_map(|(_, data)| {
+        map_chunks(data, |_, chunk| {
+            let mut m = f64::INFINITY; let mut seen = false;
+            for &v in chunk {
+                if !v.is_nan() { if v < m { m = v; } seen = true; }
+            }
+            if seen { m } else { f64::NAN }
+        })
     });
-    if ptr.is_null() || len == 0 { return f64::NAN; }
-    let mut m = f64::INFINITY; let mut seen = false;
-    unsafe {
-        for i in 0..len {
-            let v = *ptr.add(i);
-            if !v.is_nan() { if v < m { m = v; } seen = true; }
-        }
-    }
-    if seen { m } else { f64::NAN }
+    partials.filter(|v| !v.is_nan()).reduce(|a, b| if b < a { b } else { a }).unwrap_or(f64::NAN)
 }
 
 #[wasm_bindgen]
 pub fn engine_series_max_f64(series_id: u32) -> f64 {
-    let (ptr, len) = ENGINE.with(|cell| {
-        let eng = cell.borrow();
-        if let Some((p, l)) = eng.series_store.get(&series_id) { (*p, *l) } else { (std::ptr::null_mut(), 0) }
+    let _prof = profile("engine_series_max_f64", || series_bytes(series_id));
+    let values = match unsafe { f64_values(series_id) } { Some(v) => v, None => return f64::NAN };
+    let partials = values.chunks().flat_map(|(_, data)| {
+        map_chunks(data, |_, chunk| {
+            let mut m = f64::NEG_INFINITY; let mut seen = false;
+            for &v in chunk {
+                if !v.is_nan() { if v > m { m = v; } seen = true; }
+            }
+            if seen { m } else { f64::NAN }
+        })
+    });
+    partials.filter(|v| !v.is_nan()).reduce(|a, b| if b > a { b } else { a }).unwrap_or(f64::NAN)
+}
+
+#[wasm_bindgen]
+pub fn engine_series_count_f64(series_id: u32) -> u32 {
+    let _prof = profile("engine_series_count_f64", || series_bytes(series_id));
+    let values = match unsafe { f64_values(series_id) } { Some(v) => v, None => return 0 };
+    values
+        .chunks()
+        .flat_map(|(_, data)| map_chunks(data, |_, chunk| chunk.iter().filter(|v| !v.is_nan()).count() as u32))
+        .sum()
+}
+
+/// Minimum and maximum of an f64 series with the row of their first
+/// occurrence, in one pass (as pandas' `idxmin` / `idxmax` on a default
+/// index). Returns `[min, min_row, max, max_row]`, with NaN values and rows
+/// if the series is unknown or has no non-null values.
+#[wasm_bindgen]
+pub fn engine_series_minmax_with_index_f64(series_id: u32) -> Box<[f64]> {
+    let _prof = profile("engine_series_minmax_with_index_f64", || series_bytes(series_id));
+    let none = Box::new([f64::NAN; 4]);
+    let values = match unsafe { f64_values(series_id) } { Some(v) => v, None => return none };
+    // (min, min_row, max, max_row) of each chunk, None if it has no values
+    let partials = values.chunks().flat_map(|(start, data)| {
+        map_chunks(data, move |offset, chunk| {
+            let mut found: Option<(f64, usize, f64, usize)> = None;
+            for (i, &v) in chunk.iter().enumerate() {
+                if v.is_nan() { continue; }
+                let row = start + offset + i;
+                match found.as_mut() {
+                    None => found = Some((v, row, v, row)),
+                    Some(m) => {
+                        if v < m.0 { m.0 = v; m.1 = row; }
+                        if v > m.2 { m.2 = v; m.3 = row; }
+                    }
+                }
+            }
+            found
+        })
+    });
+    // Chunks come in row order, so strict comparisons keep the first occurrence
+    let result = partials.flatten().reduce(|a, b| {
+        let (min, min_row) = if b.0 < a.0 { (b.0, b.1) } else { (a.0, a.1) };
+        let (max, max_row) = if b.2 > a.2 { (b.2, b.3) } else { (a.2, a.3) };
+        (min, min_row, max, max_row)
+    });
+    match result {
+        Some((min, min_row, max, max_row)) => Box::new([min, min_row as f64, max, max_row as f64]),
+        None => none,
+    }
+}
+
+/// Row of the first non-null value of an f64 series, as pandas'
+/// `first_valid_index` on a default index (e.g. to trim leading gaps);
+/// u32::MAX if every value is null or the id is unknown
+#[wasm_bindgen]
+pub fn engine_series_first_valid_index_f64(series_id: u32) -> u32 {
+    let _prof = profile("engine_series_first_valid_index_f64", || series_bytes(series_id));
+    let values = match unsafe { f64_values(series_id) } { Some(v) => v, None => return u32::MAX };
+    let found = values.chunks().find_map(|(start, chunk)| chunk.iter().position(|v| !v.is_nan()).map(|i| start + i));
+    found.map_or(u32::MAX, |row| row as u32)
+}
+
+/// Row of the last non-null value of an f64 series, as pandas'
+/// `last_valid_index` (e.g. to trim trailing gaps); u32::MAX if every value
+/// is null or the id is unknown
+#[wasm_bindgen]
+pub fn engine_series_last_valid_index_f64(series_id: u32) -> u32 {
+    let _prof = profile("engine_series_last_valid_index_f64", || series_bytes(series_id));
+    let values = match unsafe { f64_values(series_id) } { Some(v) => v, None => return u32::MAX };
+    let chunks: Vec<(usize, &[f64])> = values.chunks().collect();
+    let found = chunks.iter().rev().find_map(|&(start, chunk)| chunk.iter().rposition(|v| !v.is_nan()).map(|i| start + i));
+    found.map_or(u32::MAX, |row| row as u32)
+}
+
+/// Single-pass `RunningStats` of an f64 series, merged across chunks;
+/// None if the id is unknown
+fn series_running_stats(series_id: u32) -> Option<RunningStats> {
+    let values = unsafe { f64_values(series_id) }?;
+    let partials = values.chunks().flat_map(|(_, data)| {
+        map_chunks(data, |_, chunk| {
+            let mut stats = RunningStats::default();
+            chunk.iter().for_each(|&v| stats.push(v));
+            stats
+        })
     });
-    if ptr.is_null() || len == 0 { return f64::NAN; }
-    let mut m = f64::NEG_INFINITY; let mut seen = false;
-    unsafe {
-        for i in 0..len {
-            let v = *ptr.add(i);
-            if !v.is_nan() { if v > m { m = v; } seen = true; }
+    Some(partials.fold(RunningStats::default(), |mut acc, stats| { acc.merge(&stats); acc }))
+}
+
+/// Several aggregates of an f64 series in one pass over the buffer.
+/// agg_mask bit layout (LSB -> MSB), as `engine_groupby_multi_f64`:
+/// 1=sum, 2=mean, 4=count, 8=min, 16=max, 32=std, 64=var
+/// Returns one value per bit set, in the above order (nulls skipped; std and
+/// var are sample statistics); empty if the id is unknown.
+#[wasm_bindgen]
+pub fn engine_series_agg_multi_f64(series_id: u32, agg_mask: u32) -> Vec<f64> {
+    let _prof = profile("engine_series_agg_multi_f64", || series_bytes(series_id));
+    let stats = match series_running_stats(series_id) { Some(s) => s, None => return Vec::new() };
+    (AGG_SUM..=AGG_VAR).filter(|&agg| agg_mask & (1 << agg) != 0).map(|agg| stats.finish(agg)).collect()
+}
+
+/// The same aggregate (an `AGG_*` code: 0=sum, 1=mean, 2=count, 3=min,
+/// 4=max, 5=std, 6=var) of each of several f64 series in one call, e.g. for
+/// a column summary footer. Returns one value per id, NaN for ids that are
+/// not float64 series.
+#[wasm_bindgen]
+pub fn engine_many_series_agg_f64(series_ids: &[u32], agg_kind: u8) -> Vec<f64> {
+    let _prof = profile("engine_many_series_agg_f64", || series_ids.iter().map(|&id| series_bytes(id)).sum());
+    series_ids
+        .iter()
+        .map(|&id| series_running_stats(id).map_or(f64::NAN, |stats| stats.finish(agg_kind)))
+        .collect()
+}
+
+/// Each value of an f64 series as a share of the series total (nulls
+/// skipped in the total), as pandas' `s / s.sum()`; multiply by 100 for
+/// percent of total. Null rows stay null. Returns u32::MAX if the id is
+/// unknown.
+#[wasm_bindgen]
+pub fn engine_share_of_total_f64(series_id: u32) -> u32 {
+    let _prof = profile("engine_share_of_total_f64", || series_bytes(series_id));
+    if unsafe { f64_values(series_id) }.is_none() {
+        return u32::MAX;
+    }
+    let total = engine_series_sum_f64(series_id);
+    map_values_f64(series_id, false, |v| v / total)
+}
+
+// Aggregates over unsigned series (no nulls); results are f64 so sums
+// cannot overflow the element type
+
+/// Sum, min and max of a uint32 or uint8 series, None if unknown or empty
+fn unsigned_summary(series_id: u32) -> Option<(f64, f64, f64, usize)> {
+    fn summarize<T: Copy + Into<f64>>(values: &[T]) -> Option<(f64, f64, f64, usize)> {
+        let (sum, min, max) = values.iter().fold((0.0, f64::INFINITY, f64::NEG_INFINITY), |(s, lo, hi), &v| {
+            let v: f64 = v.into();
+            (s + v, lo.min(v), hi.max(v))
+        });
+        if values.is_empty() { None } else { Some((sum, min, max, values.len())) }
+    }
+    ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        if let Some(&(ptr, len)) = eng.series_store_u32.get(&series_id) {
+            summarize(unsafe { std::slice::from_raw_parts(ptr, len) })
+        } else if let Some(&(ptr, len)) = eng.series_store_u8.get(&series_id) {
+            summarize(unsafe { std::slice::from_raw_parts(ptr, len) })
+        } else {
+            None
         }
+    })
+}
+
+/// Sum of a uint32 series (0 if unknown or empty)
+#[wasm_bindgen]
+pub fn engine_series_sum_u32(series_id: u32) -> f64 {
+    let _prof = profile("engine_series_sum_u32", || series_bytes(series_id));
+    unsigned_summary(series_id).map_or(0.0, |s| s.0)
+}
+
+/// Mean of a uint32 series (NaN if unknown or empty)
+#[wasm_bindgen]
+pub fn engine_series_mean_u32(series_id: u32) -> f64 {
+    let _prof = profile("engine_series_mean_u32", || series_bytes(series_id));
+    unsigned_summary(series_id).map_or(f64::NAN, |s| s.0 / s.3 as f64)
+}
+
+/// Minimum of a uint32 series (NaN if unknown or empty)
+#[wasm_bindgen]
+pub fn engine_series_min_u32(series_id: u32) -> f64 {
+    let _prof = profile("engine_series_min_u32", || series_bytes(series_id));
+    unsigned_summary(series_id).map_or(f64::NAN, |s| s.1)
+}
+
+/// Maximum of a uint32 series (NaN if unknown or empty)
+#[wasm_bindgen]
+pub fn engine_series_max_u32(series_id: u32) -> f64 {
+    let _prof = profile("engine_series_max_u32", || series_bytes(series_id));
+    unsigned_summary(series_id).map_or(f64::NAN, |s| s.2)
+}
+
+/// Sum of a uint8 series (0 if unknown or empty)
+#[wasm_bindgen]
+pub fn engine_series_sum_u8(series_id: u32) -> f64 {
+    let _prof = profile("engine_series_sum_u8", || series_bytes(series_id));
+    unsigned_summary(series_id).map_or(0.0, |s| s.0)
+}
+
+/// Mean of a uint8 series (NaN if unknown or empty)
+#[wasm_bindgen]
+pub fn engine_series_mean_u8(series_id: u32) -> f64 {
+    let _prof = profile("engine_series_mean_u8", || series_bytes(series_id));
+    unsigned_summary(series_id).map_or(f64::NAN, |s| s.0 / s.3 as f64)
+}
+
+/// Minimum of a uint8 series (NaN if unknown or empty)
+#[wasm_bindgen]
+pub fn engine_series_min_u8(series_id: u32) -> f64 {
+    let _prof = profile("engine_series_min_u8", || series_bytes(series_id));
+    unsigned_summary(series_id).map_or(f64::NAN, |s| s.1)
+}
+
+/// Maximum of a uint8 series (NaN if unknown or empty)
+#[wasm_bindgen]
+pub fn engine_series_max_u8(series_id: u32) -> f64 {
+    let _prof = profile("engine_series_max_u8", || series_bytes(series_id));
+    unsigned_summary(series_id).map_or(f64::NAN, |s| s.2)
+}
+
+// Copy-out into caller-provided WASM memory (e.g. a preallocated TypedArray
+// view over wasm memory), avoiding a fresh allocation per call.
+
+/// Copy a registered f64 series into `dst_ptr` (capacity `dst_len` values).
+/// Returns the number of values written, or usize::MAX if the series is
+/// unknown, `dst_ptr` is null, misaligned or inside an engine buffer, or the
+/// destination is too small.
+#[wasm_bindgen]
+pub fn engine_series_copy_into_f64(series_id: u32, dst_ptr: usize, dst_len: usize) -> usize {
+    let _prof = profile("engine_series_copy_into_f64", || series_bytes(series_id));
+    let values = match unsafe { f64_values(series_id) } { Some(v) => v, None => return usize::MAX };
+    if dst_len < values.len() { return usize::MAX; }
+    let dst = match destination::<f64>(dst_ptr, values.len()) { Some(d) => d, None => return usize::MAX };
+    for (start, chunk) in values.chunks() {
+        unsafe { std::ptr::copy_nonoverlapping(chunk.as_ptr(), dst.add(start), chunk.len()); }
     }
-    if seen { m } else { f64::NAN }
+    values.len()
 }
 
+/// Copy a registered i32 series into `dst_ptr` (capacity `dst_len` values).
+/// Returns the number of values written, or usize::MAX on failure.
 #[wasm_bindgen]
-pub fn engine_series_count_f64(series_id: u32) -> u32 {
+pub fn engine_series_copy_into_i32(series_id: u32, dst_ptr: usize, dst_len: usize) -> usize {
+    let _prof = profile("engine_series_copy_into_i32", || series_bytes(series_id));
     let (ptr, len) = ENGINE.with(|cell| {
         let eng = cell.borrow();
-        if let Some((p, l)) = eng.series_store.get(&series_id) { (*p, *l) } else { (std::ptr::null_mut(), 0) }
+        if let Some((p, l)) = eng.series_store_i32.get(&series_id) { (*p, *l) } else { (std::ptr::null_mut(), usize::MAX) }
     });
-    if ptr.is_null() { return 0; }
-    let mut cnt: u32 = 0;
-    unsafe {
-        for i in 0..len {
-            let v = *ptr.add(i);
-            if !v.is_nan() { cnt += 1; }
+    if len == usize::MAX || dst_len < len { return usize::MAX; }
+    let dst = match destination::<i32>(dst_ptr, len) { Some(d) => d, None => return usize::MAX };
+    if len > 0 && !ptr.is_null() {
+        unsafe { std::ptr::copy_nonoverlapping(ptr as *const i32, dst, len); }
+    }
+    len
+}
+
+/// Write a null mask (1 where the value is NaN, else 0) for a registered f64
+/// series into `dst_ptr` (capacity `dst_len` bytes).
+/// Returns the number of bytes written, or usize::MAX on failure.
+#[wasm_bindgen]
+pub fn engine_isna_mask_into_f64(series_id: u32, dst_ptr: usize, dst_len: usize) -> usize {
+    let _prof = profile("engine_isna_mask_into_f64", || series_bytes(series_id));
+    let values = match unsafe { f64_values(series_id) } { Some(v) => v, None => return usize::MAX };
+    if dst_len < values.len() { return usize::MAX; }
+    let dst = match destination::<u8>(dst_ptr, values.len()) { Some(d) => d, None => return usize::MAX };
+    for (start, chunk) in values.chunks() {
+        for (i, v) in chunk.iter().enumerate() {
+            unsafe { *dst.add(start + i) = v.is_nan() as u8; }
+        }
+    }
+    values.len()
+}
+
+// Arithmetic operator codes (shared with expression arithmetic)
+pub(crate) const ARITH_ADD: u8 = 0;
+pub(crate) const ARITH_SUB: u8 = 1;
+pub(crate) const ARITH_MUL: u8 = 2;
+pub(crate) const ARITH_DIV: u8 = 3;
+
+pub(crate) fn arith(op: u8, a: f64, b: f64) -> f64 {
+    match op {
+        ARITH_ADD => a + b,
+        ARITH_SUB => a - b,
+        ARITH_MUL => a * b,
+        ARITH_DIV => a / b,
+        _ => f64::NAN,
+    }
+}
+
+/// Run `f` over the values of an f64 series. In place, the series' buffer
+/// is copied first if it is shared (copy-on-write) and the same id is
+/// returned; otherwise `f` runs on a copy registered as a new series.
+fn transform_f64<F: FnOnce(&mut [f64])>(series_id: u32, in_place: bool, f: F) -> u32 {
+    if in_place {
+        let target = ENGINE.with(|cell| cell.borrow_mut().make_mut_f64(series_id));
+        return match target {
+            Ok(Some((ptr, len))) => {
+                if len > 0 {
+                    f(unsafe { std::slice::from_raw_parts_mut(ptr, len) });
+                }
+                series_id
+            }
+            Ok(None) => u32::MAX,
+            Err(e) => {
+                set_last_error(e);
+                u32::MAX
+            }
+        };
+    }
+    match unsafe { f64_values(series_id) } {
+        Some(values) => {
+            let mut values = values.to_vec();
+            f(&mut values);
+            ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&values))
         }
+        None => u32::MAX,
+    }
+}
+
+/// Apply `f` to every value of an f64 series (see `transform_f64`)
+fn map_values_f64<F: Fn(f64) -> f64>(series_id: u32, in_place: bool, f: F) -> u32 {
+    transform_f64(series_id, in_place, |values| {
+        for v in values.iter_mut() {
+            *v = f(*v);
+        }
+    })
+}
+
+/// User-defined element-wise transform of an f64 series through a JS
+/// callback, called once per batch of up to `batch_size` rows (0 = the
+/// whole series in one call) rather than per element: `cb(view, start)`
+/// receives a Float64Array view over a copy of the batch starting at row
+/// `start` and overwrites its values in place; its return value is ignored.
+/// The view follows the rules in `callbacks`.
+///
+/// Returns a new float64 series id, or u32::MAX if the series is unknown
+/// or the callback throws (error code 6).
+#[wasm_bindgen]
+pub fn engine_series_map_f64(series_id: u32, cb: &JsFunction, batch_size: usize) -> u32 {
+    let _prof = profile("engine_series_map_f64", || series_bytes(series_id));
+    let mut out = match unsafe { f64_values(series_id) } { Some(values) => values.to_vec(), None => return u32::MAX };
+    let len = out.len();
+    let batch_size = if batch_size == 0 { len.max(1) } else { batch_size };
+    // The engine is not borrowed here, so the callback may call back into it
+    let mut caller = WindowCaller::new_mut(&mut out);
+    for start in (0..len).step_by(batch_size) {
+        if let Err(e) = caller.call_mut(cb, start, (start + batch_size).min(len), start as f64) {
+            set_last_error(e);
+            return u32::MAX;
+        }
+    }
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&out))
+}
+
+/// Replace NaN values of an f64 series with `value`. With `in_place` = 1 the
+/// series itself is updated and its id returned (a buffer shared with clones
+/// or views is copied first, so they are unaffected); otherwise a new series
+/// is registered. Returns u32::MAX if the series is unknown or allocation fails.
+#[wasm_bindgen]
+pub fn engine_series_fillna_f64(series_id: u32, value: f64, in_place: u8) -> u32 {
+    let _prof = profile("engine_series_fillna_f64", || series_bytes(series_id));
+    map_values_f64(series_id, in_place != 0, |v| if v.is_nan() { value } else { v })
+}
+
+/// Element-wise `series <op> scalar` (op: 0=add, 1=sub, 2=mul, 3=div), with
+/// the same `in_place` and copy-on-write behavior as `engine_series_fillna_f64`.
+#[wasm_bindgen]
+pub fn engine_series_scalar_op_f64(series_id: u32, op: u8, scalar: f64, in_place: u8) -> u32 {
+    let _prof = profile("engine_series_scalar_op_f64", || series_bytes(series_id));
+    map_values_f64(series_id, in_place != 0, |v| arith(op, v, scalar))
+}
+
+/// Set the rows of an f64 series where a registered mask (a bool or uint8
+/// series, non-zero = true) is true, as `df.loc[cond, "x"] = v`: to
+/// `value`, or with `other_id` set (u32::MAX for none) to the same rows of
+/// that float64 series. Same `in_place` and copy-on-write behavior as
+/// `engine_series_fillna_f64`. Returns u32::MAX if a series is unknown or
+/// the lengths differ.
+#[wasm_bindgen]
+pub fn engine_series_set_where_f64(series_id: u32, mask_id: u32, value: f64, other_id: u32, in_place: u8) -> u32 {
+    let _prof = profile("engine_series_set_where_f64", || series_bytes(series_id));
+    let len = unsafe { f64_values(series_id) }.map_or(usize::MAX, |values| values.len());
+    let mask = with_mask(mask_id, |mask| mask.to_vec());
+    let other = if other_id == u32::MAX { None } else { unsafe { f64_values(other_id) }.map(|values| values.to_vec()) };
+    let (mask, other) = match (mask, other) {
+        (Some(mask), other) if mask.len() == len && (other_id == u32::MAX || other.as_ref().is_some_and(|o| o.len() == len)) => (mask, other),
+        _ => {
+            engine_log!(warn, "engine_series_set_where_f64: unknown series or length mismatch series_id={} mask_id={} other_id={}", series_id, mask_id, other_id);
+            return u32::MAX;
+        }
+    };
+    transform_f64(series_id, in_place != 0, |values| {
+        for (row, v) in values.iter_mut().enumerate() {
+            if mask[row] != 0 {
+                *v = other.as_ref().map_or(value, |other| other[row]);
+            }
+        }
+    })
+}
+
+/// Set `values[i]` at row `indices[i]` of an f64 series (point updates,
+/// e.g. from an editable grid; a repeated row takes its last value), with
+/// the same `in_place` and copy-on-write behavior as
+/// `engine_series_fillna_f64`. Returns u32::MAX if the series is unknown,
+/// the arrays differ in length or a row is out of range; nothing is
+/// written in that case.
+#[wasm_bindgen]
+pub fn engine_series_scatter_f64(series_id: u32, indices: &[u32], values: &[f64], in_place: u8) -> u32 {
+    let _prof = profile("engine_series_scatter_f64", || series_bytes(series_id));
+    let len = match unsafe { f64_values(series_id) } { Some(v) => v.len(), None => return u32::MAX };
+    if indices.len() != values.len() || indices.iter().any(|&row| row as usize >= len) {
+        engine_log!(warn, "engine_series_scatter_f64: length mismatch or row out of range series_id={} len={}", series_id, len);
+        return u32::MAX;
+    }
+    transform_f64(series_id, in_place != 0, |target| {
+        for (&row, &v) in indices.iter().zip(values) {
+            target[row as usize] = v;
+        }
+    })
+}
+
+/// Reverse the order of an f64 series, in place (`in_place` = 1, with
+/// copy-on-write for shared buffers) or into a new series. Returns the
+/// result id, or u32::MAX if the series is unknown.
+#[wasm_bindgen]
+pub fn engine_reverse_f64(series_id: u32, in_place: u8) -> u32 {
+    let _prof = profile("engine_reverse_f64", || series_bytes(series_id));
+    transform_f64(series_id, in_place != 0, |values| values.reverse())
+}
+
+/// Circularly shift an f64 series by `shift` positions (positive moves
+/// values toward the end, like `numpy.roll`), in place or into a new series
+/// as in `engine_reverse_f64`
+#[wasm_bindgen]
+pub fn engine_roll_f64(series_id: u32, shift: i32, in_place: u8) -> u32 {
+    let _prof = profile("engine_roll_f64", || series_bytes(series_id));
+    transform_f64(series_id, in_place != 0, |values| {
+        if values.is_empty() {
+            return;
+        }
+        let k = (shift as i64).rem_euclid(values.len() as i64) as usize;
+        values.rotate_right(k);
+    })
+}
+
+/// Lagged copies of an f64 series for autoregressive features, one per
+/// entry of `lags`: lag `k` holds the value `k` rows earlier (pandas'
+/// `shift(k)`), NaN for the first `k` rows; lag 0 is a plain copy. The
+/// values are read once for all lags.
+///
+/// Returns one float64 series id per lag, in the order given, or an empty
+/// array if the id is unknown.
+#[wasm_bindgen]
+pub fn engine_lag_matrix_f64(series_id: u32, lags: &[u32]) -> Box<[u32]> {
+    let _prof = profile("engine_lag_matrix_f64", || series_bytes(series_id) * lags.len());
+    let values = match unsafe { f64_values(series_id) } { Some(v) => v.to_vec(), None => return Box::new([]) };
+    let n = values.len();
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        lags.iter()
+            .map(|&lag| {
+                let k = (lag as usize).min(n);
+                let mut lagged = vec![f64::NAN; k];
+                lagged.extend_from_slice(&values[..n - k]);
+                eng.register_series_f64(&lagged)
+            })
+            .collect()
+    })
+}
+
+/// Row indices selected by a Python-style slice `[start:stop:step]` over
+/// `len` rows; `None` bounds take the defaults for the step's direction
+fn slice_indices(len: usize, start: Option<i64>, stop: Option<i64>, step: i64) -> Vec<usize> {
+    let n = len as i64;
+    let resolve = |bound: i64, lower: i64, upper: i64| {
+        let bound = if bound < 0 { bound + n } else { bound };
+        bound.clamp(lower, upper)
+    };
+    if step > 0 {
+        let start = start.map_or(0, |b| resolve(b, 0, n));
+        let stop = stop.map_or(n, |b| resolve(b, 0, n));
+        (start..stop.max(start)).step_by(step as usize).map(|i| i as usize).collect()
+    } else {
+        let start = start.map_or(n - 1, |b| resolve(b, -1, n - 1));
+        let stop = stop.map_or(-1, |b| resolve(b, -1, n - 1));
+        let mut out = Vec::new();
+        let mut i = start;
+        while i > stop {
+            out.push(i as usize);
+            i += step;
+        }
+        out
+    }
+}
+
+/// Copy `series[start:stop:step]` of an f64 series into a new series, with
+/// Python slice semantics: negative bounds count from the end, a negative
+/// step walks backwards, and i32::MIN stands for an omitted bound (so
+/// `[::2]` is `(MIN, MIN, 2)` and a 5-row tail is `(-5, MIN, 1)`).
+/// Returns u32::MAX if the series is unknown or `step` is 0.
+#[wasm_bindgen]
+pub fn engine_slice_f64(series_id: u32, start: i32, stop: i32, step: i32) -> u32 {
+    let _prof = profile("engine_slice_f64", || series_bytes(series_id));
+    let values = match unsafe { f64_values(series_id) } {
+        Some(values) if step != 0 => values,
+        _ => return u32::MAX,
+    };
+    let bound = |b: i32| (b != i32::MIN).then_some(b as i64);
+    let out: Vec<f64> = slice_indices(values.len(), bound(start), bound(stop), step as i64)
+        .into_iter()
+        .map(|i| values.get(i))
+        .collect();
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        engine_chunked_append_f64, engine_chunked_create_f64, engine_create_series_f64, engine_create_series_u32,
+        engine_create_series_u8, engine_free_series_u32, engine_free_series_u8,
+    };
+    use crate::sorting::engine_sort_indices_into_f64;
+
+    #[test]
+    fn copy_into_handles_chunked_series() {
+        let series = engine_chunked_create_f64();
+        assert!(engine_chunked_append_f64(series, &[1.0, f64::NAN]));
+        assert!(engine_chunked_append_f64(series, &[3.0]));
+        let mut values = [0.0f64; 3];
+        assert_eq!(engine_series_copy_into_f64(series, values.as_mut_ptr() as usize, 3), 3);
+        assert_eq!(values[2], 3.0);
+        let mut mask = [9u8; 3];
+        assert_eq!(engine_isna_mask_into_f64(series, mask.as_mut_ptr() as usize, 3), 3);
+        assert_eq!(mask, [0, 1, 0]);
+    }
+
+    #[test]
+    fn copy_into_rejects_bad_destinations() {
+        let series = engine_create_series_f64(&[2.0, 1.0]);
+        let mut values = [0.0f64; 3];
+        let misaligned = values.as_mut_ptr() as usize + 1;
+        assert_eq!(engine_series_copy_into_f64(series, misaligned, 2), usize::MAX);
+        assert_eq!(engine_series_copy_into_f64(series, values.as_mut_ptr() as usize, 1), usize::MAX);
+        // The destination may not be another series' buffer
+        let other = engine_create_series_f64(&[0.0, 0.0]);
+        let other_ptr = engine_series_ptr_f64(other);
+        assert_eq!(engine_series_copy_into_f64(series, other_ptr, 2), usize::MAX);
+        assert_eq!(engine_isna_mask_into_f64(series, other_ptr + 8, 2), usize::MAX);
+        assert_eq!(engine_sort_indices_into_f64(series, 1, 1, other_ptr, 4), usize::MAX);
+        assert_eq!(engine_series_to_vec_f64(other), vec![0.0, 0.0]);
+        let mut indices = [0u32; 2];
+        assert_eq!(engine_sort_indices_into_f64(series, 1, 1, indices.as_mut_ptr() as usize, 2), 2);
+        assert_eq!(indices, [1, 0]);
+    }
+
+    #[test]
+    fn string_accessors_expose_the_packed_buffers() {
+        use crate::core::engine_create_series_str_packed;
+        let series = engine_create_series_str_packed("h\u{E9}ab".as_bytes(), &[0, 3, 3, 4, 5], &[0, 1, 0, 0]);
+        let len = engine_series_str_bytes_len(series);
+        let bytes = unsafe { std::slice::from_raw_parts(engine_series_str_bytes_ptr(series) as *const u8, len) };
+        let offsets = unsafe { std::slice::from_raw_parts(engine_series_str_offsets_ptr(series) as *const u32, 5) };
+        assert_eq!(bytes, "h\u{E9}ab".as_bytes());
+        assert_eq!(offsets, [0, 3, 3, 4, 5]);
+        assert_eq!(engine_series_str_null_mask(series), [0, 1, 0, 0]);
+        assert_eq!(engine_series_get_str(series, 0).as_deref(), Some("h\u{E9}"));
+        assert_eq!(engine_series_get_str(series, 1), None);
+        assert_eq!(engine_series_get_str(series, 3).as_deref(), Some("b"));
+        assert_eq!(engine_series_get_str(series, 4), None);
+        // Unknown ids
+        let floats = engine_create_series_f64(&[1.0]);
+        assert_eq!(engine_series_str_bytes_ptr(floats), 0);
+        assert_eq!(engine_series_str_offsets_ptr(floats), 0);
+        assert!(engine_series_str_null_mask(floats).is_empty());
+        assert_eq!(engine_series_get_str(floats, 0), None);
+    }
+
+    #[test]
+    fn set_where_takes_a_scalar_or_the_other_series() {
+        use crate::core::{engine_create_series_bool, engine_create_series_u8};
+        let series = engine_create_series_f64(&[1.0, 2.0, 3.0]);
+        let mask = engine_create_series_bool(&[1, 0, 1]);
+        let out = engine_series_set_where_f64(series, mask, 0.0, u32::MAX, 0);
+        assert_eq!(engine_series_to_vec_f64(out), vec![0.0, 2.0, 0.0]);
+        assert_eq!(engine_series_to_vec_f64(series), vec![1.0, 2.0, 3.0]);
+        let other = engine_create_series_f64(&[10.0, 20.0, 30.0]);
+        let mask = engine_create_series_u8(&[0, 7, 0]);
+        assert_eq!(engine_series_set_where_f64(series, mask, 0.0, other, 1), series);
+        assert_eq!(engine_series_to_vec_f64(series), vec![1.0, 20.0, 3.0]);
+        assert_eq!(engine_series_set_where_f64(series, engine_create_series_bool(&[1]), 0.0, u32::MAX, 0), u32::MAX);
+        assert_eq!(engine_series_set_where_f64(series, mask, 0.0, engine_create_series_f64(&[1.0]), 0), u32::MAX);
+        assert_eq!(engine_series_set_where_f64(series, other, 0.0, u32::MAX, 0), u32::MAX);
+    }
+
+    #[test]
+    fn int32_copy_and_sort_into_write_the_destination() {
+        use crate::core::engine_create_series_i32;
+        use crate::sorting::engine_sort_indices_into_i32;
+        let series = engine_create_series_i32(&[3, i32::MIN, 1]);
+        let mut values = [0i32; 4];
+        assert_eq!(engine_series_copy_into_i32(series, values.as_mut_ptr() as usize, 4), 3);
+        assert_eq!(values, [3, i32::MIN, 1, 0]);
+        assert_eq!(engine_series_copy_into_i32(series, values.as_mut_ptr() as usize, 2), usize::MAX);
+        let mut indices = [9u32; 3];
+        assert_eq!(engine_sort_indices_into_i32(series, 1, 1, indices.as_mut_ptr() as usize, 3), 3);
+        assert_eq!(indices, [2, 0, 1]);
+        let misaligned = indices.as_mut_ptr() as usize + 1;
+        assert_eq!(engine_sort_indices_into_i32(series, 1, 1, misaligned, 3), usize::MAX);
+        assert_eq!(engine_sort_indices_into_i32(engine_create_series_f64(&[1.0]), 1, 1, indices.as_mut_ptr() as usize, 3), usize::MAX);
+    }
+
+    #[test]
+    fn fillna_copies_shared_buffers_before_writing_in_place() {
+        use crate::core::engine_series_clone;
+        let series = engine_create_series_f64(&[1.0, f64::NAN, 3.0]);
+        let filled = engine_series_fillna_f64(series, 0.0, 0);
+        assert_eq!(engine_series_to_vec_f64(filled), vec![1.0, 0.0, 3.0]);
+        let clone = engine_series_clone(series);
+        assert_eq!(engine_series_fillna_f64(series, -1.0, 1), series);
+        assert_eq!(engine_series_to_vec_f64(series), vec![1.0, -1.0, 3.0]);
+        assert!(engine_series_to_vec_f64(clone)[1].is_nan());
+        assert_eq!(engine_series_fillna_f64(u32::MAX - 1, 0.0, 0), u32::MAX);
+    }
+
+    #[test]
+    fn reverse_and_roll_move_values_like_numpy() {
+        let series = engine_create_series_f64(&[1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(engine_series_to_vec_f64(engine_reverse_f64(series, 0)), vec![4.0, 3.0, 2.0, 1.0]);
+        assert_eq!(engine_series_to_vec_f64(engine_roll_f64(series, 1, 0)), vec![4.0, 1.0, 2.0, 3.0]);
+        assert_eq!(engine_series_to_vec_f64(engine_roll_f64(series, -1, 0)), vec![2.0, 3.0, 4.0, 1.0]);
+        assert_eq!(engine_series_to_vec_f64(engine_roll_f64(series, 6, 0)), vec![3.0, 4.0, 1.0, 2.0]);
+        assert_eq!(engine_series_to_vec_f64(series), vec![1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(engine_reverse_f64(series, 1), series);
+        assert_eq!(engine_series_to_vec_f64(series), vec![4.0, 3.0, 2.0, 1.0]);
+        assert!(engine_series_to_vec_f64(engine_roll_f64(engine_create_series_f64(&[]), 3, 0)).is_empty());
+        assert_eq!(engine_roll_f64(u32::MAX - 1, 1, 0), u32::MAX);
+    }
+
+    #[test]
+    fn slices_follow_python_semantics() {
+        const NONE: i32 = i32::MIN;
+        let series = engine_chunked_create_f64();
+        assert!(engine_chunked_append_f64(series, &[0.0, 1.0, 2.0]));
+        assert!(engine_chunked_append_f64(series, &[3.0, 4.0]));
+        let slice = |start, stop, step| engine_series_to_vec_f64(engine_slice_f64(series, start, stop, step));
+        assert_eq!(slice(NONE, NONE, 2), vec![0.0, 2.0, 4.0]);
+        assert_eq!(slice(-2, NONE, 1), vec![3.0, 4.0]);
+        assert_eq!(slice(NONE, NONE, -1), vec![4.0, 3.0, 2.0, 1.0, 0.0]);
+        assert_eq!(slice(3, 0, -1), vec![3.0, 2.0, 1.0]);
+        assert_eq!(slice(-10, 2, 1), vec![0.0, 1.0]);
+        assert_eq!(slice(10, -10, -2), vec![4.0, 2.0, 0.0]);
+        assert!(slice(4, 1, 1).is_empty());
+        assert_eq!(engine_slice_f64(series, NONE, NONE, 0), u32::MAX);
+    }
+
+    #[test]
+    fn unsigned_summaries_widen_to_f64() {
+        let ids = engine_create_series_u32(&[u32::MAX, 1, 5]);
+        let codes = engine_create_series_u8(&[200, 100, 0, 4]);
+        assert_eq!(engine_series_len_u32(ids), 3);
+        assert_ne!(engine_series_ptr_u32(ids), 0);
+        assert_eq!(engine_series_len_u8(codes), 4);
+        assert_ne!(engine_series_ptr_u8(codes), 0);
+        // No overflow: the sums are accumulated in f64
+        assert_eq!(engine_series_sum_u32(ids), u32::MAX as f64 + 6.0);
+        assert_eq!(engine_series_mean_u32(ids), (u32::MAX as f64 + 6.0) / 3.0);
+        assert_eq!((engine_series_min_u32(ids), engine_series_max_u32(ids)), (1.0, u32::MAX as f64));
+        assert_eq!(engine_series_sum_u8(codes), 304.0);
+        assert_eq!(engine_series_mean_u8(codes), 76.0);
+        assert_eq!((engine_series_min_u8(codes), engine_series_max_u8(codes)), (0.0, 200.0));
+
+        let empty = engine_create_series_u8(&[]);
+        assert_eq!(engine_series_sum_u8(empty), 0.0);
+        assert!(engine_series_mean_u8(empty).is_nan() && engine_series_min_u8(empty).is_nan());
+        engine_free_series_u8(ids);
+        assert_eq!(engine_series_len_u32(ids), 3);
+        engine_free_series_u32(ids);
+        engine_free_series_u8(codes);
+        assert_eq!((engine_series_len_u32(ids), engine_series_ptr_u8(codes)), (0, 0));
+        assert_eq!(engine_series_sum_u32(ids), 0.0);
+        assert!(engine_series_max_u32(ids).is_nan());
+    }
+
+    #[test]
+    fn minmax_with_index_keeps_first_occurrence_across_chunks() {
+        let id = engine_chunked_create_f64();
+        assert!(engine_chunked_append_f64(id, &[3.0, f64::NAN, 1.0]));
+        assert!(engine_chunked_append_f64(id, &[5.0, 1.0, 5.0]));
+        assert_eq!(*engine_series_minmax_with_index_f64(id), [1.0, 2.0, 5.0, 3.0]);
+        let all_null = engine_series_minmax_with_index_f64(engine_create_series_f64(&[f64::NAN]));
+        assert!(all_null.iter().all(|v| v.is_nan()));
+        assert!(engine_series_minmax_with_index_f64(u32::MAX - 1).iter().all(|v| v.is_nan()));
+    }
+
+    #[test]
+    fn valid_index_skips_null_chunks_at_either_end() {
+        let id = engine_chunked_create_f64();
+        assert!(engine_chunked_append_f64(id, &[f64::NAN, f64::NAN]));
+        assert!(engine_chunked_append_f64(id, &[f64::NAN, 4.0, f64::NAN, 6.0]));
+        assert!(engine_chunked_append_f64(id, &[f64::NAN]));
+        assert_eq!(engine_series_first_valid_index_f64(id), 3);
+        assert_eq!(engine_series_last_valid_index_f64(id), 5);
+        let all_null = engine_create_series_f64(&[f64::NAN, f64::NAN]);
+        assert_eq!(engine_series_first_valid_index_f64(all_null), u32::MAX);
+        assert_eq!(engine_series_last_valid_index_f64(all_null), u32::MAX);
+        assert_eq!(engine_series_first_valid_index_f64(u32::MAX - 1), u32::MAX);
+    }
+
+    #[test]
+    fn agg_multi_returns_requested_aggregates_in_bit_order() {
+        use crate::statistics::{AGG_COUNT, AGG_MAX, AGG_MEAN};
+        let id = engine_chunked_create_f64();
+        assert!(engine_chunked_append_f64(id, &[1.0, f64::NAN, 2.0]));
+        assert!(engine_chunked_append_f64(id, &[3.0, 6.0]));
+        let mask = (1 << AGG_VAR) | (1 << AGG_MAX) | (1 << AGG_COUNT) | (1 << AGG_SUM);
+        assert_eq!(engine_series_agg_multi_f64(id, mask), [12.0, 4.0, 6.0, 14.0 / 3.0]);
+        let single = engine_series_agg_multi_f64(engine_create_series_f64(&[5.0]), (1 << AGG_MEAN) | (1 << AGG_VAR));
+        assert_eq!(single[0], 5.0);
+        assert!(single[1].is_nan());
+        let empty = engine_series_agg_multi_f64(engine_create_series_f64(&[]), (1 << AGG_SUM) | (1 << AGG_MEAN));
+        assert_eq!(format!("{:?}", empty), "[0.0, NaN]");
+        assert!(engine_series_agg_multi_f64(id, 0).is_empty());
+        assert!(engine_series_agg_multi_f64(u32::MAX - 1, mask).is_empty());
+    }
+
+    #[test]
+    fn many_series_agg_yields_nan_for_non_float_ids() {
+        use crate::core::engine_create_series_i64;
+        use crate::statistics::{AGG_MAX, AGG_MEAN};
+        let a = engine_create_series_f64(&[1.0, f64::NAN, 5.0]);
+        let b = engine_create_series_f64(&[-2.0]);
+        let ints = engine_create_series_i64(&[7]);
+        let means = engine_many_series_agg_f64(&[a, b, ints, u32::MAX - 1], AGG_MEAN);
+        assert_eq!(format!("{:?}", means), "[3.0, -2.0, NaN, NaN]");
+        assert_eq!(engine_many_series_agg_f64(&[b, a], AGG_MAX), [-2.0, 5.0]);
+        assert!(engine_many_series_agg_f64(&[a], 99)[0].is_nan());
+        assert!(engine_many_series_agg_f64(&[], AGG_SUM).is_empty());
+    }
+
+    #[test]
+    fn scatter_writes_points_or_nothing() {
+        let id = engine_create_series_f64(&[0.0, 1.0, 2.0, 3.0]);
+        let copy = engine_series_scatter_f64(id, &[3, 0, 3], &[9.0, f64::NAN, 7.0], 0);
+        assert_eq!(format!("{:?}", engine_series_to_vec_f64(copy)), "[NaN, 1.0, 2.0, 7.0]");
+        assert_eq!(engine_series_to_vec_f64(id), vec![0.0, 1.0, 2.0, 3.0]);
+        assert_eq!(engine_series_scatter_f64(id, &[1], &[5.0], 1), id);
+        assert_eq!(engine_series_to_vec_f64(id), vec![0.0, 5.0, 2.0, 3.0]);
+        assert_eq!(engine_series_scatter_f64(id, &[0, 4], &[1.0, 1.0], 1), u32::MAX);
+        assert_eq!(engine_series_scatter_f64(id, &[0, 1], &[1.0], 1), u32::MAX);
+        assert_eq!(engine_series_to_vec_f64(id), vec![0.0, 5.0, 2.0, 3.0]);
+        assert_eq!(engine_series_scatter_f64(u32::MAX - 1, &[], &[], 0), u32::MAX);
+    }
+
+    #[test]
+    fn share_of_total_skips_nulls_in_the_total() {
+        let id = engine_chunked_create_f64();
+        assert!(engine_chunked_append_f64(id, &[1.0, f64::NAN]));
+        assert!(engine_chunked_append_f64(id, &[3.0]));
+        assert_eq!(format!("{:?}", engine_series_to_vec_f64(engine_share_of_total_f64(id))), "[0.25, NaN, 0.75]");
+        assert!(engine_series_to_vec_f64(engine_share_of_total_f64(engine_create_series_f64(&[]))).is_empty());
+        assert_eq!(engine_share_of_total_f64(u32::MAX - 1), u32::MAX);
+    }
+
+    #[test]
+    fn lag_matrix_shifts_like_pandas() {
+        let id = engine_create_series_f64(&[1.0, 2.0, 3.0]);
+        let ids = engine_lag_matrix_f64(id, &[2, 0, 1, 5]);
+        let columns: Vec<String> = ids.iter().map(|&id| format!("{:?}", engine_series_to_vec_f64(id))).collect();
+        assert_eq!(columns, ["[NaN, NaN, 1.0]", "[1.0, 2.0, 3.0]", "[NaN, 1.0, 2.0]", "[NaN, NaN, NaN]"]);
+        assert!(engine_lag_matrix_f64(id, &[]).is_empty());
+        assert!(engine_lag_matrix_f64(u32::MAX - 1, &[1]).is_empty());
     }
-    cnt
 }
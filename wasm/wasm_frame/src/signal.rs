@@ -0,0 +1,227 @@
+//! Convolution and smoothing filters
+//!
+//! Discrete convolution with an arbitrary kernel plus Gaussian and
+//! Savitzky-Golay smoothing, following numpy / scipy conventions so results
+//! can be compared with the Python reference. Nulls are not skipped: a NaN
+//! spreads to every output it contributes to.
+
+use wasm_bindgen::prelude::*;
+use crate::core::{f64_values, ENGINE};
+use crate::profiling::{profile, series_bytes};
+
+// Convolution modes, as numpy's `convolve`
+pub const CONVOLVE_FULL: u8 = 0;
+pub const CONVOLVE_SAME: u8 = 1;
+pub const CONVOLVE_VALID: u8 = 2;
+
+/// Full discrete convolution, `n + m - 1` values
+fn convolve_full(values: &[f64], kernel: &[f64]) -> Vec<f64> {
+    let mut out = vec![0.0; values.len() + kernel.len() - 1];
+    for (i, &v) in values.iter().enumerate() {
+        for (j, &k) in kernel.iter().enumerate() {
+            out[i + j] += v * k;
+        }
+    }
+    out
+}
+
+/// Convolve a float64 series with `kernel` as numpy's `convolve`: `mode`
+/// 0 = full (`n + m - 1` values), 1 = same (`max(n, m)` values, centered),
+/// 2 = valid (`max(n, m) - min(n, m) + 1` values where the two fully
+/// overlap). A normalized kernel such as `[0.25, 0.5, 0.25]` gives a
+/// weighted moving average.
+///
+/// Returns a new float64 series id, or u32::MAX if the id is unknown, the
+/// series or kernel is empty or `mode` is unknown.
+#[wasm_bindgen]
+pub fn engine_convolve_f64(series_id: u32, kernel: &[f64], mode: u8) -> u32 {
+    let _prof = profile("engine_convolve_f64", || series_bytes(series_id));
+    let values = match unsafe { f64_values(series_id) } {
+        Some(values) if !values.is_empty() && !kernel.is_empty() && mode <= CONVOLVE_VALID => values.to_vec(),
+        _ => {
+            engine_log!(warn, "engine_convolve_f64: unknown or empty series, empty kernel or bad mode series_id={} mode={}", series_id, mode);
+            return u32::MAX;
+        }
+    };
+    let full = convolve_full(&values, kernel);
+    let (n, m) = (values.len(), kernel.len());
+    let (start, len) = match mode {
+        CONVOLVE_FULL => (0, full.len()),
+        CONVOLVE_SAME => ((n.min(m) - 1) / 2, n.max(m)),
+        _ => (n.min(m) - 1, n.max(m) - n.min(m) + 1),
+    };
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&full[start..start + len]))
+}
+
+/// Index into `0..n` reflecting at the edges (`d c b a | a b c d | d c b a`),
+/// scipy.ndimage's default `reflect` mode
+fn reflect(i: isize, n: usize) -> usize {
+    let period = 2 * n as isize;
+    let i = i.rem_euclid(period);
+    if i < n as isize { i as usize } else { (period - 1 - i) as usize }
+}
+
+/// Gaussian smoothing with standard deviation `sigma` (in rows), as
+/// scipy's `ndimage.gaussian_filter1d` defaults: the kernel is truncated at
+/// 4 sigma and normalized, and the series is reflected at its edges so the
+/// output keeps its length. The kernel radius is capped at the series
+/// length, so very large sigmas do not allocate beyond the input.
+///
+/// Returns a new float64 series id, or u32::MAX if the id is unknown, the
+/// series is empty or `sigma` is not positive.
+#[wasm_bindgen]
+pub fn engine_gaussian_smooth_f64(series_id: u32, sigma: f64) -> u32 {
+    let _prof = profile("engine_gaussian_smooth_f64", || series_bytes(series_id));
+    let values = match unsafe { f64_values(series_id) } {
+        Some(values) if !values.is_empty() && sigma > 0.0 && sigma.is_finite() => values.to_vec(),
+        _ => {
+            engine_log!(warn, "engine_gaussian_smooth_f64: unknown or empty series or invalid sigma series_id={} sigma={}", series_id, sigma);
+            return u32::MAX;
+        }
+    };
+    let n = values.len();
+    let radius = ((4.0 * sigma + 0.5) as usize).min(n) as isize;
+    let weights: Vec<f64> = (-radius..=radius).map(|x| (-0.5 * (x as f64 / sigma).powi(2)).exp()).collect();
+    let total: f64 = weights.iter().sum();
+    let out: Vec<f64> = (0..n as isize)
+        .map(|row| {
+            let acc: f64 = weights.iter().zip(-radius..=radius).map(|(w, d)| w * values[reflect(row + d, n)]).sum();
+            acc / total
+        })
+        .collect();
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&out))
+}
+
+/// Coefficients `c[0..=order]` of the least-squares polynomial through
+/// `(x, y)` points, by the normal equations with partial pivoting; None if
+/// they are singular
+fn polyfit(xs: &[f64], ys: &[f64], order: usize) -> Option<Vec<f64>> {
+    let k = order + 1;
+    // Augmented normal equations [X^T X | X^T y]
+    let mut a = vec![vec![0.0; k + 1]; k];
+    for (&x, &y) in xs.iter().zip(ys) {
+        let powers: Vec<f64> = (0..k).map(|p| x.powi(p as i32)).collect();
+        for r in 0..k {
+            for c in 0..k {
+                a[r][c] += powers[r] * powers[c];
+            }
+            a[r][k] += powers[r] * y;
+        }
+    }
+    for col in 0..k {
+        let pivot = (col..k).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col] == 0.0 {
+            return None;
+        }
+        a.swap(col, pivot);
+        let pivot_row = a[col].clone();
+        for (r, row) in a.iter_mut().enumerate() {
+            if r != col {
+                let f = row[col] / pivot_row[col];
+                for (x, p) in row.iter_mut().zip(&pivot_row).skip(col) {
+                    *x -= f * p;
+                }
+            }
+        }
+    }
+    Some((0..k).map(|r| a[r][k] / a[r][r]).collect())
+}
+
+fn polyval(coeffs: &[f64], x: f64) -> f64 {
+    coeffs.iter().rev().fold(0.0, |acc, &c| acc * x + c)
+}
+
+/// Savitzky-Golay smoothing as scipy's `signal.savgol_filter` defaults:
+/// each value is replaced by a least-squares polynomial of degree
+/// `polyorder` fitted to the `window` rows centered on it, and the first
+/// and last `window / 2` rows take the polynomial fitted to the first and
+/// last full window (`mode="interp"`). Smooths noise while keeping peak
+/// shapes better than a moving average.
+///
+/// Returns a new float64 series id, or u32::MAX if the id is unknown or
+/// `window` is not odd, larger than `polyorder` and at most the length.
+#[wasm_bindgen]
+pub fn engine_savgol_f64(series_id: u32, window: u32, polyorder: u32) -> u32 {
+    let _prof = profile("engine_savgol_f64", || series_bytes(series_id));
+    let w = window as usize;
+    let values = match unsafe { f64_values(series_id) } {
+        Some(values) if w % 2 == 1 && polyorder < window && w <= values.len() => values.to_vec(),
+        _ => {
+            engine_log!(warn, "engine_savgol_f64: unknown series or invalid window series_id={} window={} polyorder={}", series_id, window, polyorder);
+            return u32::MAX;
+        }
+    };
+    let half = w / 2;
+    let offsets: Vec<f64> = (0..w).map(|i| i as f64 - half as f64).collect();
+    // Smoothed center value = fitted constant term: a fixed linear filter,
+    // found by fitting each unit impulse
+    let filter: Vec<f64> = (0..w)
+        .map(|i| {
+            let impulse: Vec<f64> = (0..w).map(|j| (i == j) as u8 as f64).collect();
+            polyfit(&offsets, &impulse, polyorder as usize).map_or(f64::NAN, |c| c[0])
+        })
+        .collect();
+    let n = values.len();
+    let mut out = vec![f64::NAN; n];
+    for row in half..n - half {
+        out[row] = filter.iter().zip(&values[row - half..=row + half]).map(|(f, v)| f * v).sum();
+    }
+    for (edge_start, rows) in [(0, 0..half), (n - w, n - half..n)] {
+        if let Some(coeffs) = polyfit(&offsets, &values[edge_start..edge_start + w], polyorder as usize) {
+            for row in rows {
+                out[row] = polyval(&coeffs, row as f64 - (edge_start + half) as f64);
+            }
+        }
+    }
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::engine_create_series_f64;
+    use crate::series::engine_series_to_vec_f64;
+
+    fn close(a: &[f64], b: &[f64]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(x, y)| (x - y).abs() < 1e-12)
+    }
+
+    #[test]
+    fn convolution_modes_match_numpy() {
+        let id = engine_create_series_f64(&[1.0, 2.0, 3.0]);
+        let kernel = [0.0, 1.0, 0.5];
+        assert_eq!(engine_series_to_vec_f64(engine_convolve_f64(id, &kernel, CONVOLVE_FULL)), [0.0, 1.0, 2.5, 4.0, 1.5]);
+        assert_eq!(engine_series_to_vec_f64(engine_convolve_f64(id, &kernel, CONVOLVE_SAME)), [1.0, 2.5, 4.0]);
+        assert_eq!(engine_series_to_vec_f64(engine_convolve_f64(id, &kernel, CONVOLVE_VALID)), [2.5]);
+        assert_eq!(engine_convolve_f64(id, &[], CONVOLVE_FULL), u32::MAX);
+        assert_eq!(engine_convolve_f64(id, &kernel, 3), u32::MAX);
+    }
+
+    #[test]
+    fn gaussian_smoothing_keeps_levels_and_reflects_edges() {
+        assert_eq!((reflect(-1, 4), reflect(-2, 4), reflect(4, 4), reflect(9, 4)), (0, 1, 3, 1));
+        let flat = engine_series_to_vec_f64(engine_gaussian_smooth_f64(engine_create_series_f64(&[2.0; 6]), 1.5));
+        assert!(close(&flat, &[2.0; 6]));
+        let impulse = engine_series_to_vec_f64(engine_gaussian_smooth_f64(engine_create_series_f64(&[0.0, 0.0, 1.0, 0.0, 0.0]), 1.0));
+        let total: f64 = (-4..=4).map(|x: i32| (-0.5 * (x * x) as f64).exp()).sum();
+        assert!((impulse[2] - 1.0 / total).abs() < 1e-12);
+        assert!((impulse[1] - impulse[3]).abs() < 1e-12 && impulse[1] < impulse[2]);
+        assert_eq!(engine_gaussian_smooth_f64(engine_create_series_f64(&[1.0]), 0.0), u32::MAX);
+    }
+
+    #[test]
+    fn savgol_reproduces_polynomials_and_known_coefficients() {
+        let squares: Vec<f64> = (0..7).map(|x| (x * x) as f64 - 3.0 * x as f64).collect();
+        let smoothed = engine_series_to_vec_f64(engine_savgol_f64(engine_create_series_f64(&squares), 5, 2));
+        assert!(smoothed.iter().zip(&squares).all(|(a, b)| (a - b).abs() < 1e-9));
+        // The window-5, order-2 filter is [-3, 12, 17, 12, -3] / 35
+        let mut impulse = [0.0; 9];
+        impulse[4] = 1.0;
+        let response = engine_series_to_vec_f64(engine_savgol_f64(engine_create_series_f64(&impulse), 5, 2));
+        assert!(close(&response[2..7], &[-3.0, 12.0, 17.0, 12.0, -3.0].map(|c| c / 35.0)));
+        let id = engine_create_series_f64(&impulse);
+        assert_eq!(engine_savgol_f64(id, 4, 2), u32::MAX);
+        assert_eq!(engine_savgol_f64(id, 3, 3), u32::MAX);
+        assert_eq!(engine_savgol_f64(id, 11, 2), u32::MAX);
+    }
+}
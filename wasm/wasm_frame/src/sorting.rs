@@ -5,13 +5,21 @@
 
 use std::cmp::Ordering;
 use wasm_bindgen::prelude::*;
-use crate::core::ENGINE;
+use crate::core::{destination, ENGINE};
+use crate::error::set_last_error;
+use crate::groupby::key_codes;
+use crate::parallel::sort_indices_by;
+use crate::profiling::{profile, series_bytes};
+use crate::tasks::{cancel_requested, cancelled, CancelScope};
 
 // Engine-based sorting functions
 
-/// Sort values (float64) ascending/descending, nulls last flag applies to NaN
+/// Sort values (float64) ascending/descending, nulls last flag applies to NaN.
+/// Returns u32::MAX if the series is unknown or empty, or the sort is cancelled.
 #[wasm_bindgen]
 pub fn engine_sort_values_f64(series_id: u32, ascending: u8, nulls_last: u8) -> u32 {
+    let _prof = profile("engine_sort_values_f64", || series_bytes(series_id));
+    let _cancel = CancelScope::enter();
     let (src_ptr, src_len) = ENGINE.with(|cell| {
         let eng = cell.borrow();
         if let Some((ptr, len)) = eng.series_store.get(&series_id) {
@@ -21,6 +29,7 @@ pub fn engine_sort_values_f64(series_id: u32, ascending: u8, nulls_last: u8) ->
         }
     });
     if src_ptr.is_null() || src_len == 0 {
+        engine_log!(warn, "engine_sort_values_f64: unknown or empty series series_id={}", series_id);
         return u32::MAX;
     }
 
@@ -32,37 +41,105 @@ pub fn engine_sort_values_f64(series_id: u32, ascending: u8, nulls_last: u8) ->
         }
     }
 
-    let idx = sort_single_column_f64(&values, ascending != 0, nulls_last != 0);
+    let idx = match order_f64(&values, ascending != 0, nulls_last != 0) {
+        Some(idx) => idx,
+        None => return cancelled("engine_sort_values_f64", u32::MAX),
+    };
     let mut sorted: Vec<f64> = Vec::with_capacity(idx.len());
     for i in idx {
         sorted.push(values[i]);
     }
 
-    ENGINE.with(|cell| {
-        let mut eng = cell.borrow_mut();
-        let id = eng.next_series_id;
-        eng.next_series_id = eng.next_series_id.wrapping_add(1);
-        let len = sorted.len();
-        let dst_ptr = unsafe {
-            let layout = std::alloc::Layout::from_size_align(
-                len * std::mem::size_of::<f64>(),
-                std::mem::align_of::<f64>(),
-            )
-            .unwrap();
-            let raw = std::alloc::alloc(layout) as *mut f64;
-            if !raw.is_null() && len > 0 {
-                std::ptr::copy_nonoverlapping(sorted.as_ptr(), raw, len);
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&sorted))
+}
+
+/// Sort a registered float64 series in place, with the same ordering as
+/// `engine_sort_values_f64` but without allocating a new series. A buffer
+/// shared with clones or views is copied first so they keep their order.
+/// Returns `series_id`, or u32::MAX if the series is unknown (or chunked).
+#[wasm_bindgen]
+pub fn engine_sort_values_f64_inplace(series_id: u32, ascending: u8, nulls_last: u8) -> u32 {
+    let _prof = profile("engine_sort_values_f64_inplace", || series_bytes(series_id));
+    let target = ENGINE.with(|cell| cell.borrow_mut().make_mut_f64(series_id));
+    let (ptr, len) = match target {
+        Ok(Some(buffer)) => buffer,
+        Ok(None) => {
+            engine_log!(warn, "engine_sort_values_f64_inplace: unknown series series_id={}", series_id);
+            return u32::MAX;
+        }
+        Err(e) => {
+            set_last_error(e);
+            return u32::MAX;
+        }
+    };
+    if len > 0 {
+        let values = unsafe { std::slice::from_raw_parts_mut(ptr, len) };
+        let (asc, nl) = (ascending != 0, nulls_last != 0);
+        values.sort_by(|a, b| compare_values_f64(*a, *b, asc, nl));
+    }
+    series_id
+}
+
+/// Sort all columns of a frame by the key columns in `by_json` (JSON array
+/// of names, compared in order; the sort is stable). `ascending` holds one
+/// flag per key (missing flags mean ascending); nulls are placed per
+/// `nulls_last` as in `engine_sort_two_columns_indices_f64`. Returns a new
+/// frame, or u32::MAX if the frame or a key column is unknown or the sort is
+/// cancelled.
+#[wasm_bindgen]
+pub fn engine_frame_sort(frame_id: u32, by_json: &str, ascending: &[u8], nulls_last: u8) -> u32 {
+    let by: Vec<String> = serde_json::from_str(by_json).unwrap_or_default();
+    let _prof = profile("engine_frame_sort", || {
+        ENGINE.with(|cell| cell.borrow().frames.get(&frame_id).map(|f| f.columns.len()).unwrap_or(0))
+    });
+    let _cancel = CancelScope::enter();
+    // Key columns as f64 (i32 values convert exactly; the i32::MIN null becomes NaN)
+    let keys: Option<Vec<Vec<f64>>> = ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        let frame = eng.frames.get(&frame_id)?;
+        by.iter()
+            .map(|name| {
+                let id = frame.column(name)?;
+                if let Some(values) = eng.f64_values(id) {
+                    Some(values.to_vec())
+                } else {
+                    let &(ptr, len) = eng.series_store_i32.get(&id)?;
+                    Some(unsafe { std::slice::from_raw_parts(ptr, len) }.iter().map(|&v| if v == i32::MIN { f64::NAN } else { v as f64 }).collect())
+                }
+            })
+            .collect()
+    });
+    let keys = match keys {
+        Some(keys) if !keys.is_empty() => keys,
+        _ => {
+            engine_log!(warn, "engine_frame_sort: unknown frame or key column frame_id={} by={}", frame_id, by_json);
+            return u32::MAX;
+        }
+    };
+    let nrows = keys[0].len();
+    let nulls_last = nulls_last != 0;
+    let idx = sort_indices_by(nrows, |a, b| {
+        for (k, values) in keys.iter().enumerate() {
+            let asc = ascending.get(k).is_none_or(|&flag| flag != 0);
+            let ord = compare_values_f64(values[a], values[b], asc, nulls_last);
+            if ord != Ordering::Equal {
+                return ord;
             }
-            raw
-        };
-        eng.series_store.insert(id, (dst_ptr, len));
-        id
-    })
+        }
+        Ordering::Equal
+    });
+    let idx = match idx {
+        Some(idx) => idx,
+        None => return cancelled("engine_frame_sort", u32::MAX),
+    };
+    ENGINE.with(|cell| cell.borrow_mut().take_frame_rows(frame_id, &idx).unwrap_or(u32::MAX))
 }
 
 /// Return sort indices (float64) for a registered series (no materialization)
 #[wasm_bindgen]
 pub fn engine_sort_indices_f64(series_id: u32, ascending: u8, nulls_last: u8) -> Box<[u32]> {
+    let _prof = profile("engine_sort_indices_f64", || series_bytes(series_id));
+    let _cancel = CancelScope::enter();
     let (src_ptr, src_len) = ENGINE.with(|cell| {
         let eng = cell.borrow();
         if let Some((ptr, len)) = eng.series_store.get(&series_id) { (*ptr, *len) } else { (std::ptr::null_mut(), 0) }
@@ -76,7 +153,10 @@ pub fn engine_sort_indices_f64(series_id: u32, ascending: u8, nulls_last: u8) ->
     unsafe {
         for i in 0..src_len { values.push(*src_ptr.add(i)); }
     }
-    let idx = sort_single_column_f64(&values, ascending != 0, nulls_last != 0);
+    let idx = match order_f64(&values, ascending != 0, nulls_last != 0) {
+        Some(idx) => idx,
+        None => return cancelled("engine_sort_indices_f64", Box::new([])),
+    };
     let idx_u32: Vec<u32> = idx.into_iter().map(|i| i as u32).collect();
     idx_u32.into_boxed_slice()
 }
@@ -84,6 +164,8 @@ pub fn engine_sort_indices_f64(series_id: u32, ascending: u8, nulls_last: u8) ->
 /// Return sort indices by two registered f64 series (provided as two series ids)
 #[wasm_bindgen]
 pub fn engine_sort_two_columns_indices_f64(series1_id: u32, series2_id: u32, asc1: u8, asc2: u8, nulls_last: u8) -> Box<[u32]> {
+    let _prof = profile("engine_sort_two_columns_indices_f64", || series_bytes(series1_id) + series_bytes(series2_id));
+    let _cancel = CancelScope::enter();
     let (ptr1, len1) = ENGINE.with(|cell| {
         let eng = cell.borrow();
         if let Some((p, l)) = eng.series_store.get(&series1_id) { (*p, *l) } else { (std::ptr::null_mut(), 0) }
@@ -98,7 +180,10 @@ pub fn engine_sort_two_columns_indices_f64(series1_id: u32, series2_id: u32, asc
     unsafe {
         for i in 0..len1 { col1.push(*ptr1.add(i)); col2.push(*ptr2.add(i)); }
     }
-    let idx = sort_two_columns_f64(&col1, &col2, asc1, asc2, nulls_last);
+    let idx = match order_two_columns_f64(&col1, &col2, asc1, asc2, nulls_last) {
+        Some(idx) => idx,
+        None => return cancelled("engine_sort_two_columns_indices_f64", Box::new([])),
+    };
     let idx_u32: Vec<u32> = idx.into_iter().map(|i| i as u32).collect();
     idx_u32.into_boxed_slice()
 }
@@ -106,6 +191,8 @@ pub fn engine_sort_two_columns_indices_f64(series1_id: u32, series2_id: u32, asc
 /// Return sort indices (int32) for a registered i32 series
 #[wasm_bindgen]
 pub fn engine_sort_indices_i32(series_id: u32, ascending: u8, nulls_last: u8) -> Box<[u32]> {
+    let _prof = profile("engine_sort_indices_i32", || series_bytes(series_id));
+    let _cancel = CancelScope::enter();
     let (src_ptr, src_len) = ENGINE.with(|cell| {
         let eng = cell.borrow();
         if let Some((ptr, len)) = eng.series_store_i32.get(&series_id) { (*ptr, *len) } else { (std::ptr::null_mut(), 0) }
@@ -113,7 +200,10 @@ pub fn engine_sort_indices_i32(series_id: u32, ascending: u8, nulls_last: u8) ->
     if src_ptr.is_null() || src_len == 0 { return Box::new([]); }
     let mut values: Vec<i32> = Vec::with_capacity(src_len);
     unsafe { for i in 0..src_len { values.push(*src_ptr.add(i)); } }
-    let idx = sort_single_column_i32(&values, ascending != 0, nulls_last != 0);
+    let idx = match order_i32(&values, ascending != 0, nulls_last != 0) {
+        Some(idx) => idx,
+        None => return cancelled("engine_sort_indices_i32", Box::new([])),
+    };
     let idx_u32: Vec<u32> = idx.into_iter().map(|i| i as u32).collect();
     idx_u32.into_boxed_slice()
 }
@@ -121,6 +211,8 @@ pub fn engine_sort_indices_i32(series_id: u32, ascending: u8, nulls_last: u8) ->
 /// Return sort indices by two registered i32 series
 #[wasm_bindgen]
 pub fn engine_sort_two_columns_indices_i32(series1_id: u32, series2_id: u32, asc1: u8, asc2: u8, nulls_last: u8) -> Box<[u32]> {
+    let _prof = profile("engine_sort_two_columns_indices_i32", || series_bytes(series1_id) + series_bytes(series2_id));
+    let _cancel = CancelScope::enter();
     let (ptr1, len1) = ENGINE.with(|cell| {
         let eng = cell.borrow();
         if let Some((p, l)) = eng.series_store_i32.get(&series1_id) { (*p, *l) } else { (std::ptr::null_mut(), 0) }
@@ -133,11 +225,78 @@ pub fn engine_sort_two_columns_indices_i32(series1_id: u32, series2_id: u32, asc
     let mut col1: Vec<i32> = Vec::with_capacity(len1);
     let mut col2: Vec<i32> = Vec::with_capacity(len1);
     unsafe { for i in 0..len1 { col1.push(*ptr1.add(i)); col2.push(*ptr2.add(i)); } }
-    let idx = sort_two_columns_i32(&col1, &col2, asc1, asc2, nulls_last);
+    let idx = match order_two_columns_i32(&col1, &col2, asc1, asc2, nulls_last) {
+        Some(idx) => idx,
+        None => return cancelled("engine_sort_two_columns_indices_i32", Box::new([])),
+    };
     let idx_u32: Vec<u32> = idx.into_iter().map(|i| i as u32).collect();
     idx_u32.into_boxed_slice()
 }
 
+/// Stable multi-key argsort by pre-factorized code columns (group-code
+/// series, see `groupby::key_codes`, e.g. int32 categorical codes), the
+/// fastest path for sorting by categorical columns: one counting-sort pass
+/// per key, least significant key first, with no comparisons. A column
+/// whose codes are sparse (max code well above the row count) falls back to
+/// a stable comparison sort for its pass. `ascending` holds one flag per
+/// column; nulls sort last in either direction and ties keep row order.
+/// Returns the row indices, or an empty array if an id is unknown, the
+/// lengths differ, `ascending` does not have one flag per column or the
+/// sort is cancelled.
+#[wasm_bindgen]
+pub fn engine_sort_by_codes(codes_series_ids: &[u32], ascending: &[u8]) -> Box<[u32]> {
+    let _prof = profile("engine_sort_by_codes", || codes_series_ids.iter().map(|&id| series_bytes(id)).sum());
+    let _cancel = CancelScope::enter();
+    let columns: Option<Vec<Vec<u32>>> = codes_series_ids.iter().map(|&id| key_codes(id)).collect();
+    let columns = match columns {
+        Some(columns)
+            if !columns.is_empty()
+                && ascending.len() == columns.len()
+                && columns.iter().all(|c| c.len() == columns[0].len()) =>
+        {
+            columns
+        }
+        _ => {
+            engine_log!(warn, "engine_sort_by_codes: unknown series or length mismatch columns={} ascending={}", codes_series_ids.len(), ascending.len());
+            return Box::new([]);
+        }
+    };
+
+    let n = columns[0].len();
+    let mut order: Vec<u32> = (0..n as u32).collect();
+    let mut next: Vec<u32> = vec![0; n];
+    for (codes, &asc) in columns.iter().zip(ascending).rev() {
+        if cancel_requested() {
+            return cancelled("engine_sort_by_codes", Box::new([]));
+        }
+        let max = codes.iter().copied().filter(|&c| c != u32::MAX).max().unwrap_or(0) as usize;
+        // Bucket per code with nulls in the last bucket
+        let bucket = |code: u32| match code {
+            u32::MAX => max + 1,
+            code if asc != 0 => code as usize,
+            code => max - code as usize,
+        };
+        if max <= 4 * n + 1024 {
+            let mut starts = vec![0usize; max + 3];
+            for &code in codes.iter() {
+                starts[bucket(code) + 1] += 1;
+            }
+            for b in 1..starts.len() {
+                starts[b] += starts[b - 1];
+            }
+            for &row in order.iter() {
+                let b = bucket(codes[row as usize]);
+                next[starts[b]] = row;
+                starts[b] += 1;
+            }
+            std::mem::swap(&mut order, &mut next);
+        } else {
+            order.sort_by_key(|&row| bucket(codes[row as usize]));
+        }
+    }
+    order.into_boxed_slice()
+}
+
 // Direct sorting functions
 
 /// Sort indices by two float64 columns (most common multi-column case)
@@ -152,23 +311,21 @@ pub fn engine_sort_two_columns_indices_i32(series1_id: u32, series2_id: u32, asc
 /// # Returns
 /// * Array of indices sorted according to the multi-column criteria
 #[wasm_bindgen]
-pub fn sort_two_columns_f64(
-    col1: &[f64], 
-    col2: &[f64], 
-    asc1: u8, 
-    asc2: u8, 
-    nulls_last: u8
-) -> Vec<usize> {
+pub fn sort_two_columns_f64(col1: &[f64], col2: &[f64], asc1: u8, asc2: u8, nulls_last: u8) -> Vec<usize> {
     if col1.len() != col2.len() {
         return vec![];
     }
+    direct_sort(|| order_two_columns_f64(col1, col2, asc1, asc2, nulls_last))
+}
+
+/// Stable sort order by two columns (see `sort_two_columns_f64`); None if cancelled
+pub(crate) fn order_two_columns_f64(col1: &[f64], col2: &[f64], asc1: u8, asc2: u8, nulls_last: u8) -> Option<Vec<usize>> {
     
     let num_rows = col1.len();
-    let mut indices: Vec<usize> = (0..num_rows).collect();
     let nulls_last_bool = nulls_last == 1;
     
-    // Create a stable sort comparator
-    indices.sort_by(|&a, &b| {
+    // Stable sort comparator (runs are sorted separately and merged, see parallel.rs)
+    sort_indices_by(num_rows, |a, b| {
         // Compare first column
         let val_a1 = col1[a];
         let val_b1 = col1[b];
@@ -226,9 +383,7 @@ pub fn sort_two_columns_f64(
         } else {
             comparison2.reverse()
         }
-    });
-    
-    indices
+    })
 }
 
 /// Sort indices by two int32 columns
@@ -243,23 +398,21 @@ pub fn sort_two_columns_f64(
 /// # Returns
 /// * Array of indices sorted according to the multi-column criteria
 #[wasm_bindgen]
-pub fn sort_two_columns_i32(
-    col1: &[i32], 
-    col2: &[i32], 
-    asc1: u8, 
-    asc2: u8, 
-    nulls_last: u8
-) -> Vec<usize> {
+pub fn sort_two_columns_i32(col1: &[i32], col2: &[i32], asc1: u8, asc2: u8, nulls_last: u8) -> Vec<usize> {
     if col1.len() != col2.len() {
         return vec![];
     }
+    direct_sort(|| order_two_columns_i32(col1, col2, asc1, asc2, nulls_last))
+}
+
+/// Stable sort order by two columns (see `sort_two_columns_i32`); None if cancelled
+pub(crate) fn order_two_columns_i32(col1: &[i32], col2: &[i32], asc1: u8, asc2: u8, nulls_last: u8) -> Option<Vec<usize>> {
     
     let num_rows = col1.len();
-    let mut indices: Vec<usize> = (0..num_rows).collect();
     let nulls_last_bool = nulls_last == 1;
     
-    // Create a stable sort comparator
-    indices.sort_by(|&a, &b| {
+    // Stable sort comparator (runs are sorted separately and merged, see parallel.rs)
+    sort_indices_by(num_rows, |a, b| {
         // Compare first column
         let val_a1 = col1[a];
         let val_b1 = col1[b];
@@ -301,9 +454,7 @@ pub fn sort_two_columns_i32(
         } else {
             comparison2.reverse()
         }
-    });
-    
-    indices
+    })
 }
 
 /// Sort indices by a single float64 column (optimized single-column version)
@@ -317,39 +468,39 @@ pub fn sort_two_columns_i32(
 /// * Array of indices sorted according to the column
 #[wasm_bindgen]
 pub fn sort_single_column_f64(data: &[f64], ascending: bool, nulls_last: bool) -> Vec<usize> {
-    let mut indices: Vec<usize> = (0..data.len()).collect();
+    direct_sort(|| order_f64(data, ascending, nulls_last))
+}
+
+/// Stable sort order by one column (see `sort_single_column_f64`); None if cancelled
+pub(crate) fn order_f64(data: &[f64], ascending: bool, nulls_last: bool) -> Option<Vec<usize>> {
+    sort_indices_by(data.len(), |a, b| compare_values_f64(data[a], data[b], ascending, nulls_last))
+}
+
+/// Ordering of two float64 values for sorting, with NaN treated as null
+pub(crate) fn compare_values_f64(val_a: f64, val_b: f64, ascending: bool, nulls_last: bool) -> Ordering {
+    let a_is_nan = val_a.is_nan();
+    let b_is_nan = val_b.is_nan();
     
-    indices.sort_by(|&a, &b| {
-        let val_a = data[a];
-        let val_b = data[b];
-        
-        // Handle NaN values (treat as null)
-        let a_is_nan = val_a.is_nan();
-        let b_is_nan = val_b.is_nan();
-        
-        let comparison = match (a_is_nan, b_is_nan) {
-            (true, true) => Ordering::Equal,
-            (true, false) => if nulls_last { Ordering::Greater } else { Ordering::Less },
-            (false, true) => if nulls_last { Ordering::Less } else { Ordering::Greater },
-            (false, false) => {
-                if val_a < val_b {
-                    Ordering::Less
-                } else if val_a > val_b {
-                    Ordering::Greater
-                } else {
-                    Ordering::Equal
-                }
+    let comparison = match (a_is_nan, b_is_nan) {
+        (true, true) => Ordering::Equal,
+        (true, false) => if nulls_last { Ordering::Greater } else { Ordering::Less },
+        (false, true) => if nulls_last { Ordering::Less } else { Ordering::Greater },
+        (false, false) => {
+            if val_a < val_b {
+                Ordering::Less
+            } else if val_a > val_b {
+                Ordering::Greater
+            } else {
+                Ordering::Equal
             }
-        };
-        
-        if ascending {
-            comparison
-        } else {
-            comparison.reverse()
         }
-    });
+    };
     
-    indices
+    if ascending {
+        comparison
+    } else {
+        comparison.reverse()
+    }
 }
 
 /// Sort indices by a single int32 column (optimized single-column version)
@@ -363,9 +514,12 @@ pub fn sort_single_column_f64(data: &[f64], ascending: bool, nulls_last: bool) -
 /// * Array of indices sorted according to the column
 #[wasm_bindgen]
 pub fn sort_single_column_i32(data: &[i32], ascending: bool, nulls_last: bool) -> Vec<usize> {
-    let mut indices: Vec<usize> = (0..data.len()).collect();
-    
-    indices.sort_by(|&a, &b| {
+    direct_sort(|| order_i32(data, ascending, nulls_last))
+}
+
+/// Stable sort order by one column (see `sort_single_column_i32`); None if cancelled
+pub(crate) fn order_i32(data: &[i32], ascending: bool, nulls_last: bool) -> Option<Vec<usize>> {
+    sort_indices_by(data.len(), |a, b| {
         let val_a = data[a];
         let val_b = data[b];
         
@@ -385,7 +539,176 @@ pub fn sort_single_column_i32(data: &[i32], ascending: bool, nulls_last: bool) -
         } else {
             comparison.reverse()
         }
+    })
+}
+
+// Direct sorting functions returning compact u32 indices (Uint32Array)
+// instead of usize, for inputs shorter than 2^32 rows
+
+/// `sort_two_columns_f64` returning u32 indices
+#[wasm_bindgen]
+pub fn sort_two_columns_f64_u32(col1: &[f64], col2: &[f64], asc1: u8, asc2: u8, nulls_last: u8) -> Vec<u32> {
+    to_u32_indices(sort_two_columns_f64(col1, col2, asc1, asc2, nulls_last))
+}
+
+/// `sort_two_columns_i32` returning u32 indices
+#[wasm_bindgen]
+pub fn sort_two_columns_i32_u32(col1: &[i32], col2: &[i32], asc1: u8, asc2: u8, nulls_last: u8) -> Vec<u32> {
+    to_u32_indices(sort_two_columns_i32(col1, col2, asc1, asc2, nulls_last))
+}
+
+/// `sort_single_column_f64` returning u32 indices
+#[wasm_bindgen]
+pub fn sort_single_column_f64_u32(data: &[f64], ascending: bool, nulls_last: bool) -> Vec<u32> {
+    to_u32_indices(sort_single_column_f64(data, ascending, nulls_last))
+}
+
+/// `sort_single_column_i32` returning u32 indices
+#[wasm_bindgen]
+pub fn sort_single_column_i32_u32(data: &[i32], ascending: bool, nulls_last: bool) -> Vec<u32> {
+    to_u32_indices(sort_single_column_i32(data, ascending, nulls_last))
+}
+
+/// Run a direct sort as a polling kernel; empty if it is
+/// cancelled
+fn direct_sort(sort: impl FnOnce() -> Option<Vec<usize>>) -> Vec<usize> {
+    let _cancel = CancelScope::enter();
+    sort().unwrap_or_else(|| cancelled("sort", Vec::new()))
+}
+
+fn to_u32_indices(indices: Vec<usize>) -> Vec<u32> {
+    indices.into_iter().map(|i| i as u32).collect()
+}
+
+// Sort indices written into caller-provided WASM memory
+
+/// Write sort indices (float64) of a registered series into `dst_ptr`
+/// (capacity `dst_len` u32 values). Returns the number of indices written,
+/// or usize::MAX if the series is unknown, the destination is too small,
+/// misaligned or inside an engine buffer, or the sort is cancelled.
+#[wasm_bindgen]
+pub fn engine_sort_indices_into_f64(series_id: u32, ascending: u8, nulls_last: u8, dst_ptr: usize, dst_len: usize) -> usize {
+    let _prof = profile("engine_sort_indices_into_f64", || series_bytes(series_id));
+    let _cancel = CancelScope::enter();
+    let (src_ptr, src_len) = ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        if let Some((ptr, len)) = eng.series_store.get(&series_id) { (*ptr, *len) } else { (std::ptr::null_mut(), usize::MAX) }
+    });
+    if src_len == usize::MAX || dst_len < src_len { return usize::MAX; }
+    let dst = match destination::<u32>(dst_ptr, src_len) { Some(d) => d, None => return usize::MAX };
+    if src_ptr.is_null() || src_len == 0 { return 0; }
+    let values = unsafe { std::slice::from_raw_parts(src_ptr, src_len) };
+    let idx = match order_f64(values, ascending != 0, nulls_last != 0) {
+        Some(idx) => idx,
+        None => return cancelled("engine_sort_indices_into_f64", usize::MAX),
+    };
+    unsafe {
+        for (i, &ix) in idx.iter().enumerate() {
+            *dst.add(i) = ix as u32;
+        }
+    }
+    src_len
+}
+
+/// Write sort indices (int32) of a registered i32 series into `dst_ptr`
+/// (capacity `dst_len` u32 values). Returns the number of indices written,
+/// or usize::MAX on failure or cancellation.
+#[wasm_bindgen]
+pub fn engine_sort_indices_into_i32(series_id: u32, ascending: u8, nulls_last: u8, dst_ptr: usize, dst_len: usize) -> usize {
+    let _prof = profile("engine_sort_indices_into_i32", || series_bytes(series_id));
+    let _cancel = CancelScope::enter();
+    let (src_ptr, src_len) = ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        if let Some((ptr, len)) = eng.series_store_i32.get(&series_id) { (*ptr, *len) } else { (std::ptr::null_mut(), usize::MAX) }
     });
-    
-    indices
+    if src_len == usize::MAX || dst_len < src_len { return usize::MAX; }
+    let dst = match destination::<u32>(dst_ptr, src_len) { Some(d) => d, None => return usize::MAX };
+    if src_ptr.is_null() || src_len == 0 { return 0; }
+    let values = unsafe { std::slice::from_raw_parts(src_ptr, src_len) };
+    let idx = match order_i32(values, ascending != 0, nulls_last != 0) {
+        Some(idx) => idx,
+        None => return cancelled("engine_sort_indices_into_i32", usize::MAX),
+    };
+    unsafe {
+        for (i, &ix) in idx.iter().enumerate() {
+            *dst.add(i) = ix as u32;
+        }
+    }
+    src_len
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{engine_chunked_create_f64, engine_create_series_f64, engine_series_clone, engine_series_slice_view_f64};
+    use crate::series::engine_series_to_vec_f64;
+
+    fn floats(id: u32) -> String {
+        format!("{:?}", engine_series_to_vec_f64(id))
+    }
+
+    #[test]
+    fn inplace_sort_keeps_clones_and_views_unchanged() {
+        let series = engine_create_series_f64(&[3.0, f64::NAN, 1.0, 2.0]);
+        let clone = engine_series_clone(series);
+        let view = engine_series_slice_view_f64(series, 1, 2);
+        assert_eq!(engine_sort_values_f64_inplace(series, 1, 1), series);
+        assert_eq!(floats(series), "[1.0, 2.0, 3.0, NaN]");
+        assert_eq!(floats(clone), "[3.0, NaN, 1.0, 2.0]");
+        assert_eq!(floats(view), "[NaN, 1.0]");
+        // Same order as the allocating sort, which reverses nulls with the values
+        let sorted = engine_sort_values_f64(clone, 0, 0);
+        assert_eq!(engine_sort_values_f64_inplace(series, 0, 0), series);
+        assert_eq!(floats(series), floats(sorted));
+        assert_eq!(floats(series), "[3.0, 2.0, 1.0, NaN]");
+        assert_eq!(engine_sort_values_f64_inplace(engine_chunked_create_f64(), 1, 1), u32::MAX);
+        assert_eq!(engine_sort_values_f64_inplace(u32::MAX - 1, 1, 1), u32::MAX);
+    }
+
+    #[test]
+    fn u32_index_variants_match_the_usize_sorts() {
+        let floats = [2.0, f64::NAN, 1.0, 2.0];
+        let ints = [5, i32::MIN, 7, 5];
+        let widen = |indices: Vec<u32>| indices.into_iter().map(|i| i as usize).collect::<Vec<_>>();
+        assert_eq!(sort_single_column_f64_u32(&floats, true, true), [2, 0, 3, 1]);
+        assert_eq!(widen(sort_single_column_f64_u32(&floats, false, false)), sort_single_column_f64(&floats, false, false));
+        assert_eq!(sort_single_column_i32_u32(&ints, true, false), [1, 0, 3, 2]);
+        assert_eq!(sort_two_columns_f64_u32(&floats, &[1.0, 0.0, 0.0, 0.0], 1, 1, 1), [2, 3, 0, 1]);
+        assert_eq!(sort_two_columns_i32_u32(&ints, &[1, 0, 0, 0], 0, 1, 1), [1, 2, 3, 0]);
+        assert_eq!(widen(sort_two_columns_i32_u32(&ints, &[1, 0, 0, 0], 0, 1, 1)), sort_two_columns_i32(&ints, &[1, 0, 0, 0], 0, 1, 1));
+        assert!(sort_two_columns_f64_u32(&floats, &[0.0], 1, 1, 1).is_empty());
+        assert!(sort_single_column_i32_u32(&[], true, true).is_empty());
+    }
+
+    #[test]
+    fn code_sort_is_stable_with_nulls_last_in_both_directions() {
+        use crate::core::engine_create_series_u32;
+        let first = engine_create_series_u32(&[1, 0, u32::MAX, 1, 0]);
+        let second = engine_create_series_u32(&[0, 1, 0, 1, 0]);
+        assert_eq!(*engine_sort_by_codes(&[first, second], &[1, 0]), [1, 4, 3, 0, 2]);
+        assert_eq!(*engine_sort_by_codes(&[first, second], &[0, 1]), [0, 3, 4, 1, 2]);
+        assert_eq!(*engine_sort_by_codes(&[first], &[1]), [1, 4, 0, 3, 2]);
+        // Sparse codes take the comparison sort
+        let sparse = engine_create_series_u32(&[1_000_000, 5, 1_000_000, u32::MAX]);
+        assert_eq!(*engine_sort_by_codes(&[sparse], &[1]), [1, 0, 2, 3]);
+        assert_eq!(*engine_sort_by_codes(&[sparse], &[0]), [0, 2, 1, 3]);
+        assert!(engine_sort_by_codes(&[first, second], &[1]).is_empty());
+        assert!(engine_sort_by_codes(&[first, sparse], &[1, 1]).is_empty());
+        assert!(engine_sort_by_codes(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn frame_sort_orders_rows_by_each_key_in_turn() {
+        use crate::core::{engine_create_series_i32, engine_frame_column, engine_frame_create};
+        use crate::series::engine_series_to_vec_i32;
+        let a = engine_create_series_f64(&[2.0, f64::NAN, 1.0, 2.0]);
+        let b = engine_create_series_i32(&[1, 2, 3, 0]);
+        let frame = engine_frame_create(r#"["a","b"]"#, &[a, b]);
+        let sorted = engine_frame_sort(frame, r#"["a","b"]"#, &[1, 0], 1);
+        assert_eq!(floats(engine_frame_column(sorted, "a")), "[1.0, 2.0, 2.0, NaN]");
+        assert_eq!(engine_series_to_vec_i32(engine_frame_column(sorted, "b")), [3, 1, 0, 2]);
+        assert_eq!(engine_frame_sort(frame, r#"["z"]"#, &[], 1), u32::MAX);
+        assert_eq!(engine_frame_sort(frame, "[]", &[], 1), u32::MAX);
+    }
+}
+
@@ -5,6 +5,22 @@
 
 use wasm_bindgen::prelude::*;
 
+// Covariance and correlation matrices
+pub mod correlation;
+pub use correlation::*;
+
+// Principal component analysis
+pub mod pca;
+pub use pca::*;
+
+// Winsorization and trimmed means
+pub mod robust;
+pub use robust::*;
+
+// Hypothesis tests (t-test, chi-square)
+pub mod hypothesis;
+pub use hypothesis::*;
+
 /// High-performance vectorized sum
 #[wasm_bindgen]
 pub fn sum_f64(data: &[f64]) -> f64 {
@@ -62,3 +78,147 @@ pub fn max_f64(data: &[f64]) -> f64 {
 pub fn count_non_null_f64(data: &[f64]) -> usize {
     data.iter().filter(|&&x| !x.is_nan()).count()
 }
+
+/// Exact sum of i64 values (BigInt64Array), skipping i64::MIN nulls.
+/// Returns i64::MIN (null) if the sum overflows i64.
+#[wasm_bindgen]
+pub fn sum_i64(data: &[i64]) -> i64 {
+    let sum: i128 = data.iter().filter(|&&x| x != i64::MIN).map(|&x| x as i128).sum();
+    i64::try_from(sum).ok().filter(|&s| s != i64::MIN).unwrap_or_else(|| {
+        engine_log!(warn, "sum_i64: sum overflows i64");
+        i64::MIN
+    })
+}
+
+/// Mean of i64 values as f64 (exact sum, one rounding), skipping
+/// i64::MIN nulls; NaN if there are none
+#[wasm_bindgen]
+pub fn mean_i64_as_f64(data: &[i64]) -> f64 {
+    let (sum, count) = data
+        .iter()
+        .filter(|&&x| x != i64::MIN)
+        .fold((0i128, 0usize), |(sum, count), &x| (sum + x as i128, count + 1));
+    if count == 0 { f64::NAN } else { sum as f64 / count as f64 }
+}
+
+/// Minimum of i64 values, skipping i64::MIN nulls; i64::MIN if there are none
+#[wasm_bindgen]
+pub fn min_i64(data: &[i64]) -> i64 {
+    data.iter().copied().filter(|&x| x != i64::MIN).min().unwrap_or(i64::MIN)
+}
+
+/// Maximum of i64 values, skipping i64::MIN nulls; i64::MIN if there are none
+#[wasm_bindgen]
+pub fn max_i64(data: &[i64]) -> i64 {
+    data.iter().copied().filter(|&x| x != i64::MIN).max().unwrap_or(i64::MIN)
+}
+
+// Aggregation codes shared by the batch protocol, expressions and fused kernels
+pub(crate) const AGG_SUM: u8 = 0;
+pub(crate) const AGG_MEAN: u8 = 1;
+pub(crate) const AGG_COUNT: u8 = 2;
+pub(crate) const AGG_MIN: u8 = 3;
+pub(crate) const AGG_MAX: u8 = 4;
+pub(crate) const AGG_STD: u8 = 5;
+pub(crate) const AGG_VAR: u8 = 6;
+
+/// Single-pass accumulator for the aggregation codes above (NaN skipped;
+/// std/var are sample statistics via Welford's update)
+#[derive(Clone, Copy)]
+pub(crate) struct RunningStats {
+    count: usize,
+    sum: f64,
+    mean: f64,
+    m2: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Default for RunningStats {
+    fn default() -> Self {
+        RunningStats { count: 0, sum: 0.0, mean: 0.0, m2: 0.0, min: f64::INFINITY, max: f64::NEG_INFINITY }
+    }
+}
+
+impl RunningStats {
+    pub(crate) fn push(&mut self, v: f64) {
+        if v.is_nan() {
+            return;
+        }
+        self.count += 1;
+        self.sum += v;
+        let delta = v - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (v - self.mean);
+        if v < self.min { self.min = v; }
+        if v > self.max { self.max = v; }
+    }
+
+    /// Combine with stats accumulated over a disjoint set of values (Chan et al.)
+    pub(crate) fn merge(&mut self, other: &RunningStats) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = *other;
+            return;
+        }
+        let n = (self.count + other.count) as f64;
+        let delta = other.mean - self.mean;
+        self.m2 += other.m2 + delta * delta * self.count as f64 * other.count as f64 / n;
+        self.mean += delta * other.count as f64 / n;
+        self.count += other.count;
+        self.sum += other.sum;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    /// Final value for an aggregation code; NaN for unknown codes or empty input
+    pub(crate) fn finish(&self, agg: u8) -> f64 {
+        let n = self.count;
+        match agg {
+            AGG_SUM => self.sum,
+            AGG_COUNT => n as f64,
+            _ if n == 0 => f64::NAN,
+            AGG_MEAN => self.sum / n as f64,
+            AGG_MIN => self.min,
+            AGG_MAX => self.max,
+            AGG_VAR if n > 1 => self.m2 / (n - 1) as f64,
+            AGG_STD if n > 1 => (self.m2 / (n - 1) as f64).sqrt(),
+            _ => f64::NAN,
+        }
+    }
+}
+
+/// Quantile `q` in [0, 1] of ascending, null-free values with linear
+/// interpolation between neighbouring ranks (pandas' default); NaN if empty
+pub(crate) fn quantile_sorted(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    let pos = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let lo = pos.floor() as usize;
+    let hi = pos.ceil() as usize;
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (pos - lo as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn i64_reductions_are_exact_and_skip_nulls() {
+        let big = 1i64 << 60;
+        let data = [big + 1, i64::MIN, big + 3, -5];
+        assert_eq!(sum_i64(&data), 2 * big - 1);
+        assert_eq!(mean_i64_as_f64(&[big + 1, big + 3]), (big + 2) as f64);
+        assert_eq!(mean_i64_as_f64(&[1, i64::MIN, 2]), 1.5);
+        assert_eq!(min_i64(&data), -5);
+        assert_eq!(max_i64(&data), big + 3);
+        assert_eq!(sum_i64(&[i64::MAX, 1]), i64::MIN);
+        assert_eq!(sum_i64(&[i64::MAX, 1, -2]), i64::MAX - 1);
+        assert_eq!(sum_i64(&[i64::MIN + 1, -1]), i64::MIN);
+        assert!(mean_i64_as_f64(&[i64::MIN]).is_nan());
+        assert_eq!((min_i64(&[]), max_i64(&[i64::MIN])), (i64::MIN, i64::MIN));
+    }
+}
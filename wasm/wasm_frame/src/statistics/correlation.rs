@@ -0,0 +1,201 @@
+//! Covariance and correlation
+//!
+//! Sample covariance (ddof = 1) and Pearson correlation between float64
+//! series, as pandas' `DataFrame.cov()` / `corr()`: a pair of columns is
+//! computed over the rows where both are non-null (pairwise deletion) or
+//! over the rows where every column is non-null (listwise deletion), and is
+//! NaN when fewer than `min_periods` rows remain.
+
+use wasm_bindgen::prelude::*;
+use crate::core::{f64_values, F64Values};
+use crate::profiling::{profile, series_bytes};
+
+// Null handling across the columns of a matrix
+pub(crate) const NULLS_PAIRWISE: u8 = 0;
+pub(crate) const NULLS_LISTWISE: u8 = 1;
+
+/// Co-moments of a pair of columns (Welford's update)
+#[derive(Default)]
+struct CoMoments {
+    count: usize,
+    mean_x: f64,
+    mean_y: f64,
+    m2_x: f64,
+    m2_y: f64,
+    c_xy: f64,
+}
+
+impl CoMoments {
+    fn push(&mut self, x: f64, y: f64) {
+        self.count += 1;
+        let n = self.count as f64;
+        let dx = x - self.mean_x;
+        self.mean_x += dx / n;
+        let dy = y - self.mean_y;
+        self.mean_y += dy / n;
+        self.m2_x += dx * (x - self.mean_x);
+        self.m2_y += dy * (y - self.mean_y);
+        self.c_xy += dx * (y - self.mean_y);
+    }
+
+    fn cov(&self, min_periods: usize) -> f64 {
+        if self.count < min_periods.max(2) {
+            return f64::NAN;
+        }
+        self.c_xy / (self.count - 1) as f64
+    }
+
+    fn corr(&self, min_periods: usize) -> f64 {
+        if self.count < min_periods.max(1) {
+            return f64::NAN;
+        }
+        let divisor = (self.m2_x * self.m2_y).sqrt();
+        if divisor == 0.0 {
+            return f64::NAN;
+        }
+        (self.c_xy / divisor).clamp(-1.0, 1.0)
+    }
+}
+
+/// Co-moments of every pair (i, j) with i <= j, row-major over the upper
+/// triangle; None if an id is unknown, the lengths differ or `nulls` is
+/// unknown
+fn pair_moments(series_ids: &[u32], nulls: u8) -> Option<Vec<CoMoments>> {
+    if nulls > NULLS_LISTWISE {
+        return None;
+    }
+    let columns: Vec<F64Values> = series_ids.iter().map(|&id| unsafe { f64_values(id) }).collect::<Option<_>>()?;
+    let len = columns.first().map_or(0, |c| c.len());
+    if columns.iter().any(|c| c.len() != len) {
+        return None;
+    }
+    let columns: Vec<Vec<f64>> = columns.iter().map(|c| c.to_vec()).collect();
+    let k = columns.len();
+    let mut moments: Vec<CoMoments> = (0..k * (k + 1) / 2).map(|_| CoMoments::default()).collect();
+    for row in 0..len {
+        if nulls == NULLS_LISTWISE && columns.iter().any(|c| c[row].is_nan()) {
+            continue;
+        }
+        let mut pair = 0;
+        for i in 0..k {
+            for j in i..k {
+                let (x, y) = (columns[i][row], columns[j][row]);
+                if !x.is_nan() && !y.is_nan() {
+                    moments[pair].push(x, y);
+                }
+                pair += 1;
+            }
+        }
+    }
+    Some(moments)
+}
+
+/// Full k x k row-major matrix from upper-triangle pair values
+fn symmetric_matrix(k: usize, moments: &[CoMoments], f: impl Fn(&CoMoments) -> f64) -> Box<[f64]> {
+    let mut out = vec![f64::NAN; k * k];
+    let mut pair = 0;
+    for i in 0..k {
+        for j in i..k {
+            let v = f(&moments[pair]);
+            out[i * k + j] = v;
+            out[j * k + i] = v;
+            pair += 1;
+        }
+    }
+    out.into_boxed_slice()
+}
+
+/// Sample covariance matrix of float64 series (see the module docs):
+/// `nulls` is 0 for pairwise deletion (pandas' behaviour) or 1 for
+/// listwise deletion, and an entry is NaN with fewer than `min_periods`
+/// (at least 2) rows. Returns the k x k matrix row-major, or an empty
+/// array if an id is unknown, the lengths differ or `nulls` is unknown.
+#[wasm_bindgen]
+pub fn engine_cov_matrix_f64(series_ids: &[u32], min_periods: usize, nulls: u8) -> Box<[f64]> {
+    let _prof = profile("engine_cov_matrix_f64", || series_ids.iter().map(|&id| series_bytes(id)).sum());
+    match pair_moments(series_ids, nulls) {
+        Some(moments) => symmetric_matrix(series_ids.len(), &moments, |m| m.cov(min_periods)),
+        None => {
+            engine_log!(warn, "engine_cov_matrix_f64: unknown series, length mismatch or nulls={}", nulls);
+            Box::new([])
+        }
+    }
+}
+
+/// Pearson correlation matrix of float64 series, with `nulls` and
+/// `min_periods` as `engine_cov_matrix_f64` (entries NaN for constant
+/// columns). Returns the k x k matrix row-major, or an empty array if an id
+/// is unknown, the lengths differ or `nulls` is unknown.
+#[wasm_bindgen]
+pub fn engine_corr_matrix_f64(series_ids: &[u32], min_periods: usize, nulls: u8) -> Box<[f64]> {
+    let _prof = profile("engine_corr_matrix_f64", || series_ids.iter().map(|&id| series_bytes(id)).sum());
+    match pair_moments(series_ids, nulls) {
+        Some(moments) => symmetric_matrix(series_ids.len(), &moments, |m| m.corr(min_periods)),
+        None => {
+            engine_log!(warn, "engine_corr_matrix_f64: unknown series, length mismatch or nulls={}", nulls);
+            Box::new([])
+        }
+    }
+}
+
+/// Sample covariance of two float64 series over the rows where both are
+/// non-null, NaN with fewer than `min_periods` (at least 2) such rows or if
+/// an id is unknown or the lengths differ, as pandas' `Series.cov()`
+#[wasm_bindgen]
+pub fn engine_series_cov_f64(a_id: u32, b_id: u32, min_periods: usize) -> f64 {
+    let _prof = profile("engine_series_cov_f64", || series_bytes(a_id) + series_bytes(b_id));
+    pair_moments(&[a_id, b_id], NULLS_PAIRWISE).map_or(f64::NAN, |moments| moments[1].cov(min_periods))
+}
+
+/// Pearson correlation of two float64 series over the rows where both are
+/// non-null, NaN with fewer than `min_periods` such rows, a constant side,
+/// an unknown id or differing lengths, as pandas' `Series.corr()`
+#[wasm_bindgen]
+pub fn engine_series_corr_f64(a_id: u32, b_id: u32, min_periods: usize) -> f64 {
+    let _prof = profile("engine_series_corr_f64", || series_bytes(a_id) + series_bytes(b_id));
+    pair_moments(&[a_id, b_id], NULLS_PAIRWISE).map_or(f64::NAN, |moments| moments[1].corr(min_periods))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::engine_create_series_f64;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-12
+    }
+
+    fn columns() -> [u32; 3] {
+        [
+            engine_create_series_f64(&[1.0, 2.0, 3.0, 4.0, f64::NAN]),
+            engine_create_series_f64(&[2.0, 4.0, 6.0, 8.0, 10.0]),
+            engine_create_series_f64(&[f64::NAN, 1.0, 0.0, 1.0, 0.0]),
+        ]
+    }
+
+    #[test]
+    fn pairwise_and_listwise_deletion_differ() {
+        let ids = columns();
+        let pairwise = engine_cov_matrix_f64(&ids, 1, NULLS_PAIRWISE);
+        assert_eq!(pairwise.len(), 9);
+        assert!(close(pairwise[1], 10.0 / 3.0) && close(pairwise[3], 10.0 / 3.0));
+        assert!(close(pairwise[4], 10.0));
+        let listwise = engine_cov_matrix_f64(&ids, 1, NULLS_LISTWISE);
+        assert!(close(listwise[1], 2.0) && close(listwise[4], 4.0));
+        let corr = engine_corr_matrix_f64(&ids, 1, NULLS_PAIRWISE);
+        assert!(close(corr[0], 1.0) && close(corr[1], 1.0) && close(corr[2], 0.0));
+        assert!(engine_cov_matrix_f64(&ids, 1, 2).is_empty());
+        assert!(engine_corr_matrix_f64(&[ids[0], engine_create_series_f64(&[1.0])], 1, NULLS_PAIRWISE).is_empty());
+    }
+
+    #[test]
+    fn pairs_need_min_periods_and_spread() {
+        let [x, y, _] = columns();
+        assert!(close(engine_series_cov_f64(x, y, 4), 10.0 / 3.0));
+        assert!(engine_series_cov_f64(x, y, 5).is_nan());
+        assert!(close(engine_series_corr_f64(y, x, 0), 1.0));
+        let constant = engine_create_series_f64(&[3.0; 5]);
+        assert!(engine_series_corr_f64(x, constant, 1).is_nan());
+        assert!(engine_series_cov_f64(x, u32::MAX - 1, 1).is_nan());
+    }
+}
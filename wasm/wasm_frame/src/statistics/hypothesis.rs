@@ -0,0 +1,310 @@
+//! Hypothesis tests
+//!
+//! Two-sample t-tests and chi-square tests of independence with p-values,
+//! for lightweight A/B analysis without shipping a statistics library to
+//! the browser. The distribution functions are computed from the
+//! regularized incomplete beta and gamma functions (Numerical Recipes'
+//! series and continued fractions), accurate to about 1e-10.
+
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+use crate::core::f64_values;
+use crate::groupby::key_codes;
+use crate::profiling::{profile, series_bytes};
+use super::RunningStats;
+
+const MAX_ITERATIONS: usize = 300;
+const EPSILON: f64 = 1e-15;
+// Floor for continued-fraction denominators (avoids division by zero)
+const TINY: f64 = 1e-300;
+
+/// Natural log of the gamma function for x > 0 (Lanczos, g = 7)
+pub(crate) fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // Reflection formula
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = COEFFICIENTS[1..].iter().enumerate().fold(COEFFICIENTS[0], |sum, (i, &c)| sum + c / (x + i as f64 + 1.0));
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+/// Continued fraction for the incomplete beta function (modified Lentz)
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    let (qab, qap, qam) = (a + b, a + 1.0, a - 1.0);
+    let mut c = 1.0;
+    let mut d = 1.0 - qab * x / qap;
+    if d.abs() < TINY { d = TINY; }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..=MAX_ITERATIONS {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+        // Even step
+        let aa = m * (b - m) * x / ((qam + m2) * (a + m2));
+        d = 1.0 + aa * d;
+        if d.abs() < TINY { d = TINY; }
+        c = 1.0 + aa / c;
+        if c.abs() < TINY { c = TINY; }
+        d = 1.0 / d;
+        h *= d * c;
+        // Odd step
+        let aa = -(a + m) * (qab + m) * x / ((a + m2) * (qap + m2));
+        d = 1.0 + aa * d;
+        if d.abs() < TINY { d = TINY; }
+        c = 1.0 + aa / c;
+        if c.abs() < TINY { c = TINY; }
+        d = 1.0 / d;
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    h
+}
+
+/// Regularized incomplete beta function I_x(a, b) for a, b > 0
+pub(crate) fn beta_inc(a: f64, b: f64, x: f64) -> f64 {
+    if x.is_nan() || a.is_nan() || b.is_nan() {
+        return f64::NAN;
+    }
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    // The continued fraction converges fastest below the mean; use symmetry above it
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+/// Regularized upper incomplete gamma function Q(a, x) for a > 0, x >= 0
+pub(crate) fn gamma_inc_upper(a: f64, x: f64) -> f64 {
+    if x.is_nan() || a.is_nan() {
+        return f64::NAN;
+    }
+    if x <= 0.0 {
+        return 1.0;
+    }
+    let ln_front = a * x.ln() - x - ln_gamma(a);
+    if x < a + 1.0 {
+        // Series for the lower function P(a, x)
+        let (mut ap, mut sum) = (a, 1.0 / a);
+        let mut term = sum;
+        for _ in 0..MAX_ITERATIONS {
+            ap += 1.0;
+            term *= x / ap;
+            sum += term;
+            if term.abs() < sum.abs() * EPSILON {
+                break;
+            }
+        }
+        1.0 - sum * ln_front.exp()
+    } else {
+        // Continued fraction for Q(a, x) (modified Lentz)
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / TINY;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..=MAX_ITERATIONS {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < TINY { d = TINY; }
+            c = b + an / c;
+            if c.abs() < TINY { c = TINY; }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < EPSILON {
+                break;
+            }
+        }
+        ln_front.exp() * h
+    }
+}
+
+/// Two-sided p-value of Student's t statistic with `df` degrees of freedom
+pub(crate) fn t_two_sided_p(t: f64, df: f64) -> f64 {
+    if t.is_nan() || df.is_nan() || df <= 0.0 {
+        return f64::NAN;
+    }
+    if t.is_infinite() {
+        return 0.0;
+    }
+    beta_inc(df / 2.0, 0.5, df / (df + t * t))
+}
+
+/// Upper-tail p-value of a chi-square statistic with `df` degrees of freedom
+pub(crate) fn chi2_upper_p(stat: f64, df: f64) -> f64 {
+    if stat.is_nan() || df.is_nan() || df <= 0.0 {
+        return f64::NAN;
+    }
+    gamma_inc_upper(df / 2.0, stat / 2.0)
+}
+
+/// Two-sample t-test for equal means of two float64 series (nulls
+/// skipped), as scipy's `ttest_ind`: with `equal_var` = 1 Student's test
+/// on the pooled variance, otherwise Welch's test with
+/// Welch-Satterthwaite degrees of freedom.
+///
+/// Returns `[statistic, p_value, df]` with a two-sided p-value (NaN when
+/// either sample has fewer than two values or both have zero variance), or
+/// an empty array if an id is unknown.
+#[wasm_bindgen]
+pub fn engine_ttest_ind_f64(a_id: u32, b_id: u32, equal_var: u8) -> Box<[f64]> {
+    let _prof = profile("engine_ttest_ind_f64", || series_bytes(a_id) + series_bytes(b_id));
+    let (a, b) = match (unsafe { f64_values(a_id) }, unsafe { f64_values(b_id) }) {
+        (Some(a), Some(b)) => (a, b),
+        _ => {
+            engine_log!(warn, "engine_ttest_ind_f64: unknown series a_id={} b_id={}", a_id, b_id);
+            return Box::new([]);
+        }
+    };
+    let (mut sa, mut sb) = (RunningStats::default(), RunningStats::default());
+    a.iter().for_each(|v| sa.push(v));
+    b.iter().for_each(|v| sb.push(v));
+    let (n1, n2) = (sa.count as f64, sb.count as f64);
+    if sa.count < 2 || sb.count < 2 {
+        return Box::new([f64::NAN, f64::NAN, f64::NAN]);
+    }
+    let (m1, m2) = (sa.finish(super::AGG_MEAN), sb.finish(super::AGG_MEAN));
+    let (v1, v2) = (sa.finish(super::AGG_VAR), sb.finish(super::AGG_VAR));
+    let (se, df) = if equal_var != 0 {
+        let df = n1 + n2 - 2.0;
+        let pooled = ((n1 - 1.0) * v1 + (n2 - 1.0) * v2) / df;
+        ((pooled * (1.0 / n1 + 1.0 / n2)).sqrt(), df)
+    } else {
+        let (q1, q2) = (v1 / n1, v2 / n2);
+        let df = (q1 + q2).powi(2) / (q1 * q1 / (n1 - 1.0) + q2 * q2 / (n2 - 1.0));
+        ((q1 + q2).sqrt(), df)
+    };
+    let t = (m1 - m2) / se;
+    Box::new([t, t_two_sided_p(t, df), df])
+}
+
+/// Chi-square test of independence between two categorical group-code
+/// series (see `groupby::key_codes`) over their contingency table, as
+/// scipy's `chi2_contingency(correction=False)`; rows where either key is
+/// null are skipped.
+///
+/// Returns `[statistic, p_value, dof]` with `dof` = (rows - 1) * (columns - 1)
+/// of the table (p-value NaN when `dof` is 0), or an empty array if an id is
+/// not a group-code series or the lengths differ.
+#[wasm_bindgen]
+pub fn engine_chi2_crosstab(row_keys_id: u32, col_keys_id: u32) -> Box<[f64]> {
+    let _prof = profile("engine_chi2_crosstab", || series_bytes(row_keys_id) + series_bytes(col_keys_id));
+    let (rows, cols) = match (key_codes(row_keys_id), key_codes(col_keys_id)) {
+        (Some(rows), Some(cols)) if rows.len() == cols.len() => (rows, cols),
+        _ => {
+            engine_log!(warn, "engine_chi2_crosstab: unknown series or length mismatch row_keys_id={} col_keys_id={}", row_keys_id, col_keys_id);
+            return Box::new([]);
+        }
+    };
+    let mut cells: HashMap<(u32, u32), f64> = HashMap::new();
+    let mut row_totals: HashMap<u32, f64> = HashMap::new();
+    let mut col_totals: HashMap<u32, f64> = HashMap::new();
+    let mut total = 0.0;
+    for (&r, &c) in rows.iter().zip(cols.iter()) {
+        if r == u32::MAX || c == u32::MAX {
+            continue;
+        }
+        *cells.entry((r, c)).or_insert(0.0) += 1.0;
+        *row_totals.entry(r).or_insert(0.0) += 1.0;
+        *col_totals.entry(c).or_insert(0.0) += 1.0;
+        total += 1.0;
+    }
+    // Sum over every cell of the table, observed or not
+    let mut stat = 0.0;
+    for (r, &row_total) in row_totals.iter() {
+        for (c, &col_total) in col_totals.iter() {
+            let expected = row_total * col_total / total;
+            let observed = cells.get(&(*r, *c)).copied().unwrap_or(0.0);
+            stat += (observed - expected).powi(2) / expected;
+        }
+    }
+    let dof = (row_totals.len().saturating_sub(1) * col_totals.len().saturating_sub(1)) as f64;
+    Box::new([stat, chi2_upper_p(stat, dof), dof])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{engine_create_series_f64, engine_create_series_u32};
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn distribution_tails_match_closed_forms() {
+        // df = 1 is the Cauchy distribution, df = 2 has p = 1 - |t| / sqrt(t^2 + 2)
+        for t in [0.5, 1.0, 3.0, 12.0] {
+            assert!(close(t_two_sided_p(t, 1.0), 1.0 - 2.0 / std::f64::consts::PI * t.atan()));
+            assert!(close(t_two_sided_p(-t, 2.0), 1.0 - t / (t * t + 2.0).sqrt()));
+        }
+        // Chi-square with 2 degrees of freedom has p = exp(-x / 2)
+        for x in [0.1, 2.0, 9.0] {
+            assert!(close(chi2_upper_p(x, 2.0), (-x / 2.0).exp()));
+        }
+        assert!(close(ln_gamma(5.0), 24f64.ln()));
+        assert!(t_two_sided_p(1.0, 0.0).is_nan());
+    }
+
+    #[test]
+    fn ttest_pools_or_separates_variances() {
+        let a = engine_create_series_f64(&[1.0, 3.0, f64::NAN]);
+        let b = engine_create_series_f64(&[4.0, 6.0]);
+        let student = engine_ttest_ind_f64(a, b, 1);
+        let t = -3.0 / 2f64.sqrt();
+        assert!(close(student[0], t) && close(student[2], 2.0));
+        assert!(close(student[1], 1.0 - t.abs() / (t * t + 2.0).sqrt()));
+        let b = engine_create_series_f64(&[4.0, 6.0, 8.0]);
+        let welch = engine_ttest_ind_f64(a, b, 0);
+        assert!(close(welch[0], -4.0 / (7.0f64 / 3.0).sqrt()) && close(welch[2], 49.0 / 17.0));
+        assert!(welch[1] > 0.0 && welch[1] < 0.2);
+        let single = engine_create_series_f64(&[1.0]);
+        assert!(engine_ttest_ind_f64(a, single, 1).iter().all(|v| v.is_nan()));
+        assert!(engine_ttest_ind_f64(a, u32::MAX - 1, 1).is_empty());
+    }
+
+    #[test]
+    fn chi2_crosstab_tests_independence() {
+        // Row 0 spreads 10/10/20 over the columns and row 1 20/10/10
+        let mut rows = Vec::new();
+        let mut cols = Vec::new();
+        for (row, counts) in [[10, 10, 20], [20, 10, 10]].iter().enumerate() {
+            for (col, &count) in counts.iter().enumerate() {
+                rows.extend(std::iter::repeat_n(row as u32, count));
+                cols.extend(std::iter::repeat_n(col as u32, count));
+            }
+        }
+        rows.push(u32::MAX);
+        cols.push(0);
+        let result = engine_chi2_crosstab(engine_create_series_u32(&rows), engine_create_series_u32(&cols));
+        assert!(close(result[0], 20.0 / 3.0) && close(result[2], 2.0));
+        assert!(close(result[1], (-10.0f64 / 3.0).exp()));
+        let one_column = engine_create_series_u32(&vec![0; rows.len()]);
+        assert!(engine_chi2_crosstab(engine_create_series_u32(&rows), one_column)[1].is_nan());
+        assert!(engine_chi2_crosstab(one_column, engine_create_series_u32(&[0])).is_empty());
+    }
+}
@@ -0,0 +1,181 @@
+//! Principal component analysis
+//!
+//! PCA of a handful of float64 columns: the sample covariance matrix over
+//! the complete rows is diagonalized with the cyclic Jacobi method, which is
+//! exact enough and simple for the modest widths (tens of columns) frames
+//! are reduced from in the browser.
+
+use wasm_bindgen::prelude::*;
+use crate::core::{f64_values, ENGINE};
+use crate::profiling::{profile, series_bytes};
+
+const MAX_SWEEPS: usize = 100;
+
+/// Eigenvalues and eigenvectors of a symmetric n x n row-major matrix by
+/// cyclic Jacobi rotations; eigenvector `i` is column `i` of the returned
+/// row-major matrix
+pub(crate) fn symmetric_eigen(mut a: Vec<f64>, n: usize) -> (Vec<f64>, Vec<f64>) {
+    let mut v = vec![0.0; n * n];
+    for i in 0..n {
+        v[i * n + i] = 1.0;
+    }
+    let scale: f64 = a.iter().map(|x| x * x).sum();
+    for _ in 0..MAX_SWEEPS {
+        let off: f64 = (0..n).flat_map(|p| (p + 1..n).map(move |q| (p, q))).map(|(p, q)| a[p * n + q].powi(2)).sum();
+        if off <= scale * 1e-30 {
+            break;
+        }
+        for p in 0..n {
+            for q in p + 1..n {
+                let apq = a[p * n + q];
+                if apq == 0.0 {
+                    continue;
+                }
+                // Rotation by the angle that zeroes a[p][q]
+                let theta = (a[q * n + q] - a[p * n + p]) / (2.0 * apq);
+                let t = if theta >= 0.0 { 1.0 } else { -1.0 } / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for k in 0..n {
+                    let (akp, akq) = (a[k * n + p], a[k * n + q]);
+                    a[k * n + p] = c * akp - s * akq;
+                    a[k * n + q] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let (apk, aqk) = (a[p * n + k], a[q * n + k]);
+                    a[p * n + k] = c * apk - s * aqk;
+                    a[q * n + k] = s * apk + c * aqk;
+                }
+                for k in 0..n {
+                    let (vkp, vkq) = (v[k * n + p], v[k * n + q]);
+                    v[k * n + p] = c * vkp - s * vkq;
+                    v[k * n + q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+    ((0..n).map(|i| a[i * n + i]).collect(), v)
+}
+
+/// Project float64 columns onto their first `n_components` principal
+/// components. Columns are centered (not scaled; standardize them first
+/// for a correlation-based PCA) and only rows where every column is
+/// non-null are used; components are ordered by explained variance and
+/// signed so their largest loading is positive.
+///
+/// Returns one float64 series per component holding each row's score
+/// (NaN for rows with a null), or an empty array if an id is unknown, the
+/// lengths differ, `n_components` is 0 or exceeds the number of columns, or
+/// there are fewer than two complete rows.
+#[wasm_bindgen]
+pub fn engine_pca(series_ids: &[u32], n_components: usize) -> Box<[u32]> {
+    let _prof = profile("engine_pca", || series_ids.iter().map(|&id| series_bytes(id)).sum());
+    let k = series_ids.len();
+    let columns: Option<Vec<Vec<f64>>> = series_ids.iter().map(|&id| unsafe { f64_values(id) }.map(|values| values.to_vec())).collect();
+    let columns = match columns {
+        Some(columns) if n_components > 0 && n_components <= k && columns.iter().all(|c| c.len() == columns[0].len()) => columns,
+        _ => {
+            engine_log!(warn, "engine_pca: unknown series, length mismatch or n_components={} for {} columns", n_components, k);
+            return Box::new([]);
+        }
+    };
+    let len = columns[0].len();
+    let complete: Vec<bool> = (0..len).map(|row| columns.iter().all(|c| !c[row].is_nan())).collect();
+    let count = complete.iter().filter(|&&ok| ok).count();
+    if count < 2 {
+        engine_log!(warn, "engine_pca: fewer than two complete rows");
+        return Box::new([]);
+    }
+
+    let means: Vec<f64> = columns
+        .iter()
+        .map(|c| c.iter().zip(&complete).filter(|(_, &ok)| ok).map(|(v, _)| v).sum::<f64>() / count as f64)
+        .collect();
+    let mut cov = vec![0.0; k * k];
+    for row in (0..len).filter(|&row| complete[row]) {
+        for i in 0..k {
+            let di = columns[i][row] - means[i];
+            for j in i..k {
+                cov[i * k + j] += di * (columns[j][row] - means[j]);
+            }
+        }
+    }
+    for i in 0..k {
+        for j in i..k {
+            cov[i * k + j] /= (count - 1) as f64;
+            cov[j * k + i] = cov[i * k + j];
+        }
+    }
+
+    let (eigenvalues, vectors) = symmetric_eigen(cov, k);
+    let mut order: Vec<usize> = (0..k).collect();
+    order.sort_by(|&a, &b| eigenvalues[b].total_cmp(&eigenvalues[a]));
+    let components: Vec<Vec<f64>> = order[..n_components]
+        .iter()
+        .map(|&c| {
+            let mut loadings: Vec<f64> = (0..k).map(|i| vectors[i * k + c]).collect();
+            let largest = loadings.iter().copied().fold(0.0f64, |m, x| if x.abs() > m.abs() { x } else { m });
+            if largest < 0.0 {
+                loadings.iter_mut().for_each(|x| *x = -*x);
+            }
+            loadings
+        })
+        .collect();
+
+    let scores: Vec<Vec<f64>> = components
+        .iter()
+        .map(|loadings| {
+            (0..len)
+                .map(|row| {
+                    if !complete[row] {
+                        return f64::NAN;
+                    }
+                    (0..k).map(|i| (columns[i][row] - means[i]) * loadings[i]).sum()
+                })
+                .collect()
+        })
+        .collect();
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        scores.iter().map(|score| eng.register_series_f64(score)).collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::engine_create_series_f64;
+    use crate::series::engine_series_to_vec_f64;
+
+    #[test]
+    fn jacobi_diagonalizes_a_symmetric_matrix() {
+        let a = vec![4.0, 1.0, 2.0, 1.0, 3.0, 0.0, 2.0, 0.0, 5.0];
+        let (values, vectors) = symmetric_eigen(a.clone(), 3);
+        for (c, &lambda) in values.iter().enumerate() {
+            for i in 0..3 {
+                let av: f64 = (0..3).map(|j| a[i * 3 + j] * vectors[j * 3 + c]).sum();
+                assert!((av - lambda * vectors[i * 3 + c]).abs() < 1e-10);
+            }
+        }
+        assert!((values.iter().sum::<f64>() - 12.0).abs() < 1e-10);
+    }
+
+    #[test]
+    fn components_follow_the_direction_of_most_variance() {
+        let x = engine_create_series_f64(&[1.0, 2.0, 3.0, f64::NAN, 4.0]);
+        let y = engine_create_series_f64(&[2.0, 4.0, 6.0, 0.0, 8.0]);
+        let ids = engine_pca(&[x, y], 2);
+        assert_eq!(ids.len(), 2);
+        // Every point lies on y = 2x, so the first component carries all
+        // of the spread and the second none
+        let first = engine_series_to_vec_f64(ids[0]);
+        let expected = [-1.5, -0.5, 0.5, f64::NAN, 1.5].map(|v| v * 5f64.sqrt());
+        assert!(first[3].is_nan());
+        assert!(first.iter().zip(expected).filter(|(_, e)| !e.is_nan()).all(|(v, e)| (v - e).abs() < 1e-10));
+        let second = engine_series_to_vec_f64(ids[1]);
+        assert!(second.iter().filter(|v| !v.is_nan()).all(|v| v.abs() < 1e-10));
+        assert!(engine_pca(&[x, y], 0).is_empty());
+        assert!(engine_pca(&[x, y], 3).is_empty());
+        assert!(engine_pca(&[engine_create_series_f64(&[1.0, f64::NAN])], 1).is_empty());
+    }
+}
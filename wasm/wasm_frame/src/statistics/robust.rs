@@ -0,0 +1,89 @@
+//! Robust statistics
+//!
+//! Winsorization and trimmed means limit the influence of outliers in one
+//! call instead of a quantile, clip and mean round trip.
+
+use wasm_bindgen::prelude::*;
+use crate::core::{f64_values, ENGINE};
+use crate::profiling::{profile, series_bytes};
+use super::quantile_sorted;
+
+/// Non-null values of a float64 series in ascending order; None if the id
+/// is unknown
+fn sorted_non_null(series_id: u32) -> Option<Vec<f64>> {
+    let values = unsafe { f64_values(series_id) }?;
+    let mut sorted: Vec<f64> = values.iter().filter(|v| !v.is_nan()).collect();
+    sorted.sort_unstable_by(f64::total_cmp);
+    Some(sorted)
+}
+
+/// Copy of a float64 series with values clipped to its `lower_q` and
+/// `upper_q` quantiles (linear interpolation, as pandas' `quantile`), e.g.
+/// 0.05 and 0.95 to winsorize the outer 5% on each side; nulls stay null.
+/// Returns u32::MAX if the id is unknown or the quantiles are not
+/// 0 <= lower_q <= upper_q <= 1.
+#[wasm_bindgen]
+pub fn engine_winsorize_f64(series_id: u32, lower_q: f64, upper_q: f64) -> u32 {
+    let _prof = profile("engine_winsorize_f64", || series_bytes(series_id));
+    if !(0.0 <= lower_q && lower_q <= upper_q && upper_q <= 1.0) {
+        engine_log!(warn, "engine_winsorize_f64: invalid quantiles lower_q={} upper_q={}", lower_q, upper_q);
+        return u32::MAX;
+    }
+    let (values, sorted) = match (unsafe { f64_values(series_id) }, sorted_non_null(series_id)) {
+        (Some(values), Some(sorted)) => (values, sorted),
+        _ => return u32::MAX,
+    };
+    let (low, high) = (quantile_sorted(&sorted, lower_q), quantile_sorted(&sorted, upper_q));
+    let out: Vec<f64> = values.iter().map(|v| if v.is_nan() { v } else { v.clamp(low, high) }).collect();
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&out))
+}
+
+/// Mean of a float64 series after dropping `proportion` of its non-null
+/// values from each end (rounded down to whole values), as scipy's
+/// `trim_mean`; 0 gives the plain mean. NaN if the id is unknown, there are
+/// no non-null values or `proportion` is not in [0, 0.5).
+#[wasm_bindgen]
+pub fn engine_trimmed_mean_f64(series_id: u32, proportion: f64) -> f64 {
+    let _prof = profile("engine_trimmed_mean_f64", || series_bytes(series_id));
+    if !(0.0..0.5).contains(&proportion) {
+        return f64::NAN;
+    }
+    let sorted = match sorted_non_null(series_id) {
+        Some(sorted) if !sorted.is_empty() => sorted,
+        _ => return f64::NAN,
+    };
+    let cut = (proportion * sorted.len() as f64) as usize;
+    let kept = &sorted[cut..sorted.len() - cut];
+    kept.iter().sum::<f64>() / kept.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::engine_create_series_f64;
+    use crate::series::engine_series_to_vec_f64;
+
+    fn sample() -> u32 {
+        let mut values: Vec<f64> = (1..=10).map(f64::from).collect();
+        values.extend([100.0, f64::NAN]);
+        engine_create_series_f64(&values)
+    }
+
+    #[test]
+    fn winsorize_clips_to_the_quantiles() {
+        let clipped = engine_series_to_vec_f64(engine_winsorize_f64(sample(), 0.1, 0.9));
+        assert_eq!(clipped[..11], [2.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 10.0]);
+        assert!(clipped[11].is_nan());
+        assert_eq!(engine_winsorize_f64(sample(), 0.9, 0.1), u32::MAX);
+        assert_eq!(engine_winsorize_f64(sample(), 0.0, 1.5), u32::MAX);
+    }
+
+    #[test]
+    fn trimmed_mean_drops_whole_values_from_each_end() {
+        // 10% of 11 values rounds down to one from each end
+        assert_eq!(engine_trimmed_mean_f64(sample(), 0.1), 6.0);
+        assert_eq!(engine_trimmed_mean_f64(sample(), 0.0), 155.0 / 11.0);
+        assert!(engine_trimmed_mean_f64(sample(), 0.5).is_nan());
+        assert!(engine_trimmed_mean_f64(engine_create_series_f64(&[f64::NAN]), 0.1).is_nan());
+    }
+}
@@ -0,0 +1,566 @@
+//! Cooperative chunked execution for long-running operations
+//!
+//! Heavy operations (sort, groupby, join) can be started as tasks and driven
+//! incrementally from
+//! the UI thread: `engine_task_start_*` snapshots the inputs and returns a
+//! task id, each `engine_task_step(task_id, ms_budget)` call performs work
+//! until the time budget is spent and reports progress, and
+//! `engine_task_result` hands back the registered result series once done.
+//! Results are identical to the corresponding one-shot functions.
+//!
+//! Tasks can be abandoned with `engine_task_cancel`. For one-shot kernels
+//! there is a shared cancellation flag in WASM memory
+//! (`engine_cancel_flag_ptr`): a worker sharing the memory, or a callback
+//! running inside a kernel, can set it via `engine_request_cancel`, and
+//! polling kernels then stop early and return their usual failure value.
+//! A request raised before a kernel starts cancels it as well; the flag is
+//! cleared when the outermost polling kernel returns, so a request raised
+//! too late to be observed is not left over for the next operation.
+
+use std::cell::{Cell, RefCell};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering as AtomicOrdering};
+use wasm_bindgen::prelude::*;
+use crate::clock::now_ms;
+use crate::core::{f64_values, ENGINE};
+use crate::join::{register_join_indices, JoinTable};
+use crate::groupby::{output_keys, register_group_aggs, take_group_order, GroupOrdering};
+use crate::profiling::{profile, series_bytes};
+use crate::sorting::compare_values_f64;
+use crate::statistics::RunningStats;
+
+/// Rows handled between clock (and cancellation) checks
+pub(crate) const STEP_ROWS: usize = 4096;
+
+/// Non-zero when cancellation of the in-flight operation was requested
+#[cfg(not(test))]
+static CANCEL_FLAG: AtomicU32 = AtomicU32::new(0);
+
+// Unit tests run on parallel threads, each with its own engine, so each
+// gets its own flag as well
+#[cfg(test)]
+thread_local! {
+    static CANCEL_FLAG: &'static AtomicU32 = Box::leak(Box::new(AtomicU32::new(0)));
+}
+
+fn cancel_flag() -> &'static AtomicU32 {
+    #[cfg(not(test))]
+    {
+        &CANCEL_FLAG
+    }
+    #[cfg(test)]
+    {
+        CANCEL_FLAG.with(|flag| *flag)
+    }
+}
+
+/// Whether cancellation was requested (cheap enough to poll per chunk)
+pub(crate) fn cancel_requested() -> bool {
+    cancel_flag().load(AtomicOrdering::Relaxed) != 0
+}
+
+/// Consume a pending cancellation request, returning whether there was one
+pub(crate) fn take_cancel_request() -> bool {
+    cancel_flag().swap(0, AtomicOrdering::Relaxed) != 0
+}
+
+/// Drop any pending cancellation request
+pub(crate) fn clear_cancel_request() {
+    cancel_flag().store(0, AtomicOrdering::Relaxed);
+}
+
+thread_local! {
+    // Polling kernels currently running (kernels may call one another)
+    static KERNEL_DEPTH: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Held by a polling kernel while it runs; dropping the outermost scope
+/// clears the cancellation flag
+pub(crate) struct CancelScope(());
+
+impl CancelScope {
+    pub(crate) fn enter() -> Self {
+        KERNEL_DEPTH.with(|depth| depth.set(depth.get() + 1));
+        CancelScope(())
+    }
+}
+
+impl Drop for CancelScope {
+    fn drop(&mut self) {
+        let outermost = KERNEL_DEPTH.with(|depth| {
+            depth.set(depth.get() - 1);
+            depth.get() == 0
+        });
+        if outermost {
+            clear_cancel_request();
+        }
+    }
+}
+
+/// Consume the cancellation request observed by `kernel`, returning its
+/// failure value
+pub(crate) fn cancelled<T>(kernel: &str, failure: T) -> T {
+    take_cancel_request();
+    engine_log!(info, "{}: cancelled", kernel);
+    failure
+}
+
+/// Address of the u32 cancellation flag, for hosts that share WASM memory
+/// with a worker and set it directly (non-zero = cancel)
+#[wasm_bindgen]
+pub fn engine_cancel_flag_ptr() -> usize {
+    cancel_flag() as *const AtomicU32 as usize
+}
+
+/// Request cancellation of the in-flight operation
+#[wasm_bindgen]
+pub fn engine_request_cancel() {
+    cancel_flag().store(1, AtomicOrdering::Relaxed);
+}
+
+/// Clear a stale cancellation request (e.g. one raised after the operation finished)
+#[wasm_bindgen]
+pub fn engine_cancel_reset() {
+    clear_cancel_request();
+}
+
+/// Incremental stable merge sort: runs of `STEP_ROWS` are sorted first,
+/// then merged bottom-up with resumable merge cursors
+struct SortTask {
+    values: Vec<f64>,
+    ascending: bool,
+    nulls_last: bool,
+    idx: Vec<usize>,
+    buf: Vec<usize>,
+    /// Current merge width; 0 while still sorting runs
+    width: usize,
+    /// Next run start (run phase) or next output row (merge phase)
+    pos: usize,
+    /// Bounds of the pair being merged and the cursors into its halves
+    mid: usize,
+    end: usize,
+    left: usize,
+    right: usize,
+    done_work: usize,
+    done: bool,
+}
+
+impl SortTask {
+    fn new(values: Vec<f64>, ascending: bool, nulls_last: bool) -> Self {
+        let n = values.len();
+        SortTask {
+            values,
+            ascending,
+            nulls_last,
+            idx: (0..n).collect(),
+            buf: vec![0; n],
+            width: 0,
+            pos: 0,
+            mid: 0,
+            end: 0,
+            left: 0,
+            right: 0,
+            done_work: 0,
+            done: n == 0,
+        }
+    }
+
+    fn total_work(&self) -> usize {
+        let n = self.values.len();
+        let mut passes = 1;
+        let mut width = STEP_ROWS;
+        while width < n {
+            passes += 1;
+            width *= 2;
+        }
+        n * passes
+    }
+
+    fn less(&self, a: usize, b: usize) -> bool {
+        compare_values_f64(self.values[a], self.values[b], self.ascending, self.nulls_last) == Ordering::Less
+    }
+
+    /// Perform up to `STEP_ROWS` rows of work
+    fn step(&mut self) {
+        let n = self.values.len();
+        if self.width == 0 {
+            let end = (self.pos + STEP_ROWS).min(n);
+            let (values, asc, nl) = (&self.values, self.ascending, self.nulls_last);
+            self.idx[self.pos..end].sort_by(|&a, &b| compare_values_f64(values[a], values[b], asc, nl));
+            self.done_work += end - self.pos;
+            self.pos = end;
+            if end == n {
+                self.start_pass(STEP_ROWS);
+            }
+            return;
+        }
+
+        let stop = (self.pos + STEP_ROWS).min(n);
+        self.done_work += stop - self.pos;
+        while self.pos < stop {
+            // Ties take from the left half, keeping the sort stable
+            let take_right = self.left >= self.mid
+                || (self.right < self.end && self.less(self.idx[self.right], self.idx[self.left]));
+            if take_right {
+                self.buf[self.pos] = self.idx[self.right];
+                self.right += 1;
+            } else {
+                self.buf[self.pos] = self.idx[self.left];
+                self.left += 1;
+            }
+            self.pos += 1;
+            if self.pos == self.end {
+                self.start_pair(self.end);
+            }
+        }
+        if self.pos == n {
+            std::mem::swap(&mut self.idx, &mut self.buf);
+            self.start_pass(self.width * 2);
+        }
+    }
+
+    fn start_pass(&mut self, width: usize) {
+        self.width = width;
+        self.done = width >= self.values.len();
+        self.start_pair(0);
+    }
+
+    fn start_pair(&mut self, start: usize) {
+        let n = self.values.len();
+        self.pos = start;
+        self.left = start;
+        self.mid = (start + self.width).min(n);
+        self.end = (start + 2 * self.width).min(n);
+        self.right = self.mid;
+    }
+
+    fn finish(&self) -> Box<[u32]> {
+        let sorted: Vec<f64> = self.idx.iter().map(|&i| self.values[i]).collect();
+        let id = ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&sorted));
+        Box::new([id])
+    }
+}
+
+/// Incremental groupby accumulation over rows
+struct GroupByTask {
+    values: Vec<f64>,
+    keys: Vec<String>,
+    agg_mask: u32,
+    groups: HashMap<String, RunningStats>,
+    pos: usize,
+    // Taken when the task starts, so later groupby calls do not affect it
+    order: GroupOrdering,
+}
+
+impl GroupByTask {
+    fn step(&mut self) {
+        let end = (self.pos + STEP_ROWS).min(self.values.len());
+        for row in self.pos..end {
+            let v = self.values[row];
+            if v.is_nan() {
+                continue;
+            }
+            match self.groups.get_mut(&self.keys[row]) {
+                Some(stats) => stats.push(v),
+                None => {
+                    let mut stats = RunningStats::default();
+                    stats.push(v);
+                    self.groups.insert(self.keys[row].clone(), stats);
+                }
+            }
+        }
+        self.pos = end;
+    }
+
+    fn finish(&self) -> Box<[u32]> {
+        let ordered = output_keys(&self.order, self.groups.keys().cloned().collect(), &self.keys);
+        let stats: Vec<RunningStats> = ordered.iter().map(|k| self.groups.get(k).copied().unwrap_or_default()).collect();
+        register_group_aggs(&stats, self.agg_mask)
+    }
+}
+
+/// Incremental inner hash join: the right keys are hashed first, then the
+/// left keys probed, `STEP_ROWS` rows per step
+struct JoinTask {
+    left_keys: Vec<f64>,
+    right_keys: Vec<f64>,
+    table: JoinTable,
+    /// Rows handled so far, right rows first
+    pos: usize,
+    left: Vec<u32>,
+    right: Vec<u32>,
+}
+
+impl JoinTask {
+    fn total_work(&self) -> usize {
+        self.right_keys.len() + self.left_keys.len()
+    }
+
+    fn step(&mut self) {
+        let end = (self.pos + STEP_ROWS).min(self.total_work());
+        let built = self.right_keys.len();
+        for pos in self.pos..end {
+            if pos < built {
+                self.table.insert(pos as u32, self.right_keys[pos]);
+            } else {
+                let row = pos - built;
+                self.table.probe(row as u32, self.left_keys[row], &mut self.left, &mut self.right);
+            }
+        }
+        self.pos = end;
+    }
+
+    fn finish(&self) -> Box<[u32]> {
+        register_join_indices(&self.left, &self.right)
+    }
+}
+
+enum Task {
+    Sort(SortTask),
+    GroupBy(GroupByTask),
+    Join(JoinTask),
+}
+
+impl Task {
+    fn is_done(&self) -> bool {
+        match self {
+            Task::Sort(t) => t.done,
+            Task::GroupBy(t) => t.pos >= t.values.len(),
+            Task::Join(t) => t.pos >= t.total_work(),
+        }
+    }
+
+    fn progress(&self) -> f64 {
+        if self.is_done() {
+            return 1.0;
+        }
+        match self {
+            Task::Sort(t) => t.done_work as f64 / t.total_work() as f64,
+            Task::GroupBy(t) => t.pos as f64 / t.values.len() as f64,
+            Task::Join(t) => t.pos as f64 / t.total_work() as f64,
+        }
+    }
+
+    fn step(&mut self) {
+        match self {
+            Task::Sort(t) => t.step(),
+            Task::GroupBy(t) => t.step(),
+            Task::Join(t) => t.step(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct TaskState {
+    next_task_id: u32,
+    tasks: HashMap<u32, Task>,
+}
+
+thread_local! {
+    static TASKS: RefCell<TaskState> = RefCell::new(TaskState::default());
+}
+
+fn add_task(task: Task) -> u32 {
+    TASKS.with(|cell| {
+        let mut state = cell.borrow_mut();
+        let id = state.next_task_id;
+        state.next_task_id = state.next_task_id.wrapping_add(1);
+        state.tasks.insert(id, task);
+        engine_log!(debug, "task started task_id={} active={}", id, state.tasks.len());
+        id
+    })
+}
+
+fn snapshot_f64(series_id: u32) -> Option<Vec<f64>> {
+    unsafe { f64_values(series_id) }.map(|values| values.to_vec())
+}
+
+/// Start an incremental sort of a registered f64 series (same ordering as
+/// `engine_sort_values_f64`). Returns a task id, or u32::MAX if the series
+/// is unknown or empty.
+#[wasm_bindgen]
+pub fn engine_task_start_sort_f64(series_id: u32, ascending: u8, nulls_last: u8) -> u32 {
+    let _prof = profile("engine_task_start_sort_f64", || series_bytes(series_id));
+    match snapshot_f64(series_id) {
+        Some(values) if !values.is_empty() => {
+            add_task(Task::Sort(SortTask::new(values, ascending != 0, nulls_last != 0)))
+        }
+        _ => u32::MAX,
+    }
+}
+
+/// Start an incremental groupby multi-aggregation (same bit layout and
+/// results as `engine_groupby_multi_f64`, groups in the groupby order in
+/// effect when the task starts). Returns a task id, or u32::MAX if
+/// the series is unknown or the keys do not match its length.
+#[wasm_bindgen]
+pub fn engine_task_start_groupby_f64(series_id: u32, group_keys_json: &str, agg_mask: u32) -> u32 {
+    let _prof = profile("engine_task_start_groupby_f64", || series_bytes(series_id));
+    let order = take_group_order();
+    let keys: Vec<String> = serde_json::from_str(group_keys_json).unwrap_or_default();
+    match snapshot_f64(series_id) {
+        Some(values) if values.len() == keys.len() => add_task(Task::GroupBy(GroupByTask {
+            values,
+            keys,
+            agg_mask,
+            groups: HashMap::new(),
+            pos: 0,
+            order,
+        })),
+        _ => u32::MAX,
+    }
+}
+
+/// Start an incremental inner join of two float64 key series (same pairs as
+/// `engine_join_indices_f64`). The result is `[left_indices_id,
+/// right_indices_id]`. Returns a task id, or u32::MAX if an id is unknown
+/// or a table has more than u32::MAX rows.
+#[wasm_bindgen]
+pub fn engine_task_start_join_f64(left_keys_id: u32, right_keys_id: u32) -> u32 {
+    let _prof = profile("engine_task_start_join_f64", || series_bytes(left_keys_id) + series_bytes(right_keys_id));
+    match (snapshot_f64(left_keys_id), snapshot_f64(right_keys_id)) {
+        (Some(left_keys), Some(right_keys)) if left_keys.len() <= u32::MAX as usize && right_keys.len() <= u32::MAX as usize => {
+            add_task(Task::Join(JoinTask {
+                left_keys,
+                right_keys,
+                table: JoinTable::default(),
+                pos: 0,
+                left: Vec::new(),
+                right: Vec::new(),
+            }))
+        }
+        _ => u32::MAX,
+    }
+}
+
+/// Run a task for up to `ms_budget` milliseconds (at least one unit of work).
+/// Returns progress in [0, 1] (1 = finished), or -1 for an unknown task.
+/// If the cancellation flag is raised before or while stepping, the task is
+/// dropped and -1 is returned.
+#[wasm_bindgen]
+pub fn engine_task_step(task_id: u32, ms_budget: f64) -> f64 {
+    let _prof = profile("engine_task_step", || 0);
+    let _cancel = CancelScope::enter();
+    TASKS.with(|cell| {
+        let mut state = cell.borrow_mut();
+        let task = match state.tasks.get_mut(&task_id) {
+            Some(t) => t,
+            None => return -1.0,
+        };
+        let deadline = now_ms() + ms_budget;
+        while !task.is_done() {
+            if take_cancel_request() {
+                engine_log!(info, "engine_task_step: cancelled task_id={}", task_id);
+                state.tasks.remove(&task_id);
+                return -1.0;
+            }
+            task.step();
+            if now_ms() >= deadline {
+                break;
+            }
+        }
+        task.progress()
+    })
+}
+
+/// Cancel a task and release its buffers. Returns false if the task is unknown.
+#[wasm_bindgen]
+pub fn engine_task_cancel(task_id: u32) -> bool {
+    TASKS.with(|cell| cell.borrow_mut().tasks.remove(&task_id).is_some())
+}
+
+/// Current progress of a task in [0, 1], or -1 for an unknown task
+#[wasm_bindgen]
+pub fn engine_task_progress(task_id: u32) -> f64 {
+    TASKS.with(|cell| cell.borrow().tasks.get(&task_id).map(|t| t.progress()).unwrap_or(-1.0))
+}
+
+/// Take the result series ids of a finished task and release the task.
+/// Returns an empty array if the task is unknown or still running.
+#[wasm_bindgen]
+pub fn engine_task_result(task_id: u32) -> Box<[u32]> {
+    let task = TASKS.with(|cell| {
+        let mut state = cell.borrow_mut();
+        match state.tasks.get(&task_id) {
+            Some(t) if t.is_done() => state.tasks.remove(&task_id),
+            _ => None,
+        }
+    });
+    match task {
+        Some(Task::Sort(t)) => t.finish(),
+        Some(Task::GroupBy(t)) => t.finish(),
+        Some(Task::Join(t)) => t.finish(),
+        None => Box::new([]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{engine_chunked_append_f64, engine_chunked_create_f64, engine_create_series_f64};
+    use crate::expr::{engine_expr_col, engine_expr_collect};
+    use crate::join::engine_join_indices_f64;
+    use crate::parallel::sort_indices_by;
+    use crate::series::engine_series_to_vec_u32;
+    use crate::sorting::engine_sort_values_f64;
+
+    #[test]
+    fn request_before_start_cancels_the_next_kernel() {
+        let series = engine_create_series_f64(&[3.0, 1.0, 2.0]);
+        engine_request_cancel();
+        assert_eq!(engine_sort_values_f64(series, 1, 1), u32::MAX);
+        assert!(!cancel_requested());
+        assert_ne!(engine_sort_values_f64(series, 1, 1), u32::MAX);
+        engine_request_cancel();
+        assert_eq!(engine_expr_collect(engine_expr_col(series)), u32::MAX);
+        // The task is dropped, and the flag cleared for the next call
+        let task = engine_task_start_sort_f64(series, 1, 1);
+        engine_request_cancel();
+        assert_eq!(engine_task_step(task, 10.0), -1.0);
+        assert!(!cancel_requested());
+        assert_eq!(engine_task_progress(task), -1.0);
+    }
+
+    #[test]
+    fn join_task_matches_the_one_shot_join() {
+        let left = engine_create_series_f64(&[2.0, f64::NAN, 1.0, 2.0, 5.0]);
+        let right = engine_create_series_f64(&[2.0, -0.0, 2.0, 1.0, f64::NAN]);
+        let expected = engine_join_indices_f64(left, right);
+        let task = engine_task_start_join_f64(left, right);
+        while engine_task_step(task, 10.0) < 1.0 {}
+        let result = engine_task_result(task);
+        for (id, pairs) in [(expected[0], [0, 0, 2, 3, 3]), (expected[1], [0, 2, 3, 0, 2])] {
+            assert_eq!(engine_series_to_vec_u32(id), pairs);
+        }
+        assert_eq!(engine_series_to_vec_u32(result[0]), engine_series_to_vec_u32(expected[0]));
+        assert_eq!(engine_series_to_vec_u32(result[1]), engine_series_to_vec_u32(expected[1]));
+        assert_eq!(engine_task_start_join_f64(left, u32::MAX - 1), u32::MAX);
+    }
+
+    #[test]
+    fn sort_stops_when_cancelled() {
+        let len = 200_000;
+        let keys: Vec<u64> = (0..len as u64).map(|i| i.wrapping_mul(0x9E37_79B9_7F4A_7C15)).collect();
+        assert!(sort_indices_by(len, |a, b| keys[a].cmp(&keys[b])).is_some());
+        // Raised by a callback or worker while the caller runs; the sort
+        // itself leaves the flag for the caller to consume
+        engine_request_cancel();
+        assert!(sort_indices_by(len, |a, b| keys[a].cmp(&keys[b])).is_none());
+        assert!(take_cancel_request());
+    }
+
+    #[test]
+    fn expressions_and_tasks_read_chunked_series() {
+        let series = engine_chunked_create_f64();
+        assert!(engine_chunked_append_f64(series, &[3.0, 1.0]));
+        assert!(engine_chunked_append_f64(series, &[2.0]));
+        let collected = engine_expr_collect(engine_expr_col(series));
+        assert_eq!(unsafe { f64_values(collected) }.unwrap().to_vec(), [3.0, 1.0, 2.0]);
+
+        let task = engine_task_start_sort_f64(series, 1, 1);
+        while engine_task_step(task, 10.0) < 1.0 {}
+        let result = engine_task_result(task);
+        assert_eq!(unsafe { f64_values(result[0]) }.unwrap().to_vec(), [1.0, 2.0, 3.0]);
+    }
+}
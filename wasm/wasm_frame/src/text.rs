@@ -0,0 +1,365 @@
+//! Text analytics on string series: tokenization, n-gram counts and TF-IDF
+//!
+//! Strings are split into tokens by a delimiter: a literal string, or one of
+//! a small set of regex forms (see `Splitter`). Empty tokens are dropped and
+//! null rows have no tokens. Frequency tables list distinct tokens most
+//! frequent first (ties in order of first occurrence), as
+//! `engine_freq_table`. TF-IDF follows the defaults of scikit-learn's
+//! `TfidfVectorizer`.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use wasm_bindgen::prelude::*;
+use crate::core::{StrSeries, ENGINE};
+use crate::profiling::{profile, series_bytes};
+
+/// One item of a regex character class
+enum ClassItem {
+    Char(char),
+    Range(char, char),
+    /// `\s`, `\d` or `\w`, negated if upper case
+    Shorthand(char),
+}
+
+impl ClassItem {
+    fn matches(&self, c: char) -> bool {
+        match *self {
+            ClassItem::Char(x) => c == x,
+            ClassItem::Range(lo, hi) => (lo..=hi).contains(&c),
+            ClassItem::Shorthand(kind) => {
+                let hit = match kind.to_ascii_lowercase() {
+                    's' => c.is_whitespace(),
+                    'd' => c.is_numeric(),
+                    _ => c.is_alphanumeric() || c == '_',
+                };
+                hit != kind.is_ascii_uppercase()
+            }
+        }
+    }
+}
+
+/// How strings are split into tokens. A delimiter is matched literally
+/// unless it is one of the supported regex forms: a character class
+/// (`[,;]`, `[^a-z0-9]`, with ranges, `\`-escapes and the shorthands below)
+/// or a shorthand alone (`\s`, `\d`, `\w` or the negations `\S`, `\D`,
+/// `\W`), either optionally followed by `+`. An empty delimiter splits on
+/// runs of whitespace.
+enum Splitter {
+    Literal(String),
+    Class { items: Vec<ClassItem>, negated: bool },
+}
+
+impl Splitter {
+    /// None if a character class is malformed
+    fn parse(delimiter: &str) -> Option<Splitter> {
+        if delimiter.is_empty() {
+            return Some(Splitter::Class { items: vec![ClassItem::Shorthand('s')], negated: false });
+        }
+        // Empty tokens are dropped, so a repeated separator splits as a single one
+        let pattern = delimiter.strip_suffix('+').unwrap_or(delimiter);
+        let mut chars = pattern.chars();
+        if let (Some('\\'), Some(kind), None) = (chars.next(), chars.next(), chars.next()) {
+            if "sdwSDW".contains(kind) {
+                return Some(Splitter::Class { items: vec![ClassItem::Shorthand(kind)], negated: false });
+            }
+        }
+        let body = match pattern.strip_prefix('[').and_then(|p| p.strip_suffix(']')) {
+            Some(body) => body,
+            None => return Some(Splitter::Literal(delimiter.to_string())),
+        };
+        let (body, negated) = match body.strip_prefix('^') {
+            Some(body) => (body, true),
+            None => (body, false),
+        };
+        let mut items = Vec::new();
+        let mut chars = body.chars();
+        while let Some(c) = chars.next() {
+            let c = if c == '\\' {
+                let escaped = chars.next()?;
+                if "sdwSDW".contains(escaped) {
+                    items.push(ClassItem::Shorthand(escaped));
+                    continue;
+                }
+                escaped
+            } else {
+                c
+            };
+            // A '-' between two characters is a range, elsewhere a literal
+            let mut ahead = chars.clone();
+            match (ahead.next(), ahead.next()) {
+                (Some('-'), Some(hi)) if hi != '\\' => {
+                    if hi < c {
+                        return None;
+                    }
+                    items.push(ClassItem::Range(c, hi));
+                    chars = ahead;
+                }
+                _ => items.push(ClassItem::Char(c)),
+            }
+        }
+        if items.is_empty() {
+            return None;
+        }
+        Some(Splitter::Class { items, negated })
+    }
+
+    /// Non-empty tokens of `text`
+    fn split<'a>(&'a self, text: &'a str) -> Box<dyn Iterator<Item = &'a str> + 'a> {
+        match self {
+            Splitter::Literal(delimiter) => Box::new(text.split(delimiter.as_str()).filter(|t| !t.is_empty())),
+            Splitter::Class { items, negated } => Box::new(
+                text.split(move |c: char| items.iter().any(|item| item.matches(c)) != *negated).filter(|t| !t.is_empty()),
+            ),
+        }
+    }
+}
+
+/// Distinct `items` most frequent first (ties in order of first
+/// occurrence), with their counts
+fn frequency_table<'a>(items: impl Iterator<Item = Cow<'a, str>>) -> (StrSeries, Vec<u32>) {
+    // item -> (count, first occurrence)
+    let mut counts: HashMap<Cow<str>, (u32, usize)> = HashMap::new();
+    for (i, item) in items.enumerate() {
+        counts.entry(item).or_insert((0, i)).0 += 1;
+    }
+    let mut counts: Vec<(Cow<str>, (u32, usize))> = counts.into_iter().collect();
+    counts.sort_unstable_by(|a, b| b.1 .0.cmp(&a.1 .0).then(a.1 .1.cmp(&b.1 .1)));
+    let values = counts.iter().map(|(item, _)| Some(item.as_ref())).collect();
+    (values, counts.iter().map(|&(_, (count, _))| count).collect())
+}
+
+/// Register the frequency table `table` builds from a string series as
+/// `[values_id, counts_id]`, or an empty array if the id is not a string
+/// series
+fn register_frequency_table(name: &str, series_id: u32, table: impl FnOnce(&StrSeries) -> (StrSeries, Vec<u32>)) -> Box<[u32]> {
+    let table = ENGINE.with(|cell| cell.borrow().series_store_str.get(&series_id).map(|strings| table(strings)));
+    match table {
+        Some((values, counts)) => ENGINE.with(|cell| {
+            let mut eng = cell.borrow_mut();
+            Box::new([eng.register_series_str(values), eng.register_series_u32(&counts)])
+        }),
+        None => {
+            engine_log!(warn, "{}: unknown string series {}", name, series_id);
+            Box::new([])
+        }
+    }
+}
+
+/// Token frequency table of a string series: each string is split by
+/// `delimiter_or_regex` (a literal delimiter or a supported regex form, see
+/// `Splitter`; empty to split on whitespace), empty tokens are dropped and
+/// null rows skipped. Returns `[tokens_id, counts_id]`, the distinct tokens
+/// as strings (most frequent first, ties in order of first occurrence) and
+/// their counts as uint32, or an empty array if the id is not a string
+/// series or the character class is malformed.
+#[wasm_bindgen]
+pub fn engine_str_tokenize(series_id: u32, delimiter_or_regex: &str) -> Box<[u32]> {
+    let _prof = profile("engine_str_tokenize", || series_bytes(series_id));
+    let splitter = match Splitter::parse(delimiter_or_regex) {
+        Some(splitter) => splitter,
+        None => {
+            engine_log!(warn, "engine_str_tokenize: malformed character class {:?}", delimiter_or_regex);
+            return Box::new([]);
+        }
+    };
+    register_frequency_table("engine_str_tokenize", series_id, |strings| {
+        frequency_table(strings.iter().flatten().flat_map(|text| splitter.split(text)).map(Cow::Borrowed))
+    })
+}
+
+/// Word n-gram frequency table of a string series: each string is split on
+/// whitespace and every run of `n` consecutive words within it becomes an
+/// n-gram, its words joined by a single space (n-grams never span rows;
+/// strings with fewer than `n` words have none). Returns `[ngrams_id,
+/// counts_id]` as `engine_str_tokenize`, or an empty array if the id is not
+/// a string series or `n` is 0.
+#[wasm_bindgen]
+pub fn engine_ngram_counts(series_id: u32, n: u32) -> Box<[u32]> {
+    let _prof = profile("engine_ngram_counts", || series_bytes(series_id));
+    if n == 0 {
+        engine_log!(warn, "engine_ngram_counts: n must be positive");
+        return Box::new([]);
+    }
+    let n = n as usize;
+    register_frequency_table("engine_ngram_counts", series_id, |strings| {
+        frequency_table(strings.iter().flatten().flat_map(|text| {
+            let words: Vec<&str> = text.split_whitespace().collect();
+            let ngrams: Vec<Cow<str>> = words
+                .windows(n)
+                .map(|window| if n == 1 { Cow::Borrowed(window[0]) } else { Cow::Owned(window.join(" ")) })
+                .collect();
+            ngrams
+        }))
+    })
+}
+
+/// Lowercased words of `text` for TF-IDF: runs of at least two word
+/// characters (alphanumeric or `_`), as scikit-learn's default
+/// `token_pattern`
+fn tfidf_terms(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| word.chars().nth(1).is_some())
+        .map(str::to_string)
+        .collect()
+}
+
+/// TF-IDF document-term matrix of a string series, one document per row
+/// (null rows are empty documents), with the defaults of scikit-learn's
+/// `TfidfVectorizer`: terms are lowercased words of two or more word
+/// characters, weights are raw term counts times the smoothed inverse
+/// document frequency `ln((1 + n) / (1 + df)) + 1`, and each row is scaled
+/// to unit Euclidean length. With `max_features` > 0 only that many terms
+/// are kept, the most frequent across the whole series (ties
+/// alphabetically).
+///
+/// Returns the matrix in compressed sparse row form as `[indptr_id,
+/// indices_id, values_id, vocabulary_id]`: row `i` holds the column
+/// indices `indices[indptr[i]..indptr[i + 1]]` (ascending, uint32) with
+/// weights in `values` (float64), and `vocabulary` is the string series of
+/// terms by column index, in alphabetical order. An empty array if the id
+/// is not a string series.
+#[wasm_bindgen]
+pub fn engine_tfidf(series_id: u32, max_features: u32) -> Box<[u32]> {
+    let _prof = profile("engine_tfidf", || series_bytes(series_id));
+    let documents: Option<Vec<Vec<String>>> = ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        let strings = eng.series_store_str.get(&series_id)?;
+        Some(strings.iter().map(|text| text.map(tfidf_terms).unwrap_or_default()).collect())
+    });
+    let documents = match documents {
+        Some(documents) => documents,
+        None => {
+            engine_log!(warn, "engine_tfidf: unknown string series {}", series_id);
+            return Box::new([]);
+        }
+    };
+
+    // term -> (total count, document frequency)
+    let mut terms: HashMap<&str, (u64, u32)> = HashMap::new();
+    for document in &documents {
+        let mut seen: HashSet<&str> = HashSet::new();
+        for term in document {
+            let entry = terms.entry(term.as_str()).or_insert((0, 0));
+            entry.0 += 1;
+            if seen.insert(term.as_str()) {
+                entry.1 += 1;
+            }
+        }
+    }
+    let mut vocabulary: Vec<(&str, (u64, u32))> = terms.into_iter().collect();
+    if max_features > 0 && vocabulary.len() > max_features as usize {
+        vocabulary.sort_unstable_by(|a, b| b.1 .0.cmp(&a.1 .0).then(a.0.cmp(b.0)));
+        vocabulary.truncate(max_features as usize);
+    }
+    vocabulary.sort_unstable_by(|a, b| a.0.cmp(b.0));
+    let n = documents.len() as f64;
+    let idf: Vec<f64> = vocabulary.iter().map(|&(_, (_, df))| ((1.0 + n) / (1.0 + df as f64)).ln() + 1.0).collect();
+    let columns: HashMap<&str, u32> = vocabulary.iter().enumerate().map(|(col, &(term, _))| (term, col as u32)).collect();
+
+    let mut indptr: Vec<u32> = Vec::with_capacity(documents.len() + 1);
+    let mut indices: Vec<u32> = Vec::new();
+    let mut values: Vec<f64> = Vec::new();
+    indptr.push(0);
+    for document in &documents {
+        let mut counts: HashMap<u32, u32> = HashMap::new();
+        for term in document {
+            if let Some(&col) = columns.get(term.as_str()) {
+                *counts.entry(col).or_insert(0) += 1;
+            }
+        }
+        let mut row: Vec<(u32, f64)> = counts.into_iter().map(|(col, count)| (col, count as f64 * idf[col as usize])).collect();
+        row.sort_unstable_by_key(|&(col, _)| col);
+        let norm = row.iter().map(|&(_, w)| w * w).sum::<f64>().sqrt();
+        for (col, w) in row {
+            indices.push(col);
+            values.push(w / norm);
+        }
+        indptr.push(indices.len() as u32);
+    }
+
+    let vocabulary: StrSeries = vocabulary.iter().map(|&(term, _)| Some(term)).collect();
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        Box::new([
+            eng.register_series_u32(&indptr),
+            eng.register_series_u32(&indices),
+            eng.register_series_f64(&values),
+            eng.register_series_str(vocabulary),
+        ])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{engine_create_series_f64, engine_create_series_str, engine_create_series_str_packed};
+    use crate::series::{engine_series_to_vec_str, engine_series_to_vec_u32};
+
+    fn strings(values: &[&str]) -> u32 {
+        engine_create_series_str(values.iter().map(|v| v.to_string()).collect())
+    }
+
+    fn table(ids: &[u32]) -> Vec<(String, u32)> {
+        engine_series_to_vec_str(ids[0]).into_iter().zip(engine_series_to_vec_u32(ids[1])).collect()
+    }
+
+    fn counts(pairs: &[(&str, u32)]) -> Vec<(String, u32)> {
+        pairs.iter().map(|&(token, count)| (token.to_string(), count)).collect()
+    }
+
+    #[test]
+    fn tokenize_splits_on_literals_and_regex_forms() {
+        let id = strings(&["to be  or", "not to be"]);
+        assert_eq!(table(&engine_str_tokenize(id, "")), counts(&[("to", 2), ("be", 2), ("or", 1), ("not", 1)]));
+        let id = strings(&["a,b;;a", "c a"]);
+        assert_eq!(table(&engine_str_tokenize(id, "[,;]+")), counts(&[("a", 2), ("b", 1), ("c a", 1)]));
+        assert_eq!(table(&engine_str_tokenize(strings(&["x, y,z"]), ", ")), counts(&[("x", 1), ("y,z", 1)]));
+        assert_eq!(table(&engine_str_tokenize(strings(&["a1b22c"]), "\\d")), counts(&[("a", 1), ("b", 1), ("c", 1)]));
+        assert_eq!(table(&engine_str_tokenize(strings(&["ab-CD.e"]), "[^a-z]")), counts(&[("ab", 1), ("e", 1)]));
+        // Null rows have no tokens
+        let id = engine_create_series_str_packed(b"ab", &[0, 2, 2], &[1, 0]);
+        assert!(table(&engine_str_tokenize(id, "")).is_empty());
+    }
+
+    #[test]
+    fn tokenize_rejects_malformed_classes_and_non_string_series() {
+        let id = strings(&["abc"]);
+        assert!(engine_str_tokenize(id, "[z-a]").is_empty());
+        assert!(engine_str_tokenize(id, "[]").is_empty());
+        assert!(engine_str_tokenize(id, "[a\\]").is_empty());
+        assert!(engine_str_tokenize(engine_create_series_f64(&[1.0]), "").is_empty());
+    }
+
+    #[test]
+    fn ngrams_stay_within_rows() {
+        let id = strings(&["the cat sat", "the  cat", "dog"]);
+        assert_eq!(table(&engine_ngram_counts(id, 2)), counts(&[("the cat", 2), ("cat sat", 1)]));
+        assert_eq!(table(&engine_ngram_counts(id, 1)), counts(&[("the", 2), ("cat", 2), ("sat", 1), ("dog", 1)]));
+        assert!(table(&engine_ngram_counts(id, 4)).is_empty());
+        assert!(engine_ngram_counts(id, 0).is_empty());
+    }
+
+    #[test]
+    fn tfidf_matches_scikit_learn_defaults() {
+        use crate::series::engine_series_to_vec_f64;
+        let id = engine_create_series_str_packed(b"Apple I bananaapple apple", &[0, 14, 25, 25], &[0, 0, 1]);
+        let ids = engine_tfidf(id, 0);
+        assert_eq!(engine_series_to_vec_u32(ids[0]), [0, 2, 3, 3]);
+        assert_eq!(engine_series_to_vec_u32(ids[1]), [0, 1, 0]);
+        assert_eq!(engine_series_to_vec_str(ids[3]), ["apple", "banana"]);
+        let (apple, banana) = ((4.0f64 / 3.0).ln() + 1.0, 2.0f64.ln() + 1.0);
+        let norm = apple.hypot(banana);
+        let values = engine_series_to_vec_f64(ids[2]);
+        assert!((values[0] - apple / norm).abs() < 1e-12);
+        assert!((values[1] - banana / norm).abs() < 1e-12);
+        assert_eq!(values[2], 1.0);
+        // Only the most frequent term is kept
+        let ids = engine_tfidf(id, 1);
+        assert_eq!(engine_series_to_vec_u32(ids[0]), [0, 1, 2, 2]);
+        assert_eq!(engine_series_to_vec_f64(ids[2]), [1.0, 1.0]);
+        assert_eq!(engine_series_to_vec_str(ids[3]), ["apple"]);
+        assert!(engine_tfidf(engine_create_series_f64(&[1.0]), 0).is_empty());
+    }
+}
+
@@ -0,0 +1,284 @@
+//! Time-series helpers
+//!
+//! Functions over a value series paired with a timestamp series of any
+//! numeric dtype (e.g. epoch milliseconds as float64 or int64), for
+//! preparing plots and aligning streams, plus statistics over row segments
+//! and CUSUM change detection.
+
+use wasm_bindgen::prelude::*;
+use crate::core::{f64_values, ENGINE};
+use crate::profiling::{profile, series_bytes};
+use crate::statistics::{RunningStats, AGG_COUNT, AGG_MAX, AGG_MEAN, AGG_MIN, AGG_STD};
+use crate::window::order_keys;
+
+/// Min, max, first and last non-null value of a decimation bucket
+struct Bucket {
+    min: f64,
+    max: f64,
+    first: f64,
+    last: f64,
+}
+
+impl Bucket {
+    fn new() -> Self {
+        Bucket { min: f64::NAN, max: f64::NAN, first: f64::NAN, last: f64::NAN }
+    }
+
+    fn push(&mut self, v: f64) {
+        if v.is_nan() {
+            return;
+        }
+        if self.first.is_nan() {
+            self.first = v;
+            self.min = v;
+            self.max = v;
+        }
+        self.last = v;
+        self.min = self.min.min(v);
+        self.max = self.max.max(v);
+    }
+}
+
+/// Downsample a series for zoomable plots and candlestick (OHLC) charts:
+/// the time range is split into `bucket_count` equal-width buckets and each
+/// non-empty bucket keeps its min, max, first and last value (first and
+/// last in row order, so in time order for sorted timestamps). Rows with a
+/// null timestamp are skipped and null values are ignored, so a bucket
+/// holding only nulls has NaN statistics.
+///
+/// Returns five float64 series ids, one row per non-empty bucket:
+/// `[bucket_start_time, min, max, first, last]`; empty if an id is unknown,
+/// the lengths differ or `bucket_count` is 0.
+#[wasm_bindgen]
+pub fn engine_min_max_decimate(time_id: u32, value_id: u32, bucket_count: u32) -> Box<[u32]> {
+    let _prof = profile("engine_min_max_decimate", || series_bytes(value_id));
+    let (times, values) = match (order_keys(time_id), unsafe { f64_values(value_id) }) {
+        (Some(times), Some(values)) if times.len() == values.len() && bucket_count > 0 => (times, values),
+        _ => {
+            engine_log!(warn, "engine_min_max_decimate: invalid input time_id={} value_id={} bucket_count={}", time_id, value_id, bucket_count);
+            return Box::new([]);
+        }
+    };
+    let (t_min, t_max) = times
+        .iter()
+        .filter(|t| !t.is_nan())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &t| (lo.min(t), hi.max(t)));
+    let n = bucket_count as usize;
+    let width = (t_max - t_min) / n as f64;
+    let mut buckets: Vec<Option<Bucket>> = (0..n).map(|_| None).collect();
+    for (row, &t) in times.iter().enumerate() {
+        if t.is_nan() {
+            continue;
+        }
+        // The last edge belongs to the last bucket; a zero width puts every row in the first
+        let b = if width > 0.0 { (((t - t_min) / width) as usize).min(n - 1) } else { 0 };
+        buckets[b].get_or_insert_with(Bucket::new).push(values.get(row));
+    }
+
+    let mut columns: [Vec<f64>; 5] = Default::default();
+    for (b, bucket) in buckets.iter().enumerate() {
+        if let Some(bucket) = bucket {
+            let start = if width > 0.0 { t_min + b as f64 * width } else { t_min };
+            for (column, v) in columns.iter_mut().zip([start, bucket.min, bucket.max, bucket.first, bucket.last]) {
+                column.push(v);
+            }
+        }
+    }
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        columns.iter().map(|column| eng.register_series_f64(column)).collect()
+    })
+}
+
+/// Statistics of consecutive row segments of a float64 series, e.g. after
+/// change-point detection or per session: `boundary_indices` are the
+/// ascending rows where new segments start, so `[3, 7]` over 10 rows gives
+/// rows 0..3, 3..7 and 7..10 (a repeated boundary gives an empty segment).
+/// Nulls are skipped; std is the sample standard deviation.
+///
+/// Returns five float64 series ids with one row per segment:
+/// `[mean, std, min, max, count]`; empty if the id is unknown or the
+/// boundaries are not ascending or exceed the length.
+#[wasm_bindgen]
+pub fn engine_segmented_stats_f64(value_id: u32, boundary_indices: &[u32]) -> Box<[u32]> {
+    let _prof = profile("engine_segmented_stats_f64", || series_bytes(value_id));
+    let values = unsafe { f64_values(value_id) };
+    let len = values.as_ref().map_or(0, |v| v.len());
+    let values = match values {
+        Some(values) if boundary_indices.windows(2).all(|w| w[0] <= w[1]) && boundary_indices.last().is_none_or(|&b| b as usize <= len) => values,
+        _ => {
+            engine_log!(warn, "engine_segmented_stats_f64: unknown series or invalid boundaries value_id={} len={}", value_id, len);
+            return Box::new([]);
+        }
+    };
+    let starts = std::iter::once(0).chain(boundary_indices.iter().map(|&b| b as usize));
+    let ends = boundary_indices.iter().map(|&b| b as usize).chain(std::iter::once(len));
+    let segments: Vec<RunningStats> = starts
+        .zip(ends)
+        .map(|(start, end)| {
+            let mut stats = RunningStats::default();
+            (start..end).for_each(|row| stats.push(values.get(row)));
+            stats
+        })
+        .collect();
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        [AGG_MEAN, AGG_STD, AGG_MIN, AGG_MAX, AGG_COUNT]
+            .iter()
+            .map(|&agg| eng.register_series_f64(&segments.iter().map(|stats| stats.finish(agg)).collect::<Vec<_>>()))
+            .collect()
+    })
+}
+
+/// Two-sided tabular CUSUM for detecting a shift away from `target`:
+/// `upper = max(0, upper + x - target - k)` accumulates upward drift and
+/// `lower = max(0, lower + target - k - x)` downward drift, where `k` is
+/// the allowance (slack, often half the shift to detect). A row where
+/// either statistic exceeds the decision interval `h` raises an alarm and
+/// both restart from 0. Null rows are NaN and leave the statistics as they
+/// are.
+///
+/// Returns `[upper_id, lower_id, alarms_id]`: two float64 series aligned
+/// with the input and a uint32 series of the alarm rows; empty if the id
+/// is unknown, `target` is not finite, `k` is negative or `h` is not
+/// positive.
+#[wasm_bindgen]
+pub fn engine_cusum_f64(series_id: u32, target: f64, k: f64, h: f64) -> Box<[u32]> {
+    let _prof = profile("engine_cusum_f64", || series_bytes(series_id));
+    let values = match unsafe { f64_values(series_id) } {
+        Some(values) if target.is_finite() && k >= 0.0 && h > 0.0 => values,
+        _ => {
+            engine_log!(warn, "engine_cusum_f64: unknown series or invalid parameters series_id={} target={} k={} h={}", series_id, target, k, h);
+            return Box::new([]);
+        }
+    };
+    let (mut upper, mut lower) = (vec![f64::NAN; values.len()], vec![f64::NAN; values.len()]);
+    let mut alarms = Vec::new();
+    let (mut hi, mut lo) = (0.0f64, 0.0f64);
+    for (row, x) in values.iter().enumerate() {
+        if x.is_nan() {
+            continue;
+        }
+        hi = (hi + x - target - k).max(0.0);
+        lo = (lo + target - k - x).max(0.0);
+        upper[row] = hi;
+        lower[row] = lo;
+        if hi > h || lo > h {
+            alarms.push(row as u32);
+            hi = 0.0;
+            lo = 0.0;
+        }
+    }
+    ENGINE.with(|cell| {
+        let mut eng = cell.borrow_mut();
+        Box::new([eng.register_series_f64(&upper), eng.register_series_f64(&lower), eng.register_series_u32(&alarms)])
+    })
+}
+
+/// Linearly interpolate a series onto new timestamps, e.g. to align sensor
+/// streams sampled at different rates: each target time takes the value on
+/// the line between the nearest source points before and after it (as
+/// numpy's `interp`), or the source value at an exact match. Null source
+/// values are skipped; targets that are null or outside the source time
+/// range are NaN rather than extrapolated.
+///
+/// `src_times_id` and `target_times_id` are numeric timestamp series (any
+/// numeric dtype); the source times must be non-decreasing without nulls.
+/// Returns a float64 series aligned with the targets, or u32::MAX if an id
+/// is unknown, the source lengths differ or the source times are unsorted.
+#[wasm_bindgen]
+pub fn engine_interp_to_grid_f64(src_times_id: u32, src_values_id: u32, target_times_id: u32) -> u32 {
+    let _prof = profile("engine_interp_to_grid_f64", || series_bytes(src_values_id) + series_bytes(target_times_id));
+    let (times, values, targets) = match (order_keys(src_times_id), unsafe { f64_values(src_values_id) }, order_keys(target_times_id)) {
+        (Some(times), Some(values), Some(targets))
+            if times.len() == values.len() && times.iter().all(|t| !t.is_nan()) && times.windows(2).all(|w| w[0] <= w[1]) =>
+        {
+            (times, values, targets)
+        }
+        _ => {
+            engine_log!(warn, "engine_interp_to_grid_f64: unknown series, length mismatch or unsorted source times src_times_id={} src_values_id={}", src_times_id, src_values_id);
+            return u32::MAX;
+        }
+    };
+    let points: Vec<(f64, f64)> = times.iter().zip(values.iter()).filter(|(_, v)| !v.is_nan()).map(|(&t, v)| (t, v)).collect();
+    let out: Vec<f64> = targets
+        .iter()
+        .map(|&x| {
+            let i = points.partition_point(|p| p.0 < x);
+            match (i.checked_sub(1).map(|j| points[j]), points.get(i)) {
+                (_, Some(&(t, v))) if t == x => v,
+                (Some((t0, v0)), Some(&(t1, v1))) => v0 + (v1 - v0) * (x - t0) / (t1 - t0),
+                _ => f64::NAN,
+            }
+        })
+        .collect();
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{engine_create_series_f64, engine_create_series_i64};
+    use crate::series::{engine_series_to_vec_f64, engine_series_to_vec_u32};
+
+    fn columns(ids: &[u32]) -> Vec<Vec<f64>> {
+        ids.iter().map(|&id| engine_series_to_vec_f64(id)).collect()
+    }
+
+    #[test]
+    fn decimation_keeps_the_extremes_and_ends_of_each_bucket() {
+        let times = engine_create_series_i64(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 10]);
+        let values = engine_create_series_f64(&[3.0, 1.0, f64::NAN, 5.0, 2.0, 7.0, 9.0, 8.0, 6.0, 4.0]);
+        assert_eq!(
+            columns(&engine_min_max_decimate(times, values, 2)),
+            [vec![0.0, 5.0], vec![1.0, 4.0], vec![5.0, 9.0], vec![3.0, 7.0], vec![2.0, 4.0]]
+        );
+        // Empty buckets are left out; the last edge falls in the last bucket
+        let sparse = engine_min_max_decimate(engine_create_series_f64(&[0.0, 10.0]), engine_create_series_f64(&[1.0, 2.0]), 4);
+        assert_eq!(columns(&sparse)[0], [0.0, 7.5]);
+        assert!(engine_min_max_decimate(times, values, 0).is_empty());
+        assert!(engine_min_max_decimate(times, engine_create_series_f64(&[1.0]), 2).is_empty());
+    }
+
+    #[test]
+    fn segments_summarize_rows_between_boundaries() {
+        let values = engine_create_series_f64(&[1.0, 2.0, 3.0, f64::NAN, 5.0, 7.0, 10.0, 10.0]);
+        let stats = columns(&engine_segmented_stats_f64(values, &[3, 3, 6]));
+        let debug: Vec<String> = stats.iter().map(|column| format!("{:?}", column)).collect();
+        assert_eq!(debug, [
+            format!("{:?}", [2.0, f64::NAN, 6.0, 10.0]),
+            format!("{:?}", [1.0, f64::NAN, 2f64.sqrt(), 0.0]),
+            format!("{:?}", [1.0, f64::NAN, 5.0, 10.0]),
+            format!("{:?}", [3.0, f64::NAN, 7.0, 10.0]),
+            format!("{:?}", [3.0, 0.0, 2.0, 2.0]),
+        ]);
+        assert_eq!(columns(&engine_segmented_stats_f64(values, &[]))[4], [7.0]);
+        assert!(engine_segmented_stats_f64(values, &[4, 2]).is_empty());
+        assert!(engine_segmented_stats_f64(values, &[9]).is_empty());
+    }
+
+    #[test]
+    fn cusum_alarms_and_restarts_on_drift() {
+        let values = engine_create_series_f64(&[1.0, 1.0, 1.0, f64::NAN, -3.0, 0.0]);
+        let ids = engine_cusum_f64(values, 0.0, 0.5, 2.0);
+        let debug = |id| format!("{:?}", engine_series_to_vec_f64(id));
+        assert_eq!(debug(ids[0]), "[0.5, 1.0, 1.5, NaN, 0.0, 0.0]");
+        assert_eq!(debug(ids[1]), "[0.0, 0.0, 0.0, NaN, 2.5, 0.0]");
+        assert_eq!(engine_series_to_vec_u32(ids[2]), [4]);
+        assert!(engine_cusum_f64(values, f64::NAN, 0.5, 2.0).is_empty());
+        assert!(engine_cusum_f64(values, 0.0, -1.0, 2.0).is_empty());
+        assert!(engine_cusum_f64(values, 0.0, 0.5, 0.0).is_empty());
+    }
+
+    #[test]
+    fn interpolation_skips_null_sources_and_never_extrapolates() {
+        let times = engine_create_series_i64(&[0, 10, 20]);
+        let values = engine_create_series_f64(&[0.0, f64::NAN, 40.0]);
+        let targets = engine_create_series_f64(&[5.0, 10.0, 20.0, 0.0, -1.0, 25.0, f64::NAN]);
+        let out = engine_interp_to_grid_f64(times, values, targets);
+        assert_eq!(format!("{:?}", engine_series_to_vec_f64(out)), "[10.0, 20.0, 40.0, 0.0, NaN, NaN, NaN]");
+        let unsorted = engine_create_series_f64(&[0.0, 20.0, 10.0]);
+        assert_eq!(engine_interp_to_grid_f64(unsorted, values, targets), u32::MAX);
+        assert_eq!(engine_interp_to_grid_f64(times, engine_create_series_f64(&[1.0]), targets), u32::MAX);
+    }
+}
@@ -0,0 +1,305 @@
+//! Data-quality validation
+//!
+//! Checks columns against declarative rules in one pass and reports the
+//! violations with a sample of offending rows, for validation APIs that
+//! would otherwise need one kernel call per rule. String columns can also be
+//! checked value by value against common formats (email, URL, number),
+//! giving a mask series.
+
+use std::collections::HashSet;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+use crate::core::{f64_values, ENGINE};
+use crate::profiling::{profile, series_bytes};
+
+/// Violations of one rule
+#[derive(Default)]
+struct Violations {
+    count: usize,
+    rows: Vec<usize>,
+}
+
+impl Violations {
+    fn push(&mut self, row: usize, max_samples: usize) {
+        self.count += 1;
+        if self.rows.len() < max_samples {
+            self.rows.push(row);
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        serde_json::json!({ "count": self.count, "rows": self.rows })
+    }
+}
+
+/// Check an f64 series against the rules in `rules_json`, an object with
+/// any of:
+/// - `"not_null": true` - no null values
+/// - `"min"` / `"max"`: number - inclusive bounds for non-null values
+/// - `"unique": true` - no repeated non-null value (each repeat after the
+///   first occurrence is a violation)
+/// - `"monotonic"`: `"increasing"` / `"decreasing"` (non-strict) or
+///   `"strictly_increasing"` / `"strictly_decreasing"` - order of the
+///   non-null values (a violation is a value out of order with the
+///   previous non-null value)
+/// - `"max_samples"`: number - rows listed per rule (default 10)
+///
+/// Returns `{"len", "valid", "violations": {"<rule>": {"count", "rows"}}}`
+/// with one entry per checked rule (`rows` ascending), or an empty string if
+/// the series is unknown or the rules are not a valid rules object.
+#[wasm_bindgen]
+pub fn engine_validate_f64(series_id: u32, rules_json: &str) -> String {
+    let _prof = profile("engine_validate_f64", || series_bytes(series_id));
+    let rules: Value = match serde_json::from_str(rules_json) {
+        Ok(rules @ Value::Object(_)) => rules,
+        _ => {
+            engine_log!(warn, "engine_validate_f64: invalid rules JSON");
+            return String::new();
+        }
+    };
+    let values = match unsafe { f64_values(series_id) } {
+        Some(values) => values,
+        None => return String::new(),
+    };
+    let flag = |name: &str| rules.get(name).and_then(Value::as_bool).unwrap_or(false);
+    let not_null = flag("not_null");
+    let unique = flag("unique");
+    let min = rules.get("min").and_then(Value::as_f64);
+    let max = rules.get("max").and_then(Value::as_f64);
+    // (allowed order of consecutive values, strict)
+    let monotonic = match rules.get("monotonic").and_then(Value::as_str) {
+        None => None,
+        Some("increasing") => Some((std::cmp::Ordering::Less, false)),
+        Some("strictly_increasing") => Some((std::cmp::Ordering::Less, true)),
+        Some("decreasing") => Some((std::cmp::Ordering::Greater, false)),
+        Some("strictly_decreasing") => Some((std::cmp::Ordering::Greater, true)),
+        Some(other) => {
+            engine_log!(warn, "engine_validate_f64: unknown monotonic rule {}", other);
+            return String::new();
+        }
+    };
+    let max_samples = rules.get("max_samples").and_then(Value::as_u64).unwrap_or(10) as usize;
+
+    let mut nulls = Violations::default();
+    let mut below = Violations::default();
+    let mut above = Violations::default();
+    let mut repeats = Violations::default();
+    let mut disorder = Violations::default();
+    let mut seen: HashSet<u64> = HashSet::new();
+    let mut prev: Option<f64> = None;
+    for (row, v) in values.iter().enumerate() {
+        if v.is_nan() {
+            if not_null {
+                nulls.push(row, max_samples);
+            }
+            continue;
+        }
+        if min.is_some_and(|min| v < min) {
+            below.push(row, max_samples);
+        }
+        if max.is_some_and(|max| v > max) {
+            above.push(row, max_samples);
+        }
+        // -0.0 and 0.0 are the same value
+        if unique && !seen.insert((v + 0.0).to_bits()) {
+            repeats.push(row, max_samples);
+        }
+        if let (Some((order, strict)), Some(p)) = (monotonic, prev) {
+            let in_order = p.partial_cmp(&v) == Some(order) || (!strict && p == v);
+            if !in_order {
+                disorder.push(row, max_samples);
+            }
+        }
+        prev = Some(v);
+    }
+
+    let mut violations = serde_json::Map::new();
+    let checked = [
+        (not_null, "not_null", &nulls),
+        (min.is_some(), "min", &below),
+        (max.is_some(), "max", &above),
+        (unique, "unique", &repeats),
+        (monotonic.is_some(), "monotonic", &disorder),
+    ];
+    for (enabled, name, found) in checked {
+        if enabled {
+            violations.insert(name.to_string(), found.to_json());
+        }
+    }
+    let valid = checked.iter().all(|(_, _, found)| found.count == 0);
+    serde_json::json!({
+        "len": values.len(),
+        "valid": valid,
+        "violations": violations,
+    })
+    .to_string()
+}
+
+/// Domain name: dot-separated labels of 1 to 63 ASCII letters, digits and
+/// hyphens, neither starting nor ending with a hyphen
+fn is_domain(host: &str) -> bool {
+    !host.is_empty()
+        && host.split('.').all(|label| {
+            (1..=63).contains(&label.len())
+                && label.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+        })
+}
+
+/// Email address as accepted by an HTML `type=email` input (the WHATWG
+/// definition): a local part of ASCII letters, digits and
+/// ``.!#$%&'*+/=?^_`{|}~-``, `@`, and a domain name
+fn is_email(text: &str) -> bool {
+    match text.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty() && local.bytes().all(|b| b.is_ascii_alphanumeric() || b".!#$%&'*+/=?^_`{|}~-".contains(&b)) && is_domain(domain)
+        }
+        None => false,
+    }
+}
+
+/// Absolute URL with an authority: a scheme (a letter, then letters, digits,
+/// `+`, `-` or `.`), `://`, optional `user@`, a host (domain name, dotted
+/// IPv4 address or bracketed IPv6 address), an optional port up to 65535,
+/// then an optional path, query or fragment without whitespace
+fn is_url(text: &str) -> bool {
+    let (scheme, rest) = match text.split_once("://") {
+        Some(parts) => parts,
+        None => return false,
+    };
+    let scheme_ok = scheme.bytes().next().is_some_and(|b| b.is_ascii_alphabetic())
+        && scheme.bytes().all(|b| b.is_ascii_alphanumeric() || b"+-.".contains(&b));
+    if !scheme_ok || text.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return false;
+    }
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, host_port)| host_port);
+    let (host_ok, port) = match host_port.strip_prefix('[') {
+        Some(bracketed) => match bracketed.split_once(']') {
+            Some((ipv6, port)) => (ipv6.parse::<Ipv6Addr>().is_ok(), port),
+            None => return false,
+        },
+        None => {
+            let (host, port) = host_port.split_at(host_port.find(':').unwrap_or(host_port.len()));
+            (host.parse::<Ipv4Addr>().is_ok() || is_domain(host), port)
+        }
+    };
+    let port_ok = match port.strip_prefix(':') {
+        Some(port) => port.bytes().all(|b| b.is_ascii_digit()) && port.parse::<u16>().is_ok(),
+        None => port.is_empty(),
+    };
+    host_ok && port_ok
+}
+
+/// Decimal number, optionally signed and in exponent notation ("-1.5",
+/// ".5", "2e-3"), ignoring surrounding whitespace; "inf" and "NaN" are not
+/// numbers here
+fn is_numeric(text: &str) -> bool {
+    let text = text.trim();
+    text.bytes().any(|b| b.is_ascii_digit())
+        && text.bytes().all(|b| b.is_ascii_digit() || b"+-.eE".contains(&b))
+        && text.parse::<f64>().is_ok()
+}
+
+/// Bool mask of a string series with `check` applied to each non-null
+/// value (nulls are false), or u32::MAX if the id is not a string series
+fn str_mask(name: &str, series_id: u32, check: fn(&str) -> bool) -> u32 {
+    let mask: Option<Vec<u8>> = ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        let strings = eng.series_store_str.get(&series_id)?;
+        Some(strings.iter().map(|text| text.is_some_and(check) as u8).collect())
+    });
+    match mask {
+        Some(mask) => ENGINE.with(|cell| cell.borrow_mut().register_series_bool(&mask)),
+        None => {
+            engine_log!(warn, "{}: unknown string series {}", name, series_id);
+            u32::MAX
+        }
+    }
+}
+
+/// Bool mask of the values of a string series that are email addresses, as
+/// accepted by an HTML `type=email` input: a local part of ASCII letters,
+/// digits and ``.!#$%&'*+/=?^_`{|}~-``, `@`, and a domain of dot-separated
+/// labels (no quoted local parts or IP address literals). Nulls are false.
+/// Returns u32::MAX if the id is not a string series.
+#[wasm_bindgen]
+pub fn engine_str_is_email(series_id: u32) -> u32 {
+    let _prof = profile("engine_str_is_email", || series_bytes(series_id));
+    str_mask("engine_str_is_email", series_id, is_email)
+}
+
+/// Bool mask of the values of a string series that are absolute URLs with
+/// a host, such as `https://example.com:8080/path?q=1`: a scheme, `://`, an
+/// optional `user@`, a domain name, IPv4 address or bracketed IPv6
+/// address, an optional port, and an optional path, query or fragment, with
+/// no whitespace anywhere. Nulls are false. Returns u32::MAX if the id is
+/// not a string series.
+#[wasm_bindgen]
+pub fn engine_str_is_url(series_id: u32) -> u32 {
+    let _prof = profile("engine_str_is_url", || series_bytes(series_id));
+    str_mask("engine_str_is_url", series_id, is_url)
+}
+
+/// Bool mask of the values of a string series that are decimal numbers,
+/// optionally signed and in exponent notation ("42", "-1.5", ".5",
+/// "2e-3"), ignoring surrounding whitespace; "inf", "NaN", hex and
+/// thousands separators are not numbers. Nulls are false. Returns u32::MAX
+/// if the id is not a string series.
+#[wasm_bindgen]
+pub fn engine_str_is_numeric(series_id: u32) -> u32 {
+    let _prof = profile("engine_str_is_numeric", || series_bytes(series_id));
+    str_mask("engine_str_is_numeric", series_id, is_numeric)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{engine_create_series_f64, engine_create_series_str_packed};
+    use crate::series::engine_series_to_vec_bool;
+
+    #[test]
+    fn rules_report_violations_with_samples() {
+        let id = engine_create_series_f64(&[3.0, f64::NAN, 1.0, 1.0, 12.0, -0.0, 0.0]);
+        let rules = r#"{"not_null": true, "min": 0, "max": 10, "unique": true, "monotonic": "increasing", "max_samples": 1}"#;
+        let report: Value = serde_json::from_str(&engine_validate_f64(id, rules)).unwrap();
+        assert_eq!((report["len"].as_u64(), report["valid"].as_bool()), (Some(7), Some(false)));
+        let violations = &report["violations"];
+        assert_eq!(violations["not_null"], serde_json::json!({"count": 1, "rows": [1]}));
+        assert_eq!(violations["min"], serde_json::json!({"count": 0, "rows": []}));
+        assert_eq!(violations["max"], serde_json::json!({"count": 1, "rows": [4]}));
+        assert_eq!(violations["unique"], serde_json::json!({"count": 2, "rows": [3]}));
+        assert_eq!(violations["monotonic"], serde_json::json!({"count": 2, "rows": [2]}));
+        let strict: Value = serde_json::from_str(&engine_validate_f64(id, r#"{"monotonic": "strictly_decreasing"}"#)).unwrap();
+        assert_eq!(strict["violations"]["monotonic"]["rows"], serde_json::json!([3, 4, 6]));
+        assert_eq!(engine_validate_f64(id, r#"{"monotonic": "sideways"}"#), "");
+        assert_eq!(engine_validate_f64(id, "[]"), "");
+    }
+
+    #[test]
+    fn string_formats_are_recognised() {
+        let (bytes, offsets, nulls) = packed(&["a.b+c@example.com", "no-at.example", "x@-bad.com", "@example.com", ""]);
+        let emails = engine_create_series_str_packed(&bytes, &offsets, &nulls);
+        assert_eq!(engine_series_to_vec_bool(engine_str_is_email(emails)), [1, 0, 0, 0, 0]);
+        let urls = ["https://example.com:8080/p?q=1", "http://user@10.0.0.1", "ftp://[::1]:21/", "http://exa mple.com", "https://host:99999", "example.com"];
+        let (bytes, offsets, nulls) = packed(&urls);
+        let urls = engine_create_series_str_packed(&bytes, &offsets, &nulls);
+        assert_eq!(engine_series_to_vec_bool(engine_str_is_url(urls)), [1, 1, 1, 0, 0, 0]);
+        let (bytes, offsets, nulls) = packed(&[" -1.5 ", ".5", "2e-3", "inf", "NaN", "1,000", "0x1F"]);
+        let numbers = engine_create_series_str_packed(&bytes, &offsets, &nulls);
+        assert_eq!(engine_series_to_vec_bool(engine_str_is_numeric(numbers)), [1, 1, 1, 0, 0, 0, 0]);
+        assert_eq!(engine_str_is_email(engine_create_series_f64(&[1.0])), u32::MAX);
+    }
+
+    /// Packed buffers of `values`; the empty string stands for null
+    fn packed(values: &[&str]) -> (Vec<u8>, Vec<u32>, Vec<u8>) {
+        let bytes: Vec<u8> = values.concat().into_bytes();
+        let mut offsets = vec![0u32];
+        for value in values {
+            offsets.push(offsets.last().unwrap() + value.len() as u32);
+        }
+        (bytes, offsets, values.iter().map(|v| v.is_empty() as u8).collect())
+    }
+}
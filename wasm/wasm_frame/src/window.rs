@@ -0,0 +1,264 @@
+//! SQL-style window functions over partitions
+//!
+//! Window functions return a full-length series aligned with the input: each
+//! row gets a value computed from the rows of its partition, taken in window
+//! order. Partitions are given as a group-code series (see
+//! `groupby::key_codes`; null codes form a partition of their own) and the
+//! window order as a numeric series sorted ascending with nulls last, ties
+//! kept in row order. Pass u32::MAX as the order series to use row order.
+//!
+//! All functions return u32::MAX if an id is unknown or the lengths differ.
+
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+use crate::cast::Source;
+use crate::core::{f64_values, ENGINE};
+use crate::groupby::key_codes;
+use crate::profiling::{profile, series_bytes};
+
+/// Partitions of a window in window order
+struct Window {
+    /// Rows of each partition, sorted by `order`
+    partitions: Vec<Vec<usize>>,
+    /// Sort key of each row (NaN = null)
+    order: Vec<f64>,
+    len: usize,
+}
+
+impl Window {
+    fn new(partition_codes_id: u32, order_series_id: u32) -> Option<Window> {
+        let codes = key_codes(partition_codes_id)?;
+        let order = if order_series_id == u32::MAX {
+            (0..codes.len()).map(|row| row as f64).collect()
+        } else {
+            order_keys(order_series_id)?
+        };
+        if order.len() != codes.len() {
+            return None;
+        }
+        let mut index: HashMap<u32, usize> = HashMap::new();
+        let mut partitions: Vec<Vec<usize>> = Vec::new();
+        for (row, &code) in codes.iter().enumerate() {
+            let part = *index.entry(code).or_insert_with(|| {
+                partitions.push(Vec::new());
+                partitions.len() - 1
+            });
+            partitions[part].push(row);
+        }
+        // Stable sort: rows are pushed in row order, so ties stay in row order
+        for rows in partitions.iter_mut() {
+            rows.sort_by(|&a, &b| match (order[a].is_nan(), order[b].is_nan()) {
+                (false, false) => order[a].total_cmp(&order[b]),
+                (nan_a, nan_b) => nan_a.cmp(&nan_b),
+            });
+        }
+        Some(Window { partitions, order, len: codes.len() })
+    }
+
+    /// Whether two rows have equal sort keys (nulls are peers of each other)
+    fn peers(&self, a: usize, b: usize) -> bool {
+        let (x, y) = (self.order[a], self.order[b]);
+        x == y || (x.is_nan() && y.is_nan())
+    }
+
+    /// Call `f(rows, peer_start, peer_end)` for each run of peers, with
+    /// `rows` the partition in window order
+    fn for_each_peer_group(&self, mut f: impl FnMut(&[usize], usize, usize)) {
+        for rows in self.partitions.iter() {
+            let mut start = 0;
+            while start < rows.len() {
+                let mut end = start + 1;
+                while end < rows.len() && self.peers(rows[start], rows[end]) {
+                    end += 1;
+                }
+                f(rows, start, end);
+                start = end;
+            }
+        }
+    }
+}
+
+/// Numeric sort keys of a series of any numeric dtype (NaN for null); None
+/// for unknown ids and string series
+pub(crate) fn order_keys(series_id: u32) -> Option<Vec<f64>> {
+    ENGINE.with(|cell| {
+        let eng = cell.borrow();
+        let source = Source::new(&eng, series_id)?;
+        if matches!(source, Source::Str(_) | Source::Interned(..)) {
+            return None;
+        }
+        Some((0..source.len()).map(|row| source.cell(row).number().unwrap_or(f64::NAN)).collect())
+    })
+}
+
+/// Build a window, fill a full-length output with `f` and register it;
+/// `value_len` is the length of the value series the window reads, if any
+fn window_series(name: &str, partition_codes_id: u32, order_series_id: u32, value_len: Option<usize>, f: impl FnOnce(&Window, &mut [f64])) -> u32 {
+    let window = match Window::new(partition_codes_id, order_series_id) {
+        Some(window) if value_len.is_none_or(|len| len == window.len) => window,
+        _ => {
+            engine_log!(warn, "{}: unknown series or length mismatch partition_codes_id={} order_series_id={}", name, partition_codes_id, order_series_id);
+            return u32::MAX;
+        }
+    };
+    let mut out = vec![f64::NAN; window.len];
+    f(&window, &mut out);
+    ENGINE.with(|cell| cell.borrow_mut().register_series_f64(&out))
+}
+
+/// Position of each row within its partition in window order, from 1
+/// (ties are numbered in row order)
+#[wasm_bindgen]
+pub fn engine_window_row_number(partition_codes_id: u32, order_series_id: u32) -> u32 {
+    let _prof = profile("engine_window_row_number", || series_bytes(partition_codes_id));
+    window_series("engine_window_row_number", partition_codes_id, order_series_id, None, |window, out| {
+        for rows in window.partitions.iter() {
+            for (i, &row) in rows.iter().enumerate() {
+                out[row] = (i + 1) as f64;
+            }
+        }
+    })
+}
+
+/// Bucket 1..=n of each row when its partition is split, in window order,
+/// into `n` buckets whose sizes differ by at most one (larger buckets
+/// first), as SQL `NTILE(n)`. Returns u32::MAX if `n` is 0.
+#[wasm_bindgen]
+pub fn engine_window_ntile(partition_codes_id: u32, order_series_id: u32, n: u32) -> u32 {
+    let _prof = profile("engine_window_ntile", || series_bytes(partition_codes_id));
+    if n == 0 {
+        return u32::MAX;
+    }
+    window_series("engine_window_ntile", partition_codes_id, order_series_id, None, |window, out| {
+        let n = n as usize;
+        for rows in window.partitions.iter() {
+            let (size, extra) = (rows.len() / n, rows.len() % n);
+            // The first `extra` buckets hold one more row
+            let big_rows = extra * (size + 1);
+            for (i, &row) in rows.iter().enumerate() {
+                let bucket = if i < big_rows { i / (size + 1) } else { extra + (i - big_rows) / size };
+                out[row] = (bucket + 1) as f64;
+            }
+        }
+    })
+}
+
+/// Relative rank `(rank - 1) / (rows - 1)` of each row within its
+/// partition, in [0, 1], with peers sharing the lowest rank; 0 for
+/// single-row partitions, as SQL `PERCENT_RANK()`
+#[wasm_bindgen]
+pub fn engine_window_percent_rank(partition_codes_id: u32, order_series_id: u32) -> u32 {
+    let _prof = profile("engine_window_percent_rank", || series_bytes(partition_codes_id));
+    window_series("engine_window_percent_rank", partition_codes_id, order_series_id, None, |window, out| {
+        window.for_each_peer_group(|rows, start, end| {
+            let rank = if rows.len() > 1 { start as f64 / (rows.len() - 1) as f64 } else { 0.0 };
+            for &row in &rows[start..end] {
+                out[row] = rank;
+            }
+        })
+    })
+}
+
+/// Fraction of its partition's rows that come before or are peers of each
+/// row, in (0, 1], as SQL `CUME_DIST()`
+#[wasm_bindgen]
+pub fn engine_window_cume_dist(partition_codes_id: u32, order_series_id: u32) -> u32 {
+    let _prof = profile("engine_window_cume_dist", || series_bytes(partition_codes_id));
+    window_series("engine_window_cume_dist", partition_codes_id, order_series_id, None, |window, out| {
+        window.for_each_peer_group(|rows, start, end| {
+            let dist = end as f64 / rows.len() as f64;
+            for &row in &rows[start..end] {
+                out[row] = dist;
+            }
+        })
+    })
+}
+
+/// Rank of each row within its partition from 1, with peers sharing a rank
+/// and no gaps after ties, as SQL `DENSE_RANK()`
+#[wasm_bindgen]
+pub fn engine_window_dense_rank(partition_codes_id: u32, order_series_id: u32) -> u32 {
+    let _prof = profile("engine_window_dense_rank", || series_bytes(partition_codes_id));
+    window_series("engine_window_dense_rank", partition_codes_id, order_series_id, None, |window, out| {
+        let mut rank = 0.0;
+        window.for_each_peer_group(|rows, start, end| {
+            rank = if start == 0 { 1.0 } else { rank + 1.0 };
+            for &row in &rows[start..end] {
+                out[row] = rank;
+            }
+        })
+    })
+}
+
+/// Value `offset` rows before (negative `offset`: after) each row within its
+/// partition in window order, or `default` past the partition edge
+fn shift_window(name: &str, value_id: u32, partition_codes_id: u32, order_series_id: u32, offset: isize, default: f64) -> u32 {
+    let values = match unsafe { f64_values(value_id) } {
+        Some(values) => values,
+        None => return u32::MAX,
+    };
+    window_series(name, partition_codes_id, order_series_id, Some(values.len()), |window, out| {
+        for rows in window.partitions.iter() {
+            for (i, &row) in rows.iter().enumerate() {
+                let source = i.checked_add_signed(-offset).filter(|&j| j < rows.len());
+                out[row] = source.map_or(default, |j| values.get(rows[j]));
+            }
+        }
+    })
+}
+
+/// Value `offset` rows earlier in the window order of each row's partition
+/// (`default` for the first `offset` rows of each partition), as SQL
+/// `LAG(value, offset, default)`; e.g. the previous event of a session for
+/// per-session deltas
+#[wasm_bindgen]
+pub fn engine_window_lag_f64(value_id: u32, partition_codes_id: u32, order_series_id: u32, offset: u32, default: f64) -> u32 {
+    let _prof = profile("engine_window_lag_f64", || series_bytes(value_id));
+    shift_window("engine_window_lag_f64", value_id, partition_codes_id, order_series_id, offset as isize, default)
+}
+
+/// Value `offset` rows later in the window order of each row's partition
+/// (`default` for the last `offset` rows of each partition), as SQL
+/// `LEAD(value, offset, default)`
+#[wasm_bindgen]
+pub fn engine_window_lead_f64(value_id: u32, partition_codes_id: u32, order_series_id: u32, offset: u32, default: f64) -> u32 {
+    let _prof = profile("engine_window_lead_f64", || series_bytes(value_id));
+    shift_window("engine_window_lead_f64", value_id, partition_codes_id, order_series_id, -(offset as isize), default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{engine_create_series_f64, engine_create_series_u32};
+    use crate::series::engine_series_to_vec_f64;
+
+    /// Partition 0 holds rows 0, 2, 3 (window order 2, 0, 3 with the null
+    /// last); partition 1 holds the tied rows 1 and 4
+    fn fixture() -> (u32, u32) {
+        (engine_create_series_u32(&[0, 1, 0, 0, 1]), engine_create_series_f64(&[3.0, 1.0, 1.0, f64::NAN, 1.0]))
+    }
+
+    #[test]
+    fn ranks_follow_the_window_order() {
+        let (parts, order) = fixture();
+        assert_eq!(engine_series_to_vec_f64(engine_window_row_number(parts, order)), [2.0, 1.0, 1.0, 3.0, 2.0]);
+        assert_eq!(engine_series_to_vec_f64(engine_window_row_number(parts, u32::MAX)), [1.0, 1.0, 2.0, 3.0, 2.0]);
+        assert_eq!(engine_series_to_vec_f64(engine_window_dense_rank(parts, order)), [2.0, 1.0, 1.0, 3.0, 1.0]);
+        assert_eq!(engine_series_to_vec_f64(engine_window_percent_rank(parts, order)), [0.5, 0.0, 0.0, 1.0, 0.0]);
+        assert_eq!(engine_series_to_vec_f64(engine_window_cume_dist(parts, order)), [2.0 / 3.0, 1.0, 1.0 / 3.0, 1.0, 1.0]);
+        assert_eq!(engine_series_to_vec_f64(engine_window_ntile(parts, order, 2)), [1.0, 1.0, 1.0, 2.0, 2.0]);
+        assert_eq!(engine_window_ntile(parts, order, 0), u32::MAX);
+    }
+
+    #[test]
+    fn lag_and_lead_stay_within_partitions() {
+        let (parts, order) = fixture();
+        let values = engine_create_series_f64(&[10.0, 20.0, 30.0, 40.0, 50.0]);
+        assert_eq!(engine_series_to_vec_f64(engine_window_lag_f64(values, parts, order, 1, -1.0)), [30.0, -1.0, -1.0, 10.0, 20.0]);
+        assert_eq!(engine_series_to_vec_f64(engine_window_lead_f64(values, parts, order, 1, -1.0)), [40.0, 50.0, 10.0, -1.0, -1.0]);
+        assert_eq!(engine_series_to_vec_f64(engine_window_lag_f64(values, parts, order, 0, -1.0)), [10.0, 20.0, 30.0, 40.0, 50.0]);
+        let short = engine_create_series_f64(&[1.0, 2.0]);
+        assert_eq!(engine_window_lag_f64(short, parts, order, 1, 0.0), u32::MAX);
+        assert_eq!(engine_window_row_number(parts, short), u32::MAX);
+    }
+}
@@ -0,0 +1,241 @@
+"""Write testdata/small.parquet, the Parquet reader's test fixture.
+
+The file follows the layout parquet-cpp (pyarrow) writes with its defaults:
+dictionary pages with RLE_DICTIONARY data pages (v1) for every column but
+booleans, Snappy compression, RLE/bit-packed definition levels and min/max
+statistics per column chunk. Rows 0-2 form the first row group, rows 3-4
+the second:
+
+    id     INT64 required   1, 2, 3, 4, 5
+    score  DOUBLE optional  1.5, null, 2.5, 9.0, 9.0
+    count  INT32 optional   10, 20, null, null, 7
+    flag   BOOLEAN required true, false, true, true, false
+    name   BYTE_ARRAY UTF8  "a", "b", null, "d", "e" (unsupported type)
+
+Run with any Python 3; no third-party packages are needed.
+"""
+
+import os
+import struct
+
+# Thrift compact protocol
+CT = {"bool": 1, "i32": 5, "i64": 6, "bin": 8, "list": 9, "struct": 12}
+
+
+def varint(n):
+    out = bytearray()
+    while True:
+        b = n & 0x7F
+        n >>= 7
+        if n:
+            out.append(b | 0x80)
+        else:
+            out.append(b)
+            return bytes(out)
+
+
+def zigzag(n):
+    return varint((n << 1) ^ (n >> 63))
+
+
+def value(kind, v):
+    if kind in ("i32", "i64"):
+        return zigzag(v)
+    if kind == "bin":
+        v = v if isinstance(v, bytes) else v.encode()
+        return varint(len(v)) + v
+    if kind == "struct":
+        return struct_(v)
+    elem = kind[len("list_"):]
+    code = CT[elem]
+    head = bytes([(len(v) << 4) | code]) if len(v) < 15 else bytes([0xF0 | code]) + varint(len(v))
+    return head + b"".join(value(elem, x) for x in v)
+
+
+def struct_(fields):
+    out = bytearray()
+    last = 0
+    for fid, kind, v in fields:
+        if v is None:
+            continue
+        code = (1 if v else 2) if kind == "bool" else CT["list" if kind.startswith("list_") else kind]
+        delta = fid - last
+        out += bytes([(delta << 4) | code]) if 0 < delta <= 15 else bytes([code]) + zigzag(fid)
+        if kind != "bool":
+            out += value(kind, v)
+        last = fid
+    return bytes(out) + b"\x00"
+
+
+# RLE / bit-packing hybrid, as parquet-cpp's RleEncoder emits it: runs of 8
+# or more equal values as RLE runs, everything else bit-packed in groups of 8
+def hybrid(values, bit_width):
+    out = bytearray()
+    byte_width = (bit_width + 7) // 8
+    i = 0
+    pending = []
+
+    def flush_packed():
+        if not pending:
+            return
+        groups = (len(pending) + 7) // 8
+        acc = 0
+        for k, v in enumerate(pending + [0] * (groups * 8 - len(pending))):
+            acc |= v << (k * bit_width)
+        out.extend(varint((groups << 1) | 1) + acc.to_bytes(groups * bit_width, "little"))
+        pending.clear()
+
+    while i < len(values):
+        run = 1
+        while i + run < len(values) and values[i + run] == values[i]:
+            run += 1
+        if run >= 8 or (not pending and i + run == len(values)):
+            flush_packed()
+            out.extend(varint(run << 1) + values[i].to_bytes(byte_width, "little"))
+            i += run
+        else:
+            pending.append(values[i])
+            i += 1
+    flush_packed()
+    return bytes(out)
+
+
+def levels_v1(defs):
+    data = hybrid(defs, 1)
+    return struct.pack("<I", len(data)) + data
+
+
+# Snappy block format with greedy 4-byte matching (literal, copy-1, copy-2)
+def snappy(data):
+    out = bytearray(varint(len(data)))
+    literal_start = 0
+    i = 0
+    seen = {}
+
+    def literal(end):
+        chunk = data[literal_start:end]
+        if not chunk:
+            return
+        n = len(chunk) - 1
+        out.extend(bytes([n << 2]) if n < 60 else bytes([60 << 2, n]))
+        out.extend(chunk)
+
+    while i + 4 <= len(data):
+        key = data[i:i + 4]
+        j = seen.get(key)
+        seen[key] = i
+        if j is None or i - j > 0xFFFF:
+            i += 1
+            continue
+        length = 4
+        while i + length < len(data) and data[j + length] == data[i + length] and length < 64:
+            length += 1
+        literal(i)
+        offset = i - j
+        if 4 <= length <= 11 and offset < 2048:
+            out.extend(bytes([((offset >> 8) << 5) | ((length - 4) << 2) | 1, offset & 0xFF]))
+        else:
+            out.extend(bytes([((length - 1) << 2) | 2]) + struct.pack("<H", offset))
+        i += length
+        literal_start = i
+    literal(len(data))
+    return bytes(out)
+
+
+PLAIN, RLE, RLE_DICTIONARY = 0, 3, 8
+TYPES = {"BOOLEAN": 0, "INT32": 1, "INT64": 2, "DOUBLE": 5, "BYTE_ARRAY": 6}
+FORMATS = {"INT32": "<i", "INT64": "<q", "DOUBLE": "<d"}
+
+COLUMNS = [
+    ("id", "INT64", False, [1, 2, 3, 4, 5]),
+    ("score", "DOUBLE", True, [1.5, None, 2.5, 9.0, 9.0]),
+    ("count", "INT32", True, [10, 20, None, None, 7]),
+    ("flag", "BOOLEAN", False, [True, False, True, True, False]),
+    ("name", "BYTE_ARRAY", True, ["a", "b", None, "d", "e"]),
+]
+ROW_GROUPS = [(0, 3), (3, 5)]
+
+
+def plain(physical, values):
+    if physical == "BOOLEAN":
+        acc = sum(1 << k for k, v in enumerate(values) if v)
+        return acc.to_bytes((len(values) + 7) // 8, "little")
+    if physical == "BYTE_ARRAY":
+        return b"".join(struct.pack("<I", len(v)) + v.encode() for v in values)
+    return b"".join(struct.pack(FORMATS[physical], v) for v in values)
+
+
+def page(page_type, raw, header_field, header):
+    compressed = snappy(raw)
+    return struct_([(1, "i32", page_type), (2, "i32", len(raw)), (3, "i32", len(compressed)), (header_field, "struct", header)]) + compressed
+
+
+def column_chunk(out, name, physical, optional, values):
+    present = [v for v in values if v is not None]
+    defs = levels_v1([int(v is not None) for v in values]) if optional else b""
+    start = len(out)
+    dictionary_offset = None
+    if physical == "BOOLEAN":
+        encodings = [PLAIN, RLE]
+        data_offset = start
+        out += page(0, defs + plain(physical, present), 5, [(1, "i32", len(values)), (2, "i32", PLAIN), (3, "i32", RLE), (4, "i32", RLE)])
+    else:
+        dictionary = list(dict.fromkeys(present))
+        dictionary_offset = start
+        out += page(2, plain(physical, dictionary), 7, [(1, "i32", len(dictionary)), (2, "i32", PLAIN)])
+        data_offset = len(out)
+        bit_width = (len(dictionary) - 1).bit_length()
+        indices = [dictionary.index(v) for v in present]
+        data = bytes([bit_width]) + hybrid(indices, bit_width)
+        encodings = [PLAIN, RLE, RLE_DICTIONARY]
+        out += page(0, defs + data, 5, [(1, "i32", len(values)), (2, "i32", RLE_DICTIONARY), (3, "i32", RLE), (4, "i32", RLE)])
+    size = len(out) - start
+    statistics = None
+    if physical != "BYTE_ARRAY" and present:
+        statistics = [
+            (3, "i64", len(values) - len(present)),
+            (5, "bin", plain(physical, [max(present)])),
+            (6, "bin", plain(physical, [min(present)])),
+        ]
+    meta = [
+        (1, "i32", TYPES[physical]),
+        (2, "list_i32", encodings),
+        (3, "list_bin", [name]),
+        (4, "i32", 1),
+        (5, "i64", len(values)),
+        (6, "i64", size),
+        (7, "i64", size),
+        (9, "i64", data_offset),
+        (11, "i64", dictionary_offset),
+        (12, "struct", statistics),
+    ]
+    return [(2, "i64", start), (3, "struct", meta)]
+
+
+out = bytearray(b"PAR1")
+row_groups = []
+for lo, hi in ROW_GROUPS:
+    chunks = []
+    for name, physical, optional, values in COLUMNS:
+        chunks.append(column_chunk(out, name, physical, optional, values[lo:hi]))
+    row_groups.append([(1, "list_struct", chunks), (2, "i64", 0), (3, "i64", hi - lo)])
+
+schema = [[(4, "bin", "schema"), (5, "i32", len(COLUMNS))]]
+for name, physical, optional, _ in COLUMNS:
+    element = [(1, "i32", TYPES[physical]), (3, "i32", 1 if optional else 0), (4, "bin", name)]
+    if physical == "BYTE_ARRAY":
+        element.append((6, "i32", 0))
+    schema.append(element)
+footer = struct_([
+    (1, "i32", 2),
+    (2, "list_struct", schema),
+    (3, "i64", ROW_GROUPS[-1][1]),
+    (4, "list_struct", row_groups),
+    (6, "bin", "parquet-cpp-arrow version 14.0.2"),
+])
+out += footer + struct.pack("<I", len(footer)) + b"PAR1"
+
+path = os.path.join(os.path.dirname(os.path.abspath(__file__)), "small.parquet")
+with open(path, "wb") as f:
+    f.write(out)
+print(path, len(out))